@@ -1,14 +1,18 @@
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use iron_broadcast::UIMsg;
 use iron_db::DB;
 use iron_types::ui_events;
-#[cfg(not(target_os = "linux"))]
-use tauri::{AboutMetadata, Menu, MenuItem, Submenu, WindowMenuEvent};
 use tauri::{
-    AppHandle, Builder, CustomMenuItem, GlobalWindowEvent, Manager, SystemTray, SystemTrayEvent,
-    SystemTrayMenu, SystemTrayMenuItem, WindowBuilder, WindowEvent, WindowUrl,
+    api::notification::Notification, AppHandle, Builder, CustomMenuItem, GlobalWindowEvent,
+    Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem, WindowBuilder,
+    WindowEvent, WindowUrl,
 };
+#[cfg(not(target_os = "linux"))]
+use tauri::{AboutMetadata, Menu, MenuItem, Submenu, WindowMenuEvent};
 use tauri_plugin_window_state::{AppHandleExt, Builder as windowStatePlugin, StateFlags};
 
 use crate::{commands, error::AppResult};
@@ -25,41 +29,145 @@ impl IronApp {
             .plugin(windowStatePlugin::default().build())
             .invoke_handler(tauri::generate_handler![
                 commands::get_build_mode,
+                commands::import_settings,
+                commands::backup_export,
+                commands::backup_restore,
+                commands::logs_export,
+                commands::get_balance,
                 iron_settings::commands::settings_get,
                 iron_settings::commands::settings_set,
                 iron_settings::commands::settings_set_dark_mode,
                 iron_settings::commands::settings_finish_onboarding,
                 iron_settings::commands::settings_set_alias,
+                iron_settings::commands::contacts_get_all,
+                iron_settings::commands::contacts_add,
+                iron_settings::commands::contacts_update,
+                iron_settings::commands::contacts_remove,
+                iron_settings::commands::contacts_resolve,
                 iron_settings::commands::settings_get_alias,
                 iron_networks::commands::networks_get_list,
                 iron_networks::commands::networks_get_current,
+                iron_networks::commands::networks_get_active_rpc_url,
+                iron_networks::commands::networks_get_in_flight_requests,
+                iron_networks::commands::networks_get_health,
+                iron_networks::commands::networks_use_rpc_url,
                 iron_networks::commands::networks_set_list,
                 iron_networks::commands::networks_set_current,
+                iron_networks::commands::networks_add,
+                iron_networks::commands::networks_update,
+                iron_networks::commands::networks_remove,
                 iron_networks::commands::networks_reset,
+                iron_networks::commands::networks_get_storage_at,
+                iron_networks::commands::networks_get_block_number,
+                iron_networks::commands::networks_get_fee_estimates,
+                iron_networks::commands::networks_resolve_name,
+                iron_networks::commands::networks_lookup_address,
+                iron_networks::commands::networks_spawn_anvil,
+                iron_networks::commands::networks_stop_anvil,
+                iron_networks::commands::networks_get_anvil,
+                iron_networks::commands::networks_get_presets,
+                iron_networks::commands::networks_search_chains,
+                iron_networks::commands::networks_anvil_set_balance,
+                iron_networks::commands::networks_anvil_impersonate,
+                iron_networks::commands::networks_anvil_stop_impersonating,
+                iron_networks::commands::networks_anvil_get_impersonated,
+                iron_networks::commands::networks_anvil_mine,
+                iron_networks::commands::networks_anvil_set_next_block_timestamp,
+                iron_networks::commands::networks_anvil_snapshot,
+                iron_networks::commands::networks_anvil_revert,
+                iron_networks::commands::networks_get_explorer_link,
+                iron_networks::commands::networks_get_verified_contract,
                 iron_db::commands::db_get_contracts,
                 iron_db::commands::db_insert_contract,
                 iron_db::commands::db_get_transactions,
+                iron_db::commands::db_prune_history,
+                iron_db::commands::db_get_audit_log,
+                iron_db::commands::db_get_signed_messages,
+                iron_db::commands::db_get_history,
+                iron_db::commands::db_export_history,
                 iron_db::commands::db_get_contracts,
                 iron_db::commands::db_get_erc20_balances,
                 iron_db::commands::db_get_native_balance,
+                iron_db::commands::db_get_nfts,
                 iron_ws::commands::ws_peers_by_domain,
+                iron_ws::commands::ws_get_sessions,
+                iron_ws::commands::ws_disconnect_peer,
+                iron_ws::commands::ws_set_peer_network,
+                iron_ws::commands::ws_get_connection_token,
+                iron_ws::commands::ws_regenerate_connection_token,
                 iron_wallets::commands::wallets_get_all,
                 iron_wallets::commands::wallets_get_current,
                 iron_wallets::commands::wallets_get_current_address,
                 iron_wallets::commands::wallets_create,
                 iron_wallets::commands::wallets_update,
+                iron_wallets::commands::wallets_rename,
                 iron_wallets::commands::wallets_remove,
+                iron_wallets::commands::wallets_export_mnemonic,
+                iron_wallets::commands::wallets_export_keystore,
+                iron_wallets::commands::wallets_set_keychain,
+                iron_wallets::commands::wallets_unlock,
+                iron_wallets::commands::wallets_lock,
+                iron_wallets::commands::wallets_touch,
+                iron_wallets::commands::wallets_is_locked,
+                iron_wallets::commands::wallets_unlock_all,
+                iron_wallets::commands::wallets_change_password,
+                iron_wallets::commands::wallets_discover_accounts,
+                iron_wallets::commands::wallets_preview_derivation,
+                iron_wallets::commands::wallets_set_derivation,
+                iron_wallets::commands::wallets_get_labels,
+                iron_wallets::commands::wallets_set_label,
                 iron_wallets::commands::wallets_set_current_wallet,
                 iron_wallets::commands::wallets_set_current_path,
+                iron_wallets::commands::wallets_set_current_index,
                 iron_wallets::commands::wallets_get_wallet_addresses,
                 iron_wallets::commands::wallets_get_mnemonic_addresses,
+                iron_wallets::commands::wallets_verify_mnemonic,
+                iron_wallets::commands::wallets_validate,
                 iron_dialogs::commands::dialog_get_payload,
                 iron_dialogs::commands::dialog_send,
                 iron_dialogs::commands::dialog_finish,
+                iron_dialogs::commands::dialog_get_pending,
+                iron_dialogs::commands::dialog_resolve,
                 iron_forge::commands::foundry_get_abi,
+                iron_forge::commands::foundry_get_deployments,
                 iron_rpc::commands::rpc_send_transaction,
+                iron_rpc::commands::rpc_sign_transaction,
+                iron_rpc::commands::rpc_speed_up_tx,
+                iron_rpc::commands::rpc_cancel_tx,
+                iron_rpc::commands::rpc_build_unsigned,
+                iron_rpc::commands::rpc_sign_exported,
+                iron_rpc::commands::rpc_broadcast_signed,
+                iron_rpc::commands::rpc_estimate_gas,
+                iron_rpc::commands::rpc_estimate_l1_fee,
+                iron_rpc::commands::rpc_simulate,
+                iron_rpc::commands::rpc_watch_token,
+                iron_rpc::commands::rpc_unwatch_token,
+                iron_rpc::commands::rpc_get_safe,
+                iron_rpc::commands::rpc_propose_safe_tx,
+                iron_rpc::commands::rpc_get_smart_account,
+                iron_rpc::commands::rpc_get_allowances,
+                iron_rpc::commands::rpc_revoke_allowance,
+                iron_rpc::commands::rpc_contract_functions,
+                iron_rpc::commands::rpc_contract_encode,
+                iron_rpc::commands::rpc_contract_call,
+                iron_rpc::commands::rpc_contract_send,
+                iron_rpc::commands::rpc_get_balance,
+                iron_rpc::commands::rpc_get_code,
+                iron_rpc::commands::rpc_get_storage_at,
+                iron_rpc::commands::rpc_call,
+                iron_rpc::commands::rpc_get_gas_history,
                 iron_connections::commands::connections_affinity_for,
-                iron_connections::commands::connections_set_affinity
+                iron_connections::commands::connections_set_affinity,
+                iron_connections::commands::connections_get_pending,
+                iron_connections::commands::connections_approve,
+                iron_connections::commands::connections_reject,
+                iron_connections::commands::connections_get_approved,
+                iron_connections::commands::connections_revoke,
+                iron_connections::commands::connections_set_account,
+                iron_walletconnect::commands::walletconnect_pair,
+                iron_walletconnect::commands::walletconnect_get_sessions,
+                iron_walletconnect::commands::walletconnect_disconnect,
+                iron_exchange_rates::commands::prices_get
             ])
             .setup(|app| {
                 let handle = app.handle();
@@ -96,7 +204,7 @@ impl IronApp {
             .expect("error while running tauri application");
 
         let db = DB::connect(&resource(&app, "db.sqlite3")).await?;
-        init(&app, &db).await?;
+        init(|name| resource(&app, name), &db).await?;
 
         app.manage(db);
         let res = Self { app };
@@ -224,9 +332,7 @@ impl IronApp {
 #[cfg(not(target_os = "linux"))]
 fn on_menu_event(event: WindowMenuEvent) {
     match event.menu_item_id() {
-        "quit" => {
-            std::process::exit(0);
-        }
+        "quit" => quit(&event.window().app_handle()),
         "close" => {
             event.window().close().unwrap();
         }
@@ -239,21 +345,54 @@ fn on_menu_event(event: WindowMenuEvent) {
     }
 }
 
-async fn init(app: &tauri::App, db: &DB) -> AppResult<()> {
+/// Starts every core module, reading and writing their files through `resource`
+/// Shared with headless mode, which has no app to resolve resources with
+pub(crate) async fn init(resource: impl Fn(&str) -> PathBuf, db: &DB) -> AppResult<()> {
     // anvil needs to be started before networks, otherwise the initial tracker won't be ready to
     // spawn
     iron_sync::init(db.clone()).await;
 
-    iron_settings::init(resource(app, "settings.json")).await;
-    iron_ws::init().await;
-    iron_connections::init(resource(app, "connections.json")).await;
-    iron_wallets::init(resource(app, "wallets.json")).await;
-    iron_networks::init(resource(app, "networks.json")).await;
+    iron_settings::init(resource("settings.json")).await;
+    iron_settings::init_contacts(resource("contacts.json")).await;
+    iron_ws::init(resource("connection-token")).await;
+    iron_connections::init(resource("connections.json")).await;
+    iron_wallets::init(resource("wallets.json")).await;
+    iron_networks::init(resource("networks.json")).await?;
     iron_forge::init().await?;
+    iron_rpc::init(db.clone()).await;
+    iron_exchange_rates::init(db.clone());
+    iron_walletconnect::init(resource("walletconnect.json")).await;
 
     Ok(())
 }
 
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Notifies peers, clears caches and closes the database ahead of the process exiting
+/// Only the first call does anything, so every exit path can safely go through it
+pub(crate) async fn shutdown(db: &DB) {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    iron_ws::shutdown().await;
+    iron_rpc::shutdown().await;
+    // the process is killed on drop regardless, so a failure here isn't worth delaying exit for
+    let _ = iron_networks::stop_anvil().await;
+    iron_networks::clear_caches().await;
+    db.close().await;
+}
+
+/// Shuts down cleanly, then exits the app
+fn quit(app: &AppHandle) {
+    let app = app.clone();
+
+    tauri::async_runtime::spawn(async move {
+        shutdown(&app.state::<DB>()).await;
+        app.exit(0);
+    });
+}
+
 fn on_window_event(event: GlobalWindowEvent) {
     if let WindowEvent::CloseRequested {
         #[cfg(not(target_os = "linux"))]
@@ -278,7 +417,7 @@ fn on_system_tray_event(app: &AppHandle, event: SystemTrayEvent) {
 
     match event {
         MenuItemClick { id, .. } => match id.as_str() {
-            "quit" => app.exit(0),
+            "quit" => quit(app),
             "hide" => app.get_window("main").unwrap().hide().unwrap(),
             "show" => show_main_window(app),
             _ => {}
@@ -309,9 +448,7 @@ async fn event_listener(handle: AppHandle) {
                 Notify(msg) => {
                     // forward directly to main window
                     // if window is not open, just ignore them
-                    if let Some(window) = handle.get_window("main") {
-                        window.emit(msg.label(), &msg).unwrap();
-                    }
+                    emit_to(&handle, "main", msg.label(), &msg);
                 }
 
                 DialogOpen(ui_events::DialogOpen {
@@ -321,36 +458,64 @@ async fn event_listener(handle: AppHandle) {
                     w,
                     h,
                 }) => {
-                    WindowBuilder::new(&handle, label, WindowUrl::App(url.into()))
+                    // the request stays pending, so it can still be rejected from the main window
+                    if let Err(e) = WindowBuilder::new(&handle, &label, WindowUrl::App(url.into()))
                         .min_inner_size(w, h)
                         .max_inner_size(w, h)
-                        .title(title)
+                        .title(&title)
                         .build()
-                        .unwrap();
+                    {
+                        tracing::warn!("Failed to open {}: {}", label, e);
+                        iron_broadcast::error(title, e.to_string()).await;
+                    }
                 }
 
                 DialogClose(ui_events::DialogClose { label }) => {
                     if let Some(window) = handle.get_window(&label) {
-                        window.close().unwrap();
+                        if let Err(e) = window.close() {
+                            tracing::warn!("Failed to close {}: {}", label, e);
+                        }
                     }
                 }
 
+                // the dialog may have been closed by the user in the meantime
                 DialogSend(ui_events::DialogSend {
                     label,
                     event_type,
                     payload,
-                }) => {
-                    handle
-                        .get_window(&label)
-                        .unwrap()
-                        .emit(&event_type, &payload)
-                        .unwrap();
+                }) => emit_to(&handle, &label, &event_type, &payload),
+
+                DesktopNotify(notification) => {
+                    // failing to show one, e.g. because the OS denied permission, is not fatal
+                    let _ = Notification::new(&handle.config().tauri.bundle.identifier)
+                        .title(&notification.title)
+                        .body(&notification.body)
+                        .show();
+
+                    emit_to(&handle, "main", "desktop-notification", &notification);
+                }
+
+                TxUpdated(update) => emit_to(&handle, "main", "transaction-updated", &update),
+
+                Error(notice) => {
+                    tracing::warn!("{}: {}", notice.title, notice.message);
+                    emit_to(&handle, "main", "error", &notice);
                 }
             }
         }
     }
 }
 
+/// Sends an event to a window, if it's open
+/// A failure only loses the event, so it's logged rather than allowed to stop the event loop
+fn emit_to<S: serde::Serialize + Clone>(handle: &AppHandle, label: &str, event: &str, payload: S) {
+    if let Some(window) = handle.get_window(label) {
+        if let Err(e) = window.emit(event, payload) {
+            tracing::warn!("Failed to send {} to {}: {}", event, label, e);
+        }
+    }
+}
+
 fn resource(app: &tauri::App, resource: &str) -> PathBuf {
     app.path_resolver()
         .resolve_resource(resource)
@@ -0,0 +1,166 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iron_crypto::EncryptedData;
+use iron_networks::{Network, Networks};
+use iron_settings::{Contact, Contacts, SerializedSettings, Settings};
+use iron_types::{GlobalState, Json};
+use iron_wallets::{Wallet, Wallets};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Tells backups apart from any other JSON file
+const BACKUP_FORMAT: &str = "iron-backup";
+
+/// The version of the payload written by this build
+/// Whenever the payload changes shape, bump this and teach `migrate` to upgrade older ones
+const BACKUP_VERSION: u32 = 1;
+
+/// A backup file, as written to disk
+/// Only the version sits in plaintext, so that a restore can tell how to read the payload before
+/// decrypting it
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupFile {
+    format: String,
+    version: u32,
+    created_at: u64,
+    data: EncryptedData<Json>,
+}
+
+/// Everything needed to rebuild the app's state on a fresh install
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Backup {
+    settings: SerializedSettings,
+    networks: Vec<Network>,
+    wallets: Vec<Wallet>,
+    #[serde(default)]
+    contacts: Vec<Contact>,
+}
+
+/// Writes wallets, networks, contacts and settings to a single file, encrypted under `password`
+/// Wallet secrets that are already encrypted are kept as they are, so they still need their own
+/// password after a restore. Those whose password lives in the OS keychain are re-encrypted under
+/// `password` instead, since the keychain entry isn't part of the backup
+pub async fn export(path: &Path, password: &str) -> AppResult<()> {
+    let backup = Backup {
+        settings: Settings::read().await.get().clone(),
+        networks: Networks::read().await.networks.values().cloned().collect(),
+        wallets: Wallets::read().await.for_backup(password)?,
+        contacts: Contacts::read()
+            .await
+            .get_all()
+            .iter()
+            .filter(|c| !c.deployment)
+            .cloned()
+            .collect(),
+    };
+
+    let file = seal(&backup, password)?;
+    iron_types::save_json(path, &file)?;
+
+    Ok(())
+}
+
+/// Restores a file written by `export`
+/// Follows the same rules as `import_settings`: sections are merged into the current state unless
+/// `overwrite` is set
+pub async fn restore(path: &Path, password: &str, overwrite: bool) -> AppResult<()> {
+    let file = File::open(path)?;
+    let file: BackupFile = serde_json::from_reader(BufReader::new(file))?;
+    let backup = open(file, password)?;
+
+    Settings::write().await.import(backup.settings, overwrite)?;
+    Networks::write()
+        .await
+        .import(backup.networks, overwrite)
+        .await?;
+    if !backup.wallets.is_empty() {
+        Wallets::write()
+            .await
+            .import(backup.wallets, overwrite)
+            .await?;
+    }
+    Contacts::write().await.import(backup.contacts, overwrite)?;
+
+    Ok(())
+}
+
+fn seal(backup: &Backup, password: &str) -> AppResult<BackupFile> {
+    let payload = serde_json::to_value(backup)?;
+    let data = iron_crypto::encrypt(&payload, password).map_err(|_| AppError::BackupEncrypt)?;
+
+    Ok(BackupFile {
+        format: BACKUP_FORMAT.into(),
+        version: BACKUP_VERSION,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        data,
+    })
+}
+
+fn open(file: BackupFile, password: &str) -> AppResult<Backup> {
+    if file.format != BACKUP_FORMAT {
+        return Err(AppError::NotABackup);
+    }
+
+    let payload =
+        iron_crypto::decrypt(&file.data, password).map_err(|_| AppError::BackupDecrypt)?;
+
+    migrate(file.version, payload)
+}
+
+/// Reads a payload written by any version up to the current one
+fn migrate(version: u32, payload: Json) -> AppResult<Backup> {
+    match version {
+        // older versions go here, each upgrading the payload to the next one
+        BACKUP_VERSION => Ok(serde_json::from_value(payload)?),
+        v => Err(AppError::UnsupportedBackupVersion(v)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup() -> Backup {
+        Backup {
+            settings: Default::default(),
+            networks: Network::all_default(),
+            wallets: vec![Wallet::initial()],
+            contacts: vec![],
+        }
+    }
+
+    #[test]
+    fn restores_what_was_sealed() {
+        let file = seal(&backup(), "hunter2").unwrap();
+        let json = serde_json::to_string(&file).unwrap();
+
+        let file: BackupFile = serde_json::from_str(&json).unwrap();
+        let restored = open(file, "hunter2").unwrap();
+        assert_eq!(restored.networks.len(), Network::all_default().len());
+        assert_eq!(restored.wallets.len(), 1);
+    }
+
+    #[test]
+    fn rejects_wrong_passwords_and_newer_versions() {
+        let file = seal(&backup(), "hunter2").unwrap();
+        assert!(matches!(open(file, "wrong"), Err(AppError::BackupDecrypt)));
+
+        let mut file = seal(&backup(), "hunter2").unwrap();
+        file.version = BACKUP_VERSION + 1;
+        assert!(matches!(
+            open(file, "hunter2"),
+            Err(AppError::UnsupportedBackupVersion(_))
+        ));
+    }
+}
@@ -1,3 +1,13 @@
+use std::path::PathBuf;
+
+use ethers::types::{Address, U256};
+use iron_networks::{Network, Networks};
+use iron_settings::{SerializedSettings, Settings};
+use iron_types::GlobalState;
+use iron_wallets::{Wallet, WalletControl, Wallets};
+
+use crate::{backup, error::AppResult};
+
 #[tauri::command]
 pub fn get_build_mode() -> String {
     if cfg!(debug_assertions) {
@@ -6,3 +16,72 @@ pub fn get_build_mode() -> String {
         "release".to_string()
     }
 }
+
+/// Imports a backup of settings, networks and wallets
+///
+/// By default, everything is merged into the current state: networks and wallets are replaced by
+/// name, aliases by address, and anything missing from the backup is kept as is. Wallets are only
+/// touched if the backup includes any. With `overwrite`, each section present in the backup
+/// replaces the current one entirely
+#[tauri::command]
+pub async fn import_settings(
+    settings: Option<SerializedSettings>,
+    networks: Option<Vec<Network>>,
+    wallets: Option<Vec<Wallet>>,
+    overwrite: bool,
+) -> AppResult<()> {
+    if let Some(settings) = settings {
+        Settings::write().await.import(settings, overwrite)?;
+    }
+
+    if let Some(networks) = networks {
+        Networks::write().await.import(networks, overwrite).await?;
+    }
+
+    if let Some(wallets) = wallets.filter(|w| !w.is_empty()) {
+        Wallets::write().await.import(wallets, overwrite).await?;
+    }
+
+    Ok(())
+}
+
+/// Writes an encrypted backup of wallets, networks, contacts and settings
+#[tauri::command]
+pub async fn backup_export(path: PathBuf, password: String) -> AppResult<()> {
+    backup::export(&path, &password).await
+}
+
+/// Restores a backup written by `backup_export`, merging it as `import_settings` does
+#[tauri::command]
+pub async fn backup_restore(path: PathBuf, password: String, overwrite: bool) -> AppResult<()> {
+    backup::restore(&path, &password, overwrite).await
+}
+
+/// Writes the most recent logs to a file, for attaching to bug reports
+/// Request params and dialog payloads are kept out of the logs, so they're safe to share
+#[tauri::command]
+pub async fn logs_export(path: PathBuf) -> AppResult<()> {
+    std::fs::write(path, iron_tracing::recent_logs())?;
+    Ok(())
+}
+
+/// Gets the balance of an address on the current network
+/// Defaults to the current account
+#[tauri::command]
+pub async fn get_balance(address: Option<Address>) -> AppResult<U256> {
+    let address = match address {
+        Some(address) => address,
+        None => {
+            Wallets::read()
+                .await
+                .get_current_wallet()
+                .get_current_address()
+                .await
+                .0
+        }
+    };
+
+    let network = Networks::read().await.get_current()?.clone();
+
+    Ok(network.get_balance(address).await?)
+}
@@ -14,6 +14,45 @@ pub enum AppError {
 
     #[error(transparent)]
     Tracing(#[from] iron_tracing::TracingError),
+
+    #[error(transparent)]
+    Settings(#[from] iron_settings::Error),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
+
+    #[error(transparent)]
+    Wallets(#[from] iron_wallets::Error),
+
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error("serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("invalid argument: {0}")]
+    InvalidArg(String),
+
+    #[error("not an iron backup file")]
+    NotABackup,
+
+    #[error("failed to encrypt backup")]
+    BackupEncrypt,
+
+    #[error("wrong password, or the backup is corrupted")]
+    BackupDecrypt,
+
+    #[error("backup version {0} is newer than this app can read")]
+    UnsupportedBackupVersion(u32),
 }
 
 pub type AppResult<T> = std::result::Result<T, AppError>;
+
+impl serde::Serialize for AppError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
@@ -0,0 +1,332 @@
+//! Runs the wallet without any window, answering JSON-RPC over a unix socket
+//! Meant for scripts and tools like `cast`, which can point `--rpc-url` at the socket's path.
+//! Requests that would open a dialog are settled by an approval policy instead
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use iron_broadcast::UIMsg;
+use iron_connections::Ctx;
+use iron_db::DB;
+use iron_networks::Networks;
+use iron_types::{ui_events, GlobalState, Json};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    sync::{broadcast::error::RecvError, Mutex},
+};
+
+use crate::{
+    app,
+    error::{AppError, AppResult},
+};
+
+/// Held while a question is asked on the terminal
+static PROMPT: Lazy<Mutex<()>> = Lazy::new(Default::default);
+
+/// How requests that need the user's approval are settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    /// Approved without asking on local dev chains, asked for on the terminal elsewhere
+    Dev,
+
+    /// Always asked for on the terminal
+    Prompt,
+
+    /// Always approved. Only sensible against throwaway chains
+    Auto,
+
+    /// Always rejected, leaving the wallet read-only
+    Reject,
+}
+
+pub struct Opts {
+    socket: PathBuf,
+    data_dir: PathBuf,
+    policy: Policy,
+}
+
+impl Opts {
+    /// Reads headless options from the command line, or `None` if headless mode wasn't asked for
+    ///
+    /// `iron --headless [--socket PATH] [--data-dir DIR] [--approve dev|prompt|auto|reject]`
+    pub fn from_args() -> AppResult<Option<Self>> {
+        let mut args = env::args().skip(1);
+        if !env::args().any(|a| a == "--headless") {
+            return Ok(None);
+        }
+
+        let data_dir = env::var("IRON_DATA_DIR")
+            .map(PathBuf::from)
+            .or_else(|_| env::current_dir())?;
+        let mut opts = Self {
+            socket: data_dir.join("iron.ipc"),
+            data_dir,
+            policy: Policy::Dev,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => continue,
+                "--socket" => opts.socket = value(&mut args, &arg)?.into(),
+                "--data-dir" => opts.data_dir = value(&mut args, &arg)?.into(),
+                "--approve" => {
+                    opts.policy = match value(&mut args, &arg)?.as_str() {
+                        "dev" => Policy::Dev,
+                        "prompt" => Policy::Prompt,
+                        "auto" => Policy::Auto,
+                        "reject" => Policy::Reject,
+                        other => return Err(AppError::InvalidArg(other.into())),
+                    }
+                }
+                _ => return Err(AppError::InvalidArg(arg.clone())),
+            }
+        }
+
+        Ok(Some(opts))
+    }
+}
+
+/// The value following a flag
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> AppResult<String> {
+    args.next()
+        .ok_or_else(|| AppError::InvalidArg(format!("{} needs a value", flag)))
+}
+
+/// Starts the core modules, and serves requests until interrupted
+pub async fn run(opts: Opts) -> AppResult<()> {
+    std::fs::create_dir_all(&opts.data_dir)?;
+
+    let db = DB::connect(&opts.data_dir.join("db.sqlite3")).await?;
+    app::init(|name| opts.data_dir.join(name), &db).await?;
+
+    tokio::spawn(approvals(opts.policy));
+    tokio::select! {
+        res = serve(&opts.socket) => res?,
+        _ = tokio::signal::ctrl_c() => (),
+    }
+
+    let _ = std::fs::remove_file(&opts.socket);
+    app::shutdown(&db).await;
+
+    Ok(())
+}
+
+/// Answers JSON-RPC requests on a unix socket, as geth's IPC endpoint does
+/// Requests on a connection are answered in order, with each reply on its own line
+#[cfg(unix)]
+async fn serve(path: &Path) -> AppResult<()> {
+    // a socket left behind by a previous run would make binding fail
+    let _ = std::fs::remove_file(path);
+    let listener = tokio::net::UnixListener::bind(path)?;
+    tracing::info!("listening on {}", path.display());
+    eprintln!("Iron is listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection(stream).await {
+                tracing::debug!("IPC connection closed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+async fn serve(_path: &Path) -> AppResult<()> {
+    Err(AppError::InvalidArg(
+        "headless mode needs unix sockets".into(),
+    ))
+}
+
+#[cfg(unix)]
+async fn connection(mut stream: tokio::net::UnixStream) -> AppResult<()> {
+    let handler = iron_rpc::Handler::new(Ctx::empty());
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..read]);
+
+        // clients aren't required to delimit requests, so complete JSON values are split off the
+        // front of the buffer, and whatever is left waits for more data
+        let mut values = serde_json::Deserializer::from_slice(&buf).into_iter::<Json>();
+        let mut requests = vec![];
+        let mut consumed = 0;
+        let mut invalid = false;
+        while let Some(value) = values.next() {
+            match value {
+                Ok(value) => {
+                    requests.push(value);
+                    consumed = values.byte_offset();
+                }
+                Err(e) if e.is_eof() => break,
+                Err(_) => {
+                    invalid = true;
+                    break;
+                }
+            }
+        }
+
+        // there's no telling where a malformed request ends, so the rest of the buffer goes too
+        match invalid {
+            true => buf.clear(),
+            false => {
+                buf.drain(..consumed);
+            }
+        }
+
+        for request in requests {
+            if let Some(reply) = handler.handle(request.to_string()).await {
+                stream.write_all(format!("{}\n", reply).as_bytes()).await?;
+            }
+        }
+        if invalid {
+            let reply = json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": "Parse error" },
+            });
+            stream.write_all(format!("{}\n", reply).as_bytes()).await?;
+        }
+    }
+}
+
+/// Settles dialogs according to the policy, in place of the windows the app would open
+async fn approvals(policy: Policy) {
+    let mut rx = iron_broadcast::subscribe_ui().await;
+
+    loop {
+        // a lagging receiver only missed some messages, but a closed one will never get another
+        let msg = match rx.recv().await {
+            Ok(msg) => msg,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+
+        match msg {
+            UIMsg::DialogOpen(ui_events::DialogOpen { label, .. }) => {
+                if let Some(id) = dialog_id(&label) {
+                    tokio::spawn(settle(id, policy));
+                }
+            }
+
+            // a wrong password, which gets another attempt
+            UIMsg::DialogSend(ui_events::DialogSend {
+                label, event_type, ..
+            }) if event_type == "failed" => {
+                if let Some(id) = dialog_id(&label) {
+                    eprintln!("Wrong password");
+                    tokio::spawn(settle(id, policy));
+                }
+            }
+
+            UIMsg::Error(ui_events::ErrorNotice { title, message }) => {
+                eprintln!("{}: {}", title, message);
+            }
+
+            _ => (),
+        }
+    }
+}
+
+async fn settle(id: u32, policy: Policy) {
+    let Some(request) = iron_dialogs::pending_requests()
+        .await
+        .into_iter()
+        .find(|r| r.id == id)
+    else {
+        return;
+    };
+
+    // passwords can't be approved automatically, so they're always asked for
+    if request.preset == "wallet-unlock" {
+        let name = request.payload["name"].as_str().unwrap_or_default();
+        let password = match policy {
+            Policy::Reject => None,
+            _ => ask_password(format!("Password for wallet {}: ", name)).await,
+        };
+
+        let _ = match password {
+            Some(password) => {
+                iron_dialogs::commands::dialog_send(id, json!({ "password": password })).await
+            }
+            None => iron_dialogs::resolve(id, Err(Json::Null)).await,
+        };
+        return;
+    }
+
+    let on_dev_chain = match Networks::read().await.get_current() {
+        Ok(network) => network.is_dev(),
+        Err(_) => false,
+    };
+    let approved = match policy {
+        Policy::Auto => true,
+        Policy::Dev if on_dev_chain => true,
+        Policy::Dev | Policy::Prompt => {
+            let summary = serde_json::to_string_pretty(&request.payload).unwrap_or_default();
+            let answer = ask(format!("{}\n{}\nApprove? [y/N] ", request.preset, summary)).await;
+            matches!(answer.as_deref(), Some("y" | "Y" | "yes"))
+        }
+        Policy::Reject => false,
+    };
+
+    tracing::info!(dialog = %request.preset, approved, "settled headless request");
+    let result = match approved {
+        true => Ok(request.payload),
+        false => Err(Json::Null),
+    };
+    let _ = iron_dialogs::resolve(id, result).await;
+}
+
+/// Asks a question on the terminal, returning the trimmed answer
+/// Questions are asked one at a time, so that concurrent requests don't interleave on the terminal
+async fn ask(question: String) -> Option<String> {
+    let _turn = PROMPT.lock().await;
+
+    let mut stderr = tokio::io::stderr();
+    stderr.write_all(question.as_bytes()).await.ok()?;
+    stderr.flush().await.ok()?;
+
+    let mut line = String::new();
+    BufReader::new(tokio::io::stdin())
+        .read_line(&mut line)
+        .await
+        .ok()?;
+
+    Some(line.trim().to_string())
+}
+
+/// Asks for a password on the terminal, without echoing it as it's typed
+async fn ask_password(prompt: String) -> Option<String> {
+    let _turn = PROMPT.lock().await;
+
+    tokio::task::spawn_blocking(move || rpassword::prompt_password(prompt))
+        .await
+        .ok()?
+        .ok()
+}
+
+/// Dialog windows are labelled `dialog/<id>`
+fn dialog_id(label: &str) -> Option<u32> {
+    label.strip_prefix("dialog/")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dialog_ids_from_labels() {
+        assert_eq!(dialog_id("dialog/12"), Some(12));
+        assert_eq!(dialog_id("main"), None);
+        assert_eq!(dialog_id("dialog/x"), None);
+    }
+}
@@ -2,8 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod app;
+mod backup;
 mod commands;
 mod error;
+mod headless;
 
 use error::AppResult;
 
@@ -12,6 +14,10 @@ async fn main() -> AppResult<()> {
     iron_tracing::init()?;
     fix_path_env::fix()?;
 
+    if let Some(opts) = headless::Opts::from_args()? {
+        return headless::run(opts).await;
+    }
+
     app::IronApp::build().await?.run();
 
     Ok(())
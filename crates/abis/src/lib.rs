@@ -9,6 +9,9 @@ abigen!(
         function name() public view returns (string)
         function symbol() public view returns (string)
         function decimals() public view returns (uint8)
+        function balanceOf(address owner) public view returns (uint256)
+        function allowance(address owner, address spender) public view returns (uint256)
+        function approve(address spender, uint256 value) public returns (bool)
     ]"#,
     event_derives(serde::Deserialize)
 );
@@ -24,6 +27,55 @@ abigen!(
     r#"[
         event Transfer(address indexed from, address indexed to, uint256 indexed tokenId)
         event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)
+        event ApprovalForAll(address indexed owner, address indexed operator, bool approved)
+
+        function tokenURI(uint256 tokenId) public view returns (string)
+        function ownerOf(uint256 tokenId) public view returns (address)
+        function getApproved(uint256 tokenId) public view returns (address)
+        function isApprovedForAll(address owner, address operator) public view returns (bool)
+        function approve(address to, uint256 tokenId) public
+        function setApprovalForAll(address operator, bool approved) public
     ]"#,
     event_derives(serde::Deserialize)
 );
+
+abigen!(
+    IAggregatorV3,
+    r#"[
+        function decimals() external view returns (uint8)
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#,
+);
+
+abigen!(
+    ISafe,
+    r#"[
+        function VERSION() external view returns (string)
+        function getOwners() external view returns (address[])
+        function getThreshold() external view returns (uint256)
+        function nonce() external view returns (uint256)
+        function getTransactionHash(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, uint256 _nonce) external view returns (bytes32)
+    ]"#,
+);
+
+abigen!(
+    IEntryPoint,
+    r#"[
+        function getNonce(address sender, uint192 key) external view returns (uint256)
+    ]"#,
+);
+
+abigen!(
+    ISimpleAccountFactory,
+    r#"[
+        function createAccount(address owner, uint256 salt) external returns (address)
+        function getAddress(address owner, uint256 salt) external view returns (address)
+    ]"#,
+);
+
+abigen!(
+    ISimpleAccount,
+    r#"[
+        function execute(address dest, uint256 value, bytes func) external
+    ]"#,
+);
@@ -1,5 +1,7 @@
+use std::net::SocketAddr;
+
 pub use internal_msgs::*;
-use iron_types::{ui_events, Affinity, ChecksummedAddress};
+use iron_types::{ui_events, Affinity, ChecksummedAddress, Json, TxUpdate};
 use once_cell::sync::Lazy;
 use tokio::sync::{broadcast, RwLock};
 pub use ui_msgs::*;
@@ -9,8 +11,24 @@ use url::Url;
 #[derive(Debug, Clone)]
 pub enum InternalMsg {
     ChainChanged(u32, Option<String>, Affinity),
+
+    /// A single peer connection, pinned to its own network, switched chains
+    PeerChainChanged(SocketAddr, u32),
     AccountsChanged(Vec<ChecksummedAddress>),
 
+    /// The accounts an origin may see changed, after its approval was revoked or scoped
+    OriginAccessChanged(String),
+
+    /// A transaction sent by a peer moved on in its lifecycle
+    PeerTxUpdated(SocketAddr, TxUpdate),
+
+    /// An EIP-1193 `message` event, for app-level notices to peers
+    PeerMessage {
+        type_: String,
+        data: Json,
+        domain: Option<String>,
+    },
+
     ResetAnvilListener { chain_id: u32, http: Url, ws: Url },
 
     AddressAdded(ChecksummedAddress),
@@ -20,6 +38,10 @@ pub enum InternalMsg {
     NetworkAdded(u32),
     NetworkRemoved(u32),
     CurrentNetworkChanged(u32),
+
+    /// The current network's RPC became unreachable, or reachable again
+    NetworkDisconnected(u32),
+    NetworkConnected(u32),
 }
 
 #[derive(Debug, Clone)]
@@ -35,6 +57,15 @@ pub enum UIMsg {
 
     /// sends a new event to a dialog
     DialogSend(ui_events::DialogSend),
+
+    /// shows a native desktop notification
+    DesktopNotify(ui_events::DesktopNotification),
+
+    /// a sent transaction moved on in its lifecycle
+    TxUpdated(TxUpdate),
+
+    /// shows an error in the main window
+    Error(ui_events::ErrorNotice),
 }
 
 mod internal_msgs {
@@ -52,11 +83,36 @@ mod internal_msgs {
         send(ChainChanged(chain_id, domain, affinity)).await;
     }
 
+    /// Broadcasts a `chainChanged` event to a single pinned peer
+    pub async fn peer_chain_changed(socket: SocketAddr, chain_id: u32) {
+        send(PeerChainChanged(socket, chain_id)).await;
+    }
+
     /// Broadcasts `AccountsChanged` events
     pub async fn accounts_changed(addresses: Vec<ChecksummedAddress>) {
         send(AccountsChanged(addresses)).await;
     }
 
+    /// Tells an origin's peers which accounts they can see now
+    pub async fn origin_access_changed(origin: String) {
+        send(OriginAccessChanged(origin)).await;
+    }
+
+    /// Broadcasts a `transactionUpdated` event to the peer that sent the transaction
+    pub async fn peer_tx_updated(socket: SocketAddr, update: TxUpdate) {
+        send(PeerTxUpdated(socket, update)).await;
+    }
+
+    /// Broadcasts a custom `message` event to all peers, or only to those of a given domain
+    pub async fn peer_message(type_: &str, data: Json, domain: Option<String>) {
+        send(PeerMessage {
+            type_: type_.into(),
+            data,
+            domain,
+        })
+        .await;
+    }
+
     /// Requests a reset of the anvil listener for a given chain_id
     pub async fn reset_anvil_listener(chain_id: u32, http: Url, ws: Url) {
         send(ResetAnvilListener { chain_id, http, ws }).await;
@@ -86,6 +142,14 @@ mod internal_msgs {
         send(CurrentNetworkChanged(chain_id)).await;
     }
 
+    pub async fn network_disconnected(chain_id: u32) {
+        send(NetworkDisconnected(chain_id)).await;
+    }
+
+    pub async fn network_connected(chain_id: u32) {
+        send(NetworkConnected(chain_id)).await;
+    }
+
     /// broadcaster for internal msgs
     static INTERNAL: Lazy<RwLock<broadcast::Sender<InternalMsg>>> = Lazy::new(|| {
         let (tx, _rx) = broadcast::channel(16);
@@ -93,7 +157,9 @@ mod internal_msgs {
     });
 
     async fn send<'a>(msg: InternalMsg) {
-        INTERNAL.read().await.send(msg).unwrap();
+        // sending only fails if there are no active subscribers, in which case there's no one to
+        // notify anyway
+        let _ = INTERNAL.read().await.send(msg);
     }
 }
 
@@ -123,6 +189,23 @@ mod ui_msgs {
         send(DialogSend(params)).await;
     }
 
+    pub async fn desktop_notify(title: String, body: String) {
+        send(DesktopNotify(ui_events::DesktopNotification {
+            title,
+            body,
+        }))
+        .await;
+    }
+
+    pub async fn tx_updated(update: TxUpdate) {
+        send(TxUpdated(update)).await;
+    }
+
+    /// Reports a failure that has no command to return it to
+    pub async fn error(title: String, message: String) {
+        send(Error(ui_events::ErrorNotice { title, message })).await;
+    }
+
     /// broadcaster for UI msgs
     static INTERNAL: Lazy<RwLock<broadcast::Sender<UIMsg>>> = Lazy::new(|| {
         let (tx, _rx) = broadcast::channel(16);
@@ -130,6 +213,6 @@ mod ui_msgs {
     });
 
     async fn send<'a>(msg: UIMsg) {
-        INTERNAL.read().await.send(msg).unwrap();
+        let _ = INTERNAL.read().await.send(msg);
     }
 }
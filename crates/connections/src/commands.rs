@@ -1,7 +1,7 @@
 use iron_networks::Networks;
-use iron_types::{Affinity, GlobalState};
+use iron_types::{Affinity, ChecksummedAddress, GlobalState, UINotify};
 
-use crate::{Result, Store};
+use crate::{Approval, ConnectionId, PendingConnection, Result, Store};
 
 #[tauri::command]
 pub async fn connections_affinity_for(domain: String) -> Result<Affinity> {
@@ -13,7 +13,7 @@ pub async fn connections_set_affinity(domain: &str, affinity: Affinity) -> Resul
     // TODO: validate this chain ID
     let new_chain_id = match affinity {
         Affinity::Sticky(chain_id) => chain_id,
-        _ => Networks::read().await.get_current().chain_id,
+        _ => Networks::read().await.get_current()?.chain_id,
     };
 
     Store::write().await.set_affinity(domain, affinity)?;
@@ -21,3 +21,53 @@ pub async fn connections_set_affinity(domain: &str, affinity: Affinity) -> Resul
 
     Ok(())
 }
+
+/// Lists peers waiting for approval to see the wallet's accounts
+#[tauri::command]
+pub async fn connections_get_pending() -> Vec<PendingConnection> {
+    Store::read().await.pending_connections()
+}
+
+#[tauri::command]
+pub async fn connections_approve(id: ConnectionId) -> Result<()> {
+    Store::write().await.approve_connection(id)?;
+    iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn connections_reject(id: ConnectionId) -> Result<()> {
+    Store::write().await.reject_connection(id)?;
+    iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+
+    Ok(())
+}
+
+/// Lists the origins allowed to see the wallet's accounts
+#[tauri::command]
+pub async fn connections_get_approved() -> Vec<Approval> {
+    Store::read().await.approvals()
+}
+
+#[tauri::command]
+pub async fn connections_revoke(origin: String) -> Result<()> {
+    Store::write().await.revoke(&origin)?;
+    iron_broadcast::origin_access_changed(origin).await;
+    iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+
+    Ok(())
+}
+
+/// Restricts an origin to a single account, or lets it see all of them again if none is given
+#[tauri::command]
+pub async fn connections_set_account(
+    origin: String,
+    account: Option<ChecksummedAddress>,
+) -> Result<()> {
+    Store::write().await.set_scoped_account(&origin, account)?;
+    iron_broadcast::origin_access_changed(origin).await;
+    iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use iron_networks::{Network, Networks};
-use iron_types::{Affinity, GlobalState};
+use iron_types::{Affinity, ChecksummedAddress, GlobalState, RequestSource, UINotify};
 
 use crate::{Error, Result, Store};
 
@@ -11,13 +11,19 @@ use crate::{Error, Result, Store};
 pub struct Ctx {
     /// The domain associated with a connection
     pub domain: Option<String>,
+
+    /// The peer this connection belongs to, if any
+    pub source: Option<RequestSource>,
 }
 
 impl jsonrpc_core::Metadata for Ctx {}
 
 impl Ctx {
     pub fn empty() -> Self {
-        Self { domain: None }
+        Self {
+            domain: None,
+            source: None,
+        }
     }
 
     pub async fn get_affinity(&self) -> Affinity {
@@ -36,49 +42,102 @@ impl Ctx {
         Ok(())
     }
 
-    pub async fn network(&self) -> Network {
-        let chain_id = self.chain_id().await;
+    /// Whether this connection may see the wallet's accounts
+    /// Requests that don't come from a peer, such as the app's own, are always allowed
+    pub async fn is_approved(&self) -> bool {
+        match self.source {
+            Some(ref source) => Store::read().await.is_approved(&source.origin),
+            None => true,
+        }
+    }
+
+    /// The only account this connection may see, if the user scoped its origin to one
+    pub async fn scoped_account(&self) -> Option<ChecksummedAddress> {
+        let source = self.source.as_ref()?;
+        Store::read().await.get_scoped_account(&source.origin)
+    }
+
+    /// Asks the user to let this connection see the wallet's accounts, unless it already can
+    /// Origins known for phishing are flagged as such in the request. Resolves to whether access
+    /// was granted
+    pub async fn request_approval(&self, phishing: bool) -> bool {
+        let Some(ref source) = self.source else {
+            return true;
+        };
+
+        let approval = {
+            let mut store = Store::write().await;
+            if store.is_approved(&source.origin) {
+                return true;
+            }
+            store.request_connection(source.clone(), phishing).1
+        };
+        iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+
+        approval.await.unwrap_or(false)
+    }
+
+    pub async fn network(&self) -> Result<Network> {
+        let chain_id = self.chain_id().await?;
 
         Networks::read()
             .await
             .get_network(chain_id)
-            .unwrap()
-            .clone()
+            .ok_or(Error::InvalidChainId(chain_id))
+    }
+
+    /// The network this connection's peer was pinned to, if any
+    pub async fn peer_chain_id(&self) -> Option<u32> {
+        let source = self.source.as_ref()?;
+        Store::read().await.get_peer_chain_id(&source.socket)
     }
 
     pub async fn switch_chain(&mut self, new_chain_id: u32) -> Result<()> {
-        if self.chain_id().await == new_chain_id {
+        if self.chain_id().await? == new_chain_id {
             return Ok(());
         }
 
-        if Networks::read().await.validate_chain_id(new_chain_id) {
-            match self.get_affinity().await {
-                // If affinity is not set, or sticky, update local affinity, and publish event
-                Affinity::Unset | Affinity::Sticky(_) => {
-                    let affinity = new_chain_id.into();
-                    self.set_affinity(affinity).await?;
-
-                    iron_broadcast::chain_changed(new_chain_id, self.domain.clone(), affinity)
-                        .await;
-                }
-
-                // If current affinity is global, there's nothing to update on this Ctx, and the
-                // domain is irrelevant in the update,
-                Affinity::Global => {
-                    iron_broadcast::chain_changed(new_chain_id, None, Affinity::Global).await;
-                }
-            };
-
-            Ok(())
-        } else {
-            Err(Error::InvalidChainId(new_chain_id))
+        if !Networks::read().await.validate_chain_id(new_chain_id) {
+            return Err(Error::InvalidChainId(new_chain_id));
         }
+
+        // a pinned peer switches on its own, without affecting the rest of its domain
+        if let (Some(source), Some(_)) = (&self.source, self.peer_chain_id().await) {
+            Store::write()
+                .await
+                .set_peer_chain_id(source.socket, Some(new_chain_id));
+            iron_broadcast::peer_chain_changed(source.socket, new_chain_id).await;
+
+            return Ok(());
+        }
+
+        match self.get_affinity().await {
+            // If affinity is not set, or sticky, update local affinity, and publish event
+            Affinity::Unset | Affinity::Sticky(_) => {
+                let affinity = new_chain_id.into();
+                self.set_affinity(affinity).await?;
+
+                iron_broadcast::chain_changed(new_chain_id, self.domain.clone(), affinity).await;
+            }
+
+            // If current affinity is global, there's nothing to update on this Ctx, and the
+            // domain is irrelevant in the update,
+            Affinity::Global => {
+                iron_broadcast::chain_changed(new_chain_id, None, Affinity::Global).await;
+            }
+        };
+
+        Ok(())
     }
 
-    pub async fn chain_id(&self) -> u32 {
+    pub async fn chain_id(&self) -> Result<u32> {
+        if let Some(chain_id) = self.peer_chain_id().await {
+            return Ok(chain_id);
+        }
+
         match self.get_affinity().await {
-            Affinity::Sticky(chain_id) => chain_id,
-            _ => Networks::read().await.get_current().chain_id,
+            Affinity::Sticky(chain_id) => Ok(chain_id),
+            _ => Ok(Networks::read().await.get_current()?.chain_id),
         }
     }
 }
@@ -8,6 +8,15 @@ pub enum Error {
 
     #[error("invalid chain id: {0}")]
     InvalidChainId(u32),
+
+    #[error("unknown connection request: {0}")]
+    UnknownConnectionRequest(u32),
+
+    #[error("origin {0} is not connected")]
+    UnknownOrigin(String),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
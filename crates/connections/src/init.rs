@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::BufReader,
     path::{Path, PathBuf},
@@ -21,6 +21,9 @@ pub async fn init(pathbuf: PathBuf) {
     #[derive(Debug, Deserialize)]
     struct PersistedStore {
         affinities: HashMap<String, Affinity>,
+
+        #[serde(default)]
+        approved: HashSet<String>,
     }
 
     let store: Store = if path.exists() {
@@ -31,7 +34,9 @@ pub async fn init(pathbuf: PathBuf) {
 
         Store {
             affinities: store.affinities,
+            approved: store.approved,
             file: pathbuf,
+            ..Default::default()
         }
     } else {
         Store {
@@ -2,10 +2,12 @@ pub mod commands;
 mod ctx;
 mod error;
 mod init;
+mod pending;
 mod store;
 pub mod utils;
 
 pub use ctx::Ctx;
 pub use error::{Error, Result};
 pub use init::init;
+pub use pending::{Approval, ConnectionId, PendingConnection};
 pub use store::Store;
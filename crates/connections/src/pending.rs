@@ -0,0 +1,27 @@
+use iron_types::{ChecksummedAddress, RequestSource};
+use serde::Serialize;
+
+/// Identifies a pending connection request
+pub type ConnectionId = u32;
+
+/// A peer asking to see the wallet's accounts, awaiting the user's approval
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingConnection {
+    pub id: ConnectionId,
+    pub origin: String,
+    pub peer: RequestSource,
+
+    /// Whether the origin is on a phishing blocklist, for the UI to warn about
+    pub phishing: bool,
+}
+
+/// An origin the user allowed to see the wallet's accounts
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Approval {
+    pub origin: String,
+
+    /// If set, the only account this origin gets to see
+    pub account: Option<ChecksummedAddress>,
+}
@@ -1,13 +1,17 @@
 use std::{
-    collections::HashMap,
-    fs::File,
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
     path::{Path, PathBuf},
 };
 
-use iron_types::Affinity;
+use iron_types::{Affinity, ChecksummedAddress, RequestSource};
 use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
 
-use crate::Result;
+use crate::{Approval, ConnectionId, Error, PendingConnection, Result};
+
+/// A peer waiting on a connection request, and how to answer it
+type Responder = (SocketAddr, oneshot::Sender<bool>);
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
@@ -19,6 +23,25 @@ pub struct Store {
     // rule is currently a domain, but may eventually grow
     // TODO: removing networks will cause some affinities to become invalid. need to clean them up
     pub(crate) affinities: HashMap<String, Affinity>,
+
+    // origins the user allowed to see the wallet's accounts
+    pub(crate) approved: HashSet<String>,
+
+    // maps origin -> the only account it may see, for origins the user scoped to a single account
+    pub(crate) scoped_accounts: HashMap<String, ChecksummedAddress>,
+
+    // connection requests awaiting the user's decision, at most one per origin, along with every
+    // peer waiting on it
+    #[serde(skip)]
+    pending: HashMap<ConnectionId, (PendingConnection, Vec<Responder>)>,
+
+    #[serde(skip)]
+    next_connection_id: ConnectionId,
+
+    // maps peer socket -> chain_id, for connections pinned to a network of their own
+    // these take precedence over affinities, and only last as long as the connection
+    #[serde(skip)]
+    peer_chains: HashMap<SocketAddr, u32>,
 }
 
 impl Store {
@@ -36,14 +59,171 @@ impl Store {
         Ok(())
     }
 
+    /// The network a single peer connection was pinned to, if any
+    pub fn get_peer_chain_id(&self, socket: &SocketAddr) -> Option<u32> {
+        self.peer_chains.get(socket).copied()
+    }
+
+    /// Pins a single peer connection to a network, or clears it with `None`
+    pub fn set_peer_chain_id(&mut self, socket: SocketAddr, chain_id: Option<u32>) {
+        match chain_id {
+            Some(chain_id) => self.peer_chains.insert(socket, chain_id),
+            None => self.peer_chains.remove(&socket),
+        };
+    }
+
+    pub fn is_approved(&self, origin: &str) -> bool {
+        self.approved.contains(origin)
+    }
+
+    /// Lists every origin that was granted access, along with its account scope
+    pub fn approvals(&self) -> Vec<Approval> {
+        let mut approvals: Vec<_> = self
+            .approved
+            .iter()
+            .map(|origin| Approval {
+                origin: origin.clone(),
+                account: self.scoped_accounts.get(origin).copied(),
+            })
+            .collect();
+        approvals.sort_by(|a, b| a.origin.cmp(&b.origin));
+
+        approvals
+    }
+
+    /// Takes away an origin's access to the wallet's accounts
+    /// It will have to ask again through `eth_requestAccounts`
+    pub fn revoke(&mut self, origin: &str) -> Result<()> {
+        if !self.approved.remove(origin) {
+            return Err(Error::UnknownOrigin(origin.into()));
+        }
+        self.scoped_accounts.remove(origin);
+        self.save()?;
+
+        Ok(())
+    }
+
+    /// The only account an origin may see, if it was scoped to one
+    pub fn get_scoped_account(&self, origin: &str) -> Option<ChecksummedAddress> {
+        self.scoped_accounts.get(origin).copied()
+    }
+
+    /// Restricts an approved origin to a single account, or lifts the restriction with `None`
+    pub fn set_scoped_account(
+        &mut self,
+        origin: &str,
+        account: Option<ChecksummedAddress>,
+    ) -> Result<()> {
+        if !self.is_approved(origin) {
+            return Err(Error::UnknownOrigin(origin.into()));
+        }
+
+        match account {
+            Some(account) => self.scoped_accounts.insert(origin.into(), account),
+            None => self.scoped_accounts.remove(origin),
+        };
+        self.save()?;
+
+        Ok(())
+    }
+
+    /// Registers a peer's request to see the wallet's accounts
+    /// An origin with a request already awaiting the user joins that one, rather than prompting
+    /// again. The returned receiver resolves to whether the user approved it
+    pub fn request_connection(
+        &mut self,
+        peer: RequestSource,
+        phishing: bool,
+    ) -> (ConnectionId, oneshot::Receiver<bool>) {
+        let (tx, rx) = oneshot::channel();
+
+        if let Some((request, responders)) = self
+            .pending
+            .values_mut()
+            .find(|(p, _)| p.origin == peer.origin)
+        {
+            request.phishing |= phishing;
+            responders.push((peer.socket, tx));
+            return (request.id, rx);
+        }
+
+        let id = self.next_connection_id;
+        self.next_connection_id += 1;
+
+        let socket = peer.socket;
+        let request = PendingConnection {
+            id,
+            origin: peer.origin.clone(),
+            peer,
+            phishing,
+        };
+        self.pending.insert(id, (request, vec![(socket, tx)]));
+
+        (id, rx)
+    }
+
+    pub fn pending_connections(&self) -> Vec<PendingConnection> {
+        self.pending.values().map(|(p, _)| p.clone()).collect()
+    }
+
+    /// Grants a pending request's origin access to the wallet's accounts
+    /// Every peer of that origin waiting on it is answered at once
+    pub fn approve_connection(&mut self, id: ConnectionId) -> Result<()> {
+        let (request, responders) = self
+            .pending
+            .remove(&id)
+            .ok_or(Error::UnknownConnectionRequest(id))?;
+
+        self.approved.insert(request.origin.clone());
+        self.save()?;
+
+        for (_, responder) in responders {
+            let _ = responder.send(true);
+        }
+
+        Ok(())
+    }
+
+    /// Grants an origin access right away, for peers the user approved through other means, such
+    /// as a WalletConnect session proposal
+    pub fn approve(&mut self, origin: &str) -> Result<()> {
+        if self.approved.insert(origin.to_string()) {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Forgets what was kept for a peer that went away: its pinned network, and its place in any
+    /// connection request still awaiting the user. Requests no one waits on anymore are dropped
+    pub fn forget_peer(&mut self, socket: SocketAddr) {
+        self.peer_chains.remove(&socket);
+
+        for (_, responders) in self.pending.values_mut() {
+            responders.retain(|(peer, _)| *peer != socket);
+        }
+        self.pending
+            .retain(|_, (_, responders)| !responders.is_empty());
+    }
+
+    /// Drops a pending connection request
+    pub fn reject_connection(&mut self, id: ConnectionId) -> Result<()> {
+        let (_, responders) = self
+            .pending
+            .remove(&id)
+            .ok_or(Error::UnknownConnectionRequest(id))?;
+
+        for (_, responder) in responders {
+            let _ = responder.send(false);
+        }
+
+        Ok(())
+    }
+
     // Persists current state to disk
     fn save(&self) -> Result<()> {
         let pathbuf = self.file.clone();
-        let path = Path::new(&pathbuf);
-        let file = File::create(path)?;
-
-        serde_json::to_writer_pretty(file, self)?;
 
-        Ok(())
+        Ok(iron_types::save_json(Path::new(&pathbuf), self)?)
     }
 }
@@ -4,6 +4,9 @@
 /// https://kerkour.com/rust-file-encryption-chacha20poly1305-argon2
 /// Encrypted secrets are secured by a password. We use Argon2 to derive a key from it, and then
 /// the ChaCha20poly1305 scheme to encrypt the data.
+///
+/// Encryption happens per value: only secret fields (e.g. a wallet's mnemonic) are stored as
+/// `EncryptedData`, while the rest of the config they live in stays in plaintext.
 use aead::{KeyInit, OsRng};
 use chacha20poly1305::XChaCha20Poly1305;
 use ethers::core::rand::RngCore;
@@ -30,28 +33,31 @@ where
     OsRng.fill_bytes(&mut salt);
     OsRng.fill_bytes(&mut nonce);
 
+    let json = serde_json::to_string(data)?;
+
     let mut key = password_to_key(password, &salt);
 
     let aead = XChaCha20Poly1305::new(key[..32].into());
     let encryptor = aead::stream::EncryptorBE32::from_aead(aead, nonce.as_ref().into());
 
-    let json = serde_json::to_string(data).unwrap();
     let bytes = json.as_bytes();
-    let ciphertext = encryptor.encrypt_last(bytes).unwrap();
+    let ciphertext = encryptor
+        .encrypt_last(bytes)
+        .map_err(|_| anyhow::anyhow!("failed to encrypt secret"));
 
-    let res = EncryptedData {
+    let res = ciphertext.map(|ciphertext| EncryptedData {
         salt,
         nonce,
         ciphertext,
         phantom: Default::default(),
-    };
+    });
 
     // zero out sensitive data
     salt.zeroize();
     nonce.zeroize();
     key.zeroize();
 
-    Ok(res)
+    res
 }
 
 #[allow(unused)]
@@ -70,14 +76,17 @@ where
 
     const BUFFER_LEN: usize = 500 + 16;
     let mut buffer = [0u8; BUFFER_LEN];
-    let plaintext: Vec<u8> = decryptor.decrypt_last(&data.ciphertext[..]).unwrap();
+    // a wrong password results in an authentication failure here
+    let plaintext = decryptor
+        .decrypt_last(&data.ciphertext[..])
+        .map_err(|_| anyhow::anyhow!("invalid password"));
 
     // zero out sensitive data
     salt.zeroize();
     nonce.zeroize();
     key.zeroize();
 
-    Ok(serde_json::from_slice(&plaintext)?)
+    Ok(serde_json::from_slice(&plaintext?)?)
 }
 
 fn password_to_key(password: &str, salt: &[u8; 32]) -> Vec<u8> {
@@ -115,4 +124,15 @@ mod tests {
 
         assert_eq!(decrypted, secret);
     }
+
+    #[test]
+    fn test_decryption_with_wrong_password() {
+        let secret = SecretData {
+            foo: "The quick brown fox jumps over the lazy dog".to_string(),
+        };
+
+        let encrypted_data = encrypt(&secret, "foo bar!@").unwrap();
+
+        assert!(decrypt(&encrypted_data, "wrong").is_err());
+    }
 }
@@ -1,12 +1,15 @@
-use ethers::types::{Address, Chain, U256};
-use iron_types::{events::Tx, TokenBalance, UINotify};
+use std::path::PathBuf;
 
-use super::{Paginated, Pagination, Result};
-use crate::{
-    utils::{fetch_etherscan_abi, fetch_etherscan_contract_name},
-    Error, StoredContract, DB,
+use ethers::types::{Address, U256};
+use iron_networks::Networks;
+use iron_settings::Settings;
+use iron_types::{
+    events::Tx, AuditEntry, GlobalState, NftToken, SignedMessage, TokenBalance, TxRecord, UINotify,
 };
 
+use super::{Paginated, Pagination, Result, MAX_PAGE_SIZE};
+use crate::{export, Error, ExportFormat, ExportRange, StoredContract, DB};
+
 #[tauri::command]
 pub async fn db_get_transactions(
     address: Address,
@@ -18,6 +21,75 @@ pub async fn db_get_transactions(
         .await
 }
 
+/// Deletes transaction history older than the given number of seconds
+#[tauri::command]
+pub async fn db_prune_history(older_than_secs: u64, db: tauri::State<'_, DB>) -> Result<u64> {
+    db.prune_history(std::time::Duration::from_secs(older_than_secs))
+        .await
+}
+
+/// Reads the log of signing requests, optionally starting at the given unix timestamp
+#[tauri::command]
+pub async fn db_get_audit_log(
+    since: Option<u64>,
+    db: tauri::State<'_, DB>,
+) -> Result<Vec<AuditEntry>> {
+    db.get_audit_log(since).await
+}
+
+/// Browses the log of message and typed data signatures, most recent first
+/// `search` matches part of the origin, method or address, or a whole payload hash or signature
+#[tauri::command]
+pub async fn db_get_signed_messages(
+    search: Option<String>,
+    pagination: Option<Pagination>,
+    db: tauri::State<'_, DB>,
+) -> Result<Paginated<SignedMessage>> {
+    db.get_signed_messages(search.as_deref(), pagination.unwrap_or_default())
+        .await
+}
+
+/// Exports an account's indexed transactions on a network to a CSV or JSON file, valued in the
+/// configured fiat currency where prices from the time are known, and optionally along with the
+/// messages it signed
+/// Returns how many transactions were exported
+#[tauri::command]
+pub async fn db_export_history(
+    path: PathBuf,
+    range: ExportRange,
+    format: ExportFormat,
+    include_signatures: bool,
+    db: tauri::State<'_, DB>,
+) -> Result<usize> {
+    let network = Networks::read()
+        .await
+        .get_network(range.chain_id)
+        .ok_or(Error::InvalidChain)?;
+    let currency = Settings::read().await.get().fiat_currency.clone();
+
+    export::export(
+        &db,
+        &network,
+        &range,
+        format,
+        include_signatures,
+        &currency,
+        &path,
+    )
+    .await
+}
+
+/// Reads the history of transactions sent through the wallet on a network, most recent first
+#[tauri::command]
+pub async fn db_get_history(
+    chain_id: u32,
+    limit: Option<u32>,
+    db: tauri::State<'_, DB>,
+) -> Result<Vec<TxRecord>> {
+    let limit = limit.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    db.get_history(chain_id, limit).await
+}
+
 #[tauri::command]
 pub async fn db_get_erc20_balances(
     chain_id: u32,
@@ -27,6 +99,16 @@ pub async fn db_get_erc20_balances(
     db.get_erc20_balances(chain_id, address).await
 }
 
+/// Lists the NFTs an address owns on a network
+#[tauri::command]
+pub async fn db_get_nfts(
+    chain_id: u32,
+    address: Address,
+    db: tauri::State<'_, DB>,
+) -> Result<Vec<NftToken>> {
+    db.get_nfts(chain_id, address).await
+}
+
 #[tauri::command]
 pub async fn db_get_native_balance(
     chain_id: u32,
@@ -50,11 +132,19 @@ pub async fn db_insert_contract(
     address: Address,
     db: tauri::State<'_, DB>,
 ) -> Result<()> {
-    let chain = Chain::try_from(chain_id).map_err(|_| Error::InvalidChain)?;
-    let name = fetch_etherscan_contract_name(chain, address).await?;
-    let abi = fetch_etherscan_abi(chain, address)
-        .await?
-        .map(|abi| serde_json::to_string(&abi).unwrap());
+    let network = Networks::read()
+        .await
+        .get_network(chain_id)
+        .ok_or(Error::InvalidChain)?;
+
+    // unverified contracts are still added, just without a name or ABI
+    let (name, abi) = match network.explorer().await?.verified_contract(address).await? {
+        Some(contract) => (
+            Some(contract.name),
+            Some(serde_json::to_string(&contract.abi)?),
+        ),
+        None => (None, None),
+    };
 
     // self.window_snd.send(UINotify::BalancesUpdated.into())?;
     // send ContractsUpdated event to UI using iron_broadcast
@@ -1,5 +1,3 @@
-use ethers::prelude::errors::EtherscanError;
-
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -9,13 +7,19 @@ pub enum Error {
     SqlxMigrate(#[from] sqlx::migrate::MigrateError),
 
     #[error(transparent)]
-    Etherscan(#[from] EtherscanError),
+    Networks(#[from] iron_networks::Error),
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
 
     #[error("Invalid chain")]
     InvalidChain,
 
-    #[error(transparent)]
-    Settings(#[from] iron_settings::Error),
+    #[error("stored payload of transaction {0:?} is not valid hex")]
+    InvalidPayload(ethers::types::H256),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,257 @@
+//! Writes an account's history on a network to a file, for accounting and tax tools
+//! Transactions are valued in fiat at the last hourly price recorded before them. Prices are
+//! only recorded while the app runs, so transactions from before then are left without a value
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ethers::{
+    types::{Address, H256},
+    utils::{format_units, to_checksum},
+};
+use iron_networks::Network;
+use iron_types::SignedMessage;
+use serde::{Deserialize, Serialize};
+
+use super::{Result, DB};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Which account, network and block range to export
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportRange {
+    pub chain_id: u32,
+    pub address: Address,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedTx {
+    /// unix timestamp, in seconds
+    timestamp: u64,
+    hash: H256,
+    block_number: u64,
+    from: Address,
+    to: Option<Address>,
+
+    /// `in`, `out` or `self`, from the account's point of view
+    direction: &'static str,
+
+    /// In the native currency's units, rather than wei
+    value: String,
+    fiat_value: Option<f64>,
+    failed: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Export<'a> {
+    chain_id: u32,
+    address: Address,
+    symbol: &'a str,
+    fiat_currency: &'a str,
+    transactions: Vec<ExportedTx>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signatures: Option<Vec<SignedMessage>>,
+}
+
+/// Writes the export to `path`, returning how many transactions it has
+/// JSON exports are a single document. CSV ones put signatures, if included, in a second file
+/// next to the first, with `-signatures` appended to its name
+pub(crate) async fn export(
+    db: &DB,
+    network: &Network,
+    range: &ExportRange,
+    format: ExportFormat,
+    include_signatures: bool,
+    fiat_currency: &str,
+    path: &Path,
+) -> Result<usize> {
+    let txs = db
+        .get_transactions_in_range(
+            range.chain_id,
+            range.address,
+            range.from_block,
+            range.to_block,
+        )
+        .await?;
+
+    let mut transactions = Vec::with_capacity(txs.len());
+    for (tx, timestamp) in txs {
+        let value = format_units(tx.value, network.decimals).unwrap_or_default();
+        let price = db
+            .get_price_at(range.chain_id, None, fiat_currency, timestamp)
+            .await?;
+
+        transactions.push(ExportedTx {
+            timestamp,
+            hash: tx.hash,
+            block_number: tx.block_number,
+            from: tx.from,
+            to: tx.to,
+            direction: direction(range.address, tx.from, tx.to),
+            fiat_value: price.and_then(|p| Some(value.parse::<f64>().ok()? * p)),
+            value,
+            failed: tx.status == 0,
+        });
+    }
+
+    let signatures = if include_signatures {
+        Some(
+            db.get_signed_messages_by(range.address, range.chain_id)
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    let export = Export {
+        chain_id: range.chain_id,
+        address: range.address,
+        symbol: &network.currency,
+        fiat_currency,
+        transactions,
+        signatures,
+    };
+    let count = export.transactions.len();
+
+    match format {
+        ExportFormat::Json => fs::write(path, serde_json::to_string_pretty(&export)?)?,
+        ExportFormat::Csv => {
+            fs::write(path, txs_csv(&export))?;
+            if let Some(signatures) = &export.signatures {
+                fs::write(signatures_path(path), signatures_csv(signatures))?;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+fn direction(account: Address, from: Address, to: Option<Address>) -> &'static str {
+    match (from == account, to == Some(account)) {
+        (true, true) => "self",
+        (true, false) => "out",
+        _ => "in",
+    }
+}
+
+fn txs_csv(export: &Export) -> String {
+    let mut csv = String::from(
+        "timestamp,hash,block_number,from,to,direction,value,symbol,fiat_value,fiat_currency,failed\n",
+    );
+
+    for tx in &export.transactions {
+        let row = [
+            tx.timestamp.to_string(),
+            format!("0x{:x}", tx.hash),
+            tx.block_number.to_string(),
+            to_checksum(&tx.from, None),
+            tx.to.map(|to| to_checksum(&to, None)).unwrap_or_default(),
+            tx.direction.to_string(),
+            tx.value.clone(),
+            export.symbol.to_string(),
+            tx.fiat_value.map(|v| v.to_string()).unwrap_or_default(),
+            export.fiat_currency.to_string(),
+            tx.failed.to_string(),
+        ];
+        push_row(&mut csv, &row);
+    }
+
+    csv
+}
+
+fn signatures_csv(signatures: &[SignedMessage]) -> String {
+    let mut csv = String::from("timestamp,address,origin,method,payload_hash,signature\n");
+
+    for message in signatures {
+        let row = [
+            message.timestamp.to_string(),
+            to_checksum(&message.address, None),
+            message.origin.clone().unwrap_or_default(),
+            message.method.clone(),
+            format!("0x{:x}", message.payload_hash),
+            message.signature.clone(),
+        ];
+        push_row(&mut csv, &row);
+    }
+
+    csv
+}
+
+fn push_row(csv: &mut String, fields: &[String]) {
+    let row: Vec<_> = fields.iter().map(|f| escape(f)).collect();
+    let _ = writeln!(csv, "{}", row.join(","));
+}
+
+/// Quotes fields that would otherwise break the row, such as origins with commas
+/// Fields that spreadsheets would run as formulas, such as a dapp-chosen title starting with `=`,
+/// are prefixed with a single quote so they're shown as text instead
+fn escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+fn signatures_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-signatures.{}", stem, ext.to_string_lossy()),
+        None => format!("{}-signatures", stem),
+    };
+
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(escape("@SUM(A1)"), "'@SUM(A1)");
+
+        assert_eq!(
+            signatures_path(Path::new("/tmp/history.csv")),
+            PathBuf::from("/tmp/history-signatures.csv")
+        );
+    }
+
+    #[test]
+    fn labels_directions() {
+        let account = Address::from_low_u64_be(1);
+        let other = Address::from_low_u64_be(2);
+
+        assert_eq!(direction(account, account, Some(other)), "out");
+        assert_eq!(direction(account, other, Some(account)), "in");
+        assert_eq!(direction(account, account, Some(account)), "self");
+        assert_eq!(direction(account, account, None), "out");
+    }
+}
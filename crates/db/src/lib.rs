@@ -1,14 +1,22 @@
 pub mod commands;
 mod error;
+mod export;
 mod pagination;
 mod queries;
-mod utils;
 
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use ethers::types::{Address, H256, U256};
-use iron_types::{events::Tx, Event, StoredContract, TokenBalance, TokenMetadata};
+use ethers::types::{Address, Bytes, H256, U256};
+use iron_types::{
+    events::Tx, AuditEntry, Event, GasSample, NftToken, SignedMessage, StoredContract,
+    TokenBalance, TokenMetadata, TokenPrice, TxRecord, TxStatus, NATIVE_ASSET,
+};
 use sqlx::{
+    migrate::Migrator,
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
     Row,
 };
@@ -16,9 +24,12 @@ use tracing::{instrument, trace};
 
 pub use self::{
     error::{Error, Result},
-    pagination::{Paginated, Pagination},
+    export::{ExportFormat, ExportRange},
+    pagination::{Paginated, Pagination, MAX_PAGE_SIZE},
 };
 
+static MIGRATOR: Migrator = sqlx::migrate!("../../migrations/");
+
 #[derive(Debug, Clone)]
 pub struct DB {
     pub pool: sqlx::Pool<sqlx::Sqlite>,
@@ -37,6 +48,32 @@ impl DB {
             .connect_with(connect_options)
             .await?;
 
+        let db = Self { pool };
+
+        // a failed or buggy migration would otherwise leave no way back to the user's data
+        let version = db.schema_version().await?;
+        if version > 0 && version < latest_schema_version() {
+            db.backup(&backup_path(path, version)).await?;
+        }
+        db.migrate().await?;
+
+        Ok(db)
+    }
+
+    /// Connects to a fresh, migrated in-memory database
+    /// Nothing touches the filesystem, and all data is lost once the last handle is dropped
+    pub async fn connect_memory() -> Result<Self> {
+        let connect_options = SqliteConnectOptions::from_str("sqlite::memory:")?.foreign_keys(true);
+
+        // every sqlite connection gets its own in-memory database, so the pool must keep exactly
+        // one connection alive for as long as it exists
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(connect_options)
+            .await?;
+
         let db = Self { pool };
         db.migrate().await?;
 
@@ -203,6 +240,8 @@ impl DB {
         from_or_to: Address,
         pagination: Pagination,
     ) -> Result<Paginated<Tx>> {
+        let pagination = pagination.capped();
+
         let items: Vec<_> = sqlx::query(
             r#" SELECT *
             FROM transactions
@@ -238,6 +277,365 @@ impl DB {
         Ok(Paginated::new(items, pagination, total))
     }
 
+    /// Indexed transactions from or to an address, oldest first, within an inclusive block range
+    /// Each comes with the unix timestamp it was indexed at, which for transactions synced as
+    /// they happen is close to when they were mined
+    pub async fn get_transactions_in_range(
+        &self,
+        chain_id: u32,
+        from_or_to: Address,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+    ) -> Result<Vec<(Tx, u64)>> {
+        let res: Vec<_> = sqlx::query(
+            r#" SELECT *
+            FROM transactions
+            WHERE chain_id = ?
+            AND (from_address = ? or to_address = ?) COLLATE NOCASE
+            AND block_number >= ? AND block_number <= ?
+            ORDER BY block_number ASC, position ASC"#,
+        )
+        .bind(chain_id)
+        .bind(format!("0x{:x}", from_or_to))
+        .bind(format!("0x{:x}", from_or_to))
+        .bind(from_block.unwrap_or_default() as i64)
+        .bind(to_block.map_or(i64::MAX, |b| b as i64))
+        .map(|row| {
+            (
+                Tx::try_from(&row).unwrap(),
+                row.get::<i64, _>("created_at") as u64,
+            )
+        })
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Deletes transactions recorded longer than `older_than` ago
+    /// Returns how many were removed
+    pub async fn prune_history(&self, older_than: Duration) -> Result<u64> {
+        let res = queries::prune_transactions(older_than)
+            .execute(self.pool())
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Appends an entry to the signing audit log
+    pub async fn insert_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        queries::insert_audit_entry(entry)
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Reads the signing audit log, optionally only from a given unix timestamp onwards
+    pub async fn get_audit_log(&self, since: Option<u64>) -> Result<Vec<AuditEntry>> {
+        let res: Vec<_> = sqlx::query(
+            r#" SELECT *
+            FROM signing_audit_log
+            WHERE created_at >= ?
+            ORDER BY id ASC "#,
+        )
+        .bind(since.unwrap_or_default() as i64)
+        .map(|row| AuditEntry::try_from(&row).unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Appends a signature to the signed message log
+    pub async fn insert_signed_message(&self, message: &SignedMessage) -> Result<()> {
+        queries::insert_signed_message(message)
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Reads the signed message log, most recent first
+    /// When given, `search` matches part of the origin, method or address, or a whole payload
+    /// hash or signature
+    pub async fn get_signed_messages(
+        &self,
+        search: Option<&str>,
+        pagination: Pagination,
+    ) -> Result<Paginated<SignedMessage>> {
+        let pagination = pagination.capped();
+        let search = search.map(str::trim).unwrap_or_default();
+        let pattern = format!("%{}%", search);
+
+        let items: Vec<_> = sqlx::query(
+            r#" SELECT *
+            FROM signed_messages
+            WHERE ? = ''
+            OR origin LIKE ? OR method LIKE ? OR address LIKE ?
+            OR payload_hash = ? OR signature = ?
+            ORDER BY id DESC
+            LIMIT ? OFFSET ? "#,
+        )
+        .bind(search)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(search)
+        .bind(search)
+        .bind(pagination.page_size)
+        .bind(pagination.offset())
+        .map(|row| SignedMessage::try_from(&row).unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        let total: u32 = sqlx::query(
+            r#" SELECT COUNT(*) as total
+            FROM signed_messages
+            WHERE ? = ''
+            OR origin LIKE ? OR method LIKE ? OR address LIKE ?
+            OR payload_hash = ? OR signature = ? "#,
+        )
+        .bind(search)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(search)
+        .bind(search)
+        .map(|row| row.get("total"))
+        .fetch_one(self.pool())
+        .await?;
+
+        Ok(Paginated::new(items, pagination, total))
+    }
+
+    /// Every message an address signed on a network, oldest first
+    pub async fn get_signed_messages_by(
+        &self,
+        address: Address,
+        chain_id: u32,
+    ) -> Result<Vec<SignedMessage>> {
+        let res: Vec<_> = sqlx::query(
+            r#" SELECT *
+            FROM signed_messages
+            WHERE address = ? AND chain_id = ?
+            ORDER BY id ASC "#,
+        )
+        .bind(format!("0x{:x}", address))
+        .bind(chain_id)
+        .map(|row| SignedMessage::try_from(&row).unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Whether an account ever sent a transaction to an address on a network, either through the
+    /// wallet or as found while syncing
+    pub async fn has_sent_to(&self, chain_id: u32, from: Address, to: Address) -> Result<bool> {
+        let res = sqlx::query(
+            r#" SELECT EXISTS (
+                SELECT 1 FROM tx_history WHERE chain_id = ? AND from_address = ? AND to_address = ?
+                UNION ALL
+                SELECT 1 FROM transactions WHERE chain_id = ? AND from_address = ? AND to_address = ?
+            ) as result "#,
+        )
+        .bind(chain_id)
+        .bind(format!("0x{:x}", from))
+        .bind(format!("0x{:x}", to))
+        .bind(chain_id)
+        .bind(format!("0x{:x}", from))
+        .bind(format!("0x{:x}", to))
+        .map(|row| row.get("result"))
+        .fetch_one(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Appends a sent transaction to the activity history
+    pub async fn record_tx(&self, record: &TxRecord) -> Result<()> {
+        queries::insert_tx_record(record)
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the status of a transaction in the activity history
+    pub async fn update_tx_status(&self, hash: H256, status: TxStatus) -> Result<()> {
+        queries::update_tx_status(hash, status)
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Moves a pending transaction to its final status
+    /// Returns false if it was already settled, so that callers only react to it once
+    pub async fn settle_tx(&self, hash: H256, status: TxStatus) -> Result<bool> {
+        let res =
+            sqlx::query(r#" UPDATE tx_history SET status = ? WHERE hash = ? AND status = ? "#)
+                .bind(status.as_str())
+                .bind(format!("0x{:x}", hash))
+                .bind(TxStatus::Pending.as_str())
+                .execute(self.pool())
+                .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Records the block a settled transaction was mined in
+    pub async fn set_tx_block(&self, hash: H256, block_number: u64) -> Result<()> {
+        sqlx::query(r#" UPDATE tx_history SET block_number = ? WHERE hash = ? "#)
+            .bind(block_number as i64)
+            .bind(format!("0x{:x}", hash))
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Puts transactions mined at or after a block back to pending, once that block was
+    /// reorganized away
+    /// Returns them, for the caller to watch again
+    pub async fn revert_txs_from(&self, chain_id: u32, block_number: u64) -> Result<Vec<TxRecord>> {
+        let mut conn = self.tx().await?;
+
+        let reverted: Vec<_> =
+            sqlx::query(r#" SELECT * FROM tx_history WHERE chain_id = ? AND block_number >= ? "#)
+                .bind(chain_id)
+                .bind(block_number as i64)
+                .map(|row| TxRecord::try_from(&row).unwrap())
+                .fetch_all(&mut conn)
+                .await?;
+
+        sqlx::query(
+            r#" UPDATE tx_history SET status = ?, block_number = NULL
+            WHERE chain_id = ? AND block_number >= ? "#,
+        )
+        .bind(TxStatus::Pending.as_str())
+        .bind(chain_id)
+        .bind(block_number as i64)
+        .execute(&mut conn)
+        .await?;
+
+        conn.commit().await?;
+
+        Ok(reverted
+            .into_iter()
+            .map(|record| TxRecord {
+                status: TxStatus::Pending,
+                ..record
+            })
+            .collect())
+    }
+
+    pub async fn get_tx(&self, hash: H256) -> Result<Option<TxRecord>> {
+        let res = sqlx::query(r#" SELECT * FROM tx_history WHERE hash = ? "#)
+            .bind(format!("0x{:x}", hash))
+            .map(|row| TxRecord::try_from(&row).unwrap())
+            .fetch_optional(self.pool())
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Transactions sent on a network that haven't been mined yet
+    pub async fn get_pending_txs(&self, chain_id: u32) -> Result<Vec<TxRecord>> {
+        let res: Vec<_> =
+            sqlx::query(r#" SELECT * FROM tx_history WHERE chain_id = ? AND status = ? "#)
+                .bind(chain_id)
+                .bind(TxStatus::Pending.as_str())
+                .map(|row| TxRecord::try_from(&row).unwrap())
+                .fetch_all(self.pool())
+                .await?;
+
+        Ok(res)
+    }
+
+    /// Transactions sent on any network that haven't been mined yet
+    pub async fn get_all_pending_txs(&self) -> Result<Vec<TxRecord>> {
+        let res: Vec<_> = sqlx::query(r#" SELECT * FROM tx_history WHERE status = ? "#)
+            .bind(TxStatus::Pending.as_str())
+            .map(|row| TxRecord::try_from(&row).unwrap())
+            .fetch_all(self.pool())
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Keeps the nonce and signed bytes of a sent transaction, to rebroadcast or replace it with
+    /// once the node no longer knows of it, and whether it went through a private relay
+    pub async fn save_tx_payload(
+        &self,
+        hash: H256,
+        nonce: Option<U256>,
+        raw: &Bytes,
+        private: bool,
+    ) -> Result<()> {
+        sqlx::query(r#" UPDATE tx_history SET nonce = ?, raw = ?, private = ? WHERE hash = ? "#)
+            .bind(nonce.map(|n| n.to_string()))
+            .bind(raw.to_string())
+            .bind(private)
+            .bind(format!("0x{:x}", hash))
+            .execute(self.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    /// The nonce and signed bytes of a sent transaction, if they were kept
+    pub async fn get_tx_payload(&self, hash: H256) -> Result<Option<(Option<U256>, Bytes)>> {
+        let row = sqlx::query(
+            r#" SELECT nonce, raw FROM tx_history WHERE hash = ? AND raw IS NOT NULL "#,
+        )
+        .bind(format!("0x{:x}", hash))
+        .fetch_optional(self.pool())
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let nonce: Option<String> = row.get("nonce");
+        let raw: String = row.get("raw");
+
+        Ok(Some((
+            nonce.and_then(|n| U256::from_dec_str(&n).ok()),
+            Bytes::from_str(&raw).map_err(|_| Error::InvalidPayload(hash))?,
+        )))
+    }
+
+    /// Whether a transaction was sent through a private relay
+    pub async fn is_tx_private(&self, hash: H256) -> Result<bool> {
+        let private = sqlx::query(r#" SELECT private FROM tx_history WHERE hash = ? "#)
+            .bind(format!("0x{:x}", hash))
+            .fetch_optional(self.pool())
+            .await?
+            .map(|row| row.get("private"))
+            .unwrap_or(false);
+
+        Ok(private)
+    }
+
+    /// Reads the activity history of a network, most recent first
+    pub async fn get_history(&self, chain_id: u32, limit: u32) -> Result<Vec<TxRecord>> {
+        let res: Vec<_> = sqlx::query(
+            r#" SELECT *
+            FROM tx_history
+            WHERE chain_id = ?
+            ORDER BY created_at DESC, rowid DESC
+            LIMIT ? "#,
+        )
+        .bind(chain_id)
+        .bind(limit)
+        .map(|row| TxRecord::try_from(&row).unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
     pub async fn get_contracts(&self, chain_id: u32) -> Result<Vec<StoredContract>> {
         let res: Vec<_> = sqlx::query(
             r#" SELECT *
@@ -252,6 +650,28 @@ impl DB {
         Ok(res)
     }
 
+    /// The stored name and ABI of a single contract, for those registered with an ABI
+    pub async fn get_contract_abi(
+        &self,
+        chain_id: u32,
+        address: Address,
+    ) -> Result<Option<(Option<String>, ethers::abi::Abi)>> {
+        let row = sqlx::query(
+            r#" SELECT name, abi
+            FROM contracts
+            WHERE chain_id = ? AND address = ? AND abi IS NOT NULL "#,
+        )
+        .bind(chain_id)
+        .bind(format!("0x{:x}", address))
+        .fetch_optional(self.pool())
+        .await?;
+
+        Ok(row.and_then(|row| {
+            let abi = serde_json::from_str(row.get::<&str, _>("abi")).ok()?;
+            Some((row.get::<Option<String>, _>("name"), abi))
+        }))
+    }
+
     pub async fn insert_contract_with_abi(
         &self,
         chain_id: u32,
@@ -327,6 +747,316 @@ impl DB {
         Ok(res)
     }
 
+    pub async fn get_nfts(&self, chain_id: u32, owner: Address) -> Result<Vec<NftToken>> {
+        let res: Vec<_> = sqlx::query(
+            r#"SELECT contract, token_id, token_uri
+            FROM nft_tokens
+            WHERE chain_id = ? AND owner = ?
+            ORDER BY contract, token_id"#,
+        )
+        .bind(chain_id)
+        .bind(format!("0x{:x}", owner))
+        .map(|row| row.try_into().unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Lists NFTs whose token URI hasn't been fetched yet
+    pub async fn get_nfts_missing_uri(&self, chain_id: u32) -> Result<Vec<(Address, U256)>> {
+        let res: Vec<_> = sqlx::query(
+            r#"SELECT contract, token_id FROM nft_tokens WHERE chain_id = ? AND token_uri IS NULL"#,
+        )
+        .bind(chain_id)
+        .map(|row| {
+            let token_id: &str = row.get("token_id");
+            (
+                Address::from_str(row.get::<&str, _>("contract")).unwrap(),
+                U256::from_str_radix(token_id.trim_start_matches("0x"), 16).unwrap(),
+            )
+        })
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Caches an NFT's token URI
+    /// An empty URI marks tokens whose contract doesn't provide one, so they aren't retried
+    pub async fn set_nft_token_uri(
+        &self,
+        chain_id: u32,
+        contract: Address,
+        token_id: U256,
+        uri: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE nft_tokens SET token_uri = ? WHERE chain_id = ? AND contract = ? AND token_id = ?"#,
+        )
+        .bind(uri)
+        .bind(chain_id)
+        .bind(format!("0x{:x}", contract))
+        .bind(format!("0x{:x}", token_id))
+        .execute(self.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    /// Adds a token to a network's watch list, along with its metadata
+    pub async fn watch_token(
+        &self,
+        chain_id: u32,
+        contract: Address,
+        metadata: TokenMetadata,
+    ) -> Result<()> {
+        let mut conn = self.tx().await?;
+
+        sqlx::query(r#"INSERT OR IGNORE INTO watched_tokens (chain_id, contract) VALUES (?, ?)"#)
+            .bind(chain_id)
+            .bind(format!("0x{:x}", contract))
+            .execute(&mut conn)
+            .await?;
+        queries::update_erc20_metadata(contract, chain_id, metadata)
+            .execute(&mut conn)
+            .await?;
+
+        conn.commit().await?;
+        Ok(())
+    }
+
+    /// Removes a token from a network's watch list, along with its known balances
+    pub async fn unwatch_token(&self, chain_id: u32, contract: Address) -> Result<()> {
+        let mut conn = self.tx().await?;
+
+        sqlx::query(r#"DELETE FROM watched_tokens WHERE chain_id = ? AND contract = ?"#)
+            .bind(chain_id)
+            .bind(format!("0x{:x}", contract))
+            .execute(&mut conn)
+            .await?;
+        sqlx::query(r#"DELETE FROM balances WHERE chain_id = ? AND contract = ?"#)
+            .bind(chain_id)
+            .bind(format!("0x{:x}", contract))
+            .execute(&mut conn)
+            .await?;
+
+        conn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_watched_tokens(&self, chain_id: u32) -> Result<Vec<Address>> {
+        let res: Vec<_> = sqlx::query(r#"SELECT contract FROM watched_tokens WHERE chain_id = ?"#)
+            .bind(chain_id)
+            .map(|row| Address::from_str(row.get::<&str, _>("contract")).unwrap())
+            .fetch_all(self.pool())
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Cached fiat prices of a network's native currency and tokens
+    pub async fn get_prices(&self, chain_id: u32, currency: &str) -> Result<Vec<TokenPrice>> {
+        let res: Vec<_> = sqlx::query(
+            r#"SELECT asset, price, updated_at FROM prices WHERE chain_id = ? AND currency = ?"#,
+        )
+        .bind(chain_id)
+        .bind(currency)
+        .map(|row| row.try_into().unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Replaces cached prices, with `None` standing for the network's native currency
+    pub async fn save_prices(
+        &self,
+        chain_id: u32,
+        currency: &str,
+        prices: Vec<(Option<Address>, f64)>,
+    ) -> Result<()> {
+        let mut conn = self.tx().await?;
+
+        for (contract, price) in prices {
+            let asset = asset(contract);
+
+            sqlx::query(
+                r#" INSERT OR REPLACE INTO prices (chain_id, asset, currency, price, updated_at)
+                        VALUES (?,?,?,?,CAST(strftime('%s', 'now') AS INTEGER)) "#,
+            )
+            .bind(chain_id)
+            .bind(&asset)
+            .bind(currency)
+            .bind(price)
+            .execute(&mut conn)
+            .await?;
+
+            // the last price of each hour is kept, for valuing past transactions
+            sqlx::query(
+                r#" INSERT OR REPLACE INTO price_history (chain_id, asset, currency, hour, price)
+                        VALUES (?,?,?,CAST(strftime('%s', 'now') AS INTEGER) / 3600,?) "#,
+            )
+            .bind(chain_id)
+            .bind(&asset)
+            .bind(currency)
+            .bind(price)
+            .execute(&mut conn)
+            .await?;
+        }
+
+        conn.commit().await?;
+        Ok(())
+    }
+
+    /// The fiat price of an asset at a given unix timestamp, with `None` standing for the
+    /// network's native currency
+    /// Only prices recorded within the day before are used, as older ones are too stale
+    pub async fn get_price_at(
+        &self,
+        chain_id: u32,
+        contract: Option<Address>,
+        currency: &str,
+        timestamp: u64,
+    ) -> Result<Option<f64>> {
+        let hour = (timestamp / 3600) as i64;
+
+        let res = sqlx::query(
+            r#" SELECT price
+            FROM price_history
+            WHERE chain_id = ? AND asset = ? AND currency = ? AND hour <= ? AND hour > ?
+            ORDER BY hour DESC
+            LIMIT 1 "#,
+        )
+        .bind(chain_id)
+        .bind(asset(contract))
+        .bind(currency)
+        .bind(hour)
+        .bind(hour - 24)
+        .map(|row| row.get::<f64, _>("price"))
+        .fetch_optional(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// Records the fees of a block, forgetting samples of the network older than `window`
+    /// Blocks already sampled are left as they are
+    pub async fn save_gas_sample(
+        &self,
+        chain_id: u32,
+        sample: &GasSample,
+        window: Duration,
+    ) -> Result<()> {
+        let mut conn = self.tx().await?;
+
+        sqlx::query(
+            r#" INSERT OR IGNORE INTO gas_history (chain_id, block_number, base_fee, priority_fee_low, priority_fee_medium, priority_fee_high, created_at)
+                VALUES (?,?,?,?,?,?,?) "#,
+        )
+        .bind(chain_id)
+        .bind(sample.block_number as i64)
+        .bind(sample.base_fee.to_string())
+        .bind(sample.priority_fee_low.to_string())
+        .bind(sample.priority_fee_medium.to_string())
+        .bind(sample.priority_fee_high.to_string())
+        .bind(sample.timestamp as i64)
+        .execute(&mut conn)
+        .await?;
+
+        sqlx::query(r#"DELETE FROM gas_history WHERE chain_id = ? AND created_at < ?"#)
+            .bind(chain_id)
+            .bind(sample.timestamp.saturating_sub(window.as_secs()) as i64)
+            .execute(&mut conn)
+            .await?;
+
+        conn.commit().await?;
+        Ok(())
+    }
+
+    /// The fees sampled on a network since a unix timestamp, oldest first
+    pub async fn get_gas_history(&self, chain_id: u32, since: u64) -> Result<Vec<GasSample>> {
+        let res: Vec<_> = sqlx::query(
+            r#" SELECT *
+            FROM gas_history
+            WHERE chain_id = ? AND created_at >= ?
+            ORDER BY block_number ASC "#,
+        )
+        .bind(chain_id)
+        .bind(since as i64)
+        .map(|row| GasSample::try_from(&row).unwrap())
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(res)
+    }
+
+    /// The blocklist entries cached from the last successful fetch
+    pub async fn get_blocklist(&self) -> Result<Vec<String>> {
+        let res = sqlx::query(r#"SELECT entry FROM blocklist"#)
+            .map(|row| row.get("entry"))
+            .fetch_all(self.pool())
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Replaces the cached blocklist with freshly fetched entries
+    pub async fn replace_blocklist(&self, entries: &[String]) -> Result<()> {
+        let mut conn = self.tx().await?;
+
+        sqlx::query(r#"DELETE FROM blocklist"#)
+            .execute(&mut conn)
+            .await?;
+
+        for entry in entries {
+            sqlx::query(
+                r#" INSERT OR IGNORE INTO blocklist (entry, updated_at)
+                    VALUES (?, CAST(strftime('%s', 'now') AS INTEGER)) "#,
+            )
+            .bind(entry)
+            .execute(&mut conn)
+            .await?;
+        }
+
+        conn.commit().await?;
+        Ok(())
+    }
+
+    /// The last block of a network that was scanned for incoming transfers
+    pub async fn get_last_known_block(&self, chain_id: u32) -> Result<Option<u64>> {
+        let res = sqlx::query(r#"SELECT last_known_block FROM block_listeners WHERE chain_id = ?"#)
+            .bind(chain_id)
+            .map(|row| row.get::<i64, _>("last_known_block") as u64)
+            .fetch_optional(self.pool())
+            .await?;
+
+        Ok(res)
+    }
+
+    pub async fn set_last_known_block(&self, chain_id: u32, block: u64) -> Result<()> {
+        let mut conn = self.tx().await?;
+
+        let updated =
+            sqlx::query(r#"UPDATE block_listeners SET last_known_block = ? WHERE chain_id = ?"#)
+                .bind(block as i64)
+                .bind(chain_id)
+                .execute(&mut conn)
+                .await?;
+        if updated.rows_affected() == 0 {
+            sqlx::query(
+                r#"INSERT INTO block_listeners (chain_id, last_known_block) VALUES (?, ?)"#,
+            )
+            .bind(chain_id)
+            .bind(block as i64)
+            .execute(&mut conn)
+            .await?;
+        }
+
+        conn.commit().await?;
+        Ok(())
+    }
+
     pub async fn get_tip(&self, chain_id: u32, addr: Address) -> Result<u64> {
         let tip = queries::get_tip(addr, chain_id)
             .fetch_one(self.pool())
@@ -369,6 +1099,13 @@ impl DB {
         Ok(())
     }
 
+    /// Waits for in-progress queries to finish, then closes all connections
+    /// Every clone shares the same pool, so this closes the database for all of them. Closing an
+    /// already closed database does nothing
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
     pub fn pool(&self) -> &sqlx::Pool<sqlx::Sqlite> {
         &self.pool
     }
@@ -380,8 +1117,415 @@ impl DB {
     async fn migrate(&self) -> Result<()> {
         let pool = self.pool.clone();
 
-        sqlx::migrate!("../../migrations/").run(&pool).await?;
+        MIGRATOR.run(&pool).await?;
+
+        Ok(())
+    }
+
+    /// The latest migration applied to this database, or 0 for a fresh one
+    pub async fn schema_version(&self) -> Result<i64> {
+        let migrated: i64 = sqlx::query(
+            r#" SELECT COUNT(*) AS count
+            FROM sqlite_master
+            WHERE type = 'table' AND name = '_sqlx_migrations' "#,
+        )
+        .map(|row| row.get("count"))
+        .fetch_one(self.pool())
+        .await?;
+
+        if migrated == 0 {
+            return Ok(0);
+        }
+
+        let version = sqlx::query(
+            r#" SELECT COALESCE(MAX(version), 0) AS version
+            FROM _sqlx_migrations
+            WHERE success = 1 "#,
+        )
+        .map(|row| row.get("version"))
+        .fetch_one(self.pool())
+        .await?;
+
+        Ok(version)
+    }
+
+    /// Writes a consistent copy of the database to a new file, replacing any previous one there
+    async fn backup(&self, to: &Path) -> Result<()> {
+        tracing::info!("Backing up database to {} before migrating", to.display());
+
+        if to.exists() {
+            std::fs::remove_file(to)?;
+        }
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(to.to_string_lossy())
+            .execute(self.pool())
+            .await?;
 
         Ok(())
     }
 }
+
+/// The schema version this build migrates databases to
+fn latest_schema_version() -> i64 {
+    MIGRATOR.iter().map(|m| m.version).max().unwrap_or_default()
+}
+
+/// Where a database is backed up before being migrated from a given schema version
+/// How an asset is keyed in price tables, with `None` standing for the native currency
+fn asset(contract: Option<Address>) -> String {
+    match contract {
+        Some(contract) => format!("0x{:x}", contract),
+        None => NATIVE_ASSET.to_string(),
+    }
+}
+
+/// Keeping one backup per version means a second failed upgrade doesn't overwrite the only copy
+/// of data from before the first
+fn backup_path(path: &Path, version: i64) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", version));
+    backup.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use iron_types::events::ERC721Transfer;
+
+    use super::*;
+
+    async fn test_db() -> DB {
+        DB::connect_memory().await.unwrap()
+    }
+
+    fn tx(n: u64) -> Tx {
+        Tx {
+            hash: H256::from_low_u64_be(n),
+            from: Address::from_low_u64_be(1),
+            to: Some(Address::from_low_u64_be(2)),
+            value: U256::zero(),
+            data: Default::default(),
+            block_number: n,
+            position: Some(0),
+            status: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_history_removes_old_records_only() {
+        let db = test_db().await;
+        db.save_events(1, vec![tx(1).into(), tx(2).into(), tx(3).into()])
+            .await
+            .unwrap();
+
+        // age the first two transactions by a day
+        sqlx::query(
+            "UPDATE transactions SET created_at = created_at - 86400 WHERE block_number < 3",
+        )
+        .execute(db.pool())
+        .await
+        .unwrap();
+
+        let pruned = db.prune_history(Duration::from_secs(3600)).await.unwrap();
+        assert_eq!(pruned, 2);
+
+        assert!(!db.transaction_exists(1, tx(1).hash).await.unwrap());
+        assert!(!db.transaction_exists(1, tx(2).hash).await.unwrap());
+        assert!(db.transaction_exists(1, tx(3).hash).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn history_round_trips_through_memory_db() {
+        let db = test_db().await;
+        let record = TxRecord {
+            hash: H256::from_low_u64_be(1),
+            chain_id: 1,
+            from: Address::from_low_u64_be(1),
+            to: None,
+            value: U256::from(42),
+            status: TxStatus::Pending,
+            timestamp: 0,
+        };
+
+        db.record_tx(&record).await.unwrap();
+        db.update_tx_status(record.hash, TxStatus::Confirmed)
+            .await
+            .unwrap();
+
+        let history = db.get_history(1, 10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, TxStatus::Confirmed);
+        assert_eq!(history[0].value, record.value);
+        assert!(db.get_history(5, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reorged_txs_go_back_to_pending() {
+        let db = test_db().await;
+        for (n, block) in [(1u64, 10u64), (2, 12)] {
+            let record = TxRecord {
+                hash: H256::from_low_u64_be(n),
+                chain_id: 1,
+                from: Address::from_low_u64_be(1),
+                to: None,
+                value: U256::zero(),
+                status: TxStatus::Pending,
+                timestamp: 0,
+            };
+            db.record_tx(&record).await.unwrap();
+            db.settle_tx(record.hash, TxStatus::Confirmed)
+                .await
+                .unwrap();
+            db.set_tx_block(record.hash, block).await.unwrap();
+        }
+
+        let reverted = db.revert_txs_from(1, 11).await.unwrap();
+        assert_eq!(reverted.len(), 1);
+        assert_eq!(reverted[0].hash, H256::from_low_u64_be(2));
+        assert_eq!(reverted[0].status, TxStatus::Pending);
+
+        let pending = db.get_pending_txs(1).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].hash, H256::from_low_u64_be(2));
+        assert!(db.revert_txs_from(1, 11).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pending_txs_are_settled_once() {
+        let db = test_db().await;
+        let record = TxRecord {
+            hash: H256::from_low_u64_be(2),
+            chain_id: 1,
+            from: Address::from_low_u64_be(1),
+            to: None,
+            value: U256::zero(),
+            status: TxStatus::Pending,
+            timestamp: 0,
+        };
+
+        db.record_tx(&record).await.unwrap();
+        assert_eq!(db.get_pending_txs(1).await.unwrap().len(), 1);
+
+        assert_eq!(db.get_tx_payload(record.hash).await.unwrap(), None);
+        let raw = Bytes::from(vec![0x02, 0xf8]);
+        db.save_tx_payload(record.hash, Some(7.into()), &raw, true)
+            .await
+            .unwrap();
+        assert!(db.is_tx_private(record.hash).await.unwrap());
+        assert_eq!(
+            db.get_tx_payload(record.hash).await.unwrap(),
+            Some((Some(7.into()), raw))
+        );
+
+        assert!(db.settle_tx(record.hash, TxStatus::Failed).await.unwrap());
+        assert!(!db
+            .settle_tx(record.hash, TxStatus::Confirmed)
+            .await
+            .unwrap());
+        assert!(db.get_pending_txs(1).await.unwrap().is_empty());
+        assert!(db.get_all_pending_txs().await.unwrap().is_empty());
+
+        assert_eq!(db.get_last_known_block(1).await.unwrap(), None);
+        db.set_last_known_block(1, 10).await.unwrap();
+        db.set_last_known_block(1, 12).await.unwrap();
+        assert_eq!(db.get_last_known_block(1).await.unwrap(), Some(12));
+    }
+
+    #[tokio::test]
+    async fn watched_tokens_are_scoped_per_network() {
+        let db = test_db().await;
+        let token = Address::from_low_u64_be(7);
+        let metadata = || TokenMetadata {
+            name: "Token".into(),
+            symbol: "TKN".into(),
+            decimals: 18,
+        };
+
+        db.watch_token(1, token, metadata()).await.unwrap();
+        db.watch_token(1, token, metadata()).await.unwrap();
+        assert_eq!(db.get_watched_tokens(1).await.unwrap(), vec![token]);
+        assert!(db.get_watched_tokens(5).await.unwrap().is_empty());
+
+        db.unwatch_token(1, token).await.unwrap();
+        assert!(db.get_watched_tokens(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn prices_are_replaced_per_currency() {
+        let db = test_db().await;
+        let token = Address::from_low_u64_be(7);
+
+        db.save_prices(1, "usd", vec![(None, 1800.0), (Some(token), 1.0)])
+            .await
+            .unwrap();
+        db.save_prices(1, "usd", vec![(None, 1900.0)])
+            .await
+            .unwrap();
+
+        let prices = db.get_prices(1, "usd").await.unwrap();
+        assert_eq!(prices.len(), 2);
+        let native = prices.iter().find(|p| p.contract.is_none()).unwrap();
+        assert_eq!(native.price, 1900.0);
+
+        assert!(db.get_prices(1, "eur").await.unwrap().is_empty());
+
+        // the hour's last price is what past transactions will be valued at
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let at = |timestamp| db.get_price_at(1, None, "usd", timestamp);
+        assert_eq!(at(now).await.unwrap(), Some(1900.0));
+        assert_eq!(at(now + 3 * 3600).await.unwrap(), Some(1900.0));
+        assert_eq!(at(now - 3600).await.unwrap(), None);
+        assert_eq!(at(now + 48 * 3600).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn gas_history_keeps_a_rolling_window() {
+        let db = test_db().await;
+        let sample = |block_number: u64, timestamp| GasSample {
+            block_number,
+            timestamp,
+            base_fee: U256::from(block_number),
+            priority_fee_low: 1.into(),
+            priority_fee_medium: 2.into(),
+            priority_fee_high: 3.into(),
+        };
+        let window = Duration::from_secs(100);
+
+        for (block, timestamp) in [(1, 1000), (2, 1050), (2, 1060), (3, 1150)] {
+            db.save_gas_sample(1, &sample(block, timestamp), window)
+                .await
+                .unwrap();
+        }
+
+        // the first sample fell out of the window, and block 2 was only sampled once
+        let history = db.get_gas_history(1, 0).await.unwrap();
+        assert_eq!(history, vec![sample(2, 1050), sample(3, 1150)]);
+        assert_eq!(db.get_gas_history(1, 1100).await.unwrap().len(), 1);
+        assert!(db.get_gas_history(10, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn tracks_which_addresses_were_sent_to() {
+        let db = test_db().await;
+        let (from, to) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        assert!(!db.has_sent_to(1, from, to).await.unwrap());
+
+        let record = TxRecord {
+            hash: H256::from_low_u64_be(1),
+            chain_id: 1,
+            from,
+            to: Some(to),
+            value: U256::zero(),
+            status: TxStatus::Pending,
+            timestamp: 0,
+        };
+        db.record_tx(&record).await.unwrap();
+
+        assert!(db.has_sent_to(1, from, to).await.unwrap());
+        assert!(!db.has_sent_to(1, to, from).await.unwrap());
+        assert!(!db.has_sent_to(10, from, to).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn nft_transfers_track_ownership() {
+        let db = test_db().await;
+        let owner = Address::from_low_u64_be(1);
+        let transfer = |from, to| ERC721Transfer {
+            from,
+            to,
+            token_id: U256::from(5),
+            contract: Address::from_low_u64_be(9),
+            block_number: 1,
+        };
+
+        db.save_events(1, vec![transfer(Address::zero(), owner).into()])
+            .await
+            .unwrap();
+        let nfts = db.get_nfts(1, owner).await.unwrap();
+        assert_eq!(nfts.len(), 1);
+        assert_eq!(nfts[0].token_id, U256::from(5));
+        assert_eq!(nfts[0].token_uri, None);
+
+        db.set_nft_token_uri(1, nfts[0].contract.into(), U256::from(5), "ipfs://5")
+            .await
+            .unwrap();
+        assert!(db.get_nfts_missing_uri(1).await.unwrap().is_empty());
+
+        db.save_events(1, vec![transfer(owner, Address::zero()).into()])
+            .await
+            .unwrap();
+        assert!(db.get_nfts(1, owner).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn signed_messages_are_searchable() {
+        let db = test_db().await;
+
+        for (n, origin) in [(1, "https://uniswap.org"), (2, "https://opensea.io")] {
+            let message = SignedMessage {
+                timestamp: n,
+                address: Address::from_low_u64_be(1),
+                chain_id: 1,
+                origin: Some(origin.into()),
+                method: "personal_sign".into(),
+                payload_hash: H256::from_low_u64_be(n),
+                signature: format!("0x{:0130x}", n),
+            };
+            db.insert_signed_message(&message).await.unwrap();
+        }
+
+        let all = db
+            .get_signed_messages(None, Default::default())
+            .await
+            .unwrap();
+        assert_eq!(all.total, 2);
+        assert_eq!(all.items[0].payload_hash, H256::from_low_u64_be(2));
+
+        let found = db
+            .get_signed_messages(Some("uniswap"), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(found.total, 1);
+        assert_eq!(found.items[0].timestamp, 1);
+
+        let hash = format!("0x{:x}", H256::from_low_u64_be(2));
+        let found = db
+            .get_signed_messages(Some(&hash), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(found.items[0].origin.as_deref(), Some("https://opensea.io"));
+    }
+
+    #[tokio::test]
+    async fn fresh_databases_are_fully_migrated() {
+        let db = test_db().await;
+
+        assert_eq!(db.schema_version().await.unwrap(), latest_schema_version());
+        assert!(latest_schema_version() >= 19);
+    }
+
+    #[tokio::test]
+    async fn backs_up_before_migrating() {
+        let dir = std::env::temp_dir().join(format!("iron-db-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("db.sqlite3");
+        let _ = std::fs::remove_file(&path);
+
+        let db = DB::connect(&path).await.unwrap();
+        let backup = backup_path(&path, 1);
+        db.backup(&backup).await.unwrap();
+        // replacing an earlier backup works too
+        db.backup(&backup).await.unwrap();
+
+        assert_eq!(
+            backup.file_name().unwrap().to_string_lossy(),
+            "db.sqlite3.v1.bak"
+        );
+        assert!(backup.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -11,10 +11,24 @@ pub fn default_page_size() -> u32 {
     20
 }
 
+/// Upper bound on how many items a single page can load into memory
+/// It's fixed rather than a setting: page sizes come from the frontend, and 100 is five of its
+/// default pages, plenty for any list it shows. Code that needs every row, such as history
+/// exports, queries by range instead of paging
+pub const MAX_PAGE_SIZE: u32 = 100;
+
 impl Pagination {
     pub fn offset(&self) -> u32 {
         self.page_size * self.page
     }
+
+    /// Clamps the page size to `MAX_PAGE_SIZE`
+    pub fn capped(self) -> Self {
+        Self {
+            page_size: self.page_size.min(MAX_PAGE_SIZE),
+            ..self
+        }
+    }
 }
 
 impl Default for Pagination {
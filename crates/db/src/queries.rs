@@ -1,13 +1,15 @@
-use ethers::core::types::{Address, U256};
-use iron_types::{events, TokenMetadata};
+use std::time::Duration;
+
+use ethers::core::types::{Address, H256, U256};
+use iron_types::{events, AuditEntry, SignedMessage, TokenMetadata, TxRecord, TxStatus};
 use sqlx::{sqlite::SqliteRow, Row, Sqlite};
 
 type Query<'a> = sqlx::query::Query<'a, Sqlite, sqlx::sqlite::SqliteArguments<'a>>;
 
 pub(super) fn insert_transaction(tx: &events::Tx, chain_id: u32) -> Query {
     sqlx::query(
-        r#" INSERT INTO transactions (hash, chain_id, from_address, to_address, block_number, position, value, status)
-        VALUES (?,?,?,?,?,?,?,?)
+        r#" INSERT INTO transactions (hash, chain_id, from_address, to_address, block_number, position, value, status, created_at)
+        VALUES (?,?,?,?,?,?,?,?,CAST(strftime('%s', 'now') AS INTEGER))
         ON CONFLICT(hash) DO NOTHING "#,
     )
     .bind(format!("0x{:x}", tx.hash))
@@ -115,7 +117,7 @@ pub(super) fn erc20_update_balance<'a>(
 }
 
 pub(super) fn erc721_transfer<'a>(tx: &events::ERC721Transfer, chain_id: u32) -> Query<'a> {
-    if !tx.to.is_zero() {
+    if tx.to.is_zero() {
         // burning
         sqlx::query(
             r#" DELETE FROM nft_tokens WHERE chain_id = ? AND contract = ? AND token_id = ? "#,
@@ -126,9 +128,8 @@ pub(super) fn erc721_transfer<'a>(tx: &events::ERC721Transfer, chain_id: u32) ->
     } else {
         // minting or transfer
         sqlx::query(
-            r#" INSERT INTO nft_tokens (chain_id, contract, token_id, owner)
-                            VALUES (?,?,?,?)
-                            ON CONFLICT REPLACE"#,
+            r#" INSERT OR REPLACE INTO nft_tokens (chain_id, contract, token_id, owner)
+                            VALUES (?,?,?,?) "#,
         )
         .bind(chain_id)
         .bind(format!("0x{:x}", tx.contract))
@@ -167,3 +168,59 @@ pub(super) fn set_tip<'a>(owner: Address, chain_id: u32, tip: u64) -> Query<'a>
     .bind(chain_id)
     .bind(format!("0x{:x}", tip))
 }
+
+pub(super) fn prune_transactions(older_than: Duration) -> Query<'static> {
+    sqlx::query(
+        r#" DELETE FROM transactions
+        WHERE created_at < CAST(strftime('%s', 'now') AS INTEGER) - ? "#,
+    )
+    .bind(older_than.as_secs() as i64)
+}
+
+pub(super) fn insert_audit_entry(entry: &AuditEntry) -> Query {
+    sqlx::query(
+        r#" INSERT INTO signing_audit_log (created_at, address, chain_id, kind, payload_hash, outcome)
+        VALUES (?,?,?,?,?,?) "#,
+    )
+    .bind(entry.timestamp as i64)
+    .bind(format!("0x{:x}", entry.address))
+    .bind(entry.chain_id)
+    .bind(entry.kind.as_str())
+    .bind(format!("0x{:x}", entry.payload_hash))
+    .bind(entry.outcome.as_str())
+}
+
+pub(super) fn insert_signed_message(message: &SignedMessage) -> Query {
+    sqlx::query(
+        r#" INSERT INTO signed_messages (created_at, address, chain_id, origin, method, payload_hash, signature)
+        VALUES (?,?,?,?,?,?,?) "#,
+    )
+    .bind(message.timestamp as i64)
+    .bind(format!("0x{:x}", message.address))
+    .bind(message.chain_id)
+    .bind(message.origin.as_deref())
+    .bind(message.method.as_str())
+    .bind(format!("0x{:x}", message.payload_hash))
+    .bind(message.signature.as_str())
+}
+
+pub(super) fn insert_tx_record(record: &TxRecord) -> Query {
+    sqlx::query(
+        r#" INSERT INTO tx_history (hash, chain_id, from_address, to_address, value, status, created_at)
+        VALUES (?,?,?,?,?,?,?)
+        ON CONFLICT(hash) DO NOTHING "#,
+    )
+    .bind(format!("0x{:x}", record.hash))
+    .bind(record.chain_id)
+    .bind(format!("0x{:x}", record.from))
+    .bind(record.to.map(|a| format!("0x{:x}", a)))
+    .bind(record.value.to_string())
+    .bind(record.status.as_str())
+    .bind(record.timestamp as i64)
+}
+
+pub(super) fn update_tx_status(hash: H256, status: TxStatus) -> Query<'static> {
+    sqlx::query(r#" UPDATE tx_history SET status = ? WHERE hash = ? "#)
+        .bind(status.as_str())
+        .bind(format!("0x{:x}", hash))
+}
@@ -1,11 +1,18 @@
-use super::{error::Result, global::OPEN_DIALOGS, handle::DialogMsg};
+use iron_types::ui_events::UINotify;
+
+use super::{
+    error::{Error, Result},
+    global::OPEN_DIALOGS,
+    handle::DialogMsg,
+    pending::{self, PendingRequest},
+};
 
 /// Retrieves the payload for a dialog window
 /// Dialogs can call this once ready to retrieve the data they're meant to display
 #[tauri::command]
 pub async fn dialog_get_payload(id: u32) -> Result<serde_json::Value> {
     let dialogs = OPEN_DIALOGS.lock().await;
-    let pending = dialogs.get(&id).unwrap();
+    let pending = dialogs.get(&id).ok_or(Error::UnknownDialog(id))?;
 
     Ok(pending.get_payload().await)
 }
@@ -13,7 +20,7 @@ pub async fn dialog_get_payload(id: u32) -> Result<serde_json::Value> {
 #[tauri::command]
 pub async fn dialog_send(id: u32, payload: serde_json::Value) -> Result<()> {
     let dialogs = OPEN_DIALOGS.lock().await;
-    let dialog = dialogs.get(&id).unwrap();
+    let dialog = dialogs.get(&id).ok_or(Error::UnknownDialog(id))?;
 
     let msg = DialogMsg::Data(payload);
     dialog.incoming(msg).await?;
@@ -36,13 +43,29 @@ pub async fn dialog_finish(
     dialog.close()?;
 
     let mut dialogs = OPEN_DIALOGS.lock().await;
-    let dialog = dialogs.remove(&id).unwrap();
+    let dialog = dialogs.remove(&id).ok_or(Error::UnknownDialog(id))?;
 
     let msg = match result {
         Ok(json) => DialogMsg::Accept(json),
         Err(json) => DialogMsg::Reject(json),
     };
     dialog.incoming(msg).await.unwrap();
+    drop(dialogs);
+
+    iron_broadcast::ui_notify(UINotify::PendingRequestsUpdated).await;
 
     Ok(())
 }
+
+/// Lists all pending dialogs, including which peer requested each one
+#[tauri::command]
+pub async fn dialog_get_pending() -> Vec<PendingRequest> {
+    pending::pending_requests().await
+}
+
+/// Approves or rejects a specific pending dialog by its id
+/// Unlike `dialog_finish`, this can be called from any window, not just the dialog itself
+#[tauri::command]
+pub async fn dialog_resolve(id: u32, result: super::handle::DialogResult) -> Result<()> {
+    pending::resolve(id, result).await
+}
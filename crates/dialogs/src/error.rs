@@ -8,6 +8,9 @@ pub enum Error {
 
     #[error(transparent)]
     Send(#[from] mpsc::error::SendError<super::handle::DialogMsg>),
+
+    #[error("unknown dialog: {0}")]
+    UnknownDialog(u32),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::atomic::AtomicU32};
 
 use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
@@ -7,3 +7,6 @@ type PendingDialogMap = HashMap<u32, super::handle::Dialog>;
 
 /// global map of pending dialogs
 pub(super) static OPEN_DIALOGS: Lazy<Mutex<PendingDialogMap>> = Lazy::new(Default::default);
+
+/// ids for new dialogs, so identical requests still queue up as separate dialogs
+pub(super) static NEXT_ID: AtomicU32 = AtomicU32::new(1);
@@ -1,16 +1,16 @@
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
-    sync::Arc,
-};
+use std::sync::{atomic::Ordering, Arc};
 
 use iron_types::{
-    ui_events::{DialogClose, DialogOpen, DialogSend},
-    Json,
+    ui_events::{DialogClose, DialogOpen, DialogSend, UINotify},
+    Json, RequestSource,
 };
 use tokio::sync::{mpsc, RwLock, RwLockReadGuard};
 
-use super::{global::OPEN_DIALOGS, presets, Result};
+use super::{
+    global::{NEXT_ID, OPEN_DIALOGS},
+    pending::PendingRequest,
+    presets, Result,
+};
 
 #[derive(Debug)]
 pub enum DialogMsg {
@@ -28,7 +28,13 @@ impl Dialog {
     /// Creates a new dialog handle
     /// The window itself is opened until `open` is called
     pub fn new(preset: &str, payload: Json) -> Self {
-        Self(Arc::new(RwLock::new(Inner::new(preset, payload))))
+        Self::with_source(preset, payload, None)
+    }
+
+    /// Creates a new dialog handle on behalf of a peer
+    /// The source is exposed to the UI so the user can tell which dapp is asking
+    pub fn with_source(preset: &str, payload: Json, source: Option<RequestSource>) -> Self {
+        Self(Arc::new(RwLock::new(Inner::new(preset, payload, source))))
     }
 
     /// Opens a dialog
@@ -40,12 +46,15 @@ impl Dialog {
         let clone = self.clone();
         let inner = self.read().await;
         OPEN_DIALOGS.lock().await.insert(inner.id, clone);
+        iron_broadcast::ui_notify(UINotify::PendingRequestsUpdated).await;
         inner.open().await
     }
 
     /// Closes the dialog window
     pub async fn close(self) -> Result<()> {
-        self.read().await.close().await
+        self.read().await.close().await?;
+        iron_broadcast::ui_notify(UINotify::PendingRequestsUpdated).await;
+        Ok(())
     }
 
     /// Gets a copy of the payload intended for the dialog
@@ -53,6 +62,18 @@ impl Dialog {
         self.read().await.payload.clone()
     }
 
+    /// Describes this dialog as a pending request
+    pub async fn pending_request(&self) -> PendingRequest {
+        let inner = self.read().await;
+
+        PendingRequest {
+            id: inner.id,
+            preset: inner.preset.clone(),
+            source: inner.source.clone(),
+            payload: inner.payload.clone(),
+        }
+    }
+
     /// Data received from the dialog
     pub async fn incoming(&self, result: DialogMsg) -> Result<()> {
         self.read().await.inbound_snd.send(result)?;
@@ -66,7 +87,19 @@ impl Dialog {
 
     /// Awaits data received from the dialog
     pub async fn recv(&self) -> Option<DialogMsg> {
-        self.read().await.recv().await
+        let inner = self.read().await;
+        let msg = inner.recv().await;
+
+        // only the outcome is logged, since payloads can hold passwords
+        let outcome = match msg {
+            Some(DialogMsg::Data(_)) => "data",
+            Some(DialogMsg::Accept(_)) => "accepted",
+            Some(DialogMsg::Reject(_)) => "rejected",
+            None => "dropped",
+        };
+        tracing::info!(dialog = %inner.preset, id = inner.id, outcome, "dialog answered");
+
+        msg
     }
 
     async fn read(&self) -> RwLockReadGuard<'_, Inner> {
@@ -91,6 +124,9 @@ pub struct Inner {
     /// payload to first send to dialog
     payload: Json,
 
+    /// the peer that requested this dialog, if any
+    source: Option<RequestSource>,
+
     /// inbound msgs from dialog
     inbound_snd: mpsc::UnboundedSender<DialogMsg>,
 
@@ -101,11 +137,8 @@ pub struct Inner {
 }
 
 impl Inner {
-    fn new(preset: &str, payload: Json) -> Self {
-        // TODO: make this random as well, or just increment a counter. what if we open the same payload twice?
-        let mut s = DefaultHasher::new();
-        payload.to_string().hash(&mut s);
-        let id: u32 = s.finish() as u32;
+    fn new(preset: &str, payload: Json, source: Option<RequestSource>) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
 
         let (snd, rcv) = mpsc::unbounded_channel();
 
@@ -113,6 +146,7 @@ impl Inner {
             id,
             preset: preset.to_string(),
             payload,
+            source,
             inbound_snd: snd,
             inbound_rcv: RwLock::new(rcv),
         }
@@ -121,6 +155,7 @@ impl Inner {
     async fn open(&self) -> Result<()> {
         let preset = presets::PRESETS.get(&self.preset).unwrap();
         let url = format!("/dialog/{}/{}", self.preset, self.id);
+        tracing::info!(dialog = %self.preset, id = self.id, "dialog opened");
         let title = format!("Iron Dialog - {}", preset.title);
 
         iron_broadcast::dialog_open(DialogOpen {
@@ -2,7 +2,9 @@ pub mod commands;
 mod error;
 mod global;
 mod handle;
+mod pending;
 mod presets;
 
 pub use error::{Error, Result};
 pub use handle::{Dialog, DialogMsg};
+pub use pending::{pending_requests, resolve, PendingRequest};
@@ -0,0 +1,48 @@
+use iron_types::{Json, RequestSource};
+use serde::Serialize;
+
+use super::{
+    global::OPEN_DIALOGS,
+    handle::{DialogMsg, DialogResult},
+    Error, Result,
+};
+
+/// A dialog currently awaiting a response from the user
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingRequest {
+    pub id: u32,
+    pub preset: String,
+    pub source: Option<RequestSource>,
+    pub payload: Json,
+}
+
+/// Lists all currently open dialogs, along with the peer that requested each of them
+pub async fn pending_requests() -> Vec<PendingRequest> {
+    let dialogs = OPEN_DIALOGS.lock().await;
+
+    let mut res = Vec::with_capacity(dialogs.len());
+    for dialog in dialogs.values() {
+        res.push(dialog.pending_request().await);
+    }
+
+    res
+}
+
+/// Approves or rejects a specific pending request, closing its window
+pub async fn resolve(id: u32, result: DialogResult) -> Result<()> {
+    let dialog = OPEN_DIALOGS
+        .lock()
+        .await
+        .remove(&id)
+        .ok_or(Error::UnknownDialog(id))?;
+
+    let msg = match result {
+        Ok(json) => DialogMsg::Accept(json),
+        Err(json) => DialogMsg::Reject(json),
+    };
+    dialog.incoming(msg).await?;
+    dialog.close().await?;
+
+    Ok(())
+}
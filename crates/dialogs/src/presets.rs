@@ -29,6 +29,24 @@ pub(super) static PRESETS: Lazy<HashMap<String, Preset>> = Lazy::new(|| {
         },
     );
 
+    presets.insert(
+        "chain-change".into(),
+        Preset {
+            title: "Chain Change".into(),
+            w: 400.0,
+            h: 260.0,
+        },
+    );
+
+    presets.insert(
+        "watch-asset".into(),
+        Preset {
+            title: "Watch Token".into(),
+            w: 400.0,
+            h: 240.0,
+        },
+    );
+
     presets.insert(
         "wallet-unlock".into(),
         Preset {
@@ -38,5 +56,14 @@ pub(super) static PRESETS: Lazy<HashMap<String, Preset>> = Lazy::new(|| {
         },
     );
 
+    presets.insert(
+        "walletconnect-proposal".into(),
+        Preset {
+            title: "WalletConnect".into(),
+            w: 400.0,
+            h: 260.0,
+        },
+    );
+
     presets
 });
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use ethers::{providers::Provider, types::Address};
+use iron_abis::IAggregatorV3;
+use iron_networks::{Failover, Networks};
+use iron_types::GlobalState;
+
+use super::{feed::Feed, prices::db, Error, Feeds, Result};
+
+/// Reads prices of a network's native currency and of the given tokens from the network's own
+/// Chainlink feeds
+/// Tokens are matched to feeds by symbol, so only those with a feed quoted in `currency` are
+/// priced
+pub(crate) async fn fetch(
+    chain_id: u32,
+    currency: &str,
+    tokens: &[Address],
+) -> Result<Vec<(Option<Address>, f64)>> {
+    let network = Networks::read()
+        .await
+        .get_network(chain_id)
+        .ok_or(Error::UnrecognizedChain(chain_id))?;
+    let client = Arc::new(network.get_provider()?);
+    let currency = currency.to_uppercase();
+
+    let mut assets = vec![(None, network.currency.clone())];
+    for token in tokens {
+        if let Ok(metadata) = db()?.get_erc20_metadata(*token, chain_id).await {
+            assets.push((Some(*token), metadata.symbol));
+        }
+    }
+
+    let feeds = Feeds::read().await;
+    let mut prices = vec![];
+
+    for (contract, symbol) in assets {
+        let Some(Feed::Chainlink(feed)) = feeds
+            .get(chain_id, &currency, &symbol.to_uppercase())
+            .and_then(|f| f.first())
+        else {
+            continue;
+        };
+
+        match read_feed(*feed, client.clone()).await {
+            Ok(Some(price)) => prices.push((contract, price)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read the {} feed: {}", symbol, e),
+        }
+    }
+
+    Ok(prices)
+}
+
+/// The latest answer of an aggregator, scaled down by its decimals
+/// Non-positive answers are discarded
+async fn read_feed(feed: Address, client: Arc<Provider<Failover>>) -> Result<Option<f64>> {
+    let aggregator = IAggregatorV3::new(feed, client);
+
+    let (_, answer, _, _, _) = aggregator.latest_round_data().call().await?;
+    let decimals = aggregator.decimals().call().await?;
+
+    if answer.is_negative() || answer.is_zero() {
+        return Ok(None);
+    }
+
+    Ok(answer
+        .to_string()
+        .parse::<f64>()
+        .ok()
+        .map(|answer| answer / 10f64.powi(decimals as i32)))
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, str::FromStr};
+
+use ethers::types::Address;
+
+use super::Result;
+
+const API_URL: &str = "https://api.coingecko.com/api/v3";
+
+/// CoinGecko's response to both `/simple/price` and `/simple/token_price`, keyed by coin ID or
+/// contract, then by currency
+type SimplePrices = HashMap<String, HashMap<String, f64>>;
+
+/// CoinGecko's IDs for a network's native currency and for the platform its tokens are listed
+/// under
+/// Testnets are left out, since their currency has no market value
+fn ids(chain_id: u32) -> Option<(&'static str, &'static str)> {
+    match chain_id {
+        1 => Some(("ethereum", "ethereum")),
+        10 => Some(("ethereum", "optimistic-ethereum")),
+        56 => Some(("binancecoin", "binance-smart-chain")),
+        137 => Some(("matic-network", "polygon-pos")),
+        8453 => Some(("ethereum", "base")),
+        42161 => Some(("ethereum", "arbitrum-one")),
+        _ => None,
+    }
+}
+
+/// Fetches prices of a network's native currency and of the given tokens
+/// `None` stands for the native currency
+pub(crate) async fn fetch(
+    chain_id: u32,
+    currency: &str,
+    tokens: &[Address],
+) -> Result<Vec<(Option<Address>, f64)>> {
+    let Some((coin, platform)) = ids(chain_id) else {
+        return Ok(vec![]);
+    };
+
+    let client = reqwest::Client::new();
+    let currency = currency.to_lowercase();
+    let mut prices = vec![];
+
+    let native: SimplePrices = client
+        .get(format!("{}/simple/price", API_URL))
+        .query(&[("ids", coin), ("vs_currencies", &currency)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if let Some(price) = native.get(coin).and_then(|p| p.get(&currency)) {
+        prices.push((None, *price));
+    }
+
+    if tokens.is_empty() {
+        return Ok(prices);
+    }
+
+    let contracts = tokens
+        .iter()
+        .map(|t| format!("0x{:x}", t))
+        .collect::<Vec<_>>()
+        .join(",");
+    let tokens: SimplePrices = client
+        .get(format!("{}/simple/token_price/{}", API_URL, platform))
+        .query(&[
+            ("contract_addresses", &contracts),
+            ("vs_currencies", &currency),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    prices.extend(parse_token_prices(tokens, &currency));
+
+    Ok(prices)
+}
+
+fn parse_token_prices(prices: SimplePrices, currency: &str) -> Vec<(Option<Address>, f64)> {
+    prices
+        .into_iter()
+        .filter_map(|(contract, p)| {
+            Some((Some(Address::from_str(&contract).ok()?), *p.get(currency)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_token_prices() {
+        let prices: SimplePrices = serde_json::from_str(
+            r#"{
+                "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48": { "usd": 0.999 },
+                "0x6b175474e89094c44da98b954eedeac495271d0f": { "eur": 0.92 }
+            }"#,
+        )
+        .unwrap();
+
+        let parsed = parse_token_prices(prices, "usd");
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(
+            parsed[0].0,
+            Some(Address::from_str("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").unwrap())
+        );
+        assert_eq!(parsed[0].1, 0.999);
+    }
+}
@@ -0,0 +1,7 @@
+use super::{Prices, Result};
+
+/// Fiat prices of a network's native currency and watched tokens, in the configured currency
+#[tauri::command]
+pub async fn prices_get(chain_id: u32) -> Result<Prices> {
+    super::get_prices(chain_id).await
+}
@@ -0,0 +1,34 @@
+use ethers::prelude::*;
+use iron_networks::Failover;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{0} is not initialized")]
+    NotInitialized(&'static str),
+
+    #[error("unrecognized chain ID: {0}")]
+    UnrecognizedChain(u32),
+
+    #[error("error fetching prices: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
+
+    #[error(transparent)]
+    DB(#[from] iron_db::Error),
+
+    #[error(transparent)]
+    Contract(#[from] ContractError<Provider<Failover>>),
+}
+
+impl serde::Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -36,10 +36,10 @@ static FEEDS: Lazy<RwLock<Feeds>> = Lazy::new(|| {
 #[async_trait]
 impl GlobalState for Feeds {
     async fn read<'a>() -> RwLockReadGuard<'a, Self> {
-        Lazy::get(&FEEDS).unwrap().read().await
+        FEEDS.read().await
     }
 
     async fn write<'a>() -> RwLockWriteGuard<'a, Self> {
-        Lazy::get(&FEEDS).unwrap().write().await
+        FEEDS.write().await
     }
 }
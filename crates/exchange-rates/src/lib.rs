@@ -1,9 +1,16 @@
+mod chainlink;
+mod coingecko;
+pub mod commands;
+mod error;
 mod feed;
 mod init;
+mod prices;
 
 use std::collections::HashMap;
 
+pub use error::{Error, Result};
 use feed::Feed;
+pub use prices::{get_prices, init, Prices};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -11,3 +18,11 @@ pub struct Feeds {
     // Tuple structure: (network, currency, token symbol)
     feeds: HashMap<(u64, String, String), Vec<Feed>>,
 }
+
+impl Feeds {
+    /// The feeds pricing a token, with currencies and symbols in uppercase, e.g. `USD` and `ETH`
+    pub(crate) fn get(&self, chain_id: u32, currency: &str, symbol: &str) -> Option<&Vec<Feed>> {
+        self.feeds
+            .get(&(chain_id as u64, currency.to_string(), symbol.to_string()))
+    }
+}
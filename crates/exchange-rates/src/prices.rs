@@ -0,0 +1,110 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use iron_db::DB;
+use iron_networks::Networks;
+use iron_settings::{PriceSource, Settings};
+use iron_types::{GlobalState, TokenPrice, UINotify};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tokio::time::{interval, Duration};
+
+use super::{chainlink, coingecko, Error, Result};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+/// How long a fetched price is considered fresh, and how often prices of the current network
+/// are refreshed
+const TTL: Duration = Duration::from_secs(60);
+
+/// Fiat prices of a network's native currency and watched tokens
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prices {
+    pub currency: String,
+    pub native: Option<TokenPrice>,
+    pub tokens: Vec<TokenPrice>,
+}
+
+pub fn init(db: DB) {
+    DB.set(db).unwrap();
+    tokio::spawn(async { refresher().await });
+}
+
+pub(crate) fn db() -> Result<&'static DB> {
+    DB.get().ok_or(Error::NotInitialized("prices"))
+}
+
+/// Prices for a network, fetched anew if the cached ones are older than the TTL
+/// Tokens without a known price are left out
+pub async fn get_prices(chain_id: u32) -> Result<Prices> {
+    let currency = Settings::read().await.inner.fiat_currency.clone();
+    let mut cached = db()?.get_prices(chain_id, &currency).await?;
+
+    if is_stale(&cached) {
+        refresh(chain_id).await?;
+        cached = db()?.get_prices(chain_id, &currency).await?;
+    }
+
+    let (native, tokens): (Vec<_>, Vec<_>) = cached.into_iter().partition(|p| p.contract.is_none());
+
+    Ok(Prices {
+        currency,
+        native: native.into_iter().next(),
+        tokens,
+    })
+}
+
+fn is_stale(prices: &[TokenPrice]) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    prices
+        .iter()
+        .map(|p| p.updated_at)
+        .min()
+        .is_none_or(|oldest| oldest + TTL.as_secs() <= now)
+}
+
+/// Fetches prices for a network's native currency and watched tokens from the configured source
+async fn refresh(chain_id: u32) -> Result<()> {
+    let db = db()?;
+    let tokens = db.get_watched_tokens(chain_id).await?;
+    let (source, currency) = {
+        let settings = Settings::read().await;
+        (
+            settings.inner.price_source,
+            settings.inner.fiat_currency.clone(),
+        )
+    };
+
+    let prices = match source {
+        PriceSource::Coingecko => coingecko::fetch(chain_id, &currency, &tokens).await?,
+        PriceSource::Chainlink => chainlink::fetch(chain_id, &currency, &tokens).await?,
+    };
+
+    if !prices.is_empty() {
+        db.save_prices(chain_id, &currency, prices).await?;
+    }
+
+    Ok(())
+}
+
+/// Keeps prices of the current network fresh, notifying the UI after every refresh
+async fn refresher() {
+    let mut ticker = interval(TTL);
+
+    loop {
+        ticker.tick().await;
+
+        let Ok(chain_id) = Networks::read().await.get_current().map(|n| n.chain_id) else {
+            continue;
+        };
+
+        match refresh(chain_id).await {
+            Ok(_) => iron_broadcast::ui_notify(UINotify::PricesUpdated).await,
+            Err(e) => tracing::warn!("Failed to refresh prices: {}", e),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::{fs::File, io::BufReader, path::PathBuf, str::FromStr};
+
+use ethers::types::Address;
+use serde::Serialize;
+
+use super::{
+    error::{Error, Result},
+    watcher::BroadcastMatch,
+};
+
+/// A contract created by a forge script, as recorded in its broadcast file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deployment {
+    pub chain_id: u32,
+    pub address: Address,
+    pub project: String,
+    pub name: String,
+
+    /// The broadcast file this deployment was read from
+    pub path: PathBuf,
+}
+
+/// Reads every contract creation out of a broadcast file
+pub(super) fn deployments_from_match(m: &BroadcastMatch) -> Result<Vec<Deployment>> {
+    if !m.full_path.exists() {
+        return Err(Error::FileNotFound(m.full_path.clone()));
+    }
+
+    let file = File::open(&m.full_path).map_err(|_| Error::FileNotFound(m.full_path.clone()))?;
+    let json: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+
+    let transactions = json["transactions"]
+        .as_array()
+        .ok_or_else(|| Error::NotABroadcast(m.full_path.clone()))?;
+
+    Ok(transactions
+        .iter()
+        .filter(|tx| matches!(tx["transactionType"].as_str(), Some("CREATE" | "CREATE2")))
+        .filter_map(|tx| {
+            Some(Deployment {
+                chain_id: m.chain_id,
+                address: Address::from_str(tx["contractAddress"].as_str()?).ok()?,
+                project: m.project.clone(),
+                name: tx["contractName"].as_str()?.to_string(),
+                path: m.full_path.clone(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn reads_contract_creations() {
+        let dir = std::env::temp_dir().join("iron-forge-broadcast-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let full_path = dir.join("run-latest.json");
+
+        let mut file = File::create(&full_path).unwrap();
+        write!(
+            file,
+            r#"{{ "transactions": [
+                {{ "transactionType": "CREATE", "contractName": "Counter",
+                   "contractAddress": "0x5FbDB2315678afecb367f032d93F642f64180aa3" }},
+                {{ "transactionType": "CALL", "contractName": "Counter",
+                   "contractAddress": "0x5FbDB2315678afecb367f032d93F642f64180aa3" }}
+            ] }}"#
+        )
+        .unwrap();
+
+        let m = BroadcastMatch {
+            full_path,
+            project: "counter".into(),
+            chain_id: 31337,
+        };
+        let deployments = deployments_from_match(&m).unwrap();
+
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].name, "Counter");
+        assert_eq!(
+            deployments[0].address,
+            Address::from_str("0x5FbDB2315678afecb367f032d93F642f64180aa3").unwrap()
+        );
+    }
+}
@@ -2,18 +2,23 @@ use ethers::providers::Middleware;
 use iron_networks::Networks;
 use iron_types::{ChecksummedAddress, GlobalState};
 
-use super::{abi::Abi, FORGE};
+use super::{abi::Abi, Deployment, FORGE};
 
 /// Gets the ABI, if known, for a given address and chain_id
+/// Contracts deployed by forge scripts are matched by name, and anything else by bytecode
 #[tauri::command]
 pub async fn foundry_get_abi(
     address: ChecksummedAddress,
     chain_id: u32,
 ) -> Result<Option<Abi>, String> {
+    if let Some(abi) = super::get_abi_for_address(chain_id, address.0).await {
+        return Ok(Some(abi));
+    }
+
     let code = {
         let networks = Networks::read().await;
         let network = networks.get_network(chain_id).ok_or("invalid chain_id")?;
-        let provider = network.get_provider();
+        let provider = network.get_provider().map_err(|_| "invalid rpc url")?;
         provider
             .get_code(address.0, None)
             .await
@@ -24,6 +29,11 @@ pub async fn foundry_get_abi(
         return Ok(None);
     }
 
-    let foundry = FORGE.read().await;
-    Ok(foundry.get_abi_for(code))
+    Ok(super::get_abi_for_code(code).await)
+}
+
+/// Contracts deployed to a chain by forge scripts
+#[tauri::command]
+pub async fn foundry_get_deployments(chain_id: u32) -> Vec<Deployment> {
+    FORGE.read().await.get_deployments(chain_id)
 }
@@ -11,6 +11,9 @@ pub enum Error {
     #[error("file does not have the expected schema: {0}")]
     NotAnABI(PathBuf),
 
+    #[error("file is not a forge broadcast: {0}")]
+    NotABroadcast(PathBuf),
+
     #[error("file has an empty ABI. is it a library?")]
     EmptyABI(PathBuf),
 }
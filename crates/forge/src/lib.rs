@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 mod abi;
+mod broadcast;
 pub mod commands;
 pub mod error;
 mod init;
@@ -8,25 +9,42 @@ mod watcher;
 
 use std::{collections::BTreeMap, path::PathBuf};
 
+pub use abi::Abi;
+pub use broadcast::Deployment;
 pub use error::{Error, Result};
-use ethers::types::Bytes;
+use ethers::types::{Address, Bytes};
 pub use init::init;
+use iron_settings::Contacts;
+use iron_types::GlobalState;
 use once_cell::sync::Lazy;
 use tokio::{
     spawn,
     sync::{mpsc, RwLock},
 };
 
-use self::watcher::Match;
+use self::watcher::{Artifact, BroadcastMatch};
 
 #[derive(Default)]
 pub struct Forge {
     abis_by_path: BTreeMap<PathBuf, abi::Abi>,
+
+    /// Contracts deployed by forge scripts, by chain ID and address
+    deployments: BTreeMap<(u32, Address), Deployment>,
 }
 
 static FORGE: Lazy<RwLock<Forge>> = Lazy::new(Default::default);
 static FUZZ_DIFF_THRESHOLD: f64 = 0.2;
 
+/// The ABI of a contract deployed by a forge script, if it's known
+pub async fn get_abi_for_address(chain_id: u32, address: Address) -> Option<Abi> {
+    FORGE.read().await.get_abi_for_deployment(chain_id, address)
+}
+
+/// The ABI of a compiled contract whose bytecode closely matches the given one, if any
+pub async fn get_abi_for_code(code: Bytes) -> Option<Abi> {
+    FORGE.read().await.get_abi_for(code)
+}
+
 impl Forge {
     fn get_abi_for(&self, code: Bytes) -> Option<abi::Abi> {
         self.abis_by_path
@@ -35,6 +53,30 @@ impl Forge {
             .cloned()
     }
 
+    /// Matches a deployment to its ABI by contract name, preferring the deploying project's own
+    /// artifacts
+    fn get_abi_for_deployment(&self, chain_id: u32, address: Address) -> Option<abi::Abi> {
+        let deployment = self.deployments.get(&(chain_id, address))?;
+        let candidates = || {
+            self.abis_by_path
+                .values()
+                .filter(|abi| abi.name == deployment.name)
+        };
+
+        candidates()
+            .find(|abi| abi.project == deployment.project)
+            .or_else(|| candidates().next())
+            .cloned()
+    }
+
+    fn get_deployments(&self, chain_id: u32) -> Vec<Deployment> {
+        self.deployments
+            .values()
+            .filter(|d| d.chain_id == chain_id)
+            .cloned()
+            .collect()
+    }
+
     /// starts the ABI watcher service
     async fn watch(path: String) -> Result<()> {
         let (snd, rcv) = mpsc::unbounded_channel();
@@ -51,20 +93,56 @@ impl Forge {
         Ok(())
     }
 
-    /// Handlers ABI file events
-    async fn handle_events(mut rcv: mpsc::UnboundedReceiver<Match>) -> Result<()> {
-        while let Some(m) = rcv.recv().await {
+    /// Handlers ABI and broadcast file events
+    async fn handle_events(mut rcv: mpsc::UnboundedReceiver<Artifact>) -> Result<()> {
+        while let Some(artifact) = rcv.recv().await {
             let mut foundry = FORGE.write().await;
-            if let Ok(abi) = abi::Abi::try_from_match(m.clone()) {
-                foundry.insert_known_abi(abi);
-            } else {
-                foundry.remove_known_abi(m.full_path);
+            match artifact {
+                Artifact::Abi(m) => {
+                    if let Ok(abi) = abi::Abi::try_from_match(m.clone()) {
+                        foundry.insert_known_abi(abi);
+                    } else {
+                        foundry.remove_known_abi(m.full_path);
+                    }
+                }
+                Artifact::Broadcast(m) => {
+                    foundry.reindex_broadcast(m);
+
+                    // deployed contracts show up in the address book by name
+                    let deployments = foundry
+                        .deployments
+                        .values()
+                        .map(|d| (d.chain_id, d.address.into(), d.name.clone()))
+                        .collect();
+                    drop(foundry);
+                    if let Err(e) = Contacts::write().await.set_deployments(deployments) {
+                        tracing::warn!("Failed to save deployment contacts: {}", e);
+                    }
+                }
             }
         }
 
         Ok(())
     }
 
+    // replaces the deployments previously read from a broadcast file
+    // a file that's gone or unreadable just drops them
+    fn reindex_broadcast(&mut self, m: BroadcastMatch) {
+        self.deployments.retain(|_, d| d.path != m.full_path);
+
+        if let Ok(deployments) = broadcast::deployments_from_match(&m) {
+            for deployment in deployments {
+                tracing::trace!(
+                    "insert deployment: {} at {:?}",
+                    deployment.name,
+                    deployment.address
+                );
+                self.deployments
+                    .insert((deployment.chain_id, deployment.address), deployment);
+            }
+        }
+    }
+
     // indexes a new known ABI
     fn insert_known_abi(&mut self, abi: abi::Abi) {
         tracing::trace!("insert ABI: {:?}", abi.path);
@@ -18,10 +18,25 @@ pub(super) struct Match {
     pub(super) name: String,
 }
 
-/// Creates an async watch over a directory, looking for relevant ABI files to index
+/// A broadcast match points to the latest run of a deployment script on a given chain
+#[derive(Debug, Clone)]
+pub(super) struct BroadcastMatch {
+    pub(super) full_path: PathBuf,
+    pub(super) project: String,
+    pub(super) chain_id: u32,
+}
+
+/// Any foundry artifact worth indexing
+#[derive(Debug, Clone)]
+pub(super) enum Artifact {
+    Abi(Match),
+    Broadcast(BroadcastMatch),
+}
+
+/// Creates an async watch over a directory, looking for relevant ABI and broadcast files to index
 pub(super) async fn async_watch<P: AsRef<Path>>(
     path: P,
-    snd: mpsc::UnboundedSender<Match>,
+    snd: mpsc::UnboundedSender<Artifact>,
 ) -> notify::Result<()> {
     let (mut watcher, mut rx) = async_watcher()?;
 
@@ -59,21 +74,27 @@ fn async_watcher() -> notify::Result<(RecommendedWatcher, Receiver<notify::Resul
     Ok((watcher, rx))
 }
 
-/// Runs a one-off glob query looking for ABIs to index
+/// Runs a one-off glob query looking for ABIs and broadcasts to index
 /// Is meant to complement `async_watch` by running an initial sweep on boot
+/// ABIs are sent first, so that deployments can be matched to them right away
 pub(super) async fn scan_glob<P: AsRef<Path>>(
     path: P,
-    snd: mpsc::UnboundedSender<Match>,
+    snd: mpsc::UnboundedSender<Artifact>,
 ) -> notify::Result<()> {
-    let query =
-        format!("{}/**/*.sol/**/*.json", path.as_ref().to_str().unwrap()).replace("//", "/");
-
-    for entry in glob(&query)
-        .unwrap()
-        .flatten()
-        .flat_map(|path| path.try_into())
-    {
-        snd.send(entry).unwrap();
+    let root = path.as_ref().to_str().unwrap();
+    let queries = [
+        format!("{}/**/*.sol/**/*.json", root).replace("//", "/"),
+        format!("{}/**/broadcast/**/run-latest.json", root).replace("//", "/"),
+    ];
+
+    for query in queries.iter() {
+        for entry in glob(query)
+            .unwrap()
+            .flatten()
+            .flat_map(|path| path.try_into())
+        {
+            snd.send(entry).unwrap();
+        }
     }
 
     // TODO: this should glob all files at the start
@@ -98,6 +119,40 @@ static REGEX: Lazy<Regex> = Lazy::new(|| {
     .unwrap()
 });
 
+/// A regex that matches paths in the form
+/// `.../{project_name}/broadcast/{script}/{chain_id}/run-latest.json`
+#[cfg(not(target_os = "windows"))]
+static BROADCAST_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?x)
+        /
+        (?P<project>[^/]+) # project name
+        /broadcast/
+        [^/]+ # script file
+        /
+        (?P<chain_id>\d+) # chain id
+        /run-latest.json
+        $"#,
+    )
+    .unwrap()
+});
+
+#[cfg(target_os = "windows")]
+static BROADCAST_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?x)
+        \\
+        (?P<project>[^\\]+) # project name
+        \\broadcast\\
+        [^\\]+ # script file
+        \\
+        (?P<chain_id>\d+) # chain id
+        \\run-latest.json
+        $"#,
+    )
+    .unwrap()
+});
+
 #[cfg(target_os = "windows")]
 static REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
@@ -134,13 +189,42 @@ impl TryFrom<PathBuf> for Match {
     }
 }
 
-impl TryFrom<notify::Event> for Match {
+impl TryFrom<PathBuf> for BroadcastMatch {
+    type Error = ();
+
+    fn try_from(path: PathBuf) -> std::result::Result<Self, Self::Error> {
+        let path_str = path.clone();
+        let path_str = path_str.to_str().ok_or(())?;
+
+        let caps = BROADCAST_REGEX.captures(path_str).ok_or(())?;
+        Ok(Self {
+            project: caps["project"].to_string(),
+            chain_id: caps["chain_id"].parse().map_err(|_| ())?,
+            full_path: path,
+        })
+    }
+}
+
+impl TryFrom<PathBuf> for Artifact {
+    type Error = ();
+
+    fn try_from(path: PathBuf) -> std::result::Result<Self, Self::Error> {
+        // broadcasts are json files as well, but never live under `out/`
+        match BroadcastMatch::try_from(path.clone()) {
+            Ok(m) => Ok(Self::Broadcast(m)),
+            Err(_) => Match::try_from(path).map(Self::Abi),
+        }
+    }
+}
+
+impl TryFrom<notify::Event> for Artifact {
     type Error = ();
 
     fn try_from(event: notify::Event) -> Result<Self, Self::Error> {
         use EventKind::*;
         match event.kind {
-            Modify(_) | Remove(_) => event.paths[0].clone().try_into(),
+            // forge writes a new `run-latest.json` on every script run
+            Create(_) | Modify(_) | Remove(_) => event.paths[0].clone().try_into(),
             _ => Err(()),
         }
     }
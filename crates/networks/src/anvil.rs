@@ -0,0 +1,217 @@
+use iron_types::GlobalState;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, Command},
+    sync::Mutex,
+    time::{timeout, Duration},
+};
+
+use super::{
+    network::{default_rpc_backoff_ms, default_rpc_retries, default_rpc_timeout_secs},
+    Error, Network, Networks, Result,
+};
+
+/// How long a freshly spawned anvil gets to start listening
+/// Forks can take a while, since anvil fetches the fork block before binding its port
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What anvil prints to stdout once it's accepting connections
+const READY_LINE: &str = "Listening on";
+
+/// The anvil process spawned by the app, if any
+static ANVIL: Lazy<Mutex<Option<Anvil>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnvilOpts {
+    /// Defaults to 8545
+    pub port: Option<u16>,
+
+    /// Forks from the current network's RPC when set
+    #[serde(default)]
+    pub fork: bool,
+
+    /// Block to fork from. Defaults to the latest one
+    pub fork_block: Option<u64>,
+
+    /// Defaults to 31337, so that a fork doesn't clash with the network it was forked from
+    pub chain_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnvilStatus {
+    pub network: String,
+    pub chain_id: u32,
+    pub port: u16,
+    pub fork_url: Option<String>,
+    pub fork_block: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Anvil {
+    child: Child,
+    status: AnvilStatus,
+
+    /// Whether the network was added by us, as opposed to an existing one that happened to
+    /// point at the same URL
+    registered: bool,
+}
+
+/// The anvil process spawned by the app, if it's running
+pub async fn anvil_status() -> Option<AnvilStatus> {
+    ANVIL.lock().await.as_ref().map(|a| a.status.clone())
+}
+
+/// Spawns a local anvil process and registers it as a network
+/// If a network with the same chain ID already points at it, such as the default `anvil` one,
+/// that network is reused instead
+pub async fn spawn_anvil(opts: AnvilOpts) -> Result<AnvilStatus> {
+    let mut anvil = ANVIL.lock().await;
+    if anvil.is_some() {
+        return Err(Error::AnvilAlreadyRunning);
+    }
+
+    let port = opts.port.unwrap_or(8545);
+    let chain_id = opts.chain_id.unwrap_or(31337);
+    let mut network = Network {
+        name: format!("anvil-{}", port),
+        chain_id,
+        explorer_url: None,
+        http_url: format!("http://localhost:{}", port),
+        ws_url: Some(format!("ws://localhost:{}", port)),
+        currency: String::from("ETH"),
+        decimals: 18,
+        fallback_rpc_urls: vec![],
+        supports_eip1559: false,
+        explorer_api_url: None,
+        explorer_api_key: None,
+        bundler_url: None,
+        private_rpc_url: None,
+        private_by_default: false,
+        rpc_timeout_secs: default_rpc_timeout_secs(),
+        rpc_retries: default_rpc_retries(),
+        rpc_backoff_ms: default_rpc_backoff_ms(),
+    };
+
+    // settle what to register ahead of spawning, so a conflicting network doesn't leave an
+    // orphan process behind
+    let (fork_url, existing) = {
+        let networks = Networks::read().await;
+        let fork_url = match opts.fork {
+            true => Some(networks.get_current()?.active_rpc_url().to_string()),
+            false => None,
+        };
+
+        let existing = match networks.get_network(chain_id) {
+            Some(n) if n.http_url == network.http_url => Some(n),
+            Some(_) => return Err(Error::DuplicateChainId(chain_id)),
+            None => None,
+        };
+
+        (fork_url, existing)
+    };
+
+    let mut cmd = Command::new("anvil");
+    cmd.arg("--port")
+        .arg(port.to_string())
+        .arg("--chain-id")
+        .arg(chain_id.to_string())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(ref url) = fork_url {
+        cmd.arg("--fork-url").arg(url);
+        if let Some(block) = opts.fork_block {
+            cmd.arg("--fork-block-number").arg(block.to_string());
+        }
+    }
+
+    let mut child = cmd.spawn().map_err(Error::AnvilSpawn)?;
+    wait_until_ready(&mut child).await?;
+
+    let registered = match existing {
+        Some(existing) => {
+            network = existing;
+            false
+        }
+        None => {
+            Networks::write().await.add_network(network.clone()).await?;
+            true
+        }
+    };
+    network.reset_listener().await?;
+
+    let status = AnvilStatus {
+        network: network.name,
+        chain_id,
+        port,
+        fork_url,
+        fork_block: opts.fork_block,
+    };
+    *anvil = Some(Anvil {
+        child,
+        status: status.clone(),
+        registered,
+    });
+
+    Ok(status)
+}
+
+/// Kills the anvil process spawned by the app, deregistering its network if we registered it
+/// The app moves to another network first if that one is currently selected
+pub async fn stop_anvil() -> Result<()> {
+    let Some(mut anvil) = ANVIL.lock().await.take() else {
+        return Ok(());
+    };
+
+    if anvil.registered {
+        let mut networks = Networks::write().await;
+        let name = &anvil.status.network;
+
+        if networks.current == *name {
+            if let Some(other) = networks.networks.keys().find(|n| *n != name).cloned() {
+                networks.set_current_by_name(other).await?;
+            }
+        }
+        if let Err(e) = networks.remove_network(name).await {
+            tracing::warn!("Failed to deregister {}: {}", name, e);
+        }
+    }
+
+    anvil.child.kill().await?;
+
+    Ok(())
+}
+
+/// Reads anvil's stdout until it reports it's listening
+/// The rest of its output is drained in the background, so that anvil never blocks on a full
+/// pipe
+async fn wait_until_ready(child: &mut Child) -> Result<()> {
+    let stdout = child.stdout.take().ok_or(Error::AnvilNotReady)?;
+    let mut lines = BufReader::new(stdout).lines();
+
+    let ready = timeout(READY_TIMEOUT, async {
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.contains(READY_LINE) {
+                return true;
+            }
+        }
+        false
+    })
+    .await;
+
+    if !matches!(ready, Ok(true)) {
+        let _ = child.kill().await;
+        return Err(Error::AnvilNotReady);
+    }
+
+    tokio::spawn(async move {
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::trace!(target: "anvil", "{}", line);
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+//! Chain metadata from chainlist's dataset, for adding chains that aren't among the presets
+//! The dataset covers every chain registered in ethereum-lists/chains, so it's only fetched when
+//! searched, and kept for the rest of the session
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use super::{
+    network::{default_rpc_backoff_ms, default_rpc_retries, default_rpc_timeout_secs},
+    presets, Network, Result,
+};
+
+const DATASET_URL: &str = "https://chainid.network/chains.json";
+
+/// How many results a search returns at most
+const MAX_RESULTS: usize = 20;
+
+static DATASET: Lazy<RwLock<Option<Vec<Chain>>>> = Lazy::new(Default::default);
+
+/// An entry of the dataset, with only the fields needed to build a network
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Chain {
+    name: String,
+
+    /// Some registered chain IDs don't fit the `u32`s networks use, and are skipped
+    chain_id: u64,
+    native_currency: Currency,
+    #[serde(default)]
+    rpc: Vec<String>,
+    #[serde(default)]
+    explorers: Vec<Explorer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Currency {
+    symbol: String,
+    decimals: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Explorer {
+    url: String,
+}
+
+impl Chain {
+    /// Builds a network from this chain, or `None` if it has no RPC that can be used as is
+    /// RPCs that need an API key are templated with `${...}` in the dataset, and are left out
+    fn into_network(self) -> Option<Network> {
+        let usable = |url: &String| !url.contains("${");
+        let mut http = self
+            .rpc
+            .iter()
+            .filter(|url| usable(url) && url.starts_with("http"))
+            .cloned();
+        let ws_url = self
+            .rpc
+            .iter()
+            .find(|url| usable(url) && url.starts_with("ws"))
+            .cloned();
+
+        Some(Network {
+            name: self.name,
+            chain_id: self.chain_id.try_into().ok()?,
+            explorer_url: self
+                .explorers
+                .first()
+                .map(|e| format!("{}/search?q=", e.url.trim_end_matches('/'))),
+            http_url: http.next()?,
+            ws_url,
+            currency: self.native_currency.symbol,
+            decimals: self.native_currency.decimals,
+            fallback_rpc_urls: http.collect(),
+            supports_eip1559: true,
+            explorer_api_url: None,
+            explorer_api_key: None,
+            bundler_url: None,
+            private_rpc_url: None,
+            private_by_default: false,
+            rpc_timeout_secs: default_rpc_timeout_secs(),
+            rpc_retries: default_rpc_retries(),
+            rpc_backoff_ms: default_rpc_backoff_ms(),
+        })
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        self.chain_id.to_string() == query || self.name.to_lowercase().contains(query)
+    }
+}
+
+/// Finds chains by name or chain ID
+/// Presets come first, since their RPCs are known to work. The dataset is only searched for
+/// chains they don't cover
+pub async fn search(query: &str) -> Result<Vec<Network>> {
+    let query = query.trim().to_lowercase();
+    let mut results: Vec<_> = presets()
+        .into_iter()
+        .filter(|n| n.chain_id.to_string() == query || n.name.contains(&query))
+        .collect();
+
+    let known: Vec<_> = results.iter().map(|n| n.chain_id as u64).collect();
+    let chains = dataset().await?;
+    results.extend(
+        chains
+            .into_iter()
+            .filter(|c| !known.contains(&c.chain_id) && c.matches(&query))
+            .filter_map(Chain::into_network),
+    );
+    results.truncate(MAX_RESULTS);
+
+    Ok(results)
+}
+
+async fn dataset() -> Result<Vec<Chain>> {
+    if let Some(ref chains) = *DATASET.read().await {
+        return Ok(chains.clone());
+    }
+
+    // entries are parsed one by one, so that a malformed one doesn't take the others down
+    let entries: Vec<Value> = reqwest::get(DATASET_URL).await?.json().await?;
+    let chains: Vec<Chain> = entries
+        .into_iter()
+        .filter_map(|entry| serde_json::from_value(entry).ok())
+        .collect();
+    *DATASET.write().await = Some(chains.clone());
+
+    Ok(chains)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_networks_from_usable_rpcs() {
+        let chain: Chain = serde_json::from_value(serde_json::json!({
+            "name": "Example Chain",
+            "chainId": 1234,
+            "nativeCurrency": { "name": "Example", "symbol": "EXM", "decimals": 18 },
+            "rpc": [
+                "https://example.io/${API_KEY}",
+                "https://rpc.example.io",
+                "wss://ws.example.io",
+                "https://backup.example.io",
+            ],
+            "explorers": [{ "name": "explorer", "url": "https://scan.example.io/" }],
+        }))
+        .unwrap();
+
+        let network = chain.into_network().unwrap();
+        assert_eq!(network.http_url, "https://rpc.example.io");
+        assert_eq!(network.fallback_rpc_urls, vec!["https://backup.example.io"]);
+        assert_eq!(network.ws_url.as_deref(), Some("wss://ws.example.io"));
+        assert_eq!(
+            network.explorer_url.as_deref(),
+            Some("https://scan.example.io/search?q=")
+        );
+        assert_eq!(network.currency, "EXM");
+    }
+
+    #[test]
+    fn skips_chains_without_public_rpcs() {
+        let chain: Chain = serde_json::from_value(serde_json::json!({
+            "name": "Private",
+            "chainId": 4321,
+            "nativeCurrency": { "name": "P", "symbol": "P", "decimals": 18 },
+            "rpc": ["https://private.io/${API_KEY}"],
+        }))
+        .unwrap();
+
+        assert!(chain.into_network().is_none());
+    }
+}
@@ -0,0 +1,116 @@
+//! Anvil cheatcodes, for setting up state on a local dev chain while testing against it
+//! Every call targets the current network, and is refused unless it's a dev one
+
+use std::{collections::HashSet, sync::Mutex};
+
+use ethers::types::{Address, U256};
+use iron_types::{GlobalState, UINotify};
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{anvil_status, Error, InFlight, Network, Networks, Result};
+
+/// Accounts being impersonated, by chain ID
+/// Transactions from these are sent to the node unsigned, which anvil accepts for them
+static IMPERSONATED: Lazy<Mutex<HashSet<(u32, Address)>>> = Lazy::new(Default::default);
+
+/// Overrides an account's native balance
+pub async fn set_balance(address: Address, balance: U256) -> Result<()> {
+    call::<_, ()>("anvil_setBalance", (address, balance)).await?;
+    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+
+    Ok(())
+}
+
+/// Lets transactions be sent from an account whose key isn't known
+pub async fn impersonate(address: Address) -> Result<()> {
+    let network = dev_network().await?;
+    call::<_, ()>("anvil_impersonateAccount", [address]).await?;
+    impersonated().insert((network.chain_id, address));
+
+    Ok(())
+}
+
+pub async fn stop_impersonating(address: Address) -> Result<()> {
+    let network = dev_network().await?;
+    call::<_, ()>("anvil_stopImpersonatingAccount", [address]).await?;
+    impersonated().remove(&(network.chain_id, address));
+
+    Ok(())
+}
+
+/// Whether transactions from an account should be sent as an impersonation
+pub fn is_impersonating(chain_id: u32, address: Address) -> bool {
+    impersonated().contains(&(chain_id, address))
+}
+
+/// The accounts impersonated on a chain
+pub fn impersonated_accounts(chain_id: u32) -> Vec<Address> {
+    impersonated()
+        .iter()
+        .filter(|(id, _)| *id == chain_id)
+        .map(|(_, address)| *address)
+        .collect()
+}
+
+/// Mines a number of blocks right away, or a single one by default
+pub async fn mine(blocks: Option<u64>) -> Result<()> {
+    match blocks {
+        Some(blocks) => call::<_, ()>("anvil_mine", [U256::from(blocks)]).await?,
+        None => call::<_, String>("evm_mine", ()).await.map(|_| ())?,
+    };
+    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+
+    Ok(())
+}
+
+/// Sets the timestamp of the next block, in seconds since the epoch
+pub async fn set_next_block_timestamp(timestamp: u64) -> Result<()> {
+    call::<_, ()>("evm_setNextBlockTimestamp", [timestamp]).await
+}
+
+/// Takes a snapshot of the chain's state, returning its ID for `revert`
+pub async fn snapshot() -> Result<U256> {
+    call("evm_snapshot", ()).await
+}
+
+/// Restores a snapshot. Returns whether it existed
+/// Snapshots can only be reverted to once, since reverting discards them
+pub async fn revert(id: U256) -> Result<bool> {
+    let reverted = call("evm_revert", [id]).await?;
+
+    // history indexed past the snapshot no longer exists, so it's synced again from scratch
+    if reverted {
+        dev_network().await?.reset_listener().await?;
+        iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+    }
+
+    Ok(reverted)
+}
+
+async fn call<P, R>(method: &str, params: P) -> Result<R>
+where
+    P: Serialize + Send + Sync + std::fmt::Debug,
+    R: Serialize + DeserializeOwned + std::fmt::Debug + Send,
+{
+    let provider = dev_network().await?.get_provider()?;
+    let _in_flight = InFlight::start();
+
+    Ok(provider.request(method, params).await?)
+}
+
+/// The current network, as long as it's one cheatcodes can be used on: the default dev chain, or
+/// the anvil process spawned by the app
+async fn dev_network() -> Result<Network> {
+    let network = Networks::read().await.get_current()?.clone();
+    let spawned = anvil_status().await.map(|s| s.network);
+
+    match network.is_dev() || spawned.as_ref() == Some(&network.name) {
+        true => Ok(network),
+        false => Err(Error::NotADevNetwork(network.name)),
+    }
+}
+
+fn impersonated() -> std::sync::MutexGuard<'static, HashSet<(u32, Address)>> {
+    IMPERSONATED.lock().unwrap_or_else(|e| e.into_inner())
+}
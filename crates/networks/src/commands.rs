@@ -1,12 +1,43 @@
+use std::collections::HashMap;
+
+use ethers::types::{Address, BlockNumber, H256, U256};
 use iron_types::GlobalState;
 
-use super::{network::Network, Networks, Result};
+use super::{
+    cheatcodes, lookup_address, network::Network, resolve_name, AnvilOpts, AnvilStatus, Error,
+    FeeEstimates, NetworkHealth, Networks, Result, VerifiedContract,
+};
 
 #[tauri::command]
 pub async fn networks_get_current() -> Result<Network> {
     let networks = Networks::read().await;
 
-    Ok(networks.get_current().clone())
+    Ok(networks.get_current()?.clone())
+}
+
+#[tauri::command]
+pub async fn networks_get_active_rpc_url() -> Result<Option<String>> {
+    let networks = Networks::read().await;
+
+    Ok(networks.active_rpc_url())
+}
+
+/// The latest probes of every network's RPC endpoints, by network name
+#[tauri::command]
+pub async fn networks_get_health() -> HashMap<String, NetworkHealth> {
+    super::network_health(&Networks::read().await)
+}
+
+/// Makes one of a network's RPC URLs its primary one
+#[tauri::command]
+pub async fn networks_use_rpc_url(network: String, url: String) -> Result<()> {
+    Networks::write().await.use_rpc_url(&network, &url).await
+}
+
+/// How many RPC requests are currently awaiting a response
+#[tauri::command]
+pub async fn networks_get_in_flight_requests() -> usize {
+    super::in_flight_requests()
 }
 
 #[tauri::command]
@@ -34,6 +65,39 @@ pub async fn networks_set_list(new_networks: Vec<Network>) -> Result<()> {
     Ok(())
 }
 
+/// Adds a custom network, once its RPC has been checked to serve the given chain ID
+#[tauri::command]
+pub async fn networks_add(network: Network) -> Result<()> {
+    network.verify_rpc().await?;
+
+    Networks::write().await.add_network(network).await
+}
+
+/// Replaces a network by name, checking its RPC the same way as when adding one
+#[tauri::command]
+pub async fn networks_update(name: String, network: Network) -> Result<()> {
+    network.verify_rpc().await?;
+
+    Networks::write().await.update_network(&name, network).await
+}
+
+#[tauri::command]
+pub async fn networks_remove(name: String) -> Result<()> {
+    Networks::write().await.remove_network(&name).await
+}
+
+/// Popular chains that can be added without entering their details
+#[tauri::command]
+pub async fn networks_get_presets() -> Vec<Network> {
+    super::presets()
+}
+
+/// Searches the presets and chainlist's dataset for chains to add, by name or chain ID
+#[tauri::command]
+pub async fn networks_search_chains(query: String) -> Result<Vec<Network>> {
+    super::search_chains(&query).await
+}
+
 #[tauri::command]
 pub async fn networks_reset() -> Result<Vec<Network>> {
     let mut networks = Networks::write().await;
@@ -42,3 +106,129 @@ pub async fn networks_reset() -> Result<Vec<Network>> {
 
     Ok(networks.networks.values().cloned().collect())
 }
+
+/// Reads a raw storage slot of a contract on the current network
+/// Defaults to the latest block
+#[tauri::command]
+pub async fn networks_get_storage_at(
+    address: Address,
+    slot: H256,
+    block: Option<u64>,
+) -> Result<H256> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    network
+        .get_storage_at(address, slot, block.map(|b| BlockNumber::from(b).into()))
+        .await
+}
+
+/// The latest block number of the current network
+#[tauri::command]
+pub async fn networks_get_block_number() -> Result<u64> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    network.get_block_number().await
+}
+
+/// Suggests fees for a new transaction on the current network
+#[tauri::command]
+pub async fn networks_get_fee_estimates() -> Result<FeeEstimates> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    network.get_fee_estimates().await
+}
+
+/// Resolves an ENS name to an address, using mainnet even if another network is selected
+#[tauri::command]
+pub async fn networks_resolve_name(name: String) -> Result<Address> {
+    resolve_name(&name).await
+}
+
+/// The primary ENS name of an address, if any
+#[tauri::command]
+pub async fn networks_lookup_address(address: Address) -> Result<Option<String>> {
+    lookup_address(address).await
+}
+
+/// Link to an address, transaction or block on the current network's explorer, if it has one
+#[tauri::command]
+pub async fn networks_get_explorer_link(query: String) -> Result<Option<String>> {
+    let networks = Networks::read().await;
+
+    Ok(networks.get_current()?.explorer_link(&query))
+}
+
+/// The verified source of a contract, fetched from its network's explorer
+#[tauri::command]
+pub async fn networks_get_verified_contract(
+    chain_id: u32,
+    address: Address,
+) -> Result<Option<VerifiedContract>> {
+    let network = Networks::read()
+        .await
+        .get_network(chain_id)
+        .ok_or(Error::InvalidChainId(chain_id))?;
+
+    network.explorer().await?.verified_contract(address).await
+}
+
+/// Spawns a local anvil process, optionally forked from the current network, and registers it
+#[tauri::command]
+pub async fn networks_spawn_anvil(opts: AnvilOpts) -> Result<AnvilStatus> {
+    super::spawn_anvil(opts).await
+}
+
+#[tauri::command]
+pub async fn networks_stop_anvil() -> Result<()> {
+    super::stop_anvil().await
+}
+
+/// The anvil process spawned by the app, if it's running
+#[tauri::command]
+pub async fn networks_get_anvil() -> Option<AnvilStatus> {
+    super::anvil_status().await
+}
+
+#[tauri::command]
+pub async fn networks_anvil_set_balance(address: Address, balance: U256) -> Result<()> {
+    cheatcodes::set_balance(address, balance).await
+}
+
+/// Starts impersonating an account, after which transactions can be sent from it as from any
+/// other, through the usual review
+#[tauri::command]
+pub async fn networks_anvil_impersonate(address: Address) -> Result<()> {
+    cheatcodes::impersonate(address).await
+}
+
+#[tauri::command]
+pub async fn networks_anvil_stop_impersonating(address: Address) -> Result<()> {
+    cheatcodes::stop_impersonating(address).await
+}
+
+/// The accounts impersonated on the current network
+#[tauri::command]
+pub async fn networks_anvil_get_impersonated() -> Result<Vec<Address>> {
+    let chain_id = Networks::read().await.get_current()?.chain_id;
+    Ok(cheatcodes::impersonated_accounts(chain_id))
+}
+
+#[tauri::command]
+pub async fn networks_anvil_mine(blocks: Option<u64>) -> Result<()> {
+    cheatcodes::mine(blocks).await
+}
+
+#[tauri::command]
+pub async fn networks_anvil_set_next_block_timestamp(timestamp: u64) -> Result<()> {
+    cheatcodes::set_next_block_timestamp(timestamp).await
+}
+
+#[tauri::command]
+pub async fn networks_anvil_snapshot() -> Result<U256> {
+    cheatcodes::snapshot().await
+}
+
+#[tauri::command]
+pub async fn networks_anvil_revert(id: U256) -> Result<bool> {
+    cheatcodes::revert(id).await
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ethers::{
+    providers::{Middleware, ProviderError},
+    types::Address,
+};
+use iron_types::GlobalState;
+use once_cell::sync::Lazy;
+
+use super::{Error, Networks, Result};
+
+/// ENS lives on mainnet, so lookups always go there regardless of the current network
+const ENS_CHAIN_ID: u32 = 1;
+
+/// How long resolved names and addresses are trusted before asking the RPC again
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+static NAMES: Lazy<Mutex<HashMap<String, (Address, Instant)>>> = Lazy::new(Default::default);
+static ADDRESSES: Lazy<Mutex<HashMap<Address, (Option<String>, Instant)>>> =
+    Lazy::new(Default::default);
+
+/// Forgets all resolved names and addresses
+pub(crate) fn clear_cache() {
+    NAMES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    ADDRESSES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Resolves an ENS name, such as `vitalik.eth`, to an address
+pub async fn resolve_name(name: &str) -> Result<Address> {
+    let name = normalize_name(name)?;
+
+    if let Some(address) = cached(&NAMES, &name) {
+        return Ok(address);
+    }
+
+    let provider = Networks::read()
+        .await
+        .get_network(ENS_CHAIN_ID)
+        .ok_or(Error::EnsUnavailable)?
+        .get_provider()?;
+
+    let address = provider.resolve_name(&name).await.map_err(|e| match e {
+        ProviderError::EnsError(_) => Error::UnresolvedEnsName(name.clone()),
+        e => e.into(),
+    })?;
+
+    NAMES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name, (address, Instant::now()));
+
+    Ok(address)
+}
+
+/// Finds the primary ENS name of an address, if it set one
+pub async fn lookup_address(address: Address) -> Result<Option<String>> {
+    if let Some(name) = cached(&ADDRESSES, &address) {
+        return Ok(name);
+    }
+
+    let provider = Networks::read()
+        .await
+        .get_network(ENS_CHAIN_ID)
+        .ok_or(Error::EnsUnavailable)?
+        .get_provider()?;
+
+    let name = match provider.lookup_address(address).await {
+        Ok(name) => Some(name),
+        Err(ProviderError::EnsError(_)) | Err(ProviderError::EnsNotOwned(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    ADDRESSES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(address, (name.clone(), Instant::now()));
+
+    Ok(name)
+}
+
+fn cached<K, V>(cache: &Mutex<HashMap<K, (V, Instant)>>, key: &K) -> Option<V>
+where
+    K: std::hash::Hash + Eq,
+    V: Clone,
+{
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(key)
+        .filter(|(_, at)| at.elapsed() < CACHE_TTL)
+        .map(|(value, _)| value.clone())
+}
+
+/// Lowercases a name and checks that it's made of non-empty dot-separated labels
+fn normalize_name(name: &str) -> Result<String> {
+    let name = name.trim().to_lowercase();
+
+    let valid = name.contains('.')
+        && name
+            .split('.')
+            .all(|label| !label.is_empty() && !label.chars().any(char::is_whitespace));
+
+    if valid {
+        Ok(name)
+    } else {
+        Err(Error::InvalidEnsName(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_name_rejects_malformed_names() {
+        for name in [
+            "",
+            "eth",
+            ".eth",
+            "vitalik.",
+            "vitalik..eth",
+            "vita lik.eth",
+        ] {
+            assert!(
+                normalize_name(name).is_err(),
+                "{:?} should be invalid",
+                name
+            );
+        }
+
+        assert_eq!(normalize_name(" Vitalik.ETH ").unwrap(), "vitalik.eth");
+        assert_eq!(
+            normalize_name("pay.vitalik.eth").unwrap(),
+            "pay.vitalik.eth"
+        );
+    }
+}
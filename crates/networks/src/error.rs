@@ -14,6 +14,85 @@ pub enum Error {
 
     #[error("Invalid chain ID: {0}")]
     InvalidChainId(u32),
+
+    #[error("Unknown network: {0}")]
+    UnknownNetwork(String),
+
+    #[error("Current network {0} is not in the network list")]
+    UnknownCurrentNetwork(String),
+
+    #[error("Network list cannot be empty")]
+    EmptyNetworkList,
+
+    #[error("Invalid RPC URL: {0}")]
+    InvalidRpcUrl(String),
+
+    #[error("{0} is not one of the network's RPC URLs")]
+    UnknownRpcUrl(String),
+
+    #[error("A network with chain ID {0} already exists")]
+    DuplicateChainId(u32),
+
+    #[error("A network named {0} already exists")]
+    DuplicateNetworkName(String),
+
+    #[error("Invalid chain parameters: {0}")]
+    InvalidChainParams(String),
+
+    #[error("RPC {url} serves chain {actual}, not {expected}")]
+    RpcChainMismatch {
+        url: String,
+        expected: u32,
+        actual: u64,
+    },
+
+    #[error("Chain {0} already exists with different parameters")]
+    ConflictingChain(u32),
+
+    #[error("Cannot remove the current network: {0}")]
+    CannotRemoveCurrent(String),
+
+    #[error("Dev network {0} is missing a websockets URL")]
+    MissingWsUrl(String),
+
+    #[error("Failed to spawn anvil: {0}")]
+    AnvilSpawn(std::io::Error),
+
+    #[error("anvil did not start listening in time")]
+    AnvilNotReady,
+
+    #[error("anvil is already running")]
+    AnvilAlreadyRunning,
+
+    #[error("{0} is not a dev network, so cheatcodes are unavailable")]
+    NotADevNetwork(String),
+
+    #[error("Invalid ENS name: {0}")]
+    InvalidEnsName(String),
+
+    #[error("ENS name {0} does not resolve to an address")]
+    UnresolvedEnsName(String),
+
+    #[error("ENS lookups require a mainnet network")]
+    EnsUnavailable,
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("{0} has no explorer API configured")]
+    NoExplorer(String),
+
+    #[error(transparent)]
+    Explorer(#[from] ethers::etherscan::errors::EtherscanError),
+
+    #[error(transparent)]
+    Provider(#[from] ethers::providers::ProviderError),
+
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Conversion(#[from] ethers::utils::ConversionError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
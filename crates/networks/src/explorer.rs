@@ -0,0 +1,189 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use ethers::{
+    abi::Abi,
+    etherscan::{
+        account::{Sort, TxListParams},
+        errors::EtherscanError,
+        Client,
+    },
+    types::{Address, Chain},
+};
+use iron_settings::Settings;
+use iron_types::{events::Tx, GlobalState};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::{Error, Network, Result};
+
+/// How many transactions are fetched per history request
+/// Etherscan caps a single query at 10000 results, and is much slower near that
+const HISTORY_PAGE_SIZE: u64 = 1000;
+
+/// What Etherscan's own docs use as the end block, to mean the latest one
+const LATEST_BLOCK: u64 = 99_999_999;
+
+/// Verified contracts looked up so far, by chain ID and address
+/// Unverified ones are remembered too, so that decoding calls to them doesn't hit the API every
+/// time. Verified code can't change, so entries never expire
+static CONTRACTS: Lazy<Mutex<HashMap<(u32, Address), Option<VerifiedContract>>>> =
+    Lazy::new(Default::default);
+
+/// A contract whose source was verified on an explorer
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedContract {
+    pub name: String,
+    pub abi: Abi,
+    pub source: String,
+}
+
+/// The Etherscan-compatible API of a network's explorer
+#[derive(Debug)]
+pub struct Explorer {
+    chain_id: u32,
+    client: Client,
+}
+
+impl Network {
+    /// The explorer API of this network
+    /// Uses the configured API URL, or Etherscan's own instance for chains it knows of
+    pub async fn explorer(&self) -> Result<Explorer> {
+        let (api_url, url) = match self.explorer_api_url.as_deref().map(str::trim) {
+            Some(api_url) if !api_url.is_empty() => (
+                api_url.to_string(),
+                self.explorer_base().unwrap_or(api_url).to_string(),
+            ),
+            _ => Chain::try_from(self.chain_id)
+                .ok()
+                .and_then(|chain| chain.etherscan_urls())
+                .map(|(api_url, url)| (api_url.to_string(), url.to_string()))
+                .ok_or_else(|| Error::NoExplorer(self.name.clone()))?,
+        };
+
+        let api_key = match self.explorer_api_key.as_deref() {
+            Some(key) if !key.is_empty() => Some(key.to_string()),
+            _ => Settings::read().await.get_etherscan_api_key().ok(),
+        };
+
+        let mut builder = Client::builder()
+            .with_api_url(api_url.as_str())?
+            .with_url(url.as_str())?;
+        if let Some(key) = api_key {
+            builder = builder.with_api_key(key);
+        }
+
+        Ok(Explorer {
+            chain_id: self.chain_id,
+            client: builder.build()?,
+        })
+    }
+}
+
+impl Explorer {
+    /// Name, ABI and source of a contract, or `None` if it isn't verified
+    pub async fn verified_contract(&self, address: Address) -> Result<Option<VerifiedContract>> {
+        let key = (self.chain_id, address);
+        if let Some(cached) = CONTRACTS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+        {
+            return Ok(cached.clone());
+        }
+
+        let contract = match self.client.contract_source_code(address).await {
+            Ok(metadata) => metadata.items.into_iter().next().and_then(|item| {
+                Some(VerifiedContract {
+                    abi: serde_json::from_str(&item.abi).ok()?,
+                    source: item.source_code.source_code(),
+                    name: item.contract_name,
+                })
+            }),
+            Err(EtherscanError::ContractCodeNotVerified(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        CONTRACTS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, contract.clone());
+
+        Ok(contract)
+    }
+
+    /// The ABI of a contract, or `None` if it isn't verified
+    pub async fn contract_abi(&self, address: Address) -> Result<Option<Abi>> {
+        Ok(self.verified_contract(address).await?.map(|c| c.abi))
+    }
+
+    /// Transactions sent from or to an address, starting at a given block, oldest first
+    /// Meant for networks where scanning for them over RPC is impractical
+    /// At most one page is returned, so callers should continue from the last block they got
+    pub async fn transactions(&self, address: Address, from_block: u64) -> Result<Vec<Tx>> {
+        let params = TxListParams::new(from_block, LATEST_BLOCK, 1, HISTORY_PAGE_SIZE, Sort::Asc);
+
+        let txs = match self.client.get_transactions(&address, Some(params)).await {
+            Ok(txs) => txs,
+            // explorers report an empty history as an error
+            Err(EtherscanError::EmptyResult { .. }) => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(txs
+            .into_iter()
+            .filter_map(|tx| {
+                Some(Tx {
+                    hash: *tx.hash.value()?,
+                    from: *tx.from.value()?,
+                    to: tx.to,
+                    value: tx.value,
+                    data: tx.input.value().cloned().unwrap_or_default(),
+                    block_number: tx.block_number.as_number()?.as_u64(),
+                    position: tx.transaction_index.map(|i| i as usize),
+                    status: match tx.is_error.as_str() {
+                        "0" => 1,
+                        _ => 0,
+                    },
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn explorer_config() {
+        // keys are given, so the global settings are never reached for one
+        let key = Some(String::from("key"));
+
+        // chains Etherscan knows of need no API URL
+        let network = Network {
+            explorer_api_key: key.clone(),
+            ..Network::mainnet()
+        };
+        assert!(network.explorer().await.is_ok());
+        assert!(matches!(
+            Network::anvil().explorer().await,
+            Err(Error::NoExplorer(_))
+        ));
+
+        let network = Network {
+            explorer_api_url: Some("https://api.example.org/api".into()),
+            explorer_url: Some("https://example.org".into()),
+            explorer_api_key: key.clone(),
+            ..Network::anvil()
+        };
+        assert!(network.explorer().await.is_ok());
+
+        let network = Network {
+            explorer_api_url: Some("not a url".into()),
+            explorer_api_key: key,
+            ..Network::anvil()
+        };
+        assert!(network.explorer().await.is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient, JsonRpcError};
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use url::Url;
+
+/// How long a failing endpoint is skipped for after its first failure
+/// Each further consecutive failure doubles this, up to `MAX_COOLDOWN`
+const BASE_COOLDOWN: Duration = Duration::from_secs(15);
+const MAX_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// Health of each RPC endpoint, by URL
+/// Shared across providers, so that a rebuilt provider doesn't start over with a dead endpoint
+static HEALTH: Lazy<Mutex<HashMap<String, Health>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Health {
+    /// Consecutive failures since the last successful request
+    failures: u32,
+    down_since: Option<Instant>,
+}
+
+impl Health {
+    fn is_down(&self) -> bool {
+        let Some(since) = self.down_since else {
+            return false;
+        };
+        let cooldown = BASE_COOLDOWN
+            .saturating_mul(2u32.saturating_pow(self.failures.saturating_sub(1)))
+            .min(MAX_COOLDOWN);
+
+        since.elapsed() < cooldown
+    }
+}
+
+/// How long requests may take, and how persistently they're retried, as configured per network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+    /// How long a single endpoint is waited on before it's taken as failed
+    pub timeout: Duration,
+
+    /// How many more rounds through the endpoints are made once all of them failed
+    pub retries: u32,
+
+    /// Wait before the first retry, doubling with each further one
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// The wait before a given retry, counting from 1
+    fn backoff(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// The longest wait between retries, however many there are
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A JSON-RPC transport over a network's ordered list of HTTP endpoints
+/// Requests go to the first healthy endpoint, and move on to the next one on connection errors,
+/// timeouts or rate limits. Endpoints that fail are put on a cooldown, and only tried again once
+/// every healthy one also failed. Once all of them did, the whole list is retried with backoff
+#[derive(Debug, Clone)]
+pub struct Failover {
+    endpoints: Vec<(String, Http)>,
+    policy: RetryPolicy,
+}
+
+impl Failover {
+    pub(crate) fn new<'a>(
+        urls: impl Iterator<Item = &'a str>,
+        policy: RetryPolicy,
+    ) -> Option<Self> {
+        let endpoints = urls
+            .map(|url| {
+                let client = reqwest::Client::builder()
+                    .timeout(policy.timeout)
+                    .build()
+                    .ok()?;
+                Some((
+                    url.to_string(),
+                    Http::new_with_client(url.parse::<Url>().ok()?, client),
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        (!endpoints.is_empty()).then_some(Self { endpoints, policy })
+    }
+
+    /// The URLs this transport was built for, in their configured order
+    pub(crate) fn urls(&self) -> impl Iterator<Item = &str> {
+        self.endpoints.iter().map(|(url, _)| url.as_str())
+    }
+
+    pub(crate) fn policy(&self) -> RetryPolicy {
+        self.policy
+    }
+
+    /// Endpoints in the order they'll be tried
+    fn ordered(&self) -> Vec<&(String, Http)> {
+        order(self.urls())
+            .into_iter()
+            .map(|i| &self.endpoints[i])
+            .collect()
+    }
+}
+
+/// Indices of the given endpoints in the order they'll be tried: healthy ones first, in their
+/// configured order, then the ones on cooldown
+pub(crate) fn order<'a>(urls: impl Iterator<Item = &'a str>) -> Vec<usize> {
+    let health = HEALTH.lock().unwrap_or_else(|e| e.into_inner());
+    let is_down = |url: &str| health.get(url).is_some_and(Health::is_down);
+
+    let (up, down): (Vec<_>, Vec<_>) = urls.enumerate().partition(|(_, url)| !is_down(url));
+    up.into_iter().chain(down).map(|(i, _)| i).collect()
+}
+
+#[async_trait]
+impl JsonRpcClient for Failover {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: std::fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // serialized once, since each attempt consumes its params
+        let params = serde_json::to_value(params).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: String::new(),
+        })?;
+
+        let mut last_error = None;
+
+        for retry in 0..=self.policy.retries {
+            if retry > 0 {
+                let backoff = self.policy.backoff(retry);
+                tracing::warn!(
+                    "every RPC endpoint failed, retrying {} in {:?}",
+                    method,
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+            }
+
+            for (url, client) in self.ordered() {
+                match client.request(method, &params).await {
+                    Ok(res) => {
+                        mark_up(url);
+                        return Ok(res);
+                    }
+                    Err(e) if is_retryable(&e) => {
+                        tracing::warn!("RPC {} failed, trying the next endpoint: {}", url, e);
+                        mark_down(url);
+                        last_error = Some(e);
+                    }
+                    Err(e) => {
+                        // the endpoint answered, even if with an error, so it's alive
+                        mark_up(url);
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        // endpoints were checked to be non-empty when this transport was built
+        Err(last_error.expect("at least one endpoint"))
+    }
+}
+
+/// Resets the health of endpoints, such as when a network's URLs are edited
+pub(crate) fn reset_health<'a>(urls: impl Iterator<Item = &'a str>) {
+    let mut health = HEALTH.lock().unwrap_or_else(|e| e.into_inner());
+    for url in urls {
+        health.remove(url);
+    }
+}
+
+pub(crate) fn clear_health() {
+    HEALTH.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+fn mark_up(url: &str) {
+    HEALTH.lock().unwrap_or_else(|e| e.into_inner()).remove(url);
+}
+
+fn mark_down(url: &str) {
+    let mut health = HEALTH.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = health.entry(url.to_string()).or_default();
+
+    entry.failures = entry.failures.saturating_add(1);
+    entry.down_since = Some(Instant::now());
+}
+
+/// Whether an error means the endpoint itself is unusable, rather than the request being wrong
+fn is_retryable(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::ReqwestError(_) => true,
+        HttpClientError::JsonRpcError(e) => is_rate_limit(e),
+        // a body that isn't even JSON, such as a proxy's error page or a bare `429 Too Many
+        // Requests`. Valid JSON that didn't match the expected result is the request's fault
+        HttpClientError::SerdeJson { text, .. } => {
+            serde_json::from_str::<serde_json::Value>(text).is_err()
+        }
+    }
+}
+
+pub(crate) fn is_rate_limit(error: &JsonRpcError) -> bool {
+    // -32005 is the commonly used "limit exceeded" code
+    error.code == 429 || error.code == -32005 || error.message.to_lowercase().contains("rate limit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        timeout: Duration::from_secs(1),
+        retries: 2,
+        backoff: Duration::from_millis(500),
+    };
+
+    #[test]
+    fn skips_endpoints_on_cooldown() {
+        let urls = ["http://failover-a.test", "http://failover-b.test"];
+        assert_eq!(order(urls.into_iter()), vec![0, 1]);
+
+        mark_down(urls[0]);
+        assert_eq!(order(urls.into_iter()), vec![1, 0]);
+
+        // when everything is down, the configured order is kept
+        mark_down(urls[1]);
+        assert_eq!(order(urls.into_iter()), vec![0, 1]);
+
+        reset_health(urls.into_iter());
+        mark_down(urls[1]);
+        assert_eq!(order(urls.into_iter()), vec![0, 1]);
+        assert!(Failover::new(urls.into_iter(), POLICY).is_some());
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(POLICY.backoff(1), Duration::from_millis(500));
+        assert_eq!(POLICY.backoff(2), Duration::from_secs(1));
+        assert_eq!(POLICY.backoff(3), Duration::from_secs(2));
+        assert_eq!(POLICY.backoff(20), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn retries_rate_limits_but_not_reverts() {
+        let rpc_error = |code, message: &str| {
+            HttpClientError::JsonRpcError(JsonRpcError {
+                code,
+                message: message.into(),
+                data: None,
+            })
+        };
+
+        assert!(is_retryable(&rpc_error(429, "Too many requests")));
+        assert!(is_retryable(&rpc_error(-32005, "limit exceeded")));
+        assert!(!is_retryable(&rpc_error(3, "execution reverted")));
+
+        let not_json = serde_json::from_str::<u64>("Too Many Requests").unwrap_err();
+        assert!(is_retryable(&HttpClientError::SerdeJson {
+            err: not_json,
+            text: "Too Many Requests".into(),
+        }));
+    }
+}
@@ -0,0 +1,229 @@
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, U256},
+};
+use iron_types::FeeSpeed;
+use serde::Serialize;
+
+use super::{FeeMode, InFlight, Network, Result};
+
+/// How many past blocks fee suggestions are based on
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Reward percentiles used for the low, medium and high priority fee suggestions
+const PRIORITY_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Fee suggestions for a network, to preview how much a transaction will cost
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FeeEstimates {
+    #[serde(rename_all = "camelCase")]
+    Eip1559 {
+        /// The base fee of the next block
+        base_fee: U256,
+        priority_fees: PriorityFees,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    Legacy { gas_price: U256 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorityFees {
+    pub low: U256,
+    pub medium: U256,
+    pub high: U256,
+}
+
+/// Fee fields to set on a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Fees {
+    #[serde(rename_all = "camelCase")]
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    Legacy { gas_price: U256 },
+}
+
+/// Suggested fees for each speed
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeSuggestions {
+    pub slow: Fees,
+    pub normal: Fees,
+    pub fast: Fees,
+}
+
+impl FeeSuggestions {
+    pub fn get(&self, speed: FeeSpeed) -> Fees {
+        match speed {
+            FeeSpeed::Slow => self.slow,
+            FeeSpeed::Normal => self.normal,
+            FeeSpeed::Fast => self.fast,
+        }
+    }
+}
+
+impl FeeEstimates {
+    /// Suggests fee fields for a given speed
+    /// The max fee leaves room for the base fee to double before the transaction is included,
+    /// which covers several full blocks in a row
+    pub fn suggest(&self, speed: FeeSpeed) -> Fees {
+        match self {
+            Self::Eip1559 {
+                base_fee,
+                priority_fees,
+            } => {
+                let priority = match speed {
+                    FeeSpeed::Slow => priority_fees.low,
+                    FeeSpeed::Normal => priority_fees.medium,
+                    FeeSpeed::Fast => priority_fees.high,
+                };
+                Fees::Eip1559 {
+                    max_fee_per_gas: base_fee.saturating_mul(2.into()).saturating_add(priority),
+                    max_priority_fee_per_gas: priority,
+                }
+            }
+
+            // without a fee market, only bumping the price itself changes anything
+            Self::Legacy { gas_price } => Fees::Legacy {
+                gas_price: match speed {
+                    FeeSpeed::Slow => *gas_price * 9 / 10,
+                    FeeSpeed::Normal => *gas_price,
+                    FeeSpeed::Fast => *gas_price * 12 / 10,
+                },
+            },
+        }
+    }
+
+    pub fn suggestions(&self) -> FeeSuggestions {
+        FeeSuggestions {
+            slow: self.suggest(FeeSpeed::Slow),
+            normal: self.suggest(FeeSpeed::Normal),
+            fast: self.suggest(FeeSpeed::Fast),
+        }
+    }
+}
+
+impl Network {
+    /// Estimates the gas a transaction will use
+    pub async fn estimate_gas(&self, tx: TypedTransaction) -> Result<U256> {
+        let _in_flight = InFlight::start();
+
+        Ok(self.get_provider()?.estimate_gas(&tx, None).await?)
+    }
+
+    /// Suggests fees for a new transaction
+    /// On EIP-1559 networks, priority fees are the median of a few reward percentiles over the last
+    /// blocks, as reported by `eth_feeHistory`
+    pub async fn get_fee_estimates(&self) -> Result<FeeEstimates> {
+        let provider = self.get_provider()?;
+        let _in_flight = InFlight::start();
+
+        if self.fee_mode() == FeeMode::Legacy {
+            return Ok(FeeEstimates::Legacy {
+                gas_price: provider.get_gas_price().await?,
+            });
+        }
+
+        let history = provider
+            .fee_history(
+                FEE_HISTORY_BLOCKS,
+                BlockNumber::Latest,
+                &PRIORITY_PERCENTILES,
+            )
+            .await?;
+
+        let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+        let median_reward = |idx: usize| {
+            let mut rewards: Vec<U256> = history
+                .reward
+                .iter()
+                .filter_map(|block| block.get(idx).copied())
+                .collect();
+            rewards.sort();
+            rewards.get(rewards.len() / 2).copied().unwrap_or_default()
+        };
+
+        Ok(FeeEstimates::Eip1559 {
+            base_fee,
+            priority_fees: PriorityFees {
+                low: median_reward(0),
+                medium: median_reward(1),
+                high: median_reward(2),
+            },
+        })
+    }
+
+    /// The base fee and priority fee percentiles of the latest block, along with its number
+    /// Used to track how fees move over time, rather than to price a transaction
+    pub async fn get_latest_block_fees(&self) -> Result<(u64, U256, PriorityFees)> {
+        let provider = self.get_provider()?;
+        let _in_flight = InFlight::start();
+
+        let history = provider
+            .fee_history(1, BlockNumber::Latest, &PRIORITY_PERCENTILES)
+            .await?;
+
+        let base_fee = history
+            .base_fee_per_gas
+            .first()
+            .copied()
+            .unwrap_or_default();
+        let reward = |idx: usize| {
+            history
+                .reward
+                .first()
+                .and_then(|block| block.get(idx).copied())
+                .unwrap_or_default()
+        };
+
+        Ok((
+            history.oldest_block.as_u64(),
+            base_fee,
+            PriorityFees {
+                low: reward(0),
+                medium: reward(1),
+                high: reward(2),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_fees_by_speed() {
+        let estimates = FeeEstimates::Eip1559 {
+            base_fee: 100.into(),
+            priority_fees: PriorityFees {
+                low: 1.into(),
+                medium: 2.into(),
+                high: 3.into(),
+            },
+        };
+
+        assert_eq!(
+            estimates.suggest(FeeSpeed::Fast),
+            Fees::Eip1559 {
+                max_fee_per_gas: 203.into(),
+                max_priority_fee_per_gas: 3.into(),
+            }
+        );
+
+        let legacy = FeeEstimates::Legacy {
+            gas_price: 100.into(),
+        };
+        assert_eq!(
+            legacy.suggest(FeeSpeed::Slow),
+            Fees::Legacy {
+                gas_price: 90.into()
+            }
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Marks a provider request as in flight for as long as it's alive
+///
+/// The counter is decremented on drop, so requests that error out or whose futures get cancelled
+/// are accounted for as well
+#[must_use]
+pub struct InFlight(&'static AtomicUsize);
+
+impl InFlight {
+    pub fn start() -> Self {
+        Self::counted_by(&IN_FLIGHT)
+    }
+
+    fn counted_by(counter: &'static AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// How many provider requests are currently awaiting a response
+pub fn in_flight_requests() -> usize {
+    IN_FLIGHT.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancelled_requests_are_released() {
+        // a counter of its own, since other tests make requests concurrently
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let task = tokio::spawn(async {
+            let _guard = InFlight::counted_by(&COUNTER);
+            std::future::pending::<()>().await;
+        });
+        tokio::task::yield_now().await;
+
+        task.abort();
+        let _ = task.await;
+
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 0);
+    }
+}
@@ -3,20 +3,27 @@ use std::{
     fs::File,
     io::BufReader,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use async_trait::async_trait;
 use iron_broadcast::InternalMsg;
+use iron_settings::Settings;
 use iron_types::{GlobalState, UINotify};
 use once_cell::sync::OnceCell;
 use serde::Deserialize;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use super::{network::Network, Networks};
+use super::{network::Network, probe, Networks, Result};
 
 static NETWORKS: OnceCell<RwLock<Networks>> = OnceCell::new();
 
-pub async fn init(pathbuf: PathBuf) {
+/// How often the current network's RPC is checked for reachability
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Restores the networks persisted at `pathbuf`, or the initial ones if there are none yet
+/// A persisted list that can't be read fails startup, rather than being replaced by defaults
+pub async fn init(pathbuf: PathBuf) -> Result<()> {
     /// The persisted format of the networks object
     #[derive(Debug, Deserialize)]
     struct PersistedNetworks {
@@ -27,10 +34,10 @@ pub async fn init(pathbuf: PathBuf) {
     let path = Path::new(&pathbuf);
 
     let mut res: Networks = if path.exists() {
-        let file = File::open(path).unwrap();
+        let file = File::open(path)?;
         let reader = BufReader::new(file);
 
-        let res: PersistedNetworks = serde_json::from_reader(reader).unwrap();
+        let res: PersistedNetworks = serde_json::from_reader(reader)?;
 
         Networks {
             networks: res.networks,
@@ -38,8 +45,13 @@ pub async fn init(pathbuf: PathBuf) {
             file: pathbuf,
         }
     } else {
-        let networks = Network::all_default();
-        let current = networks[0].name.clone();
+        let networks = Network::initial();
+
+        // `IRON_DEFAULT_NETWORK` may pick the starting network, as long as it's in the list
+        let current = std::env::var("IRON_DEFAULT_NETWORK")
+            .ok()
+            .filter(|name| networks.iter().any(|n| n.name == *name))
+            .unwrap_or_else(|| networks[0].name.clone());
         Networks {
             networks: networks.into_iter().map(|n| (n.name.clone(), n)).collect(),
             current,
@@ -47,12 +59,23 @@ pub async fn init(pathbuf: PathBuf) {
         }
     };
 
+    // a default network set in the settings wins over the one last used
+    if let Some(name) = Settings::read().await.get().default_network.clone() {
+        if res.networks.contains_key(&name) {
+            res.current = name;
+        }
+    }
+
     res.broadcast_init().await;
     res.reset_listeners().await;
 
     NETWORKS.set(RwLock::new(res)).unwrap();
 
     tokio::spawn(async { receiver().await });
+    tokio::spawn(async { health_check().await });
+    tokio::spawn(async { probe::run().await });
+
+    Ok(())
 }
 
 #[async_trait]
@@ -83,3 +106,31 @@ async fn receiver() -> ! {
         }
     }
 }
+
+/// Periodically checks the current network's RPC, notifying when it goes down or comes back up
+/// Switching networks starts over from healthy, since peers take the `chainChanged` they get for
+/// the switch as a `connect`
+async fn health_check() -> ! {
+    let mut healthy = true;
+    let mut checked = None;
+
+    loop {
+        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+
+        let Ok(network) = Networks::read().await.get_current().cloned() else {
+            continue;
+        };
+        if checked.replace(network.chain_id) != Some(network.chain_id) {
+            healthy = true;
+        }
+        let now_healthy = network.check_connection().await;
+
+        match (healthy, now_healthy) {
+            (true, false) => iron_broadcast::network_disconnected(network.chain_id).await,
+            (false, true) => iron_broadcast::network_connected(network.chain_id).await,
+            _ => (),
+        }
+
+        healthy = now_healthy;
+    }
+}
@@ -1,24 +1,51 @@
+mod anvil;
+mod chainlist;
+pub mod cheatcodes;
 pub mod commands;
+mod ens;
 mod error;
+mod explorer;
+mod failover;
+mod fees;
+mod in_flight;
 mod init;
 mod network;
+mod presets;
+mod probe;
+mod rollup;
 
 use std::{
     collections::{HashMap, HashSet},
-    fs::File,
     path::{Path, PathBuf},
 };
 
-use ethers::providers::{Http, Provider};
+use ethers::providers::Provider;
 pub use init::init;
+pub use iron_types::FeeSpeed;
 use iron_types::{Affinity, UINotify};
 use serde::Serialize;
 
 pub use self::{
+    anvil::{anvil_status, spawn_anvil, stop_anvil, AnvilOpts, AnvilStatus},
+    chainlist::search as search_chains,
+    ens::{lookup_address, resolve_name},
     error::{Error, Result},
-    network::Network,
+    explorer::{Explorer, VerifiedContract},
+    failover::Failover,
+    fees::{FeeEstimates, FeeSuggestions, Fees, PriorityFees},
+    in_flight::{in_flight_requests, InFlight},
+    network::{AddChainParams, FeeMode, NativeCurrency, Network},
+    presets::presets,
+    probe::{network_health, NetworkHealth},
+    rollup::{L1Fee, Rollup},
 };
 
+/// Clears in-memory network caches, such as providers and ENS lookups
+pub async fn clear_caches() {
+    Network::clear_caches().await;
+    ens::clear_cache();
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Networks {
     pub networks: HashMap<String, Network>,
@@ -35,11 +62,15 @@ impl Networks {
     ///
     /// Broadcasts `chainChanged` to all connections with global or no affinity
     pub async fn set_current_by_name(&mut self, new_current_network: String) -> Result<()> {
-        let previous = self.get_current().chain_id;
+        if !self.networks.contains_key(&new_current_network) {
+            return Err(Error::UnknownNetwork(new_current_network));
+        }
+
+        let previous = self.get_current().map(|n| n.chain_id).ok();
         self.current = new_current_network;
-        let new = self.get_current().chain_id;
+        let new = self.get_current()?.chain_id;
 
-        if previous != new {
+        if previous != Some(new) {
             self.on_network_changed().await?;
         }
 
@@ -56,7 +87,7 @@ impl Networks {
             .networks
             .values()
             .find(|n| n.chain_id == new_chain_id)
-            .unwrap();
+            .ok_or(Error::InvalidChainId(new_chain_id))?;
 
         self.set_current_by_name(new_network.name.clone()).await?;
         self.save()?;
@@ -68,12 +99,10 @@ impl Networks {
         self.networks.iter().any(|(_, n)| n.chain_id == chain_id)
     }
 
-    pub fn get_current(&self) -> &Network {
-        if !self.networks.contains_key(&self.current) {
-            return self.networks.values().next().unwrap();
-        }
-
-        &self.networks[&self.current]
+    pub fn get_current(&self) -> Result<&Network> {
+        self.networks
+            .get(&self.current)
+            .ok_or_else(|| Error::UnknownCurrentNetwork(self.current.clone()))
     }
 
     pub fn get_network(&self, chain_id: u32) -> Option<Network> {
@@ -83,35 +112,201 @@ impl Networks {
             .cloned()
     }
 
+    pub fn get_network_by_name(&self, name: &str) -> Option<Network> {
+        self.networks.get(name).cloned()
+    }
+
+    /// Adds a single custom network
+    ///
+    /// Its RPC URLs must be valid, and both its name and chain ID must be unique
+    pub async fn add_network(&mut self, network: Network) -> Result<()> {
+        network.validate_rpc_urls()?;
+
+        if self.networks.contains_key(&network.name) {
+            return Err(Error::DuplicateNetworkName(network.name));
+        }
+
+        if self.validate_chain_id(network.chain_id) {
+            return Err(Error::DuplicateChainId(network.chain_id));
+        }
+
+        let chain_id = network.chain_id;
+        Network::invalidate_providers(Some(&network.name));
+        self.networks.insert(network.name.clone(), network);
+        self.save()?;
+
+        iron_broadcast::network_added(chain_id).await;
+        iron_broadcast::ui_notify(UINotify::NetworksUpdated).await;
+
+        Ok(())
+    }
+
+    /// Replaces a single network, which may be renamed
+    /// The same rules apply as when adding one. If it's the current network, it stays selected
+    pub async fn update_network(&mut self, name: &str, network: Network) -> Result<()> {
+        network.validate_rpc_urls()?;
+
+        let previous = self
+            .networks
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::UnknownNetwork(name.into()))?;
+
+        if network.name != name && self.networks.contains_key(&network.name) {
+            return Err(Error::DuplicateNetworkName(network.name));
+        }
+
+        if self
+            .networks
+            .values()
+            .any(|n| n.name != name && n.chain_id == network.chain_id)
+        {
+            return Err(Error::DuplicateChainId(network.chain_id));
+        }
+
+        self.networks.remove(name);
+        Network::invalidate_providers(Some(name));
+        Network::invalidate_providers(Some(&network.name));
+        let chain_id = network.chain_id;
+        self.networks.insert(network.name.clone(), network.clone());
+
+        let was_current = self.current == name;
+        if was_current {
+            self.current = network.name.clone();
+        }
+        self.save()?;
+
+        if previous.chain_id != chain_id {
+            iron_broadcast::network_removed(previous.chain_id).await;
+            iron_broadcast::network_added(chain_id).await;
+            if was_current {
+                self.on_network_changed().await?;
+            }
+        }
+        iron_broadcast::ui_notify(UINotify::NetworksUpdated).await;
+
+        Ok(())
+    }
+
+    /// Registers a chain requested by a dapp through `wallet_addEthereumChain`, optionally
+    /// switching to it
+    /// Requesting a chain that already exists is fine, as long as it uses one of our RPC URLs
+    pub async fn add_ethereum_chain(&mut self, params: AddChainParams, switch: bool) -> Result<()> {
+        let chain_id = params.parsed_chain_id()?;
+
+        match self.get_network(chain_id) {
+            Some(existing) if params.rpc_urls.contains(&existing.http_url) => (),
+            Some(_) => return Err(Error::ConflictingChain(chain_id)),
+            None => self.add_network(params.into_network()?).await?,
+        }
+
+        if switch {
+            self.set_current_by_id(chain_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes a network by name
+    /// The currently selected network cannot be removed
+    pub async fn remove_network(&mut self, name: &str) -> Result<()> {
+        if self.current == name {
+            return Err(Error::CannotRemoveCurrent(name.into()));
+        }
+
+        let removed = self
+            .networks
+            .remove(name)
+            .ok_or_else(|| Error::UnknownNetwork(name.into()))?;
+        Network::invalidate_providers(Some(name));
+        self.save()?;
+
+        iron_broadcast::network_removed(removed.chain_id).await;
+        iron_broadcast::ui_notify(UINotify::NetworksUpdated).await;
+
+        Ok(())
+    }
+
     pub async fn set_networks(&mut self, networks: Vec<Network>) -> Result<()> {
         self.do_set_networks(networks).await
     }
 
+    /// Imports networks from a backup
+    /// Unless `overwrite` is set, they're merged by name into the current list
+    pub async fn import(&mut self, networks: Vec<Network>, overwrite: bool) -> Result<()> {
+        if overwrite {
+            return self.do_set_networks(networks).await;
+        }
+
+        let mut merged: Vec<_> = self
+            .networks
+            .values()
+            .filter(|n| !networks.iter().any(|i| i.name == n.name))
+            .cloned()
+            .collect();
+        merged.extend(networks);
+
+        self.do_set_networks(merged).await
+    }
+
     pub async fn reset_networks(&mut self) -> Result<()> {
         self.do_set_networks(Network::all_default()).await
     }
 
-    pub fn get_current_provider(&self) -> Provider<Http> {
-        self.get_current().get_provider()
+    /// Makes one of a network's RPC URLs its primary one, keeping the others as fallbacks in
+    /// their order, such as when the primary one degrades and the user picks a healthier one
+    pub async fn use_rpc_url(&mut self, name: &str, url: &str) -> Result<()> {
+        let mut network = self
+            .networks
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::UnknownNetwork(name.into()))?;
+
+        let mut urls: Vec<String> = network.rpc_urls().map(String::from).collect();
+        let index = urls
+            .iter()
+            .position(|u| u == url)
+            .ok_or_else(|| Error::UnknownRpcUrl(url.into()))?;
+        network.http_url = urls.remove(index);
+        network.fallback_rpc_urls = urls;
+
+        self.update_network(name, network).await
+    }
+
+    pub fn get_current_provider(&self) -> Result<Provider<Failover>> {
+        self.get_current()?.get_provider()
+    }
+
+    /// The RPC URL that requests for the current network are sent to
+    pub fn active_rpc_url(&self) -> Option<String> {
+        self.networks
+            .get(&self.current)
+            .map(|n| n.active_rpc_url().to_string())
     }
 
     async fn on_network_changed(&self) -> Result<()> {
+        let chain_id = self.get_current()?.chain_id;
+
         // TODO: check domain
-        self.notify_peers();
+        self.notify_peers(chain_id);
         iron_broadcast::ui_notify(UINotify::NetworkChanged).await;
 
-        let chain_id = self.get_current().chain_id;
         iron_broadcast::current_network_changed(chain_id).await;
 
         Ok(())
     }
 
     async fn do_set_networks(&mut self, networks: Vec<Network>) -> Result<()> {
-        let first = networks[0].name.clone();
+        Network::validate_list(&networks)?;
+        let first = networks
+            .first()
+            .map(|n| n.name.clone())
+            .ok_or(Error::EmptyNetworkList)?;
 
         // update networks, keeping track of chain_ids before and after
         let before: HashSet<_> = self.current_chain_ids();
         self.networks = networks.into_iter().map(|n| (n.name.clone(), n)).collect();
+        Network::invalidate_providers(None);
         let after: HashSet<_> = self.current_chain_ids();
 
         tokio::spawn(async move {
@@ -130,14 +325,16 @@ impl Networks {
             self.on_network_changed().await?;
         }
 
-        self.save()
+        self.save()?;
+        iron_broadcast::ui_notify(UINotify::NetworksUpdated).await;
+
+        Ok(())
     }
 
     // broadcasts `accountsChanged` to all peers
-    fn notify_peers(&self) {
-        let current = self.get_current().clone();
+    fn notify_peers(&self, chain_id: u32) {
         tokio::spawn(async move {
-            iron_broadcast::chain_changed(current.chain_id, None, Affinity::Global).await;
+            iron_broadcast::chain_changed(chain_id, None, Affinity::Global).await;
         });
     }
 
@@ -146,13 +343,15 @@ impl Networks {
             iron_broadcast::network_added(network.chain_id).await;
         }
 
-        let chain_id = self.get_current().chain_id;
-        iron_broadcast::current_network_changed(chain_id).await;
+        if let Ok(current) = self.get_current() {
+            iron_broadcast::current_network_changed(current.chain_id).await;
+        }
     }
 
     async fn reset_listeners(&mut self) {
         for network in self.networks.values_mut() {
-            network.reset_listener().await.unwrap();
+            // a misconfigured dev network shouldn't prevent the others from being tracked
+            let _ = network.reset_listener().await;
         }
     }
 
@@ -163,11 +362,7 @@ impl Networks {
     // Persists current state to disk
     fn save(&self) -> Result<()> {
         let pathbuf = self.file.clone();
-        let path = Path::new(&pathbuf);
-        let file = File::create(path)?;
 
-        serde_json::to_writer_pretty(file, self)?;
-
-        Ok(())
+        Ok(iron_types::save_json(Path::new(&pathbuf), self)?)
     }
 }
@@ -1,8 +1,35 @@
-use ethers::providers::{Http, Provider};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::Path,
+    str::FromStr,
+    sync::Mutex,
+    time::Duration,
+};
+
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    types::{Address, BlockId, BlockNumber, H256, U256},
+};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 use url::Url;
 
-use super::Result;
+use super::{
+    failover::{self, RetryPolicy},
+    rollup, Error, Failover, InFlight, Result,
+};
+
+/// Whether each chain supports EIP-1559, by chain ID
+/// This never changes for a given chain, so it only needs to be detected once
+static EIP1559_SUPPORT: Lazy<RwLock<HashMap<u32, bool>>> = Lazy::new(Default::default);
+
+/// Providers for each network, by name
+/// Building a provider creates new HTTP clients, so they're reused across calls, unless the
+/// network's RPC URLs changed since
+static PROVIDERS: Lazy<Mutex<HashMap<String, Provider<Failover>>>> = Lazy::new(Default::default);
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Network {
@@ -13,6 +40,171 @@ pub struct Network {
     pub ws_url: Option<String>,
     pub currency: String,
     pub decimals: u32,
+
+    /// Alternative HTTP endpoints, used in order when `http_url` is unreachable
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+
+    /// Whether transactions on this network should use EIP-1559 fee fields
+    #[serde(default = "default_supports_eip1559")]
+    pub supports_eip1559: bool,
+
+    /// Etherscan-compatible API of this network's explorer
+    /// Chains Etherscan knows of use their own instance when unset
+    #[serde(default)]
+    pub explorer_api_url: Option<String>,
+
+    /// Falls back to the global Etherscan API key when unset
+    #[serde(default)]
+    pub explorer_api_key: Option<String>,
+
+    /// ERC-4337 bundler RPC, which smart account transactions are sent through
+    #[serde(default)]
+    pub bundler_url: Option<String>,
+
+    /// Private relay, such as Flashbots Protect, that transactions can be sent through instead
+    /// of the public mempool, keeping them from being front-run
+    #[serde(default)]
+    pub private_rpc_url: Option<String>,
+
+    /// Whether transactions go through the private relay unless the user opts out on review
+    #[serde(default)]
+    pub private_by_default: bool,
+
+    /// How long a single RPC endpoint is waited on before the next one is tried
+    #[serde(default = "default_rpc_timeout_secs")]
+    pub rpc_timeout_secs: u64,
+
+    /// How many more times a request is retried once every RPC endpoint failed
+    #[serde(default = "default_rpc_retries")]
+    pub rpc_retries: u32,
+
+    /// Wait before the first retry, doubling with each further one
+    #[serde(default = "default_rpc_backoff_ms")]
+    pub rpc_backoff_ms: u64,
+}
+
+/// A chain to add, as requested by a dapp through `wallet_addEthereumChain` (EIP-3085)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddChainParams {
+    /// `0x`-prefixed hex chain ID
+    pub chain_id: String,
+    pub chain_name: String,
+    pub rpc_urls: Vec<String>,
+    pub native_currency: Option<NativeCurrency>,
+    #[serde(default)]
+    pub block_explorer_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+impl AddChainParams {
+    pub fn parsed_chain_id(&self) -> Result<u32> {
+        self.chain_id
+            .strip_prefix("0x")
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| {
+                Error::InvalidChainParams(format!("invalid chain id: {}", self.chain_id))
+            })
+    }
+
+    /// Checks that the first RPC URL actually serves the requested chain
+    /// Otherwise, a dapp could get transactions signed for one chain sent to another
+    pub async fn verify_rpc(&self) -> Result<()> {
+        let expected = self.parsed_chain_id()?;
+        let url = self
+            .rpc_urls
+            .first()
+            .ok_or_else(|| Error::InvalidChainParams("missing rpcUrls".into()))?;
+
+        verify_chain_id(url, expected).await
+    }
+
+    /// Builds the network this request describes, using its first RPC URL
+    /// Any other URLs become fallbacks
+    pub fn into_network(self) -> Result<Network> {
+        let chain_id = self.parsed_chain_id()?;
+        let mut rpc_urls = self.rpc_urls.into_iter();
+        let http_url = rpc_urls
+            .next()
+            .ok_or_else(|| Error::InvalidChainParams("missing rpcUrls".into()))?;
+        let (currency, decimals) = self
+            .native_currency
+            .map(|c| (c.symbol, c.decimals))
+            .unwrap_or_else(|| (String::from("ETH"), 18));
+
+        Ok(Network {
+            name: self.chain_name,
+            chain_id,
+            explorer_url: self.block_explorer_urls.into_iter().next(),
+            http_url,
+            ws_url: None,
+            currency,
+            decimals,
+            fallback_rpc_urls: rpc_urls.collect(),
+            supports_eip1559: true,
+            explorer_api_url: None,
+            explorer_api_key: None,
+            bundler_url: None,
+            private_rpc_url: None,
+            private_by_default: false,
+            rpc_timeout_secs: default_rpc_timeout_secs(),
+            rpc_retries: default_rpc_retries(),
+            rpc_backoff_ms: default_rpc_backoff_ms(),
+        })
+    }
+}
+
+/// How transaction fees are priced on a network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FeeMode {
+    /// `maxFeePerGas` and `maxPriorityFeePerGas`
+    Eip1559,
+
+    /// a single `gasPrice`
+    Legacy,
+}
+
+fn default_supports_eip1559() -> bool {
+    true
+}
+
+pub(crate) fn default_rpc_timeout_secs() -> u64 {
+    30
+}
+
+pub(crate) fn default_rpc_retries() -> u32 {
+    2
+}
+
+pub(crate) fn default_rpc_backoff_ms() -> u64 {
+    500
+}
+
+/// Fetches `eth_chainId` from an RPC URL, failing unless it matches the expected chain
+async fn verify_chain_id(url: &str, expected: u32) -> Result<()> {
+    let provider = Provider::<Http>::try_from(url).map_err(|_| Error::InvalidRpcUrl(url.into()))?;
+    let _in_flight = InFlight::start();
+    let actual = provider
+        .get_chainid()
+        .await
+        .map_err(|_| Error::InvalidRpcUrl(url.into()))?;
+
+    if actual != U256::from(expected) {
+        return Err(Error::RpcChainMismatch {
+            url: url.into(),
+            expected,
+            actual: actual.low_u64(),
+        });
+    }
+
+    Ok(())
 }
 
 impl Network {
@@ -25,6 +217,16 @@ impl Network {
             ws_url: None,
             currency: String::from("ETH"),
             decimals: 18,
+            fallback_rpc_urls: vec![],
+            supports_eip1559: true,
+            explorer_api_url: None,
+            explorer_api_key: None,
+            bundler_url: None,
+            private_rpc_url: Some(String::from("https://rpc.flashbots.net")),
+            private_by_default: false,
+            rpc_timeout_secs: default_rpc_timeout_secs(),
+            rpc_retries: default_rpc_retries(),
+            rpc_backoff_ms: default_rpc_backoff_ms(),
         }
     }
 
@@ -37,6 +239,16 @@ impl Network {
             ws_url: None,
             currency: String::from("ETH"),
             decimals: 18,
+            fallback_rpc_urls: vec![],
+            supports_eip1559: true,
+            explorer_api_url: None,
+            explorer_api_key: None,
+            bundler_url: None,
+            private_rpc_url: None,
+            private_by_default: false,
+            rpc_timeout_secs: default_rpc_timeout_secs(),
+            rpc_retries: default_rpc_retries(),
+            rpc_backoff_ms: default_rpc_backoff_ms(),
         }
     }
 
@@ -49,6 +261,16 @@ impl Network {
             ws_url: None,
             currency: String::from("ETH"),
             decimals: 18,
+            fallback_rpc_urls: vec![],
+            supports_eip1559: true,
+            explorer_api_url: None,
+            explorer_api_key: None,
+            bundler_url: None,
+            private_rpc_url: None,
+            private_by_default: false,
+            rpc_timeout_secs: default_rpc_timeout_secs(),
+            rpc_retries: default_rpc_retries(),
+            rpc_backoff_ms: default_rpc_backoff_ms(),
         }
     }
 
@@ -61,6 +283,16 @@ impl Network {
             ws_url: Some(String::from("ws://localhost:8545")),
             currency: String::from("ETH"),
             decimals: 18,
+            fallback_rpc_urls: vec![],
+            supports_eip1559: false,
+            explorer_api_url: None,
+            explorer_api_key: None,
+            bundler_url: None,
+            private_rpc_url: None,
+            private_by_default: false,
+            rpc_timeout_secs: default_rpc_timeout_secs(),
+            rpc_retries: default_rpc_retries(),
+            rpc_backoff_ms: default_rpc_backoff_ms(),
         }
     }
 
@@ -73,6 +305,123 @@ impl Network {
         ]
     }
 
+    /// Loads a list of networks from a JSON array, so a curated set can be shipped without
+    /// recompiling
+    /// The list must not be empty, and both names and chain IDs must be unique
+    pub fn list_from_file(path: &Path) -> Result<Vec<Self>> {
+        let reader = BufReader::new(File::open(path)?);
+        let networks: Vec<Self> = serde_json::from_reader(reader)?;
+        Self::validate_list(&networks)?;
+
+        Ok(networks)
+    }
+
+    /// Checks a list of networks could replace the current one: it must not be empty, and both
+    /// names and chain IDs must be unique
+    pub(crate) fn validate_list(networks: &[Self]) -> Result<()> {
+        if networks.is_empty() {
+            return Err(Error::EmptyNetworkList);
+        }
+
+        let mut names = HashSet::new();
+        let mut chain_ids = HashSet::new();
+        for network in networks.iter() {
+            if !names.insert(&network.name) {
+                return Err(Error::DuplicateNetworkName(network.name.clone()));
+            }
+            if !chain_ids.insert(network.chain_id) {
+                return Err(Error::DuplicateChainId(network.chain_id));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The networks to start with when none are configured
+    /// `IRON_NETWORKS_FILE` may point to a JSON list of networks. If it's unset, missing, or
+    /// invalid, the built-in ones are used
+    pub fn initial() -> Vec<Self> {
+        let Ok(path) = std::env::var("IRON_NETWORKS_FILE") else {
+            return Self::all_default();
+        };
+        let path = Path::new(&path);
+
+        if !path.exists() {
+            tracing::warn!("{} not found, using default networks", path.display());
+            return Self::all_default();
+        }
+
+        Self::list_from_file(path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring {}: {}", path.display(), e);
+            Self::all_default()
+        })
+    }
+
+    /// The symbol and decimals of this network's native currency
+    pub fn native_currency(&self) -> (String, u32) {
+        (self.currency.clone(), self.decimals)
+    }
+
+    /// Formats an amount of the native currency, given in its smallest unit, e.g. `1.5 MATIC`
+    /// Trailing zeros are dropped, so whole amounts have no decimal point at all
+    pub fn format_amount(&self, amount: U256) -> String {
+        let value = match self.unit() {
+            Some(unit) => {
+                let (whole, fraction) = (amount / unit, amount % unit);
+                if fraction.is_zero() {
+                    whole.to_string()
+                } else {
+                    let fraction = format!("{:0>width$}", fraction, width = self.decimals as usize);
+                    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+                }
+            }
+            None => amount.to_string(),
+        };
+
+        format!("{} {}", value, self.currency)
+    }
+
+    /// Parses a human-readable amount of the native currency, such as `1.5` or `.01`, into its
+    /// smallest unit
+    /// Amounts more precise than the currency's decimals are rejected rather than rounded
+    pub fn parse_amount(&self, amount: &str) -> Result<U256> {
+        let invalid = || Error::InvalidAmount(amount.to_string());
+
+        let trimmed = amount.trim();
+        let (whole, fraction) = trimmed.split_once('.').unwrap_or((trimmed, ""));
+
+        let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty())
+            || !is_digits(whole)
+            || !is_digits(fraction)
+            || fraction.len() > self.decimals as usize
+        {
+            return Err(invalid());
+        }
+
+        let unit = self.unit().ok_or_else(invalid)?;
+        let padded = format!("{:0<width$}", fraction, width = self.decimals as usize);
+        let whole = match whole {
+            "" => U256::zero(),
+            whole => U256::from_dec_str(whole).map_err(|_| invalid())?,
+        };
+        let fraction = match padded.as_str() {
+            "" => U256::zero(),
+            fraction => U256::from_dec_str(fraction).map_err(|_| invalid())?,
+        };
+
+        whole
+            .checked_mul(unit)
+            .and_then(|whole| whole.checked_add(fraction))
+            .ok_or_else(invalid)
+    }
+
+    /// One whole unit of the native currency, in its smallest unit
+    /// `None` if the configured decimals are too large to represent
+    fn unit(&self) -> Option<U256> {
+        U256::from(10).checked_pow(self.decimals.into())
+    }
+
     pub fn chain_id_hex(&self) -> String {
         format!("0x{:x}", self.chain_id)
     }
@@ -81,14 +430,222 @@ impl Network {
         self.chain_id == 31337
     }
 
-    pub fn get_provider(&self) -> Provider<Http> {
-        Provider::<Http>::try_from(self.http_url.clone()).unwrap()
+    /// Link to a transaction on this network's block explorer, if it has one
+    pub fn tx_url(&self, hash: &H256) -> Option<String> {
+        self.explorer_base()
+            .map(|base| format!("{}/tx/0x{:x}", base, hash))
+    }
+
+    /// Link to an address on this network's block explorer, if it has one
+    pub fn address_url(&self, address: &Address) -> Option<String> {
+        self.explorer_base()
+            .map(|base| format!("{}/address/0x{:x}", base, address))
+    }
+
+    /// Link to a block on this network's block explorer, if it has one
+    pub fn block_url(&self, number: u64) -> Option<String> {
+        self.explorer_base()
+            .map(|base| format!("{}/block/{}", base, number))
+    }
+
+    /// Link to whatever a query names on this network's block explorer: an address, a
+    /// transaction hash or a block number
+    pub fn explorer_link(&self, query: &str) -> Option<String> {
+        let query = query.trim();
+        let hex = query.strip_prefix("0x").unwrap_or(query);
+
+        match hex.len() {
+            40 => self.address_url(&Address::from_str(hex).ok()?),
+            64 => self.tx_url(&H256::from_str(hex).ok()?),
+            _ => self.block_url(query.parse().ok()?),
+        }
+    }
+
+    /// The root of the explorer URL
+    /// Built-in networks store a search URL (e.g. `https://etherscan.io/search?q=`), which the UI
+    /// appends queries to, while networks added by dapps usually store just the root
+    pub(crate) fn explorer_base(&self) -> Option<&str> {
+        let url = self.explorer_url.as_deref()?.trim();
+        let url = url.strip_suffix("/search?q=").unwrap_or(url);
+        let url = url.trim_end_matches('/');
+
+        (!url.is_empty()).then_some(url)
+    }
+
+    /// The RPC URL requests for this network are sent to first
+    /// This is the primary one, unless it recently failed and a fallback is still healthy
+    pub fn active_rpc_url(&self) -> &str {
+        failover::order(self.rpc_urls())
+            .first()
+            .and_then(|i| self.rpc_urls().nth(*i))
+            .unwrap_or(&self.http_url)
+    }
+
+    /// The primary RPC URL, followed by all fallbacks
+    pub fn rpc_urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.http_url.as_str())
+            .chain(self.fallback_rpc_urls.iter().map(|u| u.as_str()))
+    }
+
+    /// The private relay transactions can be sent through, if one is configured
+    pub fn private_relay_url(&self) -> Option<&str> {
+        self.private_rpc_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+    }
+
+    /// A provider for the private relay, if one is configured and its URL is valid
+    /// Relays only take transactions and answer for the ones they hold, so everything else still
+    /// goes through `get_provider`
+    pub fn get_private_relay(&self) -> Option<Provider<Http>> {
+        Provider::<Http>::try_from(self.private_relay_url()?).ok()
+    }
+
+    /// Checks that every RPC URL of this network can be connected to
+    pub fn validate_rpc_urls(&self) -> Result<()> {
+        for url in self.rpc_urls() {
+            Provider::<Http>::try_from(url).map_err(|_| Error::InvalidRpcUrl(url.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the primary RPC URL is reachable and serves this network's chain
+    pub async fn verify_rpc(&self) -> Result<()> {
+        verify_chain_id(&self.http_url, self.chain_id).await
+    }
+
+    /// How requests to this network's RPC URLs time out and are retried
+    /// A timeout of zero would fail every request, so at least a second is allowed
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            timeout: Duration::from_secs(self.rpc_timeout_secs.max(1)),
+            retries: self.rpc_retries,
+            backoff: Duration::from_millis(self.rpc_backoff_ms),
+        }
+    }
+
+    /// Gets a provider for this network, reusing a cached one unless its RPC settings changed
+    /// Requests fail over through the network's RPC URLs, in order
+    pub fn get_provider(&self) -> Result<Provider<Failover>> {
+        let mut providers = PROVIDERS.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(provider) = providers.get(&self.name) {
+            let transport = provider.as_ref();
+            if transport.urls().eq(self.rpc_urls()) && transport.policy() == self.retry_policy() {
+                return Ok(provider.clone());
+            }
+        }
+
+        let failover = Failover::new(self.rpc_urls(), self.retry_policy())
+            .ok_or_else(|| Error::InvalidRpcUrl(self.active_rpc_url().to_string()))?;
+        let provider = Provider::new(failover);
+        providers.insert(self.name.clone(), provider.clone());
+
+        Ok(provider)
+    }
+
+    /// Drops cached providers, either for a single network or for all of them
+    /// The health of their RPC URLs is reset as well, so the primary one is tried first again
+    pub(crate) fn invalidate_providers(name: Option<&str>) {
+        let mut providers = PROVIDERS.lock().unwrap_or_else(|e| e.into_inner());
+
+        match name {
+            Some(name) => {
+                if let Some(provider) = providers.remove(name) {
+                    failover::reset_health(provider.as_ref().urls());
+                }
+            }
+            None => {
+                providers.clear();
+                failover::clear_health();
+            }
+        }
+    }
+
+    /// Drops every in-memory cache kept per network
+    /// Providers, RPC URL choices, EIP-1559 support and rollup kinds are all rebuilt lazily on
+    /// next use
+    pub(crate) async fn clear_caches() {
+        Self::invalidate_providers(None);
+        EIP1559_SUPPORT.write().await.clear();
+        rollup::clear().await;
+    }
+
+    /// The fee fields transactions on this network should be built with
+    pub fn fee_mode(&self) -> FeeMode {
+        if self.supports_eip1559 {
+            FeeMode::Eip1559
+        } else {
+            FeeMode::Legacy
+        }
+    }
+
+    /// Detects whether this network supports EIP-1559 fees, by checking the latest block for a
+    /// base fee
+    pub async fn detect_eip1559(&self) -> Result<bool> {
+        if let Some(supported) = EIP1559_SUPPORT.read().await.get(&self.chain_id) {
+            return Ok(*supported);
+        }
+
+        let _in_flight = InFlight::start();
+        let supported = self
+            .get_provider()?
+            .get_block(BlockNumber::Latest)
+            .await?
+            .map(|block| block.base_fee_per_gas.is_some())
+            .unwrap_or(false);
+
+        EIP1559_SUPPORT
+            .write()
+            .await
+            .insert(self.chain_id, supported);
+
+        Ok(supported)
+    }
+
+    /// Reads a raw storage slot of a contract, at the latest block unless one is given
+    pub async fn get_storage_at(
+        &self,
+        address: Address,
+        slot: H256,
+        block: Option<BlockId>,
+    ) -> Result<H256> {
+        let _in_flight = InFlight::start();
+
+        Ok(self
+            .get_provider()?
+            .get_storage_at(address, slot, block)
+            .await?)
+    }
+
+    /// Gets the balance of an address, in the native currency's smallest unit
+    pub async fn get_balance(&self, address: Address) -> Result<U256> {
+        let _in_flight = InFlight::start();
+
+        Ok(self.get_provider()?.get_balance(address, None).await?)
+    }
+
+    pub async fn get_block_number(&self) -> Result<u64> {
+        let _in_flight = InFlight::start();
+
+        Ok(self.get_provider()?.get_block_number().await?.as_u64())
+    }
+
+    /// Whether any of this network's RPC URLs is currently reachable
+    pub async fn check_connection(&self) -> bool {
+        self.get_block_number().await.is_ok()
     }
 
     pub async fn reset_listener(&mut self) -> Result<()> {
         if self.is_dev() {
             let http = Url::parse(&self.http_url)?;
-            let ws = Url::parse(&self.ws_url.clone().unwrap())?;
+            let ws = self
+                .ws_url
+                .as_ref()
+                .ok_or_else(|| Error::MissingWsUrl(self.name.clone()))?;
+            let ws = Url::parse(ws)?;
             iron_broadcast::reset_anvil_listener(self.chain_id, http, ws).await;
         }
 
@@ -101,3 +658,86 @@ impl std::fmt::Display for Network {
         write!(f, "{}-{}", self.chain_id, self.name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_amount_trims_trailing_zeros() {
+        let network = Network::mainnet();
+        let eth = U256::exp10(18);
+
+        assert_eq!(network.format_amount(eth * 3 / 2), "1.5 ETH");
+        assert_eq!(network.format_amount(eth * 2), "2 ETH");
+        assert_eq!(
+            network.format_amount(U256::one()),
+            "0.000000000000000001 ETH"
+        );
+        assert_eq!(network.format_amount(U256::zero()), "0 ETH");
+    }
+
+    #[test]
+    fn explorer_links_follow_the_query() {
+        let network = Network::mainnet();
+        let address = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+        let hash = format!("0x{}", "ab".repeat(32));
+
+        assert_eq!(
+            network.explorer_link(address).unwrap(),
+            "https://etherscan.io/address/0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert_eq!(
+            network.explorer_link(&hash).unwrap(),
+            format!("https://etherscan.io/tx/{}", hash)
+        );
+        assert_eq!(
+            network.explorer_link(" 17000000 ").unwrap(),
+            "https://etherscan.io/block/17000000"
+        );
+        assert!(network.explorer_link("vitalik.eth").is_none());
+        assert!(Network::anvil().explorer_link(address).is_none());
+    }
+
+    #[test]
+    fn amounts_respect_custom_decimals() {
+        let network = Network {
+            currency: "TKN".into(),
+            decimals: 6,
+            ..Network::anvil()
+        };
+
+        assert_eq!(network.format_amount(1_250_000.into()), "1.25 TKN");
+        assert_eq!(network.parse_amount("1.25").unwrap(), U256::from(1_250_000));
+        assert_eq!(network.parse_amount(".000001").unwrap(), U256::one());
+        assert!(network.parse_amount("0.0000001").is_err());
+    }
+
+    #[test]
+    fn parse_amount_rejects_malformed_input() {
+        let network = Network::mainnet();
+
+        for input in ["", ".", "1.2.3", "-1", "1e18", "abc", "1,5"] {
+            assert!(network.parse_amount(input).is_err(), "{:?}", input);
+        }
+
+        assert_eq!(network.parse_amount(" 2 ").unwrap(), U256::exp10(18) * 2);
+        assert_eq!(network.parse_amount("1.").unwrap(), U256::exp10(18));
+    }
+
+    #[test]
+    fn network_lists_need_unique_chain_ids() {
+        let mut renamed = Network::mainnet();
+        renamed.name = "ethereum".into();
+
+        assert!(Network::validate_list(&Network::all_default()).is_ok());
+        assert!(matches!(
+            Network::validate_list(&[Network::mainnet(), renamed]),
+            Err(Error::DuplicateChainId(1))
+        ));
+        assert!(matches!(
+            Network::validate_list(&[]),
+            Err(Error::EmptyNetworkList)
+        ));
+    }
+}
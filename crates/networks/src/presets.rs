@@ -0,0 +1,127 @@
+//! Popular chains that can be added in one click, without looking up their details
+//! Only the default networks are set up from the start. These are offered on top of them
+
+use super::{
+    network::{default_rpc_backoff_ms, default_rpc_retries, default_rpc_timeout_secs},
+    Network,
+};
+
+/// Every preset, including the default networks
+pub fn presets() -> Vec<Network> {
+    let mut presets = Network::all_default();
+    presets.extend([
+        preset(
+            "optimism",
+            10,
+            "https://mainnet.optimism.io",
+            "https://optimistic.etherscan.io",
+            "ETH",
+        ),
+        preset(
+            "bnb",
+            56,
+            "https://bsc-dataseed.bnbchain.org",
+            "https://bscscan.com",
+            "BNB",
+        ),
+        preset(
+            "gnosis",
+            100,
+            "https://rpc.gnosischain.com",
+            "https://gnosisscan.io",
+            "xDAI",
+        ),
+        preset(
+            "polygon",
+            137,
+            "https://polygon-rpc.com",
+            "https://polygonscan.com",
+            "MATIC",
+        ),
+        preset(
+            "base",
+            8453,
+            "https://mainnet.base.org",
+            "https://basescan.org",
+            "ETH",
+        ),
+        preset(
+            "arbitrum",
+            42161,
+            "https://arb1.arbitrum.io/rpc",
+            "https://arbiscan.io",
+            "ETH",
+        ),
+        preset(
+            "avalanche",
+            43114,
+            "https://api.avax.network/ext/bc/C/rpc",
+            "https://snowtrace.io",
+            "AVAX",
+        ),
+        preset(
+            "base-sepolia",
+            84532,
+            "https://sepolia.base.org",
+            "https://sepolia.basescan.org",
+            "ETH",
+        ),
+        preset(
+            "arbitrum-sepolia",
+            421614,
+            "https://sepolia-rollup.arbitrum.io/rpc",
+            "https://sepolia.arbiscan.io",
+            "ETH",
+        ),
+        preset(
+            "optimism-sepolia",
+            11155420,
+            "https://sepolia.optimism.io",
+            "https://sepolia-optimism.etherscan.io",
+            "ETH",
+        ),
+    ]);
+
+    presets
+}
+
+/// A chain with an 18-decimals native currency, and an explorer searchable at `/search?q=`
+fn preset(name: &str, chain_id: u32, http_url: &str, explorer: &str, currency: &str) -> Network {
+    Network {
+        name: name.into(),
+        chain_id,
+        explorer_url: Some(format!("{}/search?q=", explorer)),
+        http_url: http_url.into(),
+        ws_url: None,
+        currency: currency.into(),
+        decimals: 18,
+        fallback_rpc_urls: vec![],
+        supports_eip1559: true,
+        explorer_api_url: None,
+        explorer_api_key: None,
+        bundler_url: None,
+        private_rpc_url: None,
+        private_by_default: false,
+        rpc_timeout_secs: default_rpc_timeout_secs(),
+        rpc_retries: default_rpc_retries(),
+        rpc_backoff_ms: default_rpc_backoff_ms(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn presets_are_unique() {
+        let presets = presets();
+        let names: HashSet<_> = presets.iter().map(|n| &n.name).collect();
+        let chain_ids: HashSet<_> = presets.iter().map(|n| n.chain_id).collect();
+
+        assert_eq!(names.len(), presets.len());
+        assert_eq!(chain_ids.len(), presets.len());
+        assert!(presets.iter().all(|n| n.validate_rpc_urls().is_ok()));
+    }
+}
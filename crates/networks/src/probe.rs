@@ -0,0 +1,291 @@
+//! Measures each configured network's RPC endpoints in the background, so that the UI can show
+//! how healthy the current one is, and suggest another endpoint when it degrades
+//! Endpoints are probed one by one, outside of the failover transport, so that a slow primary
+//! doesn't hide behind a working fallback
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ethers::providers::{Http, Middleware, Provider};
+use iron_types::{GlobalState, UINotify};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use url::Url;
+
+use super::{network::Network, Networks};
+
+/// How often every network is probed
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long an endpoint may take to answer before it's taken as down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Endpoints answering slower than this are degraded
+const SLOW_LATENCY: Duration = Duration::from_secs(2);
+
+/// Endpoints this many blocks behind the most advanced one of their network are degraded
+const MAX_LAG: u64 = 3;
+
+/// Samples kept per endpoint
+const HISTORY_LEN: usize = 20;
+
+/// Recent samples of each endpoint, by URL
+static HISTORY: Lazy<Mutex<HashMap<String, VecDeque<Sample>>>> = Lazy::new(Default::default);
+
+/// A single probe of an endpoint
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    /// Unix timestamp, in seconds
+    pub timestamp: u64,
+
+    /// `None` if the endpoint failed to answer
+    pub latency_ms: Option<u64>,
+    pub block_number: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    Healthy,
+    Degraded,
+    Down,
+
+    /// Not probed yet
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointHealth {
+    pub url: String,
+    pub status: Status,
+    pub latency_ms: Option<u64>,
+    pub block_number: Option<u64>,
+
+    /// Blocks behind the most advanced endpoint of the same network, as of the last probe
+    pub lag: Option<u64>,
+
+    /// Oldest first
+    pub history: Vec<Sample>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkHealth {
+    pub chain_id: u32,
+
+    /// That of the endpoint requests are currently sent to
+    pub status: Status,
+    pub active_url: String,
+    pub endpoints: Vec<EndpointHealth>,
+
+    /// A healthy endpoint to switch to, when the active one isn't
+    pub suggested_url: Option<String>,
+}
+
+/// The health of every configured network, by name
+pub fn network_health(networks: &Networks) -> HashMap<String, NetworkHealth> {
+    let history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+
+    networks
+        .networks
+        .values()
+        .map(|network| (network.name.clone(), health_of(network, &history)))
+        .collect()
+}
+
+/// Probes every network forever, notifying the UI after each round
+pub(crate) async fn run() -> ! {
+    loop {
+        let networks: Vec<Network> = Networks::read().await.networks.values().cloned().collect();
+
+        for network in networks {
+            for url in network.rpc_urls() {
+                let sample = probe(url).await;
+
+                let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+                let samples = history.entry(url.to_string()).or_default();
+                samples.push_back(sample);
+                while samples.len() > HISTORY_LEN {
+                    samples.pop_front();
+                }
+            }
+        }
+
+        iron_broadcast::ui_notify(UINotify::NetworkHealthUpdated).await;
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
+
+async fn probe(url: &str) -> Sample {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let failed = Sample {
+        timestamp,
+        latency_ms: None,
+        block_number: None,
+    };
+
+    let Some(provider) = provider(url) else {
+        return failed;
+    };
+
+    let start = Instant::now();
+    match provider.get_block_number().await {
+        Ok(number) => Sample {
+            timestamp,
+            latency_ms: Some(start.elapsed().as_millis() as u64),
+            block_number: Some(number.as_u64()),
+        },
+        Err(e) => {
+            tracing::debug!("RPC {} failed its health probe: {}", url, e);
+            failed
+        }
+    }
+}
+
+fn provider(url: &str) -> Option<Provider<Http>> {
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .ok()?;
+
+    Some(Provider::new(Http::new_with_client(
+        url.parse::<Url>().ok()?,
+        client,
+    )))
+}
+
+fn health_of(network: &Network, history: &HashMap<String, VecDeque<Sample>>) -> NetworkHealth {
+    let latest = |url: &str| history.get(url).and_then(|s| s.back()).copied();
+    let head = network
+        .rpc_urls()
+        .filter_map(|url| latest(url)?.block_number)
+        .max();
+
+    let endpoints: Vec<_> = network
+        .rpc_urls()
+        .map(|url| {
+            let sample = latest(url);
+            let lag = sample
+                .and_then(|s| s.block_number)
+                .zip(head)
+                .map(|(number, head)| head.saturating_sub(number));
+
+            EndpointHealth {
+                url: url.to_string(),
+                status: status(sample, lag),
+                latency_ms: sample.and_then(|s| s.latency_ms),
+                block_number: sample.and_then(|s| s.block_number),
+                lag,
+                history: history
+                    .get(url)
+                    .map(|s| s.iter().copied().collect())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let active_url = network.active_rpc_url().to_string();
+    let active_status = endpoints
+        .iter()
+        .find(|e| e.url == active_url)
+        .map_or(Status::Unknown, |e| e.status);
+
+    let suggested_url = match active_status {
+        Status::Degraded | Status::Down => endpoints
+            .iter()
+            .filter(|e| e.status == Status::Healthy)
+            .min_by_key(|e| e.latency_ms)
+            .map(|e| e.url.clone()),
+        _ => None,
+    };
+
+    NetworkHealth {
+        chain_id: network.chain_id,
+        status: active_status,
+        active_url,
+        endpoints,
+        suggested_url,
+    }
+}
+
+fn status(sample: Option<Sample>, lag: Option<u64>) -> Status {
+    let Some(sample) = sample else {
+        return Status::Unknown;
+    };
+    let Some(latency) = sample.latency_ms else {
+        return Status::Down;
+    };
+
+    if latency > SLOW_LATENCY.as_millis() as u64 || lag.unwrap_or_default() > MAX_LAG {
+        Status::Degraded
+    } else {
+        Status::Healthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_healthy_endpoints_when_degraded() {
+        let mut network = Network::mainnet();
+        network.http_url = "http://probe-primary.test".into();
+        network.fallback_rpc_urls = vec![
+            "http://probe-slow.test".into(),
+            "http://probe-fast.test".into(),
+        ];
+
+        let sample = |latency_ms, block_number| {
+            VecDeque::from([Sample {
+                timestamp: 0,
+                latency_ms,
+                block_number,
+            }])
+        };
+        let mut history = HashMap::new();
+        history.insert(network.http_url.clone(), sample(Some(100), Some(90)));
+        history.insert(
+            "http://probe-slow.test".into(),
+            sample(Some(3000), Some(100)),
+        );
+        history.insert(
+            "http://probe-fast.test".into(),
+            sample(Some(200), Some(100)),
+        );
+
+        let health = health_of(&network, &history);
+        assert_eq!(health.status, Status::Degraded);
+        assert_eq!(health.endpoints[0].lag, Some(10));
+        assert_eq!(health.endpoints[1].status, Status::Degraded);
+        assert_eq!(
+            health.suggested_url.as_deref(),
+            Some("http://probe-fast.test")
+        );
+
+        history.insert(network.http_url.clone(), sample(Some(100), Some(100)));
+        let health = health_of(&network, &history);
+        assert_eq!(health.status, Status::Healthy);
+        assert_eq!(health.suggested_url, None);
+    }
+
+    #[test]
+    fn unanswered_probes_are_down() {
+        let down = Sample {
+            timestamp: 0,
+            latency_ms: None,
+            block_number: None,
+        };
+
+        assert_eq!(status(None, None), Status::Unknown);
+        assert_eq!(status(Some(down), None), Status::Down);
+    }
+}
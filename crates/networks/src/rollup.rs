@@ -0,0 +1,236 @@
+//! The L1 data fee rollups charge for posting transactions to L1, on top of L2 execution
+//! OP-stack chains charge it separately from gas, as priced by their `GasPriceOracle` predeploy.
+//! Arbitrum folds it into gas estimates instead, but its `NodeInterface` tells how much of them
+//! it makes up
+
+use std::collections::HashMap;
+
+use ethers::{
+    abi::{self, ParamType, Token},
+    providers::{Middleware, ProviderError, RpcError},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use super::{failover, InFlight, Network, Result};
+
+/// Which kind of rollup each chain is, if any, by chain ID
+static ROLLUPS: Lazy<RwLock<HashMap<u32, Option<Rollup>>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Rollup {
+    OpStack,
+    Arbitrum,
+}
+
+/// The L1 component of a transaction's cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L1Fee {
+    pub rollup: Rollup,
+
+    /// In wei
+    pub fee: U256,
+
+    /// Whether gas estimates already cover it, rather than it being charged on top of gas
+    pub included_in_gas: bool,
+}
+
+impl L1Fee {
+    /// What needs paying on top of `gas * gasPrice`
+    pub fn extra(&self) -> U256 {
+        if self.included_in_gas {
+            U256::zero()
+        } else {
+            self.fee
+        }
+    }
+}
+
+/// OP-stack predeploy pricing the L1 data fee
+fn op_gas_price_oracle() -> Address {
+    "0x420000000000000000000000000000000000000F"
+        .parse()
+        .unwrap()
+}
+
+/// Arbitrum precompile with system info, only there to tell Arbitrum apart
+fn arb_sys() -> Address {
+    Address::from_low_u64_be(0x64)
+}
+
+/// Arbitrum's virtual contract for estimates, only reachable through `eth_call`
+fn arb_node_interface() -> Address {
+    Address::from_low_u64_be(0xc8)
+}
+
+impl Network {
+    /// Detects which kind of rollup this network is, if any, by probing for its system contracts
+    /// Local dev chains are never taken as rollups, even when forked from one. Only definitive
+    /// answers are cached, so a probe that failed on the way to the node is tried again next time
+    pub async fn detect_rollup(&self) -> Result<Option<Rollup>> {
+        if self.is_dev() {
+            return Ok(None);
+        }
+        if let Some(rollup) = ROLLUPS.read().await.get(&self.chain_id) {
+            return Ok(*rollup);
+        }
+
+        let provider = self.get_provider()?;
+        let _in_flight = InFlight::start();
+
+        let oracle = provider.get_code(op_gas_price_oracle(), None).await?;
+        let rollup = if !oracle.is_empty() {
+            Some(Rollup::OpStack)
+        } else {
+            let arb_chain_id = call(arb_sys(), encode("arbChainID()", &[]));
+            match provider.call(&arb_chain_id, None).await {
+                Ok(out) => (out.len() == 32).then_some(Rollup::Arbitrum),
+                Err(e) if is_node_answer(&e) => None,
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        ROLLUPS.write().await.insert(self.chain_id, rollup);
+        Ok(rollup)
+    }
+
+    /// The L1 data fee of a transaction, on rollups that charge one
+    pub async fn estimate_l1_fee(&self, tx: &TypedTransaction) -> Result<Option<L1Fee>> {
+        let Some(rollup) = self.detect_rollup().await? else {
+            return Ok(None);
+        };
+
+        let provider = self.get_provider()?;
+        let _in_flight = InFlight::start();
+
+        let fee = match rollup {
+            // priced from the unsigned transaction, which the oracle pads for the signature
+            Rollup::OpStack => {
+                let data = encode("getL1Fee(bytes)", &[Token::Bytes(tx.rlp().to_vec())]);
+                let output = provider
+                    .call(&call(op_gas_price_oracle(), data), None)
+                    .await?;
+
+                abi::decode(&[ParamType::Uint(256)], &output)
+                    .ok()
+                    .and_then(|mut tokens| tokens.pop()?.into_uint())
+            }
+            Rollup::Arbitrum => {
+                let to = tx.to().and_then(|to| to.as_address()).copied();
+                let data = encode(
+                    "gasEstimateL1Component(address,bool,bytes)",
+                    &[
+                        Token::Address(to.unwrap_or_default()),
+                        Token::Bool(to.is_none()),
+                        Token::Bytes(tx.data().map(|d| d.to_vec()).unwrap_or_default()),
+                    ],
+                );
+                let mut request = call(arb_node_interface(), data);
+                if let Some(from) = tx.from() {
+                    request.set_from(*from);
+                }
+                let output = provider.call(&request, None).await?;
+
+                arbitrum_l1_fee(&output)
+            }
+        };
+
+        Ok(fee.map(|fee| L1Fee {
+            rollup,
+            fee,
+            included_in_gas: rollup == Rollup::Arbitrum,
+        }))
+    }
+}
+
+/// Forgets which chains are rollups
+pub(crate) async fn clear() {
+    ROLLUPS.write().await.clear();
+}
+
+/// Whether the node itself answered with an error, e.g. a revert for a precompile that isn't
+/// there. Anything else, such as a timeout or a rate limit, says nothing about the chain
+fn is_node_answer(error: &ProviderError) -> bool {
+    match error {
+        ProviderError::JsonRpcClientError(e) => e
+            .as_error_response()
+            .is_some_and(|e| !failover::is_rate_limit(e)),
+        _ => false,
+    }
+}
+
+fn call(to: Address, data: Bytes) -> TypedTransaction {
+    TransactionRequest::new().to(to).data(data).into()
+}
+
+fn encode(signature: &str, args: &[Token]) -> Bytes {
+    [&id(signature)[..], &abi::encode(args)].concat().into()
+}
+
+/// `gasEstimateL1Component` returns the L1 component in L2 gas, along with the L2 base fee that
+/// gas is priced at
+fn arbitrum_l1_fee(output: &[u8]) -> Option<U256> {
+    let types = [
+        ParamType::Uint(64),
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+    ];
+    let mut tokens = abi::decode(&types, output).ok()?.into_iter();
+
+    let gas = tokens.next()?.into_uint()?;
+    let base_fee = tokens.next()?.into_uint()?;
+    Some(gas.saturating_mul(base_fee))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_arbitrum_l1_gas_at_the_base_fee() {
+        let output = abi::encode(&[
+            Token::Uint(1_500.into()),
+            Token::Uint(100_000_000.into()),
+            Token::Uint(30_000_000_000u64.into()),
+        ]);
+
+        assert_eq!(
+            arbitrum_l1_fee(&output),
+            Some(U256::from(150_000_000_000u64))
+        );
+        assert_eq!(arbitrum_l1_fee(&[]), None);
+
+        let fee = L1Fee {
+            rollup: Rollup::Arbitrum,
+            fee: 1.into(),
+            included_in_gas: true,
+        };
+        assert!(fee.extra().is_zero());
+    }
+
+    #[test]
+    fn only_node_errors_rule_out_arbitrum() {
+        use ethers::providers::{HttpClientError, JsonRpcError};
+
+        let answer = |code, message: &str| {
+            ProviderError::JsonRpcClientError(Box::new(HttpClientError::JsonRpcError(
+                JsonRpcError {
+                    code,
+                    message: message.into(),
+                    data: None,
+                },
+            )))
+        };
+
+        assert!(is_node_answer(&answer(3, "execution reverted")));
+        assert!(!is_node_answer(&answer(429, "too many requests")));
+        assert!(!is_node_answer(&ProviderError::CustomError(
+            "timeout".into()
+        )));
+    }
+}
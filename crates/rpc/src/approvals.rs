@@ -0,0 +1,321 @@
+//! Audits the token approvals an account has handed out, so that stale ones can be revoked
+//! Approvals are found through their events, then checked against the contracts, since most of
+//! them were since spent, overwritten or revoked
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use ethers::{
+    abi::AbiEncode,
+    prelude::*,
+    utils::{keccak256, to_checksum},
+};
+use iron_abis::{ierc20, ierc721, IERC20, IERC721};
+use iron_connections::Ctx;
+use iron_networks::{Failover, InFlight, Network};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Handler, Result};
+
+/// Shared by ERC-20 and ERC-721, which tell them apart by what's indexed
+const APPROVAL: &str = "Approval(address,address,uint256)";
+
+const APPROVAL_FOR_ALL: &str = "ApprovalForAll(address,address,bool)";
+
+/// The smallest block range a failing log query is split down to
+/// Providers cap the range, or the number of results, of a single query
+const MIN_RANGE: u64 = 1_000;
+
+/// What a spender was approved for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Grant {
+    /// An amount of an ERC-20 token
+    Erc20 { amount: U256 },
+
+    /// A single ERC-721 token
+    Erc721 {
+        #[serde(rename = "tokenId")]
+        token_id: U256,
+    },
+
+    /// Every ERC-721 token of a collection, now and in the future
+    Operator,
+}
+
+/// An approval that still stands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Allowance {
+    pub token: Address,
+    pub spender: Address,
+
+    #[serde(flatten)]
+    pub grant: Grant,
+
+    /// Left out by tokens that don't implement it
+    #[serde(default)]
+    pub symbol: Option<String>,
+
+    /// Only known for ERC-20 tokens
+    #[serde(default)]
+    pub decimals: Option<u8>,
+}
+
+/// An approval found in the logs, which may not stand anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Candidate {
+    Erc20 { token: Address, spender: Address },
+    Erc721 { token: Address, token_id: U256 },
+    Operator { token: Address, operator: Address },
+}
+
+/// Every approval of an owner that still stands on a network
+pub(crate) async fn scan(network: &Network, owner: Address) -> Result<Vec<Allowance>> {
+    let client = Arc::new(network.get_provider()?);
+    let _in_flight = InFlight::start();
+
+    let head = client.get_block_number().await?.as_u64();
+    let filter = Filter::new()
+        .events([APPROVAL, APPROVAL_FOR_ALL])
+        .topic1(H256::from(owner));
+
+    // later approvals of the same spender overwrite earlier ones, so each is checked only once
+    let candidates: BTreeSet<Candidate> = logs(&client, filter, head)
+        .await?
+        .iter()
+        .filter_map(candidate)
+        .collect();
+
+    let mut metadata = HashMap::new();
+    let mut allowances = vec![];
+    for candidate in candidates {
+        let Some((token, spender, grant)) = check(&client, owner, candidate).await else {
+            continue;
+        };
+
+        if !metadata.contains_key(&token) {
+            metadata.insert(token, fetch_metadata(&client, token, &grant).await);
+        }
+        let (symbol, decimals) = metadata[&token].clone();
+
+        allowances.push(Allowance {
+            token,
+            spender,
+            grant,
+            symbol,
+            decimals,
+        });
+    }
+
+    Ok(allowances)
+}
+
+/// Sends a transaction from the owner revoking an approval
+/// It goes through the same review as any other transaction
+pub(crate) async fn revoke(owner: Address, allowance: &Allowance) -> Result<H256> {
+    let data = match allowance.grant {
+        Grant::Erc20 { .. } => ierc20::ApproveCall {
+            spender: allowance.spender,
+            value: U256::zero(),
+        }
+        .encode(),
+        Grant::Erc721 { token_id } => ierc721::ApproveCall {
+            to: Address::zero(),
+            token_id,
+        }
+        .encode(),
+        Grant::Operator => ierc721::SetApprovalForAllCall {
+            operator: allowance.spender,
+            approved: false,
+        }
+        .encode(),
+    };
+
+    let params = json!({
+        "from": to_checksum(&owner, None),
+        "to": to_checksum(&allowance.token, None),
+        "data": Bytes::from(data),
+    });
+
+    Handler::send_transaction(params, Ctx::empty()).await
+}
+
+/// Logs matching a filter from genesis up to the given block
+/// Queries the provider refuses are halved until they go through
+async fn logs(client: &Provider<Failover>, filter: Filter, head: u64) -> Result<Vec<Log>> {
+    let mut ranges = vec![(0, head)];
+    let mut logs = vec![];
+
+    while let Some((from, to)) = ranges.pop() {
+        let query = filter.clone().from_block(from).to_block(to);
+
+        match client.get_logs(&query).await {
+            Ok(found) => logs.extend(found),
+            Err(_) if to - from > MIN_RANGE => {
+                let mid = from + (to - from) / 2;
+                ranges.push((mid + 1, to));
+                ranges.push((from, mid));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(logs)
+}
+
+/// The approval a log records, if it's one
+fn candidate(log: &Log) -> Option<Candidate> {
+    let topic = *log.topics.first()?;
+    let token = log.address;
+
+    if topic == H256::from(keccak256(APPROVAL_FOR_ALL)) {
+        return match log.topics.len() {
+            3 => Some(Candidate::Operator {
+                token,
+                operator: Address::from(log.topics[2]),
+            }),
+            _ => None,
+        };
+    }
+
+    if topic != H256::from(keccak256(APPROVAL)) {
+        return None;
+    }
+
+    // ERC-721 indexes the token ID as a 4th topic, where ERC-20 has the amount as data
+    match log.topics.len() {
+        3 if log.data.len() == 32 => Some(Candidate::Erc20 {
+            token,
+            spender: Address::from(log.topics[2]),
+        }),
+        4 => Some(Candidate::Erc721 {
+            token,
+            token_id: U256::from_big_endian(log.topics[3].as_bytes()),
+        }),
+        _ => None,
+    }
+}
+
+/// Whether an approval still stands, as token, spender and grant
+/// Contracts that fail to answer are taken as not granting anything
+async fn check(
+    client: &Arc<Provider<Failover>>,
+    owner: Address,
+    candidate: Candidate,
+) -> Option<(Address, Address, Grant)> {
+    match candidate {
+        Candidate::Erc20 { token, spender } => {
+            let amount = IERC20::new(token, client.clone())
+                .allowance(owner, spender)
+                .call()
+                .await
+                .ok()?;
+
+            (!amount.is_zero()).then_some((token, spender, Grant::Erc20 { amount }))
+        }
+
+        // the token may have changed hands since, taking its approval along
+        Candidate::Erc721 { token, token_id } => {
+            let contract = IERC721::new(token, client.clone());
+            if contract.owner_of(token_id).call().await.ok()? != owner {
+                return None;
+            }
+            let spender = contract.get_approved(token_id).call().await.ok()?;
+
+            (!spender.is_zero()).then_some((token, spender, Grant::Erc721 { token_id }))
+        }
+
+        Candidate::Operator { token, operator } => {
+            let approved = IERC721::new(token, client.clone())
+                .is_approved_for_all(owner, operator)
+                .call()
+                .await
+                .ok()?;
+
+            approved.then_some((token, operator, Grant::Operator))
+        }
+    }
+}
+
+/// Symbol and decimals of a token, for displaying amounts
+/// ERC-721 collections share the symbol selector with ERC-20, but have no decimals
+async fn fetch_metadata(
+    client: &Arc<Provider<Failover>>,
+    token: Address,
+    grant: &Grant,
+) -> (Option<String>, Option<u8>) {
+    let contract = IERC20::new(token, client.clone());
+    let symbol = contract.symbol().call().await.ok();
+    let decimals = match grant {
+        Grant::Erc20 { .. } => contract.decimals().call().await.ok(),
+        _ => None,
+    };
+
+    (symbol, decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(event: &str, topics: &[H256], data: Vec<u8>) -> Log {
+        let mut all = vec![H256::from(keccak256(event))];
+        all.extend_from_slice(topics);
+
+        Log {
+            address: Address::from_low_u64_be(1),
+            topics: all,
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tells_approvals_apart() {
+        let owner = H256::from(Address::from_low_u64_be(2));
+        let spender = H256::from(Address::from_low_u64_be(3));
+        let token = Address::from_low_u64_be(1);
+
+        let erc20 = log(APPROVAL, &[owner, spender], vec![0; 32]);
+        assert_eq!(
+            candidate(&erc20),
+            Some(Candidate::Erc20 {
+                token,
+                spender: Address::from_low_u64_be(3)
+            })
+        );
+
+        let erc721 = log(
+            APPROVAL,
+            &[owner, spender, H256::from_low_u64_be(7)],
+            vec![],
+        );
+        assert_eq!(
+            candidate(&erc721),
+            Some(Candidate::Erc721 {
+                token,
+                token_id: 7.into()
+            })
+        );
+
+        let operator = log(APPROVAL_FOR_ALL, &[owner, spender], vec![0; 32]);
+        assert_eq!(
+            candidate(&operator),
+            Some(Candidate::Operator {
+                token,
+                operator: Address::from_low_u64_be(3)
+            })
+        );
+
+        let transfer = log(
+            "Transfer(address,address,uint256)",
+            &[owner, spender],
+            vec![0; 32],
+        );
+        assert_eq!(candidate(&transfer), None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ethers::{
+    types::{Signature, H256},
+    utils::keccak256,
+};
+use iron_db::DB;
+use iron_types::{AuditEntry, SignedMessage, SigningKind, SigningOutcome};
+use iron_wallets::{Wallet, WalletControl};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use super::{Error, Result};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+}
+
+/// A signing request awaiting its outcome, to be appended to the audit log
+pub(crate) struct Attempt {
+    entry: AuditEntry,
+}
+
+impl Attempt {
+    pub async fn new<T: Serialize>(
+        wallet: &Wallet,
+        wallet_path: &str,
+        chain_id: u32,
+        kind: SigningKind,
+        payload: &T,
+    ) -> Self {
+        let address = wallet
+            .get_address_for_path(wallet_path)
+            .await
+            .map(Into::into)
+            .unwrap_or_default();
+
+        let payload_hash = serde_json::to_vec(payload)
+            .map(|bytes| H256::from(keccak256(bytes)))
+            .unwrap_or_default();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            entry: AuditEntry {
+                timestamp,
+                address,
+                chain_id,
+                kind,
+                payload_hash,
+                outcome: SigningOutcome::Failed,
+            },
+        }
+    }
+
+    /// Adds a produced signature to the signed message log
+    pub async fn record_signed(&self, method: &str, origin: Option<String>, signature: &Signature) {
+        let message = SignedMessage {
+            timestamp: self.entry.timestamp,
+            address: self.entry.address,
+            chain_id: self.entry.chain_id,
+            origin,
+            method: method.to_string(),
+            payload_hash: self.entry.payload_hash,
+            signature: format!("0x{}", signature),
+        };
+
+        let Some(db) = DB.get() else {
+            tracing::warn!("audit log not initialized, dropping signed message");
+            return;
+        };
+
+        if let Err(e) = db.insert_signed_message(&message).await {
+            tracing::warn!("Failed to write signed message log entry: {}", e);
+        }
+    }
+
+    /// Records the attempt, whether it succeeded or not
+    pub async fn record<T>(mut self, result: &Result<T>) {
+        self.entry.outcome = match result {
+            Ok(_) => SigningOutcome::Signed,
+            Err(Error::TxDialogRejected) | Err(Error::SignatureRejected) => {
+                SigningOutcome::Rejected
+            }
+            Err(_) => SigningOutcome::Failed,
+        };
+
+        let Some(db) = DB.get() else {
+            tracing::warn!("audit log not initialized, dropping entry");
+            return;
+        };
+
+        if let Err(e) = db.insert_audit_entry(&self.entry).await {
+            tracing::warn!("Failed to write audit log entry: {}", e);
+        }
+    }
+}
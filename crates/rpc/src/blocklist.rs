@@ -0,0 +1,306 @@
+//! Known phishing sites and malicious addresses, from lists the user can configure in settings
+//! Lists are fetched on startup and every few hours after, and cached in the database so that
+//! origins and recipients are still checked while offline. Entries the user adds themselves
+//! apply right away
+
+use std::{collections::HashSet, str::FromStr, sync::RwLock};
+
+use ethers::{
+    providers::Middleware, types::transaction::eip2718::TypedTransaction, types::Address,
+};
+use iron_db::DB;
+use iron_networks::Network;
+use iron_settings::Settings;
+use iron_types::{GlobalState, Json, RequestSource};
+use once_cell::sync::{Lazy, OnceCell};
+use tokio::time::{sleep, Duration};
+use url::Url;
+
+use super::{selectors, Error, Result, Warning};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+static BLOCKLIST: Lazy<RwLock<Blocklist>> = Lazy::new(Default::default);
+
+/// How often the configured lists are fetched again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// How long fetching a single list may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct Blocklist {
+    domains: HashSet<String>,
+    addresses: HashSet<Address>,
+}
+
+impl Blocklist {
+    /// Entries that parse as addresses are addresses, and anything else a domain
+    fn from_entries<'a>(entries: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut blocklist = Self::default();
+
+        for entry in entries {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            match Address::from_str(entry) {
+                Ok(address) => blocklist.addresses.insert(address),
+                Err(_) => blocklist.domains.insert(entry.to_lowercase()),
+            };
+        }
+
+        blocklist
+    }
+
+    /// Subdomains of a blocked domain are blocked too
+    fn has_origin(&self, origin: &str) -> bool {
+        let Some(host) = host(origin) else {
+            return false;
+        };
+
+        let mut domain = host.as_str();
+        loop {
+            if self.domains.contains(domain) {
+                return true;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => domain = parent,
+                _ => return false,
+            }
+        }
+    }
+}
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+    tokio::spawn(async { refresher().await });
+}
+
+fn db() -> Result<&'static DB> {
+    DB.get().ok_or(Error::NotInitialized("blocklist"))
+}
+
+/// Whether an origin, or a domain it's under, is known for phishing
+pub(crate) async fn is_phishing_origin(origin: &str) -> bool {
+    let custom = Settings::read().await.get().custom_blocklist.clone();
+
+    Blocklist::from_entries(custom.iter().map(String::as_str)).has_origin(origin)
+        || BLOCKLIST
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .has_origin(origin)
+}
+
+/// Whether an address is known to belong to scammers or drainers
+pub(crate) async fn is_malicious(address: Address) -> bool {
+    let custom = Settings::read().await.get().custom_blocklist.clone();
+
+    Blocklist::from_entries(custom.iter().map(String::as_str))
+        .addresses
+        .contains(&address)
+        || BLOCKLIST
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .addresses
+            .contains(&address)
+}
+
+/// Warnings about who's asking for a transaction, and who it's sent to
+/// A contract is flagged as unverified only when the explorer says so, and only the first time
+/// the account interacts with it
+pub(crate) async fn check(
+    request: &TypedTransaction,
+    network: &Network,
+    source: Option<&RequestSource>,
+) -> Vec<Warning> {
+    let mut warnings = vec![];
+
+    if let Some(source) = source {
+        if is_phishing_origin(&source.origin).await {
+            warnings.push(Warning::PhishingOrigin {
+                origin: source.origin.clone(),
+            });
+        }
+    }
+
+    let Some(to) = request.to().and_then(|to| to.as_address()).copied() else {
+        return warnings;
+    };
+
+    if is_malicious(to).await {
+        warnings.push(Warning::MaliciousAddress { address: to });
+    }
+
+    if let Some(from) = request.from().copied() {
+        match is_unverified_first_interaction(network, from, to).await {
+            Ok(true) => warnings.push(Warning::UnverifiedContract { address: to }),
+            Ok(false) => (),
+            Err(e) => tracing::debug!("Failed to check whether {:?} is verified: {}", to, e),
+        }
+    }
+
+    warnings
+}
+
+async fn is_unverified_first_interaction(
+    network: &Network,
+    from: Address,
+    to: Address,
+) -> Result<bool> {
+    if db()?.has_sent_to(network.chain_id, from, to).await? {
+        return Ok(false);
+    }
+
+    // contracts the wallet has an ABI for were registered or compiled locally
+    if selectors::known_abi(network.chain_id, to).await.is_some() {
+        return Ok(false);
+    }
+
+    let code = network.get_provider()?.get_code(to, None).await?;
+    if code.is_empty() {
+        return Ok(false);
+    }
+
+    let explorer = network.explorer().await?;
+    Ok(explorer.verified_contract(to).await?.is_none())
+}
+
+/// Loads the cached lists, then keeps them fresh
+async fn refresher() {
+    match db() {
+        Ok(db) => match db.get_blocklist().await {
+            Ok(entries) => set(&entries),
+            Err(e) => tracing::warn!("Failed to load the cached blocklist: {}", e),
+        },
+        Err(_) => return,
+    }
+
+    loop {
+        if let Err(e) = refresh().await {
+            tracing::warn!("Failed to refresh the blocklist: {}", e);
+        }
+        sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+/// Fetches every configured list, keeping the cached ones if none could be fetched
+async fn refresh() -> Result<()> {
+    let urls = Settings::read().await.get().blocklist_urls.clone();
+    let client = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+
+    let mut entries = vec![];
+    let mut fetched = false;
+    for url in urls.iter().map(|u| u.trim()).filter(|u| !u.is_empty()) {
+        match fetch(&client, url).await {
+            Ok(list) => {
+                entries.extend(list);
+                fetched = true;
+            }
+            Err(e) => tracing::warn!("Failed to fetch blocklist {}: {}", url, e),
+        }
+    }
+
+    if fetched {
+        db()?.replace_blocklist(&entries).await?;
+        set(&entries);
+    }
+
+    Ok(())
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<Vec<String>> {
+    let body = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    Ok(parse(&body))
+}
+
+fn set(entries: &[String]) {
+    let blocklist = Blocklist::from_entries(entries.iter().map(String::as_str));
+    *BLOCKLIST.write().unwrap_or_else(|e| e.into_inner()) = blocklist;
+}
+
+/// Reads a JSON array of entries, the blocked entries of a JSON object such as MetaMask's
+/// `eth-phishing-detect` config, or plain text with one entry per line
+fn parse(body: &str) -> Vec<String> {
+    let strings = |value: &Json| -> Vec<String> {
+        value
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|e| e.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    match serde_json::from_str::<Json>(body) {
+        Ok(value @ Json::Array(_)) => strings(&value),
+        Ok(Json::Object(object)) => ["blacklist", "blocklist", "domains", "addresses"]
+            .iter()
+            .filter_map(|key| object.get(*key))
+            .flat_map(strings)
+            .collect(),
+        Ok(_) => vec![],
+        Err(_) => body
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect(),
+    }
+}
+
+/// The lowercase host of an origin, which may be a full URL or a bare domain
+fn host(origin: &str) -> Option<String> {
+    match Url::parse(origin) {
+        Ok(url) => url.host_str().map(str::to_lowercase),
+        Err(_) => {
+            Some(origin.trim().trim_end_matches('/').to_lowercase()).filter(|h| !h.is_empty())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_blocked_domains_and_their_subdomains() {
+        let blocklist = Blocklist::from_entries([
+            "uniswap-airdrop.com",
+            "0x000000000000000000000000000000000000dEaD",
+        ]);
+
+        assert!(blocklist.has_origin("https://uniswap-airdrop.com"));
+        assert!(blocklist.has_origin("https://claim.Uniswap-Airdrop.com/path"));
+        assert!(blocklist.has_origin("uniswap-airdrop.com"));
+        assert!(!blocklist.has_origin("https://app.uniswap.org"));
+        assert!(!blocklist.has_origin("https://airdrop.com"));
+
+        assert!(blocklist
+            .addresses
+            .contains(&Address::from_low_u64_be(0xdead)));
+    }
+
+    #[test]
+    fn parses_list_formats() {
+        assert_eq!(parse(r#"["a.com", "b.com"]"#), vec!["a.com", "b.com"]);
+        assert_eq!(
+            parse(r#"{"version": 2, "whitelist": ["ok.com"], "blacklist": ["bad.com"]}"#),
+            vec!["bad.com"]
+        );
+        assert_eq!(
+            parse("# comment\nbad.com\n\n  worse.com  \n"),
+            vec!["bad.com", "worse.com"]
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ethers::providers::{Middleware, Provider};
+use iron_connections::Ctx;
+use iron_networks::{Failover, InFlight, Network};
+use jsonrpc_core::{ErrorCode, Params};
+use once_cell::sync::Lazy;
+use serde_json::Value as Json;
+
+use super::{error, Error};
+
+/// How long the latest block number is trusted for before asking the network again
+/// Block-scoped entries are only as fresh as this
+const BLOCK_NUMBER_TTL: Duration = Duration::from_secs(2);
+
+/// Blocks this far behind the head are assumed safe from reorgs, so reads pinned to them can be
+/// kept for good
+const REORG_DEPTH: u64 = 64;
+
+/// Entries kept before the cache is flushed, so a busy dapp can't grow it without bound
+const MAX_ENTRIES: usize = 10_000;
+
+/// Requests each origin may send upstream per second, on average, and in a single burst
+const RATE_LIMIT: f64 = 20.0;
+const BURST: f64 = 100.0;
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(Default::default);
+static LIMITS: Lazy<Mutex<HashMap<String, Bucket>>> = Lazy::new(Default::default);
+
+/// How long a response can be reused for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    /// Until the network moves on to another block
+    Block,
+
+    /// For good, since it's pinned to a block hash or a block deep enough in the past
+    Permanent,
+
+    Never,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    /// The latest block of each network, by chain ID, along with when it was fetched
+    heads: HashMap<u32, (u64, Instant)>,
+
+    /// Responses by chain ID, method and params, along with the block they're scoped to
+    /// Permanent entries have no block
+    entries: HashMap<(u32, String, String), (Json, Option<u64>)>,
+}
+
+/// A token bucket, refilled at `RATE_LIMIT` tokens per second
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Forwards a request to the network of a connection, answering from the cache when possible
+/// Requests that do reach the network are rate limited per origin. The app's own are not
+pub(crate) async fn request(ctx: &Ctx, method: &str, params: Params) -> jsonrpc_core::Result<Json> {
+    let network = ctx.network().await.map_err(Error::from)?;
+    let provider = network.get_provider().map_err(Error::from)?;
+    let params: Json = params.into();
+
+    if method == "eth_blockNumber" {
+        return Ok(format!("0x{:x}", head(&network, &provider).await?).into());
+    }
+
+    // dev networks change state without new blocks, through cheatcodes and resets
+    let scope = match network.is_dev() {
+        true => Scope::Never,
+        false => match block_param(method, &params) {
+            BlockParam::Unsupported => Scope::Never,
+            BlockParam::Hash => Scope::Permanent,
+            BlockParam::Latest => Scope::Block,
+            BlockParam::Number(n) => match head(&network, &provider).await? {
+                head if head.saturating_sub(n) >= REORG_DEPTH => Scope::Permanent,
+                _ => Scope::Block,
+            },
+        },
+    };
+
+    let key = (network.chain_id, method.to_string(), params.to_string());
+    let block = match scope {
+        Scope::Block => Some(head(&network, &provider).await?),
+        _ => None,
+    };
+    if scope != Scope::Never {
+        if let Some(hit) = CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key, block)
+        {
+            return Ok(hit);
+        }
+    }
+
+    if let Some(ref source) = ctx.source {
+        check_rate_limit(&source.origin)?;
+    }
+
+    let _in_flight = InFlight::start();
+    let res: Json = provider
+        .request(method, params)
+        .await
+        .map_err(error::ethers_to_jsonrpc_error)?;
+
+    match (scope, method) {
+        (_, "eth_sendRawTransaction") => invalidate(network.chain_id),
+
+        // not found yet, or still pending, so it will change
+        (_, "eth_getTransactionByHash" | "eth_getTransactionReceipt")
+            if res.is_null() || res["blockHash"].is_null() => {}
+        (_, "eth_getTransactionByHash" | "eth_getTransactionReceipt") => CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, res.clone(), None),
+        (Scope::Never, _) => {}
+        // e.g. a block that isn't known yet, which might just not have reached this node
+        (Scope::Permanent, _) if res.is_null() => {}
+        (_, _) => CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, res.clone(), block),
+    }
+
+    Ok(res)
+}
+
+/// Drops every entry scoped to the current block of a network, along with its block number
+/// Called after sending a transaction, since balances and nonces read before it are now stale
+pub(crate) fn invalidate(chain_id: u32) {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+
+    cache.heads.remove(&chain_id);
+    cache
+        .entries
+        .retain(|(c, _, _), (_, block)| *c != chain_id || block.is_none());
+}
+
+/// The latest block of a network, fetched at most once per `BLOCK_NUMBER_TTL`
+async fn head(network: &Network, provider: &Provider<Failover>) -> jsonrpc_core::Result<u64> {
+    if let Some((block, at)) = CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .heads
+        .get(&network.chain_id)
+    {
+        if at.elapsed() < BLOCK_NUMBER_TTL {
+            return Ok(*block);
+        }
+    }
+
+    let _in_flight = InFlight::start();
+    let block = provider
+        .get_block_number()
+        .await
+        .map_err(error::ethers_to_jsonrpc_error)?
+        .as_u64();
+
+    CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .heads
+        .insert(network.chain_id, (block, Instant::now()));
+
+    Ok(block)
+}
+
+impl Cache {
+    fn get(&self, key: &(u32, String, String), block: Option<u64>) -> Option<Json> {
+        match self.entries.get(key)? {
+            (value, None) => Some(value.clone()),
+            (value, scoped) if *scoped == block => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, key: (u32, String, String), value: Json, block: Option<u64>) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.clear();
+        }
+        self.entries.insert(key, (value, block));
+    }
+}
+
+fn check_rate_limit(origin: &str) -> jsonrpc_core::Result<()> {
+    let mut limits = LIMITS.lock().unwrap_or_else(|e| e.into_inner());
+    let bucket = limits.entry(origin.to_string()).or_insert_with(|| Bucket {
+        tokens: BURST,
+        refilled_at: Instant::now(),
+    });
+
+    let refill = bucket.refilled_at.elapsed().as_secs_f64() * RATE_LIMIT;
+    bucket.tokens = (bucket.tokens + refill).min(BURST);
+    bucket.refilled_at = Instant::now();
+
+    if bucket.tokens < 1.0 {
+        return Err(jsonrpc_core::Error {
+            code: ErrorCode::ServerError(-32005),
+            data: None,
+            message: String::from("rate limit exceeded"),
+        });
+    }
+    bucket.tokens -= 1.0;
+
+    Ok(())
+}
+
+/// Which block a read request is pinned to
+#[derive(Debug, PartialEq)]
+enum BlockParam {
+    Latest,
+    Number(u64),
+    Hash,
+
+    /// Not a read, or pinned to the pending block, so not worth caching
+    Unsupported,
+}
+
+fn block_param(method: &str, params: &Json) -> BlockParam {
+    let index = match method {
+        "eth_getBlockByHash"
+        | "eth_getBlockTransactionCountByHash"
+        | "eth_getTransactionByBlockHashAndIndex"
+        | "eth_getUncleByBlockHashAndIndex"
+        | "eth_getUncleCountByBlockHash" => return BlockParam::Hash,
+
+        // pinned to however the transaction ends up, which is checked against the response
+        "eth_getTransactionByHash" | "eth_getTransactionReceipt" => return BlockParam::Hash,
+
+        "eth_getLogs" => {
+            return match params[0].get("blockHash") {
+                Some(_) => BlockParam::Hash,
+                None => BlockParam::Latest,
+            }
+        }
+
+        "eth_getBlockByNumber"
+        | "eth_getBlockTransactionCountByNumber"
+        | "eth_getTransactionByBlockNumberAndIndex"
+        | "eth_getUncleByBlockNumberAndIndex"
+        | "eth_getUncleCountByBlockNumber" => 0,
+
+        "eth_getBalance"
+        | "eth_getCode"
+        | "eth_getTransactionCount"
+        | "eth_call"
+        | "eth_estimateGas" => 1,
+        "eth_getStorageAt" => 2,
+
+        _ => return BlockParam::Unsupported,
+    };
+
+    match &params[index] {
+        Json::Null => BlockParam::Latest,
+        Json::String(tag) => match tag.as_str() {
+            "latest" | "safe" | "finalized" => BlockParam::Latest,
+            "earliest" => BlockParam::Number(0),
+            "pending" => BlockParam::Unsupported,
+            hex => match u64::from_str_radix(hex.trim_start_matches("0x"), 16) {
+                Ok(n) => BlockParam::Number(n),
+                Err(_) => BlockParam::Unsupported,
+            },
+        },
+        // EIP-1898 block identifiers
+        Json::Object(o) if o.contains_key("blockHash") => BlockParam::Hash,
+        Json::Object(o) => match o.get("blockNumber").and_then(|n| n.as_str()) {
+            Some(n) => block_param(method, &replace(params, index, n)),
+            None => BlockParam::Unsupported,
+        },
+        _ => BlockParam::Unsupported,
+    }
+}
+
+fn replace(params: &Json, index: usize, value: &str) -> Json {
+    let mut params = params.clone();
+    params[index] = value.into();
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_block_params() {
+        let addr = "0x0000000000000000000000000000000000000001";
+
+        assert_eq!(
+            block_param("eth_getBalance", &json!([addr, "latest"])),
+            BlockParam::Latest
+        );
+        assert_eq!(
+            block_param("eth_call", &json!([{ "to": addr }])),
+            BlockParam::Latest
+        );
+        assert_eq!(
+            block_param("eth_getStorageAt", &json!([addr, "0x0", "0x10"])),
+            BlockParam::Number(16)
+        );
+        assert_eq!(
+            block_param("eth_getCode", &json!([addr, { "blockNumber": "0x2" }])),
+            BlockParam::Number(2)
+        );
+        assert_eq!(
+            block_param(
+                "eth_call",
+                &json!([{ "to": addr }, { "blockHash": "0x01" }])
+            ),
+            BlockParam::Hash
+        );
+        assert_eq!(
+            block_param("eth_getTransactionCount", &json!([addr, "pending"])),
+            BlockParam::Unsupported
+        );
+        assert_eq!(
+            block_param("eth_sendRawTransaction", &json!(["0x00"])),
+            BlockParam::Unsupported
+        );
+    }
+
+    #[test]
+    fn block_scoped_entries_expire_with_the_block() {
+        let mut cache = Cache::default();
+        let key = |m: &str| (1, m.to_string(), String::new());
+
+        cache.insert(key("eth_getBalance"), json!("0x1"), Some(10));
+        cache.insert(key("eth_getBlockByHash"), json!({}), None);
+
+        assert_eq!(
+            cache.get(&key("eth_getBalance"), Some(10)),
+            Some(json!("0x1"))
+        );
+        assert_eq!(cache.get(&key("eth_getBalance"), Some(11)), None);
+        assert!(cache.get(&key("eth_getBlockByHash"), None).is_some());
+    }
+
+    #[test]
+    fn rate_limits_each_origin() {
+        for _ in 0..BURST as usize {
+            check_rate_limit("https://busy.test").unwrap();
+        }
+
+        assert!(check_rate_limit("https://busy.test").is_err());
+        assert!(check_rate_limit("https://quiet.test").is_ok());
+    }
+}
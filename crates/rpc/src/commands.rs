@@ -1,7 +1,262 @@
-use super::{Handler, Result};
+use ethers::types::{Address, BlockId, Bytes, H256, U256};
+use iron_networks::{L1Fee, Networks};
+use iron_types::{GlobalState, Json};
+use iron_wallets::{WalletControl, Wallets};
+
+use super::{
+    approvals::{self, Allowance},
+    contracts::{self, ContractFunction},
+    gas_tracker::{self, GasHistory},
+    inspect::{self, CallOutput},
+    offline, params, replace,
+    safe::{self, SafeInfo, SafeTx},
+    selectors::DecodedArg,
+    simulate, tokens,
+    user_ops::{self, SmartAccount},
+    Handler, Result,
+};
 use crate::Ctx;
 
 #[tauri::command]
-pub async fn rpc_send_transaction(params: serde_json::Value) -> Result<serde_json::Value> {
-    Ok(Handler::send_transaction(params, Ctx::empty()).await?)
+pub async fn rpc_send_transaction(params: serde_json::Value) -> Result<H256> {
+    let params = params::resolve_ens_recipient(params).await?;
+    Handler::send_transaction(params, Ctx::empty()).await
+}
+
+/// Reviews and signs a transaction without broadcasting it, returning the raw signed bytes
+#[tauri::command]
+pub async fn rpc_sign_transaction(params: serde_json::Value) -> Result<Bytes> {
+    let params = params::resolve_ens_recipient(params).await?;
+    Handler::sign_request(params::transaction_request(params)?, Ctx::empty()).await
+}
+
+/// Resends a pending transaction with higher fees, returning the replacement's hash
+#[tauri::command]
+pub async fn rpc_speed_up_tx(hash: H256) -> Result<H256> {
+    replace::speed_up(hash).await
+}
+
+/// Cancels a pending transaction by replacing it with an empty self-transfer, returning the
+/// replacement's hash
+#[tauri::command]
+pub async fn rpc_cancel_tx(hash: H256) -> Result<H256> {
+    replace::cancel(hash).await
+}
+
+/// Builds an unsigned transaction, with nonce, gas and fees filled in, to be signed offline
+#[tauri::command]
+pub async fn rpc_build_unsigned(params: serde_json::Value) -> Result<Json> {
+    let params = params::resolve_ens_recipient(params).await?;
+    offline::build_unsigned(params::transaction_request(params)?).await
+}
+
+/// Signs a transaction exported by `rpc_build_unsigned`, returning the raw signed bytes
+#[tauri::command]
+pub async fn rpc_sign_exported(tx: Json) -> Result<Bytes> {
+    offline::sign_exported(tx).await
+}
+
+/// Broadcasts a raw transaction signed by `rpc_sign_exported`
+#[tauri::command]
+pub async fn rpc_broadcast_signed(raw: Bytes) -> Result<H256> {
+    offline::broadcast_signed(raw).await
+}
+
+/// Estimates the gas a transaction would use on the current network, to preview its cost
+#[tauri::command]
+pub async fn rpc_estimate_gas(params: serde_json::Value) -> Result<U256> {
+    let params = params::resolve_ens_recipient(params).await?;
+    let request = params::transaction_request(params)?;
+    let network = Networks::read().await.get_current()?.clone();
+
+    Ok(network.estimate_gas(request).await?)
+}
+
+/// The L1 data fee a transaction would pay on the current network, if it's a rollup
+/// Gas estimates alone understate what transactions cost there
+#[tauri::command]
+pub async fn rpc_estimate_l1_fee(params: serde_json::Value) -> Result<Option<L1Fee>> {
+    let params = params::resolve_ens_recipient(params).await?;
+    let request = params::transaction_request(params)?;
+    let network = Networks::read().await.get_current()?.clone();
+
+    Ok(network.estimate_l1_fee(&request).await?)
+}
+
+/// Dry-runs a transaction on the current network, failing with the revert reason if it would revert
+#[tauri::command]
+pub async fn rpc_simulate(params: serde_json::Value) -> Result<Bytes> {
+    let params = params::resolve_ens_recipient(params).await?;
+    let request = params::transaction_request(params)?;
+    let network = Networks::read().await.get_current()?.clone();
+
+    simulate::simulate(&request, &network).await
+}
+
+/// Adds a custom ERC-20 token to the current network's watch list
+#[tauri::command]
+pub async fn rpc_watch_token(address: Address) -> Result<()> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    tokens::watch(&network, address).await
+}
+
+/// Removes a token from the current network's watch list
+#[tauri::command]
+pub async fn rpc_unwatch_token(address: Address) -> Result<()> {
+    let chain_id = Networks::read().await.get_current()?.chain_id;
+
+    tokens::unwatch(chain_id, address).await
+}
+
+/// Owners and threshold of a Safe on the current network, or `None` if the address isn't one
+#[tauri::command]
+pub async fn rpc_get_safe(address: Address) -> Result<Option<SafeInfo>> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    safe::detect(&network, address).await
+}
+
+/// Signs a transaction as an owner of a Safe, and proposes it to the Safe transaction service,
+/// returning the Safe transaction hash
+#[tauri::command]
+pub async fn rpc_propose_safe_tx(address: Address, tx: SafeTx) -> Result<H256> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    safe::propose(network, address, tx, None).await
+}
+
+/// The ERC-4337 smart account of the active account, if the current network has a bundler
+/// Transactions sent from its address go through the bundler as UserOperations
+#[tauri::command]
+pub async fn rpc_get_smart_account() -> Result<Option<SmartAccount>> {
+    let network = Networks::read().await.get_current()?.clone();
+    if !user_ops::has_bundler(&network) {
+        return Ok(None);
+    }
+
+    let owner = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await;
+
+    Ok(Some(user_ops::smart_account(&network, owner.into()).await?))
+}
+
+/// Approvals the active account has handed out on the current network, which still stand
+#[tauri::command]
+pub async fn rpc_get_allowances() -> Result<Vec<Allowance>> {
+    let network = Networks::read().await.get_current()?.clone();
+    let owner = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await;
+
+    approvals::scan(&network, owner.into()).await
+}
+
+/// Revokes one of the active account's approvals, returning the transaction hash
+#[tauri::command]
+pub async fn rpc_revoke_allowance(allowance: Allowance) -> Result<H256> {
+    let owner = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await;
+
+    approvals::revoke(owner.into(), &allowance).await
+}
+
+/// The functions of a contract on the current network, from its known ABI
+#[tauri::command]
+pub async fn rpc_contract_functions(address: Address) -> Result<Vec<ContractFunction>> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    contracts::functions(&network, address).await
+}
+
+/// Calldata for calling a contract function with the given arguments
+#[tauri::command]
+pub async fn rpc_contract_encode(
+    address: Address,
+    signature: String,
+    args: Vec<String>,
+) -> Result<Bytes> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    contracts::encode(&network, address, &signature, &args).await
+}
+
+/// Calls a read-only contract function from the active account, returning its outputs
+#[tauri::command]
+pub async fn rpc_contract_call(
+    address: Address,
+    signature: String,
+    args: Vec<String>,
+) -> Result<Vec<DecodedArg>> {
+    let network = Networks::read().await.get_current()?.clone();
+    let from = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await;
+
+    contracts::call(&network, from.into(), address, &signature, &args).await
+}
+
+/// Sends a transaction calling a contract function, returning its hash
+#[tauri::command]
+pub async fn rpc_contract_send(
+    address: Address,
+    signature: String,
+    args: Vec<String>,
+    value: Option<U256>,
+) -> Result<H256> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    contracts::send(&network, address, &signature, &args, value).await
+}
+
+/// An account's native balance on the current network, at `block` or the latest one
+#[tauri::command]
+pub async fn rpc_get_balance(address: Address, block: Option<BlockId>) -> Result<U256> {
+    inspect::balance(address, block).await
+}
+
+/// The bytecode deployed at an address on the current network
+#[tauri::command]
+pub async fn rpc_get_code(address: Address, block: Option<BlockId>) -> Result<Bytes> {
+    inspect::code(address, block).await
+}
+
+/// A raw storage slot of a contract on the current network
+#[tauri::command]
+pub async fn rpc_get_storage_at(
+    address: Address,
+    slot: H256,
+    block: Option<BlockId>,
+) -> Result<H256> {
+    inspect::storage(address, slot, block).await
+}
+
+/// Calls any contract through a human-readable function signature, without needing its ABI
+#[tauri::command]
+pub async fn rpc_call(
+    to: Address,
+    signature: String,
+    args: Vec<String>,
+    block: Option<BlockId>,
+) -> Result<CallOutput> {
+    inspect::call(to, &signature, &args, block).await
+}
+
+/// Fees sampled on the current network over the last `hours`, a day by default, for charting
+/// them along with the hour of the day they tend to be lowest at
+#[tauri::command]
+pub async fn rpc_get_gas_history(hours: Option<u32>) -> Result<GasHistory> {
+    let chain_id = Networks::read().await.get_current()?.chain_id;
+
+    gas_tracker::history(chain_id, hours.unwrap_or(24)).await
 }
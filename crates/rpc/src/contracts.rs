@@ -0,0 +1,248 @@
+//! Reads from and writes to a contract through its ABI, given arguments as the user typed them
+//! The ABI is looked up the same way calls are decoded for review, so any contract whose calls
+//! can be decoded can be interacted with
+
+use ethers::{
+    abi::{
+        token::{LenientTokenizer, Tokenizer},
+        Abi, Function, Param as AbiParam, StateMutability,
+    },
+    prelude::*,
+    types::transaction::eip2718::TypedTransaction,
+    utils::to_checksum,
+};
+use iron_connections::Ctx;
+use iron_networks::Network;
+use serde::Serialize;
+use serde_json::json;
+
+use super::{
+    selectors::{self, DecodedArg},
+    simulate, Error, Handler, Result,
+};
+
+/// A function of a contract's ABI, as listed for the user to pick from
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractFunction {
+    pub name: String,
+
+    /// Canonical signature, such as `transfer(address,uint256)`, which identifies the function
+    /// across overloads
+    pub signature: String,
+
+    pub inputs: Vec<Param>,
+    pub outputs: Vec<Param>,
+
+    /// Whether it's called through `eth_call`, rather than sent as a transaction
+    pub read_only: bool,
+    pub payable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Param {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// The functions of a contract on a network
+pub(crate) async fn functions(
+    network: &Network,
+    address: Address,
+) -> Result<Vec<ContractFunction>> {
+    let abi = abi(network, address).await?;
+
+    Ok(abi
+        .functions()
+        .map(|function| ContractFunction {
+            name: function.name.clone(),
+            signature: signature(function),
+            inputs: params(&function.inputs),
+            outputs: params(&function.outputs),
+            read_only: is_read_only(function),
+            payable: function.state_mutability == StateMutability::Payable,
+        })
+        .collect())
+}
+
+/// Calldata for a function, with arguments parsed from their text form
+pub(crate) async fn encode(
+    network: &Network,
+    address: Address,
+    signature: &str,
+    args: &[String],
+) -> Result<Bytes> {
+    let abi = abi(network, address).await?;
+    let function = find(&abi, signature)?;
+
+    encode_with(function, args)
+}
+
+/// Calls a read-only function, returning its decoded outputs
+/// Reverts fail with their reason, as they do when simulating transactions
+pub(crate) async fn call(
+    network: &Network,
+    from: Address,
+    address: Address,
+    signature: &str,
+    args: &[String],
+) -> Result<Vec<DecodedArg>> {
+    let abi = abi(network, address).await?;
+    let function = find(&abi, signature)?;
+    let data = encode_with(function, args)?;
+
+    let request: TypedTransaction = TransactionRequest::new()
+        .from(from)
+        .to(address)
+        .data(data)
+        .into();
+    let output = simulate::simulate(&request, network).await?;
+    let tokens = function
+        .decode_output(&output)
+        .map_err(|e| Error::InvalidParams(e.to_string()))?;
+
+    Ok(function
+        .outputs
+        .iter()
+        .zip(tokens)
+        .map(|(param, token)| DecodedArg {
+            name: param.name.clone(),
+            value: selectors::format_token(token),
+        })
+        .collect())
+}
+
+/// Sends a transaction calling a function from the active account
+/// It goes through the same review as any other transaction
+pub(crate) async fn send(
+    network: &Network,
+    address: Address,
+    signature: &str,
+    args: &[String],
+    value: Option<U256>,
+) -> Result<H256> {
+    let data = encode(network, address, signature, args).await?;
+
+    let params = json!({
+        "to": to_checksum(&address, None),
+        "data": data,
+        "value": value.unwrap_or_default(),
+    });
+
+    Handler::send_transaction(params, Ctx::empty()).await
+}
+
+/// Contracts whose ABI isn't otherwise known may still match one compiled by a local forge
+/// project, by bytecode
+async fn abi(network: &Network, address: Address) -> Result<Abi> {
+    if let Some((_, abi)) = selectors::known_abi(network.chain_id, address).await {
+        return Ok(abi);
+    }
+
+    let code = network.get_provider()?.get_code(address, None).await?;
+    if code.is_empty() {
+        return Err(Error::NoAbi(address));
+    }
+
+    iron_forge::get_abi_for_code(code)
+        .await
+        .and_then(|abi| serde_json::from_value(abi.abi).ok())
+        .ok_or(Error::NoAbi(address))
+}
+
+fn find<'a>(abi: &'a Abi, signature: &str) -> Result<&'a Function> {
+    abi.functions()
+        .find(|f| self::signature(f) == signature)
+        .ok_or_else(|| Error::UnknownFunction(signature.to_string()))
+}
+
+/// Arguments are parsed leniently, so that amounts can be typed in base 10, and bytes without
+/// padding
+pub(crate) fn encode_with(function: &Function, args: &[String]) -> Result<Bytes> {
+    if args.len() != function.inputs.len() {
+        return Err(Error::InvalidParams(format!(
+            "{} takes {} arguments, but {} were given",
+            function.name,
+            function.inputs.len(),
+            args.len()
+        )));
+    }
+
+    let tokens = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            LenientTokenizer::tokenize(&param.kind, arg.trim()).map_err(|e| {
+                Error::InvalidParams(format!("{} ({}): {}", param.name, param.kind, e))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    function
+        .encode_input(&tokens)
+        .map(Into::into)
+        .map_err(|e| Error::InvalidParams(e.to_string()))
+}
+
+/// The signature without outputs, unlike `Function::signature`
+fn signature(function: &Function) -> String {
+    let inputs: Vec<_> = function.inputs.iter().map(|p| p.kind.to_string()).collect();
+
+    format!("{}({})", function.name, inputs.join(","))
+}
+
+/// Pre-0.5 ABIs only have the `constant` flag, which ethers reads as `view`
+fn is_read_only(function: &Function) -> bool {
+    matches!(
+        function.state_mutability,
+        StateMutability::View | StateMutability::Pure
+    )
+}
+
+fn params(params: &[AbiParam]) -> Vec<Param> {
+    params
+        .iter()
+        .map(|p| Param {
+            name: p.name.clone(),
+            kind: p.kind.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::AbiParser;
+
+    use super::*;
+
+    #[test]
+    fn encodes_text_arguments() {
+        let abi = AbiParser::default()
+            .parse(&[
+                "function transfer(address to, uint256 amount) returns (bool)",
+                "function transfer(address to, uint256 amount, bytes data) returns (bool)",
+            ])
+            .unwrap();
+
+        let function = find(&abi, "transfer(address,uint256)").unwrap();
+        assert_eq!(function.inputs.len(), 2);
+
+        let data = encode_with(
+            function,
+            &[
+                "0x0000000000000000000000000000000000000001".into(),
+                " 1000 ".into(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(&data[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(data.len(), 4 + 64);
+
+        assert!(encode_with(function, &["0x01".into()]).is_err());
+        assert!(encode_with(function, &["not an address".into(), "1".into()]).is_err());
+        assert!(find(&abi, "transfer(address)").is_err());
+    }
+}
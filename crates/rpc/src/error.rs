@@ -1,8 +1,6 @@
-use ethers::{
-    core::k256::ecdsa::SigningKey,
-    prelude::{signer::SignerMiddlewareError, *},
-    signers,
-};
+use ethers::prelude::{signer::SignerMiddlewareError, *};
+use iron_networks::Failover;
+use iron_wallets::{WalletSigner, WalletSignerError};
 use jsonrpc_core::ErrorCode;
 
 #[derive(thiserror::Error, Debug)]
@@ -17,11 +15,17 @@ pub enum Error {
     SignerBuild(String),
 
     #[error(transparent)]
-    SignerMiddleware(#[from] SignerMiddlewareError<Provider<Http>, signers::Wallet<SigningKey>>),
+    SignerMiddleware(#[from] SignerMiddlewareError<Provider<Failover>, WalletSigner>),
 
     #[error(transparent)]
     Wallet(#[from] ethers::signers::WalletError),
 
+    #[error(transparent)]
+    Signer(#[from] WalletSignerError),
+
+    #[error(transparent)]
+    Provider(#[from] ProviderError),
+
     #[error(transparent)]
     JsonRpc(#[from] jsonrpc_core::Error),
 
@@ -31,6 +35,98 @@ pub enum Error {
     #[error(transparent)]
     Connection(#[from] iron_connections::Error),
 
+    #[error(transparent)]
+    Wallets(#[from] iron_wallets::Error),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
+
+    #[error(transparent)]
+    DB(#[from] iron_db::Error),
+
+    #[error(transparent)]
+    Contract(#[from] ContractError<Provider<Failover>>),
+
+    #[error(transparent)]
+    Multicall(#[from] MulticallError<Provider<Failover>>),
+
+    #[error("{0} is not initialized")]
+    NotInitialized(&'static str),
+
+    #[error("this site is not connected to the wallet")]
+    Unauthorized,
+
+    #[error("connection request rejected")]
+    ConnectionRejected,
+
+    #[error("chain change rejected")]
+    ChainChangeRejected,
+
+    #[error("unrecognized chain ID: {0}")]
+    UnrecognizedChain(u32),
+
+    #[error("transaction will revert: {0}")]
+    Reverted(String),
+
+    #[error("transaction is for chain {0}, but the current chain is {1}")]
+    ChainIdMismatch(u64, u32),
+
+    #[error(
+        "insufficient funds: transaction needs {needed} wei, but only {available} are available"
+    )]
+    InsufficientFunds { needed: U256, available: U256 },
+
+    #[error("account {0:?} does not belong to the current wallet")]
+    UnknownAccount(Address),
+
+    #[error("transaction {0:?} is no longer pending")]
+    TxNotPending(H256),
+
+    #[error("transaction {0:?} is not known to the node")]
+    TxNotFound(H256),
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("invalid params: {0}")]
+    InvalidParams(String),
+
+    #[error("unsupported method: {0}")]
+    UnsupportedMethod(String),
+
+    #[error("no ABI known for contract {0:?}")]
+    NoAbi(Address),
+
+    #[error("no function {0} in the contract's ABI")]
+    UnknownFunction(String),
+
+    #[error("{0:?} is not a Safe")]
+    NotASafe(Address),
+
+    #[error("none of the wallet's accounts is an owner of Safe {0:?}")]
+    NotASafeOwner(Address),
+
+    #[error("no Safe transaction service for chain {0}")]
+    NoSafeTxService(u32),
+
+    #[error("transaction hash does not match the one computed by Safe {0:?}")]
+    SafeHashMismatch(Address),
+
+    #[error("Safe transaction service error: {0}")]
+    SafeTxService(String),
+
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error("no ERC-4337 bundler configured for {0}")]
+    NoBundler(String),
+
+    #[error("no private relay configured for {0}")]
+    NoPrivateRelay(String),
+
+    #[error("bundler error: {0}")]
+    Bundler(String),
+
     #[error("serialization error: {0}")]
     Serde(#[from] serde_json::Error),
 
@@ -42,10 +138,47 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 impl From<Error> for jsonrpc_core::Error {
     fn from(value: Error) -> Self {
-        Self {
-            code: ErrorCode::ServerError(0),
-            data: None,
-            message: value.to_string(),
+        match value {
+            Error::JsonRpc(e) => e,
+            Error::InvalidParams(msg) => Self::invalid_params(msg),
+            e @ Error::InvalidAddress(_) => Self::invalid_params(e.to_string()),
+            e @ Error::ChainIdMismatch(..) => Self::invalid_params(e.to_string()),
+            e @ (Error::ConnectionRejected
+            | Error::ChainChangeRejected
+            | Error::TxDialogRejected
+            | Error::SignatureRejected
+            | Error::Wallets(iron_wallets::Error::UnlockDialogRejected)) => Self {
+                code: ErrorCode::ServerError(4001),
+                data: None,
+                message: e.to_string(),
+            },
+            e @ Error::UnrecognizedChain(_) => Self {
+                code: ErrorCode::ServerError(4902),
+                data: None,
+                message: e.to_string(),
+            },
+            e @ (Error::Unauthorized
+            | Error::UnknownAccount(_)
+            | Error::Wallets(iron_wallets::Error::WatchOnly(_))
+            | Error::Wallets(iron_wallets::Error::Locked)) => Self {
+                code: ErrorCode::ServerError(4100),
+                data: None,
+                message: e.to_string(),
+            },
+            e @ Error::UnsupportedMethod(_) => Self {
+                code: ErrorCode::ServerError(4200),
+                data: None,
+                message: e.to_string(),
+            },
+            Error::Provider(e)
+            | Error::SignerMiddleware(SignerMiddlewareError::MiddlewareError(e)) => {
+                ethers_to_jsonrpc_error(e)
+            }
+            value => Self {
+                code: ErrorCode::ServerError(0),
+                data: None,
+                message: value.to_string(),
+            },
         }
     }
 }
@@ -72,9 +205,18 @@ pub(crate) fn ethers_to_jsonrpc_error(e: ProviderError) -> jsonrpc_core::Error {
             } else if e.as_serde_error().is_some() {
                 jsonrpc_core::Error::invalid_request()
             } else {
-                jsonrpc_core::Error::internal_error()
+                // every endpoint timed out or was unreachable, even after retrying
+                jsonrpc_core::Error {
+                    code: ErrorCode::ServerError(-32002),
+                    data: None,
+                    message: format!("resource unavailable: {}", e),
+                }
             }
         }
-        _ => jsonrpc_core::Error::internal_error(),
+        e => jsonrpc_core::Error {
+            code: ErrorCode::InternalError,
+            data: None,
+            message: e.to_string(),
+        },
     }
 }
@@ -0,0 +1,148 @@
+use ethers::types::{transaction::eip2718::TypedTransaction, TransactionRequest, U256};
+use iron_networks::{FeeMode, FeeSuggestions, Fees, Network};
+use iron_settings::Settings;
+use iron_types::{GlobalState, Json};
+
+use super::{Error, Result};
+
+/// Converts an EIP-1559 request into a legacy one if the network doesn't support it, so it isn't
+/// rejected for carrying 1559 fee fields
+/// Networks configured for EIP-1559 are also checked on-chain. If detection fails, the request is
+/// left untouched
+pub(crate) async fn adapt_to_network(
+    request: TypedTransaction,
+    network: &Network,
+) -> TypedTransaction {
+    if !matches!(request, TypedTransaction::Eip1559(_)) {
+        return request;
+    }
+
+    let legacy = match network.fee_mode() {
+        FeeMode::Legacy => true,
+        FeeMode::Eip1559 => matches!(network.detect_eip1559().await, Ok(false)),
+    };
+
+    if !legacy {
+        return request;
+    }
+
+    TypedTransaction::Legacy(TransactionRequest {
+        from: request.from().copied(),
+        to: request.to().cloned(),
+        gas: request.gas().copied(),
+        value: request.value().copied(),
+        data: request.data().cloned(),
+        nonce: request.nonce().copied(),
+        chain_id: request.chain_id(),
+        ..Default::default()
+    })
+}
+
+/// Sets suggested fees on a request that doesn't carry its own, at the speed picked in the
+/// settings, returning every suggestion so they can be offered for review
+/// Legacy requests on EIP-1559 networks are left for the provider to price
+pub(crate) async fn fill(
+    request: &mut TypedTransaction,
+    network: &Network,
+) -> Option<FeeSuggestions> {
+    let suggestions = network.get_fee_estimates().await.ok()?.suggestions();
+
+    if request.gas_price().is_none() {
+        let speed = Settings::read().await.get().gas_preset;
+        apply(request, suggestions.get(speed));
+    }
+
+    Some(suggestions)
+}
+
+/// Sets fee fields on a request, as long as they match its type
+pub(crate) fn apply(request: &mut TypedTransaction, fees: Fees) {
+    match (request, fees) {
+        (
+            TypedTransaction::Eip1559(inner),
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            },
+        ) => {
+            inner.max_fee_per_gas = Some(max_fee_per_gas);
+            inner.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+        }
+        (TypedTransaction::Eip1559(_), Fees::Legacy { .. }) => (),
+        (request, Fees::Legacy { gas_price }) => request.set_gas_price(gas_price),
+        (_, Fees::Eip1559 { .. }) => (),
+    }
+}
+
+/// Reads fees the user chose in the approval dialog, in the same fields as the request itself
+pub(crate) fn overrides(request: &TypedTransaction, response: &Json) -> Result<Option<Fees>> {
+    let field = |name: &str| -> Result<Option<U256>> {
+        match response.get(name) {
+            None | Some(Json::Null) => Ok(None),
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|_| Error::InvalidParams(format!("invalid {}", name))),
+        }
+    };
+
+    let fees = match request {
+        TypedTransaction::Eip1559(_) => {
+            match (field("maxFeePerGas")?, field("maxPriorityFeePerGas")?) {
+                (Some(max_fee_per_gas), Some(max_priority_fee_per_gas)) => Some(Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                }),
+                _ => None,
+            }
+        }
+        _ => field("gasPrice")?.map(|gas_price| Fees::Legacy { gas_price }),
+    };
+
+    if let Some(Fees::Eip1559 {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    }) = fees
+    {
+        if max_priority_fee_per_gas > max_fee_per_gas {
+            return Err(Error::InvalidParams(
+                "maxPriorityFeePerGas cannot exceed maxFeePerGas".into(),
+            ));
+        }
+    }
+
+    Ok(fees)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::Eip1559TransactionRequest;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reads_fee_overrides_for_the_request_type() {
+        let request = TypedTransaction::Eip1559(Eip1559TransactionRequest::new());
+
+        assert_eq!(
+            overrides(
+                &request,
+                &json!({ "maxFeePerGas": "0x64", "maxPriorityFeePerGas": "0x2" })
+            )
+            .unwrap(),
+            Some(Fees::Eip1559 {
+                max_fee_per_gas: 100.into(),
+                max_priority_fee_per_gas: 2.into(),
+            })
+        );
+        assert_eq!(
+            overrides(&request, &json!({ "gasPrice": "0x64" })).unwrap(),
+            None
+        );
+        assert!(overrides(
+            &request,
+            &json!({ "maxFeePerGas": "0x1", "maxPriorityFeePerGas": "0x2" })
+        )
+        .is_err());
+    }
+}
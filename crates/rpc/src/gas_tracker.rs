@@ -0,0 +1,184 @@
+//! Samples the fees paid in the current network's blocks, keeping a day of them, so that the UI
+//! can chart how fees moved and suggest a cheaper time to send
+//! Legacy networks have no base fee to track, and are skipped
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::types::U256;
+use iron_db::DB;
+use iron_networks::{FeeMode, Network, Networks};
+use iron_types::{GasSample, GlobalState, UINotify};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tokio::time::{interval, Duration};
+
+use super::{Error, Result};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+/// How often the current network's latest block is sampled, about once per block on mainnet
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(12);
+
+/// How long samples are kept for
+const WINDOW: Duration = Duration::from_secs(24 * 3600);
+
+/// Distinct hours of the day there must be samples for before one is suggested as the cheapest
+const MIN_HOURS_FOR_SUGGESTION: usize = 6;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasHistory {
+    pub chain_id: u32,
+
+    /// Oldest first
+    pub samples: Vec<GasSample>,
+
+    /// `None` until enough of the day has been sampled
+    pub cheapest_hour: Option<CheapestHour>,
+}
+
+/// The hour of the day base fees were lowest at, on average, over the sampled window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheapestHour {
+    /// In UTC, 0 to 23
+    pub hour: u8,
+    pub average_base_fee: U256,
+}
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+    tokio::spawn(async { sampler().await });
+}
+
+fn db() -> Result<&'static DB> {
+    DB.get().ok_or(Error::NotInitialized("gas tracker"))
+}
+
+/// Fees sampled on a network over the last `hours`, which can't go beyond the kept window
+pub(crate) async fn history(chain_id: u32, hours: u32) -> Result<GasHistory> {
+    let since = now().saturating_sub((hours as u64 * 3600).min(WINDOW.as_secs()));
+    let samples = db()?.get_gas_history(chain_id, since).await?;
+
+    Ok(GasHistory {
+        chain_id,
+        cheapest_hour: cheapest_hour(&samples),
+        samples,
+    })
+}
+
+async fn sampler() {
+    let mut ticker = interval(SAMPLE_INTERVAL);
+    let mut last_sampled = None;
+
+    loop {
+        ticker.tick().await;
+
+        let Ok(network) = Networks::read().await.get_current().cloned() else {
+            continue;
+        };
+        if network.fee_mode() == FeeMode::Legacy {
+            continue;
+        }
+
+        match sample(&network, &mut last_sampled).await {
+            Ok(true) => iron_broadcast::ui_notify(UINotify::GasUpdated).await,
+            Ok(false) => (),
+            Err(e) => tracing::debug!("Failed to sample fees on {}: {}", network.name, e),
+        }
+    }
+}
+
+/// Records the latest block's fees, unless it was already sampled
+/// Returns whether a new sample was recorded
+async fn sample(network: &Network, last_sampled: &mut Option<(u32, u64)>) -> Result<bool> {
+    let (block_number, base_fee, priority_fees) = network.get_latest_block_fees().await?;
+    if *last_sampled == Some((network.chain_id, block_number)) {
+        return Ok(false);
+    }
+
+    let sample = GasSample {
+        block_number,
+        timestamp: now(),
+        base_fee,
+        priority_fee_low: priority_fees.low,
+        priority_fee_medium: priority_fees.medium,
+        priority_fee_high: priority_fees.high,
+    };
+    db()?
+        .save_gas_sample(network.chain_id, &sample, WINDOW)
+        .await?;
+
+    *last_sampled = Some((network.chain_id, block_number));
+    Ok(true)
+}
+
+fn cheapest_hour(samples: &[GasSample]) -> Option<CheapestHour> {
+    let mut by_hour: BTreeMap<u8, (U256, u64)> = BTreeMap::new();
+    for sample in samples {
+        let hour = ((sample.timestamp / 3600) % 24) as u8;
+        let (total, count) = by_hour.entry(hour).or_default();
+        *total = total.saturating_add(sample.base_fee);
+        *count += 1;
+    }
+
+    if by_hour.len() < MIN_HOURS_FOR_SUGGESTION {
+        return None;
+    }
+
+    by_hour
+        .into_iter()
+        .map(|(hour, (total, count))| CheapestHour {
+            hour,
+            average_base_fee: total / count,
+        })
+        .min_by_key(|h| h.average_base_fee)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, base_fee: u64) -> GasSample {
+        GasSample {
+            block_number: timestamp,
+            timestamp,
+            base_fee: base_fee.into(),
+            priority_fee_low: Default::default(),
+            priority_fee_medium: Default::default(),
+            priority_fee_high: Default::default(),
+        }
+    }
+
+    #[test]
+    fn suggests_the_hour_with_the_lowest_average_base_fee() {
+        // two samples an hour, with 03:00 UTC the cheapest on average
+        let samples: Vec<_> = (0..8u64)
+            .flat_map(|hour| {
+                let fee = if hour == 3 { 10 } else { 30 + hour };
+                [sample(hour * 3600, fee), sample(hour * 3600 + 60, fee + 2)]
+            })
+            .collect();
+
+        assert_eq!(
+            cheapest_hour(&samples),
+            Some(CheapestHour {
+                hour: 3,
+                average_base_fee: 11.into(),
+            })
+        );
+
+        // too little of the day was sampled to tell
+        assert_eq!(cheapest_hour(&samples[..4]), None);
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::{prelude::*, types::transaction::eip2718::TypedTransaction};
+use iron_db::DB;
+use iron_networks::{Failover, Network, Networks};
+use iron_types::{GlobalState, TxRecord, TxStatus, TxUpdate, UINotify};
+use once_cell::sync::OnceCell;
+use tokio::time::{interval, timeout, Duration};
+
+use super::nonces;
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+/// How often a sent transaction is checked on
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Consecutive polls a transaction may be missing from the node before it's considered dropped
+/// Load-balanced RPCs don't always share a mempool, so a single miss means little
+const DROP_AFTER_MISSES: u32 = 10;
+
+/// How long a transaction is watched for. The block watcher still settles it if it's mined later
+pub(crate) const MAX_WATCH: Duration = Duration::from_secs(60 * 60);
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+}
+
+/// How a watched transaction left the mempool
+enum Outcome {
+    Mined(TransactionReceipt),
+    Replaced,
+    Dropped,
+}
+
+/// Adds a freshly sent transaction to the activity history as pending, and watches it until it
+/// is mined, replaced or dropped
+/// Each step is broadcast to the UI, and to the peer that sent it, if any. Its signed bytes, when
+/// given, are kept along, so that it can still be tracked and replaced after a restart
+/// Transactions sent through a private relay are looked up there while pending, since they
+/// never show up in the public mempool
+pub(crate) async fn track(
+    hash: H256,
+    from: Address,
+    request: &TypedTransaction,
+    raw: Option<&Bytes>,
+    private: bool,
+    network: &Network,
+    peer: Option<SocketAddr>,
+) {
+    let Some(db) = record_pending(hash, from, request, network, peer).await else {
+        return;
+    };
+
+    let nonce = request.nonce().copied();
+    if let Some(raw) = raw {
+        if let Err(e) = db.save_tx_payload(hash, nonce, raw, private).await {
+            tracing::warn!("Failed to save payload of tx {:?}: {}", hash, e);
+        }
+    }
+
+    let relay = private.then(|| network.get_private_relay()).flatten();
+    if let Ok(provider) = network.get_provider() {
+        watch(provider, relay, hash, from, nonce, network.chain_id, peer);
+    }
+}
+
+/// Picks tracking back up for transactions still pending when the app was last closed
+/// Those the node has since forgotten about are broadcast again, from their signed bytes
+pub(crate) async fn resume() {
+    let Some(db) = DB.get() else {
+        return;
+    };
+    let pending = match db.get_all_pending_txs().await {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::warn!("Failed to read pending txs: {}", e);
+            return;
+        }
+    };
+
+    for record in pending {
+        let Some(network) = Networks::read().await.get_network(record.chain_id) else {
+            continue;
+        };
+        rewatch(db, &network, &record).await;
+    }
+}
+
+/// Puts transactions mined in blocks that were reorganized away back to pending, and watches them
+/// again, since they may end up in a different block, or not at all
+pub(crate) async fn revert(network: &Network, fork: u64) {
+    let Some(db) = DB.get() else {
+        return;
+    };
+    let reverted = match db.revert_txs_from(network.chain_id, fork).await {
+        Ok(reverted) => reverted,
+        Err(e) => {
+            tracing::warn!("Failed to revert txs on {}: {}", network.name, e);
+            return;
+        }
+    };
+    if reverted.is_empty() {
+        return;
+    }
+
+    for record in reverted {
+        tracing::info!(hash = ?record.hash, fork, "transaction reorged out");
+        iron_broadcast::tx_updated(unmined(record.hash, record.chain_id, TxStatus::Pending)).await;
+        rewatch(db, network, &record).await;
+    }
+    iron_broadcast::ui_notify(UINotify::TxsUpdated).await;
+}
+
+/// Watches a recorded pending transaction again, broadcasting it again first if the node doesn't
+/// know of it anymore
+/// Private transactions go back through the same relay, and are never broadcast publicly, even
+/// if their relay is gone since
+async fn rewatch(db: &'static DB, network: &Network, record: &TxRecord) {
+    let Ok(provider) = network.get_provider() else {
+        return;
+    };
+
+    let payload = db.get_tx_payload(record.hash).await.ok().flatten();
+    let nonce = payload.as_ref().and_then(|(nonce, _)| *nonce);
+
+    let private = db.is_tx_private(record.hash).await.unwrap_or(false);
+    let relay = private.then(|| network.get_private_relay()).flatten();
+
+    let found = match &relay {
+        Some(relay) => relay.get_transaction(record.hash).await,
+        None => provider.get_transaction(record.hash).await,
+    };
+    if let (Ok(None), Some((_, raw))) = (found, payload) {
+        // fails if it was mined or replaced meanwhile, which watching it will tell apart
+        let sent = match (&relay, private) {
+            (Some(relay), _) => relay.send_raw_transaction(raw).await.map(|_| ()),
+            (None, false) => provider.send_raw_transaction(raw).await.map(|_| ()),
+            (None, true) => Ok(()),
+        };
+        match sent {
+            Ok(()) => tracing::info!(hash = ?record.hash, "rebroadcast pending transaction"),
+            Err(e) => tracing::debug!("Failed to rebroadcast {:?}: {}", record.hash, e),
+        }
+    }
+
+    watch(
+        provider,
+        relay,
+        record.hash,
+        record.from,
+        nonce,
+        record.chain_id,
+        None,
+    );
+}
+
+/// Watches a pending transaction in the background, settling it once it's mined, replaced or
+/// dropped
+fn watch(
+    provider: Provider<Failover>,
+    relay: Option<Provider<Http>>,
+    hash: H256,
+    from: Address,
+    nonce: Option<U256>,
+    chain_id: u32,
+    peer: Option<SocketAddr>,
+) {
+    tokio::spawn(async move {
+        let Some(db) = DB.get() else {
+            return;
+        };
+        let wait = wait(db, &provider, relay.as_ref(), hash, from, nonce);
+        let Ok(outcome) = timeout(MAX_WATCH, wait).await else {
+            return;
+        };
+
+        let update = match outcome {
+            Outcome::Mined(receipt) => mined(&receipt, chain_id),
+            Outcome::Replaced => unmined(hash, chain_id, TxStatus::Replaced),
+            Outcome::Dropped => unmined(hash, chain_id, TxStatus::Dropped),
+        };
+
+        // a mined transaction consumed its nonce, even if it reverted. so did whatever replaced
+        // it, which may still be pending under its own tracking
+        if let Some(nonce) = nonce {
+            match update.status {
+                TxStatus::Dropped => nonces::release(from, chain_id, nonce).await,
+                TxStatus::Replaced => (),
+                _ => nonces::confirm(from, chain_id, nonce).await,
+            }
+        }
+
+        settle(&update).await;
+        if let Some(peer) = peer {
+            iron_broadcast::peer_tx_updated(peer, update).await;
+        }
+    });
+}
+
+/// Adds a freshly sent transaction to the activity history as pending, and lets the UI and the
+/// peer that sent it know
+/// Returns the history, for the caller to settle the transaction in later
+pub(crate) async fn record_pending(
+    hash: H256,
+    from: Address,
+    request: &TypedTransaction,
+    network: &Network,
+    peer: Option<SocketAddr>,
+) -> Option<&'static DB> {
+    let Some(db) = DB.get() else {
+        tracing::warn!("tx history not initialized, dropping {:?}", hash);
+        return None;
+    };
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let record = TxRecord {
+        hash,
+        chain_id: network.chain_id,
+        from,
+        to: request.to().and_then(|to| to.as_address()).copied(),
+        value: request.value().copied().unwrap_or_default(),
+        status: TxStatus::Pending,
+        timestamp,
+    };
+
+    if let Err(e) = db.record_tx(&record).await {
+        tracing::warn!("Failed to record tx {:?}: {}", hash, e);
+        return None;
+    }
+
+    let pending = unmined(hash, network.chain_id, TxStatus::Pending);
+    iron_broadcast::tx_updated(pending.clone()).await;
+    if let Some(peer) = peer {
+        iron_broadcast::peer_tx_updated(peer, pending).await;
+    }
+
+    Some(db)
+}
+
+/// Polls for a transaction's receipt until it shows up, or until the transaction is gone
+/// Node errors are treated as transient, and retried on the next poll
+/// Whether it's still pending is asked of the relay it was sent through, if any
+async fn wait(
+    db: &DB,
+    provider: &Provider<Failover>,
+    relay: Option<&Provider<Http>>,
+    hash: H256,
+    from: Address,
+    nonce: Option<U256>,
+) -> Outcome {
+    let mut ticker = interval(POLL_INTERVAL);
+    let mut misses = 0;
+
+    loop {
+        ticker.tick().await;
+
+        if let Ok(Some(receipt)) = provider.get_transaction_receipt(hash).await {
+            return Outcome::Mined(receipt);
+        }
+
+        // sped up or cancelled from here
+        if let Ok(Some(record)) = db.get_tx(hash).await {
+            if record.status == TxStatus::Replaced {
+                return Outcome::Replaced;
+            }
+        }
+
+        let found = match relay {
+            Some(relay) => relay.get_transaction(hash).await,
+            None => provider.get_transaction(hash).await,
+        };
+        match found {
+            Ok(Some(_)) => misses = 0,
+            Ok(None) => misses += 1,
+            Err(_) => (),
+        }
+
+        if misses >= DROP_AFTER_MISSES {
+            // if its nonce was used after all, something else took its place, such as a
+            // transaction sent from another wallet holding the same key
+            let replaced = match (nonce, provider.get_transaction_count(from, None).await) {
+                (Some(nonce), Ok(mined)) => mined > nonce,
+                _ => false,
+            };
+
+            return match replaced {
+                true => Outcome::Replaced,
+                false => Outcome::Dropped,
+            };
+        }
+    }
+}
+
+/// The final update for a mined transaction
+pub(crate) fn mined(receipt: &TransactionReceipt, chain_id: u32) -> TxUpdate {
+    TxUpdate {
+        hash: receipt.transaction_hash,
+        chain_id,
+        status: match receipt.status {
+            Some(status) if status == 1.into() => TxStatus::Confirmed,
+            _ => TxStatus::Failed,
+        },
+        block_number: receipt.block_number.map(|n| n.as_u64()),
+        gas_used: receipt.gas_used,
+    }
+}
+
+/// An update for a transaction that isn't, or will never be, mined
+pub(crate) fn unmined(hash: H256, chain_id: u32, status: TxStatus) -> TxUpdate {
+    TxUpdate {
+        hash,
+        chain_id,
+        status,
+        block_number: None,
+        gas_used: None,
+    }
+}
+
+/// Records the final status of a transaction, and lets the user know about it
+/// The tracker, the block watcher and replacements may all end up here for the same transaction,
+/// but only the first one to settle it notifies
+/// Returns whether this call was that first one
+pub(crate) async fn settle(update: &TxUpdate) -> bool {
+    let Some(db) = DB.get() else {
+        return false;
+    };
+
+    match db.settle_tx(update.hash, update.status).await {
+        Ok(true) => (),
+        Ok(false) => return false,
+        Err(e) => {
+            tracing::warn!("Failed to update status of tx {:?}: {}", update.hash, e);
+            return false;
+        }
+    }
+
+    // kept to tell whether a reorg undid it
+    if let Some(block) = update.block_number {
+        if let Err(e) = db.set_tx_block(update.hash, block).await {
+            tracing::warn!("Failed to record block of tx {:?}: {}", update.hash, e);
+        }
+    }
+
+    let title = match update.status {
+        TxStatus::Confirmed => Some("Transaction confirmed"),
+        TxStatus::Failed => Some("Transaction failed"),
+        TxStatus::Dropped => Some("Transaction dropped"),
+        // replacements are the user's own doing
+        TxStatus::Pending | TxStatus::Replaced => None,
+    };
+
+    if let Some(title) = title {
+        let network = Networks::read()
+            .await
+            .get_network(update.chain_id)
+            .map_or_else(|| update.chain_id.to_string(), |n| n.name);
+        let body = match (update.status, update.block_number) {
+            (TxStatus::Dropped, _) => format!("{:?} was dropped from {}", update.hash, network),
+            (_, Some(block)) => format!(
+                "{:?} was mined in block {} on {}",
+                update.hash, block, network
+            ),
+            (_, None) => format!("{:?} was mined on {}", update.hash, network),
+        };
+
+        iron_broadcast::desktop_notify(title.into(), body).await;
+    }
+
+    iron_broadcast::tx_updated(update.clone()).await;
+    iron_broadcast::ui_notify(UINotify::TxsUpdated).await;
+
+    true
+}
@@ -0,0 +1,53 @@
+//! Transactions sent as an account impersonated on a dev chain
+//! There's no key to sign with, so they're sent to the node unsigned, which anvil accepts for
+//! impersonated accounts. They still go through the same review as any other transaction
+
+use ethers::{
+    providers::Middleware as _,
+    types::{transaction::eip2718::TypedTransaction, Address, H256},
+};
+use iron_networks::{InFlight, Network};
+use iron_types::RequestSource;
+
+use super::{fees, history, send_transaction, Result};
+
+pub(crate) async fn send(
+    mut request: TypedTransaction,
+    from: Address,
+    network: Network,
+    source: Option<RequestSource>,
+) -> Result<H256> {
+    let provider = network.get_provider()?;
+
+    if request.gas().is_none() {
+        let _in_flight = InFlight::start();
+        let gas = provider.estimate_gas(&request, None).await?;
+        request.set_gas(gas * 120 / 100);
+    }
+    let fee_suggestions = fees::fill(&mut request, &network).await;
+
+    // left unsigned for the node to send as the account, so there's nothing a relay would take
+    let _private = send_transaction::review(
+        &mut request,
+        &network,
+        &fee_suggestions,
+        None,
+        false,
+        source.clone(),
+    )
+    .await?;
+
+    let hash = {
+        let _in_flight = InFlight::start();
+        provider
+            .send_transaction(request.clone(), None)
+            .await?
+            .tx_hash()
+    };
+    tracing::info!(hash = ?hash, from = ?from, "impersonated transaction broadcast");
+
+    let peer = source.as_ref().map(|s| s.socket);
+    history::track(hash, from, &request, None, false, &network, peer).await;
+
+    Ok(hash)
+}
@@ -0,0 +1,21 @@
+use iron_db::DB;
+
+use super::{audit, blocklist, gas_tracker, history, nonces, notifier, replace, selectors, tokens};
+
+pub async fn init(db: DB) {
+    audit::init(db.clone());
+    blocklist::init(db.clone());
+    gas_tracker::init(db.clone());
+    history::init(db.clone());
+    notifier::init(db.clone());
+    replace::init(db.clone());
+    selectors::init(db.clone());
+    tokens::init(db);
+    tokio::spawn(async { nonces::receiver().await });
+    tokio::spawn(async { history::resume().await });
+}
+
+/// Clears in-memory caches ahead of the app exiting
+pub async fn shutdown() {
+    nonces::clear().await;
+}
@@ -0,0 +1,132 @@
+//! Generic chain reads for the UI's inspection panels, so that each of them doesn't need its own
+//! command
+//! Reads go through the same cache and network as a connected dapp's would
+
+use ethers::{
+    abi::{AbiParser, Function},
+    types::{Address, BlockId, Bytes, H256, U256},
+};
+use iron_connections::Ctx;
+use iron_types::Json;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+
+use super::{
+    cache, contracts,
+    selectors::{self, DecodedArg},
+    Error, Result,
+};
+
+/// The output of an `eth_call`, decoded when the function's outputs were given
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallOutput {
+    pub data: Bytes,
+    pub decoded: Vec<DecodedArg>,
+}
+
+pub(crate) async fn balance(address: Address, block: Option<BlockId>) -> Result<U256> {
+    read("eth_getBalance", json!([address, block_param(block)])).await
+}
+
+pub(crate) async fn code(address: Address, block: Option<BlockId>) -> Result<Bytes> {
+    read("eth_getCode", json!([address, block_param(block)])).await
+}
+
+pub(crate) async fn storage(address: Address, slot: H256, block: Option<BlockId>) -> Result<H256> {
+    read(
+        "eth_getStorageAt",
+        json!([address, slot, block_param(block)]),
+    )
+    .await
+}
+
+/// Calls `to` with arguments encoded from their text form, according to a human-readable
+/// signature such as `balanceOf(address)(uint256)` or
+/// `function balanceOf(address) view returns (uint256)`
+pub(crate) async fn call(
+    to: Address,
+    signature: &str,
+    args: &[String],
+    block: Option<BlockId>,
+) -> Result<CallOutput> {
+    let function = parse_signature(signature)?;
+    let data = contracts::encode_with(&function, args)?;
+
+    let data: Bytes = read(
+        "eth_call",
+        json!([{ "to": to, "data": data }, block_param(block)]),
+    )
+    .await?;
+    let tokens = function
+        .decode_output(&data)
+        .map_err(|e| Error::InvalidParams(e.to_string()))?;
+
+    Ok(CallOutput {
+        decoded: function
+            .outputs
+            .iter()
+            .zip(tokens)
+            .map(|(param, token)| DecodedArg {
+                name: param.name.clone(),
+                value: selectors::format_token(token),
+            })
+            .collect(),
+        data,
+    })
+}
+
+async fn read<T: DeserializeOwned>(method: &str, params: Json) -> Result<T> {
+    let params = serde_json::from_value(params)?;
+    let response = cache::request(&Ctx::empty(), method, params).await?;
+
+    Ok(serde_json::from_value(response)?)
+}
+
+fn block_param(block: Option<BlockId>) -> Json {
+    block.map_or_else(|| "latest".into(), |b| json!(b))
+}
+
+/// Accepts both Solidity's form and the shorter one tools like `cast` use, where outputs follow
+/// inputs in a second pair of parentheses
+fn parse_signature(signature: &str) -> Result<Function> {
+    let signature = signature.trim();
+    let signature = match signature.strip_prefix("function ") {
+        Some(_) => signature.to_string(),
+        None => match signature.find(")(") {
+            Some(i) => format!(
+                "function {} returns {}",
+                &signature[..=i],
+                &signature[i + 1..]
+            ),
+            None => format!("function {}", signature),
+        },
+    };
+
+    AbiParser::default()
+        .parse_function(&signature)
+        .map_err(|e| Error::InvalidParams(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_short_and_solidity_signatures() {
+        for signature in [
+            "balanceOf(address)(uint256)",
+            "function balanceOf(address) view returns (uint256)",
+        ] {
+            let function = parse_signature(signature).unwrap();
+            assert_eq!(function.name, "balanceOf");
+            assert_eq!(function.inputs.len(), 1);
+            assert_eq!(function.outputs.len(), 1);
+        }
+
+        let function = parse_signature("totalSupply()").unwrap();
+        assert!(function.outputs.is_empty());
+
+        assert!(parse_signature("not a signature").is_err());
+    }
+}
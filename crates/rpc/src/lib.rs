@@ -1,260 +1,380 @@
+mod approvals;
+mod audit;
+mod blocklist;
+mod cache;
 pub mod commands;
+mod contracts;
 mod error;
+mod fees;
+mod gas_tracker;
+mod history;
+mod impersonate;
+mod init;
+mod inspect;
+mod methods;
+mod nonces;
+mod notifier;
+mod offline;
+mod params;
+mod permits;
+mod replace;
+mod router;
+mod safe;
+mod selectors;
 mod send_transaction;
 mod sign_message;
-
-use std::collections::HashMap;
-
-use ethers::{abi::AbiEncode, types::transaction::eip712};
+mod simulate;
+mod siwe;
+mod tokens;
+mod user_ops;
+mod warnings;
+
+use ethers::types::{
+    transaction::{eip2718::TypedTransaction, eip712},
+    Address, Bytes, H256,
+};
 use iron_connections::Ctx;
-use iron_types::GlobalState;
-use iron_wallets::{WalletControl, Wallets};
-use jsonrpc_core::{MetaIoHandler, Params};
-use serde_json::json;
-
-pub use self::error::{Error, Result};
+use iron_networks::Network;
+use iron_types::{ChecksummedAddress, GlobalState};
+use iron_wallets::{auto_lock, Wallet, WalletControl, Wallets};
+
+pub use self::{
+    error::{Error, Result},
+    init::{init, shutdown},
+    warnings::Warning,
+};
 use self::sign_message::SignMessage;
 
+#[derive(Clone)]
 pub struct Handler {
-    io: MetaIoHandler<Ctx>,
     ctx: Ctx,
 }
 
 impl Handler {
-    pub fn new(domain: Option<String>) -> Self {
-        let mut res = Self {
-            io: MetaIoHandler::default(),
-            ctx: Ctx { domain },
-        };
-        res.add_handlers();
-        res
+    pub fn new(ctx: Ctx) -> Self {
+        Self { ctx }
     }
 
     pub async fn handle(&self, request: String) -> Option<String> {
-        self.io.handle_request(&request, self.ctx.clone()).await
+        router::handle(&request, self.ctx.clone()).await
     }
 
-    fn add_handlers(&mut self) {
-        macro_rules! self_handler {
-            ($name:literal, $fn:path) => {
-                self.io
-                    .add_method_with_meta($name, |params: Params, ctx: Ctx| async move {
-                        $fn(params, ctx).await
-                    });
-            };
+    /// The accounts a connection gets to see
+    /// Origins scoped to a single account only ever see that one
+    pub async fn visible_accounts(ctx: &Ctx) -> Vec<ChecksummedAddress> {
+        match ctx.scoped_account().await {
+            Some(account) => vec![account],
+            None => Wallets::read().await.get_current_accounts().await,
         }
+    }
 
-        macro_rules! provider_handler {
-            ($name:literal) => {
-                self.io
-                    .add_method_with_meta($name, |params: Params, ctx: Ctx| async move {
-                        tracing::debug!("{} {:?}", $name, params);
+    /// Asks the user to confirm adding or switching to a chain
+    async fn confirm_chain_change(params: serde_json::Value, ctx: &Ctx) -> Result<()> {
+        use iron_dialogs::{Dialog, DialogMsg};
 
-                        let provider = ctx.network().await.get_provider();
+        let dialog = Dialog::with_source("chain-change", params, ctx.source.clone());
+        dialog.open().await?;
 
-                        let res: jsonrpc_core::Result<serde_json::Value> = provider
-                            .request::<_, serde_json::Value>($name, params)
-                            .await
-                            .map_err(error::ethers_to_jsonrpc_error);
-                        res
-                    });
-            };
+        match dialog.recv().await {
+            Some(DialogMsg::Accept(_)) => Ok(()),
+            _ => Err(Error::ChainChangeRejected),
         }
-
-        // gossip methods
-        provider_handler!("eth_blockNumber");
-        provider_handler!("eth_sendRawTransaction");
-
-        // state methods
-        // delegate directly to provider
-        provider_handler!("eth_getBalance");
-        provider_handler!("eth_getStorageAt");
-        provider_handler!("eth_getTransactionCount");
-        provider_handler!("eth_getCode");
-        provider_handler!("eth_call");
-        provider_handler!("eth_estimateGas");
-        provider_handler!("eth_protocolVersion");
-        provider_handler!("eth_syncing");
-        provider_handler!("eth_mining");
-
-        // TODO: handle this one internally
-        provider_handler!("net_version");
-
-        // history methods
-        // delegate directly to provider
-        provider_handler!("eth_getBlockTransactionCountByHash");
-        provider_handler!("eth_getBlockTransactionCountByNumber");
-        provider_handler!("eth_getUncleCountByBlockHash");
-        provider_handler!("eth_getUncleCountByBlockNumber");
-        provider_handler!("eth_getBlockByHash");
-        provider_handler!("eth_getBlockByNumber");
-        provider_handler!("eth_getTransactionByHash");
-        provider_handler!("eth_getTransactionByBlockHashAndIndex");
-        provider_handler!("eth_getTransactionByBlockNumberAndIndex");
-        provider_handler!("eth_getTransactionReceipt");
-        provider_handler!("eth_getUncleByBlockHashAndIndex");
-        provider_handler!("eth_getUncleByBlockNumberAndIndex");
-
-        // filter methods
-        // delegate directly to provider
-        provider_handler!("eth_newFilter");
-        provider_handler!("eth_newBlockFilter");
-        provider_handler!("eth_newPendingFilter");
-        provider_handler!("eth_uninstallFilter");
-        provider_handler!("eth_getFilterLogs");
-        provider_handler!("eth_getLogs");
-
-        // handle internally
-        self_handler!("eth_accounts", Self::accounts);
-        self_handler!("eth_requestAccounts", Self::accounts);
-        self_handler!("eth_chainId", Self::chain_id);
-        self_handler!("eth_sendTransaction", Self::send_transaction);
-        self_handler!("eth_sign", Self::eth_sign);
-        self_handler!("personal_sign", Self::eth_sign);
-        self_handler!("eth_signTypedData", Self::eth_sign_typed_data_v4);
-        self_handler!("eth_signTypedData_v4", Self::eth_sign_typed_data_v4);
-        self_handler!("wallet_switchEthereumChain", Self::switch_chain);
-
-        // metamask
-        self_handler!("metamask_getProviderState", Self::provider_state);
-
-        // not yet implemented
-        self_handler!("web3_clientVersion", Self::unimplemented);
-        self_handler!("web3_sha3", Self::unimplemented);
-        self_handler!("net_listening", Self::unimplemented);
-        self_handler!("net_peerCount", Self::unimplemented);
-        self_handler!("eth_gasPrice", Self::unimplemented);
-        self_handler!("eth_signTransaction", Self::unimplemented);
     }
 
-    async fn accounts(_: Params, _: Ctx) -> jsonrpc_core::Result<serde_json::Value> {
-        let wallets = Wallets::read().await;
-        let address = wallets.get_current_wallet().get_current_address().await;
+    async fn send_transaction<T: Into<serde_json::Value>>(params: T, ctx: Ctx) -> Result<H256> {
+        let request = params::transaction_request(params.into())?;
 
-        Ok(json!([address]))
+        Self::send_request(request, ctx).await
     }
 
-    async fn chain_id(_: Params, ctx: Ctx) -> jsonrpc_core::Result<serde_json::Value> {
-        let network = ctx.network().await;
-        Ok(json!(network.chain_id_hex()))
-    }
+    /// Sends a transaction from the current wallet
+    /// Requests from a Safe whose owner the wallet can sign for are proposed to the Safe
+    /// transaction service instead, since the Safe itself can't sign anything
+    async fn send_request(request: TypedTransaction, ctx: Ctx) -> Result<H256> {
+        let from = Self::signing_account(ctx.scoped_account().await, request.from())?;
+
+        // accounts impersonated on a dev chain have no wallet behind them to sign with
+        if let Some(from) = from {
+            let network = ctx.network().await?;
+            if iron_networks::cheatcodes::is_impersonating(network.chain_id, from) {
+                auto_lock::ensure_unlocked()?;
+                return impersonate::send(request, from, network, ctx.source).await;
+            }
+        }
 
-    async fn provider_state(_: Params, ctx: Ctx) -> jsonrpc_core::Result<serde_json::Value> {
-        let wallets = Wallets::read().await;
+        let wallet_path = match Self::current_wallet_path(from.as_ref(), &ctx).await {
+            Err(Error::UnknownAccount(from)) => {
+                return Self::send_user_operation(request, from, ctx).await
+            }
+            res => res?,
+        };
 
-        let network = ctx.network().await;
-        let address = wallets.get_current_wallet().get_current_address().await;
+        let can_sign = Wallets::read()
+            .await
+            .get_current_wallet()
+            .ensure_can_sign(&wallet_path);
+        if let Err(e) = can_sign {
+            let from = match request.from() {
+                Some(from) => Some(*from),
+                None => Wallets::read()
+                    .await
+                    .get_current_wallet()
+                    .get_address_for_path(&wallet_path)
+                    .await
+                    .map(Into::into),
+            };
+            let network = ctx.network().await?;
+
+            if let Some(from) = from {
+                if safe::detect(&network, from).await?.is_some() {
+                    let tx = safe::SafeTx::try_from(&request)?;
+                    return safe::propose(network, from, tx, ctx.source).await;
+                }
+            }
+            return Err(e.into());
+        }
 
-        Ok(json!({
-            "isUnlocked": true,
-            "chainId": network.chain_id_hex(),
-            "networkVersion": network.chain_id.to_string(),
-            "accounts": [address],
-        }))
+        Self::send_transaction_with(request, ctx, wallet_path).await
     }
 
-    #[tracing::instrument()]
-    async fn switch_chain(params: Params, mut ctx: Ctx) -> jsonrpc_core::Result<serde_json::Value> {
-        let params = params.parse::<Vec<HashMap<String, String>>>().unwrap();
-        let chain_id_str = params[0].get("chainId").unwrap().clone();
-        let new_chain_id = u32::from_str_radix(&chain_id_str[2..], 16).unwrap();
+    /// Sends a transaction from the active account's ERC-4337 smart account
+    /// Any other sender outside of the current wallet is unknown
+    async fn send_user_operation(
+        request: TypedTransaction,
+        from: Address,
+        ctx: Ctx,
+    ) -> Result<H256> {
+        let network = ctx.network().await?;
+        let wallet = Self::current_wallet().await?;
 
-        Ok(ctx
-            .switch_chain(new_chain_id)
-            .await
-            .map(|_| serde_json::Value::Null)
-            .map_err(Error::Connection)?)
+        if !user_ops::has_bundler(&network) {
+            return Err(Error::UnknownAccount(from));
+        }
+        let owner = wallet.get_current_address().await.into();
+        if user_ops::smart_account(&network, owner).await?.address != from {
+            return Err(Error::UnknownAccount(from));
+        }
+
+        let wallet_path = wallet.get_current_path();
+        user_ops::send(request, &network, &wallet, wallet_path, ctx.source).await
     }
 
-    async fn send_transaction<T: Into<serde_json::Value>>(
-        params: T,
+    /// Sends a transaction from a specific account of the current wallet
+    /// Unlike switching accounts, this does not change the active account, nor notify any peer
+    pub async fn send_transaction_with(
+        request: TypedTransaction,
         ctx: Ctx,
-    ) -> jsonrpc_core::Result<serde_json::Value> {
+        wallet_path: String,
+    ) -> Result<H256> {
         use send_transaction::SendTransaction;
 
-        let wallets = Wallets::read().await;
-
-        let network = ctx.network().await;
-        let wallet = wallets.get_current_wallet();
+        let wallet = Self::current_wallet().await?;
+        let network = ctx.network().await?;
+        let request = Self::prepare_request(request, &wallet, &wallet_path, &network).await?;
 
+        let chain_id = network.chain_id;
         let mut sender = SendTransaction::build()
-            .set_wallet(wallet)
-            .set_wallet_path(wallet.get_current_path())
+            .set_wallet(&wallet)
+            .set_wallet_path(wallet_path)
             .set_network(network)
-            .set_request(params.into())
+            .set_request(request)
+            .set_source(ctx.source)
             .build();
 
-        let result = sender.estimate_gas().await.finish().await;
+        let res = sender
+            .estimate_gas()
+            .await
+            .fill_fees()
+            .await
+            .finish()
+            .await?;
+        cache::invalidate(chain_id);
 
-        match result {
-            Ok(res) => Ok(res.tx_hash().encode_hex().into()),
-            Err(e) => Ok(e.to_string().into()),
-        }
+        Ok(res.tx_hash())
     }
 
-    async fn eth_sign(params: Params, ctx: Ctx) -> jsonrpc_core::Result<serde_json::Value> {
-        use sign_message::*;
+    /// Signs a transaction from the current wallet without broadcasting it
+    /// Safes, smart accounts and impersonated accounts have no key to sign with, so they're unknown
+    async fn sign_request(request: TypedTransaction, ctx: Ctx) -> Result<Bytes> {
+        let wallet_path = Self::current_wallet_path(request.from(), &ctx).await?;
 
-        let params = params.parse::<Vec<Option<String>>>().unwrap();
-        let msg = params[0].as_ref().cloned().unwrap();
-        // TODO where should this be used?
-        // let address = Address::from_str(&params[1].as_ref().cloned().unwrap()).unwrap();
+        Self::sign_transaction_with(request, ctx, wallet_path).await
+    }
 
-        let wallets = Wallets::read().await;
+    /// Reviews and signs a transaction from a specific account of the current wallet, without
+    /// broadcasting it, returning the raw signed bytes
+    pub async fn sign_transaction_with(
+        request: TypedTransaction,
+        ctx: Ctx,
+        wallet_path: String,
+    ) -> Result<Bytes> {
+        use send_transaction::SendTransaction;
 
-        let network = ctx.network().await;
-        let wallet = wallets.get_current_wallet();
+        let wallet = Self::current_wallet().await?;
+        let network = ctx.network().await?;
+        let request = Self::prepare_request(request, &wallet, &wallet_path, &network).await?;
 
-        let mut signer = SignMessage::build()
-            .set_wallet(wallet)
-            .set_wallet_path(wallet.get_current_path())
+        let mut signer = SendTransaction::build()
+            .set_wallet(&wallet)
+            .set_wallet_path(wallet_path)
             .set_network(network)
-            .set_string_data(msg)
+            .set_request(request)
+            .set_source(ctx.source)
             .build();
 
-        // TODO: ensure from == signer
-
-        let result = signer.finish().await;
+        signer
+            .estimate_gas()
+            .await
+            .fill_fees()
+            .await
+            .finish_signing()
+            .await
+    }
 
-        match result {
-            Ok(res) => Ok(format!("0x{}", res).into()),
-            Err(e) => Ok(e.to_string().into()),
+    /// Checks a transaction is meant for the connection's network, defaulting its chain ID and
+    /// sender, and adapting it to the network's fee market
+    async fn prepare_request(
+        mut request: TypedTransaction,
+        wallet: &Wallet,
+        wallet_path: &str,
+        network: &Network,
+    ) -> Result<TypedTransaction> {
+        match request.chain_id() {
+            Some(chain_id) if chain_id.as_u64() != network.chain_id as u64 => {
+                return Err(Error::ChainIdMismatch(chain_id.as_u64(), network.chain_id))
+            }
+            Some(_) => (),
+            None => {
+                request.set_chain_id(network.chain_id);
+            }
+        }
+        if request.from().is_none() {
+            if let Some(from) = wallet.get_address_for_path(wallet_path).await {
+                request.set_from(from.into());
+            }
         }
+
+        Ok(fees::adapt_to_network(request, network).await)
+    }
+
+    /// Signs a message with a specific account of the current wallet
+    /// Unlike switching accounts, this does not change the active account, nor notify any peer
+    pub async fn eth_sign_with(msg: String, ctx: Ctx, wallet_path: String) -> Result<String> {
+        Self::sign_with(msg, "personal_sign", ctx, wallet_path).await
     }
 
-    async fn eth_sign_typed_data_v4(
-        params: Params,
+    async fn sign_with(
+        msg: String,
+        method: &'static str,
         ctx: Ctx,
-    ) -> jsonrpc_core::Result<serde_json::Value> {
-        let params = params.parse::<Vec<Option<String>>>().unwrap();
-        // TODO where should this be used?
-        // let address = Address::from_str(&params[0].as_ref().cloned().unwrap()).unwrap();
-        let data = params[1].as_ref().cloned().unwrap();
-        let typed_data: eip712::TypedData = serde_json::from_str(&data).unwrap();
+        wallet_path: String,
+    ) -> Result<String> {
+        let wallet = Self::current_wallet().await?;
+        let network = ctx.network().await?;
 
-        let wallets = Wallets::read().await;
+        let mut signer = SignMessage::build()
+            .set_wallet(&wallet)
+            .set_wallet_path(wallet_path)
+            .set_network(network)
+            .set_string_data(msg)
+            .set_method(method)
+            .set_source(ctx.source)
+            .build();
+
+        Ok(format!("0x{}", signer.finish().await?))
+    }
 
-        let wallet = wallets.get_current_wallet();
-        let network = ctx.network().await;
+    /// Signs typed data with a specific account of the current wallet
+    /// Unlike switching accounts, this does not change the active account, nor notify any peer
+    pub async fn eth_sign_typed_data_with(
+        typed_data: eip712::TypedData,
+        ctx: Ctx,
+        wallet_path: String,
+    ) -> Result<String> {
+        let wallet = Self::current_wallet().await?;
+        let network = ctx.network().await?;
 
         let mut signer = SignMessage::build()
-            .set_wallet(wallet)
-            .set_wallet_path(wallet.get_current_path())
+            .set_wallet(&wallet)
+            .set_wallet_path(wallet_path)
             .set_network(network)
             .set_typed_data(typed_data)
+            .set_source(ctx.source)
             .build();
 
-        let result = signer.finish().await;
+        Ok(format!("0x{}", signer.finish().await?))
+    }
+
+    /// A snapshot of the current wallet, unless the app is locked
+    /// Signing waits on the user, so it works on a copy rather than holding on to the wallets,
+    /// which would keep the UI from switching accounts or unlocking another wallet meanwhile
+    async fn current_wallet() -> Result<Wallet> {
+        auto_lock::ensure_unlocked()?;
+
+        Ok(Wallets::read().await.get_current_wallet().clone())
+    }
 
-        match result {
-            Ok(res) => Ok(format!("0x{}", res).into()),
-            Err(e) => Ok(e.to_string().into()),
+    /// Picks the account of the current wallet to sign with, unless the app is locked
+    async fn current_wallet_path(address: Option<&Address>, ctx: &Ctx) -> Result<String> {
+        auto_lock::ensure_unlocked()?;
+        let address = Self::signing_account(ctx.scoped_account().await, address)?;
+
+        let wallets = Wallets::read().await;
+        Self::wallet_path(wallets.get_current_wallet(), address.as_ref()).await
+    }
+
+    /// The account a request signs with, if it doesn't default to the active one
+    /// Origins scoped to a single account sign with that one, and may not name any other
+    fn signing_account(
+        scoped: Option<ChecksummedAddress>,
+        address: Option<&Address>,
+    ) -> Result<Option<Address>> {
+        match (scoped.map(Address::from), address) {
+            (Some(scoped), Some(address)) if scoped != *address => Err(Error::Unauthorized),
+            (Some(scoped), _) => Ok(Some(scoped)),
+            (None, address) => Ok(address.copied()),
         }
     }
 
-    async fn unimplemented(params: Params, _: Ctx) -> jsonrpc_core::Result<serde_json::Value> {
-        tracing::warn!("unimplemented method called: {:?}", params);
+    /// Picks the account to sign with
+    /// If the request names an account, it must belong to the current wallet. Otherwise, the
+    /// active account is used
+    async fn wallet_path(wallet: &Wallet, address: Option<&Address>) -> Result<String> {
+        match address {
+            Some(address) => wallet
+                .get_path_for_address(*address)
+                .await
+                .ok_or(Error::UnknownAccount(*address)),
+            None => Ok(wallet.get_current_path()),
+        }
+    }
+}
 
-        Err(jsonrpc_core::Error::internal_error())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoped_origins_sign_with_their_account() {
+        let scoped = Address::repeat_byte(1);
+        let other = Address::repeat_byte(2);
+
+        assert_eq!(
+            Handler::signing_account(Some(scoped.into()), None).unwrap(),
+            Some(scoped)
+        );
+        assert_eq!(
+            Handler::signing_account(Some(scoped.into()), Some(&scoped)).unwrap(),
+            Some(scoped)
+        );
+        assert!(matches!(
+            Handler::signing_account(Some(scoped.into()), Some(&other)),
+            Err(Error::Unauthorized)
+        ));
+        assert_eq!(
+            Handler::signing_account(None, Some(&other)).unwrap(),
+            Some(other)
+        );
+        assert_eq!(Handler::signing_account(None, None).unwrap(), None);
     }
 }
@@ -0,0 +1,393 @@
+//! Request and response types of the methods the wallet answers itself
+
+use async_trait::async_trait;
+use ethers::types::{
+    transaction::{eip2718::TypedTransaction, eip712},
+    Address, Bytes, H256,
+};
+use iron_connections::Ctx;
+use iron_networks::{AddChainParams, Networks};
+use iron_types::{ChecksummedAddress, GlobalState};
+use jsonrpc_core::Params;
+use serde::Serialize;
+use serde_json::json;
+
+use super::{blocklist, params, router::Method, tokens, Error, Handler, Result};
+
+// methods that take no params
+pub(crate) struct ClientVersion;
+pub(crate) struct Accounts;
+pub(crate) struct RequestAccounts;
+pub(crate) struct ChainId;
+pub(crate) struct ProviderState;
+
+#[async_trait]
+impl Method for ClientVersion {
+    type Response = String;
+
+    fn parse(_: Params) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn call(self, _: Ctx) -> Result<String> {
+        Ok(format!("Iron/v{}", env!("CARGO_PKG_VERSION")))
+    }
+}
+
+#[async_trait]
+impl Method for Accounts {
+    type Response = Vec<ChecksummedAddress>;
+
+    fn parse(_: Params) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<Self::Response> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        Ok(Handler::visible_accounts(&ctx).await)
+    }
+}
+
+/// Asks the user to connect the requesting site, if it isn't yet, before sharing accounts
+#[async_trait]
+impl Method for RequestAccounts {
+    type Response = Vec<ChecksummedAddress>;
+
+    fn parse(_: Params) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<Self::Response> {
+        let phishing = match ctx.source {
+            Some(ref source) => blocklist::is_phishing_origin(&source.origin).await,
+            None => false,
+        };
+        if !ctx.request_approval(phishing).await {
+            return Err(Error::ConnectionRejected);
+        }
+
+        Ok(Handler::visible_accounts(&ctx).await)
+    }
+}
+
+#[async_trait]
+impl Method for ChainId {
+    type Response = String;
+
+    fn parse(_: Params) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<String> {
+        Ok(ctx.network().await?.chain_id_hex())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProviderStateResponse {
+    is_unlocked: bool,
+    chain_id: String,
+    network_version: String,
+    accounts: Vec<ChecksummedAddress>,
+}
+
+#[async_trait]
+impl Method for ProviderState {
+    type Response = ProviderStateResponse;
+
+    fn parse(_: Params) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<Self::Response> {
+        let network = ctx.network().await?;
+        let accounts = match ctx.is_approved().await {
+            true => Handler::visible_accounts(&ctx).await,
+            false => vec![],
+        };
+
+        Ok(ProviderStateResponse {
+            is_unlocked: true,
+            chain_id: network.chain_id_hex(),
+            network_version: network.chain_id.to_string(),
+            accounts,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SwitchChain {
+    chain_id: u32,
+}
+
+#[async_trait]
+impl Method for SwitchChain {
+    type Response = ();
+
+    fn parse(params: Params) -> Result<Self> {
+        Ok(Self {
+            chain_id: params::switch_chain_id(params)?,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(chain_id = self.chain_id))]
+    async fn call(self, mut ctx: Ctx) -> Result<()> {
+        // dapps expect this specific error to know they should call `wallet_addEthereumChain`
+        let Some(network) = Networks::read().await.get_network(self.chain_id) else {
+            return Err(Error::UnrecognizedChain(self.chain_id));
+        };
+
+        if ctx.chain_id().await? != self.chain_id {
+            let params = json!({
+                "action": "switch",
+                "chainId": self.chain_id,
+                "chainName": network.name,
+            });
+            Handler::confirm_chain_change(params, &ctx).await?;
+        }
+
+        Ok(ctx.switch_chain(self.chain_id).await?)
+    }
+}
+
+/// Registers a chain requested by a dapp, and switches to it
+/// EIP-3085 allows doing both at once, which saves the user a second prompt
+#[derive(Debug)]
+pub(crate) struct AddChain {
+    chain: AddChainParams,
+}
+
+#[async_trait]
+impl Method for AddChain {
+    type Response = ();
+
+    fn parse(params: Params) -> Result<Self> {
+        Ok(Self {
+            chain: params::add_chain(params)?,
+        })
+    }
+
+    // RPC URLs can carry API keys, so the params stay out of the logs
+    #[tracing::instrument(skip_all)]
+    async fn call(self, mut ctx: Ctx) -> Result<()> {
+        let chain_id = self.chain.parsed_chain_id()?;
+        let known = Networks::read().await.get_network(chain_id);
+
+        // known chains are only switched to, which the user confirms just like `SwitchChain`
+        // They keep our own RPC URL, so whichever ones the dapp sent don't matter
+        match known {
+            None => {
+                self.chain.verify_rpc().await?;
+
+                let mut params = serde_json::to_value(&self.chain)?;
+                params["action"] = "add".into();
+                Handler::confirm_chain_change(params, &ctx).await?;
+
+                Networks::write()
+                    .await
+                    .add_ethereum_chain(self.chain, false)
+                    .await?;
+            }
+            Some(network) if ctx.chain_id().await? != chain_id => {
+                let params = json!({
+                    "action": "switch",
+                    "chainId": chain_id,
+                    "chainName": network.name,
+                });
+                Handler::confirm_chain_change(params, &ctx).await?;
+            }
+            Some(_) => (),
+        }
+
+        Ok(ctx.switch_chain(chain_id).await?)
+    }
+}
+
+/// Adds an ERC-20 token requested by a dapp to the current network's watch list (EIP-747)
+/// The user confirms it first, and a rejection is answered with `false` rather than an error
+pub(crate) struct WatchAsset {
+    contract: Address,
+}
+
+#[async_trait]
+impl Method for WatchAsset {
+    type Response = bool;
+
+    fn parse(params: Params) -> Result<Self> {
+        Ok(Self {
+            contract: params::watch_asset(params)?,
+        })
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<bool> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        use iron_dialogs::{Dialog, DialogMsg};
+
+        let network = ctx.network().await?;
+        let client = std::sync::Arc::new(network.get_provider()?);
+        let metadata = tokens::fetch_metadata(client, self.contract).await?;
+
+        let params = json!({
+            "address": self.contract,
+            "chainName": network.name,
+            "name": metadata.name,
+            "symbol": metadata.symbol,
+            "decimals": metadata.decimals,
+        });
+        let dialog = Dialog::with_source("watch-asset", params, ctx.source.clone());
+        dialog.open().await?;
+
+        if !matches!(dialog.recv().await, Some(DialogMsg::Accept(_))) {
+            return Ok(false);
+        }
+
+        tokens::watch_with(&network, self.contract, metadata).await?;
+        Ok(true)
+    }
+}
+
+/// Like every signing method, only answered for origins the user connected
+pub(crate) struct SendTransaction {
+    request: TypedTransaction,
+}
+
+#[async_trait]
+impl Method for SendTransaction {
+    type Response = H256;
+
+    fn parse(params: Params) -> Result<Self> {
+        Ok(Self {
+            request: params::transaction_request(params.into())?,
+        })
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<H256> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        Handler::send_request(self.request, ctx).await
+    }
+}
+
+/// Signing without sending lets a dapp hold on to a transaction indefinitely, so only connected
+/// ones may ask, and the user reviews it like any other
+pub(crate) struct SignTransaction {
+    request: TypedTransaction,
+}
+
+#[async_trait]
+impl Method for SignTransaction {
+    type Response = Bytes;
+
+    fn parse(params: Params) -> Result<Self> {
+        Ok(Self {
+            request: params::transaction_request(params.into())?,
+        })
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<Bytes> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        Handler::sign_request(self.request, ctx).await
+    }
+}
+
+/// `eth_sign` takes `[address, message]`
+pub(crate) struct EthSign {
+    address: Option<Address>,
+    message: String,
+}
+
+#[async_trait]
+impl Method for EthSign {
+    type Response = String;
+
+    fn parse(params: Params) -> Result<Self> {
+        let params = params.parse::<Vec<Option<String>>>()?;
+
+        Ok(Self {
+            address: params::nth_address(&params, 0)?,
+            message: params::nth_string(&params, 1)?,
+        })
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<String> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        let wallet_path = Handler::current_wallet_path(self.address.as_ref(), &ctx).await?;
+        Handler::sign_with(self.message, "eth_sign", ctx, wallet_path).await
+    }
+}
+
+/// `personal_sign` takes the same params as `eth_sign`, in reverse order
+pub(crate) struct PersonalSign {
+    message: String,
+    address: Option<Address>,
+}
+
+#[async_trait]
+impl Method for PersonalSign {
+    type Response = String;
+
+    fn parse(params: Params) -> Result<Self> {
+        let params = params.parse::<Vec<Option<String>>>()?;
+
+        Ok(Self {
+            message: params::nth_string(&params, 0)?,
+            address: params::nth_address(&params, 1)?,
+        })
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<String> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        let wallet_path = Handler::current_wallet_path(self.address.as_ref(), &ctx).await?;
+        Handler::eth_sign_with(self.message, ctx, wallet_path).await
+    }
+}
+
+/// `eth_signTypedData` and `eth_signTypedData_v4` take `[address, typedData]`, with the typed
+/// data JSON-encoded as a string
+pub(crate) struct SignTypedData {
+    address: Option<Address>,
+    typed_data: eip712::TypedData,
+}
+
+#[async_trait]
+impl Method for SignTypedData {
+    type Response = String;
+
+    fn parse(params: Params) -> Result<Self> {
+        let params = params.parse::<Vec<Option<String>>>()?;
+        let data = params::nth_string(&params, 1)?;
+
+        Ok(Self {
+            address: params::nth_address(&params, 0)?,
+            typed_data: serde_json::from_str(&data)
+                .map_err(|e| Error::InvalidParams(e.to_string()))?,
+        })
+    }
+
+    async fn call(self, ctx: Ctx) -> Result<String> {
+        if !ctx.is_approved().await {
+            return Err(Error::Unauthorized);
+        }
+
+        let wallet_path = Handler::current_wallet_path(self.address.as_ref(), &ctx).await?;
+        Handler::eth_sign_typed_data_with(self.typed_data, ctx, wallet_path).await
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber, U256},
+};
+use iron_broadcast::InternalMsg;
+use iron_networks::{InFlight, Network};
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
+
+use super::Result;
+
+/// Local nonce bookkeeping for each account, keyed by `(address, chain_id)`
+/// Kept locally so that transactions sent in quick succession don't collide on the RPC's pending
+/// nonce. Each account has its own lock, so syncing one doesn't hold up the others
+static NONCES: Lazy<std::sync::Mutex<HashMap<(Address, u32), Slot>>> = Lazy::new(Default::default);
+
+/// An account's nonces, or `None` until they're synced with the chain
+type Slot = Arc<Mutex<Option<AccountNonces>>>;
+
+#[derive(Debug, Default)]
+struct AccountNonces {
+    /// The next nonce to hand out
+    next: U256,
+
+    /// Nonces handed out whose transactions haven't been mined or dropped yet
+    pending: BTreeSet<U256>,
+}
+
+impl AccountNonces {
+    /// Gives back a nonce that was never used
+    /// Returns whether the state should be dropped, and resynced with the chain next time
+    fn release(&mut self, nonce: U256) -> bool {
+        self.pending.remove(&nonce);
+
+        // the latest nonce can be reused right away. anything older leaves a gap that only the
+        // chain can tell us about
+        if nonce + 1 == self.next {
+            self.next = nonce;
+            false
+        } else {
+            self.pending.is_empty()
+        }
+    }
+
+    /// Marks a nonce as consumed on-chain
+    /// Once nothing is pending, the state is dropped, so that transactions sent from elsewhere are
+    /// picked up on the next resync
+    fn confirm(&mut self, nonce: U256) -> bool {
+        self.pending.remove(&nonce);
+        self.pending.is_empty()
+    }
+}
+
+/// Gets the next nonce for an account, and reserves it
+/// The on-chain pending nonce is only fetched while nothing is pending locally, and incremented
+/// locally afterwards
+pub(crate) async fn next_nonce(address: Address, network: &Network) -> Result<U256> {
+    // the account's lock is held across the fetch so concurrent requests can't get the same nonce
+    let slot = slot(address, network.chain_id);
+    let mut nonces = slot.lock().await;
+
+    if nonces.is_none() {
+        let _in_flight = InFlight::start();
+        let next = network
+            .get_provider()?
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await?;
+        *nonces = Some(AccountNonces {
+            next,
+            ..Default::default()
+        });
+    }
+
+    let state = nonces.as_mut().unwrap();
+    let nonce = state.next;
+    state.next = nonce + 1;
+    state.pending.insert(nonce);
+
+    Ok(nonce)
+}
+
+/// Gives back a reserved nonce whose transaction was never sent, or was dropped from the mempool
+pub(crate) async fn release(address: Address, chain_id: u32, nonce: U256) {
+    let slot = slot(address, chain_id);
+    let mut nonces = slot.lock().await;
+
+    if nonces.as_mut().is_some_and(|state| state.release(nonce)) {
+        *nonces = None;
+    }
+}
+
+/// Marks a reserved nonce as mined
+pub(crate) async fn confirm(address: Address, chain_id: u32, nonce: U256) {
+    let slot = slot(address, chain_id);
+    let mut nonces = slot.lock().await;
+
+    if nonces.as_mut().is_some_and(|state| state.confirm(nonce)) {
+        *nonces = None;
+    }
+}
+
+/// Forgets all cached nonces
+/// Slots are emptied rather than removed, so no one ends up syncing an account on a stale one
+pub(crate) async fn clear() {
+    let slots: Vec<_> = NONCES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+
+    for slot in slots {
+        *slot.lock().await = None;
+    }
+}
+
+/// The lock guarding an account's nonces on a chain
+fn slot(address: Address, chain_id: u32) -> Slot {
+    NONCES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry((address, chain_id))
+        .or_default()
+        .clone()
+}
+
+/// Clears the whole cache whenever the active network or account changes
+pub(crate) async fn receiver() -> ! {
+    let mut rx = iron_broadcast::subscribe_internal().await;
+
+    loop {
+        if let Ok(msg) = rx.recv().await {
+            use InternalMsg::*;
+
+            if let CurrentNetworkChanged(_) | CurrentAddressChanged(_) = msg {
+                clear().await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(next: u64, pending: &[u64]) -> AccountNonces {
+        AccountNonces {
+            next: next.into(),
+            pending: pending.iter().map(|n| U256::from(*n)).collect(),
+        }
+    }
+
+    #[test]
+    fn releasing_the_latest_nonce_reuses_it() {
+        let mut nonces = state(7, &[5, 6]);
+
+        // 5 is still pending, so only 6 is handed out again
+        assert!(!nonces.release(6.into()));
+        assert_eq!(nonces.next, 6.into());
+    }
+
+    #[test]
+    fn releasing_an_older_nonce_resyncs_once_idle() {
+        let mut nonces = state(7, &[5, 6]);
+
+        assert!(!nonces.release(5.into()));
+        assert_eq!(nonces.next, 7.into());
+        assert!(nonces.confirm(6.into()));
+    }
+}
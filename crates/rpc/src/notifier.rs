@@ -0,0 +1,256 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use ethers::{prelude::*, utils::format_units};
+use iron_db::DB;
+use iron_networks::{Network, Networks};
+use iron_types::{GlobalState, UINotify};
+use iron_wallets::{WalletControl, Wallets};
+use once_cell::sync::{Lazy, OnceCell};
+use tokio::time::{interval, Duration};
+
+use super::{cache, history, tokens, Error, Result};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+/// Hashes of the latest blocks scanned on each network, by chain ID and block number
+/// A block whose hash changed since was reorganized away, along with every block after it
+static RECENT_BLOCKS: Lazy<Mutex<HashMap<u32, BTreeMap<u64, H256>>>> = Lazy::new(Default::default);
+
+/// Blocks remembered per network. Reorgs deeper than this go unnoticed
+const REORG_DEPTH: u64 = 64;
+
+/// How often the current network is checked for new blocks
+const POLL_INTERVAL: Duration = Duration::from_secs(12);
+
+/// Blocks scanned at most when catching up, so that reopening the app after a while doesn't
+/// flood the user with old transfers
+const MAX_LOOKBACK: u64 = 100;
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+    tokio::spawn(async { watcher().await });
+}
+
+fn db() -> Result<&'static DB> {
+    DB.get().ok_or(Error::NotInitialized("notifier"))
+}
+
+/// Scans every new block of the current network for transfers to the current wallet's accounts,
+/// and for transactions we sent getting mined
+/// Blocks already scanned are checked for having been reorganized away, in which case whatever
+/// was derived from them is rolled back, and they're scanned again
+async fn watcher() {
+    let mut ticker = interval(POLL_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let Ok(network) = Networks::read().await.get_current().cloned() else {
+            continue;
+        };
+        if let Err(e) = scan(&network).await {
+            tracing::warn!("Failed to scan {} for transfers: {}", network.name, e);
+        }
+    }
+}
+
+async fn scan(network: &Network) -> Result<()> {
+    let db = db()?;
+    let provider = Arc::new(network.get_provider()?);
+    let head = provider.get_block_number().await?.as_u64();
+
+    if let Some(fork) = find_fork(network.chain_id, &provider, head).await? {
+        roll_back(db, network, fork).await?;
+    }
+
+    // the first scan of a network starts at its head, rather than notifying about its past
+    let from = match db.get_last_known_block(network.chain_id).await? {
+        Some(last) if last >= head => return Ok(()),
+        Some(last) => (last + 1).max(head.saturating_sub(MAX_LOOKBACK)),
+        None => head,
+    };
+
+    let accounts: Vec<Address> = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_all_addresses()
+        .await
+        .into_iter()
+        .map(|(_, a)| a.into())
+        .collect();
+
+    let mut received = false;
+    for number in from..=head {
+        let Some(block) = provider.get_block_with_txs(number).await? else {
+            continue;
+        };
+        if let Some(hash) = block.hash {
+            remember(network.chain_id, number, hash);
+        }
+        for tx in block.transactions {
+            // transfers between the wallet's own accounts are covered by confirmations
+            let Some(to) = tx.to else { continue };
+            if tx.value.is_zero() || !accounts.contains(&to) || accounts.contains(&tx.from) {
+                continue;
+            }
+
+            received = true;
+            notify_incoming(network.format_amount(tx.value), to).await;
+        }
+    }
+
+    if !accounts.is_empty() {
+        received |= scan_erc20_transfers(db, network, &provider, &accounts, from, head).await?;
+    }
+
+    for record in db.get_pending_txs(network.chain_id).await? {
+        if let Some(receipt) = provider.get_transaction_receipt(record.hash).await? {
+            history::settle(&history::mined(&receipt, network.chain_id)).await;
+        }
+    }
+
+    if received {
+        iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+        iron_broadcast::ui_notify(UINotify::TxsUpdated).await;
+    }
+
+    db.set_last_known_block(network.chain_id, head).await?;
+    Ok(())
+}
+
+/// The first block that's no longer part of the chain, if the chain reorganized since it was
+/// scanned
+/// Remembered blocks are checked from the latest one back, so a chain that didn't reorganize
+/// takes a single request
+async fn find_fork(
+    chain_id: u32,
+    provider: &Provider<iron_networks::Failover>,
+    head: u64,
+) -> Result<Option<u64>> {
+    let known: Vec<(u64, H256)> = recent_blocks()
+        .get(&chain_id)
+        .map(|blocks| blocks.iter().rev().map(|(n, h)| (*n, *h)).collect())
+        .unwrap_or_default();
+
+    let mut fork = None;
+    for (number, hash) in known {
+        // the chain got shorter, such as when a devnet reverts to a snapshot
+        if number > head {
+            fork = Some(number);
+            continue;
+        }
+
+        let actual = provider.get_block(number).await?.and_then(|b| b.hash);
+        if actual == Some(hash) {
+            break;
+        }
+        fork = Some(number);
+    }
+
+    Ok(fork)
+}
+
+/// Undoes what was derived from blocks that were reorganized away: transactions mined in them
+/// go back to pending, and cached responses and balances are refreshed
+/// The blocks are scanned again from the fork on
+async fn roll_back(db: &DB, network: &Network, fork: u64) -> Result<()> {
+    tracing::warn!(chain_id = network.chain_id, fork, "chain reorganized");
+
+    recent_blocks()
+        .entry(network.chain_id)
+        .or_default()
+        .retain(|n, _| *n < fork);
+
+    cache::invalidate(network.chain_id);
+    history::revert(network, fork).await;
+
+    let owner = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await;
+    if let Err(e) = tokens::refresh(network, owner.into()).await {
+        tracing::warn!("Failed to refresh token balances: {}", e);
+    }
+    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+
+    if let Some(last) = db.get_last_known_block(network.chain_id).await? {
+        if last >= fork {
+            db.set_last_known_block(network.chain_id, fork.saturating_sub(1))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn remember(chain_id: u32, number: u64, hash: H256) {
+    let mut recent = recent_blocks();
+    let blocks = recent.entry(chain_id).or_default();
+
+    blocks.insert(number, hash);
+    while blocks.len() as u64 > REORG_DEPTH {
+        blocks.pop_first();
+    }
+}
+
+fn recent_blocks() -> std::sync::MutexGuard<'static, HashMap<u32, BTreeMap<u64, H256>>> {
+    RECENT_BLOCKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Notifies about ERC-20 transfers to any of the given accounts within a block range
+/// Returns whether there were any
+async fn scan_erc20_transfers(
+    db: &DB,
+    network: &Network,
+    provider: &Provider<iron_networks::Failover>,
+    accounts: &[Address],
+    from: u64,
+    to: u64,
+) -> Result<bool> {
+    let recipients: Vec<H256> = accounts.iter().map(|a| (*a).into()).collect();
+    let filter = Filter::new()
+        .from_block(from)
+        .to_block(to)
+        .event("Transfer(address,address,uint256)")
+        .topic2(recipients);
+
+    let mut received = false;
+    for log in provider.get_logs(&filter).await? {
+        // ERC-721 transfers share the signature, but index the token ID as a 4th topic
+        if log.topics.len() != 3 || log.data.len() != 32 {
+            continue;
+        }
+
+        let sender = Address::from(log.topics[1]);
+        if accounts.contains(&sender) {
+            continue;
+        }
+
+        let value = U256::from_big_endian(&log.data);
+        let amount = match db.get_erc20_metadata(log.address, network.chain_id).await {
+            Ok(metadata) => format!(
+                "{} {}",
+                format_units(value, metadata.decimals as u32).unwrap_or_else(|_| value.to_string()),
+                metadata.symbol
+            ),
+            Err(_) => format!("{} units of {:?}", value, log.address),
+        };
+
+        received = true;
+        notify_incoming(amount, Address::from(log.topics[2])).await;
+    }
+
+    Ok(received)
+}
+
+async fn notify_incoming(amount: String, to: Address) {
+    iron_broadcast::desktop_notify(
+        String::from("Transfer received"),
+        format!("{} received on {:?}", amount, to),
+    )
+    .await;
+}
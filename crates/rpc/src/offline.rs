@@ -0,0 +1,89 @@
+/// Split signing flow for air-gapped setups
+///
+/// A transaction is built and filled on an online machine, exported as JSON, signed on an offline
+/// one, and the resulting raw transaction is finally broadcast back from the online machine
+use ethers::{
+    providers::Middleware as _,
+    signers::Signer as _,
+    types::{transaction::eip2718::TypedTransaction, Bytes, H256},
+};
+use iron_networks::{InFlight, Networks};
+use iron_types::{GlobalState, Json, SigningKind};
+use iron_wallets::{auto_lock, Wallet, WalletControl, Wallets};
+
+use super::{audit, fees, Error, Result};
+
+/// Fills in nonce, gas and fees for a transaction, returning it as portable JSON
+/// Requires network access
+pub async fn build_unsigned(mut request: TypedTransaction) -> Result<Json> {
+    let network = Networks::read().await.get_current()?.clone();
+
+    if request.from().is_none() {
+        let wallets = Wallets::read().await;
+        let address = wallets.get_current_wallet().get_current_address().await;
+        request.set_from(address.into());
+    }
+    request.set_chain_id(network.chain_id);
+    let mut request = fees::adapt_to_network(request, &network).await;
+
+    let _in_flight = InFlight::start();
+    network
+        .get_provider()?
+        .fill_transaction(&mut request, None)
+        .await?;
+
+    Ok(serde_json::to_value(request)?)
+}
+
+/// Signs a transaction previously exported by `build_unsigned`
+/// Does not require network access. The signing account is the one set as `from`
+pub async fn sign_exported(json: Json) -> Result<Bytes> {
+    let request: TypedTransaction = serde_json::from_value(json)?;
+
+    let chain_id = request
+        .chain_id()
+        .ok_or_else(|| Error::InvalidParams("missing chainId".into()))?;
+    let from = *request
+        .from()
+        .ok_or_else(|| Error::InvalidParams("missing from".into()))?;
+
+    auto_lock::ensure_unlocked()?;
+    // building the signer may wait on the user to unlock, so it works on a copy
+    let wallet = Wallets::read().await.get_current_wallet().clone();
+    let path = wallet
+        .get_path_for_address(from)
+        .await
+        .ok_or(Error::UnknownAccount(from))?;
+
+    let attempt = audit::Attempt::new(
+        &wallet,
+        &path,
+        chain_id.as_u32(),
+        SigningKind::Transaction,
+        &request,
+    )
+    .await;
+
+    let result = sign_with_path(&wallet, &path, &request).await;
+
+    attempt.record(&result).await;
+    result
+}
+
+async fn sign_with_path(wallet: &Wallet, path: &str, request: &TypedTransaction) -> Result<Bytes> {
+    let chain_id = request.chain_id().unwrap_or_default().as_u32();
+    let signer = wallet.build_signer(chain_id, path).await?;
+    let signature = signer.sign_transaction(request).await?;
+
+    Ok(request.rlp_signed(&signature))
+}
+
+/// Broadcasts a raw signed transaction to the current network
+pub async fn broadcast_signed(raw: Bytes) -> Result<H256> {
+    let provider = Networks::read().await.get_current_provider()?;
+
+    let _in_flight = InFlight::start();
+    let pending = provider.send_raw_transaction(raw).await?;
+
+    Ok(pending.tx_hash())
+}
@@ -0,0 +1,339 @@
+use std::{collections::HashMap, str::FromStr};
+
+use ethers::{
+    prelude::*,
+    types::{serde_helpers::StringifiedNumeric, transaction::eip2718::TypedTransaction},
+    utils::to_checksum,
+};
+use iron_networks::AddChainParams;
+use jsonrpc_core::Params;
+
+use super::{Error, Result};
+
+/// Parses the chain ID given to `wallet_switchEthereumChain`
+/// Expected format: `[{ "chainId": "0x1" }]`
+pub(crate) fn switch_chain_id(params: Params) -> Result<u32> {
+    let params = params.parse::<Vec<HashMap<String, String>>>()?;
+
+    let chain_id = params
+        .first()
+        .and_then(|p| p.get("chainId"))
+        .ok_or_else(|| Error::InvalidParams("missing chainId".into()))?;
+
+    hex_chain_id(chain_id)
+}
+
+/// Parses the chain given to `wallet_addEthereumChain`
+/// Expected format: `[{ "chainId": "0x1", "chainName": "...", "rpcUrls": ["..."], ... }]`
+pub(crate) fn add_chain(params: Params) -> Result<AddChainParams> {
+    params
+        .parse::<Vec<AddChainParams>>()?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::InvalidParams("missing chain".into()))
+}
+
+/// Parses the token given to `wallet_watchAsset`, returning its contract address
+/// Expected format: `{ "type": "ERC20", "options": { "address": "0x...", ... } }`
+/// Along with the address, dapps send a symbol and decimals, which are ignored in favor of the
+/// contract's own
+pub(crate) fn watch_asset(params: Params) -> Result<Address> {
+    let params: serde_json::Value = params.parse()?;
+    let params = match params {
+        serde_json::Value::Array(mut v) if !v.is_empty() => v.swap_remove(0),
+        params => params,
+    };
+
+    if params["type"].as_str() != Some("ERC20") {
+        return Err(Error::InvalidParams(
+            "only ERC20 assets are supported".into(),
+        ));
+    }
+
+    let address = params["options"]["address"]
+        .as_str()
+        .ok_or_else(|| Error::InvalidParams("missing token address".into()))?;
+
+    normalize_address(address)
+}
+
+/// Parses a `0x`-prefixed hex chain ID
+pub(crate) fn hex_chain_id(chain_id: &str) -> Result<u32> {
+    chain_id
+        .strip_prefix("0x")
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| Error::InvalidParams(format!("invalid chain id: {}", chain_id)))
+}
+
+/// Parses a `0x`-prefixed hex quantity, such as a gas limit or nonce
+fn hex_quantity(quantity: &str) -> Result<U256> {
+    quantity
+        .strip_prefix("0x")
+        .filter(|hex| !hex.is_empty())
+        .and_then(|hex| U256::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| Error::InvalidParams(format!("invalid quantity: {}", quantity)))
+}
+
+/// Gets the nth positional string param of a request
+pub(crate) fn nth_string(params: &[Option<String>], n: usize) -> Result<String> {
+    params
+        .get(n)
+        .cloned()
+        .flatten()
+        .ok_or_else(|| Error::InvalidParams(format!("missing param #{}", n)))
+}
+
+/// Gets the nth positional param of a request as an address, if given
+pub(crate) fn nth_address(params: &[Option<String>], n: usize) -> Result<Option<Address>> {
+    params
+        .get(n)
+        .cloned()
+        .flatten()
+        .map(|a| normalize_address(&a))
+        .transpose()
+}
+
+/// Parses a JSON transaction request, as sent by `eth_sendTransaction`
+pub(crate) fn transaction_request(params: serde_json::Value) -> Result<TypedTransaction> {
+    // TODO: why is this an array?
+    let params = match params {
+        serde_json::Value::Array(mut v) if !v.is_empty() => v.swap_remove(0),
+        serde_json::Value::Array(_) => {
+            return Err(Error::InvalidParams("missing transaction".into()))
+        }
+        params => params,
+    };
+
+    let mut request = TypedTransaction::default();
+
+    if let Some(from) = params["from"].as_str() {
+        request.set_from(normalize_address(from)?);
+    }
+
+    if let Some(to) = params["to"].as_str() {
+        request.set_to(normalize_address(to)?);
+    }
+
+    if let Some(value) = params["value"].as_str() {
+        let v = StringifiedNumeric::String(value.to_string());
+        let value = U256::try_from(v).map_err(Error::InvalidParams)?;
+        request.set_value(value);
+    }
+
+    if let Some(data) = params["data"].as_str() {
+        let data = Bytes::from_str(data)
+            .map_err(|_| Error::InvalidParams(format!("invalid data: {}", data)))?;
+        request.set_data(data);
+    }
+
+    if let Some(gas) = params["gas"].as_str() {
+        request.set_gas(hex_quantity(gas)?);
+    }
+
+    if let Some(nonce) = params["nonce"].as_str() {
+        request.set_nonce(hex_quantity(nonce)?);
+    }
+
+    if let Some(chain_id) = params["chainId"].as_str() {
+        request.set_chain_id(hex_chain_id(chain_id)?);
+    }
+
+    Ok(request)
+}
+
+/// Replaces an ENS name in a transaction request's `to` field with the address it resolves to
+/// Meant for requests coming from the app's own UI, where users may type names instead of
+/// addresses
+pub(crate) async fn resolve_ens_recipient(
+    mut params: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let request = match params {
+        serde_json::Value::Array(ref mut v) => v.first_mut(),
+        ref mut request => Some(request),
+    };
+
+    if let Some(request) = request {
+        if let Some(to) = request["to"].as_str().filter(|to| is_ens_name(to)) {
+            let address = iron_networks::resolve_name(to).await?;
+            request["to"] = format!("0x{:x}", address).into();
+        }
+    }
+
+    Ok(params)
+}
+
+/// Whether a recipient looks like an ENS name rather than an address
+fn is_ens_name(to: &str) -> bool {
+    to.contains('.') && !to.trim().starts_with("0x")
+}
+
+/// Validates and normalizes an address given by a dapp or user
+/// Surrounding whitespace and the `0x` prefix are optional. All-lowercase and all-uppercase
+/// inputs are accepted as-is, while mixed-case ones must carry a valid EIP-55 checksum
+pub(crate) fn normalize_address(input: &str) -> Result<Address> {
+    let invalid = || Error::InvalidAddress(input.to_string());
+
+    let trimmed = input.trim();
+    let hex = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+
+    if hex.len() != 40 {
+        return Err(invalid());
+    }
+
+    let address = Address::from_str(hex).map_err(|_| invalid())?;
+
+    let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper && to_checksum(&address, None)[2..] != *hex {
+        return Err(invalid());
+    }
+
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn bad_chain_ids_never_panic() {
+        let inputs = [
+            "", "0", "0x", "1", "0xzz", "0x0x1", "é", "0xé", "0x100000000", "-0x1", " 0x1",
+        ];
+
+        for input in inputs {
+            assert!(hex_chain_id(input).is_err(), "{} should be invalid", input);
+        }
+
+        assert_eq!(hex_chain_id("0x1").unwrap(), 1);
+        assert_eq!(hex_chain_id("0x7a69").unwrap(), 31337);
+    }
+
+    #[test]
+    fn bad_switch_chain_params_never_panic() {
+        let inputs = [
+            json!([]),
+            json!([{}]),
+            json!([{ "chainId": "" }]),
+            json!([{ "chainId": "0x" }]),
+            json!([{ "name": "mainnet" }]),
+            json!({ "chainId": "0x1" }),
+        ];
+
+        for input in inputs {
+            let params: Params = serde_json::from_value(input.clone()).unwrap();
+            assert!(switch_chain_id(params).is_err(), "{} should be invalid", input);
+        }
+
+        let params: Params = serde_json::from_value(json!([{ "chainId": "0x5" }])).unwrap();
+        assert_eq!(switch_chain_id(params).unwrap(), 5);
+    }
+
+    #[test]
+    fn normalize_address_accepts_common_formats() {
+        let expected = Address::from_str("f39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap();
+
+        let inputs = [
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+            "0xF39FD6E51AAD88F6F4CE6AB8827279CFFFB92266",
+            "f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+            "  0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266\n",
+        ];
+
+        for input in inputs {
+            assert_eq!(normalize_address(input).unwrap(), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn normalize_address_rejects_malformed_input() {
+        let inputs = [
+            "",
+            "0x",
+            "0x123",
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb9226",
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb9226600",
+            "0xg39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+            // bad checksum
+            "0xF39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "0x0xf39fd6e51aad88f6f4ce6ab8827279cfffb922",
+        ];
+
+        for input in inputs {
+            assert!(normalize_address(input).is_err(), "{:?} should be invalid", input);
+        }
+    }
+
+    #[test]
+    fn watch_asset_requires_erc20_address() {
+        let address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+        let inputs = [
+            json!({}),
+            json!({ "type": "ERC721", "options": { "address": address } }),
+            json!({ "type": "ERC20", "options": {} }),
+            json!({ "type": "ERC20", "options": { "address": "0x123" } }),
+        ];
+
+        for input in inputs {
+            let params: Params = serde_json::from_value(input.clone()).unwrap();
+            assert!(watch_asset(params).is_err(), "{} should be invalid", input);
+        }
+
+        let params: Params = serde_json::from_value(json!({
+            "type": "ERC20",
+            "options": { "address": address, "symbol": "FOO", "decimals": 18 },
+        }))
+        .unwrap();
+        assert_eq!(
+            watch_asset(params).unwrap(),
+            Address::from_str(&address[2..]).unwrap()
+        );
+    }
+
+    #[test]
+    fn ens_recipients_are_told_apart_from_addresses() {
+        assert!(is_ens_name("vitalik.eth"));
+        assert!(is_ens_name("pay.vitalik.eth"));
+        assert!(!is_ens_name("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"));
+        assert!(!is_ens_name("vitalik"));
+    }
+
+    #[test]
+    fn bad_transaction_requests_never_panic() {
+        let inputs = [
+            json!([]),
+            json!([{ "to": "0x123" }]),
+            json!([{ "from": "alice" }]),
+            json!([{ "value": "lots" }]),
+            json!([{ "data": "0xzz" }]),
+            json!([{ "gas": "21000" }]),
+            json!([{ "nonce": "0x" }]),
+            json!([{ "chainId": "1" }]),
+        ];
+
+        for input in inputs {
+            assert!(
+                transaction_request(input.clone()).is_err(),
+                "{} should be invalid",
+                input
+            );
+        }
+
+        assert!(transaction_request(json!([{
+            "from": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "to": "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "value": "0x1",
+            "data": "0x",
+            "gas": "0x5208",
+            "nonce": "0x0",
+            "chainId": "0x7a69",
+        }]))
+        .is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+//! Recognizes typed-data signatures that approve token spending, so that they're reviewed as
+//! the approvals they are rather than as opaque structs
+//! Both ERC-2612 permits, signed for the token itself, and Permit2 ones, signed for Uniswap's
+//! shared approval contract, let the spender move tokens without any transaction from the owner
+
+use std::{str::FromStr, sync::Arc};
+
+use ethers::types::{transaction::eip712::TypedData, Address, U256};
+use iron_networks::Network;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::tokens;
+
+/// Where Permit2 is deployed, at the same address on every chain
+const PERMIT2: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+/// An approval granted by signing a permit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Permit {
+    pub token: Address,
+    pub spender: Address,
+    pub amount: U256,
+
+    /// Unix timestamp, in seconds, after which the approval can't be used
+    /// Zero for Permit2 allowances means they last until the end of the block they're used in
+    pub deadline: Option<U256>,
+
+    /// Approvals of the maximum amount, which dapps ask for to never have to ask again
+    pub unlimited: bool,
+
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+}
+
+/// The approvals granted by a typed-data signature, with their token's metadata
+/// `None` if it isn't a permit
+pub(crate) async fn preview(network: &Network, data: &TypedData) -> Option<Vec<Permit>> {
+    let mut permits = detect(data)?;
+
+    let client = Arc::new(network.get_provider().ok()?);
+    for permit in permits.iter_mut() {
+        if let Ok(metadata) = tokens::fetch_metadata(client.clone(), permit.token).await {
+            permit.symbol = Some(metadata.symbol);
+            permit.decimals = Some(metadata.decimals);
+        }
+    }
+
+    Some(permits)
+}
+
+/// The approvals in a permit, without their token's metadata
+fn detect(data: &TypedData) -> Option<Vec<Permit>> {
+    let message = Value::Object(data.message.clone().into_iter().collect());
+    let spender = address(&message["spender"])?;
+
+    if is_permit2(data) {
+        return permit2(data.primary_type.as_str(), &message, spender);
+    }
+
+    // ERC-2612 permits are signed for the token itself
+    match data.primary_type.as_str() {
+        "Permit" => Some(vec![permit(
+            data.domain.verifying_contract?,
+            spender,
+            uint(&message["value"])?,
+            uint(&message["deadline"]),
+        )]),
+        _ => None,
+    }
+}
+
+fn is_permit2(data: &TypedData) -> bool {
+    let permit2 = Address::from_str(PERMIT2).unwrap();
+
+    data.domain.verifying_contract == Some(permit2)
+        || data.domain.name.as_deref() == Some("Permit2")
+}
+
+/// Allowances (`PermitSingle`, `PermitBatch`) last until their own expiration, while transfers
+/// (`PermitTransferFrom`, `PermitBatchTransferFrom`) are good for a single use before the
+/// signature's deadline
+fn permit2(primary_type: &str, message: &Value, spender: Address) -> Option<Vec<Permit>> {
+    let (entries, deadline) = match primary_type {
+        "PermitSingle" | "PermitBatch" => (&message["details"], None),
+        "PermitTransferFrom" | "PermitBatchTransferFrom" => {
+            (&message["permitted"], uint(&message["deadline"]))
+        }
+        _ => return None,
+    };
+
+    let entries = match entries {
+        Value::Array(entries) => entries.iter().collect(),
+        entry => vec![entry],
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            Some(permit(
+                address(&entry["token"])?,
+                spender,
+                uint(&entry["amount"])?,
+                deadline.or_else(|| uint(&entry["expiration"])),
+            ))
+        })
+        .collect()
+}
+
+fn permit(token: Address, spender: Address, amount: U256, deadline: Option<U256>) -> Permit {
+    // Permit2 amounts are uint160, so their maximum is lower than ERC-20's
+    let max_uint160 = (U256::one() << 160) - 1;
+
+    Permit {
+        token,
+        spender,
+        amount,
+        deadline,
+        unlimited: amount == U256::MAX || amount == max_uint160,
+        symbol: None,
+        decimals: None,
+    }
+}
+
+fn address(value: &Value) -> Option<Address> {
+    Address::from_str(value.as_str()?).ok()
+}
+
+/// Dapps send integers as decimal or hex strings, or as plain numbers when they fit
+fn uint(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(n) => n.as_u64().map(Into::into),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(s).ok(),
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn typed_data(domain: Value, primary_type: &str, message: Value) -> TypedData {
+        serde_json::from_value(json!({
+            "types": { "EIP712Domain": [] },
+            "domain": domain,
+            "primaryType": primary_type,
+            "message": message,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn detects_erc2612_permits() {
+        let data = typed_data(
+            json!({
+                "name": "USD Coin",
+                "chainId": 1,
+                "verifyingContract": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+            }),
+            "Permit",
+            json!({
+                "owner": "0x0000000000000000000000000000000000000001",
+                "spender": "0x0000000000000000000000000000000000000002",
+                "value": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                "nonce": 0,
+                "deadline": "0x6553f100",
+            }),
+        );
+
+        let permits = detect(&data).unwrap();
+        assert_eq!(permits.len(), 1);
+        assert_eq!(permits[0].spender, Address::from_low_u64_be(2));
+        assert_eq!(permits[0].deadline, Some(0x6553f100u64.into()));
+        assert!(permits[0].unlimited);
+    }
+
+    #[test]
+    fn detects_permit2_batches() {
+        let data = typed_data(
+            json!({ "name": "Permit2", "chainId": 1, "verifyingContract": PERMIT2 }),
+            "PermitBatch",
+            json!({
+                "details": [
+                    {
+                        "token": "0x0000000000000000000000000000000000000003",
+                        "amount": "1461501637330902918203684832716283019655932542975",
+                        "expiration": 1700000000,
+                        "nonce": 0,
+                    },
+                    {
+                        "token": "0x0000000000000000000000000000000000000004",
+                        "amount": "1000000",
+                        "expiration": 1700000000,
+                        "nonce": 0,
+                    },
+                ],
+                "spender": "0x0000000000000000000000000000000000000002",
+                "sigDeadline": 1700000000,
+            }),
+        );
+
+        let permits = detect(&data).unwrap();
+        assert_eq!(permits.len(), 2);
+        assert!(permits[0].unlimited);
+        assert!(!permits[1].unlimited);
+        assert_eq!(permits[1].amount, 1_000_000.into());
+        assert_eq!(permits[1].deadline, Some(1_700_000_000.into()));
+    }
+
+    #[test]
+    fn ignores_other_typed_data() {
+        let data = typed_data(
+            json!({ "name": "Mail", "chainId": 1 }),
+            "Mail",
+            json!({ "contents": "hello" }),
+        );
+
+        assert_eq!(detect(&data), None);
+    }
+}
@@ -0,0 +1,319 @@
+use ethers::{
+    prelude::*,
+    types::transaction::{eip2718::TypedTransaction, eip2930::Eip2930TransactionRequest},
+    utils::rlp::{Decodable, Rlp},
+};
+use iron_db::DB;
+use iron_networks::{Fees, Network, Networks};
+use iron_types::{GlobalState, TxStatus};
+use iron_wallets::{auto_lock, WalletControl, Wallets};
+use once_cell::sync::OnceCell;
+
+use super::{cache, history, send_transaction::SendTransaction, Error, Result};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+/// How much higher both fee fields must be for nodes to accept a replacement, in percent
+/// geth and most other clients reject anything under 10%
+const MIN_BUMP_PERCENT: u64 = 10;
+
+/// Gas used by a plain transfer, which is all a cancellation is
+const TRANSFER_GAS: u64 = 21_000;
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+}
+
+/// Resends a pending transaction with the same nonce and higher fees
+pub(crate) async fn speed_up(hash: H256) -> Result<H256> {
+    replace(hash, false).await
+}
+
+/// Replaces a pending transaction with an empty transfer to the sender itself, using the same
+/// nonce and higher fees. Whichever of them gets mined first voids the other
+pub(crate) async fn cancel(hash: H256) -> Result<H256> {
+    replace(hash, true).await
+}
+
+async fn replace(hash: H256, cancel: bool) -> Result<H256> {
+    let db = DB.get().ok_or(Error::NotInitialized("replace"))?;
+
+    let record = db.get_tx(hash).await?.ok_or(Error::TxNotFound(hash))?;
+    if record.status != TxStatus::Pending {
+        return Err(Error::TxNotPending(hash));
+    }
+
+    let network = Networks::read()
+        .await
+        .get_network(record.chain_id)
+        .ok_or(Error::UnrecognizedChain(record.chain_id))?;
+
+    // nodes forget about transactions they evicted, or that were sent before they restarted,
+    // which the signed bytes kept at broadcast still describe
+    let provider = network.get_provider()?;
+    let original = match provider.get_transaction(hash).await? {
+        Some(original) => original,
+        None => stored(db, hash, record.from)
+            .await?
+            .ok_or(Error::TxNotFound(hash))?,
+    };
+    if original.block_number.is_some() {
+        return Err(Error::TxNotPending(hash));
+    }
+
+    let mut request = replacement(&original, cancel);
+    let current = current_fees(&network).await;
+    if let Some(old) = fees_of(&original) {
+        super::fees::apply(&mut request, bump(old, current));
+    }
+
+    // a copy, so that the wallets aren't held on to while the user reviews the replacement
+    auto_lock::ensure_unlocked()?;
+    let wallet = Wallets::read().await.get_current_wallet().clone();
+    let wallet_path = wallet
+        .get_path_for_address(original.from)
+        .await
+        .ok_or(Error::UnknownAccount(original.from))?;
+
+    let chain_id = network.chain_id;
+    let mut sender = SendTransaction::build()
+        .set_wallet(&wallet)
+        .set_wallet_path(wallet_path)
+        .set_network(network)
+        .set_request(request)
+        .set_replaces(hash)
+        .build();
+
+    let new_hash = sender.fill_fees().await.finish().await?.tx_hash();
+
+    cache::invalidate(chain_id);
+    history::settle(&history::unmined(hash, chain_id, TxStatus::Replaced)).await;
+
+    Ok(new_hash)
+}
+
+/// A transaction as it was signed, if its signed bytes were kept
+async fn stored(db: &DB, hash: H256, from: Address) -> Result<Option<Transaction>> {
+    let Some((_, raw)) = db.get_tx_payload(hash).await? else {
+        return Ok(None);
+    };
+
+    Ok(Transaction::decode(&Rlp::new(&raw))
+        .ok()
+        .map(|tx| Transaction { from, ..tx }))
+}
+
+/// Rebuilds the request behind a transaction, keeping its type and nonce
+/// Cancellations keep only what's needed for a transfer of nothing back to the sender
+fn replacement(tx: &Transaction, cancel: bool) -> TypedTransaction {
+    match cancel {
+        true => with_fields(
+            tx,
+            Some(tx.from),
+            U256::zero(),
+            Bytes::default(),
+            TRANSFER_GAS.into(),
+            false,
+        ),
+        false => with_fields(tx, tx.to, tx.value, tx.input.clone(), tx.gas, true),
+    }
+}
+
+fn with_fields(
+    tx: &Transaction,
+    to: Option<Address>,
+    value: U256,
+    data: Bytes,
+    gas: U256,
+    keep_access_list: bool,
+) -> TypedTransaction {
+    let access_list = match keep_access_list {
+        true => tx.access_list.clone().unwrap_or_default(),
+        false => Default::default(),
+    };
+
+    match tx.transaction_type.map(|t| t.as_u64()) {
+        Some(2) => {
+            let mut request = Eip1559TransactionRequest::new()
+                .from(tx.from)
+                .value(value)
+                .data(data)
+                .gas(gas)
+                .nonce(tx.nonce)
+                .access_list(access_list);
+            request.to = to.map(Into::into);
+            request.chain_id = tx.chain_id.map(|id| id.as_u64().into());
+            request.into()
+        }
+        ty => {
+            let mut request = TransactionRequest::new()
+                .from(tx.from)
+                .value(value)
+                .data(data)
+                .gas(gas)
+                .nonce(tx.nonce);
+            request.to = to.map(Into::into);
+            request.chain_id = tx.chain_id.map(|id| id.as_u64().into());
+
+            match ty {
+                Some(1) => Eip2930TransactionRequest::new(request, access_list).into(),
+                _ => request.into(),
+            }
+        }
+    }
+}
+
+/// The fees a transaction was sent with
+fn fees_of(tx: &Transaction) -> Option<Fees> {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee_per_gas), Some(max_priority_fee_per_gas)) => Some(Fees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        }),
+        _ => tx.gas_price.map(|gas_price| Fees::Legacy { gas_price }),
+    }
+}
+
+/// What the network currently asks for a quick inclusion, if it can be estimated
+async fn current_fees(network: &Network) -> Option<Fees> {
+    Some(network.get_fee_estimates().await.ok()?.suggestions().fast)
+}
+
+/// Fees for a replacement: at least the minimum bump over the original on every field, and no
+/// less than what the network currently asks for, in case fees went up since it was sent
+fn bump(old: Fees, current: Option<Fees>) -> Fees {
+    match old {
+        Fees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        } => {
+            let (current_max, current_priority) = match current {
+                Some(Fees::Eip1559 {
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                }) => (max_fee_per_gas, max_priority_fee_per_gas),
+                Some(Fees::Legacy { gas_price }) => (gas_price, U256::zero()),
+                None => (U256::zero(), U256::zero()),
+            };
+
+            let max_priority_fee_per_gas = bumped(max_priority_fee_per_gas).max(current_priority);
+            let max_fee_per_gas = bumped(max_fee_per_gas)
+                .max(current_max)
+                .max(max_priority_fee_per_gas);
+
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            }
+        }
+
+        // a legacy transaction pays its whole gas price on any network, so the max fee is what
+        // it has to keep up with
+        Fees::Legacy { gas_price } => {
+            let current = match current {
+                Some(Fees::Legacy { gas_price }) => gas_price,
+                Some(Fees::Eip1559 {
+                    max_fee_per_gas, ..
+                }) => max_fee_per_gas,
+                None => U256::zero(),
+            };
+
+            Fees::Legacy {
+                gas_price: bumped(gas_price).max(current),
+            }
+        }
+    }
+}
+
+/// Raises a fee by the minimum bump, rounding up, and by at least 1 wei
+fn bumped(fee: U256) -> U256 {
+    let raised = (fee * (100 + MIN_BUMP_PERCENT) + 99) / 100;
+    raised.max(fee + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bumps_every_fee_field() {
+        let old = Fees::Eip1559 {
+            max_fee_per_gas: 100.into(),
+            max_priority_fee_per_gas: 3.into(),
+        };
+
+        // the minimum bump rounds up
+        assert_eq!(
+            bump(old, None),
+            Fees::Eip1559 {
+                max_fee_per_gas: 110.into(),
+                max_priority_fee_per_gas: 4.into(),
+            }
+        );
+
+        // fees that went up since are matched
+        let current = Fees::Eip1559 {
+            max_fee_per_gas: 200.into(),
+            max_priority_fee_per_gas: 2.into(),
+        };
+        assert_eq!(
+            bump(old, Some(current)),
+            Fees::Eip1559 {
+                max_fee_per_gas: 200.into(),
+                max_priority_fee_per_gas: 4.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn bumps_legacy_gas_price() {
+        let old = Fees::Legacy {
+            gas_price: 1_000.into(),
+        };
+
+        assert_eq!(
+            bump(old, None),
+            Fees::Legacy {
+                gas_price: 1_100.into()
+            }
+        );
+        assert_eq!(
+            bump(
+                old,
+                Some(Fees::Eip1559 {
+                    max_fee_per_gas: 5_000.into(),
+                    max_priority_fee_per_gas: 1.into(),
+                })
+            ),
+            Fees::Legacy {
+                gas_price: 5_000.into()
+            }
+        );
+        assert_eq!(bumped(U256::zero()), 1.into());
+    }
+
+    #[test]
+    fn cancellations_are_empty_self_transfers() {
+        let tx = Transaction {
+            from: Address::from_low_u64_be(1),
+            to: Some(Address::from_low_u64_be(2)),
+            value: 5.into(),
+            input: vec![1, 2, 3].into(),
+            nonce: 7.into(),
+            gas: 100_000.into(),
+            transaction_type: Some(2.into()),
+            ..Default::default()
+        };
+
+        let request = replacement(&tx, true);
+        assert!(matches!(request, TypedTransaction::Eip1559(_)));
+        assert_eq!(request.to().and_then(|to| to.as_address()), Some(&tx.from));
+        assert_eq!(request.value(), Some(&U256::zero()));
+        assert_eq!(request.nonce(), Some(&tx.nonce));
+        assert_eq!(request.gas(), Some(&TRANSFER_GAS.into()));
+
+        let request = replacement(&tx, false);
+        assert_eq!(request.to().and_then(|to| to.as_address()), tx.to.as_ref());
+        assert_eq!(request.data(), Some(&tx.input));
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use async_trait::async_trait;
+use futures_util::future::{join_all, BoxFuture};
+use iron_connections::Ctx;
+use iron_types::Json;
+use jsonrpc_core::{Call, MethodCall, Output, Params, Request, Response, Version};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tracing::Instrument;
+
+use super::{cache, methods::*, Error, Result};
+
+/// A method answered by the wallet itself, with its params parsed up front
+#[async_trait]
+pub(crate) trait Method: Sized + Send + 'static {
+    type Response: Serialize;
+
+    fn parse(params: Params) -> Result<Self>;
+
+    async fn call(self, ctx: Ctx) -> Result<Self::Response>;
+}
+
+type LocalHandler = fn(Params, Ctx) -> BoxFuture<'static, jsonrpc_core::Result<Json>>;
+
+/// How a method is answered
+#[derive(Clone, Copy)]
+enum Route {
+    /// By the wallet itself
+    Local(LocalHandler),
+
+    /// By the connection's network, through the cache
+    Proxy,
+
+    /// Known, but deliberately not supported
+    Unsupported,
+}
+
+/// Prefixes of methods that only read chain state
+/// Methods missing from the table that match one of these are still forwarded upstream, so that
+/// e.g. `eth_getProof` or `eth_feeHistory` don't each need an entry
+const READ_PREFIXES: &[&str] = &[
+    "eth_get",
+    "eth_call",
+    "eth_estimate",
+    "eth_fee",
+    "eth_max",
+    "eth_blob",
+    "eth_createAccessList",
+    "net_",
+    "web3_",
+];
+
+static ROUTER: Lazy<Router> = Lazy::new(Router::new);
+
+/// Requests are numbered, so that the logs of everything they lead to, from approval to signing
+/// and broadcasting, can be told apart from those of concurrent requests
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+struct Router {
+    routes: HashMap<&'static str, Route>,
+}
+
+impl Router {
+    fn new() -> Self {
+        let mut router = Self {
+            routes: HashMap::new(),
+        };
+
+        // gossip methods
+        router.proxy("eth_blockNumber");
+        router.proxy("eth_sendRawTransaction");
+
+        // state methods
+        router.proxy("eth_getBalance");
+        router.proxy("eth_getStorageAt");
+        router.proxy("eth_getTransactionCount");
+        router.proxy("eth_getCode");
+        router.proxy("eth_call");
+        router.proxy("eth_estimateGas");
+        router.proxy("eth_gasPrice");
+        router.proxy("eth_protocolVersion");
+        router.proxy("eth_syncing");
+        router.proxy("eth_mining");
+        router.proxy("net_version");
+        router.proxy("net_listening");
+        router.proxy("net_peerCount");
+        router.proxy("web3_sha3");
+
+        // history methods
+        router.proxy("eth_getBlockTransactionCountByHash");
+        router.proxy("eth_getBlockTransactionCountByNumber");
+        router.proxy("eth_getUncleCountByBlockHash");
+        router.proxy("eth_getUncleCountByBlockNumber");
+        router.proxy("eth_getBlockByHash");
+        router.proxy("eth_getBlockByNumber");
+        router.proxy("eth_getTransactionByHash");
+        router.proxy("eth_getTransactionByBlockHashAndIndex");
+        router.proxy("eth_getTransactionByBlockNumberAndIndex");
+        router.proxy("eth_getTransactionReceipt");
+        router.proxy("eth_getUncleByBlockHashAndIndex");
+        router.proxy("eth_getUncleByBlockNumberAndIndex");
+
+        // filter methods
+        router.proxy("eth_newFilter");
+        router.proxy("eth_newBlockFilter");
+        router.proxy("eth_newPendingFilter");
+        router.proxy("eth_uninstallFilter");
+        router.proxy("eth_getFilterChanges");
+        router.proxy("eth_getFilterLogs");
+        router.proxy("eth_getLogs");
+
+        // handled internally
+        router.local::<ClientVersion>("web3_clientVersion");
+        router.local::<Accounts>("eth_accounts");
+        router.local::<RequestAccounts>("eth_requestAccounts");
+        router.local::<ChainId>("eth_chainId");
+        router.local::<SendTransaction>("eth_sendTransaction");
+        router.local::<SignTransaction>("eth_signTransaction");
+        router.local::<EthSign>("eth_sign");
+        router.local::<PersonalSign>("personal_sign");
+        router.local::<SignTypedData>("eth_signTypedData");
+        router.local::<SignTypedData>("eth_signTypedData_v4");
+        router.local::<SwitchChain>("wallet_switchEthereumChain");
+        router.local::<AddChain>("wallet_addEthereumChain");
+        router.local::<WatchAsset>("wallet_watchAsset");
+
+        // metamask
+        router.local::<ProviderState>("metamask_getProviderState");
+
+        router
+    }
+
+    fn local<M: Method>(&mut self, name: &'static str) {
+        self.routes.insert(name, Route::Local(dispatch::<M>));
+    }
+
+    fn proxy(&mut self, name: &'static str) {
+        self.routes.insert(name, Route::Proxy);
+    }
+
+    /// Looks up a method, falling back to the upstream network for unknown read methods
+    fn route(&self, method: &str) -> Route {
+        match self.routes.get(method) {
+            Some(route) => *route,
+            None if READ_PREFIXES.iter().any(|p| method.starts_with(p)) => Route::Proxy,
+            None => Route::Unsupported,
+        }
+    }
+}
+
+fn dispatch<M: Method>(params: Params, ctx: Ctx) -> BoxFuture<'static, jsonrpc_core::Result<Json>> {
+    Box::pin(async move {
+        let response = M::parse(params)?.call(ctx).await?;
+        Ok(serde_json::to_value(response).map_err(Error::from)?)
+    })
+}
+
+/// Handles a raw JSON-RPC request, returning the serialized response
+/// Notifications get no response
+pub(crate) async fn handle(request: &str, ctx: Ctx) -> Option<String> {
+    let response = match serde_json::from_str::<Request>(request) {
+        Ok(Request::Single(call)) => Response::Single(handle_call(call, ctx).await?),
+        Ok(Request::Batch(calls)) if calls.is_empty() => {
+            Response::from(jsonrpc_core::Error::invalid_request(), Some(Version::V2))
+        }
+
+        // entries are answered concurrently, but replies keep the batch's order
+        Ok(Request::Batch(calls)) => {
+            let outputs: Vec<_> = join_all(calls.into_iter().map(|c| handle_call(c, ctx.clone())))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+
+            // a batch of only notifications gets no response at all
+            if outputs.is_empty() {
+                return None;
+            }
+            Response::Batch(outputs)
+        }
+        Err(_) => Response::from(jsonrpc_core::Error::parse_error(), Some(Version::V2)),
+    };
+
+    serde_json::to_string(&response).ok()
+}
+
+async fn handle_call(call: Call, ctx: Ctx) -> Option<Output> {
+    match call {
+        Call::MethodCall(MethodCall {
+            jsonrpc,
+            method,
+            params,
+            id,
+        }) => Some(Output::from(
+            call_method(&method, params, ctx).await,
+            id,
+            jsonrpc,
+        )),
+        Call::Notification(n) => {
+            let _ = call_method(&n.method, n.params, ctx).await;
+            None
+        }
+        Call::Invalid { id } => Some(Output::invalid_request(id, Some(Version::V2))),
+    }
+}
+
+/// Answers a single call within its own span, which tags every log line it leads to with the
+/// request's ID
+/// Params are left out of the logs, since they can carry messages, calldata or RPC credentials
+async fn call_method(method: &str, params: Params, ctx: Ctx) -> jsonrpc_core::Result<Json> {
+    let span = tracing::info_span!(
+        "request",
+        id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+        method,
+        origin = ctx.domain.as_deref().unwrap_or("app"),
+    );
+
+    let result = route(method, params, ctx).instrument(span.clone()).await;
+    if let Err(ref e) = result {
+        span.in_scope(|| tracing::debug!(code = e.code.code(), "failed: {}", e.message));
+    }
+
+    result
+}
+
+async fn route(method: &str, params: Params, ctx: Ctx) -> jsonrpc_core::Result<Json> {
+    match ROUTER.route(method) {
+        Route::Local(handler) => handler(params, ctx).await,
+        Route::Proxy => cache::request(&ctx, method, params).await,
+        Route::Unsupported => Err(Error::UnsupportedMethod(method.to_string()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_read_methods_fall_through_upstream() {
+        assert!(matches!(ROUTER.route("eth_chainId"), Route::Local(_)));
+        assert!(matches!(ROUTER.route("eth_getBalance"), Route::Proxy));
+        assert!(matches!(ROUTER.route("eth_getProof"), Route::Proxy));
+        assert!(matches!(ROUTER.route("eth_feeHistory"), Route::Proxy));
+
+        assert!(matches!(
+            ROUTER.route("eth_signTransaction"),
+            Route::Local(_)
+        ));
+        assert!(matches!(
+            ROUTER.route("anvil_setBalance"),
+            Route::Unsupported
+        ));
+        assert!(matches!(ROUTER.route("wallet_foo"), Route::Unsupported));
+    }
+
+    #[tokio::test]
+    async fn unsupported_methods_get_4200() {
+        let request = r#"{"jsonrpc":"2.0","id":1,"method":"debug_traceTransaction","params":[]}"#;
+        let response = handle(request, Ctx::empty()).await.unwrap();
+        let response: Json = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], 4200);
+    }
+
+    #[tokio::test]
+    async fn batches_keep_their_order() {
+        let request = r#"[
+            {"jsonrpc":"2.0","id":1,"method":"web3_clientVersion","params":[]},
+            {"jsonrpc":"2.0","method":"debug_traceCall","params":[]},
+            {"jsonrpc":"2.0","id":"b","method":"debug_traceTransaction","params":[]}
+        ]"#;
+        let response = handle(request, Ctx::empty()).await.unwrap();
+        let response: Json = serde_json::from_str(&response).unwrap();
+
+        assert_eq!(response.as_array().unwrap().len(), 2);
+        assert_eq!(response[0]["id"], 1);
+        assert!(response[0]["result"].is_string());
+        assert_eq!(response[1]["id"], "b");
+
+        let response = handle("[]", Ctx::empty()).await.unwrap();
+        let response: Json = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+    }
+}
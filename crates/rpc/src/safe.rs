@@ -0,0 +1,350 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware as _,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712, eip712::TypedData},
+        Address, Bytes, H256, U256,
+    },
+    utils::to_checksum,
+};
+use iron_abis::ISafe;
+use iron_networks::Network;
+use iron_types::{GlobalState, RequestSource};
+use iron_wallets::{auto_lock, Wallet, WalletControl, Wallets};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{sign_message::SignMessage, Error, Result};
+
+/// Safe transaction services, by chain ID
+/// Proposals are sent here for the other owners to see and confirm in the Safe UI
+const TX_SERVICES: &[(u32, &str)] = &[
+    (1, "https://safe-transaction-mainnet.safe.global"),
+    (5, "https://safe-transaction-goerli.safe.global"),
+    (10, "https://safe-transaction-optimism.safe.global"),
+    (100, "https://safe-transaction-gnosis-chain.safe.global"),
+    (137, "https://safe-transaction-polygon.safe.global"),
+    (8453, "https://safe-transaction-base.safe.global"),
+    (42161, "https://safe-transaction-arbitrum.safe.global"),
+    (11155111, "https://safe-transaction-sepolia.safe.global"),
+];
+
+/// Shown as the origin of proposals in the Safe UI
+const ORIGIN: &str = "Iron";
+
+/// The setup of a Safe, as read from the contract
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeInfo {
+    pub address: Address,
+    pub version: String,
+    pub owners: Vec<Address>,
+    pub threshold: U256,
+    pub nonce: U256,
+
+    /// Owners whose keys are in one of the app's wallets
+    pub own_owners: Vec<Address>,
+
+    /// Whether this chain has a transaction service to propose to
+    pub can_propose: bool,
+}
+
+/// A call for a Safe to make
+/// Gas refunds are never used, so the related fields of a Safe transaction are always zero
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTx {
+    pub to: Address,
+
+    #[serde(default)]
+    pub value: U256,
+
+    #[serde(default)]
+    pub data: Bytes,
+
+    /// 0 for a call, 1 for a delegatecall
+    #[serde(default)]
+    pub operation: u8,
+
+    /// Defaults to the Safe's current nonce
+    pub nonce: Option<U256>,
+}
+
+impl TryFrom<&TypedTransaction> for SafeTx {
+    type Error = Error;
+
+    fn try_from(request: &TypedTransaction) -> Result<Self> {
+        let to = request
+            .to()
+            .and_then(|to| to.as_address())
+            .ok_or_else(|| Error::InvalidParams("Safe transactions need a recipient".into()))?;
+
+        Ok(Self {
+            to: *to,
+            value: request.value().copied().unwrap_or_default(),
+            data: request.data().cloned().unwrap_or_default(),
+            operation: 0,
+            nonce: None,
+        })
+    }
+}
+
+/// Reads the setup of a Safe, or `None` if the address isn't one
+pub(crate) async fn detect(network: &Network, address: Address) -> Result<Option<SafeInfo>> {
+    let provider = Arc::new(network.get_provider()?);
+    if provider.get_code(address, None).await?.is_empty() {
+        return Ok(None);
+    }
+
+    // any contract that doesn't answer these is not a Safe
+    let safe = ISafe::new(address, provider);
+    let (Ok(owners), Ok(threshold)) = (
+        safe.get_owners().call().await,
+        safe.get_threshold().call().await,
+    ) else {
+        return Ok(None);
+    };
+    let nonce = safe.nonce().call().await?;
+    let version = safe.version().call().await.unwrap_or_default();
+
+    let wallets = Wallets::read().await;
+    let own_owners = signers_among(&wallets, &owners)
+        .await
+        .into_iter()
+        .map(|(_, _, owner)| owner)
+        .collect();
+
+    Ok(Some(SafeInfo {
+        address,
+        version,
+        owners,
+        threshold,
+        nonce,
+        own_owners,
+        can_propose: tx_service(network.chain_id).is_some(),
+    }))
+}
+
+/// Signs a transaction as one of the Safe's owners, and proposes it to the Safe transaction
+/// service, for the other owners to confirm
+/// Returns the Safe transaction hash, which is not the hash of any Ethereum transaction
+pub(crate) async fn propose(
+    network: Network,
+    address: Address,
+    tx: SafeTx,
+    source: Option<RequestSource>,
+) -> Result<H256> {
+    let info = detect(&network, address)
+        .await?
+        .ok_or(Error::NotASafe(address))?;
+    let service = tx_service(network.chain_id).ok_or(Error::NoSafeTxService(network.chain_id))?;
+    let nonce = tx.nonce.unwrap_or(info.nonce);
+
+    let typed_data = typed_data(&info.version, network.chain_id, address, &tx, nonce)?;
+    let hash: H256 = typed_data
+        .encode_eip712()
+        .map_err(|e| Error::InvalidParams(e.to_string()))?
+        .into();
+
+    // the domain changed across Safe versions, so the hash is checked against the Safe's own
+    // before any owner is asked to sign it
+    let expected = ISafe::new(address, Arc::new(network.get_provider()?))
+        .get_transaction_hash(
+            tx.to,
+            tx.value,
+            tx.data.clone(),
+            tx.operation,
+            U256::zero(),
+            U256::zero(),
+            U256::zero(),
+            Address::zero(),
+            Address::zero(),
+            nonce,
+        )
+        .call()
+        .await?;
+    if H256::from(expected) != hash {
+        return Err(Error::SafeHashMismatch(address));
+    }
+
+    // a copy, so that the wallets aren't held on to while the owner reviews the signature
+    auto_lock::ensure_unlocked()?;
+    let (wallet, path, owner) = signers_among(&Wallets::read().await, &info.owners)
+        .await
+        .into_iter()
+        .next()
+        .map(|(wallet, path, owner)| (wallet.clone(), path, owner))
+        .ok_or(Error::NotASafeOwner(address))?;
+
+    let mut signer = SignMessage::build()
+        .set_wallet(&wallet)
+        .set_wallet_path(path)
+        .set_network(network)
+        .set_typed_data(typed_data)
+        .set_source(source)
+        .build();
+    let signature = signer.finish().await?;
+
+    let zero = to_checksum(&Address::zero(), None);
+    let body = json!({
+        "to": to_checksum(&tx.to, None),
+        "value": tx.value.to_string(),
+        "data": (!tx.data.is_empty()).then(|| tx.data.to_string()),
+        "operation": tx.operation,
+        "safeTxGas": "0",
+        "baseGas": "0",
+        "gasPrice": "0",
+        "gasToken": zero,
+        "refundReceiver": zero,
+        "nonce": nonce.to_string(),
+        "contractTransactionHash": format!("0x{:x}", hash),
+        "sender": to_checksum(&owner, None),
+        "signature": format!("0x{}", signature),
+        "origin": ORIGIN,
+    });
+
+    let url = format!(
+        "{}/api/v1/safes/{}/multisig-transactions/",
+        service,
+        to_checksum(&address, None)
+    );
+    let res = reqwest::Client::new().post(url).json(&body).send().await?;
+    if !res.status().is_success() {
+        return Err(Error::SafeTxService(res.text().await.unwrap_or_default()));
+    }
+
+    Ok(hash)
+}
+
+fn tx_service(chain_id: u32) -> Option<&'static str> {
+    TX_SERVICES
+        .iter()
+        .find(|(id, _)| *id == chain_id)
+        .map(|(_, url)| *url)
+}
+
+/// Accounts that can sign for any of the given owners, across every wallet
+/// Watch-only accounts are skipped, since they can't sign either
+async fn signers_among<'a>(
+    wallets: &'a Wallets,
+    owners: &[Address],
+) -> Vec<(&'a Wallet, String, Address)> {
+    let mut signers = vec![];
+
+    for wallet in wallets.get_all() {
+        for (path, address) in wallet.get_all_addresses().await {
+            let address: Address = address.into();
+            if owners.contains(&address) && wallet.ensure_can_sign(&path).is_ok() {
+                signers.push((wallet, path, address));
+            }
+        }
+    }
+
+    signers
+}
+
+/// The EIP-712 payload owners sign to approve a Safe transaction
+fn typed_data(
+    version: &str,
+    chain_id: u32,
+    safe: Address,
+    tx: &SafeTx,
+    nonce: U256,
+) -> Result<TypedData> {
+    let (domain_type, domain) = match domain_has_chain_id(version) {
+        true => (
+            json!([
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" },
+            ]),
+            json!({ "chainId": chain_id, "verifyingContract": safe }),
+        ),
+        false => (
+            json!([{ "name": "verifyingContract", "type": "address" }]),
+            json!({ "verifyingContract": safe }),
+        ),
+    };
+
+    let typed_data = json!({
+        "types": {
+            "EIP712Domain": domain_type,
+            "SafeTx": [
+                { "name": "to", "type": "address" },
+                { "name": "value", "type": "uint256" },
+                { "name": "data", "type": "bytes" },
+                { "name": "operation", "type": "uint8" },
+                { "name": "safeTxGas", "type": "uint256" },
+                { "name": "baseGas", "type": "uint256" },
+                { "name": "gasPrice", "type": "uint256" },
+                { "name": "gasToken", "type": "address" },
+                { "name": "refundReceiver", "type": "address" },
+                { "name": "nonce", "type": "uint256" },
+            ],
+        },
+        "primaryType": "SafeTx",
+        "domain": domain,
+        "message": {
+            "to": tx.to,
+            "value": tx.value.to_string(),
+            "data": tx.data,
+            "operation": tx.operation,
+            "safeTxGas": "0",
+            "baseGas": "0",
+            "gasPrice": "0",
+            "gasToken": Address::zero(),
+            "refundReceiver": Address::zero(),
+            "nonce": nonce.to_string(),
+        },
+    });
+
+    Ok(serde_json::from_value(typed_data)?)
+}
+
+/// Safes before 1.3.0 leave the chain ID out of their EIP-712 domain
+/// Unknown versions are assumed to be recent
+fn domain_has_chain_id(version: &str) -> bool {
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(major), Some(minor)) => (major, minor) >= (1, 3),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_safes_leave_chain_out_of_domain() {
+        assert!(!domain_has_chain_id("1.1.1"));
+        assert!(!domain_has_chain_id("1.2.0"));
+        assert!(domain_has_chain_id("1.3.0"));
+        assert!(domain_has_chain_id("1.4.1"));
+        assert!(domain_has_chain_id(""));
+    }
+
+    #[test]
+    fn builds_safe_tx_payloads() {
+        let safe = Address::from_low_u64_be(1);
+        let tx = SafeTx {
+            to: Address::from_low_u64_be(2),
+            value: 5.into(),
+            data: Bytes::default(),
+            operation: 0,
+            nonce: None,
+        };
+
+        let modern = typed_data("1.3.0", 1, safe, &tx, 7.into()).unwrap();
+        let legacy = typed_data("1.2.0", 1, safe, &tx, 7.into()).unwrap();
+
+        assert_eq!(modern.primary_type, "SafeTx");
+        assert_eq!(modern.domain.chain_id, Some(1.into()));
+        assert_eq!(legacy.domain.chain_id, None);
+        assert_ne!(
+            modern.encode_eip712().unwrap(),
+            legacy.encode_eip712().unwrap()
+        );
+    }
+}
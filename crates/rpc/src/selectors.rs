@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use ethers::{
+    abi::{Abi, AbiParser, Function, Token},
+    types::{Address, I256},
+    utils::to_checksum,
+};
+use iron_db::DB;
+use iron_networks::Networks;
+use iron_types::GlobalState;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+}
+
+/// Common function signatures, decodable without the user registering any ABI
+const KNOWN_SIGNATURES: &[&str] = &[
+    // ERC-20
+    "function transfer(address to, uint256 amount)",
+    "function approve(address spender, uint256 amount)",
+    "function transferFrom(address from, address to, uint256 amount)",
+    // ERC-721 / ERC-1155
+    "function safeTransferFrom(address from, address to, uint256 tokenId)",
+    "function setApprovalForAll(address operator, bool approved)",
+    // WETH
+    "function deposit()",
+    "function withdraw(uint256 amount)",
+    // multicall
+    "function multicall(bytes[] data)",
+    "function aggregate((address,bytes)[] calls)",
+];
+
+/// Known functions, indexed by their 4-byte selector
+static REGISTRY: Lazy<HashMap<[u8; 4], Function>> = Lazy::new(|| {
+    KNOWN_SIGNATURES
+        .iter()
+        .filter_map(|sig| AbiParser::default().parse_function(sig).ok())
+        .map(|function| (function.short_signature(), function))
+        .collect()
+});
+
+/// A call decoded from its calldata
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedCall {
+    /// Name of the called contract, when decoded from its own ABI
+    pub contract: Option<String>,
+    pub signature: String,
+    pub args: Vec<DecodedArg>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DecodedArg {
+    pub name: String,
+    pub value: String,
+}
+
+/// Decodes a call to a given contract
+/// Uses the contract's full ABI when it's known, either because it was registered with one,
+/// because it was deployed by a local forge script, or because it's verified on the network's
+/// explorer, and falls back to well-known selectors otherwise
+pub(crate) async fn decode_for(chain_id: u32, to: Address, data: &[u8]) -> Option<DecodedCall> {
+    known_abi(chain_id, to)
+        .await
+        .and_then(|(name, abi)| decode_with_abi(name, &abi, data))
+        .or_else(|| decode(data))
+}
+
+/// The name and ABI of a contract, from the first of the registered contracts, local forge
+/// deployments or the network's explorer to know it
+pub(crate) async fn known_abi(chain_id: u32, address: Address) -> Option<(Option<String>, Abi)> {
+    let stored = match DB.get() {
+        Some(db) => db.get_contract_abi(chain_id, address).await.ok().flatten(),
+        None => None,
+    };
+    if stored.is_some() {
+        return stored;
+    }
+
+    let forge = iron_forge::get_abi_for_address(chain_id, address)
+        .await
+        .and_then(|abi| Some((Some(abi.name), serde_json::from_value(abi.abi).ok()?)));
+    if forge.is_some() {
+        return forge;
+    }
+
+    verified_abi(chain_id, address).await
+}
+
+/// The ABI of a contract verified on its network's explorer, if the network has one
+async fn verified_abi(chain_id: u32, address: Address) -> Option<(Option<String>, Abi)> {
+    let network = Networks::read().await.get_network(chain_id)?;
+    let explorer = network.explorer().await.ok()?;
+    let contract = explorer.verified_contract(address).await.ok()??;
+
+    Some((Some(contract.name), contract.abi))
+}
+
+/// Decodes calldata against a contract's ABI
+fn decode_with_abi(contract: Option<String>, abi: &Abi, data: &[u8]) -> Option<DecodedCall> {
+    let selector = data.get(..4)?;
+    let function = abi
+        .functions()
+        .find(|f| f.short_signature().as_slice() == selector)?;
+
+    decode_function(function, data).map(|call| DecodedCall { contract, ..call })
+}
+
+/// Decodes calldata for a known selector
+/// Returns `None` for unknown selectors, or if the arguments don't match the known signature, in
+/// which case the raw calldata should be displayed instead
+pub(crate) fn decode(data: &[u8]) -> Option<DecodedCall> {
+    let selector: [u8; 4] = data.get(..4)?.try_into().ok()?;
+    decode_function(REGISTRY.get(&selector)?, data)
+}
+
+fn decode_function(function: &Function, data: &[u8]) -> Option<DecodedCall> {
+    let tokens = function.decode_input(data.get(4..)?).ok()?;
+
+    let args = function
+        .inputs
+        .iter()
+        .zip(tokens)
+        .map(|(param, token)| DecodedArg {
+            name: param.name.clone(),
+            value: format_token(token),
+        })
+        .collect();
+
+    Some(DecodedCall {
+        contract: None,
+        signature: function.signature(),
+        args,
+    })
+}
+
+/// Formats a token for display, with numbers in base 10 and checksummed addresses
+pub(crate) fn format_token(token: Token) -> String {
+    match token {
+        Token::Address(address) => to_checksum(&address, None),
+        Token::Uint(n) => n.to_string(),
+        Token::Int(n) => I256::from_raw(n).to_string(),
+        token => token.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi,
+        types::{Address, U256},
+    };
+
+    use super::*;
+
+    #[test]
+    fn decodes_known_selectors() {
+        let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+        data.extend(abi::encode(&[
+            Token::Address(Address::from_low_u64_be(1)),
+            Token::Uint(U256::from(42)),
+        ]));
+
+        let decoded = decode(&data).unwrap();
+        assert_eq!(decoded.signature, "transfer(address,uint256)");
+        assert_eq!(decoded.args.len(), 2);
+        assert_eq!(decoded.args[0].name, "to");
+        assert_eq!(decoded.args[1].name, "amount");
+        assert_eq!(decoded.args[1].value, "42");
+    }
+
+    #[test]
+    fn decodes_with_contract_abi() {
+        let abi = AbiParser::default()
+            .parse(&["function setNumber(uint256 newNumber)"])
+            .unwrap();
+        let mut data = abi
+            .function("setNumber")
+            .unwrap()
+            .short_signature()
+            .to_vec();
+        data.extend(abi::encode(&[Token::Uint(U256::from(7))]));
+
+        let decoded = decode_with_abi(Some("Counter".into()), &abi, &data).unwrap();
+        assert_eq!(decoded.contract.as_deref(), Some("Counter"));
+        assert_eq!(decoded.signature, "setNumber(uint256)");
+        assert_eq!(decoded.args[0].value, "7");
+
+        // selectors missing from the ABI aren't decoded against it
+        assert!(decode_with_abi(None, &abi, &[0xa9, 0x05, 0x9c, 0xbb]).is_none());
+    }
+
+    #[test]
+    fn registers_all_known_signatures() {
+        assert_eq!(REGISTRY.len(), KNOWN_SIGNATURES.len());
+    }
+
+    #[test]
+    fn falls_back_on_unknown_or_malformed_calldata() {
+        assert!(decode(&[]).is_none());
+        assert!(decode(&[0xde, 0xad, 0xbe, 0xef]).is_none());
+        // transfer selector, with truncated arguments
+        assert!(decode(&[0xa9, 0x05, 0x9c, 0xbb, 0x00]).is_none());
+    }
+}
@@ -1,16 +1,15 @@
-use std::str::FromStr;
-
-use ethers::{
-    core::k256::ecdsa::SigningKey,
-    prelude::*,
-    signers,
-    types::{serde_helpers::StringifiedNumeric, transaction::eip2718::TypedTransaction},
-};
+use ethers::{prelude::*, types::transaction::eip2718::TypedTransaction};
 use iron_dialogs::{Dialog, DialogMsg};
-use iron_networks::Network;
-use iron_wallets::{Wallet, WalletControl};
+use iron_networks::{Failover, InFlight, Network};
+use iron_settings::{Contacts, Settings};
+use iron_types::{GlobalState, RequestSource, SigningKind};
+use iron_wallets::{Wallet, WalletControl, WalletSigner};
 
-use super::{Error, Result};
+use iron_networks::FeeSuggestions;
+
+use super::{
+    audit, blocklist, fees, history, nonces, selectors, simulate, warnings, Error, Result,
+};
 
 /// Orchestrates the signing of a transaction
 /// Takes references to both the wallet and network where this
@@ -18,8 +17,22 @@ pub struct SendTransaction<'a> {
     pub wallet: &'a Wallet,
     pub wallet_path: String,
     pub network: Network,
+    pub source: Option<RequestSource>,
     pub request: TypedTransaction,
-    pub signer: Option<SignerMiddleware<Provider<Http>, signers::Wallet<SigningKey>>>,
+    pub signer: Option<SignerMiddleware<Provider<Failover>, WalletSigner>>,
+
+    /// Fees offered for review, if they could be estimated
+    pub fee_suggestions: Option<FeeSuggestions>,
+
+    /// The pending transaction this one speeds up or cancels, reusing its nonce
+    pub replaces: Option<H256>,
+
+    /// Whether it's sent through the network's private relay rather than the public mempool
+    /// Starts out as the network's default, and is up to the user on review
+    pub private: bool,
+
+    /// Whether it's only signed, and handed back to be broadcast elsewhere
+    pub sign_only: bool,
 }
 
 impl<'a> SendTransaction<'a> {
@@ -28,65 +41,344 @@ impl<'a> SendTransaction<'a> {
     }
 
     pub async fn estimate_gas(&mut self) -> &mut SendTransaction<'a> {
+        if self.request.gas().is_some() {
+            return self;
+        }
+
         // TODO: we're defaulting to 1_000_000 gas cost if estimation fails
         // estimation failing means the tx will faill anyway, so this is fine'ish
         // but can probably be improved a lot in the future
-        let gas_limit = self
-            .network
-            .get_provider()
-            .estimate_gas(&self.request, None)
-            .await
-            .unwrap_or(1_000_000.into());
+        let _in_flight = InFlight::start();
+        let gas_limit = match self.network.get_provider() {
+            Ok(provider) => provider
+                .estimate_gas(&self.request, None)
+                .await
+                .unwrap_or(1_000_000.into()),
+            Err(_) => 1_000_000.into(),
+        };
 
         self.request.set_gas(gas_limit * 120 / 100);
         self
     }
 
-    pub async fn finish(&mut self) -> Result<PendingTransaction<'_, Http>> {
+    /// Sets suggested fees, unless the request already carries its own
+    pub async fn fill_fees(&mut self) -> &mut SendTransaction<'a> {
+        self.fee_suggestions = fees::fill(&mut self.request, &self.network).await;
+        self
+    }
+
+    pub async fn finish(&mut self) -> Result<PendingTransaction<'_, Failover>> {
         tracing::debug!("finishing transaction");
 
+        let attempt = audit::Attempt::new(
+            self.wallet,
+            &self.wallet_path,
+            self.network.chain_id,
+            SigningKind::Transaction,
+            &self.request,
+        )
+        .await;
+
+        let result = match self.can_afford().await {
+            Ok(()) => self.approve_and_send().await,
+            Err(e) => Err(e),
+        };
+
+        attempt.record(&result).await;
+        result
+    }
+
+    /// Signs the transaction without broadcasting it, returning the raw signed bytes
+    pub async fn finish_signing(&mut self) -> Result<Bytes> {
+        tracing::debug!("finishing transaction, without broadcasting");
+        self.sign_only = true;
+
+        let attempt = audit::Attempt::new(
+            self.wallet,
+            &self.wallet_path,
+            self.network.chain_id,
+            SigningKind::Transaction,
+            &self.request,
+        )
+        .await;
+
+        let result = match self.can_afford().await {
+            Ok(()) => match self.approve().await {
+                Ok(()) => self.sign().await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+
+        attempt.record(&result).await;
+        result
+    }
+
+    async fn approve_and_send(&mut self) -> Result<PendingTransaction<'_, Failover>> {
+        match self.approve().await {
+            Ok(()) => self.send().await,
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn approve(&mut self) -> Result<()> {
+        self.wallet.ensure_can_sign(&self.wallet_path)?;
+
         let skip_dialog = self.network.is_dev() && self.wallet.is_dev();
-        if !skip_dialog {
-            self.spawn_dialog().await?;
+        if skip_dialog {
+            return Ok(());
         }
-        self.send().await
+
+        let fees = (self.request.gas().copied(), self.request.gas_price());
+        self.spawn_dialog().await?;
+
+        // the user may have raised the fees while reviewing, past what the balance covers
+        if (self.request.gas().copied(), self.request.gas_price()) != fees {
+            self.can_afford().await?;
+        }
+
+        Ok(())
     }
 
-    async fn spawn_dialog(&mut self) -> Result<()> {
-        let params = serde_json::to_value(&self.request).unwrap();
+    /// Checks that the sender's balance covers both the value and the worst-case gas cost
+    /// Catching this locally gives a clearer error than the node's "insufficient funds for gas"
+    async fn can_afford(&self) -> Result<()> {
+        let (Some(from), Some(gas)) = (self.request.from(), self.request.gas()) else {
+            return Ok(());
+        };
 
-        let dialog = Dialog::new("tx-review", params);
-        dialog.open().await?;
+        let provider = self.network.get_provider()?;
+        let _in_flight = InFlight::start();
 
-        match dialog.recv().await {
-            // TODO: in the future, send json values here to override params
-            Some(DialogMsg::Accept(_response)) => Ok(()),
+        // for EIP-1559 transactions, this is the max fee per gas
+        let gas_price = match self.request.gas_price() {
+            Some(price) => price,
+            None => provider.get_gas_price().await?,
+        };
 
-            _ =>
-            // TODO: what's the appropriate error to return here?
-            // or should we return Ok(_)? Err(_) seems to close the ws connection
-            {
-                Err(Error::TxDialogRejected)
-            }
+        // rollups may charge for posting the transaction to L1 on top of gas
+        let l1_fee = match self.network.estimate_l1_fee(&self.request).await {
+            Ok(Some(fee)) => fee.extra(),
+            _ => U256::zero(),
+        };
+
+        let value = self.request.value().copied().unwrap_or_default();
+        let needed = gas
+            .saturating_mul(gas_price)
+            .saturating_add(l1_fee)
+            .saturating_add(value);
+        let available = provider.get_balance(*from, None).await?;
+
+        if available < needed {
+            return Err(Error::InsufficientFunds { needed, available });
         }
+
+        Ok(())
+    }
+
+    async fn spawn_dialog(&mut self) -> Result<()> {
+        self.private = review(
+            &mut self.request,
+            &self.network,
+            &self.fee_suggestions,
+            self.replaces,
+            self.sign_only,
+            self.source.clone(),
+        )
+        .await?;
+
+        Ok(())
     }
 
-    async fn build_signer(&mut self) {
+    async fn build_signer(&mut self) -> Result<()> {
         if self.signer.is_none() {
-            let signer: signers::Wallet<SigningKey> = self
+            let signer = self
                 .wallet
                 .build_signer(self.network.chain_id, &self.wallet_path)
-                .await
-                .unwrap();
-            self.signer = Some(SignerMiddleware::new(self.network.get_provider(), signer));
+                .await?;
+            self.signer = Some(SignerMiddleware::new(self.network.get_provider()?, signer));
         }
+
+        Ok(())
     }
 
-    async fn send(&mut self) -> Result<PendingTransaction<'_, Http>> {
-        self.build_signer().await;
+    /// Nonces are left to the node's pending count rather than reserved, as there's no telling
+    /// when, or whether, a transaction signed this way gets broadcast
+    async fn sign(&mut self) -> Result<Bytes> {
+        self.build_signer().await?;
         let signer = self.signer.as_ref().unwrap();
+        let address = signer.address();
+
+        let _in_flight = InFlight::start();
+        let mut request = self.request.clone();
+        signer.fill_transaction(&mut request, None).await?;
+        let signature = Middleware::sign_transaction(signer, &request, address).await?;
 
-        Ok(signer.send_transaction(self.request.clone(), None).await?)
+        tracing::info!(
+            chain_id = self.network.chain_id,
+            "transaction signed without broadcasting"
+        );
+        Ok(request.rlp_signed(&signature))
+    }
+
+    async fn send(&mut self) -> Result<PendingTransaction<'_, Failover>> {
+        self.build_signer().await?;
+        let signer = self.signer.as_ref().unwrap();
+        let address = signer.address();
+
+        if self.request.nonce().is_none() {
+            let nonce = nonces::next_nonce(address, &self.network).await?;
+            self.request.set_nonce(nonce);
+        }
+
+        let relay = match self.private {
+            true => Some(
+                self.network
+                    .get_private_relay()
+                    .ok_or_else(|| Error::NoPrivateRelay(self.network.name.clone()))?,
+            ),
+            false => None,
+        };
+
+        // signed separately from broadcasting, so that the signed bytes can be kept, to
+        // rebroadcast after a restart if the node forgets about the transaction
+        let _in_flight = InFlight::start();
+        let broadcast = async {
+            let mut request = self.request.clone();
+            signer.fill_transaction(&mut request, None).await?;
+            let signature = Middleware::sign_transaction(signer, &request, address).await?;
+            let raw = request.rlp_signed(&signature);
+
+            // the relay holds the transaction until a builder includes it, but the public node
+            // is still the one that ends up with its receipt
+            let hash = match &relay {
+                Some(relay) => relay
+                    .send_raw_transaction(raw.clone())
+                    .await
+                    .map_err(SignerMiddlewareError::MiddlewareError)?
+                    .tx_hash(),
+                None => signer.send_raw_transaction(raw.clone()).await?.tx_hash(),
+            };
+            let pending = PendingTransaction::new(hash, signer.provider());
+
+            Ok::<_, SignerMiddlewareError<_, _>>((pending, raw))
+        };
+
+        match broadcast.await {
+            Ok((pending, raw)) => {
+                tracing::info!(
+                    hash = ?pending.tx_hash(),
+                    chain_id = self.network.chain_id,
+                    private = self.private,
+                    "transaction broadcast"
+                );
+                let peer = self.source.as_ref().map(|s| s.socket);
+                history::track(
+                    pending.tx_hash(),
+                    address,
+                    &self.request,
+                    Some(&raw),
+                    self.private,
+                    &self.network,
+                    peer,
+                )
+                .await;
+                Ok(pending)
+            }
+            // a replacement's nonce still belongs to the transaction it was meant to replace
+            Err(e) => {
+                tracing::info!("failed to broadcast transaction: {}", e);
+                if let (Some(nonce), None) = (self.request.nonce(), self.replaces) {
+                    nonces::release(address, self.network.chain_id, *nonce).await;
+                }
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Asks the user to review a transaction, applying any fee changes they make
+/// Fails with `TxDialogRejected` if they don't approve it
+/// Returns whether they chose to send it through the network's private relay
+/// Transactions that are only signed have no relay to choose, as they're broadcast elsewhere
+pub(crate) async fn review(
+    request: &mut TypedTransaction,
+    network: &Network,
+    fee_suggestions: &Option<FeeSuggestions>,
+    replaces: Option<H256>,
+    sign_only: bool,
+    source: Option<RequestSource>,
+) -> Result<bool> {
+    let mut params = serde_json::to_value(&*request)?;
+    let mut warnings = warnings::detect(request);
+    warnings.extend(blocklist::check(request, network, source.as_ref()).await);
+    params["warnings"] = serde_json::to_value(warnings)?;
+    params["decoded"] = serde_json::to_value(decode(request, network.chain_id).await)?;
+
+    // warn upfront if the transaction is bound to fail, rather than after paying for gas, and
+    // otherwise show what it's expected to do
+    let simulation = simulate::preview(request, network).await;
+    params["revert"] = simulation.revert.clone().into();
+
+    // every storage slot it'd touch, for those who want to check exactly what it does
+    if simulation.revert.is_none() && Settings::read().await.get().state_diff_preview {
+        params["stateDiff"] = serde_json::to_value(simulate::state_diff(request, network).await)?;
+    }
+    params["simulation"] = serde_json::to_value(simulation)?;
+
+    // show who's on the other end, when they have a name
+    // the user's contacts take precedence over ENS
+    if let Some(to) = request.to().and_then(|to| to.as_address()) {
+        let contact = Contacts::read()
+            .await
+            .resolve((*to).into(), Some(network.chain_id));
+        params["toName"] = match contact {
+            Some(name) => Some(name),
+            None => iron_networks::lookup_address(*to).await.ok().flatten(),
+        }
+        .into();
+    }
+
+    let (symbol, decimals) = network.native_currency();
+    params["nativeCurrency"] = serde_json::json!({ "symbol": symbol, "decimals": decimals });
+    params["feeSuggestions"] = serde_json::to_value(fee_suggestions)?;
+    params["replaces"] = serde_json::to_value(replaces)?;
+
+    params["signOnly"] = sign_only.into();
+
+    let has_relay = !sign_only && network.get_private_relay().is_some();
+    params["privateRelay"] = has_relay.into();
+    params["private"] = (has_relay && network.private_by_default).into();
+
+    let dialog = Dialog::with_source("tx-review", params, source);
+    dialog.open().await?;
+
+    match dialog.recv().await {
+        // TODO: in the future, send json values here to override params
+        // for now, only fees can be changed
+        Some(DialogMsg::Accept(response)) => {
+            if let Some(fees) = fees::overrides(request, &response)? {
+                fees::apply(request, fees);
+            }
+            Ok(has_relay && response["private"].as_bool().unwrap_or(false))
+        }
+
+        _ =>
+        // TODO: what's the appropriate error to return here?
+        // or should we return Ok(_)? Err(_) seems to close the ws connection
+        {
+            Err(Error::TxDialogRejected)
+        }
+    }
+}
+
+/// Turns calldata into a function call for the approval dialog, if it can be made sense of
+async fn decode(request: &TypedTransaction, chain_id: u32) -> Option<selectors::DecodedCall> {
+    let data = request.data()?;
+
+    match request.to().and_then(|to| to.as_address()) {
+        Some(to) => selectors::decode_for(chain_id, *to, data.as_ref()).await,
+        None => selectors::decode(data.as_ref()),
     }
 }
 
@@ -95,7 +387,9 @@ pub struct SendTransactionBuilder<'a> {
     pub wallet: Option<&'a Wallet>,
     pub wallet_path: Option<String>,
     pub network: Option<Network>,
+    pub source: Option<RequestSource>,
     pub request: TypedTransaction,
+    pub replaces: Option<H256>,
 }
 
 impl<'a> SendTransactionBuilder<'a> {
@@ -114,43 +408,38 @@ impl<'a> SendTransactionBuilder<'a> {
         self
     }
 
-    pub fn set_request(mut self, params: serde_json::Value) -> SendTransactionBuilder<'a> {
-        // TODO: why is this an array?
-        let params = if params.is_array() {
-            &params.as_array().unwrap()[0]
-        } else {
-            &params
-        };
-
-        if let Some(from) = params["from"].as_str() {
-            self.request.set_from(Address::from_str(from).unwrap());
-        }
-
-        if let Some(to) = params["to"].as_str() {
-            self.request.set_to(Address::from_str(to).unwrap());
-        }
-
-        if let Some(value) = params["value"].as_str() {
-            let v = StringifiedNumeric::String(value.to_string());
-            self.request.set_value(U256::try_from(v).unwrap());
-        }
+    pub fn set_source(mut self, source: Option<RequestSource>) -> SendTransactionBuilder<'a> {
+        self.source = source;
+        self
+    }
 
-        if let Some(data) = params["data"].as_str() {
-            self.request.set_data(Bytes::from_str(data).unwrap());
-        }
+    pub fn set_request(mut self, request: TypedTransaction) -> SendTransactionBuilder<'a> {
+        self.request = request;
+        self
+    }
 
+    pub fn set_replaces(mut self, hash: H256) -> SendTransactionBuilder<'a> {
+        self.replaces = Some(hash);
         self
     }
 
     pub fn build(self) -> SendTransaction<'a> {
         tracing::debug!("building SendTransaction");
 
+        let network = self.network.unwrap();
+        let private = network.private_by_default && network.get_private_relay().is_some();
+
         SendTransaction {
             wallet: self.wallet.unwrap(),
             wallet_path: self.wallet_path.unwrap(),
-            network: self.network.unwrap(),
+            network,
+            source: self.source,
             request: self.request,
             signer: None,
+            fee_suggestions: None,
+            private,
+            replaces: self.replaces,
+            sign_only: false,
         }
     }
 }
@@ -1,21 +1,18 @@
-use std::str::FromStr;
-
 use ethers::{
-    core::k256::ecdsa::SigningKey,
     prelude::SignerMiddleware,
-    providers::{Http, Middleware as _, Provider},
-    signers,
+    providers::{Middleware as _, Provider},
     signers::Signer,
-    types::{transaction::eip712, Bytes, Signature},
+    types::{transaction::eip712, Address, Signature, U256},
 };
 use iron_dialogs::{Dialog, DialogMsg};
-use iron_networks::Network;
-use iron_wallets::{Wallet, WalletControl};
+use iron_networks::{Failover, Network};
+use iron_types::{RequestSource, SigningKind};
+use iron_wallets::{message_bytes, Wallet, WalletControl, WalletSigner};
 use serde::Serialize;
 
-use super::{Error, Result};
+use super::{audit, permits, siwe, Error, Result};
 
-type Middleware = SignerMiddleware<Provider<Http>, signers::Wallet<SigningKey>>;
+type Middleware = SignerMiddleware<Provider<Failover>, WalletSigner>;
 
 /// Orchestrates message signing
 /// Takes references to both the wallet and network
@@ -23,6 +20,10 @@ pub struct SignMessage<'a> {
     pub wallet: &'a Wallet,
     pub wallet_path: String,
     pub network: Network,
+    pub source: Option<RequestSource>,
+
+    /// The JSON-RPC method this was requested through, for the signed message log
+    pub method: &'static str,
     data: Data,
 }
 
@@ -32,6 +33,32 @@ impl<'a> SignMessage<'a> {
     }
 
     pub async fn finish(&mut self) -> Result<Signature> {
+        let kind = match self.data {
+            Data::Raw(_) => SigningKind::Message,
+            Data::Typed(_) => SigningKind::TypedData,
+        };
+        let attempt = audit::Attempt::new(
+            self.wallet,
+            &self.wallet_path,
+            self.network.chain_id,
+            kind,
+            &self.data,
+        )
+        .await;
+
+        let result = self.approve_and_sign().await;
+
+        if let Ok(signature) = &result {
+            let origin = self.source.as_ref().map(|s| s.origin.clone());
+            attempt.record_signed(self.method, origin, signature).await;
+        }
+        attempt.record(&result).await;
+        result
+    }
+
+    async fn approve_and_sign(&mut self) -> Result<Signature> {
+        self.wallet.ensure_can_sign(&self.wallet_path)?;
+
         let skip_dialog = self.network.is_dev() && self.wallet.is_dev();
         if !skip_dialog {
             self.spawn_dialog().await?;
@@ -40,9 +67,17 @@ impl<'a> SignMessage<'a> {
     }
 
     async fn spawn_dialog(&mut self) -> Result<()> {
-        let params = serde_json::to_value(&self.data).unwrap();
+        let origin = self.source.as_ref().map(|s| s.origin.as_str());
+        let account = self
+            .wallet
+            .get_address_for_path(&self.wallet_path)
+            .await
+            .map(Into::into);
 
-        let dialog = Dialog::new("msg-sign", params);
+        let mut params = serde_json::to_value(&self.data)?;
+        params["preview"] = self.data.preview(&self.network, origin, account).await;
+
+        let dialog = Dialog::with_source("msg-sign", params, self.source.clone());
         dialog.open().await?;
 
         match dialog.recv().await {
@@ -58,25 +93,24 @@ impl<'a> SignMessage<'a> {
     }
 
     pub async fn sign(&mut self) -> Result<Signature> {
-        let signer = self.build_signer().await;
+        let signer = self.build_signer().await?;
+        tracing::info!(signer = ?signer.address(), "signing message");
 
         match self.data {
-            Data::Raw(ref msg) => {
-                let bytes = Bytes::from_str(msg).unwrap();
-                Ok(signer.sign(bytes, &signer.address()).await?)
-            }
+            Data::Raw(ref msg) => Ok(signer
+                .sign(message_bytes(msg.as_bytes()), &signer.address())
+                .await?),
             Data::Typed(ref data) => Ok(signer.signer().sign_typed_data(&data.clone()).await?),
         }
     }
 
-    async fn build_signer(&self) -> Middleware {
-        let signer: signers::Wallet<SigningKey> = self
+    async fn build_signer(&self) -> Result<Middleware> {
+        let signer = self
             .wallet
             .build_signer(self.network.chain_id, &self.wallet_path)
-            .await
-            .unwrap();
+            .await?;
 
-        SignerMiddleware::new(self.network.get_provider(), signer)
+        Ok(SignerMiddleware::new(self.network.get_provider()?, signer))
     }
 }
 
@@ -86,11 +120,63 @@ enum Data {
     Typed(eip712::TypedData),
 }
 
+impl Data {
+    /// A summary of what's being signed, for the approval dialog
+    /// Sign-In with Ethereum messages are broken down into their fields, and checked against
+    /// the requesting origin and the signing account
+    /// Permits are broken down into the approvals they grant, with their tokens' metadata
+    async fn preview(
+        &self,
+        network: &Network,
+        origin: Option<&str>,
+        account: Option<Address>,
+    ) -> serde_json::Value {
+        let chain_id = network.chain_id;
+
+        match self {
+            Data::Raw(msg) => {
+                let text = decode_text(msg);
+                let siwe = text
+                    .as_deref()
+                    .and_then(siwe::parse)
+                    .map(|m| m.preview(origin, account, chain_id));
+
+                serde_json::json!({ "message": msg, "text": text, "siwe": siwe })
+            }
+            Data::Typed(data) => {
+                // signatures meant for another chain are a common phishing trick
+                let wrong_chain = data
+                    .domain
+                    .chain_id
+                    .is_some_and(|id| id != U256::from(chain_id));
+                let permits = permits::preview(network, data).await;
+
+                serde_json::json!({
+                    "primaryType": data.primary_type,
+                    "domain": data.domain,
+                    "message": data.message,
+                    "wrongChain": wrong_chain,
+                    "permits": permits,
+                })
+            }
+        }
+    }
+}
+
+/// A readable version of a raw message, if it decodes into printable UTF-8
+fn decode_text(msg: &str) -> Option<String> {
+    String::from_utf8(message_bytes(msg.as_bytes()))
+        .ok()
+        .filter(|text| !text.chars().any(|c| c.is_control() && !c.is_whitespace()))
+}
+
 #[derive(Default)]
 pub struct SignMessageBuilder<'a> {
     pub wallet: Option<&'a Wallet>,
     pub wallet_path: Option<String>,
     pub network: Option<Network>,
+    pub source: Option<RequestSource>,
+    pub method: Option<&'static str>,
     data: Option<Data>,
 }
 
@@ -110,6 +196,16 @@ impl<'a> SignMessageBuilder<'a> {
         self
     }
 
+    pub fn set_source(mut self, source: Option<RequestSource>) -> SignMessageBuilder<'a> {
+        self.source = source;
+        self
+    }
+
+    pub fn set_method(mut self, method: &'static str) -> SignMessageBuilder<'a> {
+        self.method = Some(method);
+        self
+    }
+
     pub fn set_string_data(mut self, msg: String) -> SignMessageBuilder<'a> {
         self.data = Some(Data::Raw(msg));
         self
@@ -123,11 +219,42 @@ impl<'a> SignMessageBuilder<'a> {
     pub fn build(self) -> SignMessage<'a> {
         tracing::debug!("building SendTransaction");
 
+        let data = self.data.unwrap();
+        let method = self.method.unwrap_or(match data {
+            Data::Raw(_) => "personal_sign",
+            Data::Typed(_) => "eth_signTypedData_v4",
+        });
+
         SignMessage {
             wallet: self.wallet.unwrap(),
             wallet_path: self.wallet_path.unwrap(),
             network: self.network.unwrap(),
-            data: self.data.unwrap(),
+            source: self.source,
+            method,
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_messages_decode_to_text_when_printable() {
+        assert_eq!(decode_text("0x68656c6c6f").as_deref(), Some("hello"));
+        assert_eq!(decode_text("hello world").as_deref(), Some("hello world"));
+        assert_eq!(decode_text("0x000102"), None);
+        assert_eq!(decode_text("0xff"), None);
+
+        assert_eq!(message_bytes(b"0x68656c6c6f"), message_bytes(b"hello"));
+    }
+
+    #[test]
+    fn unprefixed_hex_is_signed_as_text() {
+        for msg in ["cafe", "deadbeef", "1234"] {
+            assert_eq!(decode_text(msg).as_deref(), Some(msg));
+            assert_eq!(message_bytes(msg.as_bytes()), msg.as_bytes());
         }
     }
 }
@@ -0,0 +1,540 @@
+use std::str::FromStr;
+
+use ethers::{
+    abi::{self, ParamType},
+    prelude::*,
+    types::transaction::eip2718::TypedTransaction,
+};
+use iron_networks::{InFlight, L1Fee, Network};
+use serde::Serialize;
+use serde_json::json;
+
+use super::{Error, Result};
+
+/// `Error(string)` selector, used by `revert("...")` and `require(..., "...")`
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// `Panic(uint256)` selector, used by failed asserts, overflows, etc
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Dry-runs a transaction through `eth_call`, without sending it
+/// Fails with `Error::Reverted` if it would revert
+pub(crate) async fn simulate(request: &TypedTransaction, network: &Network) -> Result<Bytes> {
+    let _in_flight = InFlight::start();
+
+    match network.get_provider()?.call(request, None).await {
+        Ok(output) => Ok(output),
+        Err(e) => match revert_reason(&e) {
+            Some(reason) => Err(Error::Reverted(reason)),
+            None => Err(e.into()),
+        },
+    }
+}
+
+/// The expected outcome of a transaction, shown before asking for approval
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Simulation {
+    pub revert: Option<String>,
+    pub gas_used: Option<U256>,
+
+    /// On rollups, the cost of posting the transaction to L1, which `gas * gasPrice` leaves out
+    pub l1_fee: Option<L1Fee>,
+
+    /// Net balance changes of the sender, with `None` standing for the native currency
+    /// Gas costs aren't included
+    pub balance_changes: Vec<BalanceChange>,
+
+    /// ERC-20 transfers the transaction would emit
+    /// `None` if the node can't trace calls, as opposed to there being no transfers
+    pub transfers: Option<Vec<Transfer>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BalanceChange {
+    pub token: Option<Address>,
+
+    /// Serialized in base 10, since a signed value can't be shown as a plain hex quantity
+    #[serde(serialize_with = "serialize_decimal")]
+    pub delta: I256,
+}
+
+fn serialize_decimal<S: serde::Serializer>(
+    n: &I256,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&n.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Transfer {
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    pub amount: U256,
+}
+
+/// Simulates a transaction ahead of approval
+/// Nothing in here is fatal: whatever the node can't tell us is left out, and the dialog shows
+/// what's left
+pub(crate) async fn preview(request: &TypedTransaction, network: &Network) -> Simulation {
+    let revert = match simulate(request, network).await {
+        Err(Error::Reverted(reason)) => Some(reason),
+        _ => None,
+    };
+
+    // a reverting transaction has no outcome other than the revert itself
+    if revert.is_some() {
+        return Simulation {
+            revert,
+            ..Default::default()
+        };
+    }
+
+    let gas_used = match network.get_provider() {
+        Ok(provider) => {
+            let _in_flight = InFlight::start();
+            provider.estimate_gas(request, None).await.ok()
+        }
+        Err(_) => None,
+    };
+    let l1_fee = network.estimate_l1_fee(request).await.ok().flatten();
+    let transfers = trace_transfers(request, network).await;
+
+    let balance_changes = match request.from() {
+        Some(from) => balance_changes(
+            *from,
+            request.value().copied().unwrap_or_default(),
+            transfers.as_deref().unwrap_or_default(),
+        ),
+        None => vec![],
+    };
+
+    Simulation {
+        revert,
+        gas_used,
+        l1_fee,
+        balance_changes,
+        transfers,
+    }
+}
+
+/// Collects ERC-20 transfers from a `debug_traceCall` with logs enabled
+async fn trace_transfers(request: &TypedTransaction, network: &Network) -> Option<Vec<Transfer>> {
+    let provider = network.get_provider().ok()?;
+    let _in_flight = InFlight::start();
+
+    let trace: serde_json::Value = provider
+        .request(
+            "debug_traceCall",
+            json!([
+                request,
+                "latest",
+                { "tracer": "callTracer", "tracerConfig": { "withLog": true } }
+            ]),
+        )
+        .await
+        .ok()?;
+
+    let mut transfers = vec![];
+    collect_transfers(&trace, &mut transfers);
+    Some(transfers)
+}
+
+/// Walks a call trace depth-first, so transfers come out in the order they were emitted
+fn collect_transfers(call: &serde_json::Value, transfers: &mut Vec<Transfer>) {
+    let transfer_topic = H256::from(ethers::utils::keccak256(
+        "Transfer(address,address,uint256)",
+    ));
+
+    for log in call["logs"].as_array().into_iter().flatten() {
+        let Ok(log) = serde_json::from_value::<Log>(log.clone()) else {
+            continue;
+        };
+
+        // ERC-721 transfers share the event signature, but index the token ID as a 4th topic
+        if log.topics.len() != 3 || log.topics[0] != transfer_topic || log.data.len() != 32 {
+            continue;
+        }
+
+        transfers.push(Transfer {
+            token: log.address,
+            from: Address::from(log.topics[1]),
+            to: Address::from(log.topics[2]),
+            amount: U256::from_big_endian(&log.data),
+        });
+    }
+
+    for inner in call["calls"].as_array().into_iter().flatten() {
+        collect_transfers(inner, transfers);
+    }
+}
+
+fn balance_changes(from: Address, value: U256, transfers: &[Transfer]) -> Vec<BalanceChange> {
+    let mut changes: Vec<BalanceChange> = vec![];
+
+    if !value.is_zero() {
+        changes.push(BalanceChange {
+            token: None,
+            delta: -I256::from_raw(value),
+        });
+    }
+
+    for transfer in transfers {
+        let delta = match (transfer.from == from, transfer.to == from) {
+            (true, false) => -I256::from_raw(transfer.amount),
+            (false, true) => I256::from_raw(transfer.amount),
+            _ => continue,
+        };
+
+        match changes.iter_mut().find(|c| c.token == Some(transfer.token)) {
+            Some(change) => change.delta += delta,
+            None => changes.push(BalanceChange {
+                token: Some(transfer.token),
+                delta,
+            }),
+        }
+    }
+
+    changes.retain(|c| !c.delta.is_zero());
+    changes
+}
+
+/// What a transaction would change in an account's state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccountDiff {
+    pub address: Address,
+    pub balance: Option<Change<U256>>,
+    pub nonce: Option<Change<U256>>,
+
+    /// Whether code would be deployed at the address
+    pub code_changed: bool,
+
+    /// Ordered by slot
+    pub storage: Vec<SlotDiff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct SlotDiff {
+    pub slot: H256,
+    pub before: H256,
+    pub after: H256,
+}
+
+/// Every balance, nonce, code and storage change of a transaction, from a `debug_traceCall`
+/// with the prestate tracer in diff mode
+/// `None` if the node can't trace calls
+pub(crate) async fn state_diff(
+    request: &TypedTransaction,
+    network: &Network,
+) -> Option<Vec<AccountDiff>> {
+    let provider = network.get_provider().ok()?;
+    let _in_flight = InFlight::start();
+
+    let trace: serde_json::Value = provider
+        .request(
+            "debug_traceCall",
+            json!([
+                request,
+                "latest",
+                { "tracer": "prestateTracer", "tracerConfig": { "diffMode": true } }
+            ]),
+        )
+        .await
+        .ok()?;
+
+    Some(parse_state_diff(&trace))
+}
+
+/// Diff mode lists the prior state of every account a transaction changes in `pre`, and only
+/// the fields it changes in `post`, leaving out storage slots it zeroes. Accounts missing from
+/// `post` were destroyed
+fn parse_state_diff(trace: &serde_json::Value) -> Vec<AccountDiff> {
+    let empty = serde_json::Map::new();
+    let pre = trace["pre"].as_object().unwrap_or(&empty);
+    let post = trace["post"].as_object().unwrap_or(&empty);
+
+    let mut addresses: Vec<&String> = pre.keys().chain(post.keys()).collect();
+    addresses.sort();
+    addresses.dedup();
+
+    addresses
+        .into_iter()
+        .filter_map(|key| {
+            let address = Address::from_str(key).ok()?;
+            let destroyed = !post.contains_key(key);
+            let before = &pre.get(key).cloned().unwrap_or_default();
+            let after = &post.get(key).cloned().unwrap_or_default();
+
+            let change = |field: &str| {
+                let after = match quantity(&after[field]) {
+                    Some(after) => after,
+                    None if destroyed => U256::zero(),
+                    None => return None,
+                };
+                let before = quantity(&before[field]).unwrap_or_default();
+
+                (before != after).then_some(Change { before, after })
+            };
+
+            let slots = |account: &serde_json::Value| -> Vec<(H256, H256)> {
+                account["storage"]
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(slot, value)| {
+                        Some((
+                            H256::from_str(slot).ok()?,
+                            serde_json::from_value(value.clone()).ok()?,
+                        ))
+                    })
+                    .collect()
+            };
+            let (slots_before, slots_after) = (slots(before), slots(after));
+            let value_in = |slots: &[(H256, H256)], slot: H256| {
+                slots
+                    .iter()
+                    .find(|(s, _)| *s == slot)
+                    .map(|(_, v)| *v)
+                    .unwrap_or_default()
+            };
+
+            let mut storage: Vec<_> = slots_before
+                .iter()
+                .chain(&slots_after)
+                .map(|(slot, _)| SlotDiff {
+                    slot: *slot,
+                    before: value_in(&slots_before, *slot),
+                    after: value_in(&slots_after, *slot),
+                })
+                .filter(|diff| diff.before != diff.after)
+                .collect();
+            storage.sort_by_key(|diff| diff.slot);
+            storage.dedup();
+
+            let diff = AccountDiff {
+                address,
+                balance: change("balance"),
+                nonce: change("nonce"),
+                code_changed: after.get("code").is_some(),
+                storage,
+            };
+
+            let changed = diff.balance.is_some()
+                || diff.nonce.is_some()
+                || diff.code_changed
+                || !diff.storage.is_empty();
+            changed.then_some(diff)
+        })
+        .collect()
+}
+
+/// Nonces are traced as plain numbers, and balances as hex quantities
+fn quantity(value: &serde_json::Value) -> Option<U256> {
+    match value.as_u64() {
+        Some(n) => Some(n.into()),
+        None => serde_json::from_value(value.clone()).ok(),
+    }
+}
+
+/// Extracts the revert reason from a failed `eth_call`, if that's why it failed
+fn revert_reason(e: &ProviderError) -> Option<String> {
+    let ProviderError::JsonRpcClientError(e) = e else {
+        return None;
+    };
+    let e = e.as_error_response()?;
+
+    // most nodes include the raw revert data as a hex string
+    let decoded = e
+        .data
+        .as_ref()
+        .and_then(|data| data.as_str())
+        .and_then(|data| Bytes::from_str(data).ok())
+        .and_then(|data| decode_revert(&data));
+    if decoded.is_some() {
+        return decoded;
+    }
+
+    let reason = e.message.strip_prefix("execution reverted")?;
+    let reason = reason.trim_start_matches(':').trim();
+
+    Some(if reason.is_empty() {
+        String::from("no reason given")
+    } else {
+        reason.to_string()
+    })
+}
+
+/// Decodes standard `Error(string)` and `Panic(uint256)` revert data
+fn decode_revert(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+
+    let (selector, args) = data.split_at(4);
+
+    if selector == ERROR_SELECTOR {
+        abi::decode(&[ParamType::String], args)
+            .ok()?
+            .pop()?
+            .into_string()
+    } else if selector == PANIC_SELECTOR {
+        let code = abi::decode(&[ParamType::Uint(256)], args)
+            .ok()?
+            .pop()?
+            .into_uint()?;
+        Some(format!("panic code 0x{:x}", code))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::Token;
+
+    use super::*;
+
+    #[test]
+    fn decodes_standard_revert_data() {
+        let mut error = ERROR_SELECTOR.to_vec();
+        error.extend(abi::encode(&[Token::String(
+            "insufficient allowance".into(),
+        )]));
+        assert_eq!(
+            decode_revert(&error).as_deref(),
+            Some("insufficient allowance")
+        );
+
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend(abi::encode(&[Token::Uint(0x11.into())]));
+        assert_eq!(decode_revert(&panic).as_deref(), Some("panic code 0x11"));
+
+        let invalid: [&[u8]; 4] = [
+            &[],
+            &[0x08, 0xc3],
+            &ERROR_SELECTOR,
+            &[0xde, 0xad, 0xbe, 0xef, 0x00],
+        ];
+        for data in invalid {
+            assert_eq!(decode_revert(data), None, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn parses_prestate_diffs() {
+        let trace = json!({
+            "pre": {
+                "0x0000000000000000000000000000000000000001": {
+                    "balance": "0x10",
+                    "nonce": 1,
+                },
+                "0x0000000000000000000000000000000000000003": {
+                    "balance": "0x64",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                            "0x0000000000000000000000000000000000000000000000000000000000000005",
+                        "0x0000000000000000000000000000000000000000000000000000000000000002":
+                            "0x0000000000000000000000000000000000000000000000000000000000000007",
+                    },
+                },
+            },
+            "post": {
+                "0x0000000000000000000000000000000000000001": {
+                    "balance": "0x8",
+                    "nonce": 2,
+                },
+                "0x0000000000000000000000000000000000000003": {
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000002":
+                            "0x0000000000000000000000000000000000000000000000000000000000000009",
+                    },
+                },
+            },
+        });
+
+        let diff = parse_state_diff(&trace);
+        assert_eq!(diff.len(), 2);
+
+        assert_eq!(diff[0].address, Address::from_low_u64_be(1));
+        assert_eq!(
+            diff[0].balance,
+            Some(Change {
+                before: 0x10.into(),
+                after: 0x8.into()
+            })
+        );
+        assert_eq!(
+            diff[0].nonce,
+            Some(Change {
+                before: 1.into(),
+                after: 2.into()
+            })
+        );
+
+        // the first slot was cleared, and the balance left untouched
+        let contract = &diff[1];
+        assert_eq!(contract.balance, None);
+        assert!(!contract.code_changed);
+        assert_eq!(
+            contract.storage,
+            vec![
+                SlotDiff {
+                    slot: H256::from_low_u64_be(1),
+                    before: H256::from_low_u64_be(5),
+                    after: H256::zero(),
+                },
+                SlotDiff {
+                    slot: H256::from_low_u64_be(2),
+                    before: H256::from_low_u64_be(7),
+                    after: H256::from_low_u64_be(9),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn nets_out_sender_balance_changes() {
+        let sender = Address::from_low_u64_be(1);
+        let other = Address::from_low_u64_be(2);
+        let token = Address::from_low_u64_be(3);
+
+        let transfers = vec![
+            Transfer {
+                token,
+                from: sender,
+                to: other,
+                amount: 10.into(),
+            },
+            Transfer {
+                token,
+                from: other,
+                to: sender,
+                amount: 4.into(),
+            },
+        ];
+
+        assert_eq!(
+            balance_changes(sender, 5.into(), &transfers),
+            vec![
+                BalanceChange {
+                    token: None,
+                    delta: I256::from(-5)
+                },
+                BalanceChange {
+                    token: Some(token),
+                    delta: I256::from(-6)
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Recognizes Sign-In with Ethereum messages (ERC-4361), so that they're reviewed as a login
+//! rather than as opaque text
+//! A login signed for one site can be replayed on another, so the domain it names is checked
+//! against the site that's asking
+
+use std::str::FromStr;
+
+use ethers::types::Address;
+use serde::Serialize;
+use url::Url;
+
+const HEADER: &str = " wants you to sign in with your Ethereum account:";
+
+/// The fields of an ERC-4361 message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SiweMessage {
+    pub scheme: Option<String>,
+    pub domain: String,
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: String,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+/// A message to review, with whatever about it doesn't add up
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SiwePreview {
+    #[serde(flatten)]
+    pub message: SiweMessage,
+
+    /// The message is for a different site than the one asking to sign it
+    pub domain_mismatch: bool,
+
+    /// The message names a different account than the one signing it
+    pub wrong_account: bool,
+
+    pub wrong_chain: bool,
+}
+
+impl SiweMessage {
+    /// Checks the message against who's asking, who's signing, and on which chain
+    /// Requests without an origin come from the app itself, and aren't checked for their domain
+    pub fn preview(
+        self,
+        origin: Option<&str>,
+        account: Option<Address>,
+        chain_id: u32,
+    ) -> SiwePreview {
+        SiwePreview {
+            domain_mismatch: origin.is_some_and(|origin| !self.matches_origin(origin)),
+            wrong_account: account.is_some_and(|account| account != self.address),
+            wrong_chain: self.chain_id != chain_id as u64,
+            message: self,
+        }
+    }
+
+    /// Whether the message's domain, and scheme if it names one, are those of an origin
+    fn matches_origin(&self, origin: &str) -> bool {
+        let Ok(url) = Url::parse(origin) else {
+            return false;
+        };
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+
+        let authority = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let scheme_matches = match &self.scheme {
+            Some(scheme) => scheme.eq_ignore_ascii_case(url.scheme()),
+            None => true,
+        };
+
+        scheme_matches && authority.eq_ignore_ascii_case(&self.domain)
+    }
+}
+
+/// Parses a message, or `None` if it isn't a Sign-In with Ethereum one
+pub(crate) fn parse(text: &str) -> Option<SiweMessage> {
+    let mut lines = text.lines();
+
+    let prefix = lines.next()?.strip_suffix(HEADER)?;
+    let (scheme, domain) = match prefix.split_once("://") {
+        Some((scheme, domain)) => (Some(scheme.to_string()), domain),
+        None => (None, prefix),
+    };
+    if domain.is_empty() || domain.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let address = Address::from_str(lines.next()?.trim()).ok()?;
+
+    // the statement is optional, and sits between blank lines before the first field
+    let mut statement = vec![];
+    let mut lines = lines.peekable();
+    while let Some(line) = lines.next_if(|l| !l.starts_with("URI: ")) {
+        if !line.trim().is_empty() {
+            statement.push(line.trim());
+        }
+    }
+
+    let mut field = |name: &str| {
+        lines
+            .next_if(|l| l.starts_with(name))
+            .map(|l| l[name.len()..].trim().to_string())
+    };
+
+    let uri = field("URI: ")?;
+    let version = field("Version: ")?;
+    let chain_id = field("Chain ID: ")?.parse().ok()?;
+    let nonce = field("Nonce: ")?;
+    let issued_at = field("Issued At: ")?;
+    let expiration_time = field("Expiration Time: ");
+    let not_before = field("Not Before: ");
+    let request_id = field("Request ID: ");
+
+    let mut resources = vec![];
+    if field("Resources:").is_some() {
+        resources = lines
+            .filter_map(|l| l.strip_prefix("- "))
+            .map(|l| l.trim().to_string())
+            .collect();
+    }
+
+    Some(SiweMessage {
+        scheme,
+        domain: domain.to_string(),
+        address,
+        statement: (!statement.is_empty()).then(|| statement.join("\n")),
+        uri,
+        version,
+        chain_id,
+        nonce,
+        issued_at,
+        expiration_time,
+        not_before,
+        request_id,
+        resources,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "service.org wants you to sign in with your Ethereum account:
+0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2
+
+I accept the ServiceOrg Terms of Service: https://service.org/tos
+
+URI: https://service.org/login
+Version: 1
+Chain ID: 1
+Nonce: 32891756
+Issued At: 2021-09-30T16:25:24Z
+Expiration Time: 2021-10-30T16:25:24Z
+Resources:
+- ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/
+- https://example.com/my-web2-claim.json";
+
+    #[test]
+    fn parses_messages() {
+        let message = parse(MESSAGE).unwrap();
+
+        assert_eq!(message.scheme, None);
+        assert_eq!(message.domain, "service.org");
+        assert_eq!(
+            message.statement.as_deref(),
+            Some("I accept the ServiceOrg Terms of Service: https://service.org/tos")
+        );
+        assert_eq!(message.chain_id, 1);
+        assert_eq!(message.nonce, "32891756");
+        assert_eq!(
+            message.expiration_time.as_deref(),
+            Some("2021-10-30T16:25:24Z")
+        );
+        assert_eq!(message.not_before, None);
+        assert_eq!(message.resources.len(), 2);
+    }
+
+    #[test]
+    fn parses_messages_without_statement() {
+        let text = MESSAGE.replace(
+            "I accept the ServiceOrg Terms of Service: https://service.org/tos\n\n",
+            "",
+        );
+
+        let message = parse(&text).unwrap();
+        assert_eq!(message.statement, None);
+        assert_eq!(message.uri, "https://service.org/login");
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        assert!(parse("hello").is_none());
+        assert!(parse(&MESSAGE.replace("Nonce: 32891756\n", "")).is_none());
+    }
+
+    #[test]
+    fn checks_domain_against_origin() {
+        let message = parse(MESSAGE).unwrap();
+
+        assert!(message.matches_origin("https://service.org"));
+        assert!(!message.matches_origin("https://evil.com"));
+        assert!(!message.matches_origin("https://service.org.evil.com"));
+        assert!(!message.matches_origin("https://service.org:8080"));
+
+        let preview = message.preview(Some("https://evil.com"), None, 1);
+        assert!(preview.domain_mismatch);
+        assert!(!preview.wrong_chain);
+    }
+}
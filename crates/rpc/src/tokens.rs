@@ -0,0 +1,175 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ethers::{
+    contract::Multicall,
+    providers::{Middleware as _, Provider},
+    types::{Address, U64},
+};
+use iron_abis::IERC20;
+use iron_db::DB;
+use iron_networks::{Failover, InFlight, Network, Networks};
+use iron_types::{GlobalState, TokenMetadata, UINotify};
+use iron_wallets::{WalletControl, Wallets};
+use once_cell::sync::OnceCell;
+use tokio::time::{interval, Duration};
+
+use super::{Error, Result};
+
+static DB: OnceCell<DB> = OnceCell::new();
+
+/// How often the current network is checked for new blocks
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+pub(crate) fn init(db: DB) {
+    DB.set(db).unwrap();
+    tokio::spawn(async { watcher().await });
+}
+
+fn db() -> Result<&'static DB> {
+    DB.get().ok_or(Error::NotInitialized("tokens"))
+}
+
+/// Adds a token to the network's watch list, and fetches its balance for the current account
+/// Metadata always comes from the contract itself, so that a dapp can't pass off a token as
+/// something else through `wallet_watchAsset`
+pub(crate) async fn watch(network: &Network, contract: Address) -> Result<()> {
+    let client = Arc::new(network.get_provider()?);
+    let metadata = fetch_metadata(client, contract).await?;
+
+    watch_with(network, contract, metadata).await
+}
+
+/// Adds a token whose metadata was already fetched from its contract
+pub(crate) async fn watch_with(
+    network: &Network,
+    contract: Address,
+    metadata: TokenMetadata,
+) -> Result<()> {
+    db()?
+        .watch_token(network.chain_id, contract, metadata)
+        .await?;
+
+    let owner = Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await;
+    refresh(network, owner.into()).await
+}
+
+pub(crate) async fn unwatch(chain_id: u32, contract: Address) -> Result<()> {
+    db()?.unwatch_token(chain_id, contract).await?;
+    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+
+    Ok(())
+}
+
+/// Fetches the balances of every watched token on a network, batched in a single Multicall
+/// where the network has one deployed
+pub(crate) async fn refresh(network: &Network, owner: Address) -> Result<()> {
+    let db = db()?;
+    let tokens = db.get_watched_tokens(network.chain_id).await?;
+    if tokens.is_empty() {
+        return Ok(());
+    }
+
+    let client = Arc::new(network.get_provider()?);
+    let _in_flight = InFlight::start();
+
+    let balances = match Multicall::new(client.clone(), None).await {
+        Ok(mut multicall) => {
+            for token in tokens.iter() {
+                let call = IERC20::new(*token, client.clone()).balance_of(owner);
+                multicall.add_call(call, true);
+            }
+
+            let results = multicall.call_raw().await?;
+            tokens
+                .into_iter()
+                .zip(results)
+                .filter_map(|(token, res)| Some((token, res.ok()?.into_uint()?)))
+                .collect()
+        }
+
+        // no Multicall on this network, such as a fresh devnet
+        Err(_) => {
+            let mut balances = Vec::with_capacity(tokens.len());
+            for token in tokens {
+                let contract = IERC20::new(token, client.clone());
+                if let Ok(balance) = contract.balance_of(owner).call().await {
+                    balances.push((token, balance));
+                }
+            }
+            balances
+        }
+    };
+
+    db.save_erc20_balances(network.chain_id, owner, balances)
+        .await?;
+    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+
+    Ok(())
+}
+
+pub(crate) async fn fetch_metadata(
+    client: Arc<Provider<Failover>>,
+    contract: Address,
+) -> Result<TokenMetadata> {
+    let token = IERC20::new(contract, client.clone());
+    let _in_flight = InFlight::start();
+
+    let (name, symbol, decimals) = match Multicall::new(client, None).await {
+        Ok(mut multicall) => {
+            multicall
+                .add_call(token.name(), false)
+                .add_call(token.symbol(), false)
+                .add_call(token.decimals(), false);
+            multicall.call().await?
+        }
+        Err(_) => (
+            token.name().call().await?,
+            token.symbol().call().await?,
+            token.decimals().call().await?,
+        ),
+    };
+
+    Ok(TokenMetadata {
+        name,
+        symbol,
+        decimals,
+    })
+}
+
+/// Refreshes token balances of the current account whenever the current network moves on to a
+/// new block
+async fn watcher() {
+    let mut last_blocks: HashMap<u32, U64> = HashMap::new();
+    let mut ticker = interval(POLL_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let Ok(network) = Networks::read().await.get_current().cloned() else {
+            continue;
+        };
+        let Ok(provider) = network.get_provider() else {
+            continue;
+        };
+        let Ok(block) = provider.get_block_number().await else {
+            continue;
+        };
+
+        if last_blocks.insert(network.chain_id, block) == Some(block) {
+            continue;
+        }
+
+        let owner = Wallets::read()
+            .await
+            .get_current_wallet()
+            .get_current_address()
+            .await;
+        if let Err(e) = refresh(&network, owner.into()).await {
+            tracing::warn!("Failed to refresh token balances: {}", e);
+        }
+    }
+}
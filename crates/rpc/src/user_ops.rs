@@ -0,0 +1,394 @@
+//! ERC-4337 smart accounts
+//! Each account of the wallet owns a counterfactual SimpleAccount, deployed by its first
+//! UserOperation. Requests sent from it are wrapped into UserOperations, signed by the owner and
+//! sent through the network's bundler
+
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    abi::{encode, Token},
+    providers::{Http, Middleware as _, Provider},
+    signers::Signer as _,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, H160, H256, U256},
+    utils::keccak256,
+};
+use iron_abis::{IEntryPoint, ISimpleAccount, ISimpleAccountFactory};
+use iron_dialogs::{Dialog, DialogMsg};
+use iron_networks::{Fees, Network};
+use iron_types::{RequestSource, SigningKind, TxStatus, TxUpdate};
+use iron_wallets::{Wallet, WalletControl};
+use serde::{Deserialize, Serialize};
+use tokio::time::{interval, timeout};
+
+use super::{audit, history, Error, Result};
+
+/// The v0.6 EntryPoint, deployed at the same address on every chain
+pub const ENTRY_POINT: Address = H160([
+    0x5f, 0xf1, 0x37, 0xd4, 0xb0, 0xfd, 0xcd, 0x49, 0xdc, 0xa3, 0x0c, 0x7c, 0xf5, 0x7e, 0x57, 0x8a,
+    0x02, 0x6d, 0x27, 0x89,
+]);
+
+/// The reference SimpleAccountFactory for the v0.6 EntryPoint
+pub const ACCOUNT_FACTORY: Address = H160([
+    0x94, 0x06, 0xcc, 0x61, 0x85, 0xa3, 0x46, 0x90, 0x62, 0x96, 0x84, 0x07, 0x46, 0x12, 0x5a, 0x0e,
+    0x44, 0x97, 0x64, 0x54,
+]);
+
+/// Only one smart account is derived per owner
+const SALT: u64 = 0;
+
+/// A well-formed signature that recovers to no one, so that bundlers can simulate validation
+/// before the owner signs anything
+const DUMMY_SIGNATURE: &str = "0xfffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c";
+
+/// The smart account of one of the wallet's accounts
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartAccount {
+    pub address: Address,
+    pub owner: Address,
+
+    /// Undeployed accounts get deployed by their first UserOperation
+    pub deployed: bool,
+    pub entry_point: Address,
+}
+
+/// A v0.6 UserOperation, as bundlers expect it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// The hash owners sign, as computed by `EntryPoint.getUserOpHash`
+    /// Everything but the signature is covered, along with the entry point and chain
+    pub fn hash(&self, entry_point: Address, chain_id: u32) -> H256 {
+        let packed = encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+
+        keccak256(encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(chain_id.into()),
+        ]))
+        .into()
+    }
+
+    /// The total gas the operation may use, for showing its worst-case cost
+    fn gas(&self) -> U256 {
+        self.call_gas_limit + self.verification_gas_limit + self.pre_verification_gas
+    }
+}
+
+/// Gas limits suggested by `eth_estimateUserOperationGas`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GasEstimate {
+    pre_verification_gas: U256,
+    verification_gas_limit: U256,
+    call_gas_limit: U256,
+}
+
+/// The outcome of an included UserOperation, from `eth_getUserOperationReceipt`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOpReceipt {
+    success: bool,
+    actual_gas_used: U256,
+    receipt: ethers::types::TransactionReceipt,
+}
+
+/// The smart account owned by an address on a network
+pub(crate) async fn smart_account(network: &Network, owner: Address) -> Result<SmartAccount> {
+    let provider = Arc::new(network.get_provider()?);
+    let factory = ISimpleAccountFactory::new(ACCOUNT_FACTORY, provider.clone());
+
+    let address = factory.get_address(owner, SALT.into()).call().await?;
+    let deployed = !provider.get_code(address, None).await?.is_empty();
+
+    Ok(SmartAccount {
+        address,
+        owner,
+        deployed,
+        entry_point: ENTRY_POINT,
+    })
+}
+
+/// Sends a request from the smart account owned by one of the wallet's accounts
+/// The user reviews the inner call as they would a regular transaction. Returns the UserOperation
+/// hash, under which it's also tracked in the activity history
+pub(crate) async fn send(
+    request: TypedTransaction,
+    network: &Network,
+    wallet: &Wallet,
+    wallet_path: String,
+    source: Option<RequestSource>,
+) -> Result<H256> {
+    let bundler = bundler(network)?;
+    let owner: Address = wallet
+        .get_address_for_path(&wallet_path)
+        .await
+        .ok_or_else(|| Error::InvalidParams(format!("unknown account path {}", wallet_path)))?
+        .into();
+    let account = smart_account(network, owner).await?;
+
+    let mut op = build(&request, network, &account).await?;
+    let estimate: GasEstimate = bundler
+        .request("eth_estimateUserOperationGas", (&op, ENTRY_POINT))
+        .await?;
+    op.call_gas_limit = estimate.call_gas_limit;
+    op.verification_gas_limit = estimate.verification_gas_limit;
+    op.pre_verification_gas = estimate.pre_verification_gas;
+
+    let attempt = audit::Attempt::new(
+        wallet,
+        &wallet_path,
+        network.chain_id,
+        SigningKind::Transaction,
+        &op,
+    )
+    .await;
+    let result = approve_and_sign(&mut op, &request, network, wallet, &wallet_path, source).await;
+    attempt.record(&result).await;
+    result?;
+
+    let hash: H256 = bundler
+        .request("eth_sendUserOperation", (&op, ENTRY_POINT))
+        .await?;
+
+    track(hash, account.address, &request, network, bundler).await;
+
+    Ok(hash)
+}
+
+async fn approve_and_sign(
+    op: &mut UserOperation,
+    request: &TypedTransaction,
+    network: &Network,
+    wallet: &Wallet,
+    wallet_path: &str,
+    source: Option<RequestSource>,
+) -> Result<()> {
+    wallet.ensure_can_sign(wallet_path)?;
+
+    if !(network.is_dev() && wallet.is_dev()) {
+        let mut reviewed = request.clone();
+        reviewed.set_from(op.sender);
+        reviewed.set_gas(op.gas());
+
+        let mut params = serde_json::to_value(&reviewed)?;
+        let (symbol, decimals) = network.native_currency();
+        params["nativeCurrency"] = serde_json::json!({ "symbol": symbol, "decimals": decimals });
+        params["userOperation"] = serde_json::to_value(&*op)?;
+
+        let dialog = Dialog::with_source("tx-review", params, source);
+        dialog.open().await?;
+
+        if !matches!(dialog.recv().await, Some(DialogMsg::Accept(_))) {
+            return Err(Error::TxDialogRejected);
+        }
+    }
+
+    // SimpleAccount expects an EIP-191 signature over the hash
+    let signer = wallet.build_signer(network.chain_id, wallet_path).await?;
+    let signature = signer
+        .sign_message(op.hash(ENTRY_POINT, network.chain_id).as_bytes())
+        .await?;
+    op.signature = signature.to_vec().into();
+
+    Ok(())
+}
+
+/// Wraps a request into an unsigned UserOperation, with fees but no gas limits yet
+async fn build(
+    request: &TypedTransaction,
+    network: &Network,
+    account: &SmartAccount,
+) -> Result<UserOperation> {
+    let to = request
+        .to()
+        .and_then(|to| to.as_address())
+        .ok_or_else(|| Error::InvalidParams("smart accounts can't deploy contracts".into()))?;
+
+    let provider = Arc::new(network.get_provider()?);
+    let call_data = ISimpleAccount::new(account.address, provider.clone())
+        .execute(
+            *to,
+            request.value().copied().unwrap_or_default(),
+            request.data().cloned().unwrap_or_default(),
+        )
+        .calldata()
+        .unwrap_or_default();
+
+    let init_code = match account.deployed {
+        true => Bytes::default(),
+        false => {
+            let create = ISimpleAccountFactory::new(ACCOUNT_FACTORY, provider.clone())
+                .create_account(account.owner, SALT.into())
+                .calldata()
+                .unwrap_or_default();
+            [ACCOUNT_FACTORY.as_bytes(), create.as_ref()]
+                .concat()
+                .into()
+        }
+    };
+
+    let nonce = IEntryPoint::new(ENTRY_POINT, provider)
+        .get_nonce(account.address, U256::zero())
+        .call()
+        .await?;
+
+    let (max_fee_per_gas, max_priority_fee_per_gas) =
+        match network.get_fee_estimates().await?.suggestions().fast {
+            Fees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => (max_fee_per_gas, max_priority_fee_per_gas),
+            Fees::Legacy { gas_price } => (gas_price, gas_price),
+        };
+
+    Ok(UserOperation {
+        sender: account.address,
+        nonce,
+        init_code,
+        call_data,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        signature: Bytes::from_str(DUMMY_SIGNATURE).unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+/// Whether smart accounts can be used on a network at all
+pub(crate) fn has_bundler(network: &Network) -> bool {
+    bundler_url(network).is_some()
+}
+
+fn bundler_url(network: &Network) -> Option<&str> {
+    network
+        .bundler_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+}
+
+fn bundler(network: &Network) -> Result<Provider<Http>> {
+    let url = bundler_url(network).ok_or_else(|| Error::NoBundler(network.name.clone()))?;
+
+    Provider::<Http>::try_from(url).map_err(|e| Error::Bundler(e.to_string()))
+}
+
+/// Records a sent UserOperation as pending, and polls the bundler until it's included
+async fn track(
+    hash: H256,
+    sender: Address,
+    request: &TypedTransaction,
+    network: &Network,
+    bundler: Provider<Http>,
+) {
+    if history::record_pending(hash, sender, request, network, None)
+        .await
+        .is_none()
+    {
+        return;
+    }
+
+    let chain_id = network.chain_id;
+    tokio::spawn(async move {
+        let wait = async {
+            let mut ticker = interval(history::POLL_INTERVAL);
+            loop {
+                ticker.tick().await;
+
+                // bundler errors are treated as transient, and retried on the next poll
+                let receipt = bundler
+                    .request::<_, Option<UserOpReceipt>>("eth_getUserOperationReceipt", [hash])
+                    .await;
+                if let Ok(Some(receipt)) = receipt {
+                    return receipt;
+                }
+            }
+        };
+
+        // unlike transactions, UserOperations can't be replaced from here. one that is never
+        // included is eventually dropped by the bundler
+        let update = match timeout(history::MAX_WATCH, wait).await {
+            Ok(receipt) => TxUpdate {
+                hash,
+                chain_id,
+                status: match receipt.success {
+                    true => TxStatus::Confirmed,
+                    false => TxStatus::Failed,
+                },
+                block_number: receipt.receipt.block_number.map(|n| n.as_u64()),
+                gas_used: Some(receipt.actual_gas_used),
+            },
+            Err(_) => history::unmined(hash, chain_id, TxStatus::Dropped),
+        };
+
+        history::settle(&update).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_op_hash_covers_everything_but_the_signature() {
+        let op = UserOperation {
+            sender: Address::from_low_u64_be(1),
+            nonce: 3.into(),
+            call_data: vec![1, 2, 3].into(),
+            ..Default::default()
+        };
+        let hash = op.hash(ENTRY_POINT, 1);
+
+        let signed = UserOperation {
+            signature: Bytes::from_str(DUMMY_SIGNATURE).unwrap(),
+            ..op.clone()
+        };
+        assert_eq!(signed.hash(ENTRY_POINT, 1), hash);
+
+        let bumped = UserOperation {
+            nonce: 4.into(),
+            ..op.clone()
+        };
+        assert_ne!(bumped.hash(ENTRY_POINT, 1), hash);
+        assert_ne!(op.hash(ENTRY_POINT, 5), hash);
+    }
+
+    #[test]
+    fn user_ops_serialize_as_bundlers_expect() {
+        let op = UserOperation {
+            nonce: 1.into(),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(op).unwrap();
+
+        assert_eq!(json["nonce"], "0x1");
+        assert_eq!(json["initCode"], "0x");
+        assert!(json.get("paymasterAndData").is_some());
+    }
+}
@@ -0,0 +1,110 @@
+use ethers::{
+    abi::{self, ParamType},
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+use serde::Serialize;
+
+/// ERC-20 `approve(address,uint256)` selector
+const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+/// Approvals of at least 2^128 are treated as unlimited
+/// No sensible allowance gets anywhere close to this, while "infinite" approvals usually use
+/// `type(uint256).max` or `type(uint160).max`
+const UNLIMITED_APPROVAL_THRESHOLD: u32 = 128;
+
+/// A risk spotted in a transaction request, for the review dialog to highlight
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Warning {
+    /// An ERC-20 approval for an effectively unlimited amount
+    #[serde(rename_all = "camelCase")]
+    UnlimitedApproval {
+        token: Option<Address>,
+        spender: Address,
+    },
+
+    /// The site asking for the transaction is on a phishing blocklist
+    PhishingOrigin { origin: String },
+
+    /// The recipient is on a blocklist of malicious addresses
+    MaliciousAddress { address: Address },
+
+    /// The account's first interaction with a contract whose source isn't verified
+    UnverifiedContract { address: Address },
+}
+
+/// Inspects a transaction request's calldata for known risky patterns
+pub(crate) fn detect(request: &TypedTransaction) -> Vec<Warning> {
+    let mut warnings = vec![];
+
+    if let Some(data) = request.data() {
+        if let Some(spender) = unlimited_approval_spender(data) {
+            warnings.push(Warning::UnlimitedApproval {
+                token: request.to().and_then(|to| to.as_address()).copied(),
+                spender,
+            });
+        }
+    }
+
+    warnings
+}
+
+fn unlimited_approval_spender(data: &[u8]) -> Option<Address> {
+    if data.len() < 4 || data[..4] != APPROVE_SELECTOR {
+        return None;
+    }
+
+    let mut tokens = abi::decode(&[ParamType::Address, ParamType::Uint(256)], &data[4..]).ok()?;
+    let amount = tokens.pop()?.into_uint()?;
+    let spender = tokens.pop()?.into_address()?;
+
+    if amount >= U256::one() << UNLIMITED_APPROVAL_THRESHOLD {
+        Some(spender)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{abi::Token, types::Bytes};
+
+    use super::*;
+
+    fn approve(spender: Address, amount: U256) -> TypedTransaction {
+        let mut data = APPROVE_SELECTOR.to_vec();
+        data.extend(abi::encode(&[Token::Address(spender), Token::Uint(amount)]));
+
+        let mut request = TypedTransaction::default();
+        request.set_to(Address::from_low_u64_be(1));
+        request.set_data(Bytes::from(data));
+        request
+    }
+
+    #[test]
+    fn flags_unlimited_approvals() {
+        let spender = Address::from_low_u64_be(2);
+
+        for amount in [U256::MAX, (U256::one() << 160) - U256::one()] {
+            assert_eq!(
+                detect(&approve(spender, amount)),
+                vec![Warning::UnlimitedApproval {
+                    token: Some(Address::from_low_u64_be(1)),
+                    spender
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_bounded_approvals_and_other_calls() {
+        let spender = Address::from_low_u64_be(2);
+        assert!(detect(&approve(spender, U256::exp10(24))).is_empty());
+
+        let mut transfer = approve(spender, U256::MAX);
+        transfer.set_data(Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]));
+        assert!(detect(&transfer).is_empty());
+
+        assert!(detect(&TypedTransaction::default()).is_empty());
+    }
+}
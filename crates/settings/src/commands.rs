@@ -1,6 +1,6 @@
 use iron_types::{ChecksummedAddress, GlobalState};
 
-use super::{DarkMode, Result, SerializedSettings, Settings};
+use super::{Contact, Contacts, DarkMode, Result, SerializedSettings, Settings};
 
 #[tauri::command]
 pub async fn settings_get() -> SerializedSettings {
@@ -30,6 +30,41 @@ pub async fn settings_get_alias(address: ChecksummedAddress) -> Option<String> {
 
 /// Sets the alias for an address
 #[tauri::command]
-pub async fn settings_set_alias(address: ChecksummedAddress, alias: Option<String>) {
+pub async fn settings_set_alias(address: ChecksummedAddress, alias: Option<String>) -> Result<()> {
     Settings::write().await.set_alias(address, alias)
 }
+
+/// Lists the address book, including contacts read from forge deployments
+#[tauri::command]
+pub async fn contacts_get_all() -> Vec<Contact> {
+    Contacts::read().await.get_all().to_vec()
+}
+
+#[tauri::command]
+pub async fn contacts_add(contact: Contact) -> Result<()> {
+    Contacts::write().await.add(contact)
+}
+
+/// Replaces the contact of an address and network, which may themselves change
+#[tauri::command]
+pub async fn contacts_update(
+    address: ChecksummedAddress,
+    chain_id: Option<u32>,
+    contact: Contact,
+) -> Result<()> {
+    Contacts::write().await.update(address, chain_id, contact)
+}
+
+#[tauri::command]
+pub async fn contacts_remove(address: ChecksummedAddress, chain_id: Option<u32>) -> Result<()> {
+    Contacts::write().await.remove(address, chain_id)
+}
+
+/// Gets the contact name of an address on a network, if it has one
+#[tauri::command]
+pub async fn contacts_resolve(
+    address: ChecksummedAddress,
+    chain_id: Option<u32>,
+) -> Option<String> {
+    Contacts::read().await.resolve(address, chain_id)
+}
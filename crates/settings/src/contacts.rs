@@ -0,0 +1,247 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use iron_types::{ChecksummedAddress, GlobalState, UINotify};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::{Error, Result};
+
+static CONTACTS: OnceCell<RwLock<Contacts>> = OnceCell::new();
+
+/// A named address, either added by the user or read from a forge deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contact {
+    pub name: String,
+    pub address: ChecksummedAddress,
+
+    /// Restricts the contact to a single network. Contacts without one apply to all of them
+    #[serde(default)]
+    pub chain_id: Option<u32>,
+
+    #[serde(default)]
+    pub notes: Option<String>,
+
+    /// Whether this contact was created from a forge deployment
+    /// These are replaced whenever deployments are reindexed, unless the user edits them
+    #[serde(default)]
+    pub deployment: bool,
+}
+
+/// The address book
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Contacts {
+    #[serde(skip)]
+    file: PathBuf,
+
+    contacts: Vec<Contact>,
+}
+
+pub async fn init_contacts(pathbuf: PathBuf) {
+    let path = Path::new(&pathbuf);
+
+    let mut res: Contacts = if path.exists() {
+        let file = File::open(path).unwrap();
+        let reader = BufReader::new(file);
+
+        serde_json::from_reader(reader).unwrap()
+    } else {
+        Contacts::default()
+    };
+    res.file = pathbuf;
+
+    CONTACTS.set(RwLock::new(res)).unwrap();
+}
+
+impl Contacts {
+    pub fn get_all(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// The name of an address, preferring contacts scoped to the given network over global ones,
+    /// and then the user's own over deployments
+    pub fn resolve(&self, address: ChecksummedAddress, chain_id: Option<u32>) -> Option<String> {
+        self.contacts
+            .iter()
+            .filter(|c| c.address == address)
+            .filter(|c| c.chain_id.is_none() || c.chain_id == chain_id)
+            .min_by_key(|c| (c.chain_id.is_none(), c.deployment))
+            .map(|c| c.name.clone())
+    }
+
+    pub fn add(&mut self, contact: Contact) -> Result<()> {
+        let contact = Self::validate(contact)?;
+        if self.position(contact.address, contact.chain_id).is_some() {
+            return Err(Error::DuplicateContact(contact.address));
+        }
+
+        self.contacts.push(contact);
+        self.save()
+    }
+
+    /// Replaces the contact of an address and network
+    /// Editing a deployment turns it into a contact of the user's own
+    pub fn update(
+        &mut self,
+        address: ChecksummedAddress,
+        chain_id: Option<u32>,
+        contact: Contact,
+    ) -> Result<()> {
+        let contact = Self::validate(contact)?;
+        let index = self
+            .position(address, chain_id)
+            .ok_or(Error::ContactNotFound(address))?;
+
+        if (contact.address, contact.chain_id) != (address, chain_id)
+            && self.position(contact.address, contact.chain_id).is_some()
+        {
+            return Err(Error::DuplicateContact(contact.address));
+        }
+
+        self.contacts[index] = contact;
+        self.save()
+    }
+
+    pub fn remove(&mut self, address: ChecksummedAddress, chain_id: Option<u32>) -> Result<()> {
+        let index = self
+            .position(address, chain_id)
+            .ok_or(Error::ContactNotFound(address))?;
+
+        self.contacts.remove(index);
+        self.save()
+    }
+
+    /// Imports contacts from a backup
+    /// Unless `overwrite` is set, they're merged by address and network into the current list.
+    /// Deployments are left alone either way, since they're read from forge again
+    pub fn import(&mut self, contacts: Vec<Contact>, overwrite: bool) -> Result<()> {
+        if overwrite {
+            self.contacts.retain(|c| c.deployment);
+        }
+
+        for contact in contacts.into_iter().filter(|c| !c.deployment) {
+            let contact = Self::validate(contact)?;
+            match self.position(contact.address, contact.chain_id) {
+                Some(i) => self.contacts[i] = contact,
+                None => self.contacts.push(contact),
+            }
+        }
+
+        self.save()
+    }
+
+    /// Replaces every contact read from forge deployments, given as `(chain_id, address, name)`
+    /// Addresses the user already named on that network are skipped
+    pub fn set_deployments(
+        &mut self,
+        deployments: Vec<(u32, ChecksummedAddress, String)>,
+    ) -> Result<()> {
+        self.contacts.retain(|c| !c.deployment);
+
+        for (chain_id, address, name) in deployments {
+            if self.position(address, Some(chain_id)).is_some() {
+                continue;
+            }
+
+            self.contacts.push(Contact {
+                name,
+                address,
+                chain_id: Some(chain_id),
+                notes: None,
+                deployment: true,
+            });
+        }
+
+        self.save()
+    }
+
+    fn position(&self, address: ChecksummedAddress, chain_id: Option<u32>) -> Option<usize> {
+        self.contacts
+            .iter()
+            .position(|c| c.address == address && c.chain_id == chain_id)
+    }
+
+    fn validate(mut contact: Contact) -> Result<Contact> {
+        contact.name = contact.name.trim().to_owned();
+        if contact.name.is_empty() {
+            return Err(Error::EmptyContactName);
+        }
+        contact.notes = contact
+            .notes
+            .map(|n| n.trim().to_owned())
+            .filter(|n| !n.is_empty());
+        contact.deployment = false;
+
+        Ok(contact)
+    }
+
+    // Persists current state to disk, and lets the UI know
+    fn save(&self) -> Result<()> {
+        iron_types::save_json(&self.file, self)?;
+
+        tokio::spawn(async { iron_broadcast::ui_notify(UINotify::ContactsUpdated).await });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GlobalState for Contacts {
+    async fn read<'a>() -> RwLockReadGuard<'a, Self> {
+        CONTACTS.get().unwrap().read().await
+    }
+
+    async fn write<'a>() -> RwLockWriteGuard<'a, Self> {
+        CONTACTS.get().unwrap().write().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::Address;
+
+    use super::*;
+
+    fn contact(name: &str, chain_id: Option<u32>) -> Contact {
+        Contact {
+            name: name.into(),
+            address: Address::from_low_u64_be(1).into(),
+            chain_id,
+            notes: None,
+            deployment: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_the_most_specific_contact() {
+        let mut contacts = Contacts {
+            file: std::env::temp_dir().join("iron-contacts-test.json"),
+            ..Default::default()
+        };
+        let address = Address::from_low_u64_be(1).into();
+
+        contacts.add(contact("everywhere", None)).unwrap();
+        contacts
+            .set_deployments(vec![(31337, address, "Counter".into())])
+            .unwrap();
+        assert_eq!(contacts.resolve(address, Some(1)).unwrap(), "everywhere");
+        assert_eq!(contacts.resolve(address, Some(31337)).unwrap(), "Counter");
+
+        contacts.add(contact("local", Some(31337))).unwrap_err();
+        contacts
+            .update(address, Some(31337), contact("local", Some(31337)))
+            .unwrap();
+        assert_eq!(contacts.resolve(address, Some(31337)).unwrap(), "local");
+
+        // the user's own edit survives reindexing
+        contacts.set_deployments(vec![]).unwrap();
+        assert_eq!(contacts.resolve(address, Some(31337)).unwrap(), "local");
+        assert!(contacts.add(contact(" ", Some(5))).is_err());
+    }
+}
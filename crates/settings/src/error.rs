@@ -1,3 +1,5 @@
+use iron_types::ChecksummedAddress;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("IO error: {0}")]
@@ -8,6 +10,15 @@ pub enum Error {
 
     #[error("Etherscan API key not set")]
     EtherscanKeyNotSet,
+
+    #[error("a contact for {0:?} already exists on this network")]
+    DuplicateContact(ChecksummedAddress),
+
+    #[error("no contact for {0:?}")]
+    ContactNotFound(ChecksummedAddress),
+
+    #[error("contact name can't be empty")]
+    EmptyContactName,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
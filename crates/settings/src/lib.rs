@@ -1,17 +1,18 @@
 pub mod commands;
+mod contacts;
 mod error;
 mod init;
 
 use std::{
     collections::HashMap,
-    fs::File,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
+pub use contacts::{init_contacts, Contact, Contacts};
 use ethers::core::types::Address;
 pub use init::init;
-use iron_types::ChecksummedAddress;
+use iron_types::{ChecksummedAddress, FeeSpeed, UINotify};
 use serde::{Deserialize, Serialize};
 
 pub use self::error::{Error, Result};
@@ -31,6 +32,17 @@ pub enum DarkMode {
     Light,
 }
 
+/// Where fiat prices are fetched from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    #[default]
+    Coingecko,
+
+    /// Only covers the pairs with an on-chain feed on the network itself
+    Chainlink,
+}
+
 impl Settings {
     /// Changes the currently connected wallet
     ///
@@ -42,6 +54,36 @@ impl Settings {
         Ok(())
     }
 
+    /// Imports settings from a backup
+    ///
+    /// Unless `overwrite` is set, aliases are merged by address, and API keys missing from the
+    /// backup are kept
+    pub fn import(&mut self, imported: SerializedSettings, overwrite: bool) -> Result<()> {
+        if overwrite {
+            self.inner = imported;
+        } else {
+            let mut aliases = self.inner.aliases.clone();
+            aliases.extend(imported.aliases);
+
+            self.inner = SerializedSettings {
+                aliases,
+                alchemy_api_key: imported
+                    .alchemy_api_key
+                    .or(self.inner.alchemy_api_key.take()),
+                etherscan_api_key: imported
+                    .etherscan_api_key
+                    .or(self.inner.etherscan_api_key.take()),
+                walletconnect_project_id: imported
+                    .walletconnect_project_id
+                    .or(self.inner.walletconnect_project_id.take()),
+                onboarded: self.inner.onboarded || imported.onboarded,
+                ..imported
+            };
+        }
+
+        self.save()
+    }
+
     pub fn set_dark_mode(&mut self, mode: DarkMode) -> Result<()> {
         self.inner.dark_mode = mode;
         self.save()?;
@@ -71,7 +113,7 @@ impl Settings {
         self.inner.aliases.get(&address).cloned()
     }
 
-    fn set_alias(&mut self, address: ChecksummedAddress, alias: Option<String>) {
+    fn set_alias(&mut self, address: ChecksummedAddress, alias: Option<String>) -> Result<()> {
         // trim whitespaces
         // empty str becomes None
         let alias = alias.map(|v| v.trim().to_owned()).filter(|v| !v.is_empty());
@@ -81,16 +123,16 @@ impl Settings {
         } else {
             self.inner.aliases.remove(&address);
         }
+
+        self.save()
     }
 
-    // Persists current state to disk
+    // Persists current state to disk, and lets the UI know
     fn save(&self) -> Result<()> {
         let pathbuf = self.file.clone();
-        let path = Path::new(&pathbuf);
-        let file = File::create(path)?;
-
-        serde_json::to_writer_pretty(file, &self.inner)?;
+        iron_types::save_json(Path::new(&pathbuf), &self.inner)?;
 
+        tokio::spawn(async { iron_broadcast::ui_notify(UINotify::SettingsUpdated).await });
         Ok(())
     }
 }
@@ -101,12 +143,45 @@ pub struct SerializedSettings {
     pub dark_mode: DarkMode,
 
     pub abi_watch: bool,
+
+    /// The foundry project whose ABIs are watched
     pub abi_watch_path: Option<String>,
     pub alchemy_api_key: Option<String>,
     pub etherscan_api_key: Option<String>,
+
+    /// Identifies the app to the WalletConnect relay
+    pub walletconnect_project_id: Option<String>,
+
+    pub price_source: PriceSource,
+
+    /// The fiat currency prices are shown in, as a lowercase ISO 4217 code
+    #[serde(default = "default_fiat_currency")]
+    pub fiat_currency: String,
+
     #[serde(default = "default_true")]
     pub hide_empty_tokens: bool,
 
+    /// The network selected on startup, instead of the one last used
+    pub default_network: Option<String>,
+
+    /// Minutes of inactivity after which wallets are locked. Never, if unset
+    pub auto_lock_minutes: Option<u32>,
+
+    /// The speed new transactions are priced at, unless the dapp sets its own fees
+    pub gas_preset: FeeSpeed,
+
+    /// Lists of phishing domains and malicious addresses, refreshed periodically
+    /// Either JSON arrays, JSON objects holding arrays, or one entry per line
+    #[serde(default = "default_blocklist_urls")]
+    pub blocklist_urls: Vec<String>,
+
+    /// Domains and addresses the user blocked themselves, on top of the lists
+    pub custom_blocklist: Vec<String>,
+
+    /// Whether transactions under review show every balance, nonce and storage slot they'd change
+    /// Needs a node that traces calls
+    pub state_diff_preview: bool,
+
     #[serde(default = "default_aliases")]
     aliases: HashMap<ChecksummedAddress, String>,
 
@@ -122,13 +197,37 @@ impl Default for SerializedSettings {
             abi_watch_path: None,
             alchemy_api_key: None,
             etherscan_api_key: None,
+            walletconnect_project_id: None,
+            price_source: PriceSource::default(),
+            fiat_currency: default_fiat_currency(),
             hide_empty_tokens: true,
+            default_network: None,
+            auto_lock_minutes: None,
+            gas_preset: FeeSpeed::default(),
+            blocklist_urls: default_blocklist_urls(),
+            custom_blocklist: vec![],
+            state_diff_preview: false,
             aliases: HashMap::new(),
             onboarded: false,
         }
     }
 }
 
+fn default_fiat_currency() -> String {
+    String::from("usd")
+}
+
+fn default_blocklist_urls() -> Vec<String> {
+    vec![
+        "https://raw.githubusercontent.com/MetaMask/eth-phishing-detect/main/src/config.json"
+            .into(),
+        "https://raw.githubusercontent.com/scamsniffer/scam-database/main/blacklist/domains.json"
+            .into(),
+        "https://raw.githubusercontent.com/scamsniffer/scam-database/main/blacklist/address.json"
+            .into(),
+    ]
+}
+
 const fn default_true() -> bool {
     true
 }
@@ -156,3 +255,26 @@ fn default_aliases() -> HashMap<ChecksummedAddress, String> {
 
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_get_defaults() {
+        // as written before most settings existed
+        let settings: SerializedSettings =
+            serde_json::from_str(r#"{"darkMode":"dark","abiWatch":true}"#).unwrap();
+
+        assert!(matches!(settings.dark_mode, DarkMode::Dark));
+        assert!(settings.abi_watch);
+        assert_eq!(settings.fiat_currency, "usd");
+        assert!(settings.hide_empty_tokens);
+        assert_eq!(settings.default_network, None);
+        assert_eq!(settings.auto_lock_minutes, None);
+        assert_eq!(settings.gas_preset, FeeSpeed::Normal);
+        assert_eq!(settings.blocklist_urls, default_blocklist_urls());
+        assert!(!settings.state_diff_preview);
+        assert!(!settings.onboarded);
+    }
+}
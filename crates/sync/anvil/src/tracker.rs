@@ -8,7 +8,7 @@ use ethers::{
     types::{Address, Filter, Log, Trace, U64},
 };
 use futures_util::StreamExt;
-use iron_abis::IERC20;
+use iron_abis::{IERC20, IERC721};
 use iron_db::DB;
 use iron_types::{TokenMetadata, UINotify};
 use tokio::sync::mpsc;
@@ -229,6 +229,18 @@ async fn process(ctx: Ctx, mut block_rcv: mpsc::UnboundedReceiver<Msg>) -> Resul
                 .unwrap();
         }
 
+        for (contract, token_id) in ctx.db.get_nfts_missing_uri(ctx.chain_id).await? {
+            let uri = IERC721::new(contract, Arc::new(provider.clone()))
+                .token_uri(token_id)
+                .call()
+                .await
+                .unwrap_or_default();
+
+            ctx.db
+                .set_nft_token_uri(ctx.chain_id, contract, token_id, &uri)
+                .await?;
+        }
+
         // don't emit events until we're catching up
         // otherwise we spam too much during that phase
         if caught_up {
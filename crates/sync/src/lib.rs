@@ -3,10 +3,12 @@ use std::{
     sync::Arc,
 };
 
+use ethers::types::Address;
 use iron_broadcast::InternalMsg;
 use iron_db::DB;
+use iron_networks::Networks;
 use iron_sync_alchemy::Alchemy;
-use iron_types::{ChecksummedAddress, GlobalState, UINotify};
+use iron_types::{ChecksummedAddress, Event, GlobalState, SyncUpdates, UINotify};
 use tokio::{
     select,
     sync::{mpsc, Mutex},
@@ -97,10 +99,10 @@ impl Worker {
                 match msg {
                     TrackAddress(addr) => worker.track_addr(addr),
                     UntrackAddress(addr) => worker.untrack_addr(addr),
-                    TrackNetwork(chain_id) => worker.track_network(chain_id),
+                    TrackNetwork(chain_id) => worker.track_network(chain_id).await,
                     UntrackNetwork(chain_id) => worker.untrack_network(chain_id),
                     PollAddress(addr) => worker.prioritize_addr(addr),
-                    PollNetwork(chain_id) => worker.prioritize_network(chain_id),
+                    PollNetwork(chain_id) => worker.prioritize_network(chain_id).await,
                 };
             }
         }
@@ -113,7 +115,9 @@ impl Worker {
         for chain_id in self.chain_ids.iter() {
             let chain_id = *chain_id;
             let task = self.spawn(addr, chain_id);
-            self.workers.insert((addr, chain_id), task);
+            if let Some((old, _)) = self.workers.insert((addr, chain_id), task) {
+                old.abort();
+            }
         }
     }
 
@@ -121,18 +125,20 @@ impl Worker {
     #[instrument(skip(self), level = "trace")]
     fn untrack_addr(&mut self, addr: ChecksummedAddress) {
         self.addresses.remove(&addr);
-        self.workers.retain(|(a, _), _| a != &addr);
+        self.stop_workers(|(a, _)| a == &addr);
     }
 
     /// creates a new worker per addr for this chain_id
     #[instrument(skip(self), level = "trace")]
-    fn track_network(&mut self, chain_id: u32) {
-        if iron_sync_alchemy::supports_network(chain_id) {
+    async fn track_network(&mut self, chain_id: u32) {
+        if supports_network(chain_id).await {
             self.chain_ids.insert(chain_id);
             for addr in self.addresses.iter() {
                 let addr = *addr;
                 let task = self.spawn(addr, chain_id);
-                self.workers.insert((addr, chain_id), task);
+                if let Some((old, _)) = self.workers.insert((addr, chain_id), task) {
+                    old.abort();
+                }
             }
         }
     }
@@ -141,7 +147,20 @@ impl Worker {
     #[instrument(skip(self), level = "trace")]
     fn untrack_network(&mut self, chain_id: u32) {
         self.chain_ids.remove(&chain_id);
-        self.workers.retain(|(_, c), _| c != &chain_id);
+        self.stop_workers(|(_, c)| c == &chain_id);
+    }
+
+    /// aborts and removes every worker whose (addr, chain_id) pair matches
+    /// dropping a `JoinHandle` alone would leave the task running in the background
+    fn stop_workers(&mut self, matches: impl Fn(&(ChecksummedAddress, u32)) -> bool) {
+        self.workers.retain(|key, (handle, _)| {
+            if matches(key) {
+                handle.abort();
+                false
+            } else {
+                true
+            }
+        });
     }
 
     /// replaces worker for this addr & current chain_id with a priority one
@@ -158,8 +177,8 @@ impl Worker {
 
     /// replaces worker for this chain_id & current addr with a priority one
     #[instrument(skip(self), level = "trace")]
-    fn prioritize_network(&mut self, chain_id: u32) {
-        if iron_sync_alchemy::supports_network(chain_id) {
+    async fn prioritize_network(&mut self, chain_id: u32) {
+        if supports_network(chain_id).await {
             self.current.1 = Some(chain_id);
 
             if let (Some(address), Some(chain_id)) = self.current {
@@ -198,57 +217,71 @@ async fn unit_worker(
     db: DB,
     mut rx: mpsc::UnboundedReceiver<()>,
 ) {
-    let tip = db.get_tip(chain_id, addr.into()).await.ok();
+    let mut tip = db.get_tip(chain_id, addr.into()).await.ok();
     let delay = 60;
 
     loop {
         tracing::trace!("waiting again");
 
-        // The alchemy global object already acts as a mutex,
-        // but that's an implementation detail that may change, so we track our own mutex to ensure
-        // only 1 worker at a time
-        let _guard = mutex.lock().await;
-        tracing::trace!(event = "working");
-
-        // the alchemy global object acts as a mutex already
-        let alchemy = Alchemy::read().await;
-
-        match alchemy.fetch_updates(chain_id, addr.into(), tip).await {
-            Ok(result) => {
-                if let Some(events) = result.events {
-                    let res = db.save_events(chain_id, events).await;
-                    log_if_error("save_events", res);
-
-                    // TODO: this event should specify address and chain_id
-                    iron_broadcast::ui_notify(UINotify::TxsUpdated).await;
-                }
-
-                if let Some(tip) = result.tip {
-                    let res = db.set_tip(chain_id, addr.into(), tip).await;
-                    log_if_error("set_tip", res);
-                }
-
-                if let Some(balances) = result.erc20_balances {
-                    let res = db
-                        .save_erc20_balances(chain_id, addr.into(), balances)
-                        .await;
-                    log_if_error("erc20_balances", res);
-
-                    // TODO: this event should specify address and chain_id
-                    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+        // both guards are scoped to this block, so that other workers can proceed while this one
+        // is waiting for its next turn
+        {
+            // The alchemy global object already acts as a mutex,
+            // but that's an implementation detail that may change, so we track our own mutex to
+            // ensure only 1 worker at a time
+            let _guard = mutex.lock().await;
+            tracing::trace!(event = "working");
+
+            let updates = match iron_sync_alchemy::supports_network(chain_id) {
+                true => Alchemy::read()
+                    .await
+                    .fetch_updates(chain_id, addr.into(), tip)
+                    .await
+                    .map_err(|e| e.to_string()),
+                false => fetch_explorer_updates(chain_id, addr.into(), tip)
+                    .await
+                    .map_err(|e| e.to_string()),
+            };
+
+            match updates {
+                Ok(result) => {
+                    if let Some(events) = result.events {
+                        let res = db.save_events(chain_id, events).await;
+                        log_if_error("save_events", res);
+
+                        // TODO: this event should specify address and chain_id
+                        iron_broadcast::ui_notify(UINotify::TxsUpdated).await;
+                    }
+
+                    // subsequent polls only need to look past the new tip
+                    if let Some(new_tip) = result.tip {
+                        let res = db.set_tip(chain_id, addr.into(), new_tip).await;
+                        log_if_error("set_tip", res);
+                        tip = Some(new_tip);
+                    }
+
+                    if let Some(balances) = result.erc20_balances {
+                        let res = db
+                            .save_erc20_balances(chain_id, addr.into(), balances)
+                            .await;
+                        log_if_error("erc20_balances", res);
+
+                        // TODO: this event should specify address and chain_id
+                        iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+                    }
+
+                    if let Some(balance) = result.native_balance {
+                        let res = db.save_native_balance(balance, chain_id, addr.into()).await;
+                        log_if_error("native_balances", res);
+
+                        // TODO: this event should specify address and chain_id
+                        iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+                    }
                 }
-
-                if let Some(balance) = result.native_balance {
-                    let res = db.save_native_balance(balance, chain_id, addr.into()).await;
-                    log_if_error("native_balances", res);
-
-                    // TODO: this event should specify address and chain_id
-                    iron_broadcast::ui_notify(UINotify::BalancesUpdated).await;
+                Err(err) => {
+                    error!(call = "txs", err = err.as_str());
                 }
             }
-            Err(err) => {
-                error!(call = "txs", err = err.to_string());
-            }
         }
 
         // wait for either a set delay, or for an outside poll request
@@ -259,6 +292,47 @@ async fn unit_worker(
     }
 }
 
+/// Whether history on a network can be synced, either through Alchemy or the network's explorer
+/// Dev networks are left to their own tracker
+async fn supports_network(chain_id: u32) -> bool {
+    if iron_sync_alchemy::supports_network(chain_id) {
+        return true;
+    }
+
+    match Networks::read().await.get_network(chain_id) {
+        Some(network) => !network.is_dev() && network.explorer().await.is_ok(),
+        None => false,
+    }
+}
+
+/// Fetches new transactions of an address from its network's explorer, for networks Alchemy
+/// doesn't cover
+/// The tip block is fetched again, in case the previous page ended halfway through it
+async fn fetch_explorer_updates(
+    chain_id: u32,
+    addr: Address,
+    tip: Option<u64>,
+) -> iron_networks::Result<SyncUpdates> {
+    let network = Networks::read()
+        .await
+        .get_network(chain_id)
+        .ok_or(iron_networks::Error::InvalidChainId(chain_id))?;
+
+    let txs = network
+        .explorer()
+        .await?
+        .transactions(addr, tip.unwrap_or(0))
+        .await?;
+    let tip = txs.iter().map(|tx| tx.block_number).max().or(tip);
+
+    Ok(SyncUpdates {
+        events: Some(txs.into_iter().map(Event::Tx).collect()),
+        erc20_balances: None,
+        native_balance: None,
+        tip,
+    })
+}
+
 fn log_if_error<T, E>(call: &str, err: Result<T, E>)
 where
     E: std::error::Error + std::fmt::Display,
@@ -1,14 +1,37 @@
 mod error;
+mod recent;
 
 pub use error::{TracingError, TracingResult};
-use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+pub use recent::recent_logs;
+use tracing::Level;
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{self, format::FmtSpan},
+    prelude::*,
+    EnvFilter,
+};
 
+/// Logs to stderr as filtered by `RUST_LOG`, while keeping recent lines in memory for bug reports
+/// The in-memory copy keeps the app's own debug logs regardless of `RUST_LOG`, but only warnings
+/// from dependencies, which would otherwise crowd it out
 pub fn init() -> TracingResult<()> {
-    let subscriber = tracing_subscriber::FmtSubscriber::builder()
-        .with_env_filter(EnvFilter::from_default_env())
+    let stderr = fmt::layer()
         .with_span_events(FmtSpan::NEW)
         .compact()
-        .finish();
+        .with_filter(EnvFilter::from_default_env());
+
+    let recent = fmt::layer()
+        .with_span_events(FmtSpan::NEW)
+        .with_ansi(false)
+        .compact()
+        .with_writer(recent::RecentLogs)
+        .with_filter(
+            Targets::new()
+                .with_target("iron", Level::DEBUG)
+                .with_default(Level::WARN),
+        );
+
+    let subscriber = tracing_subscriber::registry().with(stderr).with(recent);
     tracing::subscriber::set_global_default(subscriber)?;
 
     Ok(())
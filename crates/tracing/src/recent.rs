@@ -0,0 +1,87 @@
+//! Keeps the most recent log lines in memory, so they can be attached to bug reports
+
+use std::{collections::VecDeque, io, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// How many lines are kept. Older ones are dropped as new ones come in
+const CAPACITY: usize = 5_000;
+
+static LINES: Lazy<Mutex<VecDeque<String>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(CAPACITY)));
+
+/// The most recent log lines, oldest first
+pub fn recent_logs() -> String {
+    let lines = LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut logs = String::new();
+    for line in lines.iter() {
+        logs.push_str(line);
+        logs.push('\n');
+    }
+
+    logs
+}
+
+fn push(line: String) {
+    let mut lines = LINES.lock().unwrap_or_else(|e| e.into_inner());
+    if lines.len() == CAPACITY {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// Hands out a fresh writer for each event, which the formatter writes a whole line to
+pub(crate) struct RecentLogs;
+
+impl<'a> MakeWriter<'a> for RecentLogs {
+    type Writer = LineWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LineWriter(Vec::new())
+    }
+}
+
+/// Buffers an event's formatted line, and stores it once dropped
+pub(crate) struct LineWriter(Vec<u8>);
+
+impl io::Write for LineWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for LineWriter {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.0);
+        let line = line.trim_end();
+        if !line.is_empty() {
+            push(line.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_latest_lines() {
+        for i in 0..CAPACITY + 2 {
+            let mut writer = RecentLogs.make_writer();
+            writeln!(writer, "line {}", i).unwrap();
+        }
+
+        let logs = recent_logs();
+        assert_eq!(logs.lines().count(), CAPACITY);
+        assert_eq!(logs.lines().next(), Some("line 2"));
+        assert!(logs.ends_with(&format!("line {}\n", CAPACITY + 1)));
+    }
+}
@@ -0,0 +1,98 @@
+use std::str::FromStr;
+
+use ethers::types::{Address, H256};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row};
+
+/// What was requested to be signed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SigningKind {
+    Message,
+    TypedData,
+    Transaction,
+}
+
+/// How a signing request ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SigningOutcome {
+    Signed,
+    Rejected,
+    Failed,
+}
+
+/// A record of a signing request, kept for auditing purposes
+/// Only a hash of the payload is stored, never the resulting signature
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// unix timestamp, in seconds
+    pub timestamp: u64,
+    pub address: Address,
+    pub chain_id: u32,
+    pub kind: SigningKind,
+    pub payload_hash: H256,
+    pub outcome: SigningOutcome,
+}
+
+impl SigningKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Message => "message",
+            Self::TypedData => "typedData",
+            Self::Transaction => "transaction",
+        }
+    }
+}
+
+impl FromStr for SigningKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "message" => Ok(Self::Message),
+            "typedData" => Ok(Self::TypedData),
+            "transaction" => Ok(Self::Transaction),
+            _ => Err(()),
+        }
+    }
+}
+
+impl SigningOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Signed => "signed",
+            Self::Rejected => "rejected",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for SigningOutcome {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "signed" => Ok(Self::Signed),
+            "rejected" => Ok(Self::Rejected),
+            "failed" => Ok(Self::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<&SqliteRow> for AuditEntry {
+    type Error = ();
+
+    fn try_from(row: &SqliteRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            timestamp: row.get::<i64, _>("created_at") as u64,
+            address: Address::from_str(row.get("address")).map_err(|_| ())?,
+            chain_id: row.get::<u32, _>("chain_id"),
+            kind: row.get::<&str, _>("kind").parse()?,
+            payload_hash: H256::from_str(row.get("payload_hash")).map_err(|_| ())?,
+            outcome: row.get::<&str, _>("outcome").parse()?,
+        })
+    }
+}
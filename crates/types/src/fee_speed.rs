@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// How quickly a transaction should be included, trading off against its cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
@@ -0,0 +1,36 @@
+use ethers::types::U256;
+use serde::Serialize;
+use sqlx::{sqlite::SqliteRow, Row};
+
+/// The fees paid in a block, as sampled by the gas tracker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasSample {
+    pub block_number: u64,
+
+    /// Unix timestamp, in seconds, of when the block was sampled
+    pub timestamp: u64,
+    pub base_fee: U256,
+
+    /// The 10th, 50th and 90th percentiles of the priority fees paid in the block
+    pub priority_fee_low: U256,
+    pub priority_fee_medium: U256,
+    pub priority_fee_high: U256,
+}
+
+impl TryFrom<&SqliteRow> for GasSample {
+    type Error = ();
+
+    fn try_from(row: &SqliteRow) -> Result<Self, Self::Error> {
+        let fee = |column: &str| U256::from_dec_str(row.get(column)).map_err(|_| ());
+
+        Ok(Self {
+            block_number: row.get::<i64, _>("block_number") as u64,
+            timestamp: row.get::<i64, _>("created_at") as u64,
+            base_fee: fee("base_fee")?,
+            priority_fee_low: fee("priority_fee_low")?,
+            priority_fee_medium: fee("priority_fee_medium")?,
+            priority_fee_high: fee("priority_fee_high")?,
+        })
+    }
+}
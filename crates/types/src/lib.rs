@@ -1,18 +1,32 @@
 mod affinity;
+mod audit_entry;
 mod checksummed_address;
 pub mod events;
+mod fee_speed;
+mod gas_sample;
 mod global_state;
+mod persist;
+mod request_source;
+mod signed_message;
 mod stored_contract;
 mod tokens;
+mod tx_record;
 pub mod ui_events;
 
 pub use affinity::Affinity;
+pub use audit_entry::{AuditEntry, SigningKind, SigningOutcome};
 pub use checksummed_address::ChecksummedAddress;
 use ethers::types::{Address, U256};
 pub use events::Event;
+pub use fee_speed::FeeSpeed;
+pub use gas_sample::GasSample;
 pub use global_state::GlobalState;
+pub use persist::{save_json, save_json_private};
+pub use request_source::RequestSource;
+pub use signed_message::SignedMessage;
 pub use stored_contract::StoredContract;
-pub use tokens::{TokenBalance, TokenMetadata};
+pub use tokens::{NftToken, TokenBalance, TokenMetadata, TokenPrice, NATIVE_ASSET};
+pub use tx_record::{TxRecord, TxStatus, TxUpdate};
 pub use ui_events::UINotify;
 
 pub type Json = serde_json::Value;
@@ -0,0 +1,83 @@
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use serde::Serialize;
+
+/// Persists a value as pretty JSON, replacing the file atomically
+/// The value is written to a sibling temporary file first, and only renamed over the original once
+/// fully flushed, so a crash mid-write never leaves a truncated file to be restored at startup
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    write_json(path, value, false)
+}
+
+/// Like `save_json`, for files holding secrets, which only the current user may read
+pub fn save_json_private<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    write_json(path, value, true)
+}
+
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T, private: bool) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = Path::new(&tmp);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    }
+    let file = options.open(tmp)?;
+
+    // a temporary file left over from a crash keeps whatever mode it had
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_existing_file() {
+        let path = std::env::temp_dir().join("iron-persist-test.json");
+        fs::write(&path, "stale").unwrap();
+
+        save_json(&path, &serde_json::json!({ "a": 1 })).unwrap();
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["a"], 1);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join("iron-persist-private-test.json");
+        fs::write(&path, "stale").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        save_json_private(&path, &serde_json::json!({ "a": 1 })).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_file(&path).unwrap();
+    }
+}
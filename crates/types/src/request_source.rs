@@ -0,0 +1,13 @@
+use std::net::SocketAddr;
+
+use serde::Serialize;
+
+/// Metadata about the peer connection a request originated from
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestSource {
+    pub socket: SocketAddr,
+    pub origin: String,
+    pub title: Option<String>,
+    pub favicon: Option<String>,
+}
@@ -0,0 +1,43 @@
+use std::str::FromStr;
+
+use ethers::types::{Address, H256};
+use serde::Serialize;
+use sqlx::{sqlite::SqliteRow, Row};
+
+/// A message or typed data signature produced by the wallet
+/// Unlike the audit log, only successful signatures are kept, along with the signature itself,
+/// so that what a dapp got can be checked later
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedMessage {
+    /// unix timestamp, in seconds
+    pub timestamp: u64,
+    pub address: Address,
+    pub chain_id: u32,
+
+    /// The site that asked for it, if any
+    pub origin: Option<String>,
+
+    /// The JSON-RPC method it was requested through
+    pub method: String,
+    pub payload_hash: H256,
+
+    /// `0x`-prefixed hex
+    pub signature: String,
+}
+
+impl TryFrom<&SqliteRow> for SignedMessage {
+    type Error = ();
+
+    fn try_from(row: &SqliteRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            timestamp: row.get::<i64, _>("created_at") as u64,
+            address: Address::from_str(row.get("address")).map_err(|_| ())?,
+            chain_id: row.get::<u32, _>("chain_id"),
+            origin: row.get("origin"),
+            method: row.get("method"),
+            payload_hash: H256::from_str(row.get("payload_hash")).map_err(|_| ())?,
+            signature: row.get("signature"),
+        })
+    }
+}
@@ -43,3 +43,62 @@ impl TryFrom<SqliteRow> for TokenMetadata {
         })
     }
 }
+
+/// A cached fiat price, either of a network's native currency or of one of its tokens
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenPrice {
+    /// `None` for the native currency
+    pub contract: Option<ChecksummedAddress>,
+    pub price: f64,
+
+    /// Unix timestamp, in seconds
+    pub updated_at: u64,
+}
+
+impl TryFrom<SqliteRow> for TokenPrice {
+    type Error = ();
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        let asset: &str = row.get("asset");
+
+        Ok(Self {
+            contract: match asset {
+                NATIVE_ASSET => None,
+                contract => Some(Address::from_str(contract).map_err(|_| ())?.into()),
+            },
+            price: row.get("price"),
+            updated_at: row.get::<i64, _>("updated_at") as u64,
+        })
+    }
+}
+
+/// How a network's native currency is stored wherever tokens are keyed by contract
+pub const NATIVE_ASSET: &str = "native";
+
+/// An NFT owned by an account
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NftToken {
+    pub contract: ChecksummedAddress,
+    pub token_id: U256,
+
+    /// `None` until it has been fetched, or if the contract doesn't provide one
+    pub token_uri: Option<String>,
+}
+
+impl TryFrom<SqliteRow> for NftToken {
+    type Error = ();
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        let token_id: &str = row.get("token_id");
+
+        Ok(Self {
+            contract: Address::from_str(row.get("contract")).unwrap().into(),
+            token_id: U256::from_str_radix(token_id.trim_start_matches("0x"), 16).unwrap(),
+            token_uri: row
+                .get::<Option<String>, _>("token_uri")
+                .filter(|uri| !uri.is_empty()),
+        })
+    }
+}
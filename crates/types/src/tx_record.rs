@@ -0,0 +1,91 @@
+use std::str::FromStr;
+
+use ethers::types::{Address, H256, U256};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row};
+
+/// Where a sent transaction currently stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TxStatus {
+    Pending,
+    Confirmed,
+    Failed,
+    /// Superseded by a speed-up or cancellation using the same nonce
+    Replaced,
+    /// Evicted from the mempool without its nonce ever being used
+    Dropped,
+}
+
+/// A transaction sent through the wallet, kept as part of the activity history
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxRecord {
+    pub hash: H256,
+    pub chain_id: u32,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub status: TxStatus,
+    /// unix timestamp, in seconds
+    pub timestamp: u64,
+}
+
+/// A step in a sent transaction's lifecycle, as broadcast to the UI and to the dapp that sent it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxUpdate {
+    pub hash: H256,
+    pub chain_id: u32,
+    pub status: TxStatus,
+    /// Only set once mined
+    pub block_number: Option<u64>,
+    pub gas_used: Option<U256>,
+}
+
+impl TxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Confirmed => "confirmed",
+            Self::Failed => "failed",
+            Self::Replaced => "replaced",
+            Self::Dropped => "dropped",
+        }
+    }
+}
+
+impl FromStr for TxStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "confirmed" => Ok(Self::Confirmed),
+            "failed" => Ok(Self::Failed),
+            "replaced" => Ok(Self::Replaced),
+            "dropped" => Ok(Self::Dropped),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<&SqliteRow> for TxRecord {
+    type Error = ();
+
+    fn try_from(row: &SqliteRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            hash: H256::from_str(row.get("hash")).map_err(|_| ())?,
+            chain_id: row.get::<u32, _>("chain_id"),
+            from: Address::from_str(row.get("from_address")).map_err(|_| ())?,
+            to: row
+                .get::<Option<&str>, _>("to_address")
+                .map(Address::from_str)
+                .transpose()
+                .map_err(|_| ())?,
+            value: U256::from_dec_str(row.get("value")).map_err(|_| ())?,
+            status: row.get::<&str, _>("status").parse()?,
+            timestamp: row.get::<i64, _>("created_at") as u64,
+        })
+    }
+}
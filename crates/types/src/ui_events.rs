@@ -22,15 +22,39 @@ pub struct DialogSend {
     pub payload: Option<serde_json::Value>,
 }
 
+/// A native desktop notification, also forwarded to the main window
+#[derive(Debug, Clone, Serialize)]
+pub struct DesktopNotification {
+    pub title: String,
+    pub body: String,
+}
+
+/// A failure the user should hear about, that didn't come from any command they ran
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorNotice {
+    pub title: String,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub enum UINotify {
     #[allow(unused)]
     WalletsChanged,
     NetworkChanged,
+    NetworksUpdated,
     TxsUpdated,
     PeersUpdated,
     BalancesUpdated,
     ContractsUpdated,
+    PendingRequestsUpdated,
+    PricesUpdated,
+    ContactsUpdated,
+    SettingsUpdated,
+    LockChanged,
+    PeerConnected,
+    PeerDisconnected,
+    NetworkHealthUpdated,
+    GasUpdated,
 }
 
 impl UINotify {
@@ -38,10 +62,20 @@ impl UINotify {
         match self {
             Self::WalletsChanged => "wallets-changed",
             Self::NetworkChanged => "network-changed",
+            Self::NetworksUpdated => "networks-changed",
             Self::TxsUpdated => "txs-updated",
             Self::PeersUpdated => "peers-updated",
             Self::BalancesUpdated => "balances-updated",
             Self::ContractsUpdated => "contracts-updated",
+            Self::PendingRequestsUpdated => "pending-requests-updated",
+            Self::PricesUpdated => "prices-updated",
+            Self::ContactsUpdated => "contacts-updated",
+            Self::SettingsUpdated => "settings-updated",
+            Self::LockChanged => "lock-changed",
+            Self::PeerConnected => "peer-connected",
+            Self::PeerDisconnected => "peer-disconnected",
+            Self::NetworkHealthUpdated => "network-health-updated",
+            Self::GasUpdated => "gas-updated",
         }
     }
 }
@@ -0,0 +1,20 @@
+use iron_types::GlobalState;
+
+use crate::{Result, Session, WalletConnect};
+
+/// Pairs with a dapp from the URI in its QR code
+/// The dapp then proposes a session, which the user is asked to approve
+#[tauri::command]
+pub async fn walletconnect_pair(uri: String) -> Result<()> {
+    crate::pair(&uri).await
+}
+
+#[tauri::command]
+pub async fn walletconnect_get_sessions() -> Vec<Session> {
+    WalletConnect::read().await.sessions()
+}
+
+#[tauri::command]
+pub async fn walletconnect_disconnect(topic: String) -> Result<()> {
+    crate::disconnect(&topic).await
+}
@@ -0,0 +1,105 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use ethers::core::rand::{thread_rng, RngCore};
+use hkdf::Hkdf;
+use iron_types::Json;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{Error, Result};
+
+/// The key every message on a topic is encrypted with
+pub type SymKey = [u8; 32];
+
+/// Messages are sealed in type 0 envelopes: `type (1 byte) | iv (12 bytes) | ciphertext`
+const ENVELOPE_TYPE: u8 = 0;
+const IV_LENGTH: usize = 12;
+
+/// Topics are derived from their key, so that both sides agree on them without an extra round
+pub fn topic_of(key: &SymKey) -> String {
+    hex::encode(Sha256::digest(key))
+}
+
+/// Generates the key pair used to agree on a session key with a dapp
+pub fn key_pair() -> (StaticSecret, PublicKey) {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill_bytes(&mut bytes);
+
+    let secret = StaticSecret::from(bytes);
+    let public = PublicKey::from(&secret);
+    (secret, public)
+}
+
+/// Derives a session key from our secret and the dapp's public key, as X25519 followed by
+/// HKDF-SHA256
+pub fn derive_sym_key(secret: &StaticSecret, peer: &PublicKey) -> SymKey {
+    let shared = secret.diffie_hellman(peer);
+
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared.as_bytes())
+        .expand(&[], &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+pub fn encrypt(key: &SymKey, payload: &Json) -> Result<String> {
+    let mut iv = [0u8; IV_LENGTH];
+    thread_rng().fill_bytes(&mut iv);
+
+    let sealed = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&iv), payload.to_string().as_bytes())
+        .map_err(|_| Error::InvalidEnvelope)?;
+
+    let mut envelope = Vec::with_capacity(1 + IV_LENGTH + sealed.len());
+    envelope.push(ENVELOPE_TYPE);
+    envelope.extend_from_slice(&iv);
+    envelope.extend_from_slice(&sealed);
+
+    Ok(STANDARD.encode(envelope))
+}
+
+/// Only type 0 envelopes are accepted. Type 1 ones carry the sender's public key for
+/// unauthenticated exchanges, which never happen since sessions are only ever proposed to us
+pub fn decrypt(key: &SymKey, message: &str) -> Result<Json> {
+    let envelope = STANDARD
+        .decode(message)
+        .map_err(|_| Error::InvalidEnvelope)?;
+    if envelope.len() <= 1 + IV_LENGTH || envelope[0] != ENVELOPE_TYPE {
+        return Err(Error::InvalidEnvelope);
+    }
+
+    let (iv, sealed) = envelope[1..].split_at(IV_LENGTH);
+    let plain = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(iv), sealed)
+        .map_err(|_| Error::Decryption)?;
+
+    Ok(serde_json::from_slice(&plain)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn both_sides_derive_the_same_session() {
+        let (ours, our_public) = key_pair();
+        let (theirs, their_public) = key_pair();
+
+        let key = derive_sym_key(&ours, &their_public);
+        assert_eq!(key, derive_sym_key(&theirs, &our_public));
+
+        let payload = json!({ "id": 1, "jsonrpc": "2.0", "result": true });
+        let sealed = encrypt(&key, &payload).unwrap();
+        assert_eq!(decrypt(&key, &sealed).unwrap(), payload);
+
+        assert!(matches!(
+            decrypt(&[0u8; 32], &sealed),
+            Err(Error::Decryption)
+        ));
+    }
+}
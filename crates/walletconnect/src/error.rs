@@ -0,0 +1,49 @@
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Websocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Dialog(#[from] iron_dialogs::Error),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
+
+    #[error(transparent)]
+    Connections(#[from] iron_connections::Error),
+
+    #[error("invalid pairing URI: {0}")]
+    InvalidUri(String),
+
+    #[error("malformed WalletConnect message")]
+    InvalidEnvelope,
+
+    #[error("failed to decrypt WalletConnect message")]
+    Decryption,
+
+    #[error("unknown topic: {0}")]
+    UnknownTopic(String),
+
+    #[error("no WalletConnect project ID configured")]
+    NoProjectId,
+
+    #[error("connection to the WalletConnect relay was closed")]
+    RelayClosed,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl serde::Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
@@ -0,0 +1,389 @@
+use ethers::types::Address;
+use iron_connections::Store;
+use iron_dialogs::{Dialog, DialogMsg};
+use iron_networks::Networks;
+use iron_types::{GlobalState, Json, UINotify};
+use iron_wallets::{WalletControl, Wallets};
+use serde_json::json;
+use x25519_dalek::PublicKey;
+
+use crate::{
+    crypto::{self, SymKey},
+    relay,
+    session::{self, Metadata, Session, METHODS, SESSION_TTL},
+    Error, Result, WalletConnect,
+};
+
+// tags the relay uses to tell message types apart, e.g. to decide which ones warrant a push
+// notification. Responses use the request's tag + 1
+const TAG_PAIRING_DELETE: u32 = 1000;
+const TAG_PAIRING_PING: u32 = 1002;
+const TAG_SESSION_PROPOSE: u32 = 1100;
+const TAG_SESSION_SETTLE: u32 = 1102;
+const TAG_SESSION_UPDATE: u32 = 1104;
+const TAG_SESSION_EXTEND: u32 = 1106;
+const TAG_SESSION_REQUEST: u32 = 1108;
+const TAG_SESSION_EVENT: u32 = 1110;
+const TAG_SESSION_DELETE: u32 = 1112;
+const TAG_SESSION_PING: u32 = 1114;
+
+/// Handles a message published by a dapp on one of our topics
+pub(crate) async fn handle(topic: String, message: String) {
+    if let Err(e) = dispatch(&topic, &message).await {
+        tracing::warn!("Failed to handle WalletConnect message on {}: {}", topic, e);
+    }
+}
+
+async fn dispatch(topic: &str, message: &str) -> Result<()> {
+    let key = WalletConnect::read()
+        .await
+        .key(topic)
+        .ok_or_else(|| Error::UnknownTopic(topic.into()))?;
+    let msg = crypto::decrypt(&key, message)?;
+
+    // replies to our own requests, such as the dapp acknowledging a settlement, need no action
+    let Some(method) = msg["method"].as_str() else {
+        return Ok(());
+    };
+    let id = &msg["id"];
+
+    match method {
+        "wc_sessionPropose" => propose(topic, &key, &msg).await,
+        "wc_sessionRequest" => request(topic, &key, &msg).await,
+        "wc_sessionPing" => reply(topic, &key, id, Ok(json!(true)), TAG_SESSION_PING).await,
+        "wc_pairingPing" => reply(topic, &key, id, Ok(json!(true)), TAG_PAIRING_PING).await,
+        "wc_sessionExtend" => extend(topic, &key, id).await,
+        "wc_sessionDelete" | "wc_pairingDelete" => {
+            forget(topic).await?;
+            let tag = match method {
+                "wc_sessionDelete" => TAG_SESSION_DELETE,
+                _ => TAG_PAIRING_DELETE,
+            };
+            reply(topic, &key, id, Ok(json!(true)), tag).await
+        }
+        _ => {
+            tracing::debug!("Ignoring unsupported WalletConnect method {}", method);
+            Ok(())
+        }
+    }
+}
+
+/// Asks the user whether to accept a dapp's session proposal
+/// Once accepted, the session's origin is approved the same way a websocket peer's would be, and
+/// the session is settled on a topic of its own
+async fn propose(pairing_topic: &str, pairing_key: &SymKey, msg: &Json) -> Result<()> {
+    let id = &msg["id"];
+    let params = &msg["params"];
+    let peer: Metadata = serde_json::from_value(params["proposer"]["metadata"].clone())?;
+    let peer_key: [u8; 32] = params["proposer"]["publicKey"]
+        .as_str()
+        .and_then(|key| hex::decode(key).ok())
+        .and_then(|key| key.try_into().ok())
+        .ok_or(Error::InvalidEnvelope)?;
+
+    let required = session::chains_of(&params["requiredNamespaces"]);
+    let optional = session::chains_of(&params["optionalNamespaces"]);
+
+    let chains = {
+        let networks = Networks::read().await;
+        if let Some(missing) = required.iter().find(|c| !networks.validate_chain_id(**c)) {
+            let error = json!({ "code": 5100, "message": format!("Unsupported chain: eip155:{}", missing) });
+            return reply(
+                pairing_topic,
+                pairing_key,
+                id,
+                Err(error),
+                TAG_SESSION_PROPOSE,
+            )
+            .await;
+        }
+
+        let mut chains = required.clone();
+        for chain in optional {
+            if networks.validate_chain_id(chain) && !chains.contains(&chain) {
+                chains.push(chain);
+            }
+        }
+        if chains.is_empty() {
+            chains.push(networks.get_current()?.chain_id);
+        }
+        chains
+    };
+
+    let mut methods: Vec<String> = METHODS.iter().map(|m| m.to_string()).collect();
+    for method in session::methods_of(&params["requiredNamespaces"]) {
+        if !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+
+    let dialog = Dialog::new(
+        "walletconnect-proposal",
+        json!({ "peer": peer, "chains": chains }),
+    );
+    dialog.open().await?;
+    let accepted = matches!(dialog.recv().await, Some(DialogMsg::Accept(_)));
+    dialog.close().await?;
+
+    if !accepted {
+        WalletConnect::write().await.remove(pairing_topic)?;
+        let error = json!({ "code": 5000, "message": "User rejected." });
+        return reply(
+            pairing_topic,
+            pairing_key,
+            id,
+            Err(error),
+            TAG_SESSION_PROPOSE,
+        )
+        .await;
+    }
+
+    let (secret, public) = crypto::key_pair();
+    let key = crypto::derive_sym_key(&secret, &PublicKey::from(peer_key));
+    let session = Session {
+        topic: crypto::topic_of(&key),
+        pairing_topic: pairing_topic.into(),
+        peer,
+        chains,
+        methods,
+        expiry: session::now() + SESSION_TTL,
+    };
+
+    Store::write().await.approve(&session.origin())?;
+    WalletConnect::write()
+        .await
+        .add_session(session.clone(), key)?;
+    relay::subscribe(&session.topic).await?;
+
+    let public = hex::encode(public.as_bytes());
+    let result = json!({ "relay": { "protocol": "irn" }, "responderPublicKey": public });
+    reply(
+        pairing_topic,
+        pairing_key,
+        id,
+        Ok(result),
+        TAG_SESSION_PROPOSE,
+    )
+    .await?;
+
+    let settle = relay::request(
+        "wc_sessionSettle",
+        json!({
+            "relay": { "protocol": "irn" },
+            "namespaces": session.namespaces(current_account().await),
+            "requiredNamespaces": params["requiredNamespaces"],
+            "optionalNamespaces": params["optionalNamespaces"],
+            "pairingTopic": pairing_topic,
+            "controller": { "publicKey": public, "metadata": Metadata::ours() },
+            "expiry": session.expiry,
+        }),
+    );
+    send(&session.topic, &key, &settle, TAG_SESSION_SETTLE).await?;
+
+    iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+    Ok(())
+}
+
+/// Routes a dapp's JSON-RPC request through the same handler websocket peers use, as long as
+/// the session offered its method
+/// Each request names the chain it's meant for, so the session is pinned to that chain rather
+/// than following its domain's affinity
+async fn request(topic: &str, key: &SymKey, msg: &Json) -> Result<()> {
+    let session = WalletConnect::read()
+        .await
+        .session(topic)
+        .cloned()
+        .ok_or_else(|| Error::UnknownTopic(topic.into()))?;
+    let params = &msg["params"];
+
+    if session.is_expired() {
+        return expire(topic, key, &msg["id"], TAG_SESSION_REQUEST).await;
+    }
+
+    let method = params["request"]["method"].as_str().unwrap_or_default();
+    if !session.methods.iter().any(|m| m == method) {
+        let error = json!({ "code": 5101, "message": format!("Unsupported method: {}", method) });
+        return reply(topic, key, &msg["id"], Err(error), TAG_SESSION_REQUEST).await;
+    }
+
+    if let Some(chain_id) = params["chainId"].as_str().and_then(session::parse_chain) {
+        if Networks::read().await.validate_chain_id(chain_id) {
+            Store::write()
+                .await
+                .set_peer_chain_id(session.socket(), Some(chain_id));
+        }
+    }
+
+    let request = json!({
+        "id": msg["id"],
+        "jsonrpc": "2.0",
+        "method": params["request"]["method"],
+        "params": params["request"]["params"],
+    });
+    let handler = iron_rpc::Handler::new(session.ctx());
+    let Some(response) = handler.handle(request.to_string()).await else {
+        return Ok(());
+    };
+
+    let response: Json = serde_json::from_str(&response)?;
+    send(topic, key, &response, TAG_SESSION_REQUEST + 1).await
+}
+
+/// Renews a session for another full term, unless it already expired
+async fn extend(topic: &str, key: &SymKey, id: &Json) -> Result<()> {
+    let extended = WalletConnect::write()
+        .await
+        .extend(topic, session::now() + SESSION_TTL)?;
+    if !extended {
+        return expire(topic, key, id, TAG_SESSION_EXTEND).await;
+    }
+
+    reply(topic, key, id, Ok(json!(true)), TAG_SESSION_EXTEND).await
+}
+
+/// Turns down a request made on a session that has expired, and drops the session
+async fn expire(topic: &str, key: &SymKey, id: &Json, tag: u32) -> Result<()> {
+    let error = json!({ "code": 8000, "message": "Session expired." });
+    reply(topic, key, id, Err(error), tag).await?;
+
+    forget(topic).await
+}
+
+/// Tells a session's dapp the chain it's on changed, after it switched chains itself
+pub(crate) async fn chain_changed(session: &Session, chain_id: u32) -> Result<()> {
+    event(session, "chainChanged", json!(chain_id), chain_id).await
+}
+
+/// Exposes the new current account to every session, except those scoped to another one
+pub(crate) async fn accounts_changed(account: Address) -> Result<()> {
+    let sessions = WalletConnect::read().await.sessions();
+
+    for session in sessions {
+        if Store::read()
+            .await
+            .get_scoped_account(&session.origin())
+            .is_some()
+        {
+            continue;
+        }
+        expose_account(&session, account).await?;
+    }
+
+    Ok(())
+}
+
+/// Catches a session up with the user revoking its approval, or scoping it to an account
+/// Namespaces can't be left without accounts, so a revoked session is ended instead
+pub(crate) async fn access_changed(origin: &str) -> Result<()> {
+    let Some(session) = WalletConnect::read()
+        .await
+        .session_by_origin(origin)
+        .cloned()
+    else {
+        return Ok(());
+    };
+
+    let (approved, scoped) = {
+        let store = Store::read().await;
+        (store.is_approved(origin), store.get_scoped_account(origin))
+    };
+    if !approved {
+        return disconnect(&session.topic).await;
+    }
+
+    let account = match scoped {
+        Some(account) => account.into(),
+        None => current_account().await,
+    };
+    expose_account(&session, account).await
+}
+
+/// The session's namespaces are updated first, since dapps ignore events for accounts outside
+/// of them
+async fn expose_account(session: &Session, account: Address) -> Result<()> {
+    let Some(key) = WalletConnect::read().await.key(&session.topic) else {
+        return Ok(());
+    };
+    let update = relay::request(
+        "wc_sessionUpdate",
+        json!({ "namespaces": session.namespaces(account) }),
+    );
+    send(&session.topic, &key, &update, TAG_SESSION_UPDATE).await?;
+
+    let chain_id = session.ctx().chain_id().await?;
+    event(session, "accountsChanged", json!([account]), chain_id).await
+}
+
+/// Ends a session from our side
+pub(crate) async fn disconnect(topic: &str) -> Result<()> {
+    if let Some(key) = WalletConnect::read().await.key(topic) {
+        let delete = relay::request(
+            "wc_sessionDelete",
+            json!({ "code": 6000, "message": "User disconnected." }),
+        );
+        send(topic, &key, &delete, TAG_SESSION_DELETE).await?;
+    }
+
+    forget(topic).await
+}
+
+/// Drops a session along with its approval, which no other peer can use
+async fn forget(topic: &str) -> Result<()> {
+    let removed = WalletConnect::write().await.remove(topic)?;
+
+    if let Some(session) = removed {
+        let mut store = Store::write().await;
+        store.forget_peer(session.socket());
+        if store.is_approved(&session.origin()) {
+            store.revoke(&session.origin())?;
+        }
+        drop(store);
+        iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
+    }
+
+    Ok(())
+}
+
+async fn event(session: &Session, name: &str, data: Json, chain_id: u32) -> Result<()> {
+    let Some(key) = WalletConnect::read().await.key(&session.topic) else {
+        return Ok(());
+    };
+
+    let event = relay::request(
+        "wc_sessionEvent",
+        json!({
+            "event": { "name": name, "data": data },
+            "chainId": format!("eip155:{}", chain_id),
+        }),
+    );
+    send(&session.topic, &key, &event, TAG_SESSION_EVENT).await
+}
+
+/// Answers a request from the dapp, with either a result or an error
+async fn reply(
+    topic: &str,
+    key: &SymKey,
+    id: &Json,
+    result: std::result::Result<Json, Json>,
+    request_tag: u32,
+) -> Result<()> {
+    let response = match result {
+        Ok(result) => json!({ "id": id, "jsonrpc": "2.0", "result": result }),
+        Err(error) => json!({ "id": id, "jsonrpc": "2.0", "error": error }),
+    };
+
+    send(topic, key, &response, request_tag + 1).await
+}
+
+async fn send(topic: &str, key: &SymKey, payload: &Json, tag: u32) -> Result<()> {
+    relay::publish(topic, crypto::encrypt(key, payload)?, tag).await
+}
+
+async fn current_account() -> Address {
+    Wallets::read()
+        .await
+        .get_current_wallet()
+        .get_current_address()
+        .await
+        .into()
+}
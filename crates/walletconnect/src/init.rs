@@ -0,0 +1,79 @@
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use async_trait::async_trait;
+use iron_broadcast::InternalMsg;
+use iron_types::GlobalState;
+use once_cell::sync::OnceCell;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{handler, relay, WalletConnect};
+
+static WALLETCONNECT: OnceCell<RwLock<WalletConnect>> = OnceCell::new();
+
+/// Restores previous sessions, reconnecting to the relay if there are any
+/// Needs to run after settings are initialized, since they hold the relay's project ID
+pub async fn init(pathbuf: PathBuf) {
+    let mut store: WalletConnect = match File::open(&pathbuf) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file)).unwrap_or_default(),
+        Err(_) => Default::default(),
+    };
+    store.file = pathbuf;
+    if let Err(e) = store.prune_expired() {
+        tracing::warn!("Failed to prune WalletConnect sessions: {}", e);
+    }
+
+    let resume = !store.topics().is_empty();
+    WALLETCONNECT.set(RwLock::new(store)).unwrap();
+
+    tokio::spawn(async { receiver().await });
+    if resume {
+        if let Err(e) = relay::connect().await {
+            tracing::warn!("Failed to resume WalletConnect sessions: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl GlobalState for WalletConnect {
+    async fn read<'a>() -> RwLockReadGuard<'a, Self> {
+        WALLETCONNECT.get().unwrap().read().await
+    }
+
+    async fn write<'a>() -> RwLockWriteGuard<'a, Self> {
+        WALLETCONNECT.get().unwrap().write().await
+    }
+}
+
+/// Forwards the same events websocket peers get to WalletConnect sessions
+async fn receiver() -> ! {
+    let mut rx = iron_broadcast::subscribe_internal().await;
+
+    loop {
+        if let Ok(msg) = rx.recv().await {
+            use InternalMsg::*;
+
+            let res = match msg {
+                PeerChainChanged(socket, chain_id) => {
+                    let session = WalletConnect::read()
+                        .await
+                        .session_by_socket(socket)
+                        .cloned();
+                    match session {
+                        Some(session) => handler::chain_changed(&session, chain_id).await,
+                        None => Ok(()),
+                    }
+                }
+                AccountsChanged(accounts) => match accounts.first() {
+                    Some(account) => handler::accounts_changed((*account).into()).await,
+                    None => Ok(()),
+                },
+                OriginAccessChanged(origin) => handler::access_changed(&origin).await,
+                _ => Ok(()),
+            };
+
+            if let Err(e) = res {
+                tracing::warn!("Failed to notify WalletConnect sessions: {}", e);
+            }
+        }
+    }
+}
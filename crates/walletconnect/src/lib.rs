@@ -0,0 +1,34 @@
+pub mod commands;
+mod crypto;
+mod error;
+mod handler;
+mod init;
+mod relay;
+mod session;
+mod store;
+mod uri;
+
+use std::str::FromStr;
+
+pub use error::{Error, Result};
+pub use init::init;
+use iron_types::GlobalState;
+pub use session::{Metadata, Session};
+pub use store::WalletConnect;
+pub use uri::PairingUri;
+
+/// Subscribes to a pairing topic, where the dapp's session proposal is waiting
+pub async fn pair(uri: &str) -> Result<()> {
+    let pairing = PairingUri::from_str(uri)?;
+
+    relay::connect().await?;
+    WalletConnect::write()
+        .await
+        .add_pairing(pairing.topic.clone(), pairing.sym_key)?;
+    relay::subscribe(&pairing.topic).await
+}
+
+/// Ends a session, telling the dapp about it
+pub async fn disconnect(topic: &str) -> Result<()> {
+    handler::disconnect(topic).await
+}
@@ -0,0 +1,217 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{Signer, SigningKey};
+use ethers::core::rand::{thread_rng, Rng, RngCore};
+use futures_util::{SinkExt, StreamExt};
+use iron_settings::Settings;
+use iron_types::{GlobalState, Json};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::{sleep, Duration},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::{Error, Result, WalletConnect};
+
+const RELAY_URL: &str = "wss://relay.walletconnect.com";
+
+/// How long the relay holds on to our messages while the dapp is offline
+const TTL: u64 = 300;
+
+/// How long to wait before reconnecting to the relay after losing it
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Outgoing requests to the relay
+/// The connection is only opened once there's something to pair with, and re-opened when lost
+static OUTBOX: Lazy<Mutex<Option<mpsc::UnboundedSender<Json>>>> = Lazy::new(Default::default);
+
+/// Starts receiving a topic's messages, including those the relay stored while we were away
+pub(crate) async fn subscribe(topic: &str) -> Result<()> {
+    send(request("irn_subscribe", json!({ "topic": topic }))).await
+}
+
+pub(crate) async fn publish(topic: &str, message: String, tag: u32) -> Result<()> {
+    send(request(
+        "irn_publish",
+        json!({ "topic": topic, "message": message, "ttl": TTL, "tag": tag, "prompt": false }),
+    ))
+    .await
+}
+
+/// Opens the relay connection, if it isn't already
+pub(crate) async fn connect() -> Result<()> {
+    outbox().await.map(|_| ())
+}
+
+async fn send(request: Json) -> Result<()> {
+    outbox()
+        .await?
+        .send(request)
+        .map_err(|_| Error::RelayClosed)
+}
+
+async fn outbox() -> Result<mpsc::UnboundedSender<Json>> {
+    let mut outbox = OUTBOX.lock().await;
+
+    if let Some(ref sender) = *outbox {
+        if !sender.is_closed() {
+            return Ok(sender.clone());
+        }
+    }
+
+    let project_id = project_id().await.ok_or(Error::NoProjectId)?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(run(project_id, receiver));
+    *outbox = Some(sender.clone());
+
+    Ok(sender)
+}
+
+/// Project IDs are issued by WalletConnect Cloud, and identify the app to the relay
+async fn project_id() -> Option<String> {
+    Settings::read()
+        .await
+        .get()
+        .walletconnect_project_id
+        .clone()
+        .or_else(|| std::env::var("IRON_WALLETCONNECT_PROJECT_ID").ok())
+        .filter(|id| !id.is_empty())
+}
+
+/// Keeps a connection to the relay alive, resubscribing to every known topic whenever it comes
+/// back
+/// Requests queued while disconnected are sent once it does
+async fn run(project_id: String, mut outbox: mpsc::UnboundedReceiver<Json>) {
+    loop {
+        let url = format!(
+            "{}/?auth={}&projectId={}",
+            RELAY_URL,
+            auth_token(),
+            project_id
+        );
+
+        match connect_async(url).await {
+            Ok((stream, _)) => {
+                let (mut sink, mut stream) = stream.split();
+
+                let topics = WalletConnect::read().await.topics();
+                for topic in topics {
+                    let request = request("irn_subscribe", json!({ "topic": topic }));
+                    if sink.send(request.to_string().into()).await.is_err() {
+                        break;
+                    }
+                }
+
+                loop {
+                    tokio::select! {
+                        request = outbox.recv() => match request {
+                            Some(request) => {
+                                if let Err(e) = sink.send(request.to_string().into()).await {
+                                    tracing::warn!("Failed to send to the WalletConnect relay: {}", e);
+                                    break;
+                                }
+                            }
+                            // the app is shutting down
+                            None => return,
+                        },
+
+                        msg = stream.next() => match msg {
+                            Some(Ok(Message::Text(msg))) => {
+                                if let Some(ack) = on_message(&msg) {
+                                    let _ = sink.send(ack.to_string().into()).await;
+                                }
+                            }
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            Some(Ok(_)) => (),
+                        },
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to connect to the WalletConnect relay: {}", e),
+        }
+
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Dispatches a message delivered through one of our subscriptions, returning the
+/// acknowledgement the relay expects for it
+fn on_message(msg: &str) -> Option<Json> {
+    let msg: Json = serde_json::from_str(msg).ok()?;
+
+    if let Some(error) = msg.get("error") {
+        tracing::warn!("WalletConnect relay error: {}", error);
+        return None;
+    }
+
+    if msg["method"].as_str() != Some("irn_subscription") {
+        return None;
+    }
+
+    let data = &msg["params"]["data"];
+    if let (Some(topic), Some(message)) = (data["topic"].as_str(), data["message"].as_str()) {
+        tokio::spawn(crate::handler::handle(
+            topic.to_string(),
+            message.to_string(),
+        ));
+    }
+
+    Some(json!({ "id": msg["id"], "jsonrpc": "2.0", "result": true }))
+}
+
+/// Builds a JSON-RPC request, with an ID in the format WalletConnect clients use: a millisecond
+/// timestamp followed by 3 random digits
+pub(crate) fn request(method: &str, params: Json) -> Json {
+    json!({ "id": message_id(), "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub(crate) fn message_id() -> u64 {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    millis * 1000 + thread_rng().gen_range(0..1000)
+}
+
+/// A JWT the relay authenticates clients with, signed by an ed25519 key identified as a
+/// `did:key`
+/// A fresh key is used for every connection, since the relay routes messages by topic rather
+/// than by client
+fn auth_token() -> String {
+    let mut seed = [0u8; 32];
+    thread_rng().fill_bytes(&mut seed);
+    let key = SigningKey::from_bytes(&seed);
+
+    let mut sub = [0u8; 32];
+    thread_rng().fill_bytes(&mut sub);
+
+    // multicodec prefix for ed25519 public keys
+    let mut did = vec![0xed, 0x01];
+    did.extend_from_slice(key.verifying_key().as_bytes());
+
+    let iat = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let header = json!({ "alg": "EdDSA", "typ": "JWT" });
+    let claims = json!({
+        "iss": format!("did:key:z{}", bs58::encode(did).into_string()),
+        "sub": hex::encode(sub),
+        "aud": RELAY_URL,
+        "iat": iat,
+        "exp": iat + 24 * 60 * 60,
+    });
+
+    let data = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+    let signature = key.sign(data.as_bytes());
+
+    format!("{}.{}", data, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+}
@@ -0,0 +1,167 @@
+use std::{
+    net::{Ipv6Addr, SocketAddr},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::{types::Address, utils::to_checksum};
+use iron_connections::Ctx;
+use iron_types::{Json, RequestSource};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// How long sessions last before the dapp has to pair again
+pub(crate) const SESSION_TTL: u64 = 7 * 24 * 60 * 60;
+
+/// Methods every session is offered, on top of whatever the dapp requires
+/// Requests for anything else are rejected, as the session never offered them
+pub(crate) const METHODS: &[&str] = &[
+    "eth_sendTransaction",
+    "eth_sign",
+    "personal_sign",
+    "eth_signTypedData",
+    "eth_signTypedData_v4",
+    "wallet_switchEthereumChain",
+    "wallet_addEthereumChain",
+    "wallet_watchAsset",
+];
+
+pub(crate) const EVENTS: &[&str] = &["chainChanged", "accountsChanged"];
+
+/// Describes either side of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub url: String,
+    #[serde(default)]
+    pub icons: Vec<String>,
+}
+
+impl Metadata {
+    pub(crate) fn ours() -> Self {
+        Self {
+            name: "Iron".into(),
+            description: "A developer-focused Ethereum wallet".into(),
+            url: "https://github.com/iron-wallet".into(),
+            icons: vec![],
+        }
+    }
+}
+
+/// A dapp paired through WalletConnect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub topic: String,
+    pub pairing_topic: String,
+    pub peer: Metadata,
+
+    /// The chains the session was settled with
+    pub chains: Vec<u32>,
+    pub methods: Vec<String>,
+
+    /// Unix timestamp, in seconds
+    pub expiry: u64,
+}
+
+impl Session {
+    pub fn is_expired(&self) -> bool {
+        self.expiry <= now()
+    }
+
+    /// The context this session's requests are handled with, same as for a websocket peer
+    pub fn ctx(&self) -> Ctx {
+        Ctx {
+            domain: self.domain(),
+            source: Some(self.source()),
+        }
+    }
+
+    /// Sessions have no socket, but connections track per-peer state by one
+    /// Each session gets a placeholder address made from its topic, which can't clash with a
+    /// real peer, since those all connect from localhost
+    pub fn socket(&self) -> SocketAddr {
+        let mut bytes = [0u8; 16];
+        if let Ok(topic) = hex::decode(&self.topic) {
+            let len = topic.len().min(16);
+            bytes[..len].copy_from_slice(&topic[..len]);
+        }
+
+        SocketAddr::new(Ipv6Addr::from(bytes).into(), 0)
+    }
+
+    /// The origin this session is approved under
+    /// The URL in its metadata is only what the dapp claims, so each session is kept apart by its
+    /// topic instead, and its approval never carries over to another peer claiming the same URL
+    pub fn origin(&self) -> String {
+        format!("walletconnect:{}", self.topic)
+    }
+
+    pub fn source(&self) -> RequestSource {
+        RequestSource {
+            socket: self.socket(),
+            origin: self.origin(),
+            title: Some(self.peer.name.clone()),
+            favicon: self.peer.icons.first().cloned(),
+        }
+    }
+
+    fn domain(&self) -> Option<String> {
+        self.peer
+            .url
+            .parse::<url::Url>()
+            .ok()
+            .and_then(|url| url.host_str().map(|s| s.to_owned()))
+    }
+
+    /// The `eip155` namespace offered to the dapp, exposing `account` on every session chain
+    pub fn namespaces(&self, account: Address) -> Json {
+        let account = to_checksum(&account, None);
+
+        json!({
+            "eip155": {
+                "chains": self.chains.iter().map(|c| format!("eip155:{}", c)).collect::<Vec<_>>(),
+                "accounts": self
+                    .chains
+                    .iter()
+                    .map(|c| format!("eip155:{}:{}", c, account))
+                    .collect::<Vec<_>>(),
+                "methods": self.methods,
+                "events": EVENTS,
+            }
+        })
+    }
+}
+
+/// Reads the chain IDs out of a proposal's namespaces, e.g. `{ "eip155": { "chains":
+/// ["eip155:1"] } }`
+pub(crate) fn chains_of(namespaces: &Json) -> Vec<u32> {
+    namespaces["eip155"]["chains"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|chain| parse_chain(chain.as_str()?))
+        .collect()
+}
+
+pub(crate) fn methods_of(namespaces: &Json) -> Vec<String> {
+    namespaces["eip155"]["methods"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|method| Some(method.as_str()?.to_string()))
+        .collect()
+}
+
+/// Parses a CAIP-2 chain ID, e.g. `eip155:1`
+pub(crate) fn parse_chain(chain: &str) -> Option<u32> {
+    chain.strip_prefix("eip155:")?.parse().ok()
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
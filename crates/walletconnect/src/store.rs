@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{crypto::SymKey, Result, Session};
+
+/// Pairings and sessions with dapps connected through WalletConnect
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WalletConnect {
+    #[serde(skip)]
+    pub(crate) file: PathBuf,
+
+    // maps session topic -> session
+    sessions: HashMap<String, Session>,
+
+    // maps topic -> the key its messages are encrypted with, for both pairings and sessions
+    // kept apart from sessions, so that listing them never hands a key to the UI
+    keys: HashMap<String, SymKey>,
+}
+
+impl WalletConnect {
+    pub fn sessions(&self) -> Vec<Session> {
+        self.sessions.values().cloned().collect()
+    }
+
+    pub fn session(&self, topic: &str) -> Option<&Session> {
+        self.sessions.get(topic)
+    }
+
+    pub fn session_by_origin(&self, origin: &str) -> Option<&Session> {
+        self.sessions.values().find(|s| s.origin() == origin)
+    }
+
+    pub fn session_by_socket(&self, socket: SocketAddr) -> Option<&Session> {
+        self.sessions.values().find(|s| s.socket() == socket)
+    }
+
+    pub(crate) fn key(&self, topic: &str) -> Option<SymKey> {
+        self.keys.get(topic).copied()
+    }
+
+    /// Every topic we should be subscribed to
+    pub(crate) fn topics(&self) -> Vec<String> {
+        self.keys.keys().cloned().collect()
+    }
+
+    pub(crate) fn add_pairing(&mut self, topic: String, key: SymKey) -> Result<()> {
+        self.keys.insert(topic, key);
+        self.save()
+    }
+
+    pub(crate) fn add_session(&mut self, session: Session, key: SymKey) -> Result<()> {
+        self.keys.insert(session.topic.clone(), key);
+        self.sessions.insert(session.topic.clone(), session);
+        self.save()
+    }
+
+    /// Forgets a pairing or session
+    /// Removing a session also drops the pairing it was proposed through, which isn't reused.
+    /// Returns the removed session, if any
+    pub(crate) fn remove(&mut self, topic: &str) -> Result<Option<Session>> {
+        self.keys.remove(topic);
+        let session = self.sessions.remove(topic);
+        if let Some(ref session) = session {
+            self.keys.remove(&session.pairing_topic);
+        }
+        self.save()?;
+
+        Ok(session)
+    }
+
+    /// Moves a session's expiry to `expiry`
+    /// Returns false for sessions that already expired, which can only be dropped
+    pub(crate) fn extend(&mut self, topic: &str, expiry: u64) -> Result<bool> {
+        let Some(session) = self.sessions.get_mut(topic).filter(|s| !s.is_expired()) else {
+            return Ok(false);
+        };
+        session.expiry = expiry;
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Drops sessions that expired while the app wasn't running
+    pub(crate) fn prune_expired(&mut self) -> Result<()> {
+        let expired: Vec<_> = self
+            .sessions
+            .values()
+            .filter(|s| s.is_expired())
+            .map(|s| s.topic.clone())
+            .collect();
+
+        for topic in expired {
+            self.remove(&topic)?;
+        }
+
+        Ok(())
+    }
+
+    // Persists current state to disk
+    // Only the current user may read it, since it holds every session's key
+    fn save(&self) -> Result<()> {
+        let pathbuf = self.file.clone();
+
+        Ok(iron_types::save_json_private(Path::new(&pathbuf), self)?)
+    }
+}
@@ -0,0 +1,64 @@
+use std::str::FromStr;
+
+use crate::{crypto::SymKey, Error};
+
+/// A pairing URI, as shown by a dapp in its QR code
+/// Expected format: `wc:{topic}@2?relay-protocol=irn&symKey={hex}`
+#[derive(Debug)]
+pub struct PairingUri {
+    pub topic: String,
+    pub sym_key: SymKey,
+}
+
+impl FromStr for PairingUri {
+    type Err = Error;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidUri(uri.to_string());
+
+        let rest = uri.trim().strip_prefix("wc:").ok_or_else(invalid)?;
+        let (topic, rest) = rest.split_once('@').ok_or_else(invalid)?;
+        let (version, query) = rest.split_once('?').ok_or_else(invalid)?;
+
+        // v1 URIs point at a bridge server instead of a relay, and are no longer supported
+        if version != "2" || topic.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut sym_key = None;
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "symKey" => sym_key = hex::decode(value.as_ref()).ok(),
+                "relay-protocol" if value != "irn" => return Err(invalid()),
+                _ => (),
+            }
+        }
+
+        Ok(Self {
+            topic: topic.to_string(),
+            sym_key: sym_key
+                .and_then(|key| key.try_into().ok())
+                .ok_or_else(invalid)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v2_uris() {
+        let uri = "wc:7f6e504bfad60b485450578e05678ed3e8e8c4751d3c6160be17160d63ec90f9@2?relay-protocol=irn&symKey=587d5484ce2a2a6ee3ba1962fdd7e8588e06200c46823bd18fbd67def96ad303";
+        let pairing = PairingUri::from_str(uri).unwrap();
+
+        assert_eq!(
+            pairing.topic,
+            "7f6e504bfad60b485450578e05678ed3e8e8c4751d3c6160be17160d63ec90f9"
+        );
+        assert_eq!(pairing.sym_key[0], 0x58);
+
+        assert!(PairingUri::from_str("wc:abc@1?bridge=https%3A%2F%2Fbridge&key=00").is_err());
+        assert!(PairingUri::from_str("wc:abc@2?relay-protocol=irn&symKey=1234").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+//! Locks every wallet once the user has been away for a while, as configured in the settings
+//! While locked, signing requests are turned down, until the user unlocks with a password
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use iron_settings::Settings;
+use iron_types::{GlobalState, UINotify};
+use once_cell::sync::Lazy;
+
+use super::{Error, Result, Wallets};
+
+/// How often inactivity is checked for
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// When the user last interacted with the app
+static LAST_ACTIVITY: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Records user interaction, pushing back the auto-lock
+/// Activity doesn't count while locked, so that only unlocking resumes operation
+pub fn touch() {
+    if !is_locked() {
+        *LAST_ACTIVITY.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+}
+
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::SeqCst)
+}
+
+/// Fails with `Locked` while the app is locked, so requests are turned down before any signer is
+/// built, or any dialog shown
+pub fn ensure_unlocked() -> Result<()> {
+    match is_locked() {
+        true => Err(Error::Locked),
+        false => Ok(()),
+    }
+}
+
+/// Locks the app right away, dropping every decrypted secret
+pub async fn lock() {
+    LOCKED.store(true, Ordering::SeqCst);
+
+    // secrets zeroize their memory once dropped
+    let _ = Wallets::read().await.lock(None).await;
+    iron_broadcast::ui_notify(UINotify::LockChanged).await;
+}
+
+/// Unlocks the app with the password of any password-protected wallet
+/// Wallets it decrypts are unlocked right away. A password no wallet needs is never accepted,
+/// even if the keychain unlocks every wallet by itself
+pub async fn unlock(password: &str) -> Result<()> {
+    Wallets::read().await.unlock_all(password).await?;
+
+    LOCKED.store(false, Ordering::SeqCst);
+    touch();
+    iron_broadcast::ui_notify(UINotify::LockChanged).await;
+
+    Ok(())
+}
+
+pub(crate) fn spawn() {
+    tokio::spawn(async { watch().await });
+}
+
+async fn watch() -> ! {
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+
+        let Some(minutes) = Settings::read().await.get().auto_lock_minutes else {
+            continue;
+        };
+
+        if !is_locked() && idle_for() >= Duration::from_secs(minutes as u64 * 60) {
+            tracing::info!("locking after {} minutes of inactivity", minutes);
+            lock().await;
+        }
+    }
+}
+
+fn idle_for() -> Duration {
+    LAST_ACTIVITY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .elapsed()
+}
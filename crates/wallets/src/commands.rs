@@ -1,6 +1,9 @@
-use iron_types::{ChecksummedAddress, GlobalState, Json};
+use std::{collections::HashMap, path::PathBuf};
 
-use super::{utils, Result, Wallet, WalletControl, Wallets};
+use ethers::types::Address;
+use iron_types::{ChecksummedAddress, GlobalState, Json, UINotify};
+
+use super::{auto_lock, utils, DerivedAccount, Result, Wallet, WalletControl, Wallets};
 
 /// Lists all wallets
 #[tauri::command]
@@ -34,6 +37,90 @@ pub async fn wallets_update(name: String, params: Json) -> Result<()> {
     Wallets::write().await.update(name, params).await
 }
 
+/// Renames a wallet. Names must be unique
+#[tauri::command]
+pub async fn wallets_rename(name: String, new_name: String) -> Result<()> {
+    Wallets::write().await.rename(name, new_name).await
+}
+
+/// Reveals a wallet's mnemonic, for backing it up
+#[tauri::command]
+pub async fn wallets_export_mnemonic(name: String) -> Result<String> {
+    Wallets::export_mnemonic(name).await
+}
+
+/// Exports one of a wallet's accounts as an encrypted keystore file in `dir`
+/// Returns the path of the new file
+#[tauri::command]
+pub async fn wallets_export_keystore(
+    name: String,
+    address: Address,
+    password: String,
+    dir: PathBuf,
+) -> Result<PathBuf> {
+    Wallets::read()
+        .await
+        .export_keystore(name, address, dir, password)
+        .await
+}
+
+/// Keeps a wallet's password in the OS keychain, so unlocking it needs no prompt
+/// Enabling it needs the current password, unless the wallet is a plaintext one being migrated.
+/// Disabling it needs the password to re-encrypt the wallet under
+#[tauri::command]
+pub async fn wallets_set_keychain(
+    name: String,
+    enabled: bool,
+    password: Option<String>,
+) -> Result<()> {
+    Wallets::write()
+        .await
+        .set_keychain(name, enabled, password)
+        .await
+}
+
+/// Unlocks a password-protected wallet ahead of time, instead of on its first signature
+#[tauri::command]
+pub async fn wallets_unlock(name: String) -> Result<()> {
+    Wallets::read().await.unlock(name).await
+}
+
+/// Locks a wallet, or all of them, so its password is needed again
+#[tauri::command]
+pub async fn wallets_lock(name: Option<String>) -> Result<()> {
+    Wallets::read().await.lock(name).await
+}
+
+/// Records user interaction, pushing back the auto-lock
+#[tauri::command]
+pub async fn wallets_touch() {
+    auto_lock::touch()
+}
+
+/// Whether the app was locked after a period without user interaction
+#[tauri::command]
+pub async fn wallets_is_locked() -> bool {
+    auto_lock::is_locked()
+}
+
+/// Resumes operation after the app was locked, with the password of any of the wallets
+#[tauri::command]
+pub async fn wallets_unlock_all(password: String) -> Result<()> {
+    auto_lock::unlock(&password).await
+}
+
+/// Re-encrypts a wallet's secret under a new password
+#[tauri::command]
+pub async fn wallets_change_password(
+    name: String,
+    old_password: String,
+    new_password: String,
+) -> Result<()> {
+    Wallets::write()
+        .await
+        .change_password(name, &old_password, &new_password)
+}
+
 #[tauri::command]
 pub async fn wallets_remove(name: String) -> Result<()> {
     Wallets::write().await.remove(name).await
@@ -51,12 +138,57 @@ pub async fn wallets_set_current_path(key: String) -> Result<()> {
     Wallets::write().await.set_current_path(key).await
 }
 
+/// Switches to the account at a given index of the current wallet
+#[tauri::command]
+pub async fn wallets_set_current_index(idx: usize) -> Result<()> {
+    Wallets::write().await.set_current_index(idx).await
+}
+
+/// Adds the accounts of a wallet that were used on the current network
+/// Returns the wallet's new account count
+#[tauri::command]
+pub async fn wallets_discover_accounts(name: String) -> Result<u32> {
+    Wallets::discover_accounts(name).await
+}
+
+/// Previews the accounts a wallet would have under another base derivation path, with their
+/// balances on the current network
+#[tauri::command]
+pub async fn wallets_preview_derivation(
+    name: String,
+    derivation_path: String,
+    count: u32,
+) -> Result<Vec<DerivedAccount>> {
+    Wallets::preview_derivation(name, derivation_path, count).await
+}
+
+/// Switches a wallet to another base derivation path, and to the account at `idx` within it
+#[tauri::command]
+pub async fn wallets_set_derivation(name: String, derivation_path: String, idx: u32) -> Result<()> {
+    Wallets::set_derivation(name, derivation_path, idx).await
+}
+
+/// Lists the names given to accounts
+#[tauri::command]
+pub async fn wallets_get_labels() -> HashMap<ChecksummedAddress, String> {
+    Wallets::read().await.get_labels().clone()
+}
+
+/// Names an account. An empty label removes it
+#[tauri::command]
+pub async fn wallets_set_label(address: ChecksummedAddress, label: String) -> Result<()> {
+    Wallets::write().await.set_label(address, label)?;
+    iron_broadcast::ui_notify(UINotify::WalletsChanged).await;
+
+    Ok(())
+}
+
 /// Get all known addresses of a wallet
 #[tauri::command]
 pub async fn wallets_get_wallet_addresses(
     name: String,
 ) -> Result<Vec<(String, ChecksummedAddress)>> {
-    Ok(Wallets::read().await.get_wallet_addresses(name).await)
+    Wallets::read().await.get_wallet_addresses(name).await
 }
 
 /// Derives the list of addresses for a given mnemonic Used when importing a new wallet in the UI,
@@ -65,6 +197,27 @@ pub async fn wallets_get_wallet_addresses(
 pub async fn wallets_get_mnemonic_addresses(
     mnemonic: String,
     derivation_path: String,
-) -> Vec<(String, ChecksummedAddress)> {
+) -> Result<Vec<(String, ChecksummedAddress)>> {
+    utils::validate_mnemonic(&mnemonic)?;
+    utils::validate_derivation_path(&derivation_path, 0)?;
+
     utils::derive_addresses(&mnemonic, &derivation_path, 5)
 }
+
+/// Checks a mnemonic and derivation path before they're used to create or update a wallet
+#[tauri::command]
+pub async fn wallets_validate(mnemonic: String, derivation_path: String, idx: u32) -> Result<()> {
+    Wallet::validate(&mnemonic, &derivation_path, idx)
+}
+
+/// Checks whether a mnemonic derives the expected address, without creating any wallet
+/// Used to confirm a user's backup of their seed
+#[tauri::command]
+pub async fn wallets_verify_mnemonic(
+    mnemonic: String,
+    derivation_path: String,
+    idx: u32,
+    expected: Address,
+) -> Result<bool> {
+    utils::verify_mnemonic(&mnemonic, &derivation_path, idx, expected)
+}
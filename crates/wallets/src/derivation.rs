@@ -0,0 +1,103 @@
+use ethers::types::U256;
+use iron_types::ChecksummedAddress;
+use serde::Serialize;
+
+/// Well-known base derivation paths, so users don't have to know the right one for their device
+///
+/// Base paths are stored as plain strings, with each account's index appended as a last segment.
+/// Ledger Live puts the index in the account segment instead, which its base path marks with `x`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DerivationPreset {
+    /// `m/44'/60'/0'/0/{idx}`, also used by most software wallets
+    #[default]
+    MetaMask,
+
+    /// `m/44'/60'/{idx}'/0/0`
+    LedgerLive,
+
+    /// `m/44'/60'/0'/{idx}`, used by older Ledger apps and MyEtherWallet
+    LedgerLegacy,
+
+    Custom(String),
+}
+
+/// An account a derivation path would give, for previewing it before switching to that path
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DerivedAccount {
+    pub path: String,
+    pub address: ChecksummedAddress,
+
+    /// On the current network. `None` if it couldn't be fetched
+    pub balance: Option<U256>,
+}
+
+const METAMASK: &str = "m/44'/60'/0'/0";
+const LEDGER_LIVE: &str = "m/44'/60'/x'/0/0";
+const LEDGER_LEGACY: &str = "m/44'/60'/0'";
+
+impl DerivationPreset {
+    /// The base derivation path, as kept in wallet settings
+    pub fn path(&self) -> String {
+        match self {
+            Self::MetaMask => METAMASK.into(),
+            Self::LedgerLive => LEDGER_LIVE.into(),
+            Self::LedgerLegacy => LEDGER_LEGACY.into(),
+            Self::Custom(path) => path.clone(),
+        }
+    }
+
+    /// The full derivation path of the account at `idx`
+    pub fn account_path(&self, idx: u32) -> String {
+        match self {
+            Self::LedgerLive => format!("m/44'/60'/{}'/0/0", idx),
+            preset => format!("{}/{}", preset.path(), idx),
+        }
+    }
+}
+
+impl From<&str> for DerivationPreset {
+    fn from(path: &str) -> Self {
+        match path {
+            METAMASK => Self::MetaMask,
+            LEDGER_LIVE => Self::LedgerLive,
+            LEDGER_LEGACY => Self::LedgerLegacy,
+            path => Self::Custom(path.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_round_trip_through_base_paths() {
+        for preset in [
+            DerivationPreset::MetaMask,
+            DerivationPreset::LedgerLive,
+            DerivationPreset::LedgerLegacy,
+            DerivationPreset::Custom("m/44'/60'/1'/0".into()),
+        ] {
+            assert_eq!(DerivationPreset::from(preset.path().as_str()), preset);
+        }
+
+        assert_eq!(DerivationPreset::default().path(), "m/44'/60'/0'/0");
+    }
+
+    #[test]
+    fn account_paths_place_the_index() {
+        assert_eq!(
+            DerivationPreset::MetaMask.account_path(2),
+            "m/44'/60'/0'/0/2"
+        );
+        assert_eq!(
+            DerivationPreset::LedgerLive.account_path(2),
+            "m/44'/60'/2'/0/0"
+        );
+        assert_eq!(
+            DerivationPreset::LedgerLegacy.account_path(2),
+            "m/44'/60'/0'/2"
+        );
+    }
+}
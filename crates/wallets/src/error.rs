@@ -6,9 +6,15 @@ pub enum Error {
     #[error("duplicate wallet names `{0}`")]
     DuplicateWalletNames(String),
 
+    #[error("invalid wallet name `{0}`")]
+    InvalidWalletName(String),
+
     #[error("invalid wallet index {0}")]
     InvalidWallet(usize),
 
+    #[error("invalid account index {0}")]
+    InvalidAccountIndex(usize),
+
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
 
@@ -18,6 +24,12 @@ pub enum Error {
     #[error(transparent)]
     SignerError(#[from] ethers::signers::WalletError),
 
+    #[error(transparent)]
+    Signing(#[from] crate::WalletSignerError),
+
+    #[error("Ledger error: {0}")]
+    Ledger(#[from] ethers::signers::LedgerError),
+
     #[error("wallet unlock rejected by user")]
     UnlockDialogRejected,
 
@@ -30,15 +42,69 @@ pub enum Error {
     #[error(transparent)]
     Dialog(#[from] iron_dialogs::Error),
 
+    #[error("wallet locked")]
+    Locked,
+
+    #[error("wrong password")]
+    WrongPassword,
+
+    #[error("failed to encrypt secret")]
+    EncryptionFailed,
+
+    #[error("this wallet type is not password-protected")]
+    NoPassword,
+
+    #[error("a password is required, unless this is a dev account")]
+    PasswordRequired,
+
+    #[error("keychain error: {0}")]
+    Keychain(#[from] keyring::Error),
+
     #[error("unknown wallet key: {0}")]
     InvalidKey(String),
 
+    #[error("unknown wallet: {0}")]
+    UnknownWallet(String),
+
+    #[error("this wallet type has no mnemonic")]
+    NoMnemonic,
+
     #[error("invalid wallet type: {0}")]
     InvalidWalletType(String),
 
+    #[error("invalid mnemonic")]
+    InvalidMnemonic,
+
+    #[error("invalid private key")]
+    InvalidPrivateKey,
+
+    #[error("not a valid keystore file: {0}")]
+    InvalidKeystore(std::path::PathBuf),
+
+    #[error("this wallet's keys cannot be exported")]
+    NotExportable,
+
+    #[error("invalid derivation path: {0}")]
+    InvalidDerivationPath(String),
+
     #[error("This wallet type cannot sign")]
     WalletCantSign,
 
+    #[error("{0} is a watch-only account, and cannot sign")]
+    WatchOnly(String),
+
+    #[error("watch-only wallets need at least one address")]
+    NoWatchedAddresses,
+
+    #[error("wallets need at least one account")]
+    NoAccounts,
+
+    #[error(transparent)]
+    Provider(#[from] ethers::providers::ProviderError),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
+
     #[error(transparent)]
     ParseInto(#[from] std::num::ParseIntError),
 }
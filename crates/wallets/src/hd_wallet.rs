@@ -2,19 +2,24 @@ use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use ethers::{
-    core::k256::ecdsa::SigningKey,
-    signers::{self, coins_bip39::English, MnemonicBuilder, Signer},
+    providers::Provider,
+    signers::{coins_bip39::English, MnemonicBuilder, Signer},
 };
 use iron_crypto::{self, EncryptedData};
 use iron_dialogs::{Dialog, DialogMsg};
+use iron_networks::Failover;
 use iron_types::ChecksummedAddress;
 use secrets::SecretVec;
 use tokio::{
     sync::{Mutex, RwLock},
     task::JoinHandle,
 };
+use zeroize::Zeroize;
 
-use super::{utils, wallet::WalletCreate, Error, Result, Wallet, WalletControl};
+use super::{
+    keychain, signer_cache, utils, wallet::WalletCreate, DerivationPreset, Error, Result, Wallet,
+    WalletControl, WalletSigner,
+};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -26,6 +31,10 @@ pub struct HDWallet {
     addresses: Vec<(String, ChecksummedAddress)>,
     ciphertext: EncryptedData<String>,
 
+    /// The ID of this wallet's OS keychain entry, when its password is kept there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keychain: Option<String>,
+
     /// The signer is cached inside a `RwLock` so we can have interior mutability
     /// Since JSON keystore signers are time-consuming to decrypt, we can't do it on-the-fly for
     /// every incoming signing request
@@ -58,6 +67,10 @@ impl WalletControl for HDWallet {
         self.name.clone()
     }
 
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
         if let Some(name) = params["name"].as_str() {
             self.name = name.into();
@@ -91,23 +104,29 @@ impl WalletControl for HDWallet {
         Ok(())
     }
 
-    async fn build_signer(&self, chain_id: u32, path: &str) -> Result<signers::Wallet<SigningKey>> {
+    async fn build_signer(&self, chain_id: u32, path: &str) -> Result<WalletSigner> {
         if !self.addresses.iter().any(|(p, _)| p == path) {
             return Err(Error::InvalidKey(path.to_string()));
         }
 
         self.unlock().await?;
 
-        let secret = self.secret.read().await;
-        let secret = secret.as_ref().unwrap().lock().await;
+        if let Some(signer) = signer_cache::get(&self.name, path, chain_id) {
+            return Ok(signer.into());
+        }
 
-        let mnemonic = mnemonic_from_secret(&secret);
+        let mnemonic = {
+            // the secret may have expired since unlocking
+            let secret = self.secret.read().await;
+            let secret = secret.as_ref().ok_or(Error::Locked)?.lock().await;
+            mnemonic_from_secret(&secret)
+        };
         let signer = MnemonicBuilder::<English>::default()
             .phrase(mnemonic.as_str())
             .derivation_path(path)?
             .build()?;
 
-        Ok(signer.with_chain_id(chain_id))
+        Ok(signer_cache::insert(&self.name, path, signer.with_chain_id(chain_id)).into())
     }
 
     async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)> {
@@ -117,8 +136,10 @@ impl WalletControl for HDWallet {
 
 impl HDWallet {
     pub async fn from_params(params: HDWalletParams) -> Result<Self> {
+        Wallet::validate(&params.mnemonic, &params.derivation_path, 0)?;
+
         let addresses =
-            utils::derive_addresses(&params.mnemonic, &params.derivation_path, params.count);
+            utils::derive_addresses(&params.mnemonic, &params.derivation_path, params.count)?;
 
         // use given `current`, but only after ensuring it is part of the derived list of addresses
         let current = if let Some(current) = addresses.iter().find(|(p, _)| p == &params.current) {
@@ -136,12 +157,185 @@ impl HDWallet {
             count: params.count,
             ciphertext,
             addresses,
+            keychain: None,
             secret: Default::default(),
             expirer: Default::default(),
         })
     }
 
+    /// Reveals the mnemonic, asking for the wallet's password if it is locked
+    pub async fn export_mnemonic(&self) -> Result<String> {
+        self.unlock().await?;
+
+        let secret = self.secret.read().await;
+        let secret = secret.as_ref().ok_or(Error::Locked)?.lock().await;
+
+        tracing::info!(wallet = %self.name, "exporting mnemonic");
+        Ok(mnemonic_from_secret(&secret))
+    }
+
+    /// Encrypts a mnemonic under a random password, which is only ever stored in the OS keychain
+    /// This is how plaintext wallets are migrated off of storing their mnemonic in the clear
+    pub(crate) async fn with_keychain(
+        name: String,
+        mnemonic: String,
+        derivation_path: String,
+        count: u32,
+        current: String,
+    ) -> Result<Self> {
+        let mut password = keychain::generate_password();
+        let wallet = Self::from_params(HDWalletParams {
+            mnemonic,
+            derivation_path,
+            current,
+            password: password.clone(),
+            name,
+            count,
+        })
+        .await;
+
+        let res = wallet.and_then(|mut wallet| {
+            let id = keychain::new_id();
+            keychain::set(&id, &password)?;
+            wallet.keychain = Some(id);
+            Ok(wallet)
+        });
+        password.zeroize();
+        res
+    }
+
+    /// Re-encrypts the mnemonic under a new password
+    pub fn change_password(&mut self, old_password: &str, new_password: &str) -> Result<()> {
+        let mut mnemonic: String = iron_crypto::decrypt(&self.ciphertext, old_password)
+            .map_err(|_| Error::WrongPassword)?;
+        let ciphertext = iron_crypto::encrypt(&mnemonic, new_password);
+        mnemonic.zeroize();
+
+        self.ciphertext = ciphertext.map_err(|_| Error::EncryptionFailed)?;
+        if let Some(ref id) = self.keychain {
+            keychain::set(id, new_password)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the password in the OS keychain, once it's been checked against the mnemonic
+    pub fn enable_keychain(&mut self, password: &str) -> Result<()> {
+        let mut mnemonic: String =
+            iron_crypto::decrypt(&self.ciphertext, password).map_err(|_| Error::WrongPassword)?;
+        mnemonic.zeroize();
+
+        let id = self.keychain.clone().unwrap_or_else(keychain::new_id);
+        keychain::set(&id, password)?;
+        self.keychain = Some(id);
+        Ok(())
+    }
+
+    /// Re-encrypts the mnemonic under a password of the user's choosing, and removes the keychain
+    /// entry
+    /// The keychain's password may have been generated on migration, so it can't simply be kept
+    pub fn disable_keychain(&mut self, new_password: &str) -> Result<()> {
+        let Some(id) = self.keychain.clone() else {
+            return Ok(());
+        };
+
+        let mut old_password = keychain::get(&id)?;
+        let res = self.change_password(&old_password, new_password);
+        old_password.zeroize();
+        res?;
+
+        keychain::delete(&id)?;
+        self.keychain = None;
+        Ok(())
+    }
+
+    /// A copy for backups, with a keychain-kept password swapped for `password`
+    /// The keychain's entry doesn't travel with the backup, and its password may be one the user
+    /// never saw
+    pub fn for_backup(&self, password: &str) -> Result<Self> {
+        let Some(ref id) = self.keychain else {
+            return Ok(self.clone());
+        };
+
+        let mut old_password = keychain::get(id)?;
+        let mnemonic = iron_crypto::decrypt(&self.ciphertext, &old_password);
+        old_password.zeroize();
+        let mut mnemonic: String = mnemonic.map_err(|_| Error::WrongPassword)?;
+        let ciphertext = iron_crypto::encrypt(&mnemonic, password);
+        mnemonic.zeroize();
+
+        Ok(Self {
+            ciphertext: ciphertext.map_err(|_| Error::EncryptionFailed)?,
+            keychain: None,
+            ..self.clone()
+        })
+    }
+
+    pub fn uses_keychain(&self) -> bool {
+        self.keychain.is_some()
+    }
+
+    pub fn forget_keychain(&self) {
+        if let Some(ref id) = self.keychain {
+            if let Err(e) = keychain::delete(id) {
+                tracing::warn!(wallet = %self.name, "failed to remove keychain entry: {}", e);
+            }
+        }
+    }
+
+    /// Counts the accounts that were used on-chain
+    pub async fn count_used_accounts(&self, provider: &Provider<Failover>) -> Result<u32> {
+        self.unlock().await?;
+
+        let mnemonic = {
+            let secret = self.secret.read().await;
+            let secret = secret.as_ref().ok_or(Error::Locked)?.lock().await;
+            mnemonic_from_secret(&secret)
+        };
+
+        utils::discover_used_accounts(&mnemonic, &self.derivation_path, provider).await
+    }
+
+    /// Grows the account list to at least `count` accounts
+    /// The list never shrinks, so accounts added by hand are kept. Returns the new account count
+    pub async fn grow_accounts(&mut self, count: u32) -> Result<u32> {
+        if count > self.count {
+            self.update_count(count).await?;
+        }
+
+        Ok(self.count)
+    }
+
+    /// The first `count` accounts another base derivation path would give, without switching to
+    /// it
+    pub async fn preview_derivation(
+        &self,
+        derivation_path: &str,
+        count: u32,
+    ) -> Result<Vec<(String, ChecksummedAddress)>> {
+        utils::validate_derivation_path(derivation_path, 0)?;
+        self.unlock().await?;
+
+        let mnemonic = {
+            let secret = self.secret.read().await;
+            let secret = secret.as_ref().ok_or(Error::Locked)?.lock().await;
+            mnemonic_from_secret(&secret)
+        };
+
+        utils::derive_addresses(&mnemonic, derivation_path, count)
+    }
+
+    /// Switches to another base derivation path, and to the account at `idx` within it
+    pub async fn set_derivation(&mut self, derivation_path: String, idx: u32) -> Result<()> {
+        utils::validate_derivation_path(&derivation_path, idx)?;
+        self.count = self.count.max(idx + 1);
+        self.update_derivation_path(derivation_path).await?;
+
+        let path = DerivationPreset::from(self.derivation_path.as_str()).account_path(idx);
+        self.set_current_path(path).await
+    }
+
     async fn update_derivation_path(&mut self, derivation_path: String) -> Result<()> {
+        utils::validate_derivation_path(&derivation_path, 0)?;
         self.derivation_path = derivation_path;
 
         self.update_derived_addresses().await?;
@@ -150,6 +344,9 @@ impl HDWallet {
     }
 
     async fn update_count(&mut self, count: u32) -> Result<()> {
+        if count == 0 {
+            return Err(Error::NoAccounts);
+        }
         self.count = count;
 
         self.update_derived_addresses().await?;
@@ -161,12 +358,16 @@ impl HDWallet {
         self.unlock().await?;
 
         let secret = self.secret.read().await;
-        let secret = secret.as_ref().unwrap().lock().await;
+        let secret = secret.as_ref().ok_or(Error::Locked)?.lock().await;
         let mnemonic = mnemonic_from_secret(&secret);
 
-        let addresses = utils::derive_addresses(&mnemonic, &self.derivation_path, self.count);
-        // TODO check if current address is still part of the list, instead of hardcoding a new current
-        let current = addresses.first().unwrap().clone();
+        let addresses = utils::derive_addresses(&mnemonic, &self.derivation_path, self.count)?;
+        // keep the current account if it's still part of the list
+        let current = addresses
+            .iter()
+            .find(|(path, _)| *path == self.current.0)
+            .unwrap_or_else(|| addresses.first().unwrap())
+            .clone();
 
         self.current = current;
         self.addresses = addresses;
@@ -179,12 +380,26 @@ impl HDWallet {
         secret.is_some()
     }
 
-    async fn unlock(&self) -> Result<()> {
+    /// Forgets the decrypted secret, so the password is asked for again on next use
+    pub async fn lock(&self) {
+        if let Some(expirer) = self.expirer.write().await.take() {
+            expirer.abort();
+        }
+        self.secret.write().await.take();
+        signer_cache::evict(&self.name);
+    }
+
+    pub async fn unlock(&self) -> Result<()> {
         // if we already have a signer, then we're good
         if self.is_unlocked().await {
             return Ok(());
         }
 
+        // the keychain may be locked or unavailable, in which case the password is asked for
+        if self.unlock_from_keychain().await {
+            return Ok(());
+        }
+
         // open the dialog
         let dialog = Dialog::new("wallet-unlock", serde_json::to_value(self).unwrap());
         dialog.open().await?;
@@ -216,6 +431,41 @@ impl HDWallet {
         Err(Error::UnlockDialogFailed)
     }
 
+    /// Decrypts the mnemonic with a password given up front, instead of asking for one
+    /// The keychain is deliberately left out, so success means the password is right
+    pub async fn unlock_with(&self, password: &str) -> Result<()> {
+        let mnemonic =
+            iron_crypto::decrypt(&self.ciphertext, password).map_err(|_| Error::WrongPassword)?;
+        self.store_secret(mnemonic).await;
+
+        Ok(())
+    }
+
+    /// Whether the keychain held this wallet's password, and it decrypted the mnemonic
+    pub async fn unlock_from_keychain(&self) -> bool {
+        let Some(ref id) = self.keychain else {
+            return false;
+        };
+
+        match keychain::get(id) {
+            Ok(mut password) => {
+                let mnemonic = iron_crypto::decrypt(&self.ciphertext, &password);
+                password.zeroize();
+                match mnemonic {
+                    Ok(mnemonic) => {
+                        self.store_secret(mnemonic).await;
+                        true
+                    }
+                    Err(_) => false,
+                }
+            }
+            Err(e) => {
+                tracing::warn!(wallet = %self.name, "keychain unavailable: {}", e);
+                false
+            }
+        }
+    }
+
     async fn store_secret(&self, mnemonic: String) {
         // acquire both write locks
         let mut expirer_handle = self.expirer.write().await;
@@ -227,14 +477,16 @@ impl HDWallet {
 
         // set up cache expiration for 1 minute
         let clone = Arc::clone(&self.secret);
+        let name = self.name.clone();
         *expirer_handle = Some(tokio::spawn(async move {
             tokio::time::sleep(Duration::from_secs(60)).await;
             clone.write().await.take();
+            signer_cache::evict(&name);
         }));
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct HDWalletParams {
     mnemonic: String,
@@ -245,6 +497,20 @@ pub struct HDWalletParams {
     count: u32,
 }
 
+// the mnemonic and password are deliberately left out
+impl std::fmt::Debug for HDWalletParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HDWalletParams")
+            .field("mnemonic", &"***")
+            .field("derivation_path", &self.derivation_path)
+            .field("current", &self.current)
+            .field("password", &"***")
+            .field("name", &self.name)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
 /// Converts a signer into a SecretVec
 pub fn mnemonic_into_secret(mnemonic: String) -> SecretVec<u8> {
     let signer_bytes = mnemonic.into_bytes();
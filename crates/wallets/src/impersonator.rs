@@ -1,11 +1,10 @@
 use std::str::FromStr;
 
 use async_trait::async_trait;
-use ethers::core::k256::ecdsa::SigningKey;
 use iron_types::ChecksummedAddress;
 use serde::{Deserialize, Serialize};
 
-use crate::{wallet::WalletCreate, Result, Wallet, WalletControl};
+use crate::{wallet::WalletCreate, Result, Wallet, WalletControl, WalletSigner};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Impersonator {
@@ -30,6 +29,10 @@ impl WalletControl for Impersonator {
         self.name.clone()
     }
 
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
         if let Some(name) = params["name"].as_str() {
             self.name = name.into();
@@ -65,11 +68,7 @@ impl WalletControl for Impersonator {
         self.addresses.iter().map(|v| (v.to_string(), *v)).collect()
     }
 
-    async fn build_signer(
-        &self,
-        _chain_id: u32,
-        _path: &str,
-    ) -> Result<ethers::signers::Wallet<SigningKey>> {
+    async fn build_signer(&self, _chain_id: u32, _path: &str) -> Result<WalletSigner> {
         Err(crate::Error::WalletCantSign)
     }
 }
@@ -1,16 +1,17 @@
 use std::{
+    collections::HashMap,
     fs::File,
     io::BufReader,
     path::{Path, PathBuf},
 };
 
 use async_trait::async_trait;
-use iron_types::GlobalState;
+use iron_types::{ChecksummedAddress, GlobalState};
 use once_cell::sync::OnceCell;
 use serde::Deserialize;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use super::{Wallet, Wallets};
+use super::{auto_lock, Wallet, Wallets};
 
 static WALLETS: OnceCell<RwLock<Wallets>> = OnceCell::new();
 
@@ -22,6 +23,8 @@ pub async fn init(pathbuf: PathBuf) {
         wallets: Vec<Wallet>,
         #[serde(default)]
         current: usize,
+        #[serde(default)]
+        labels: HashMap<ChecksummedAddress, String>,
     }
 
     let mut res: Wallets = if path.exists() {
@@ -33,12 +36,14 @@ pub async fn init(pathbuf: PathBuf) {
         Wallets {
             wallets: res.wallets,
             current: res.current,
+            labels: res.labels,
             file: Some(pathbuf),
         }
     } else {
         Wallets {
             wallets: Default::default(),
             current: 0,
+            labels: Default::default(),
             file: Some(pathbuf),
         }
     };
@@ -47,6 +52,8 @@ pub async fn init(pathbuf: PathBuf) {
     res.init_broadcast().await;
 
     WALLETS.set(RwLock::new(res)).unwrap();
+
+    auto_lock::spawn();
 }
 
 #[async_trait]
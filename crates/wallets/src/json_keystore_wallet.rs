@@ -1,4 +1,11 @@
-use std::{fs::File, io::BufReader, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use ethers::{
@@ -13,13 +20,17 @@ use tokio::{
     task::JoinHandle,
 };
 
-use super::{wallet::WalletCreate, Error, Result, Wallet, WalletControl};
+use super::{wallet::WalletCreate, Error, Result, Wallet, WalletControl, WalletSigner};
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct JsonKeystoreWallet {
     name: String,
     pub file: PathBuf,
 
+    /// Learnt by decrypting the keystore on import, since not every keystore lists its address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<ChecksummedAddress>,
+
     /// The signer is cached inside a `RwLock` so we can have interior mutability
     /// Since JSON keystore signers are time-consuming to decrypt, we can't do it on-the-fly for
     /// every incoming signing request
@@ -37,10 +48,45 @@ pub struct JsonKeystoreWallet {
     expirer: Arc<RwLock<Option<JoinHandle<()>>>>,
 }
 
+#[derive(serde::Deserialize)]
+struct JsonKeystoreWalletParams {
+    name: String,
+    file: PathBuf,
+    password: Option<String>,
+}
+
 #[async_trait]
 impl WalletCreate for JsonKeystoreWallet {
+    /// Imports a Web3 Secret Storage (v3) keystore
+    /// With a password, the keystore is decrypted right away, which both checks the password and
+    /// finds the account's address. Otherwise the address must be listed in the file
     async fn create(params: serde_json::Value) -> Result<Wallet> {
-        Ok(Wallet::JsonKeystore(serde_json::from_value(params)?))
+        let params: JsonKeystoreWalletParams = serde_json::from_value(params)?;
+
+        let mut wallet = Self {
+            name: params.name,
+            file: params.file,
+            address: None,
+            secret: Default::default(),
+            expirer: Default::default(),
+        };
+
+        match params.password {
+            Some(password) => {
+                let keystore = signers::Wallet::decrypt_keystore(&wallet.file, password)
+                    .map_err(|_| Error::WrongPassword)?;
+                wallet.address = Some(keystore.address().into());
+                wallet.store_secret(&keystore).await;
+            }
+            None => {
+                wallet.address = Some(
+                    address_from_file(&wallet.file)
+                        .ok_or_else(|| Error::InvalidKeystore(wallet.file.clone()))?,
+                );
+            }
+        }
+
+        Ok(Wallet::JsonKeystore(wallet))
     }
 }
 
@@ -50,19 +96,19 @@ impl WalletControl for JsonKeystoreWallet {
         self.name.clone()
     }
 
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
         Ok(Wallet::JsonKeystore(serde_json::from_value(params)?))
     }
 
     async fn get_current_address(&self) -> ChecksummedAddress {
-        let file = File::open(self.file.clone()).unwrap();
-        let reader = BufReader::new(file);
-        let mut res: serde_json::Value = serde_json::from_reader(reader).unwrap();
-
-        // TODO: this should be fail correctly
-        let address: Address = Address::from_str(res["address"].take().as_str().unwrap()).unwrap();
-
-        address.into()
+        // wallets imported before addresses were stored only have the keystore's own field
+        self.address
+            .or_else(|| address_from_file(&self.file))
+            .unwrap_or_default()
     }
 
     fn get_current_path(&self) -> String {
@@ -73,18 +119,14 @@ impl WalletControl for JsonKeystoreWallet {
         Ok(())
     }
 
-    async fn build_signer(
-        &self,
-        chain_id: u32,
-        _path: &str,
-    ) -> Result<signers::Wallet<SigningKey>> {
+    async fn build_signer(&self, chain_id: u32, _path: &str) -> Result<WalletSigner> {
         self.unlock().await?;
 
         let secret = self.secret.read().await;
-        let secret = secret.as_ref().unwrap().lock().await;
+        let secret = secret.as_ref().ok_or(Error::Locked)?.lock().await;
 
         let signer = signer_from_secret(&secret);
-        Ok(signer.with_chain_id(chain_id))
+        Ok(signer.with_chain_id(chain_id).into())
     }
 
     async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)> {
@@ -98,7 +140,15 @@ impl JsonKeystoreWallet {
         secret.is_some()
     }
 
-    async fn unlock(&self) -> Result<()> {
+    /// Forgets the decrypted secret, so the password is asked for again on next use
+    pub async fn lock(&self) {
+        if let Some(expirer) = self.expirer.write().await.take() {
+            expirer.abort();
+        }
+        self.secret.write().await.take();
+    }
+
+    pub async fn unlock(&self) -> Result<()> {
         // if we already have a signer, then we're good
         if self.is_unlocked().await {
             return Ok(());
@@ -135,6 +185,15 @@ impl JsonKeystoreWallet {
         Err(Error::UnlockDialogFailed)
     }
 
+    /// Decrypts the keystore with a password given up front, instead of asking for one
+    pub async fn unlock_with(&self, password: &str) -> Result<()> {
+        let keystore = signers::Wallet::decrypt_keystore(self.file.clone(), password)
+            .map_err(|_| Error::WrongPassword)?;
+        self.store_secret(&keystore).await;
+
+        Ok(())
+    }
+
     async fn store_secret(&self, keystore: &signers::Wallet<SigningKey>) {
         // acquire both write locks
         let mut expirer_handle = self.expirer.write().await;
@@ -153,6 +212,15 @@ impl JsonKeystoreWallet {
     }
 }
 
+/// Reads the address a keystore lists, if any
+fn address_from_file(file: &Path) -> Option<ChecksummedAddress> {
+    let reader = BufReader::new(File::open(file).ok()?);
+    let json: serde_json::Value = serde_json::from_reader(reader).ok()?;
+    let address = json["address"].as_str()?;
+
+    Address::from_str(address).ok().map(Into::into)
+}
+
 /// Converts a signer into a SecretVec
 fn signer_into_secret(keystore: &signers::Wallet<SigningKey>) -> SecretVec<u8> {
     let signer_bytes = keystore.signer().to_bytes();
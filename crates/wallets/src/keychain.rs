@@ -0,0 +1,45 @@
+use ethers::{
+    core::rand::{thread_rng, RngCore},
+    utils::hex,
+};
+use keyring::Entry;
+
+use crate::Result;
+
+/// The service every entry is filed under
+const SERVICE: &str = "iron-wallet";
+
+/// Reads a wallet's password from the platform secret store: the macOS Keychain, the Windows
+/// Credential Manager, or the Secret Service (libsecret) on Linux
+/// Entries are keyed by an ID of their own rather than the wallet's name, so renames don't
+/// orphan them
+pub(crate) fn get(id: &str) -> Result<String> {
+    Ok(Entry::new(SERVICE, id)?.get_password()?)
+}
+
+pub(crate) fn set(id: &str, password: &str) -> Result<()> {
+    Ok(Entry::new(SERVICE, id)?.set_password(password)?)
+}
+
+/// Removing an entry that's already gone is not an error
+pub(crate) fn delete(id: &str) -> Result<()> {
+    match Entry::new(SERVICE, id)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub(crate) fn new_id() -> String {
+    format!("wallet-{}", random_hex(16))
+}
+
+/// A password for wallets that only the keychain ever knows, such as migrated plaintext ones
+pub(crate) fn generate_password() -> String {
+    random_hex(32)
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use ethers::signers::{HDPath, Ledger};
+use iron_types::ChecksummedAddress;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    utils, wallet::WalletCreate, DerivationPreset, Error, Result, Wallet, WalletControl,
+    WalletSigner,
+};
+
+/// A Ledger hardware wallet
+/// Keys never leave the device: only the derived addresses are kept here, and every signature
+/// has to be confirmed on the Ledger itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerWallet {
+    name: String,
+    derivation_path: String,
+    count: u32,
+    current: (String, ChecksummedAddress),
+    addresses: Vec<(String, ChecksummedAddress)>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LedgerWalletParams {
+    name: String,
+    #[serde(default = "default_derivation_path")]
+    derivation_path: String,
+    count: u32,
+}
+
+fn default_derivation_path() -> String {
+    DerivationPreset::LedgerLive.path()
+}
+
+#[async_trait]
+impl WalletCreate for LedgerWallet {
+    async fn create(params: serde_json::Value) -> Result<Wallet> {
+        let params: LedgerWalletParams = serde_json::from_value(params)?;
+        let addresses = derive_addresses(&params.derivation_path, params.count).await?;
+
+        Ok(Wallet::Ledger(Self {
+            name: params.name,
+            derivation_path: params.derivation_path,
+            count: params.count,
+            current: addresses[0].clone(),
+            addresses,
+        }))
+    }
+}
+
+#[async_trait]
+impl WalletControl for LedgerWallet {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
+        if let Some(name) = params["name"].as_str() {
+            self.name = name.into();
+        }
+
+        let path = params["derivationPath"].as_str();
+        let count = params["count"].as_u64();
+        if let Some(path) = path {
+            self.derivation_path = path.into();
+        }
+        if let Some(count) = count {
+            if count == 0 {
+                return Err(Error::NoAccounts);
+            }
+            self.count = count as u32;
+        }
+
+        if path.is_some() || count.is_some() {
+            self.addresses = derive_addresses(&self.derivation_path, self.count).await?;
+            self.current = self
+                .addresses
+                .iter()
+                .find(|(p, _)| *p == self.current.0)
+                .unwrap_or(&self.addresses[0])
+                .clone();
+        }
+
+        Ok(Wallet::Ledger(self))
+    }
+
+    async fn get_current_address(&self) -> ChecksummedAddress {
+        self.current.1
+    }
+
+    fn get_current_path(&self) -> String {
+        self.current.0.clone()
+    }
+
+    async fn set_current_path(&mut self, path: String) -> Result<()> {
+        self.current = self
+            .addresses
+            .iter()
+            .find(|(p, _)| *p == path)
+            .cloned()
+            .ok_or(Error::InvalidKey(path))?;
+
+        Ok(())
+    }
+
+    async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)> {
+        self.addresses.clone()
+    }
+
+    async fn build_signer(&self, chain_id: u32, path: &str) -> Result<WalletSigner> {
+        if !self.addresses.iter().any(|(p, _)| p == path) {
+            return Err(Error::InvalidKey(path.to_string()));
+        }
+
+        let ledger = Ledger::new(HDPath::Other(path.to_string()), chain_id.into()).await?;
+
+        Ok(ledger.into())
+    }
+}
+
+impl LedgerWallet {
+    /// Asks the device for the first `count` accounts of another base derivation path, without
+    /// switching to it
+    pub async fn preview_derivation(
+        &self,
+        derivation_path: &str,
+        count: u32,
+    ) -> Result<Vec<(String, ChecksummedAddress)>> {
+        derive_addresses(derivation_path, count).await
+    }
+
+    /// Switches to another base derivation path, and to the account at `idx` within it
+    pub async fn set_derivation(&mut self, derivation_path: String, idx: u32) -> Result<()> {
+        utils::validate_derivation_path(&derivation_path, idx)?;
+        let count = self.count.max(idx + 1);
+        self.addresses = derive_addresses(&derivation_path, count).await?;
+        self.derivation_path = derivation_path;
+        self.count = count;
+
+        let path = DerivationPreset::from(self.derivation_path.as_str()).account_path(idx);
+        self.set_current_path(path).await
+    }
+}
+
+/// Asks the connected device for the addresses of the first `count` accounts of a derivation path
+async fn derive_addresses(
+    derivation_path: &str,
+    count: u32,
+) -> Result<Vec<(String, ChecksummedAddress)>> {
+    utils::validate_derivation_path(derivation_path, 0)?;
+    if count == 0 {
+        return Err(Error::InvalidAccountIndex(0));
+    }
+
+    let preset = DerivationPreset::from(derivation_path);
+
+    let ledger = Ledger::new(HDPath::Other(preset.account_path(0)), 1).await?;
+
+    let mut addresses = Vec::with_capacity(count as usize);
+    for idx in 0..count {
+        let path = preset.account_path(idx);
+        let address = ledger
+            .get_address_with_path(&HDPath::Other(path.clone()))
+            .await?;
+        addresses.push((path, address.into()));
+    }
+
+    Ok(addresses)
+}
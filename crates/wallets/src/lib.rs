@@ -1,26 +1,43 @@
+pub mod auto_lock;
 pub mod commands;
+mod derivation;
 mod error;
 mod hd_wallet;
 mod impersonator;
 mod init;
 mod json_keystore_wallet;
+mod keychain;
+mod ledger;
 mod plaintext;
+mod private_key;
+mod signer;
+mod signer_cache;
 mod utils;
 mod wallet;
+mod watch_only;
 
 use std::{
-    collections::HashSet,
-    fs::File,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
+pub use derivation::{DerivationPreset, DerivedAccount};
 pub use error::{Error, Result};
+use ethers::types::Address;
 pub use init::init;
-use iron_types::{ChecksummedAddress, Json, UINotify};
+use iron_networks::Networks;
+use iron_types::{ChecksummedAddress, GlobalState, Json, UINotify};
 use serde::Serialize;
 
 use self::wallet::WalletCreate;
-pub use self::wallet::{Wallet, WalletControl};
+pub use self::{
+    signer::{WalletSigner, WalletSignerError},
+    utils::message_bytes,
+    wallet::{Wallet, WalletControl},
+};
+
+/// Upper bound on how many accounts a derivation path preview derives, and fetches balances for
+const MAX_DERIVATION_PREVIEW: u32 = 20;
 
 /// Maintains a list of Ethereum wallets, including keeping track of the global current wallet &
 /// address
@@ -31,6 +48,10 @@ pub struct Wallets {
     #[serde(default)]
     current: usize,
 
+    /// User-given names for individual accounts, e.g. "hot" or "deploy"
+    #[serde(default)]
+    labels: HashMap<ChecksummedAddress, String>,
+
     #[serde(skip)]
     file: Option<PathBuf>,
 }
@@ -52,10 +73,42 @@ impl Wallets {
         Ok(())
     }
 
+    /// Sets the current key to the account at a given index within the current wallet
+    async fn set_current_index(&mut self, idx: usize) -> Result<()> {
+        let path = self
+            .get_current_wallet()
+            .get_all_addresses()
+            .await
+            .into_iter()
+            .nth(idx)
+            .map(|(path, _)| path)
+            .ok_or(Error::InvalidAccountIndex(idx))?;
+
+        self.set_current_path(path).await
+    }
+
     async fn get_current_address(&self) -> ChecksummedAddress {
         self.get_current_wallet().get_current_address().await
     }
 
+    /// All accounts of the current wallet, with the current one first
+    /// This is the order dapps expect from `eth_accounts`
+    pub async fn get_current_accounts(&self) -> Vec<ChecksummedAddress> {
+        let wallet = self.get_current_wallet();
+        let current = wallet.get_current_address().await;
+
+        std::iter::once(current)
+            .chain(
+                wallet
+                    .get_all_addresses()
+                    .await
+                    .into_iter()
+                    .map(|(_, a)| a)
+                    .filter(|a| *a != current),
+            )
+            .collect()
+    }
+
     /// Switches the current default wallet
     async fn set_current_wallet(&mut self, id: usize) -> Result<()> {
         if id >= self.wallets.len() {
@@ -69,15 +122,24 @@ impl Wallets {
     }
 
     /// Retrieves all wallets
-    fn get_all(&self) -> &Vec<Wallet> {
+    pub fn get_all(&self) -> &Vec<Wallet> {
         &self.wallets
     }
 
+    /// Every wallet, ready to be written to a backup encrypted under `password`
+    /// Wallets whose password lives in the keychain are re-encrypted under `password` instead
+    pub fn for_backup(&self, password: &str) -> Result<Vec<Wallet>> {
+        self.wallets
+            .iter()
+            .map(|w| w.for_backup(password))
+            .collect()
+    }
+
     async fn create(&mut self, params: Json) -> Result<()> {
         let wallet = Wallet::create(params).await?;
+        self.ensure_unique_name(&wallet.name())?;
         let addresses = wallet.get_all_addresses().await;
 
-        // TODO: ensure no duplicates
         self.wallets.push(wallet);
 
         self.on_wallet_changed().await?;
@@ -90,12 +152,22 @@ impl Wallets {
         Ok(())
     }
 
-    async fn update(&mut self, name: String, params: Json) -> Result<()> {
-        // TODO: should fail if no wallet of that name exists
-        let i = self.wallets.iter().position(|w| w.name() == name).unwrap();
+    async fn update(&mut self, name: String, mut params: Json) -> Result<()> {
+        let i = self
+            .wallets
+            .iter()
+            .position(|w| w.name() == name)
+            .ok_or_else(|| Error::UnknownWallet(name.clone()))?;
+
+        // a new name is held to the same rules as a rename
+        if let Some(new_name) = params["name"].as_str() {
+            let new_name = self.validate_new_name(&name, new_name)?;
+            params["name"] = new_name.into();
+        }
 
         let before = self.wallets[i].get_all_addresses().await;
         self.wallets[i] = self.wallets[i].clone().update(params).await?;
+        signer_cache::evict(&name);
         let after = self.wallets[i].get_all_addresses().await;
 
         tokio::spawn(async move {
@@ -110,12 +182,54 @@ impl Wallets {
         });
 
         self.ensure_current();
+        self.prune_labels().await;
         self.notify_peers().await;
         self.on_wallet_changed().await?;
         self.save()?;
         Ok(())
     }
 
+    /// Renames a wallet, keeping names unique
+    async fn rename(&mut self, name: String, new_name: String) -> Result<()> {
+        let new_name = self.validate_new_name(&name, &new_name)?;
+        if new_name == name {
+            return Ok(());
+        }
+
+        self.wallets
+            .iter_mut()
+            .find(|w| w.name() == name)
+            .ok_or(Error::UnknownWallet(name.clone()))?
+            .set_name(new_name);
+        signer_cache::evict(&name);
+
+        iron_broadcast::ui_notify(UINotify::WalletsChanged).await;
+        self.save()
+    }
+
+    /// Errors if a wallet with the given name already exists
+    /// Trims a wallet's new name, which must be non-empty and not taken by another wallet
+    fn validate_new_name(&self, name: &str, new_name: &str) -> Result<String> {
+        let new_name = new_name.trim().to_string();
+        if new_name == name {
+            return Ok(new_name);
+        }
+        if new_name.is_empty() {
+            return Err(Error::InvalidWalletName(new_name));
+        }
+        self.ensure_unique_name(&new_name)?;
+
+        Ok(new_name)
+    }
+
+    fn ensure_unique_name(&self, name: &str) -> Result<()> {
+        if self.wallets.iter().any(|w| w.name() == name) {
+            return Err(Error::DuplicateWalletNames(name.to_string()));
+        }
+
+        Ok(())
+    }
+
     async fn remove(&mut self, name: String) -> Result<()> {
         let found = self
             .wallets
@@ -125,12 +239,15 @@ impl Wallets {
 
         if let Some((i, _)) = found {
             let removed = self.wallets.remove(i);
+            removed.forget_keychain();
+            signer_cache::evict(&name);
 
             for (_, a) in removed.get_all_addresses().await {
                 iron_broadcast::address_removed(a).await;
             }
 
             self.ensure_current();
+            self.prune_labels().await;
             self.on_wallet_changed().await?;
             self.save()?;
         }
@@ -138,11 +255,301 @@ impl Wallets {
         Ok(())
     }
 
+    /// Names an account, or clears its label if the given one is empty
+    pub fn set_label(&mut self, address: ChecksummedAddress, label: String) -> Result<()> {
+        let label = label.trim();
+
+        if label.is_empty() {
+            self.labels.remove(&address);
+        } else {
+            self.labels.insert(address, label.to_string());
+        }
+        self.save()
+    }
+
+    pub fn get_label(&self, address: &ChecksummedAddress) -> Option<String> {
+        self.labels.get(address).cloned()
+    }
+
+    pub fn get_labels(&self) -> &HashMap<ChecksummedAddress, String> {
+        &self.labels
+    }
+
+    /// Forgets labels of accounts no wallet derives anymore, e.g. after a mnemonic was replaced
+    /// Returns how many were removed
+    async fn prune_labels(&mut self) -> usize {
+        let addresses = self.all_addresses().await;
+        let before = self.labels.len();
+
+        self.labels.retain(|address, _| addresses.contains(address));
+
+        before - self.labels.len()
+    }
+
+    /// Imports wallets from a backup
+    ///
+    /// Unless `overwrite` is set, they're merged by name into the current list. The current
+    /// wallet is kept whenever it's still present
+    pub async fn import(&mut self, wallets: Vec<Wallet>, overwrite: bool) -> Result<()> {
+        let current = self.get_current_wallet().name();
+        let before = self.all_addresses().await;
+
+        if overwrite {
+            self.wallets = wallets;
+        } else {
+            for wallet in wallets {
+                match self.wallets.iter().position(|w| w.name() == wallet.name()) {
+                    Some(i) => self.wallets[i] = wallet,
+                    None => self.wallets.push(wallet),
+                }
+            }
+        }
+        signer_cache::clear();
+
+        self.current = self
+            .wallets
+            .iter()
+            .position(|w| w.name() == current)
+            .unwrap_or_default();
+        self.ensure_current();
+        self.prune_labels().await;
+
+        let after = self.all_addresses().await;
+        for a in after.difference(&before) {
+            iron_broadcast::address_added(*a).await;
+        }
+        for a in before.difference(&after) {
+            iron_broadcast::address_removed(*a).await;
+        }
+
+        self.on_wallet_changed().await?;
+        self.save()
+    }
+
     /// Get all addresses currently enabled in a given wallet
-    async fn get_wallet_addresses(&self, name: String) -> Vec<(String, ChecksummedAddress)> {
-        let wallet = self.find_wallet(&name).unwrap();
+    async fn get_wallet_addresses(
+        &self,
+        name: String,
+    ) -> Result<Vec<(String, ChecksummedAddress)>> {
+        let wallet = self.find_wallet(&name).ok_or(Error::UnknownWallet(name))?;
 
-        wallet.get_all_addresses().await
+        Ok(wallet.get_all_addresses().await)
+    }
+
+    /// Reveals the mnemonic of a wallet, by name
+    /// Works on a copy, so the wallets aren't held on to while the user unlocks it
+    pub async fn export_mnemonic(name: String) -> Result<String> {
+        let wallet = Self::read()
+            .await
+            .find_wallet(&name)
+            .cloned()
+            .ok_or(Error::UnknownWallet(name))?;
+
+        wallet.export_mnemonic().await
+    }
+
+    /// Exports an account of a wallet, by name, as an encrypted keystore file
+    pub async fn export_keystore(
+        &self,
+        name: String,
+        address: Address,
+        dir: PathBuf,
+        password: String,
+    ) -> Result<PathBuf> {
+        self.find_wallet(&name)
+            .ok_or(Error::UnknownWallet(name))?
+            .export_keystore(address, &dir, &password)
+            .await
+    }
+
+    /// Finds the used accounts of a wallet built from a mnemonic, by checking them on the current
+    /// network, and adds any missing ones. Returns the wallet's new account count
+    /// The checks are made on a copy of the wallet, so that wallets aren't locked while they run
+    pub async fn discover_accounts(name: String) -> Result<u32> {
+        let provider = Networks::read().await.get_current_provider()?;
+        let wallet = Self::read()
+            .await
+            .find_wallet(&name)
+            .cloned()
+            .ok_or_else(|| Error::UnknownWallet(name.clone()))?;
+        let used = wallet.count_used_accounts(&provider).await?;
+
+        let mut wallets = Self::write().await;
+        let wallet = wallets
+            .wallets
+            .iter_mut()
+            .find(|w| w.name() == name)
+            .ok_or(Error::UnknownWallet(name))?;
+
+        let before = wallet.get_all_addresses().await;
+        let count = wallet.grow_accounts(used).await?;
+        for (_, a) in wallet.get_all_addresses().await {
+            if !before.iter().any(|(_, b)| *b == a) {
+                iron_broadcast::address_added(a).await;
+            }
+        }
+
+        wallets.on_wallet_changed().await?;
+        wallets.save()?;
+        Ok(count)
+    }
+
+    /// The first `count` accounts a wallet would have under another base derivation path, with
+    /// their balances on the current network, so the user can pick the path holding their funds
+    /// Works on copies of the wallet and network, since unlocking and fetching balances take time
+    pub async fn preview_derivation(
+        name: String,
+        derivation_path: String,
+        count: u32,
+    ) -> Result<Vec<DerivedAccount>> {
+        let wallet = Self::read()
+            .await
+            .find_wallet(&name)
+            .cloned()
+            .ok_or(Error::UnknownWallet(name))?;
+        let accounts = wallet
+            .preview_derivation(&derivation_path, count.min(MAX_DERIVATION_PREVIEW))
+            .await?;
+
+        let network = Networks::read().await.get_current()?.clone();
+
+        let mut preview = Vec::with_capacity(accounts.len());
+        for (path, address) in accounts {
+            preview.push(DerivedAccount {
+                balance: network.get_balance(address.into()).await.ok(),
+                path,
+                address,
+            });
+        }
+
+        Ok(preview)
+    }
+
+    /// Switches a wallet to another base derivation path, making the account at `idx` within it
+    /// the current one. The path is kept with the wallet, for the next time it's loaded
+    /// The switch is made on a copy, which may need unlocking, and only the result is written back
+    pub async fn set_derivation(name: String, derivation_path: String, idx: u32) -> Result<()> {
+        let mut wallet = Self::read()
+            .await
+            .find_wallet(&name)
+            .cloned()
+            .ok_or_else(|| Error::UnknownWallet(name.clone()))?;
+
+        let before = wallet.get_all_addresses().await;
+        wallet.set_derivation(derivation_path, idx).await?;
+        let after = wallet.get_all_addresses().await;
+
+        let mut wallets = Self::write().await;
+        *wallets
+            .wallets
+            .iter_mut()
+            .find(|w| w.name() == name)
+            .ok_or_else(|| Error::UnknownWallet(name.clone()))? = wallet;
+        signer_cache::evict(&name);
+
+        for (_, a) in &after {
+            if !before.iter().any(|(_, b)| b == a) {
+                iron_broadcast::address_added(*a).await;
+            }
+        }
+        for (_, a) in &before {
+            if !after.iter().any(|(_, b)| b == a) {
+                iron_broadcast::address_removed(*a).await;
+            }
+        }
+
+        wallets.prune_labels().await;
+        wallets.notify_peers().await;
+        wallets.on_wallet_changed().await?;
+        wallets.save()?;
+        Ok(())
+    }
+
+    /// Unlocks a wallet by name, asking for its password
+    pub async fn unlock(&self, name: String) -> Result<()> {
+        self.find_wallet(&name)
+            .ok_or(Error::UnknownWallet(name))?
+            .unlock()
+            .await
+    }
+
+    /// Unlocks every password-protected wallet the password decrypts, along with those whose
+    /// password is in the keychain
+    /// Fails unless the password decrypts at least one of them. The keychain unlocks wallets
+    /// without it, so those say nothing about whether it's right
+    pub async fn unlock_all(&self, password: &str) -> Result<()> {
+        let mut verified = false;
+
+        for wallet in self.wallets.iter() {
+            if wallet.unlock_with(password).await.is_ok() {
+                verified = true;
+            } else if wallet.uses_keychain() {
+                wallet.unlock_from_keychain().await;
+            }
+        }
+
+        match verified {
+            true => Ok(()),
+            false => Err(Error::WrongPassword),
+        }
+    }
+
+    /// Locks a wallet by name, or all of them if no name is given
+    pub async fn lock(&self, name: Option<String>) -> Result<()> {
+        match name {
+            Some(name) => {
+                self.find_wallet(&name)
+                    .ok_or(Error::UnknownWallet(name))?
+                    .lock()
+                    .await
+            }
+            None => {
+                for wallet in self.wallets.iter() {
+                    wallet.lock().await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Re-encrypts a wallet's secret under a new password, and persists it
+    pub fn change_password(
+        &mut self,
+        name: String,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        self.wallets
+            .iter_mut()
+            .find(|w| w.name() == name)
+            .ok_or(Error::UnknownWallet(name))?
+            .change_password(old_password, new_password)?;
+
+        self.save()
+    }
+
+    /// Moves a wallet's password into the OS keychain, or back out of it under a new password
+    /// Enabling it on a plaintext wallet migrates it to an encrypted one
+    pub async fn set_keychain(
+        &mut self,
+        name: String,
+        enabled: bool,
+        password: Option<String>,
+    ) -> Result<()> {
+        let wallet = self
+            .wallets
+            .iter_mut()
+            .find(|w| w.name() == name)
+            .ok_or(Error::UnknownWallet(name))?;
+
+        match enabled {
+            true => wallet.enable_keychain(password.as_deref()).await?,
+            false => wallet.disable_keychain(password.as_deref().ok_or(Error::NoPassword)?)?,
+        }
+
+        iron_broadcast::ui_notify(UINotify::WalletsChanged).await;
+        self.save()
     }
 
     /// Finds a wallet by its name
@@ -150,22 +557,25 @@ impl Wallets {
         self.wallets.iter().find(|w| w.name() == *id)
     }
 
+    async fn all_addresses(&self) -> HashSet<ChecksummedAddress> {
+        let mut res = HashSet::new();
+        for wallet in self.wallets.iter() {
+            res.extend(wallet.get_all_addresses().await.into_iter().map(|(_, a)| a));
+        }
+        res
+    }
+
     /// Persists current state to disk
     fn save(&self) -> Result<()> {
         let pathbuf = self.file.clone().unwrap();
-        let path = Path::new(&pathbuf);
-        let file = File::create(path)?;
 
-        serde_json::to_writer_pretty(file, self)?;
-
-        Ok(())
+        Ok(iron_types::save_json(Path::new(&pathbuf), self)?)
     }
 
     /// Ensures that self.current never points to an invalid wallet
     fn ensure_current(&mut self) {
         if self.wallets.is_empty() {
-            self.wallets
-                .push(Wallet::Plaintext(plaintext::PlaintextWallet::default()));
+            self.wallets.push(Wallet::initial());
         }
 
         if self.current >= self.wallets.len() {
@@ -196,7 +606,52 @@ impl Wallets {
 
     // broadcasts `accountsChanged` to all peers
     async fn notify_peers(&self) {
-        let addresses = vec![self.get_current_wallet().get_current_address().await];
+        let addresses = self.get_current_accounts().await;
         iron_broadcast::accounts_changed(addresses).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hd_wallet::HDWallet;
+
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    async fn keychain_wallet(name: &str, password: &str) -> Wallet {
+        let wallet = HDWallet::create(serde_json::json!({
+            "mnemonic": MNEMONIC,
+            "derivationPath": "m/44'/60'/0'/0",
+            "current": "m/44'/60'/0'/0/0",
+            "password": password,
+            "name": name,
+            "count": 1,
+        }))
+        .await
+        .unwrap();
+
+        // an entry the keychain doesn't have, so it can't unlock the wallet by itself
+        let mut json = serde_json::to_value(wallet).unwrap();
+        json["keychain"] = "iron-test-missing".into();
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn unlock_all_rejects_wrong_password_for_keychain_wallets() {
+        let wallets = Wallets {
+            wallets: vec![
+                keychain_wallet("one", "hunter2").await,
+                keychain_wallet("two", "hunter2").await,
+            ],
+            current: 0,
+            labels: Default::default(),
+            file: None,
+        };
+
+        assert!(matches!(
+            wallets.unlock_all("wrong").await,
+            Err(Error::WrongPassword)
+        ));
+        wallets.unlock_all("hunter2").await.unwrap();
+    }
+}
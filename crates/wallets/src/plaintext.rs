@@ -3,15 +3,20 @@ use std::str::FromStr;
 use async_trait::async_trait;
 use coins_bip32::path::DerivationPath;
 use ethers::{
-    core::k256::ecdsa::SigningKey,
+    providers::Provider,
     signers::{coins_bip39::English, MnemonicBuilder, Signer},
 };
+use iron_networks::Failover;
 use iron_types::ChecksummedAddress;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
-use super::{utils, wallet::WalletCreate, Result, Wallet, WalletControl};
+use super::{
+    hd_wallet::HDWallet, signer_cache, utils, wallet::WalletCreate, DerivationPreset, Result,
+    Wallet, WalletControl, WalletSigner,
+};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(try_from = "Deserializer", rename_all = "camelCase")]
 pub struct PlaintextWallet {
     name: String,
@@ -25,7 +30,7 @@ pub struct PlaintextWallet {
 #[async_trait]
 impl WalletCreate for PlaintextWallet {
     async fn create(params: serde_json::Value) -> Result<Wallet> {
-        Ok(Wallet::Plaintext(serde_json::from_value(params)?))
+        Ok(Wallet::Plaintext(Self::from_params(params)?))
     }
 }
 
@@ -35,12 +40,22 @@ impl WalletControl for PlaintextWallet {
         self.name.clone()
     }
 
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
     async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
-        Ok(Wallet::Plaintext(serde_json::from_value(params)?))
+        Ok(Wallet::Plaintext(Self::from_params(params)?))
     }
 
     async fn get_current_address(&self) -> ChecksummedAddress {
-        self.build_current_signer(1).await.unwrap().address().into()
+        match self.build_current_signer(1).await {
+            Ok(signer) => signer.address().into(),
+            Err(e) => {
+                tracing::error!(wallet = %self.name, "failed to derive current account: {}", e);
+                Default::default()
+            }
+        }
     }
 
     fn get_current_path(&self) -> String {
@@ -59,22 +74,27 @@ impl WalletControl for PlaintextWallet {
         }
     }
 
-    async fn build_signer(
-        &self,
-        chain_id: u32,
-        _path: &str,
-    ) -> Result<ethers::signers::Wallet<SigningKey>> {
-        // TODO: ensure path exists
+    async fn build_signer(&self, chain_id: u32, path: &str) -> Result<WalletSigner> {
+        if let Some(signer) = signer_cache::get(&self.name, path, chain_id) {
+            return Ok(signer.into());
+        }
 
-        Ok(MnemonicBuilder::<English>::default()
+        let signer = MnemonicBuilder::<English>::default()
             .phrase(self.mnemonic.as_ref())
-            .derivation_path(&self.current_path)?
-            .build()
-            .map(|v| v.with_chain_id(chain_id))?)
+            .derivation_path(path)?
+            .build()?;
+
+        Ok(signer_cache::insert(&self.name, path, signer.with_chain_id(chain_id)).into())
     }
 
     async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)> {
-        utils::derive_addresses(&self.mnemonic, &self.derivation_path, self.count)
+        match utils::derive_addresses(&self.mnemonic, &self.derivation_path, self.count) {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                tracing::error!(wallet = %self.name, "failed to derive accounts: {}", e);
+                Default::default()
+            }
+        }
     }
 
     fn is_dev(&self) -> bool {
@@ -82,11 +102,115 @@ impl WalletControl for PlaintextWallet {
     }
 }
 
+impl PlaintextWallet {
+    fn from_params(params: serde_json::Value) -> Result<Self> {
+        let wallet: Self = serde_json::from_value(params)?;
+        Wallet::validate(&wallet.mnemonic, &wallet.derivation_path, 0)?;
+
+        Ok(wallet)
+    }
+
+    /// The first `count` accounts another base derivation path would give, without switching to
+    /// it
+    pub fn preview_derivation(
+        &self,
+        derivation_path: &str,
+        count: u32,
+    ) -> Result<Vec<(String, ChecksummedAddress)>> {
+        utils::validate_derivation_path(derivation_path, 0)?;
+
+        utils::derive_addresses(&self.mnemonic, derivation_path, count)
+    }
+
+    /// Switches to another base derivation path, and to the account at `idx` within it
+    pub fn set_derivation(&mut self, derivation_path: String, idx: u32) -> Result<()> {
+        utils::validate_derivation_path(&derivation_path, idx)?;
+
+        self.current_path = DerivationPreset::from(derivation_path.as_str()).account_path(idx);
+        self.derivation_path = derivation_path;
+        self.count = self.count.max(idx + 1);
+
+        Ok(())
+    }
+
+    /// Reveals the mnemonic
+    /// Everything else only uses it internally, so this is the one place it leaves the wallet
+    pub fn export_mnemonic(&self) -> String {
+        tracing::info!(wallet = %self.name, "exporting mnemonic");
+        self.mnemonic.clone()
+    }
+
+    /// Moves the mnemonic into an encrypted wallet, whose password is kept in the OS keychain
+    pub async fn to_keychain(&self) -> Result<HDWallet> {
+        HDWallet::with_keychain(
+            self.name.clone(),
+            self.mnemonic.clone(),
+            self.derivation_path.clone(),
+            self.count,
+            self.current_path.clone(),
+        )
+        .await
+    }
+
+    /// Counts the accounts that were used on-chain
+    pub async fn count_used_accounts(&self, provider: &Provider<Failover>) -> Result<u32> {
+        utils::discover_used_accounts(&self.mnemonic, &self.derivation_path, provider).await
+    }
+
+    /// Grows the account list to at least `count` accounts, returning the new account count
+    pub fn grow_accounts(&mut self, count: u32) -> u32 {
+        self.count = self.count.max(count);
+        self.count
+    }
+
+    /// Builds a wallet from the mnemonic in `IRON_MNEMONIC`, if set
+    /// Lets CI and local setups inject a key without editing source
+    pub fn from_env() -> Option<Result<Self>> {
+        let mnemonic = std::env::var("IRON_MNEMONIC").ok()?;
+        let preset = DerivationPreset::default();
+        let derivation_path = preset.path();
+
+        if let Err(e) = Wallet::validate(&mnemonic, &derivation_path, 0) {
+            return Some(Err(e));
+        }
+
+        Some(Ok(Self {
+            name: "env".into(),
+            current_path: preset.account_path(0),
+            mnemonic,
+            derivation_path,
+            dev: true,
+            count: 3,
+        }))
+    }
+}
+
+// the mnemonic is deliberately left out
+impl std::fmt::Debug for PlaintextWallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlaintextWallet")
+            .field("name", &self.name)
+            .field("mnemonic", &"***")
+            .field("derivation_path", &self.derivation_path)
+            .field("dev", &self.dev)
+            .field("count", &self.count)
+            .field("current_path", &self.current_path)
+            .finish()
+    }
+}
+
+impl Drop for PlaintextWallet {
+    fn drop(&mut self) {
+        self.mnemonic.zeroize();
+    }
+}
+
 impl Default for PlaintextWallet {
     fn default() -> Self {
         let mnemonic = String::from("test test test test test test test test test test test junk");
-        let derivation_path = String::from("m/44'/60'/0'/0");
-        let current_path = format!("{}/{}", derivation_path, 0);
+        let preset = DerivationPreset::default();
+        let derivation_path = preset.path();
+        let current_path = preset.account_path(0);
 
         Self {
             name: "test".into(),
@@ -99,7 +223,7 @@ impl Default for PlaintextWallet {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Deserializer {
     name: String,
@@ -124,7 +248,9 @@ impl TryFrom<Deserializer> for PlaintextWallet {
         // if current_path is not given or invalid, try to build it from derivation_path
         let current_path: DerivationPath = match current_path {
             Some(path) => path,
-            None => DerivationPath::from_str(&format!("{}/0", value.derivation_path))?,
+            None => DerivationPath::from_str(
+                &DerivationPreset::from(value.derivation_path.as_str()).account_path(0),
+            )?,
         };
 
         Ok(Self {
@@ -0,0 +1,380 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    signers::{self, Signer},
+};
+use iron_crypto::EncryptedData;
+use iron_dialogs::{Dialog, DialogMsg};
+use iron_types::ChecksummedAddress;
+use secrets::SecretVec;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex, RwLock},
+    task::JoinHandle,
+};
+use zeroize::Zeroize;
+
+use crate::{
+    hd_wallet::{mnemonic_from_secret, mnemonic_into_secret},
+    wallet::WalletCreate,
+    Error, Result, Wallet, WalletControl, WalletSigner,
+};
+
+/// A wallet holding a single raw private key, e.g. one given by a faucet or a deploy script
+/// Keys are encrypted at rest under a password, the same way mnemonics are. Only dev keys, such as
+/// anvil's, can be kept in plaintext
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateKeyWallet {
+    name: String,
+
+    /// hex-encoded, with an optional `0x` prefix
+    /// Empty for encrypted keys
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    private_key: String,
+
+    /// The hex-encoded key, encrypted under the wallet's password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ciphertext: Option<EncryptedData<String>>,
+
+    /// Kept alongside encrypted keys, so the account is known while the wallet is locked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<ChecksummedAddress>,
+
+    #[serde(default)]
+    dev: bool,
+
+    /// The decrypted key, cached for a minute after unlocking
+    /// The additional Mutex within is there because `SecretVec` is not Send
+    #[serde(skip)]
+    secret: Arc<RwLock<Option<Mutex<SecretVec<u8>>>>>,
+
+    /// A join handle that will expire the secret after some time
+    #[serde(skip)]
+    expirer: Arc<RwLock<Option<JoinHandle<()>>>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrivateKeyWalletParams {
+    name: String,
+    private_key: String,
+    password: Option<String>,
+    #[serde(default)]
+    dev: bool,
+}
+
+impl PrivateKeyWallet {
+    /// A dev key, kept in plaintext
+    pub fn new(name: String, private_key: &str) -> Result<Self> {
+        Self::build(name, private_key, None, true)
+    }
+
+    /// Validates a key, encrypting it under `password`, which only dev keys may go without
+    fn build(name: String, private_key: &str, password: Option<&str>, dev: bool) -> Result<Self> {
+        let private_key = private_key.trim();
+        let address = parse_key(private_key)?.address().into();
+
+        let (private_key, ciphertext, address) = match password.filter(|p| !p.is_empty()) {
+            Some(password) => {
+                let ciphertext = iron_crypto::encrypt(&private_key.to_string(), password)
+                    .map_err(|_| Error::EncryptionFailed)?;
+                (String::new(), Some(ciphertext), Some(address))
+            }
+            None if dev => (private_key.to_string(), None, None),
+            None => return Err(Error::PasswordRequired),
+        };
+
+        Ok(Self {
+            name,
+            private_key,
+            ciphertext,
+            address,
+            dev,
+            secret: Default::default(),
+            expirer: Default::default(),
+        })
+    }
+
+    /// Builds the signer, asking for the password first if the key is encrypted and locked
+    async fn signer(&self) -> Result<signers::Wallet<SigningKey>> {
+        if self.ciphertext.is_none() {
+            return parse_key(&self.private_key);
+        }
+
+        self.unlock().await?;
+        let secret = self.secret.read().await;
+        let secret = secret
+            .as_ref()
+            .ok_or(Error::UnlockDialogFailed)?
+            .lock()
+            .await;
+
+        let mut key = mnemonic_from_secret(&secret);
+        let signer = parse_key(&key);
+        key.zeroize();
+        signer
+    }
+
+    async fn is_unlocked(&self) -> bool {
+        self.secret.read().await.is_some()
+    }
+
+    /// Forgets the decrypted key, so the password is asked for again on next use
+    pub async fn lock(&self) {
+        if let Some(expirer) = self.expirer.write().await.take() {
+            expirer.abort();
+        }
+        self.secret.write().await.take();
+    }
+
+    /// Decrypts the key, asking for the wallet's password
+    /// Plaintext keys have nothing to unlock
+    pub async fn unlock(&self) -> Result<()> {
+        let Some(ref ciphertext) = self.ciphertext else {
+            return Ok(());
+        };
+        if self.is_unlocked().await {
+            return Ok(());
+        }
+
+        let dialog = Dialog::new("wallet-unlock", serde_json::to_value(self)?);
+        dialog.open().await?;
+
+        // attempt to receive a password at most 3 times
+        for _ in 0..3 {
+            let password = match dialog.recv().await {
+                Some(DialogMsg::Data(payload)) | Some(DialogMsg::Accept(payload)) => payload
+                    ["password"]
+                    .as_str()
+                    .ok_or(Error::UnlockDialogRejected)?
+                    .to_string(),
+                _ => return Err(Error::UnlockDialogRejected),
+            };
+
+            if let Ok(key) = iron_crypto::decrypt(ciphertext, &password) {
+                self.store_secret(key).await;
+                dialog.close().await?;
+                return Ok(());
+            }
+
+            dialog.send("failed", None).await?;
+        }
+
+        dialog.close().await?;
+        Err(Error::UnlockDialogFailed)
+    }
+
+    /// Decrypts the key with a password given up front, instead of asking for one
+    pub async fn unlock_with(&self, password: &str) -> Result<()> {
+        let ciphertext = self.ciphertext.as_ref().ok_or(Error::NoPassword)?;
+        let key = iron_crypto::decrypt(ciphertext, password).map_err(|_| Error::WrongPassword)?;
+        self.store_secret(key).await;
+
+        Ok(())
+    }
+
+    async fn store_secret(&self, key: String) {
+        let mut expirer_handle = self.expirer.write().await;
+        let mut secret_handle = self.secret.write().await;
+
+        *secret_handle = Some(Mutex::new(mnemonic_into_secret(key)));
+
+        // set up cache expiration for 1 minute
+        let clone = Arc::clone(&self.secret);
+        *expirer_handle = Some(tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            clone.write().await.take();
+        }));
+    }
+
+    /// Re-encrypts the key under a new password
+    pub fn change_password(&mut self, old_password: &str, new_password: &str) -> Result<()> {
+        let ciphertext = self.ciphertext.as_ref().ok_or(Error::NoPassword)?;
+
+        let mut key: String =
+            iron_crypto::decrypt(ciphertext, old_password).map_err(|_| Error::WrongPassword)?;
+        let ciphertext = iron_crypto::encrypt(&key, new_password);
+        key.zeroize();
+
+        self.ciphertext = Some(ciphertext.map_err(|_| Error::EncryptionFailed)?);
+        Ok(())
+    }
+}
+
+fn parse_key(private_key: &str) -> Result<signers::Wallet<SigningKey>> {
+    signers::Wallet::<SigningKey>::from_str(private_key).map_err(|_| Error::InvalidPrivateKey)
+}
+
+#[async_trait]
+impl WalletCreate for PrivateKeyWallet {
+    async fn create(params: serde_json::Value) -> Result<Wallet> {
+        let mut params: PrivateKeyWalletParams = serde_json::from_value(params)?;
+        let wallet = Self::build(
+            params.name.clone(),
+            &params.private_key,
+            params.password.as_deref(),
+            params.dev,
+        );
+        params.private_key.zeroize();
+        if let Some(ref mut password) = params.password {
+            password.zeroize();
+        }
+
+        Ok(Wallet::PrivateKey(wallet?))
+    }
+}
+
+#[async_trait]
+impl WalletControl for PrivateKeyWallet {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
+        if let Some(name) = params["name"].as_str() {
+            self.name = name.into();
+        }
+
+        if let Some(dev) = params["dev"].as_bool() {
+            self.dev = dev;
+        }
+
+        // an encrypted key isn't sent back by the UI, so an empty one means it's unchanged
+        if let Some(private_key) = params["privateKey"].as_str().filter(|k| !k.is_empty()) {
+            self = Self::build(
+                self.name.clone(),
+                private_key,
+                params["password"].as_str(),
+                self.dev,
+            )?;
+        }
+
+        Ok(Wallet::PrivateKey(self))
+    }
+
+    async fn get_current_address(&self) -> ChecksummedAddress {
+        if let Some(address) = self.address {
+            return address;
+        }
+
+        // the key is validated whenever this wallet is created or updated
+        parse_key(&self.private_key)
+            .map(|signer| signer.address().into())
+            .unwrap_or_default()
+    }
+
+    fn get_current_path(&self) -> String {
+        String::from("0")
+    }
+
+    async fn set_current_path(&mut self, path: String) -> Result<()> {
+        if path != self.get_current_path() {
+            return Err(Error::InvalidKey(path));
+        }
+
+        Ok(())
+    }
+
+    async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)> {
+        vec![(self.get_current_path(), self.get_current_address().await)]
+    }
+
+    async fn build_signer(&self, chain_id: u32, path: &str) -> Result<WalletSigner> {
+        if path != self.get_current_path() {
+            return Err(Error::InvalidKey(path.to_string()));
+        }
+
+        Ok(self.signer().await?.with_chain_id(chain_id).into())
+    }
+
+    fn is_dev(&self) -> bool {
+        self.dev
+    }
+}
+
+// the key is deliberately left out
+impl std::fmt::Debug for PrivateKeyWallet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrivateKeyWallet")
+            .field("name", &self.name)
+            .field("encrypted", &self.ciphertext.is_some())
+            .field("dev", &self.dev)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for PrivateKeyWallet {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::Address;
+
+    use super::*;
+
+    // first anvil dev account
+    const KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    #[tokio::test]
+    async fn encrypts_keys_imported_with_a_password() {
+        let mut wallet =
+            PrivateKeyWallet::build("deployer".into(), KEY, Some("hunter2"), false).unwrap();
+
+        let json = serde_json::to_value(&wallet).unwrap();
+        assert!(json.get("privateKey").is_none());
+        assert!(!json.to_string().contains(&KEY[2..]));
+
+        // the address is known without unlocking
+        assert_eq!(
+            wallet.get_current_address().await,
+            Address::from_str(ADDRESS).unwrap().into()
+        );
+
+        assert!(matches!(
+            wallet.change_password("wrong", "new"),
+            Err(Error::WrongPassword)
+        ));
+        wallet.change_password("hunter2", "new").unwrap();
+    }
+
+    #[tokio::test]
+    async fn unlocks_with_a_given_password() {
+        let wallet =
+            PrivateKeyWallet::build("deployer".into(), KEY, Some("hunter2"), false).unwrap();
+
+        assert!(matches!(
+            wallet.unlock_with("wrong").await,
+            Err(Error::WrongPassword)
+        ));
+        assert!(!wallet.is_unlocked().await);
+
+        wallet.unlock_with("hunter2").await.unwrap();
+        assert!(wallet.is_unlocked().await);
+
+        wallet.lock().await;
+        assert!(!wallet.is_unlocked().await);
+    }
+
+    #[test]
+    fn keeps_dev_keys_in_plaintext() {
+        let wallet = PrivateKeyWallet::build("anvil".into(), KEY, None, true).unwrap();
+
+        assert!(wallet.ciphertext.is_none());
+        assert!(PrivateKeyWallet::new("bad".into(), "0x1234").is_err());
+        assert!(matches!(
+            PrivateKeyWallet::build("deployer".into(), KEY, Some(""), false),
+            Err(Error::PasswordRequired)
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    signers::{self, Ledger, LedgerError, Signer, WalletError},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Signature,
+    },
+};
+
+/// A signer for any wallet type, whether its key is held in memory or on a hardware device
+#[derive(Debug)]
+pub enum WalletSigner {
+    Local(signers::Wallet<SigningKey>),
+
+    /// Every signature needs to be confirmed on the device itself
+    Ledger(Ledger),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WalletSignerError {
+    #[error(transparent)]
+    Local(#[from] WalletError),
+
+    #[error(transparent)]
+    Ledger(#[from] LedgerError),
+}
+
+impl From<signers::Wallet<SigningKey>> for WalletSigner {
+    fn from(signer: signers::Wallet<SigningKey>) -> Self {
+        Self::Local(signer)
+    }
+}
+
+impl From<Ledger> for WalletSigner {
+    fn from(signer: Ledger) -> Self {
+        Self::Ledger(signer)
+    }
+}
+
+#[async_trait]
+impl Signer for WalletSigner {
+    type Error = WalletSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(signer) => Ok(signer.sign_message(message).await?),
+            Self::Ledger(signer) => Ok(signer.sign_message(message).await?),
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(signer) => Ok(signer.sign_transaction(tx).await?),
+            Self::Ledger(signer) => Ok(signer.sign_transaction(tx).await?),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(signer) => Ok(signer.sign_typed_data(payload).await?),
+            Self::Ledger(signer) => Ok(signer.sign_typed_data(payload).await?),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            Self::Ledger(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(signer) => signer.chain_id(),
+            Self::Ledger(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(signer) => Self::Local(signer.with_chain_id(chain_id)),
+            Self::Ledger(signer) => Self::Ledger(signer.with_chain_id(chain_id)),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Signers derived from a mnemonic, kept by account and chain ID, so that signing again, or on
+//! another chain, doesn't re-run BIP-39 seed stretching and BIP-32 derivation
+//! Entries are evicted when their wallet locks, expires its secret, or changes. Keys zeroize
+//! their memory once dropped
+
+use std::{collections::HashMap, sync::Mutex};
+
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    signers::{self, Signer},
+};
+use once_cell::sync::Lazy;
+
+type LocalSigner = signers::Wallet<SigningKey>;
+
+/// Wallet name, derivation path and chain ID
+type Key = (String, String, u32);
+
+static SIGNERS: Lazy<Mutex<HashMap<Key, LocalSigner>>> = Lazy::new(Default::default);
+
+/// The cached signer of an account for a chain
+/// One derived for another chain is reused, as only the chain ID it signs with differs
+pub(crate) fn get(wallet: &str, path: &str, chain_id: u32) -> Option<LocalSigner> {
+    let mut signers = SIGNERS.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (wallet.to_string(), path.to_string(), chain_id);
+
+    if let Some(signer) = signers.get(&key) {
+        return Some(signer.clone());
+    }
+
+    let signer = signers
+        .iter()
+        .find(|((w, p, _), _)| w == wallet && p == path)
+        .map(|(_, signer)| signer.clone().with_chain_id(chain_id))?;
+    signers.insert(key, signer.clone());
+
+    Some(signer)
+}
+
+/// Caches a freshly derived signer, handing it back
+pub(crate) fn insert(wallet: &str, path: &str, signer: LocalSigner) -> LocalSigner {
+    let key = (
+        wallet.to_string(),
+        path.to_string(),
+        signer.chain_id() as u32,
+    );
+
+    SIGNERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, signer.clone());
+
+    signer
+}
+
+/// Forgets every signer of a wallet
+pub(crate) fn evict(wallet: &str) {
+    SIGNERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|(w, _, _), _| w != wallet);
+}
+
+pub(crate) fn clear() {
+    SIGNERS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{coins_bip39::English, MnemonicBuilder};
+
+    use super::*;
+
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+    const PATH: &str = "m/44'/60'/0'/0/0";
+
+    #[test]
+    fn reuses_signers_across_chains() {
+        let signer = MnemonicBuilder::<English>::default()
+            .phrase(MNEMONIC)
+            .derivation_path(PATH)
+            .unwrap()
+            .build()
+            .unwrap()
+            .with_chain_id(1u64);
+
+        assert!(get("cache-test", PATH, 1).is_none());
+        insert("cache-test", PATH, signer.clone());
+
+        let other_chain = get("cache-test", PATH, 10).unwrap();
+        assert_eq!(other_chain.address(), signer.address());
+        assert_eq!(other_chain.chain_id(), 10);
+
+        evict("cache-test");
+        assert!(get("cache-test", PATH, 1).is_none());
+    }
+}
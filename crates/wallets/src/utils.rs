@@ -1,29 +1,175 @@
-use ethers::signers::{coins_bip39::English, MnemonicBuilder, Signer};
+use std::str::FromStr;
+
+use coins_bip32::path::DerivationPath;
+use ethers::{
+    providers::{Middleware, Provider},
+    signers::{
+        coins_bip39::{English, Mnemonic},
+        MnemonicBuilder, Signer,
+    },
+    types::Address,
+};
+use iron_networks::Failover;
 use iron_types::ChecksummedAddress;
 
-use super::Result;
+use super::{DerivationPreset, Error, Result};
 
 pub fn derive_addresses(
     mnemonic: &str,
     derivation_path: &str,
     count: u32,
-) -> Vec<(String, ChecksummedAddress)> {
+) -> Result<Vec<(String, ChecksummedAddress)>> {
     let builder = MnemonicBuilder::<English>::default().phrase(mnemonic);
+    let preset = DerivationPreset::from(derivation_path);
 
     (0..count)
         .map(|idx| {
-            let path = format!("{}/{}", derivation_path, idx);
-            // TODO: what to do about this unwrap?
-            let address = derive_from_builder_and_path(builder.clone(), &path).unwrap();
+            let path = preset.account_path(idx);
+            let address = derive_from_builder_and_path(builder.clone(), &path)?;
 
-            (path, address)
+            Ok((path, address))
         })
         .collect()
 }
 
+/// How many unused accounts in a row end account discovery, as suggested by BIP-44
+const DISCOVERY_GAP: u32 = 5;
+
+/// Upper bound on how many accounts discovery looks at
+const MAX_DISCOVERED_ACCOUNTS: u32 = 100;
+
+/// Counts how many of a mnemonic's accounts have been used, i.e. hold a balance or have sent a
+/// transaction. Accounts are checked in order until a gap of unused ones is found
+pub async fn discover_used_accounts(
+    mnemonic: &str,
+    derivation_path: &str,
+    provider: &Provider<Failover>,
+) -> Result<u32> {
+    let builder = MnemonicBuilder::<English>::default().phrase(mnemonic);
+    let preset = DerivationPreset::from(derivation_path);
+
+    let (mut used, mut gap, mut idx) = (0, 0, 0);
+    while gap < DISCOVERY_GAP && idx < MAX_DISCOVERED_ACCOUNTS {
+        let address = derive_from_builder_and_path(builder.clone(), &preset.account_path(idx))?.0;
+        let balance = provider.get_balance(address, None).await?;
+        let nonce = provider.get_transaction_count(address, None).await?;
+
+        if balance.is_zero() && nonce.is_zero() {
+            gap += 1;
+        } else {
+            used = idx + 1;
+            gap = 0;
+        }
+        idx += 1;
+    }
+
+    Ok(used)
+}
+
 pub fn derive_from_builder_and_path(
     builder: MnemonicBuilder<English>,
     path: &str,
 ) -> Result<ChecksummedAddress> {
     Ok(builder.derivation_path(path)?.build()?.address().into())
 }
+
+/// Checks whether a mnemonic derives the expected address at a given index
+/// Errors if the mnemonic itself is invalid, as opposed to valid but not matching
+pub fn verify_mnemonic(
+    mnemonic: &str,
+    derivation_path: &str,
+    idx: u32,
+    expected: Address,
+) -> Result<bool> {
+    validate_mnemonic(mnemonic)?;
+
+    let builder = MnemonicBuilder::<English>::default().phrase(mnemonic);
+    let path = DerivationPreset::from(derivation_path).account_path(idx);
+    let address = derive_from_builder_and_path(builder, &path)?;
+
+    Ok(address.0 == expected)
+}
+
+/// Checks that a mnemonic is a valid English BIP-39 phrase
+pub fn validate_mnemonic(mnemonic: &str) -> Result<()> {
+    Mnemonic::<English>::new_from_phrase(mnemonic).map_err(|_| Error::InvalidMnemonic)?;
+
+    Ok(())
+}
+
+/// Checks that a base derivation path has the expected `m/44'/60'/...` shape, and that the
+/// account at `idx` can be derived from it
+pub fn validate_derivation_path(derivation_path: &str, idx: u32) -> Result<()> {
+    let invalid = || Error::InvalidDerivationPath(derivation_path.to_string());
+
+    let rest = derivation_path
+        .strip_prefix("m/44'/60'")
+        .ok_or_else(invalid)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return Err(invalid());
+    }
+
+    let path = DerivationPreset::from(derivation_path).account_path(idx);
+    DerivationPath::from_str(&path).map_err(|_| invalid())?;
+
+    Ok(())
+}
+
+/// Decodes `0x`-prefixed hex messages into the raw bytes they represent, so they aren't signed as
+/// text. Anything else is signed as-is
+pub fn message_bytes(message: &[u8]) -> Vec<u8> {
+    message
+        .strip_prefix(b"0x")
+        .and_then(|hex| ethers::utils::hex::decode(hex).ok())
+        .unwrap_or_else(|| message.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+    const PATH: &str = "m/44'/60'/0'/0";
+
+    #[test]
+    fn verify_mnemonic_matches_derived_address() {
+        let first = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+        assert!(verify_mnemonic(MNEMONIC, PATH, 0, first).unwrap());
+        assert!(!verify_mnemonic(MNEMONIC, PATH, 1, first).unwrap());
+    }
+
+    #[test]
+    fn validate_derivation_path_checks_shape() {
+        for path in [PATH, "m/44'/60'", "m/44'/60'/1'/0", "m/44'/60'/x'/0/0"] {
+            assert!(validate_derivation_path(path, 0).is_ok(), "{}", path);
+        }
+
+        for path in [
+            "",
+            "m",
+            "m/44'/61'/0'/0",
+            "m/44'/600'/0'",
+            "44'/60'/0'/0",
+            "m/44'/60'/x",
+        ] {
+            assert!(
+                matches!(
+                    validate_derivation_path(path, 0),
+                    Err(Error::InvalidDerivationPath(_))
+                ),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn verify_mnemonic_rejects_invalid_mnemonics() {
+        let res = verify_mnemonic("test test junk", PATH, 0, Address::zero());
+
+        assert!(matches!(res, Err(Error::InvalidMnemonic)));
+    }
+}
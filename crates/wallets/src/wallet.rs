@@ -1,40 +1,87 @@
+use std::path::{Path, PathBuf};
+
 use async_trait::async_trait;
 use enum_dispatch::enum_dispatch;
-use ethers::core::k256::ecdsa::SigningKey;
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    providers::Provider,
+    signers::{self, Signer},
+    types::{transaction::eip712::TypedData, Address},
+};
+use iron_networks::Failover;
 use iron_types::{ChecksummedAddress, Json};
 use serde::{Deserialize, Serialize};
 
 use super::{
     hd_wallet::HDWallet, impersonator::Impersonator, json_keystore_wallet::JsonKeystoreWallet,
-    plaintext::PlaintextWallet, Error, Result,
+    ledger::LedgerWallet, plaintext::PlaintextWallet, private_key::PrivateKeyWallet, utils,
+    watch_only::WatchOnly, Error, Result, WalletSigner,
 };
 
 #[async_trait]
 #[enum_dispatch(Wallet)]
 pub trait WalletControl: Sync + Send + Deserialize<'static> + Serialize + std::fmt::Debug {
     fn name(&self) -> String;
+    fn set_name(&mut self, name: String);
     async fn update(mut self, params: Json) -> Result<Wallet>;
     async fn get_current_address(&self) -> ChecksummedAddress;
     fn get_current_path(&self) -> String;
     async fn set_current_path(&mut self, path: String) -> Result<()>;
     async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)>;
 
-    async fn build_signer(
-        &self,
-        chain_id: u32,
-        path: &str,
-    ) -> Result<ethers::signers::Wallet<SigningKey>>;
+    async fn build_signer(&self, chain_id: u32, path: &str) -> Result<WalletSigner>;
 
-    async fn build_current_signer(
-        &self,
-        chain_id: u32,
-    ) -> Result<ethers::signers::Wallet<SigningKey>> {
+    async fn build_current_signer(&self, chain_id: u32) -> Result<WalletSigner> {
         self.build_signer(chain_id, &self.get_current_path()).await
     }
 
+    /// Finds the derivation path of one of this wallet's accounts
+    async fn get_path_for_address(&self, address: Address) -> Option<String> {
+        self.get_all_addresses()
+            .await
+            .into_iter()
+            .find(|(_, a)| a.0 == address)
+            .map(|(path, _)| path)
+    }
+
+    /// Finds the address of one of this wallet's accounts, by its derivation path
+    async fn get_address_for_path(&self, path: &str) -> Option<ChecksummedAddress> {
+        self.get_all_addresses()
+            .await
+            .into_iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, address)| address)
+    }
+
+    /// Signs a message with the current account, as `personal_sign` does
+    /// The message gets the `"\x19Ethereum Signed Message:\n"` length prefix, and `0x`-prefixed
+    /// hex messages are signed as the bytes they encode. Returns a `0x`-prefixed hex signature
+    async fn personal_sign(&self, message: &[u8]) -> Result<String> {
+        // the chain ID only matters for transactions
+        let signer = self.build_current_signer(1).await?;
+        let signature = signer.sign_message(utils::message_bytes(message)).await?;
+
+        Ok(format!("0x{}", signature))
+    }
+
+    /// Signs EIP-712 typed data with the current account
+    /// Returns a `0x`-prefixed hex signature
+    async fn sign_typed_data(&self, data: &TypedData) -> Result<String> {
+        let signer = self.build_current_signer(1).await?;
+        let signature = signer.sign_typed_data(data).await?;
+
+        Ok(format!("0x{}", signature))
+    }
+
     fn is_dev(&self) -> bool {
         false
     }
+
+    /// Fails if an account has no way of signing at all, so that requests can be turned down
+    /// before the user is asked to review them
+    fn ensure_can_sign(&self, _path: &str) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// needs to be a separate trait, because enum_dispatch does not allow for static functions
@@ -54,6 +101,222 @@ pub enum Wallet {
     HDWallet(HDWallet),
 
     Impersonator(Impersonator),
+    WatchOnly(WatchOnly),
+    PrivateKey(PrivateKeyWallet),
+    Ledger(LedgerWallet),
+}
+
+impl Wallet {
+    /// Checks that a mnemonic and derivation path can derive the account at `idx`
+    /// Meant to run before a wallet is created or updated, so the UI gets a descriptive error
+    pub fn validate(mnemonic: &str, derivation_path: &str, idx: u32) -> Result<()> {
+        utils::validate_mnemonic(mnemonic)?;
+        utils::validate_derivation_path(derivation_path, idx)
+    }
+
+    /// Reveals the mnemonic of wallets that are built from one
+    pub async fn export_mnemonic(&self) -> Result<String> {
+        match self {
+            Self::Plaintext(wallet) => Ok(wallet.export_mnemonic()),
+            Self::HDWallet(wallet) => wallet.export_mnemonic().await,
+            _ => Err(Error::NoMnemonic),
+        }
+    }
+
+    /// Writes one of the wallet's accounts to `dir` as a Web3 Secret Storage (v3) keystore,
+    /// encrypted under `password`, for use with geth, foundry's `cast` and other tooling
+    /// Returns the path of the new file. Hardware and watch-only accounts have no key to export
+    pub async fn export_keystore(
+        &self,
+        address: Address,
+        dir: &Path,
+        password: &str,
+    ) -> Result<PathBuf> {
+        let path = self
+            .get_path_for_address(address)
+            .await
+            .ok_or_else(|| Error::InvalidKey(format!("{:?}", address)))?;
+
+        let WalletSigner::Local(signer) = self.build_signer(1, &path).await? else {
+            return Err(Error::NotExportable);
+        };
+
+        let name = format!("{:?}.json", address);
+        let key = signer.signer().to_bytes();
+        signers::Wallet::<SigningKey>::encrypt_keystore(
+            dir,
+            &mut ethers::core::rand::thread_rng(),
+            key.as_slice(),
+            password,
+            Some(&name),
+        )?;
+
+        Ok(dir.join(name))
+    }
+
+    /// Keeps the wallet's password in the OS keychain, so unlocking it needs no prompt
+    /// Plaintext wallets are migrated to an encrypted one instead, under a random password only
+    /// the keychain knows
+    pub async fn enable_keychain(&mut self, password: Option<&str>) -> Result<()> {
+        match self {
+            Self::HDWallet(wallet) => wallet.enable_keychain(password.ok_or(Error::NoPassword)?),
+            Self::Plaintext(wallet) => {
+                *self = Self::HDWallet(wallet.to_keychain().await?);
+                Ok(())
+            }
+            _ => Err(Error::NoPassword),
+        }
+    }
+
+    /// Takes the wallet's password out of the keychain, re-encrypting it under `password`
+    pub fn disable_keychain(&mut self, password: &str) -> Result<()> {
+        match self {
+            Self::HDWallet(wallet) => wallet.disable_keychain(password),
+            _ => Err(Error::NoPassword),
+        }
+    }
+
+    /// Whether the wallet's password is kept in the OS keychain
+    pub fn uses_keychain(&self) -> bool {
+        matches!(self, Self::HDWallet(wallet) if wallet.uses_keychain())
+    }
+
+    /// A copy for backups, with any keychain-kept password swapped for `password`
+    pub fn for_backup(&self, password: &str) -> Result<Self> {
+        match self {
+            Self::HDWallet(wallet) => Ok(Self::HDWallet(wallet.for_backup(password)?)),
+            _ => Ok(self.clone()),
+        }
+    }
+
+    /// Removes the wallet's keychain entry, if it has one, once the wallet itself is removed
+    pub fn forget_keychain(&self) {
+        if let Self::HDWallet(wallet) = self {
+            wallet.forget_keychain();
+        }
+    }
+
+    /// Unlocks password-protected wallets, asking the user for their password
+    /// Other wallet types have nothing to unlock
+    pub async fn unlock(&self) -> Result<()> {
+        match self {
+            Self::HDWallet(wallet) => wallet.unlock().await,
+            Self::JsonKeystore(wallet) => wallet.unlock().await,
+            Self::PrivateKey(wallet) => wallet.unlock().await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Unlocks password-protected wallets with a password given up front
+    /// Fails with `NoPassword` for other wallet types
+    pub async fn unlock_with(&self, password: &str) -> Result<()> {
+        match self {
+            Self::HDWallet(wallet) => wallet.unlock_with(password).await,
+            Self::JsonKeystore(wallet) => wallet.unlock_with(password).await,
+            Self::PrivateKey(wallet) => wallet.unlock_with(password).await,
+            _ => Err(Error::NoPassword),
+        }
+    }
+
+    /// Unlocks wallets whose password is kept in the OS keychain, without asking for it
+    /// Resolves to whether the keychain unlocked it
+    pub async fn unlock_from_keychain(&self) -> bool {
+        match self {
+            Self::HDWallet(wallet) => wallet.unlock_from_keychain().await,
+            _ => false,
+        }
+    }
+
+    /// Forgets the decrypted secrets of password-protected wallets
+    pub async fn lock(&self) {
+        match self {
+            Self::HDWallet(wallet) => wallet.lock().await,
+            Self::JsonKeystore(wallet) => wallet.lock().await,
+            Self::PrivateKey(wallet) => wallet.lock().await,
+            _ => (),
+        }
+    }
+
+    /// Re-encrypts a wallet's secret under a new password
+    pub fn change_password(&mut self, old_password: &str, new_password: &str) -> Result<()> {
+        match self {
+            Self::HDWallet(wallet) => wallet.change_password(old_password, new_password),
+            Self::PrivateKey(wallet) => wallet.change_password(old_password, new_password),
+            _ => Err(Error::NoPassword),
+        }
+    }
+
+    /// Counts the accounts of a wallet built from a mnemonic that were used on-chain
+    pub async fn count_used_accounts(&self, provider: &Provider<Failover>) -> Result<u32> {
+        match self {
+            Self::Plaintext(wallet) => wallet.count_used_accounts(provider).await,
+            Self::HDWallet(wallet) => wallet.count_used_accounts(provider).await,
+            _ => Err(Error::NoMnemonic),
+        }
+    }
+
+    /// Grows the account list of a wallet built from a mnemonic to at least `count` accounts
+    /// Returns the new account count
+    pub async fn grow_accounts(&mut self, count: u32) -> Result<u32> {
+        match self {
+            Self::Plaintext(wallet) => Ok(wallet.grow_accounts(count)),
+            Self::HDWallet(wallet) => wallet.grow_accounts(count).await,
+            _ => Err(Error::NoMnemonic),
+        }
+    }
+
+    /// The first `count` accounts another base derivation path would give, so the user can check
+    /// them before switching. Only wallets built from a mnemonic, and Ledgers, derive accounts
+    pub async fn preview_derivation(
+        &self,
+        derivation_path: &str,
+        count: u32,
+    ) -> Result<Vec<(String, ChecksummedAddress)>> {
+        match self {
+            Self::Plaintext(wallet) => wallet.preview_derivation(derivation_path, count),
+            Self::HDWallet(wallet) => wallet.preview_derivation(derivation_path, count).await,
+            Self::Ledger(wallet) => wallet.preview_derivation(derivation_path, count).await,
+            _ => Err(Error::NoMnemonic),
+        }
+    }
+
+    /// Switches to another base derivation path, making the account at `idx` the current one
+    pub async fn set_derivation(&mut self, derivation_path: String, idx: u32) -> Result<()> {
+        match self {
+            Self::Plaintext(wallet) => wallet.set_derivation(derivation_path, idx),
+            Self::HDWallet(wallet) => wallet.set_derivation(derivation_path, idx).await,
+            Self::Ledger(wallet) => wallet.set_derivation(derivation_path, idx).await,
+            _ => Err(Error::NoMnemonic),
+        }
+    }
+
+    /// Builds a wallet from a hex-encoded private key
+    pub fn from_private_key(name: String, key: &str) -> Result<Wallet> {
+        Ok(PrivateKeyWallet::new(name, key)?.into())
+    }
+
+    /// Reads a wallet from the mnemonic in `IRON_MNEMONIC`, if set
+    pub fn from_env() -> Option<Result<Wallet>> {
+        PlaintextWallet::from_env().map(|wallet| wallet.map(Into::into))
+    }
+
+    /// The wallet to start with when none is configured
+    /// `IRON_MNEMONIC` takes precedence. Otherwise, debug builds fall back to the well-known anvil
+    /// test mnemonic, while release builds start with an empty watch-only wallet, so no one
+    /// accidentally funds a compromised key
+    pub fn initial() -> Wallet {
+        match Self::from_env() {
+            Some(Ok(wallet)) => return wallet,
+            Some(Err(e)) => tracing::warn!("Ignoring IRON_MNEMONIC: {}", e),
+            None => (),
+        }
+
+        if cfg!(debug_assertions) {
+            PlaintextWallet::default().into()
+        } else {
+            WatchOnly::new("empty".into(), Default::default()).into()
+        }
+    }
 }
 
 #[async_trait]
@@ -66,9 +329,57 @@ impl WalletCreate for Wallet {
             "jsonKeystore" => JsonKeystoreWallet::create(params).await?,
             "HDWallet" => HDWallet::create(params).await?,
             "impersonator" => Impersonator::create(params).await?,
+            "watchOnly" => WatchOnly::create(params).await?,
+            "privateKey" => PrivateKeyWallet::create(params).await?,
+            "ledger" => LedgerWallet::create(params).await?,
             _ => return Err(Error::InvalidWalletType(wallet_type.into())),
         };
 
         Ok(wallet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ethers::types::Signature;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn personal_sign_recovers_to_current_address() {
+        let wallet: Wallet = PlaintextWallet::default().into();
+        let address = wallet.get_current_address().await.0;
+
+        for message in [&b""[..], &b"hello"[..], &b"0x68656c6c6f"[..]] {
+            let signature = wallet.personal_sign(message).await.unwrap();
+            let signature = Signature::from_str(&signature).unwrap();
+
+            let raw = utils::message_bytes(message);
+            assert_eq!(signature.recover(raw).unwrap(), address, "{:?}", message);
+        }
+
+        // hex-encoded messages sign the bytes they encode
+        assert_eq!(
+            wallet.personal_sign(b"0x68656c6c6f").await.unwrap(),
+            wallet.personal_sign(b"hello").await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn switches_derivation_paths() {
+        let mut wallet: Wallet = PlaintextWallet::default().into();
+        let ledger_live = crate::DerivationPreset::LedgerLive.path();
+
+        let preview = wallet.preview_derivation(&ledger_live, 3).await.unwrap();
+        assert_eq!(preview.len(), 3);
+        assert_eq!(preview[2].0, "m/44'/60'/2'/0/0");
+
+        wallet.set_derivation(ledger_live, 2).await.unwrap();
+        assert_eq!(wallet.get_current_path(), "m/44'/60'/2'/0/0");
+        assert_eq!(wallet.get_current_address().await, preview[2].1);
+
+        assert!(wallet.preview_derivation("m/44'/0'/0'/0", 1).await.is_err());
+    }
+}
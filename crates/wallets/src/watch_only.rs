@@ -0,0 +1,212 @@
+use async_trait::async_trait;
+use iron_types::ChecksummedAddress;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{wallet::WalletCreate, Error, Result, Wallet, WalletControl, WalletSigner};
+
+/// Tracks addresses without holding any keys for them
+/// Useful for monitoring hardware wallets or multisigs. Their balances and history show up like
+/// any other account's, but any signing attempt fails
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Unvalidated")]
+pub struct WatchOnly {
+    #[serde(default)]
+    pub name: String,
+
+    /// Older versions watched a single `address`
+    #[serde(alias = "address", deserialize_with = "one_or_many")]
+    pub addresses: Vec<ChecksummedAddress>,
+
+    #[serde(default)]
+    pub current: usize,
+}
+
+/// A wallet as read from disk, which is validated like a new one before it's used, so that an
+/// edited file can't leave `current` out of bounds
+#[derive(Deserialize)]
+struct Unvalidated {
+    #[serde(default)]
+    name: String,
+    #[serde(alias = "address", deserialize_with = "one_or_many")]
+    addresses: Vec<ChecksummedAddress>,
+    #[serde(default)]
+    current: usize,
+}
+
+impl TryFrom<Unvalidated> for WatchOnly {
+    type Error = Error;
+
+    fn try_from(wallet: Unvalidated) -> Result<Self> {
+        Self {
+            name: wallet.name,
+            addresses: wallet.addresses,
+            current: wallet.current,
+        }
+        .validate()
+    }
+}
+
+impl WatchOnly {
+    pub fn new(name: String, address: ChecksummedAddress) -> Self {
+        Self {
+            name,
+            addresses: vec![address],
+            current: 0,
+        }
+    }
+
+    /// Drops duplicate addresses, and keeps the current one within bounds
+    fn validate(mut self) -> Result<Self> {
+        let mut seen = std::collections::HashSet::new();
+        self.addresses.retain(|a| seen.insert(*a));
+
+        if self.addresses.is_empty() {
+            return Err(Error::NoWatchedAddresses);
+        }
+        self.current = self.current.min(self.addresses.len() - 1);
+
+        Ok(self)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(ChecksummedAddress),
+    Many(Vec<ChecksummedAddress>),
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<ChecksummedAddress>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(address) => vec![address],
+        OneOrMany::Many(addresses) => addresses,
+    })
+}
+
+#[async_trait]
+impl WalletCreate for WatchOnly {
+    async fn create(params: serde_json::Value) -> Result<Wallet> {
+        let mut wallet = Self::validate(serde_json::from_value(params)?)?;
+
+        if wallet.name.is_empty() {
+            wallet.name = wallet.addresses[0].to_string();
+        }
+
+        Ok(Wallet::WatchOnly(wallet))
+    }
+}
+
+#[async_trait]
+impl WalletControl for WatchOnly {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    async fn update(mut self, params: serde_json::Value) -> Result<Wallet> {
+        if let Some(name) = params["name"].as_str() {
+            self.name = name.into();
+        }
+
+        if !params["addresses"].is_null() {
+            self.addresses = one_or_many(params["addresses"].clone())?;
+        } else if !params["address"].is_null() {
+            self.addresses = one_or_many(params["address"].clone())?;
+        }
+
+        if let Some(current) = params["current"].as_u64() {
+            self.current = current as usize;
+        }
+
+        Ok(Wallet::WatchOnly(self.validate()?))
+    }
+
+    async fn get_current_address(&self) -> ChecksummedAddress {
+        self.addresses[self.current]
+    }
+
+    fn get_current_path(&self) -> String {
+        self.addresses[self.current].to_string()
+    }
+
+    async fn set_current_path(&mut self, path: String) -> Result<()> {
+        self.current = self
+            .addresses
+            .iter()
+            .position(|a| a.to_string() == path)
+            .ok_or(Error::InvalidKey(path))?;
+
+        Ok(())
+    }
+
+    async fn get_all_addresses(&self) -> Vec<(String, ChecksummedAddress)> {
+        self.addresses.iter().map(|a| (a.to_string(), *a)).collect()
+    }
+
+    async fn build_signer(&self, _chain_id: u32, path: &str) -> Result<WalletSigner> {
+        Err(Error::WatchOnly(path.into()))
+    }
+
+    fn ensure_can_sign(&self, path: &str) -> Result<()> {
+        Err(Error::WatchOnly(path.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ethers::types::Address;
+    use serde_json::json;
+
+    use super::*;
+
+    const ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+    const OTHER: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+
+    #[tokio::test]
+    async fn reads_single_address_configs() {
+        let wallet: WatchOnly =
+            serde_json::from_value(json!({ "name": "old", "address": ADDRESS })).unwrap();
+
+        assert_eq!(
+            wallet.get_current_address().await,
+            Address::from_str(ADDRESS).unwrap().into()
+        );
+
+        let wallet: WatchOnly =
+            serde_json::from_value(json!({ "addresses": [ADDRESS], "current": 3 })).unwrap();
+        assert_eq!(wallet.current, 0);
+        assert!(serde_json::from_value::<WatchOnly>(json!({ "addresses": [] })).is_err());
+    }
+
+    #[tokio::test]
+    async fn watches_many_addresses_and_never_signs() {
+        let Wallet::WatchOnly(mut wallet) = WatchOnly::create(json!({
+            "addresses": [ADDRESS, OTHER, ADDRESS],
+        }))
+        .await
+        .unwrap() else {
+            unreachable!()
+        };
+
+        assert_eq!(wallet.addresses.len(), 2);
+        assert_eq!(wallet.name, ADDRESS);
+
+        wallet.set_current_path(OTHER.into()).await.unwrap();
+        assert_eq!(wallet.current, 1);
+
+        assert!(matches!(
+            wallet.ensure_can_sign(OTHER),
+            Err(Error::WatchOnly(_))
+        ));
+        assert!(wallet.build_signer(1, OTHER).await.is_err());
+        assert!(WatchOnly::create(json!({ "addresses": [] })).await.is_err());
+    }
+}
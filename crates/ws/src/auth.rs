@@ -0,0 +1,135 @@
+//! The token peers authenticate with when connecting
+//! Any local process can reach the server's port, so only connections carrying the token shared
+//! with the extension when it's paired are accepted. It's generated on first start, and kept
+//! until the user regenerates it
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use ethers::{
+    core::rand::{thread_rng, RngCore},
+    utils::hex,
+};
+use once_cell::sync::OnceCell;
+
+use crate::WsResult;
+
+static TOKEN: OnceCell<Mutex<Token>> = OnceCell::new();
+
+struct Token {
+    file: PathBuf,
+    value: String,
+}
+
+/// Reads the token from its file, creating one if there's none yet
+pub(crate) fn init(file: PathBuf) -> WsResult<()> {
+    let value = match fs::read_to_string(&file) {
+        // files written before they were made private may still be readable by others
+        Ok(value) if !value.trim().is_empty() => {
+            restrict(&file)?;
+            value.trim().to_string()
+        }
+        _ => generate(&file)?,
+    };
+
+    let _ = TOKEN.set(Mutex::new(Token { file, value }));
+    Ok(())
+}
+
+/// The current token, for the user to pair the extension with
+pub(crate) fn get() -> Option<String> {
+    let token = TOKEN.get()?.lock().unwrap_or_else(|e| e.into_inner());
+
+    Some(token.value.clone())
+}
+
+/// Replaces the token, so that previously paired extensions have to be paired again
+pub(crate) fn regenerate() -> WsResult<Option<String>> {
+    let Some(token) = TOKEN.get() else {
+        return Ok(None);
+    };
+    let mut token = token.lock().unwrap_or_else(|e| e.into_inner());
+
+    token.value = generate(&token.file)?;
+    Ok(Some(token.value.clone()))
+}
+
+/// Whether a connection presented the current token
+/// Connections are refused until the token is loaded
+pub(crate) fn verify(given: Option<&str>) -> bool {
+    let (Some(expected), Some(given)) = (get(), given) else {
+        return false;
+    };
+
+    constant_time_eq(expected.as_bytes(), given.as_bytes())
+}
+
+fn generate(file: &Path) -> WsResult<String> {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill_bytes(&mut bytes);
+    let value = hex::encode(bytes);
+
+    if file.exists() {
+        restrict(file)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(file)?.write_all(value.as_bytes())?;
+
+    Ok(value)
+}
+
+/// Makes the token file readable by the current user only, since anyone who can read it can
+/// connect
+fn restrict(file: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(file, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = file;
+
+    Ok(())
+}
+
+/// Compares without returning early, so that timing doesn't leak how much of a guess was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert!(!constant_time_eq(b"", b"a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn token_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let file = std::env::temp_dir().join(format!("iron-token-{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let token = generate(&file).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), token);
+
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_file(&file).unwrap();
+    }
+}
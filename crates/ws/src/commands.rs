@@ -1,10 +1,49 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, net::SocketAddr};
 
 use iron_types::GlobalState;
 
-use crate::{Peer, Peers};
+use crate::{auth, Peer, Peers, Session, WsResult};
 
 #[tauri::command]
 pub async fn ws_peers_by_domain() -> HashMap<String, Vec<Peer>> {
     Peers::read().await.all_by_domain()
 }
+
+/// Lists every connected peer, with when it connected, its chain, and the accounts it can see
+#[tauri::command]
+pub async fn ws_get_sessions() -> Vec<Session> {
+    Peers::read().await.sessions().await
+}
+
+/// Closes a peer's connection
+/// The dapp may reconnect on its own, but it has to ask for accounts again if it was revoked
+#[tauri::command]
+pub async fn ws_disconnect_peer(socket: SocketAddr) -> WsResult<()> {
+    Peers::write().await.disconnect(socket).await
+}
+
+/// Pins a single peer to a network, or unpins it if no network is given
+#[tauri::command]
+pub async fn ws_set_peer_network(socket: SocketAddr, network: Option<String>) -> WsResult<()> {
+    let mut peers = Peers::write().await;
+
+    match network {
+        Some(name) => peers.set_peer_network(socket, &name).await,
+        None => peers.clear_peer_network(socket).await,
+    }
+}
+
+/// The token to pair the browser extension with
+#[tauri::command]
+pub async fn ws_get_connection_token() -> Option<String> {
+    auth::get()
+}
+
+/// Replaces the connection token, disconnecting every peer that authenticated with the old one
+#[tauri::command]
+pub async fn ws_regenerate_connection_token() -> WsResult<Option<String>> {
+    let token = auth::regenerate()?;
+    Peers::write().await.disconnect_all().await;
+
+    Ok(token)
+}
@@ -8,6 +8,33 @@ pub enum WsError {
 
     #[error(transparent)]
     TauriError(#[from] tauri::Error),
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Networks(#[from] iron_networks::Error),
+
+    #[error(transparent)]
+    Connections(#[from] iron_connections::Error),
+
+    #[error(transparent)]
+    Provider(#[from] ethers::providers::ProviderError),
+
+    #[error("unsupported subscription: {0}")]
+    UnsupportedSubscription(String),
+
+    #[error("unknown peer: {0}")]
+    UnknownPeer(std::net::SocketAddr),
 }
 
 pub type WsResult<T> = std::result::Result<T, WsError>;
+
+impl serde::Serialize for WsError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
@@ -0,0 +1,131 @@
+use iron_networks::Network;
+use iron_types::{ChecksummedAddress, Json, TxUpdate};
+use serde::Serialize;
+
+/// An EIP-1193 event pushed to peers
+/// Serializes to the `{ "method": ..., "params": ... }` shape the extension listens for
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "method", content = "params", rename_all = "camelCase")]
+pub enum ProviderEvent {
+    AccountsChanged(Vec<ChecksummedAddress>),
+
+    #[serde(rename_all = "camelCase")]
+    ChainChanged {
+        chain_id: String,
+        native_currency: Currency,
+    },
+
+    #[serde(rename_all = "camelCase")]
+    Connect {
+        chain_id: String,
+    },
+
+    Disconnect {
+        code: u32,
+        message: String,
+    },
+
+    /// A custom app-level notice
+    Message {
+        #[serde(rename = "type")]
+        type_: String,
+        data: Json,
+    },
+
+    /// A transaction sent by this peer was mined, replaced or dropped
+    TransactionUpdated(TxUpdate),
+
+    /// A notification for a subscription opened through `eth_subscribe`
+    #[serde(rename = "eth_subscription")]
+    Subscription {
+        subscription: String,
+        result: Json,
+    },
+
+    /// How the wallet presents itself to dapps through EIP-6963, sent once a peer connects
+    AnnounceProvider(ProviderInfo),
+}
+
+/// The EIP-6963 provider info, which dapps list the wallet by when several are installed
+/// The UUID is left to the page, since it has to stay the same for as long as the page is open,
+/// including before the page first connects
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderInfo {
+    pub name: &'static str,
+
+    /// A data URI, at least 96x96
+    pub icon: &'static str,
+
+    /// Reverse DNS name, which stays the same across versions, unlike the name
+    pub rdns: &'static str,
+}
+
+impl ProviderInfo {
+    pub const IRON: Self = Self {
+        name: "Iron",
+        icon: ICON,
+        rdns: "eth.iron-wallet",
+    };
+}
+
+/// Iron's logo as a base64-encoded SVG data URI, 96x96
+const ICON: &str = "data:image/svg+xml;base64,PD94bWwgdmVyc2lvbj0iMS4wIiBlbmNvZGluZz0idXRmLTgiPz4KPCEtLSBHZW5lcmF0b3I6IEFkb2JlIElsbHVzdHJhdG9yIDI3LjIuMCwgU1ZHIEV4cG9ydCBQbHVnLUluIC4gU1ZHIFZlcnNpb246IDYuMDAgQnVpbGQgMCkgIC0tPgo8c3ZnIHZlcnNpb249IjEuMSIgaWQ9IkNhbWFkYV8xIiB4bWxucz0iaHR0cDovL3d3dy53My5vcmcvMjAwMC9zdmciIHhtbG5zOnhsaW5rPSJodHRwOi8vd3d3LnczLm9yZy8xOTk5L3hsaW5rIiB4PSIwcHgiIHk9IjBweCIKCSB2aWV3Qm94PSIwIDAgNjAwIDYwMCIgc3R5bGU9ImVuYWJsZS1iYWNrZ3JvdW5kOm5ldyAwIDAgNjAwIDYwMDsiIHhtbDpzcGFjZT0icHJlc2VydmUiIHdpZHRoPSI5NiIgaGVpZ2h0PSI5NiI+CjxzdHlsZSB0eXBlPSJ0ZXh0L2NzcyI+Cgkuc3Qwe2ZpbGw6IzE2MTYxNjt9Cgkuc3Qxe2ZpbGw6I0YxRjFGMTt9Cjwvc3R5bGU+CjxnPgoJPHBvbHlnb24gY2xhc3M9InN0MCIgcG9pbnRzPSIyMTcuMiwzNzIgMjYzLjgsMzcyIDMwMCw0MjEuMSAzMzYuMiwzNzIgMzgyLjgsMzcyIDMwMCwyNjAuOCAJIi8+Cgk8cGF0aCBjbGFzcz0ic3QwIiBkPSJNNDY5LDBIMTMxQzU4LjYsMCwwLDU4LjYsMCwxMzFWNDY5YzAsNzIuMyw1OC42LDEzMSwxMzEsMTMxSDQ2OWM3Mi4zLDAsMTMxLTU4LjYsMTMxLTEzMVYxMzEKCQlDNjAwLDU4LjYsNTQxLjQsMCw0NjksMHogTTM4NCwzNzJsLTg0LDExNGwtODQtMTE0SDEwOGwxOTItMjU4bDE5MiwyNThIMzg0eiIvPgo8L2c+Cjwvc3ZnPgo=";
+
+/// The native currency of the chain a peer switched to
+#[derive(Debug, Clone, Serialize)]
+pub struct Currency {
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+impl ProviderEvent {
+    pub fn chain_changed(network: &Network) -> Self {
+        let (symbol, decimals) = network.native_currency();
+
+        Self::ChainChanged {
+            chain_id: network.chain_id_hex(),
+            native_currency: Currency { symbol, decimals },
+        }
+    }
+
+    pub fn announce_provider() -> Self {
+        Self::AnnounceProvider(ProviderInfo::IRON)
+    }
+
+    pub fn connect(chain_id: u32) -> Self {
+        Self::Connect {
+            chain_id: format!("0x{:x}", chain_id),
+        }
+    }
+
+    /// EIP-1193 code 4901: the provider is disconnected from the requested chain
+    pub fn disconnect(chain_id: u32) -> Self {
+        Self::Disconnect {
+            code: 4901,
+            message: format!("chain {} is unreachable", chain_id),
+        }
+    }
+
+    /// EIP-1193 code 4900: the provider is disconnected from all chains, because the wallet is
+    /// shutting down
+    pub fn shutdown() -> Self {
+        Self::Disconnect {
+            code: 4900,
+            message: "wallet is shutting down".into(),
+        }
+    }
+
+    /// The name peers subscribe to this event by
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AccountsChanged(_) => "accountsChanged",
+            Self::ChainChanged { .. } => "chainChanged",
+            Self::Connect { .. } => "connect",
+            Self::Disconnect { .. } => "disconnect",
+            Self::Message { .. } => "message",
+            Self::TransactionUpdated(_) => "transactionUpdated",
+            Self::Subscription { .. } => "eth_subscription",
+            Self::AnnounceProvider(_) => "announceProvider",
+        }
+    }
+}
@@ -1,16 +1,35 @@
+use std::{path::PathBuf, time::Duration};
+
 use async_trait::async_trait;
 use iron_broadcast::InternalMsg;
 use iron_types::GlobalState;
 use once_cell::sync::Lazy;
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use crate::{peers::Peers, server::server_loop};
+use crate::{auth, peers::Peers, server::server_loop};
 
 static PEERS: Lazy<RwLock<Peers>> = Lazy::new(Default::default);
 
-pub async fn init() {
+/// How long a peer may go without answering pings before it is dropped
+const MAX_PEER_IDLE: Duration = Duration::from_secs(60);
+
+/// Starts the server, with peers authenticating through the token kept in `token_file`
+pub async fn init(token_file: PathBuf) {
+    if let Err(e) = auth::init(token_file) {
+        tracing::error!(
+            "Failed to load the connection token, refusing all peers: {}",
+            e
+        );
+    }
+
     tokio::spawn(async { server_loop().await });
     tokio::spawn(async { receiver().await });
+    tokio::spawn(async { prune_loop().await });
+}
+
+/// Disconnects all peers ahead of the app exiting
+pub async fn shutdown() {
+    Peers::write().await.shutdown().await;
 }
 
 #[async_trait]
@@ -24,6 +43,15 @@ impl GlobalState for Peers {
     }
 }
 
+async fn prune_loop() -> ! {
+    let mut interval = tokio::time::interval(MAX_PEER_IDLE / 2);
+
+    loop {
+        interval.tick().await;
+        Peers::write().await.prune_stale_peers(MAX_PEER_IDLE).await;
+    }
+}
+
 async fn receiver() -> ! {
     let mut rx = iron_broadcast::subscribe_internal().await;
 
@@ -33,13 +61,42 @@ async fn receiver() -> ! {
 
             match msg {
                 ChainChanged(chain_id, domain, affinity) => {
-                    Peers::read()
+                    Peers::write()
                         .await
                         .broadcast_chain_changed(chain_id, domain, affinity)
                         .await
                 }
+                PeerChainChanged(socket, chain_id) => {
+                    let _ = Peers::write()
+                        .await
+                        .send_chain_changed(socket, chain_id)
+                        .await;
+                }
+                PeerTxUpdated(socket, update) => {
+                    Peers::write().await.send_tx_updated(socket, update).await
+                }
                 AccountsChanged(accounts) => {
-                    Peers::read().await.broadcast_accounts_changed(accounts)
+                    Peers::write()
+                        .await
+                        .broadcast_accounts_changed(accounts)
+                        .await
+                }
+                OriginAccessChanged(origin) => Peers::write().await.refresh_accounts(&origin).await,
+                PeerMessage {
+                    type_,
+                    data,
+                    domain,
+                } => {
+                    Peers::write()
+                        .await
+                        .broadcast_message(&type_, data, domain)
+                        .await
+                }
+                NetworkDisconnected(chain_id) => {
+                    Peers::write().await.broadcast_disconnect(chain_id).await
+                }
+                NetworkConnected(chain_id) => {
+                    Peers::write().await.broadcast_connect(chain_id).await
                 }
                 _ => {}
             }
@@ -1,9 +1,13 @@
+mod auth;
 pub mod commands;
 mod error;
+mod events;
 mod init;
 mod peers;
 mod server;
+mod subscriptions;
 
 pub use error::{WsError, WsResult};
-pub use init::init;
-use peers::{Peer, Peers};
+pub use events::{Currency, ProviderEvent, ProviderInfo};
+pub use init::{init, shutdown};
+pub use peers::{Peer, Peers, Session};
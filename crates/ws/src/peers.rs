@@ -1,10 +1,20 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use iron_networks::Networks;
-use iron_types::{Affinity, ChecksummedAddress, GlobalState, UINotify};
+use ethers::providers::Provider;
+use iron_connections::{Ctx, Store};
+use iron_networks::{Failover, Networks};
+use iron_types::{
+    Affinity, ChecksummedAddress, GlobalState, Json, RequestSource, TxUpdate, UINotify,
+};
 use serde::Serialize;
-use serde_json::json;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
+
+use crate::{ProviderEvent, WsError, WsResult};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct Peer {
@@ -14,25 +24,46 @@ pub struct Peer {
     pub tab_id: Option<u32>,
     pub title: Option<String>,
     pub socket: SocketAddr,
+
+    /// Events this peer opted into when connecting, e.g. `accountsChanged`
+    /// `None` means it receives all of them
+    pub subscriptions: Option<HashSet<String>>,
+
+    /// When the peer connected, in seconds since the epoch
+    pub connected_at: u64,
+
     #[serde(skip)]
-    pub sender: mpsc::UnboundedSender<serde_json::Value>,
+    pub sender: mpsc::UnboundedSender<ProviderEvent>,
+
+    /// Last time anything was heard from this peer, including replies to pings
+    /// Shared with the peer's connection task, which updates it on every message without taking
+    /// a lock on the whole list of peers
+    #[serde(skip)]
+    last_seen: LastSeen,
+
+    /// Signalled once the peer is dropped, to end its connection task
+    /// The task keeps senders of its own, for subscriptions, so the channel closing can't be
+    /// relied on to end it
+    #[serde(skip)]
+    closed: Arc<Notify>,
 }
 
 impl Peer {
     pub fn new(
         socket: SocketAddr,
-        sender: mpsc::UnboundedSender<serde_json::Value>,
+        sender: mpsc::UnboundedSender<ProviderEvent>,
         params: &HashMap<String, String>,
     ) -> Self {
-        let origin = params
-            .get("origin")
-            .cloned()
-            .unwrap_or(String::from("unknown"));
+        // connections without one are refused, so peers never share an approval by lacking it
+        let origin = params.get("origin").cloned().unwrap_or_default();
 
         let url = params.get("url").cloned();
         let favicon = params.get("favicon").cloned();
         let tab_id = params.get("tabId").cloned().and_then(|id| id.parse().ok());
         let title = params.get("title").cloned();
+        let subscriptions = params
+            .get("events")
+            .map(|events| events.split(',').map(|e| e.trim().to_string()).collect());
 
         Self {
             socket,
@@ -42,6 +73,94 @@ impl Peer {
             url,
             tab_id,
             title,
+            subscriptions,
+            connected_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            last_seen: Default::default(),
+            closed: Default::default(),
+        }
+    }
+
+    /// Tells the peer's connection task to close the socket
+    /// The signal is kept until the task gets to it, so it's never missed
+    pub(crate) fn close(&self) {
+        self.closed.notify_one();
+    }
+
+    /// What the peer's connection task waits on to close the socket
+    pub(crate) fn closed(&self) -> Arc<Notify> {
+        self.closed.clone()
+    }
+
+    /// Marks this peer as still alive
+    pub fn touch(&self) {
+        self.last_seen.touch();
+    }
+
+    /// How long since anything was last heard from this peer
+    pub fn idle_for(&self) -> Duration {
+        self.last_seen.elapsed()
+    }
+
+    /// A handle to mark this peer as alive with, which doesn't keep its channel open
+    pub(crate) fn last_seen(&self) -> LastSeen {
+        self.last_seen.clone()
+    }
+
+    /// Whether this peer wants to receive a given event
+    pub fn is_subscribed(&self, event: &str) -> bool {
+        match self.subscriptions {
+            Some(ref events) => events.contains(event),
+            None => true,
+        }
+    }
+
+    /// Metadata identifying this peer as the source of a request
+    pub fn source(&self) -> RequestSource {
+        RequestSource {
+            socket: self.socket,
+            origin: self.origin.clone(),
+            title: self.title.clone(),
+            favicon: self.favicon.clone(),
+        }
+    }
+
+    /// Tells a newly connected peer which chain it's on
+    /// Each domain remembers the last chain it switched to, so this restores it across reconnects
+    pub async fn send_current_chain(&self) {
+        let ctx = Ctx {
+            domain: self.domain(),
+            source: None,
+        };
+        let Ok(chain_id) = ctx.chain_id().await else {
+            return;
+        };
+
+        if let Some(network) = Networks::read().await.get_network(chain_id) {
+            self.sender
+                .send(ProviderEvent::chain_changed(&network))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to send message to peer: {}", e);
+                });
+        }
+    }
+
+    /// Introduces the wallet to the peer, for the page to announce it through EIP-6963
+    pub fn announce(&self) {
+        self.sender
+            .send(ProviderEvent::announce_provider())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to send message to peer: {}", e);
+            });
+    }
+
+    /// The context requests from this peer are handled with
+    pub fn ctx(&self) -> Ctx {
+        Ctx {
+            domain: self.domain(),
+            source: Some(self.source()),
         }
     }
 
@@ -55,9 +174,42 @@ impl Peer {
     }
 }
 
+/// When a peer was last heard from
+#[derive(Clone, Debug)]
+pub(crate) struct LastSeen(Arc<Mutex<Instant>>);
+
+impl Default for LastSeen {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+}
+
+impl LastSeen {
+    pub fn touch(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+/// A peer, along with what it was granted, as listed in the sessions panel
+#[derive(Debug, Serialize)]
+pub struct Session {
+    #[serde(flatten)]
+    pub peer: Peer,
+
+    /// The chain the peer is on
+    pub chain_id: Option<u32>,
+
+    /// The accounts the peer gets to see, which are none until its origin is approved
+    pub accounts: Vec<ChecksummedAddress>,
+}
+
 impl From<Peer> for iron_rpc::Handler {
     fn from(value: Peer) -> Self {
-        Self::new(value.domain())
+        Self::new(value.ctx())
     }
 }
 
@@ -72,41 +224,249 @@ impl Peers {
     /// Adds a new peer
     pub async fn add_peer(&mut self, peer: Peer) {
         self.map.insert(peer.socket, peer);
+        iron_broadcast::ui_notify(UINotify::PeerConnected).await;
         iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
         //self.window_snd.send(UINotify::PeersUpdated.into()).unwrap();
     }
 
     /// Removes an existing peer
+    /// Removing a peer that is already gone is a no-op, so a connection closed from the UI isn't
+    /// reported twice once its task ends
     pub async fn remove_peer(&mut self, peer: SocketAddr) {
-        self.map.remove(&peer);
+        let Some(removed) = self.map.remove(&peer) else {
+            return;
+        };
+        removed.close();
+        Store::write().await.forget_peer(peer);
+        iron_broadcast::ui_notify(UINotify::PeerDisconnected).await;
         iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
         //self.window_snd.send(UINotify::PeersUpdated.into()).unwrap();
     }
 
-    /// Broadcasts an `accountsChanged` event to all peers
-    pub fn broadcast_accounts_changed(&self, new_accounts: Vec<ChecksummedAddress>) {
-        self.broadcast(json!({
-            "method": "accountsChanged",
-            "params": new_accounts
-        }));
+    /// Closes a peer's connection
+    /// Removing the peer signals its connection task to close the socket
+    pub async fn disconnect(&mut self, peer: SocketAddr) -> WsResult<()> {
+        self.get_peer(peer)?;
+        tracing::info!(event = "peer disconnected by user", %peer);
+        self.remove_peer(peer).await;
+
+        Ok(())
+    }
+
+    /// Closes every peer's connection, such as when the token they authenticated with is revoked
+    pub async fn disconnect_all(&mut self) {
+        let sockets: Vec<_> = self.map.keys().copied().collect();
+        tracing::info!(event = "all peers disconnected", count = sockets.len());
+
+        for socket in sockets {
+            self.remove_peer(socket).await;
+        }
+    }
+
+    /// Every connected peer, oldest first, along with its chain and the accounts it can see
+    pub async fn sessions(&self) -> Vec<Session> {
+        let mut sessions = Vec::with_capacity(self.map.len());
+
+        for peer in self.map.values() {
+            let ctx = peer.ctx();
+            let accounts = match ctx.is_approved().await {
+                true => iron_rpc::Handler::visible_accounts(&ctx).await,
+                false => vec![],
+            };
+
+            sessions.push(Session {
+                chain_id: ctx.chain_id().await.ok(),
+                accounts,
+                peer: peer.clone(),
+            });
+        }
+
+        sessions.sort_by_key(|s| s.peer.connected_at);
+        sessions
     }
 
-    /// Broadcasts a `chainChanged` event to all peers
+    /// Drops peers that haven't been heard from within `max_idle`
+    /// This covers tabs that crashed without cleanly closing their connection. Dropping them also
+    /// signals their connection tasks to end
+    pub async fn prune_stale_peers(&mut self, max_idle: Duration) {
+        let stale = self
+            .map
+            .values()
+            .filter(|peer| peer.idle_for() > max_idle)
+            .map(|peer| peer.socket)
+            .collect();
+
+        self.remove_dead(stale).await;
+    }
+
+    /// Pins a single peer to a network, independently of the current one and of its domain's
+    /// affinity
+    /// Only that peer is sent a `chainChanged` event
+    pub async fn set_peer_network(&mut self, peer: SocketAddr, network_name: &str) -> WsResult<()> {
+        self.get_peer(peer)?;
+        let network = Networks::read()
+            .await
+            .get_network_by_name(network_name)
+            .ok_or_else(|| iron_networks::Error::UnknownNetwork(network_name.into()))?;
+
+        Store::write()
+            .await
+            .set_peer_chain_id(peer, Some(network.chain_id));
+        self.send_chain_changed(peer, network.chain_id).await
+    }
+
+    /// Unpins a peer, returning it to whichever network its domain would otherwise use
+    pub async fn clear_peer_network(&mut self, peer: SocketAddr) -> WsResult<()> {
+        Store::write().await.set_peer_chain_id(peer, None);
+
+        let chain_id = self.get_peer(peer)?.ctx().chain_id().await?;
+        self.send_chain_changed(peer, chain_id).await
+    }
+
+    /// A provider for the network a peer is on
+    /// Falls back to its domain's affinity, and then to the current network, if it isn't pinned
+    pub async fn get_provider_for_peer(&self, peer: SocketAddr) -> WsResult<Provider<Failover>> {
+        let network = self.get_peer(peer)?.ctx().network().await?;
+
+        Ok(network.get_provider()?)
+    }
+
+    /// Sends a `chainChanged` event to a single peer, if it is subscribed to it
+    pub async fn send_chain_changed(&mut self, peer: SocketAddr, chain_id: u32) -> WsResult<()> {
+        self.get_peer(peer)?;
+
+        if let Some(network) = Networks::read().await.get_network(chain_id) {
+            tracing::info!(event = "peer chain changed", %peer, chain_id);
+            let event = ProviderEvent::chain_changed(&network);
+            let name = event.name();
+            self.broadcast_to(|p| p.socket == peer && p.is_subscribed(name), event)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a `transactionUpdated` event to a single peer, if it's still around
+    pub async fn send_tx_updated(&mut self, peer: SocketAddr, update: TxUpdate) {
+        let event = ProviderEvent::TransactionUpdated(update);
+        let name = event.name();
+
+        self.broadcast_to(|p| p.socket == peer && p.is_subscribed(name), event)
+            .await;
+    }
+
+    fn get_peer(&self, peer: SocketAddr) -> WsResult<&Peer> {
+        self.map.get(&peer).ok_or(WsError::UnknownPeer(peer))
+    }
+
+    /// Broadcasts an `accountsChanged` event to all subscribed peers
+    /// Only peers whose origin the user approved get to see accounts, and those scoped to a single
+    /// account only ever see that one
+    pub async fn broadcast_accounts_changed(&mut self, new_accounts: Vec<ChecksummedAddress>) {
+        // taken before broadcasting, since dropping dead peers along the way writes to the store
+        let visible = {
+            let store = Store::read().await;
+            self.map
+                .values()
+                .filter(|peer| store.is_approved(&peer.origin))
+                .map(|peer| {
+                    let accounts = match store.get_scoped_account(&peer.origin) {
+                        Some(account) => vec![account],
+                        None => new_accounts.clone(),
+                    };
+                    (peer.socket, accounts)
+                })
+                .collect()
+        };
+
+        self.send_accounts(visible).await;
+    }
+
+    /// Sends an origin's peers the accounts they can see, after its approval changed
+    /// Revoked origins get an empty list, so that dapps stop showing accounts they can't use
+    pub async fn refresh_accounts(&mut self, origin: &str) {
+        let mut visible = HashMap::new();
+
+        for peer in self.map.values().filter(|peer| peer.origin == origin) {
+            let ctx = peer.ctx();
+            let accounts = match ctx.is_approved().await {
+                true => iron_rpc::Handler::visible_accounts(&ctx).await,
+                false => vec![],
+            };
+            visible.insert(peer.socket, accounts);
+        }
+
+        self.send_accounts(visible).await;
+    }
+
+    /// Sends each subscribed peer its own `accountsChanged` event
+    async fn send_accounts(&mut self, visible: HashMap<SocketAddr, Vec<ChecksummedAddress>>) {
+        let dead = self
+            .map
+            .values()
+            .filter(|peer| peer.is_subscribed("accountsChanged"))
+            .filter_map(|peer| Some((peer, visible.get(&peer.socket)?)))
+            .filter(|(peer, accounts)| {
+                let event = ProviderEvent::AccountsChanged(accounts.to_vec());
+                peer.sender.send(event).is_err()
+            })
+            .map(|(peer, _)| peer.socket)
+            .collect();
+
+        self.remove_dead(dead).await;
+    }
+
+    /// Broadcasts a custom EIP-1193 `message` event to all subscribed peers
+    /// If a domain is given, only peers connected from it receive the event
+    pub async fn broadcast_message(&mut self, type_: &str, data: Json, domain: Option<String>) {
+        let event = ProviderEvent::Message {
+            type_: type_.into(),
+            data,
+        };
+
+        match domain {
+            Some(domain) => self.broadcast_to_domain(&domain, event).await,
+            None => self.broadcast_event(event).await,
+        }
+    }
+
+    /// Broadcasts an EIP-1193 `disconnect` event, after the current network's RPC became
+    /// unreachable
+    pub async fn broadcast_disconnect(&mut self, chain_id: u32) {
+        self.broadcast_event(ProviderEvent::disconnect(chain_id))
+            .await;
+    }
+
+    /// Broadcasts an EIP-1193 `connect` event, after the current network's RPC recovered
+    pub async fn broadcast_connect(&mut self, chain_id: u32) {
+        self.broadcast_event(ProviderEvent::connect(chain_id)).await;
+    }
+
+    /// Broadcasts a `chainChanged` event to all subscribed peers
     pub async fn broadcast_chain_changed(
-        &self,
+        &mut self,
         chain_id: u32,
         domain: Option<String>,
         affinity: Affinity,
     ) {
-        if Networks::read().await.validate_chain_id(chain_id) {
-            let msg = json!({
-                "method": "chainChanged",
-                "params": {
-                    "chainId": format!("0x{:x}", chain_id),
-                }
-            });
+        if let Some(network) = Networks::read().await.get_network(chain_id) {
+            let event = ProviderEvent::chain_changed(&network);
+            let mut dead = vec![];
+            let pinned: HashSet<_> = {
+                let store = Store::read().await;
+                self.map
+                    .keys()
+                    .filter(|socket| store.get_peer_chain_id(socket).is_some())
+                    .copied()
+                    .collect()
+            };
 
-            for (_, peer) in self.map.iter() {
+            // pinned peers stay on their own network
+            for peer in self
+                .map
+                .values()
+                .filter(|p| p.is_subscribed(event.name()) && !pinned.contains(&p.socket))
+            {
                 if iron_connections::utils::affinity_matches(peer.domain(), &domain, affinity).await
                 {
                     tracing::info!(
@@ -114,24 +474,89 @@ impl Peers {
                         domain = peer.domain(),
                         chain_id
                     );
-                    peer.sender
-                        .send(serde_json::to_value(&msg).unwrap())
-                        .unwrap_or_else(|e| {
-                            tracing::warn!("Failed to send message to peer: {}", e);
-                        });
+                    if peer.sender.send(event.clone()).is_err() {
+                        dead.push(peer.socket);
+                    }
                 }
             }
+
+            self.remove_dead(dead).await;
         }
     }
 
-    fn broadcast<T: Serialize + std::fmt::Debug>(&self, msg: T) {
-        self.map.iter().for_each(|(_, peer)| {
-            peer.sender
-                .send(serde_json::to_value(&msg).unwrap())
-                .unwrap_or_else(|e| {
-                    tracing::warn!("Failed to send message to peer: {}", e);
-                });
-        });
+    /// Tells every peer the wallet is going away, then drops them all
+    /// Each connection task is signalled to close its socket, so dapps see it close instead of
+    /// hanging on a dead channel. Calling this again is a no-op, since no peers are left
+    pub async fn shutdown(&mut self) {
+        if self.map.is_empty() {
+            return;
+        }
+
+        self.broadcast_event(ProviderEvent::shutdown()).await;
+
+        let mut store = Store::write().await;
+        for peer in self.map.values() {
+            store.forget_peer(peer.socket);
+            peer.close();
+        }
+        drop(store);
+
+        self.map.clear();
+    }
+
+    /// Sends an event to all subscribed peers connected from a given domain
+    pub async fn broadcast_to_domain(&mut self, domain: &str, event: ProviderEvent) {
+        let name = event.name();
+
+        self.broadcast_to(
+            |peer| peer.is_subscribed(name) && peer.domain().as_deref() == Some(domain),
+            event,
+        )
+        .await;
+    }
+
+    /// Sends an event to all peers matching a predicate
+    pub async fn broadcast_to<P>(&mut self, predicate: P, event: ProviderEvent)
+    where
+        P: Fn(&Peer) -> bool,
+    {
+        let dead = self
+            .map
+            .values()
+            .filter(|peer| predicate(peer))
+            .filter(|peer| peer.sender.send(event.clone()).is_err())
+            .map(|peer| peer.socket)
+            .collect();
+
+        self.remove_dead(dead).await;
+    }
+
+    async fn broadcast_event(&mut self, event: ProviderEvent) {
+        let name = event.name();
+
+        self.broadcast_to(|peer| peer.is_subscribed(name), event)
+            .await;
+    }
+
+    /// Drops peers whose channel was found closed while sending them a message
+    /// This happens when a tab closes right as an event is being broadcast
+    async fn remove_dead(&mut self, dead: Vec<SocketAddr>) {
+        if dead.is_empty() {
+            return;
+        }
+
+        let mut store = Store::write().await;
+        for socket in dead {
+            tracing::warn!("Dropping disconnected peer {}", socket);
+            if let Some(peer) = self.map.remove(&socket) {
+                peer.close();
+            }
+            store.forget_peer(socket);
+        }
+        drop(store);
+
+        iron_broadcast::ui_notify(UINotify::PeerDisconnected).await;
+        iron_broadcast::ui_notify(UINotify::PeersUpdated).await;
     }
 
     pub(crate) fn all_by_domain(&self) -> HashMap<String, Vec<Peer>> {
@@ -1,7 +1,8 @@
 use std::{collections::HashMap, net::SocketAddr};
 
-use futures_util::{SinkExt, StreamExt};
-use iron_types::GlobalState;
+use futures_util::{future::join_all, SinkExt, StreamExt};
+use iron_connections::Ctx;
+use iron_types::{GlobalState, Json};
 use tokio::{
     net::{TcpListener, TcpStream},
     sync::mpsc,
@@ -9,38 +10,74 @@ use tokio::{
 use tokio_tungstenite::{accept_hdr_async, WebSocketStream};
 use tungstenite::{
     handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
     Message,
 };
 use url::Url;
 
 pub use crate::error::{WsError, WsResult};
-use crate::peers::{Peer, Peers};
+use crate::{
+    auth,
+    peers::{Peer, Peers},
+    subscriptions::Subscriptions,
+    ProviderEvent,
+};
 
 pub(crate) async fn server_loop() {
     let addr = std::env::var("IRON_SERVER_ENDPOINT").unwrap_or("127.0.0.1:9002".into());
-    let listener = TcpListener::bind(&addr).await.expect("Can't listen to");
 
-    while let Ok((stream, _)) = listener.accept().await {
-        let peer = stream
-            .peer_addr()
-            .expect("connected streams should have a peer address");
+    // e.g. when another instance already holds the port. the rest of the app still works, but
+    // dapps can't reach it, which the user needs to know about
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to listen on {}: {}", addr, e);
+            let message = format!("Dapps can't connect, since {} is unavailable: {}", addr, e);
+            iron_broadcast::error("Connection server failed".into(), message).await;
+            return;
+        }
+    };
 
-        tokio::spawn(accept_connection(peer, stream));
+    while let Ok((stream, socket)) = listener.accept().await {
+        tokio::spawn(accept_connection(socket, stream));
     }
 }
 
+/// Accepts a peer, which identifies itself through query parameters: its origin and tab, and the
+/// connection token it was paired with. Peers without the current token, or without an origin to
+/// key approvals on, are refused before the websocket is even opened
 async fn accept_connection(socket: SocketAddr, stream: TcpStream) {
     let mut query_params: HashMap<String, String> = Default::default();
     let callback = |req: &Request, res: Response| -> std::result::Result<Response, ErrorResponse> {
-        let url = Url::parse(&format!("{}{}", "http://localhost", req.uri())).unwrap();
-        query_params = url.query_pairs().into_owned().collect();
+        if let Ok(url) = Url::parse(&format!("{}{}", "http://localhost", req.uri())) {
+            query_params = url.query_pairs().into_owned().collect();
+        }
+
+        if !auth::verify(query_params.get("token").map(String::as_str)) {
+            let mut res = ErrorResponse::new(Some("invalid or missing connection token".into()));
+            *res.status_mut() = StatusCode::UNAUTHORIZED;
+            return Err(res);
+        }
+        if query_params
+            .get("origin")
+            .is_none_or(|o| o.trim().is_empty())
+        {
+            let mut res = ErrorResponse::new(Some("missing origin".into()));
+            *res.status_mut() = StatusCode::BAD_REQUEST;
+            return Err(res);
+        }
         Ok(res)
     };
 
-    let ws_stream = accept_hdr_async(stream, callback)
-        .await
-        .expect("Failed to accept");
-    let (snd, rcv) = mpsc::unbounded_channel::<serde_json::Value>();
+    let ws_stream = match accept_hdr_async(stream, callback).await {
+        Ok(ws_stream) => ws_stream,
+        Err(e) => {
+            tracing::warn!("Failed to accept connection from {}: {}", socket, e);
+            return;
+        }
+    };
+    query_params.remove("token");
+    let (snd, rcv) = mpsc::unbounded_channel::<ProviderEvent>();
     let url = query_params.get("url").cloned().unwrap_or_default();
 
     tracing::debug!("Peer  {}", url);
@@ -48,6 +85,8 @@ async fn accept_connection(socket: SocketAddr, stream: TcpStream) {
     let peer = Peer::new(socket, snd, &query_params);
 
     Peers::write().await.add_peer(peer.clone()).await;
+    peer.announce();
+    peer.send_current_chain().await;
     let err = handle_connection(peer, ws_stream, rcv).await;
     Peers::write().await.remove_peer(socket).await;
 
@@ -71,12 +110,23 @@ async fn accept_connection(socket: SocketAddr, stream: TcpStream) {
 async fn handle_connection(
     peer: Peer,
     stream: WebSocketStream<TcpStream>,
-    mut rcv: mpsc::UnboundedReceiver<serde_json::Value>,
+    mut rcv: mpsc::UnboundedReceiver<ProviderEvent>,
 ) -> WsResult<()> {
+    let socket = peer.socket;
+    let ctx = peer.ctx();
+    let sender = peer.sender.clone();
+    let closed = peer.closed();
+    let last_seen = peer.last_seen();
+    let mut subscriptions = Subscriptions::default();
     let handler: iron_rpc::Handler = peer.into();
     let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
     let (mut ws_sender, mut ws_receiver) = stream.split();
 
+    // requests are answered in their own tasks, so that a slow upstream call or a signing dialog
+    // doesn't hold up events, pings or other requests from the same peer. replies come back here
+    // to be written, and dapps match them to their requests by ID
+    let (reply_snd, mut reply_rcv) = mpsc::unbounded_channel::<String>();
+
     loop {
         tokio::select! {
             // RPC request
@@ -84,29 +134,44 @@ async fn handle_connection(
                 match msg {
                     Some(msg)=>{
                         let msg = msg?;
+                        last_seen.touch();
                         if let Message::Pong(_) = msg {
                             continue;
                         }
-                        let reply = handler.handle(msg.to_string()).await;
-                        let reply = reply.unwrap_or_else(||serde_json::Value::Null.to_string());
+                        let msg = msg.to_string();
 
-                        ws_sender.send(reply.into()).await?;
+                        let pending = split(msg, &mut subscriptions, &ctx, &sender).await;
+                        let handler = handler.clone();
+                        let reply_snd = reply_snd.clone();
+                        tokio::spawn(async move {
+                            let reply = pending.finish(&handler).await;
+                            let _ = reply_snd.send(reply.unwrap_or_else(||serde_json::Value::Null.to_string()));
+                        });
                     },
                     None=>break
                 }
             }
 
+            // a reply to an earlier request
+            Some(reply) = reply_rcv.recv() =>{
+                ws_sender.send(reply.into()).await?;
+            }
+
             // data sent from provider, or event broadcast
-            msg = rcv.recv() =>{
-                match msg {
-                    Some(msg)=>{
-                        ws_sender.send(msg.to_string().into()).await?;
-                    },
-                    None=>{
-                        tracing::error!("unexpected error");
-                        break
-                    }
+            Some(msg) = rcv.recv() =>{
+                ws_sender.send(serde_json::to_string(&msg)?.into()).await?;
+            }
+
+            // the peer was dropped, either for being stale, for a failed send, or by the user
+            _ = closed.notified() =>{
+                tracing::debug!("peer {} dropped", socket);
+
+                // events queued before then, such as the shutdown notice, still go out first
+                while let Ok(msg) = rcv.try_recv() {
+                    ws_sender.send(serde_json::to_string(&msg)?.into()).await?;
                 }
+                let _ = ws_sender.send(Message::Close(None)).await;
+                break
             }
 
             // send a ping every 15 seconds
@@ -119,3 +184,124 @@ async fn handle_connection(
 
     Ok(())
 }
+
+/// What's left of a peer's message once subscriptions are answered
+enum Pending {
+    /// Fully answered already
+    Done(Option<String>),
+
+    /// A single request for the RPC handler
+    Single(String),
+
+    /// A batch, with replies so far in the batch's order, and the requests still to answer
+    Batch {
+        replies: Vec<Option<String>>,
+        rest: Vec<(usize, String)>,
+    },
+}
+
+/// Answers the subscription requests of a message from a peer, which may be a single request or a
+/// batch of them
+/// Subscriptions need to push to this peer, so they're handled by the connection itself rather
+/// than by the RPC handler
+async fn split(
+    msg: String,
+    subscriptions: &mut Subscriptions,
+    ctx: &Ctx,
+    sender: &mpsc::UnboundedSender<ProviderEvent>,
+) -> Pending {
+    let batch = match serde_json::from_str::<Json>(&msg) {
+        Ok(Json::Array(batch)) if !batch.is_empty() => batch,
+        _ => {
+            return match subscriptions.handle(&msg, ctx, sender).await {
+                Some(reply) => Pending::Done(Some(reply)),
+                None => Pending::Single(msg),
+            }
+        }
+    };
+
+    let mut replies = Vec::with_capacity(batch.len());
+    let mut rest = Vec::new();
+    for (i, request) in batch.iter().enumerate() {
+        let request = request.to_string();
+        match subscriptions.handle(&request, ctx, sender).await {
+            Some(reply) => replies.push(Some(reply)),
+            None => {
+                replies.push(None);
+                rest.push((i, request));
+            }
+        }
+    }
+
+    Pending::Batch { replies, rest }
+}
+
+impl Pending {
+    /// Answers the rest of the message through the RPC handler
+    /// The rest of a batch is dispatched concurrently, and replies keep the batch's order
+    async fn finish(self, handler: &iron_rpc::Handler) -> Option<String> {
+        let (mut replies, rest) = match self {
+            Self::Done(reply) => return reply,
+            Self::Single(msg) => return handler.handle(msg).await,
+            Self::Batch { replies, rest } => (replies, rest),
+        };
+
+        let answered = join_all(
+            rest.into_iter()
+                .map(|(i, request)| async move { (i, handler.handle(request).await) }),
+        )
+        .await;
+        for (i, reply) in answered {
+            replies[i] = reply;
+        }
+
+        // notifications get no reply, and a batch of only notifications gets none at all
+        let replies: Vec<_> = replies.into_iter().flatten().collect();
+        match replies.is_empty() {
+            true => None,
+            false => Some(format!("[{}]", replies.join(","))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_tungstenite::{accept_async, connect_async};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn disconnected_peers_end_their_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client =
+            tokio::spawn(async move { connect_async(format!("ws://{}", addr)).await.unwrap().0 });
+        let (stream, socket) = listener.accept().await.unwrap();
+        let stream = accept_async(stream).await.unwrap();
+        let mut client = client.await.unwrap();
+
+        // the peer, and its sender, are kept around, as they would be by a pending request
+        let (snd, rcv) = mpsc::unbounded_channel();
+        let peer = Peer::new(socket, snd, &HashMap::new());
+        let task = tokio::spawn(handle_connection(peer.clone(), stream, rcv));
+
+        // as done when the peer is removed, e.g. by disconnecting it from the UI
+        peer.close();
+        tokio::time::timeout(Duration::from_secs(5), task)
+            .await
+            .expect("connection task didn't end")
+            .unwrap()
+            .unwrap();
+
+        let mut closed = false;
+        while let Some(Ok(msg)) = client.next().await {
+            if let Message::Close(_) = msg {
+                closed = true;
+                break;
+            }
+        }
+        assert!(closed);
+    }
+}
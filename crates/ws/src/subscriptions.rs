@@ -0,0 +1,222 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use ethers::{
+    providers::{Middleware, Provider, Ws},
+    types::Filter,
+};
+use futures_util::{Stream, StreamExt};
+use iron_connections::Ctx;
+use iron_networks::Network;
+use iron_types::Json;
+use serde::Serialize;
+use serde_json::json;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{ProviderEvent, WsError, WsResult};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What an `eth_subscribe` request asks to be notified of
+#[derive(Debug)]
+enum Kind {
+    NewHeads,
+    Logs(Box<Filter>),
+    NewPendingTransactions,
+}
+
+impl Kind {
+    /// Expected format: `["newHeads"]`, `["logs", { "address": ..., "topics": ... }]` or
+    /// `["newPendingTransactions"]`
+    fn parse(params: &Json) -> WsResult<Self> {
+        match params[0].as_str() {
+            Some("newHeads") => Ok(Self::NewHeads),
+            Some("logs") => {
+                let filter = params.get(1).cloned().unwrap_or_else(|| json!({}));
+                Ok(Self::Logs(Box::new(serde_json::from_value(filter)?)))
+            }
+            Some("newPendingTransactions") => Ok(Self::NewPendingTransactions),
+            _ => Err(WsError::UnsupportedSubscription(params[0].to_string())),
+        }
+    }
+}
+
+/// The `eth_subscribe` subscriptions of a single connection
+/// Each one is fed by a task of its own. They all stop once the connection drops
+#[derive(Debug, Default)]
+pub(crate) struct Subscriptions(HashMap<String, JoinHandle<()>>);
+
+impl Subscriptions {
+    /// Handles `eth_subscribe` and `eth_unsubscribe` requests, returning the reply to send back
+    /// Returns `None` for any other request, which is left to the RPC handler
+    pub async fn handle(
+        &mut self,
+        request: &str,
+        ctx: &Ctx,
+        sender: &mpsc::UnboundedSender<ProviderEvent>,
+    ) -> Option<String> {
+        let request: Json = serde_json::from_str(request).ok()?;
+
+        let result = match request["method"].as_str()? {
+            "eth_subscribe" => self
+                .subscribe(&request["params"], ctx, sender)
+                .await
+                .map(Json::from),
+            "eth_unsubscribe" => Ok(self.unsubscribe(&request["params"]).into()),
+            _ => return None,
+        };
+
+        let reply = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32602, "message": e.to_string() },
+            }),
+        };
+
+        Some(reply.to_string())
+    }
+
+    /// Starts forwarding notifications from the connection's current network
+    /// Subscriptions stay on that network even if the connection later switches chains, which
+    /// dapps handle by resubscribing on `chainChanged`
+    async fn subscribe(
+        &mut self,
+        params: &Json,
+        ctx: &Ctx,
+        sender: &mpsc::UnboundedSender<ProviderEvent>,
+    ) -> WsResult<String> {
+        let kind = Kind::parse(params)?;
+        let network = ctx.network().await?;
+        let id = format!("0x{:x}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+
+        let task = {
+            let id = id.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                if let Err(e) = forward(&id, kind, network, sender).await {
+                    tracing::warn!("Subscription {} ended: {}", id, e);
+                }
+            })
+        };
+        self.0.insert(id.clone(), task);
+
+        Ok(id)
+    }
+
+    fn unsubscribe(&mut self, params: &Json) -> bool {
+        params[0]
+            .as_str()
+            .and_then(|id| self.0.remove(id))
+            .map(|task| task.abort())
+            .is_some()
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        for task in self.0.values() {
+            task.abort();
+        }
+    }
+}
+
+/// Pipes notifications to a peer until it goes away
+/// Uses native subscriptions when the network has a websockets URL, and polls filters over HTTP
+/// otherwise
+async fn forward(
+    id: &str,
+    kind: Kind,
+    network: Network,
+    sender: mpsc::UnboundedSender<ProviderEvent>,
+) -> WsResult<()> {
+    if let Some(ref ws_url) = network.ws_url {
+        let provider = Provider::<Ws>::connect(ws_url.as_str()).await?;
+
+        return match kind {
+            Kind::NewHeads => pipe(id, provider.subscribe_blocks().await?, &sender).await,
+            Kind::Logs(filter) => pipe(id, provider.subscribe_logs(&filter).await?, &sender).await,
+            Kind::NewPendingTransactions => {
+                pipe(id, provider.subscribe_pending_txs().await?, &sender).await
+            }
+        };
+    }
+
+    let provider = network.get_provider()?;
+
+    match kind {
+        // block filters only yield hashes, while subscribers expect headers
+        Kind::NewHeads => {
+            let mut hashes = Box::pin(provider.watch_blocks().await?);
+            while let Some(hash) = hashes.next().await {
+                let Some(block) = provider.get_block(hash).await? else {
+                    continue;
+                };
+                if !notify(id, &block, &sender)? {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        Kind::Logs(filter) => pipe(id, provider.watch(&filter).await?, &sender).await,
+        Kind::NewPendingTransactions => {
+            pipe(id, provider.watch_pending_transactions().await?, &sender).await
+        }
+    }
+}
+
+async fn pipe<T: Serialize>(
+    id: &str,
+    stream: impl Stream<Item = T>,
+    sender: &mpsc::UnboundedSender<ProviderEvent>,
+) -> WsResult<()> {
+    let mut stream = Box::pin(stream);
+
+    while let Some(item) = stream.next().await {
+        if !notify(id, &item, sender)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends a single notification, returning whether the peer is still around
+fn notify<T: Serialize>(
+    id: &str,
+    item: &T,
+    sender: &mpsc::UnboundedSender<ProviderEvent>,
+) -> WsResult<bool> {
+    let event = ProviderEvent::Subscription {
+        subscription: id.into(),
+        result: serde_json::to_value(item)?,
+    };
+
+    Ok(sender.send(event).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_supported_subscriptions() {
+        assert!(matches!(
+            Kind::parse(&json!(["newHeads"])),
+            Ok(Kind::NewHeads)
+        ));
+        assert!(matches!(
+            Kind::parse(&json!(["logs", { "topics": [] }])),
+            Ok(Kind::Logs(_))
+        ));
+        assert!(matches!(
+            Kind::parse(&json!(["newPendingTransactions"])),
+            Ok(Kind::NewPendingTransactions)
+        ));
+        assert!(Kind::parse(&json!(["syncing"])).is_err());
+        assert!(Kind::parse(&json!([])).is_err());
+    }
+}
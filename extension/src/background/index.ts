@@ -74,7 +74,8 @@ function endpoint(port: Runtime.Port) {
 /**
  * URL-encoded connection info
  *
- * This includes all info that may be useful for the Iron server.
+ * This includes all info that may be useful for the Iron server, along with the
+ * token that authenticates the connection
  */
 function connParams(port: Runtime.Port) {
   const sender = port.sender;
@@ -86,6 +87,7 @@ function connParams(port: Runtime.Port) {
     favicon: tab?.favIconUrl,
     url: tab?.url,
     title: tab?.title,
+    token: settings.token,
   };
 
   return encodeUrlParams(params);
@@ -1,6 +1,10 @@
 import { v4 as uuidv4 } from "@lukeed/uuid";
 import log from "loglevel";
-import { EIP1193Provider, announceProvider } from "mipd";
+import {
+  EIP1193Provider,
+  type EIP6963ProviderInfo,
+  announceProvider,
+} from "mipd";
 import { type Duplex } from "stream";
 
 import { WindowPostMessageStream } from "@metamask/post-message-stream";
@@ -38,15 +42,24 @@ export function initializeProvider() {
   log.debug("provider", provider);
   setGlobalProvider(provider);
 
-  announceProvider({
-    info: {
-      icon,
-      name: "Iron",
-      rdns: "eth.iron-wallet",
-      uuid: uuidv4(),
-    },
+  // the UUID must stay the same for as long as the page is open, so it's kept
+  // across announcements
+  const uuid = uuidv4();
+  let unsubscribe = announceProvider({
+    info: { icon, name: "Iron", rdns: "eth.iron-wallet", uuid },
     provider: provider as EIP1193Provider,
   });
+
+  // once connected, the app sends its own info, which is announced again in
+  // place of the built-in one
+  type AppInfo = Omit<EIP6963ProviderInfo, "uuid">;
+  provider.on("announceProvider", (info: AppInfo) => {
+    unsubscribe();
+    unsubscribe = announceProvider({
+      info: { ...info, uuid },
+      provider: provider as EIP1193Provider,
+    });
+  });
 }
 
 type ExtendedWindow = Window & typeof globalThis & { ethereum: IronProvider };
@@ -150,6 +150,24 @@ export class IronProvider extends EventEmitter {
           this.handleChainChanged(params);
           break;
 
+        case "connect":
+          this.handleConnect(params.chainId);
+          break;
+
+        case "disconnect":
+          this.handleDisconnect(params.message);
+          break;
+
+        // the lifecycle of a transaction sent from this page
+        case "transactionUpdated":
+          this.emit("transactionUpdated", params);
+          break;
+
+        // the wallet's EIP-6963 info, for the page to re-announce it with
+        case "announceProvider":
+          this.emit("announceProvider", params);
+          break;
+
         case "METAMASK_STREAM_FAILURE":
           this.stream.destroy(
             new Error(
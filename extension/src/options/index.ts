@@ -4,18 +4,23 @@ import { Settings, defaultSettings } from "../settings";
 
 const $logLevel = document.getElementById("log-level") as HTMLInputElement;
 const $endpoint = document.getElementById("endpoint") as HTMLInputElement;
+const $token = document.getElementById("token") as HTMLInputElement;
 const $status = document.getElementById("status") as HTMLDivElement;
 const $save = document.getElementById("save") as HTMLButtonElement;
 
-// Saves options to chrome.storage
+// Saves options to browser.storage.local, since the token must not be synced
 const saveOptions = () => {
   const options: Settings = {
     logLevel:
       ($logLevel.value as Settings["logLevel"]) || defaultSettings.logLevel,
     endpoint: $endpoint.value || defaultSettings.endpoint,
+    token: $token.value.trim(),
   };
 
-  browser.storage.sync.set(options).then(() => {
+  // older versions kept them in sync storage, token included
+  browser.storage.sync.remove(Object.keys(defaultSettings));
+
+  browser.storage.local.set(options).then(() => {
     // Update status to let user know options were saved.
     $status.textContent = "Options saved. Restart browser to take effect";
     setTimeout(() => {
@@ -25,11 +30,12 @@ const saveOptions = () => {
 };
 
 // Restores select box and checkbox state using the preferences
-// stored in chrome.storage.
+// stored in browser.storage.local.
 const restoreOptions = () => {
-  browser.storage.sync.get(defaultSettings).then((items) => {
+  browser.storage.local.get(defaultSettings).then((items) => {
     $logLevel.value = items.logLevel;
     $endpoint.value = items.endpoint;
+    $token.value = items.token;
   });
 };
 
@@ -4,15 +4,19 @@ import browser from "webextension-polyfill";
 export interface Settings {
   logLevel: "info" | "debug" | "warn" | "error";
   endpoint: string;
+  // shared with the app when pairing, to authenticate connections
+  token: string;
 }
 
 export const defaultSettings: Settings = {
   logLevel: "info",
   endpoint: "ws://localhost:9002",
+  token: "",
 };
 
+// local rather than sync storage, so the token is never synced elsewhere
 export async function loadSettings() {
-  const settings = (await browser.storage.sync.get(
+  const settings = (await browser.storage.local.get(
     defaultSettings
   )) as Settings;
   log.setLevel(settings.logLevel);
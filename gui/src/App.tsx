@@ -4,13 +4,18 @@ import { QueryClient, QueryClientProvider } from "react-query";
 import { Route, Router, Switch } from "wouter";
 
 import {
+  ChainChangeDialog,
   CommandBar,
   DevBuildNotice,
   HomePage,
+  LockScreen,
   MsgSignDialog,
   TxReviewDialog,
   WagmiWrapper,
+  WalletConnectProposalDialog,
   WalletUnlockDialog,
+  WatchAssetDialog,
+  useReportActivity,
 } from "./components";
 import { OnboardingWrapper } from "./components/Onboarding";
 import { useTheme } from "./store/theme";
@@ -48,6 +53,9 @@ export default function App() {
 }
 
 function Routes() {
+  // dialogs count as interaction too, so this is set up in every window
+  useReportActivity();
+
   return (
     <Router>
       <Switch>
@@ -59,13 +67,29 @@ function Routes() {
           {({ id }: { id: string }) => <MsgSignDialog id={parseInt(id)} />}
         </Route>
 
+        <Route path="/dialog/chain-change/:id">
+          {({ id }: { id: string }) => <ChainChangeDialog id={parseInt(id)} />}
+        </Route>
+
+        <Route path="/dialog/watch-asset/:id">
+          {({ id }: { id: string }) => <WatchAssetDialog id={parseInt(id)} />}
+        </Route>
+
         <Route path="/dialog/wallet-unlock/:id">
           {({ id }: { id: string }) => <WalletUnlockDialog id={parseInt(id)} />}
         </Route>
+
+        <Route path="/dialog/walletconnect-proposal/:id">
+          {({ id }: { id: string }) => (
+            <WalletConnectProposalDialog id={parseInt(id)} />
+          )}
+        </Route>
         <Route>
-          <CommandBar>
-            <HomePage />
-          </CommandBar>
+          <LockScreen>
+            <CommandBar>
+              <HomePage />
+            </CommandBar>
+          </LockScreen>
         </Route>
       </Switch>
     </Router>
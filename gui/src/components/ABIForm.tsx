@@ -3,36 +3,39 @@ import { Stack } from "@mui/system";
 import { invoke } from "@tauri-apps/api/tauri";
 import { SyntheticEvent, useState } from "react";
 import { useForm } from "react-hook-form";
-import { encodeFunctionData } from "viem";
+import { parseEther, toHex } from "viem";
 
-import { useProvider } from "../hooks";
-import { ABIFunctionInput, ABIItem, Address } from "../types";
+import { useInvoke } from "../hooks";
+import { useNetworks } from "../store";
+import { Address, ContractFunction, DecodedArg } from "../types";
 
 interface Props {
   address: Address;
-  abi: ABIItem[];
 }
 
-export function ABIForm({ address, abi }: Props) {
-  const [currentItem, setCurrentItem] = useState<ABIItem | undefined>();
+// Functions of a contract, as the app knows its ABI, to call or send
+// transactions to
+export function ABIForm({ address }: Props) {
+  const chainId = useNetworks((s) => s.current?.chain_id);
+  const { data: functions, error } = useInvoke<ContractFunction[]>(
+    "rpc_contract_functions",
+    { address, chainId }
+  );
+  const [current, setCurrent] = useState<ContractFunction | undefined>();
+
+  if (error) return <Box>{String(error)}</Box>;
 
-  const options = abi
-    .filter((item) => item.type === "function")
-    .map((item, i) => ({
-      item,
-      label: functionSignature(item.name, item.inputs),
-      id: i,
-    }));
+  const options = (functions || []).map((item, i) => ({
+    item,
+    label: functionSignature(item),
+    id: i,
+  }));
 
   const handleChange = (
     _event: SyntheticEvent,
-    value: { item: ABIItem } | null
+    value: { item: ContractFunction } | null
   ) => {
-    if (value === null) {
-      setCurrentItem(undefined);
-    } else {
-      setCurrentItem(value.item);
-    }
+    setCurrent(value?.item);
   };
 
   return (
@@ -46,60 +49,64 @@ export function ABIForm({ address, abi }: Props) {
         renderOption={(props, { label, item }) => (
           <Box component="li" {...props}>
             <Stack direction="row" spacing={1} alignItems="center">
-              <Chip label={item.stateMutability} />
+              <Chip label={mutability(item)} />
               <Box>{label}</Box>
             </Stack>
           </Box>
         )}
       />
 
-      {currentItem && <ABIItemForm contract={address} item={currentItem} />}
+      {current && (
+        <ABIItemForm
+          key={current.signature}
+          contract={address}
+          item={current}
+        />
+      )}
     </Stack>
   );
 }
 
 interface CallArgs {
   value?: string;
-  args: Record<string, string>;
+  args: string[];
 }
 
-function ABIItemForm({ contract, item }: { contract: Address; item: ABIItem }) {
-  const provider = useProvider();
+function ABIItemForm({
+  contract,
+  item,
+}: {
+  contract: Address;
+  item: ContractFunction;
+}) {
   const { register, handleSubmit } = useForm<CallArgs>();
-  const [callResult, setCallResult] = useState<string>();
-  const [txResult, setTxResult] = useState<string>();
-
-  if (!provider) return null;
+  const [result, setResult] = useState<string>();
 
   const onSubmit = async (params: CallArgs) => {
-    const args = item.inputs.map((input) => params.args[input.name]);
-
-    const data = encodeFunctionData({
-      abi: [item],
-      functionName: item.name,
-      args,
-    });
-
-    if (item.stateMutability === "view") {
-      const result = await provider.readContract({
-        address: contract,
-        abi: [item],
-        functionName: item.name,
-        args,
-      });
-
-      if (typeof result === "bigint") {
-        setCallResult(result.toString());
-      } else if (typeof result === "string") {
-        setCallResult(result);
+    const args = item.inputs.map((_, i) => params.args?.[i] ?? "");
+    const call = { address: contract, signature: item.signature, args };
+
+    try {
+      if (item.readOnly) {
+        const outputs = await invoke<DecodedArg[]>("rpc_contract_call", call);
+        setResult(
+          outputs
+            .map(({ name, value }) => (name ? `${name}: ${value}` : value))
+            .join("\n")
+        );
       } else {
-        setCallResult(JSON.stringify(result));
+        const value =
+          item.payable && params.value
+            ? toHex(parseEther(params.value))
+            : null;
+        const hash = await invoke<string>("rpc_contract_send", {
+          ...call,
+          value,
+        });
+        setResult(hash);
       }
-    } else {
-      const result = await invoke<string>("rpc_send_transaction", {
-        params: { to: contract, data },
-      });
-      setTxResult(result);
+    } catch (err) {
+      setResult(String(err));
     }
   };
 
@@ -111,27 +118,30 @@ function ABIItemForm({ contract, item }: { contract: Address; item: ABIItem }) {
             <TextField
               sx={{ minWidth: 300 }}
               size="small"
-              {...register(`args.${name}`)}
+              {...register(`args.${key}`)}
               label={`${name} (${type})`}
             />
           </Box>
         ))}
-        {item.stateMutability === "payable" && (
+        {item.payable && (
           <TextField size="small" {...register("value")} label="value" />
         )}
         <Box>
           <Button sx={{ minWidth: 150 }} variant="contained" type="submit">
-            {item.stateMutability == "view" ? "Call" : "Send"}
+            {item.readOnly ? "Call" : "Send"}
           </Button>
         </Box>
-        {callResult && <Box>{callResult}</Box>}
-        {txResult && <Box>{txResult}</Box>}
+        {result && <Box sx={{ whiteSpace: "pre-wrap" }}>{result}</Box>}
       </Stack>
     </form>
   );
 }
 
-// TODO: this may be missing some details, such as `calldata`
-function functionSignature(name: string, inputs: ABIFunctionInput[]) {
+function mutability(item: ContractFunction) {
+  if (item.readOnly) return "view";
+  return item.payable ? "payable" : "nonpayable";
+}
+
+function functionSignature({ name, inputs }: ContractFunction) {
   return `${name}(${inputs.map((i) => `${i.type} ${i.name}`).join(", ")})`;
 }
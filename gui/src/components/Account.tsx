@@ -1,6 +1,8 @@
-import { Box, Card, CardContent, Typography } from "@mui/material";
+import { Box, Card, CardContent, Chip, Stack, Typography } from "@mui/material";
 
+import { useInvoke } from "../hooks";
 import { useTheme, useWallets } from "../store";
+import { Address, SafeInfo, SmartAccount } from "../types";
 import { AddressView, BalancesList, Panel } from "./";
 
 export function Account() {
@@ -38,8 +40,81 @@ export function Account() {
             </Typography>
           </CardContent>
         </Card>
+        <SafeCard address={address} />
+        <SmartAccountCard />
         <BalancesList />
       </Box>
     </Panel>
   );
 }
+
+function SafeCard({ address }: { address: Address }) {
+  const { data: safe } = useInvoke<SafeInfo | null>("rpc_get_safe", {
+    address,
+  });
+
+  if (!safe) return null;
+
+  const own = new Set(safe.ownOwners.map((o) => o.toLowerCase()));
+
+  return (
+    <Card sx={{ width: "fit-content" }}>
+      <CardContent sx={{ pb: 0 }}>
+        <Typography
+          textTransform="uppercase"
+          fontSize={12}
+          color="text.secondary"
+        >
+          Safe {safe.version}
+        </Typography>
+        <Typography>
+          Threshold of {Number(safe.threshold)} out of {safe.owners.length}{" "}
+          owners
+        </Typography>
+        <Stack>
+          {safe.owners.map((owner) => (
+            <Stack key={owner} direction="row" columnGap={1}>
+              <AddressView address={owner} />
+              {own.has(owner.toLowerCase()) && <Chip size="small" label="you" />}
+            </Stack>
+          ))}
+        </Stack>
+        {!safe.canPropose && (
+          <Typography fontSize={12} color="text.secondary">
+            No Safe transaction service on this network
+          </Typography>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
+
+function SmartAccountCard() {
+  const { data: account } = useInvoke<SmartAccount | null>(
+    "rpc_get_smart_account"
+  );
+
+  if (!account) return null;
+
+  return (
+    <Card sx={{ width: "fit-content" }}>
+      <CardContent sx={{ pb: 0 }}>
+        <Typography
+          textTransform="uppercase"
+          fontSize={12}
+          color="text.secondary"
+        >
+          Smart account
+        </Typography>
+        <Typography>
+          <AddressView address={account.address} copyIcon />
+        </Typography>
+        {!account.deployed && (
+          <Typography fontSize={12} color="text.secondary">
+            Deployed with its first transaction
+          </Typography>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -15,6 +15,7 @@ import truncateEthAddress from "truncate-eth-address";
 import { z } from "zod";
 
 import { useInvoke } from "../hooks";
+import { useNetworks } from "../store";
 import { ContextMenu, Modal } from "./";
 
 interface Props {
@@ -27,6 +28,11 @@ export function AddressView({ contextMenu, address, copyIcon }: Props) {
   const { data: alias, mutate } = useInvoke<string>("settings_get_alias", {
     address,
   });
+  const chainId = useNetworks((s) => s.current?.chain_id);
+  const { data: contact } = useInvoke<string | null>("contacts_resolve", {
+    address,
+    chainId,
+  });
   const [aliasFormOpen, setAliasFormOpen] = useState(false);
 
   const contextActions = [
@@ -43,7 +49,7 @@ export function AddressView({ contextMenu, address, copyIcon }: Props) {
 
   const content = (
     <>
-      {alias ? alias : truncateEthAddress(address)}
+      {alias || contact || truncateEthAddress(address)}
 
       {copyIcon && (
         <IconButton>
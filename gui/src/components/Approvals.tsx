@@ -0,0 +1,124 @@
+import {
+  Alert,
+  Button,
+  CircularProgress,
+  List,
+  ListItem,
+  ListItemText,
+  Stack,
+  Typography,
+} from "@mui/material";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useState } from "react";
+import { formatUnits } from "viem";
+
+import { useInvoke, useRefreshTransactions } from "../hooks";
+import { useNetworks, useWallets } from "../store";
+import { Allowance } from "../types";
+import { AddressView, Panel } from "./";
+
+// approvals of the maximum amount are how dapps ask for unlimited spending
+const UNLIMITED = 2n ** 256n - 1n;
+
+// Approvals the current account still has standing, each revocable with a
+// transaction
+export function Approvals() {
+  const account = useWallets((s) => s.address);
+  const chainId = useNetworks((s) => s.current?.chain_id);
+  // the account and chain aren't needed by the command, but keep them in the
+  // cache key, so that the list is reloaded when they change
+  const {
+    data: allowances,
+    error,
+    isLoading,
+    mutate,
+  } = useInvoke<Allowance[]>("rpc_get_allowances", { account, chainId });
+  const [result, setResult] = useState<{ ok: boolean; message: string }>();
+
+  useRefreshTransactions(() => mutate());
+
+  const revoke = async (allowance: Allowance) => {
+    try {
+      const hash = await invoke<string>("rpc_revoke_allowance", { allowance });
+      setResult({ ok: true, message: `Revocation sent: ${hash}` });
+    } catch (err) {
+      setResult({ ok: false, message: String(err) });
+    }
+  };
+
+  if (!account) return null;
+
+  return (
+    <Panel>
+      <Stack spacing={2}>
+        {result && (
+          <Alert severity={result.ok ? "success" : "error"}>
+            {result.message}
+          </Alert>
+        )}
+        {isLoading && <CircularProgress />}
+        {!!error && <Alert severity="error">{String(error)}</Alert>}
+        {allowances?.length === 0 && (
+          <Typography color="text.secondary">
+            No outstanding approvals on this network
+          </Typography>
+        )}
+        <List>
+          {(allowances || []).map((allowance) => (
+            <ListItem
+              key={`${allowance.token}-${allowance.spender}-${grantKey(
+                allowance
+              )}`}
+              secondaryAction={
+                <Button onClick={() => revoke(allowance)}>Revoke</Button>
+              }
+            >
+              <ListItemText
+                primary={
+                  <>
+                    {describe(allowance)} of{" "}
+                    <AddressView address={allowance.token} />
+                  </>
+                }
+                secondary={
+                  <>
+                    to <AddressView address={allowance.spender} />
+                  </>
+                }
+              />
+            </ListItem>
+          ))}
+        </List>
+      </Stack>
+    </Panel>
+  );
+}
+
+function grantKey(allowance: Allowance) {
+  switch (allowance.kind) {
+    case "erc721":
+      return allowance.tokenId;
+    default:
+      return allowance.kind;
+  }
+}
+
+function describe(allowance: Allowance) {
+  const symbol = allowance.symbol || "tokens";
+
+  switch (allowance.kind) {
+    case "erc20": {
+      const amount = BigInt(allowance.amount);
+      if (amount === UNLIMITED) return `Unlimited ${symbol}`;
+      const formatted =
+        allowance.decimals != null
+          ? formatUnits(amount, allowance.decimals)
+          : amount.toString();
+      return `${formatted} ${symbol}`;
+    }
+    case "erc721":
+      return `${symbol} #${BigInt(allowance.tokenId).toString()}`;
+    case "operator":
+      return `All ${symbol}`;
+  }
+}
@@ -11,6 +11,7 @@ import { Address, formatUnits } from "viem";
 import { useInvoke } from "../hooks";
 import { useBalances, useNetworks } from "../store";
 import { GeneralSettings } from "../types";
+import { formatFiat } from "../utils";
 import { CopyToClipboard, IconCrypto } from "./";
 
 export function BalancesList() {
@@ -25,6 +26,7 @@ export function BalancesList() {
 function BalanceETH() {
   const currentNetwork = useNetworks((s) => s.current);
   const balance = useBalances((s) => s.nativeBalance);
+  const prices = useBalances((s) => s.prices);
 
   if (!currentNetwork || !balance) return null;
 
@@ -33,12 +35,15 @@ function BalanceETH() {
       balance={balance}
       decimals={currentNetwork.decimals}
       symbol={currentNetwork.currency}
+      price={prices?.native?.price}
+      currency={prices?.currency}
     />
   );
 }
 
 function BalancesERC20() {
   const balances = useBalances((s) => s.erc20Balances);
+  const prices = useBalances((s) => s.prices);
   const { data: settings } = useInvoke<GeneralSettings>("settings_get");
 
   const filteredBalances = (balances || []).filter(
@@ -54,6 +59,12 @@ function BalancesERC20() {
           balance={BigInt(balance)}
           decimals={metadata.decimals}
           symbol={metadata.symbol}
+          price={
+            prices?.tokens.find(
+              (p) => p.contract?.toLowerCase() === contract.toLowerCase()
+            )?.price
+          }
+          currency={prices?.currency}
         />
       ))}
     </>
@@ -65,6 +76,8 @@ interface BalanceItemProps {
   balance: bigint;
   decimals: number;
   symbol: string;
+  price?: number;
+  currency?: string;
 }
 
 function BalanceItem({
@@ -72,6 +85,8 @@ function BalanceItem({
   decimals,
   symbol,
   contract,
+  price,
+  currency,
 }: BalanceItemProps) {
   const minimum = 0.001;
   // Some tokens respond with 1 decimals, that breaks this truncatedBalance without the Math.ceil
@@ -90,6 +105,13 @@ function BalanceItem({
       <ListItemText
         secondary={`${symbol} ${
           contract ? `(${truncateEthAddress(contract)})` : ``
+        }${
+          price !== undefined && currency
+            ? ` · ${formatFiat(
+                Number(formatUnits(balance, decimals)) * price,
+                currency
+              )}`
+            : ``
         }`}
       >
         <CopyToClipboard label={balance.toString()}>
@@ -0,0 +1,46 @@
+import { Button, Stack, Typography } from "@mui/material";
+
+import { useDialog } from "../hooks";
+
+interface Request {
+  action: "add" | "switch";
+  chainId: string | number;
+  chainName: string;
+  rpcUrls?: string[];
+  nativeCurrency?: { symbol: string };
+}
+
+export function ChainChangeDialog({ id }: { id: number }) {
+  const { data, accept, reject } = useDialog<Request>(id);
+
+  if (!data) return null;
+
+  const chainId = Number(data.chainId);
+
+  return (
+    <Stack direction="column" spacing={2} sx={{ p: 2 }}>
+      <Typography variant="h6" component="h1">
+        {data.action === "add" ? "Add" : "Switch to"} {data.chainName}
+      </Typography>
+
+      <Typography>Chain ID: {chainId}</Typography>
+      {data.rpcUrls?.[0] && (
+        <Typography sx={{ wordBreak: "break-all" }}>
+          RPC: {data.rpcUrls[0]}
+        </Typography>
+      )}
+      {data.nativeCurrency && (
+        <Typography>Currency: {data.nativeCurrency.symbol}</Typography>
+      )}
+
+      <Stack direction="row" justifyContent="center" spacing={2}>
+        <Button variant="contained" color="error" onClick={() => reject()}>
+          Reject
+        </Button>
+        <Button variant="contained" type="submit" onClick={() => accept(data)}>
+          {data.action === "add" ? "Add and switch" : "Switch"}
+        </Button>
+      </Stack>
+    </Stack>
+  );
+}
@@ -1,6 +1,7 @@
 import { Button, Menu, MenuItem, SxProps, Tooltip } from "@mui/material";
 import { writeText } from "@tauri-apps/api/clipboard";
-import React, { MouseEvent, ReactNode, useState } from "react";
+import { invoke } from "@tauri-apps/api/tauri";
+import React, { MouseEvent, ReactNode, useEffect, useState } from "react";
 
 import { useNetworks } from "../store";
 
@@ -37,9 +38,20 @@ export function ContextMenu({ children, sx, copy, explorer, actions }: Props) {
     mouseY: number;
   } | null>(null);
 
+  const [explorerLink, setExplorerLink] = useState<string | null>(null);
+
   const contextMenuOpen = Boolean(contextMenu?.target);
   const tooltipDelay = copied ? 0 : contextMenuOpen ? Infinity : 600;
 
+  // the link depends on the current network's explorer, so it's only built once the menu opens
+  useEffect(() => {
+    if (!contextMenuOpen || !explorer) return;
+
+    invoke<string | null>("networks_get_explorer_link", { query: explorer })
+      .then(setExplorerLink)
+      .catch(() => setExplorerLink(null));
+  }, [contextMenuOpen, explorer, currentNetwork]);
+
   const copyToClipboard = (text: string | null | undefined) => {
     if (!text) throw new Error("Nothing to copy to clipboard");
 
@@ -125,11 +137,11 @@ export function ContextMenu({ children, sx, copy, explorer, actions }: Props) {
           {copy && <MenuItem onClick={onContextCopy}>Copy</MenuItem>}
 
           {/* open in explorer */}
-          {currentNetwork?.explorer_url && explorer && (
+          {explorer && explorerLink && (
             <MenuItem
               component="a"
               target="_blank"
-              href={`${currentNetwork.explorer_url}${explorer}`}
+              href={explorerLink}
               rel="noreferrer"
               onClick={onCloseMenu}
             >
@@ -15,12 +15,23 @@ import { z } from "zod";
 
 import { useInvoke } from "../hooks";
 import { useContracts, useNetworks } from "../store";
-import { ABIMatch, IContract } from "../types";
+import { ABIMatch, Deployment, IContract } from "../types";
 import { ABIForm, AddressView, Panel } from "./";
 
 export function Contracts() {
   const chainId = useNetworks((s) => s.current?.chain_id);
   const contracts = useContracts((s) => s.contracts);
+  const { data: deployments } = useInvoke<Deployment[]>(
+    "foundry_get_deployments",
+    { chainId }
+  );
+
+  const known = new Set(
+    Array.from(contracts || []).map((c) => c.address.toLowerCase())
+  );
+  const deployed = (deployments || []).filter(
+    (d) => !known.has(d.address.toLowerCase())
+  );
 
   return (
     <Panel>
@@ -28,6 +39,9 @@ export function Contracts() {
       {Array.from(contracts || []).map((contract) => (
         <Contract key={contract.address} contract={contract} />
       ))}
+      {deployed.map(({ address, name }) => (
+        <Contract key={address} contract={{ address, name, abi: [] }} />
+      ))}
     </Panel>
   );
 }
@@ -42,7 +56,6 @@ function Contract({ contract }: { contract: IContract }) {
   });
 
   const name = foundryMatch?.name || contract.name;
-  const abi = foundryMatch?.abi || contract.abi;
 
   return (
     <Accordion>
@@ -50,11 +63,9 @@ function Contract({ contract }: { contract: IContract }) {
         <AddressView address={contract.address} />
         <Chip sx={{ marginLeft: 2 }} label={name} />
       </AccordionSummary>
-      {abi && (
-        <AccordionDetails>
-          <ABIForm address={contract.address} abi={abi} />
-        </AccordionDetails>
-      )}
+      <AccordionDetails>
+        <ABIForm address={contract.address} />
+      </AccordionDetails>
     </Accordion>
   );
 }
@@ -0,0 +1,42 @@
+import { Alert, AlertTitle, Snackbar } from "@mui/material";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+interface Notice {
+  title: string;
+  message: string;
+}
+
+// Shows failures reported by the backend that no command of the user's returned
+export function ErrorNotice() {
+  const [notice, setNotice] = useState<Notice | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<Notice>("error", ({ payload }) =>
+      setNotice(payload)
+    );
+
+    return () => {
+      unlisten.then((cb) => cb());
+    };
+  }, []);
+
+  const handleClose = (_event: unknown, reason?: string) => {
+    if (reason === "clickaway") return;
+    setNotice(null);
+  };
+
+  return (
+    <Snackbar
+      anchorOrigin={{ vertical: "bottom", horizontal: "left" }}
+      open={!!notice}
+      autoHideDuration={10000}
+      onClose={handleClose}
+    >
+      <Alert severity="error" onClose={handleClose}>
+        <AlertTitle>{notice?.title}</AlertTitle>
+        {notice?.message}
+      </Alert>
+    </Snackbar>
+  );
+}
@@ -1,6 +1,6 @@
 import { Route, Switch } from "wouter";
 
-import { Navbar, NestedRoutes, NewVersionNotice } from "./";
+import { ErrorNotice, Navbar, NestedRoutes, NewVersionNotice } from "./";
 import { DEFAULT_TAB, SidebarLayout, TABS } from "./Sidebar";
 
 export function HomePage() {
@@ -23,6 +23,7 @@ export function HomePage() {
         </Switch>
       </NestedRoutes>
       <NewVersionNotice />
+      <ErrorNotice />
     </SidebarLayout>
   );
 }
@@ -0,0 +1,86 @@
+import { Box, Button, Stack, TextField, Typography } from "@mui/material";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/tauri";
+import { ReactNode, useEffect, useState } from "react";
+import { FieldValues, useForm } from "react-hook-form";
+
+import { useInvoke } from "../hooks";
+
+// interaction is reported at most this often, which is plenty for a timeout counted in minutes
+const TOUCH_INTERVAL = 30 * 1000;
+
+// Covers the app once it auto-locks, until it's unlocked with a password
+export function LockScreen({ children }: { children: ReactNode }) {
+  const { data: locked, mutate } = useInvoke<boolean>("wallets_is_locked");
+  const { handleSubmit, register, reset } = useForm();
+  const [error, setError] = useState<string>();
+
+  useEffect(() => {
+    const unlisten = listen("lock-changed", () => mutate());
+
+    return () => {
+      unlisten.then((cb) => cb());
+    };
+  }, [mutate]);
+
+  if (!locked) return <>{children}</>;
+
+  const onSubmit = async ({ password }: FieldValues) => {
+    try {
+      await invoke("wallets_unlock_all", { password });
+      reset();
+      setError(undefined);
+      mutate();
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  return (
+    <Box sx={{ p: 4 }}>
+      <form onSubmit={handleSubmit(onSubmit)}>
+        <Stack spacing={2} alignItems="flex-start">
+          <Typography variant="h6" component="h1">
+            Iron is locked
+          </Typography>
+          <Typography variant="body2">
+            It was locked after a period without use. Signing requests are
+            rejected until it&apos;s unlocked.
+          </Typography>
+          <TextField
+            label="Password"
+            type="password"
+            error={!!error}
+            helperText={error}
+            fullWidth
+            {...register("password")}
+          />
+          <Button variant="contained" type="submit">
+            Unlock
+          </Button>
+        </Stack>
+      </form>
+    </Box>
+  );
+}
+
+// Reports user interaction, which pushes back the auto-lock
+export function useReportActivity() {
+  useEffect(() => {
+    let last = 0;
+    const touch = () => {
+      const now = Date.now();
+      if (now - last < TOUCH_INTERVAL) return;
+      last = now;
+      invoke("wallets_touch");
+    };
+
+    window.addEventListener("keydown", touch);
+    window.addEventListener("mousedown", touch);
+
+    return () => {
+      window.removeEventListener("keydown", touch);
+      window.removeEventListener("mousedown", touch);
+    };
+  }, []);
+}
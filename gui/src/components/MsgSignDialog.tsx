@@ -1,20 +1,95 @@
-import { Button, Stack, Typography } from "@mui/material";
+import {
+  Alert,
+  Button,
+  Stack,
+  Table,
+  TableBody,
+  TableCell,
+  TableRow,
+  Typography,
+} from "@mui/material";
+import { formatUnits } from "viem";
 
 import { useDialog } from "../hooks";
 
+interface Siwe {
+  domain: string;
+  address: string;
+  statement?: string | null;
+  uri: string;
+  chainId: number;
+  nonce: string;
+  issuedAt: string;
+  expirationTime?: string | null;
+  domainMismatch: boolean;
+  wrongAccount: boolean;
+  wrongChain: boolean;
+}
+
+interface Permit {
+  token: string;
+  spender: string;
+  amount: string;
+  deadline?: string | null;
+  unlimited: boolean;
+  symbol?: string | null;
+  decimals?: number | null;
+}
+
+interface Preview {
+  message?: unknown;
+  text?: string | null;
+  siwe?: Siwe | null;
+  primaryType?: string;
+  domain?: Record<string, unknown>;
+  wrongChain?: boolean;
+  permits?: Permit[] | null;
+}
+
+type Data = Record<string, unknown> & { preview?: Preview };
+
 export function MsgSignDialog({ id }: { id: number }) {
-  const { data, accept, reject } = useDialog<Record<string, string>>(id);
+  const { data, accept, reject } = useDialog<Data>(id);
 
   if (!data) return null;
 
-  const msg = data["Raw"] || JSON.stringify(data["Typed"], null, 2);
+  const preview = data.preview;
 
   return (
     <Stack direction="column" spacing={2} sx={{ p: 2 }}>
       <Typography variant="h6" component="h1">
-        Sign Message
+        {preview?.siwe
+          ? `Sign in to ${preview.siwe.domain}`
+          : preview?.permits
+          ? "Approve token spending"
+          : `Sign ${preview?.primaryType || "Message"}`}
       </Typography>
-      <Typography>{msg}</Typography>
+
+      {preview?.wrongChain && (
+        <Alert severity="warning">
+          This signature is meant for a different chain than the current one
+        </Alert>
+      )}
+
+      {preview?.siwe ? (
+        <SiwePreview siwe={preview.siwe} />
+      ) : preview?.permits ? (
+        <PermitPreview permits={preview.permits} />
+      ) : preview?.domain ? (
+        <>
+          <Fields title="Domain" fields={preview.domain} />
+          <Fields
+            title="Message"
+            fields={preview.message as Record<string, unknown>}
+          />
+        </>
+      ) : (
+        <Typography sx={{ whiteSpace: "pre-wrap", wordBreak: "break-all" }}>
+          {preview?.text ||
+            (data["Raw"] as string) ||
+            JSON.stringify(data["Typed"], null, 2)}
+        </Typography>
+      )}
 
       <Stack direction="row" justifyContent="center" spacing={2}>
         <Button variant="contained" color="error" onClick={() => reject()}>
@@ -27,3 +102,122 @@ export function MsgSignDialog({ id }: { id: number }) {
     </Stack>
   );
 }
+
+// A Sign-In with Ethereum message, with warnings about anything that suggests
+// it's being relayed from another site
+function SiwePreview({ siwe }: { siwe: Siwe }) {
+  const expired =
+    !!siwe.expirationTime && new Date(siwe.expirationTime) < new Date();
+
+  return (
+    <>
+      {siwe.domainMismatch && (
+        <Alert severity="error">
+          This login is for {siwe.domain}, but it was requested by a different
+          site. Signing it may let that site log in as you
+        </Alert>
+      )}
+      {siwe.wrongAccount && (
+        <Alert severity="warning">
+          This login names a different account than the one signing it
+        </Alert>
+      )}
+      {siwe.wrongChain && (
+        <Alert severity="warning">
+          This login is for a different chain than the current one
+        </Alert>
+      )}
+      {expired && <Alert severity="warning">This login has expired</Alert>}
+      {siwe.statement && (
+        <Typography sx={{ whiteSpace: "pre-wrap" }}>{siwe.statement}</Typography>
+      )}
+      <Fields
+        title="Details"
+        fields={{
+          Domain: siwe.domain,
+          Account: siwe.address,
+          URI: siwe.uri,
+          "Chain ID": siwe.chainId,
+          Nonce: siwe.nonce,
+          "Issued at": siwe.issuedAt,
+          ...(siwe.expirationTime && { "Expires at": siwe.expirationTime }),
+        }}
+      />
+    </>
+  );
+}
+
+// A permit, which lets the spender move tokens without any further
+// transaction from the signer
+function PermitPreview({ permits }: { permits: Permit[] }) {
+  return (
+    <>
+      <Alert severity="info">
+        Signing this lets the spender move your tokens, without any further
+        confirmation from you
+      </Alert>
+      {permits.some((p) => p.unlimited) && (
+        <Alert severity="warning">
+          This approves unlimited spending. The spender can move every token
+          of this kind you hold, now or in the future
+        </Alert>
+      )}
+      {permits.map((permit, i) => (
+        <Fields
+          key={i}
+          title={permit.symbol || "Token"}
+          fields={{
+            Token: permit.token,
+            Spender: permit.spender,
+            Amount: amount(permit),
+            ...(permit.deadline && { Expires: deadline(permit.deadline) }),
+          }}
+        />
+      ))}
+    </>
+  );
+}
+
+function amount({ amount, unlimited, symbol, decimals }: Permit) {
+  if (unlimited) return `Unlimited ${symbol || ""}`.trim();
+  const value =
+    decimals != null
+      ? formatUnits(BigInt(amount), decimals)
+      : BigInt(amount).toString();
+  return `${value} ${symbol || ""}`.trim();
+}
+
+function deadline(timestamp: string) {
+  const seconds = BigInt(timestamp);
+  if (seconds === 0n) return "After a single use";
+  // far off deadlines don't fit in a date, and never expire in practice
+  if (seconds > 8_640_000_000_000n) return "Never";
+  return new Date(Number(seconds) * 1000).toLocaleString();
+}
+
+interface FieldsProps {
+  title: string;
+  fields: Record<string, unknown>;
+}
+
+function Fields({ title, fields }: FieldsProps) {
+  return (
+    <>
+      <Typography variant="subtitle2">{title}</Typography>
+      <Table size="small">
+        <TableBody>
+          {Object.entries(fields).map(([key, value]) => (
+            <TableRow key={key}>
+              <TableCell>{key}</TableCell>
+              <TableCell sx={{ wordBreak: "break-all" }}>
+                {typeof value === "object"
+                  ? JSON.stringify(value, null, 2)
+                  : String(value)}
+              </TableCell>
+            </TableRow>
+          ))}
+        </TableBody>
+      </Table>
+    </>
+  );
+}
@@ -0,0 +1,79 @@
+import { Box, Button, Stack, Tooltip, Typography } from "@mui/material";
+import { green, grey, orange, red } from "@mui/material/colors";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useEffect } from "react";
+
+import { useInvoke } from "../hooks";
+import { useNetworks } from "../store";
+import { HealthStatus, NetworkHealth } from "../types";
+
+const colors: Record<HealthStatus, string> = {
+  healthy: green[500],
+  degraded: orange[500],
+  down: red[500],
+  unknown: grey[500],
+};
+
+// Status of the current network's RPC, with a suggestion to switch endpoints
+// when the active one degrades and another is healthy
+export function NetworkHealthIndicator() {
+  const current = useNetworks((s) => s.current);
+  const { data: health, mutate } = useInvoke<Record<string, NetworkHealth>>(
+    "networks_get_health"
+  );
+
+  useEffect(() => {
+    const unlisten = listen("network-health-updated", () => mutate());
+
+    return () => {
+      unlisten.then((cb) => cb());
+    };
+  }, [mutate]);
+
+  const network = current && health?.[current.name];
+  if (!current || !network) return null;
+
+  const active = network.endpoints.find((e) => e.url === network.activeUrl);
+  const details = active
+    ? [
+        `${active.url}: ${active.status}`,
+        active.latencyMs != null && `latency ${active.latencyMs}ms`,
+        active.blockNumber != null && `block ${active.blockNumber}`,
+        !!active.lag && `${active.lag} blocks behind`,
+      ]
+        .filter(Boolean)
+        .join(", ")
+    : network.status;
+
+  const handleSwitch = () =>
+    invoke("networks_use_rpc_url", {
+      network: current.name,
+      url: network.suggestedUrl,
+    }).then(() => mutate());
+
+  return (
+    <Stack direction="row" alignItems="center" spacing={1}>
+      <Tooltip title={details}>
+        <Stack direction="row" alignItems="center" spacing={1}>
+          <Box
+            sx={{
+              width: 8,
+              height: 8,
+              borderRadius: "50%",
+              backgroundColor: colors[network.status],
+            }}
+          />
+          <Typography variant="caption">RPC {network.status}</Typography>
+        </Stack>
+      </Tooltip>
+      {network.suggestedUrl && (
+        <Tooltip title={`Switch to ${network.suggestedUrl}`}>
+          <Button size="small" onClick={handleSwitch}>
+            Switch
+          </Button>
+        </Tooltip>
+      )}
+    </Stack>
+  );
+}
@@ -1,13 +1,16 @@
 import {
   Badge,
   Box,
+  Button,
   FormControl,
   MenuItem,
   Select,
   SelectChangeEvent,
   Stack,
+  TextField,
   Typography,
 } from "@mui/material";
+import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/tauri";
 import { map } from "lodash-es";
 import { useEffect, useState } from "react";
@@ -26,6 +29,29 @@ interface Peer {
   favicon: string;
 }
 
+interface Session extends Peer {
+  connected_at: number;
+  chain_id?: number | null;
+  accounts: string[];
+}
+
+interface PendingConnection {
+  id: number;
+  origin: string;
+  peer: { title?: string; favicon?: string };
+  phishing: boolean;
+}
+
+interface WalletConnectSession {
+  topic: string;
+  peer: { name: string; url: string; icons: string[] };
+}
+
+interface Approval {
+  origin: string;
+  account?: string;
+}
+
 export function Peers() {
   const { data: peersByDomain, mutate } =
     useInvoke<Record<string, Peer[]>>("ws_peers_by_domain");
@@ -35,6 +61,11 @@ export function Peers() {
   return (
     <Panel>
       <Stack spacing={2}>
+        <PendingConnections />
+        <WalletConnectPairing />
+        <WalletConnectSessions />
+        <Approvals />
+        <Sessions />
         {map(peersByDomain, (peers, domain) => (
           <Domain key={domain} domain={domain} peers={peers} />
         ))}
@@ -43,6 +74,202 @@ export function Peers() {
   );
 }
 
+function PendingConnections() {
+  const { data: pending, mutate } = useInvoke<PendingConnection[]>(
+    "connections_get_pending"
+  );
+
+  useRefreshPeers(mutate);
+
+  return (
+    <>
+      {(pending || []).map((request) => (
+        <Stack key={request.id} direction="row" alignItems="center" spacing={2}>
+          <Badge>
+            <img width="30" height="30" src={request.peer.favicon} />
+          </Badge>
+          <Typography color={request.phishing ? "error" : undefined}>
+            {request.origin} wants to connect
+            {request.phishing && " (known phishing site)"}
+          </Typography>
+          <Stack direction="row" spacing={1} sx={{ "&&": { ml: "auto" } }}>
+            <Button
+              size="small"
+              variant="contained"
+              onClick={() => invoke("connections_approve", { id: request.id })}
+            >
+              Approve
+            </Button>
+            <Button
+              size="small"
+              color="warning"
+              onClick={() => invoke("connections_reject", { id: request.id })}
+            >
+              Reject
+            </Button>
+          </Stack>
+        </Stack>
+      ))}
+    </>
+  );
+}
+
+function WalletConnectPairing() {
+  const [uri, setUri] = useState("");
+
+  const pair = () => {
+    invoke("walletconnect_pair", { uri }).then(() => setUri(""));
+  };
+
+  return (
+    <Stack direction="row" alignItems="center" spacing={2}>
+      <TextField
+        label="WalletConnect URI"
+        size="small"
+        value={uri}
+        onChange={(e) => setUri(e.target.value)}
+        fullWidth
+      />
+      <Button
+        size="small"
+        variant="contained"
+        disabled={!uri.startsWith("wc:")}
+        onClick={pair}
+      >
+        Pair
+      </Button>
+    </Stack>
+  );
+}
+
+function WalletConnectSessions() {
+  const { data: sessions, mutate } = useInvoke<WalletConnectSession[]>(
+    "walletconnect_get_sessions"
+  );
+
+  useRefreshPeers(mutate);
+
+  return (
+    <>
+      {(sessions || []).map((session) => (
+        <Stack
+          key={session.topic}
+          direction="row"
+          alignItems="center"
+          spacing={2}
+        >
+          <Badge>
+            <img width="30" height="30" src={session.peer.icons[0]} />
+          </Badge>
+          <Typography>{session.peer.name} (WalletConnect)</Typography>
+          <Box sx={{ "&&": { ml: "auto" } }}>
+            <Button
+              size="small"
+              color="warning"
+              onClick={() =>
+                invoke("walletconnect_disconnect", { topic: session.topic })
+              }
+            >
+              Disconnect
+            </Button>
+          </Box>
+        </Stack>
+      ))}
+    </>
+  );
+}
+
+function Approvals() {
+  const { data: approvals, mutate } = useInvoke<Approval[]>(
+    "connections_get_approved"
+  );
+
+  useRefreshPeers(mutate);
+
+  return (
+    <>
+      {(approvals || []).map((approval) => (
+        <Stack
+          key={approval.origin}
+          direction="row"
+          alignItems="center"
+          spacing={2}
+        >
+          <Typography>
+            {approval.origin}
+            {approval.account && ` (only ${approval.account})`}
+          </Typography>
+          <Box sx={{ "&&": { ml: "auto" } }}>
+            <Button
+              size="small"
+              color="warning"
+              onClick={() =>
+                invoke("connections_revoke", { origin: approval.origin })
+              }
+            >
+              Revoke
+            </Button>
+          </Box>
+        </Stack>
+      ))}
+    </>
+  );
+}
+
+function Sessions() {
+  const { data: sessions, mutate } = useInvoke<Session[]>("ws_get_sessions");
+  const networks = useNetworks((s) => s.networks);
+
+  useEffect(() => {
+    const unlisteners = ["peer-connected", "peer-disconnected"].map((event) =>
+      listen(event, () => mutate())
+    );
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((cb) => cb()));
+    };
+  }, [mutate]);
+
+  return (
+    <>
+      {(sessions || []).map((session) => (
+        <Stack
+          key={session.socket}
+          direction="row"
+          alignItems="center"
+          spacing={2}
+        >
+          <Stack>
+            <Typography>{session.title || session.origin}</Typography>
+            <Typography variant="caption">
+              {session.origin} &middot; since{" "}
+              {new Date(session.connected_at * 1000).toLocaleTimeString()}{" "}
+              &middot;{" "}
+              {networks.find((n) => n.chain_id === session.chain_id)?.name ??
+                "unknown chain"}{" "}
+              &middot;{" "}
+              {session.accounts.length
+                ? session.accounts.join(", ")
+                : "no accounts"}
+            </Typography>
+          </Stack>
+          <Box sx={{ "&&": { ml: "auto" } }}>
+            <Button
+              size="small"
+              color="warning"
+              onClick={() =>
+                invoke("ws_disconnect_peer", { socket: session.socket })
+              }
+            >
+              Disconnect
+            </Button>
+          </Box>
+        </Stack>
+      ))}
+    </>
+  );
+}
+
 function Domain({ domain, peers }: { domain: string; peers: Peer[] }) {
   return (
     <Stack direction="row" alignItems="center" spacing={2}>
@@ -61,8 +61,12 @@ function getCurrentPath(wallet: Wallet, addresses: [string, Address][]) {
       return wallet.current ? wallet.current[0] : addresses[0][0];
 
     case "impersonator":
+    case "watchOnly":
       return wallet.addresses[wallet.current || 0];
 
+    case "privateKey":
+      return addresses[0][0];
+
     default:
       return wallet.currentPath || addresses[0][0];
   }
@@ -0,0 +1,86 @@
+import {
+  Alert,
+  Button,
+  List,
+  ListItem,
+  ListItemText,
+  Stack,
+  TextField,
+} from "@mui/material";
+import { invoke } from "@tauri-apps/api/tauri";
+import { FormEvent, useState } from "react";
+
+import { useInvoke } from "../../hooks";
+import { useNetworks } from "../../store";
+import { Network } from "../../types";
+
+// Adds popular chains in one click, or any other found in chainlist's dataset
+export function AddChain() {
+  const networks = useNetworks((s) => s.networks);
+  const { data: presets } = useInvoke<Network[]>("networks_get_presets");
+  const [query, setQuery] = useState("");
+  const [results, setResults] = useState<Network[]>();
+  const [error, setError] = useState<string>();
+
+  const search = async (e: FormEvent) => {
+    e.preventDefault();
+    setError(undefined);
+    try {
+      setResults(await invoke<Network[]>("networks_search_chains", { query }));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const add = async (network: Network) => {
+    setError(undefined);
+    try {
+      await invoke("networks_add", { network });
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  // networks listen for changes, so added chains drop out of the list on their own
+  const added = new Set(networks.map((n) => n.chain_id));
+  const chains = (results ?? presets ?? []).filter(
+    (n) => !added.has(n.chain_id)
+  );
+
+  return (
+    <Stack spacing={2} sx={{ mb: 4 }}>
+      <form onSubmit={search}>
+        <Stack direction="row" spacing={2}>
+          <TextField
+            label="Find a chain by name or ID"
+            size="small"
+            value={query}
+            onChange={(e) => setQuery(e.target.value)}
+            fullWidth
+          />
+          <Button variant="outlined" type="submit" disabled={!query}>
+            Search
+          </Button>
+        </Stack>
+      </form>
+      {error && <Alert severity="error">{error}</Alert>}
+      <List dense disablePadding>
+        {chains.map((network) => (
+          <ListItem
+            key={network.chain_id}
+            secondaryAction={
+              <Button size="small" onClick={() => add(network)}>
+                Add
+              </Button>
+            }
+          >
+            <ListItemText
+              primary={network.name}
+              secondary={`${network.chain_id} · ${network.currency}`}
+            />
+          </ListItem>
+        ))}
+      </List>
+    </Stack>
+  );
+}
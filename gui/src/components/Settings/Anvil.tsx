@@ -0,0 +1,210 @@
+import {
+  Alert,
+  Button,
+  Chip,
+  Stack,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useState } from "react";
+import { parseEther, toHex } from "viem";
+
+import { useInvoke } from "../../hooks";
+
+// Cheatcodes for the current network, which only work on a local dev chain
+export function SettingsAnvil() {
+  const { data: impersonated, mutate } = useInvoke<string[]>(
+    "networks_anvil_get_impersonated"
+  );
+  const [address, setAddress] = useState("");
+  const [balance, setBalance] = useState("");
+  const [blocks, setBlocks] = useState("");
+  const [timestamp, setTimestamp] = useState("");
+  const [to, setTo] = useState("");
+  const [value, setValue] = useState("");
+  const [snapshots, setSnapshots] = useState<string[]>([]);
+  const [result, setResult] = useState<{ ok: boolean; message: string }>();
+
+  const run = async (action: () => Promise<string>) => {
+    try {
+      setResult({ ok: true, message: await action() });
+    } catch (e) {
+      setResult({ ok: false, message: String(e) });
+    }
+  };
+
+  const impersonate = () =>
+    run(async () => {
+      await invoke("networks_anvil_impersonate", { address });
+      mutate();
+      return `Impersonating ${address}`;
+    });
+
+  const stopImpersonating = (address: string) =>
+    run(async () => {
+      await invoke("networks_anvil_stop_impersonating", { address });
+      mutate();
+      return `Stopped impersonating ${address}`;
+    });
+
+  const sendAs = () =>
+    run(async () => {
+      const hash = await invoke<string>("rpc_send_transaction", {
+        params: { from: address, to, value: toHex(parseEther(value || "0")) },
+      });
+      return `Sent ${hash}`;
+    });
+
+  const setAccountBalance = () =>
+    run(async () => {
+      await invoke("networks_anvil_set_balance", {
+        address,
+        balance: toHex(parseEther(balance)),
+      });
+      return `Balance of ${address} set to ${balance}`;
+    });
+
+  const mine = () =>
+    run(async () => {
+      await invoke("networks_anvil_mine", {
+        blocks: blocks ? parseInt(blocks) : null,
+      });
+      return `Mined ${blocks || 1} block(s)`;
+    });
+
+  const setNextTimestamp = () =>
+    run(async () => {
+      await invoke("networks_anvil_set_next_block_timestamp", {
+        timestamp: parseInt(timestamp),
+      });
+      return `Next block at ${timestamp}`;
+    });
+
+  const snapshot = () =>
+    run(async () => {
+      const id = await invoke<string>("networks_anvil_snapshot");
+      setSnapshots([...snapshots, id]);
+      return `Snapshot ${id} taken`;
+    });
+
+  // reverting discards the snapshot, along with every later one
+  const revert = (id: string) =>
+    run(async () => {
+      const reverted = await invoke<boolean>("networks_anvil_revert", { id });
+      setSnapshots(snapshots.slice(0, snapshots.indexOf(id)));
+      return reverted ? `Reverted to ${id}` : `Snapshot ${id} no longer exists`;
+    });
+
+  return (
+    <Stack spacing={3} alignItems="flex-start">
+      <Stack spacing={2} alignItems="flex-start" width="100%">
+        <Typography variant="subtitle2">Accounts</Typography>
+        <TextField
+          label="Address"
+          value={address}
+          onChange={(e) => setAddress(e.target.value)}
+          fullWidth
+        />
+        <Stack direction="row" spacing={2}>
+          <TextField
+            label="Balance (ether)"
+            size="small"
+            value={balance}
+            onChange={(e) => setBalance(e.target.value)}
+          />
+          <Button
+            variant="contained"
+            disabled={!address || !balance}
+            onClick={setAccountBalance}
+          >
+            Set balance
+          </Button>
+          <Button variant="contained" disabled={!address} onClick={impersonate}>
+            Impersonate
+          </Button>
+        </Stack>
+        <Stack direction="row" spacing={1} flexWrap="wrap">
+          {impersonated?.map((account) => (
+            <Chip
+              key={account}
+              label={account}
+              onClick={() => setAddress(account)}
+              onDelete={() => stopImpersonating(account)}
+            />
+          ))}
+        </Stack>
+        {impersonated?.includes(address) && (
+          <Stack direction="row" spacing={2}>
+            <TextField
+              label="To"
+              size="small"
+              value={to}
+              onChange={(e) => setTo(e.target.value)}
+            />
+            <TextField
+              label="Value (ether)"
+              size="small"
+              value={value}
+              onChange={(e) => setValue(e.target.value)}
+            />
+            <Button variant="contained" disabled={!to} onClick={sendAs}>
+              Send as
+            </Button>
+          </Stack>
+        )}
+      </Stack>
+      <Stack spacing={2} alignItems="flex-start">
+        <Typography variant="subtitle2">Blocks</Typography>
+        <Stack direction="row" spacing={2}>
+          <TextField
+            label="Blocks"
+            size="small"
+            type="number"
+            value={blocks}
+            onChange={(e) => setBlocks(e.target.value)}
+          />
+          <Button variant="contained" onClick={mine}>
+            Mine
+          </Button>
+        </Stack>
+        <Stack direction="row" spacing={2}>
+          <TextField
+            label="Next block timestamp"
+            size="small"
+            type="number"
+            value={timestamp}
+            onChange={(e) => setTimestamp(e.target.value)}
+          />
+          <Button
+            variant="contained"
+            disabled={!timestamp}
+            onClick={setNextTimestamp}
+          >
+            Set
+          </Button>
+        </Stack>
+      </Stack>
+      <Stack spacing={2} alignItems="flex-start">
+        <Typography variant="subtitle2">Snapshots</Typography>
+        <Button variant="contained" onClick={snapshot}>
+          Take snapshot
+        </Button>
+        <Stack direction="row" spacing={1} flexWrap="wrap">
+          {snapshots.map((id) => (
+            <Chip
+              key={id}
+              label={`Revert to ${id}`}
+              onClick={() => revert(id)}
+            />
+          ))}
+        </Stack>
+      </Stack>
+      {result && (
+        <Alert severity={result.ok ? "success" : "error"}>
+          {result.message}
+        </Alert>
+      )}
+    </Stack>
+  );
+}
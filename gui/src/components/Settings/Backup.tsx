@@ -0,0 +1,176 @@
+import {
+  Alert,
+  Button,
+  Checkbox,
+  FormControlLabel,
+  MenuItem,
+  Stack,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { open, save } from "@tauri-apps/api/dialog";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useState } from "react";
+
+import { useNetworks, useWallets } from "../../store";
+
+const filters = [{ name: "Iron backup", extensions: ["json"] }];
+
+export function SettingsBackup() {
+  const [password, setPassword] = useState("");
+  const [overwrite, setOverwrite] = useState(false);
+  const [result, setResult] = useState<{ ok: boolean; message: string }>();
+  const [format, setFormat] = useState<"csv" | "json">("csv");
+  const [fromBlock, setFromBlock] = useState("");
+  const [toBlock, setToBlock] = useState("");
+  const [includeSignatures, setIncludeSignatures] = useState(false);
+  const chainId = useNetworks((s) => s.current?.chain_id);
+  const address = useWallets((s) => s.address);
+
+  const run = async (action: () => Promise<string | null>) => {
+    try {
+      const message = await action();
+      if (message) setResult({ ok: true, message });
+    } catch (e) {
+      setResult({ ok: false, message: String(e) });
+    }
+  };
+
+  const exportBackup = () =>
+    run(async () => {
+      const path = await save({ defaultPath: "iron-backup.json", filters });
+      if (!path) return null;
+
+      await invoke("backup_export", { path, password });
+      return `Backup written to ${path}`;
+    });
+
+  const restoreBackup = () =>
+    run(async () => {
+      const path = await open({ multiple: false, filters });
+      if (!path || Array.isArray(path)) return null;
+
+      await invoke("backup_restore", { path, password, overwrite });
+      return "Backup restored";
+    });
+
+  const exportLogs = () =>
+    run(async () => {
+      const path = await save({
+        defaultPath: "iron.log",
+        filters: [{ name: "Log", extensions: ["log", "txt"] }],
+      });
+      if (!path) return null;
+
+      await invoke("logs_export", { path });
+      return `Logs written to ${path}`;
+    });
+
+  const exportHistory = () =>
+    run(async () => {
+      const path = await save({
+        defaultPath: `iron-history.${format}`,
+        filters: [{ name: format.toUpperCase(), extensions: [format] }],
+      });
+      if (!path) return null;
+
+      const range = {
+        chainId,
+        address,
+        fromBlock: fromBlock ? Number(fromBlock) : null,
+        toBlock: toBlock ? Number(toBlock) : null,
+      };
+      const count = await invoke<number>("db_export_history", {
+        path,
+        range,
+        format,
+        includeSignatures,
+      });
+      return `${count} transactions written to ${path}`;
+    });
+
+  return (
+    <Stack spacing={2} alignItems="flex-start">
+      <TextField
+        label="Backup password"
+        type="password"
+        value={password}
+        onChange={(e) => setPassword(e.target.value)}
+      />
+      <FormControlLabel
+        label="Replace current wallets, networks and contacts when restoring"
+        control={
+          <Checkbox
+            checked={overwrite}
+            onChange={(e) => setOverwrite(e.target.checked)}
+          />
+        }
+      />
+      <Stack direction="row" spacing={2}>
+        <Button
+          variant="contained"
+          disabled={!password}
+          onClick={exportBackup}
+        >
+          Export
+        </Button>
+        <Button
+          variant="contained"
+          color="warning"
+          disabled={!password}
+          onClick={restoreBackup}
+        >
+          Restore
+        </Button>
+        <Button variant="outlined" onClick={exportLogs}>
+          Export logs
+        </Button>
+      </Stack>
+      <Typography variant="h6">Transaction history</Typography>
+      <Stack direction="row" spacing={2}>
+        <TextField
+          select
+          label="Format"
+          value={format}
+          onChange={(e) => setFormat(e.target.value as "csv" | "json")}
+        >
+          <MenuItem value="csv">CSV</MenuItem>
+          <MenuItem value="json">JSON</MenuItem>
+        </TextField>
+        <TextField
+          label="From block"
+          type="number"
+          value={fromBlock}
+          onChange={(e) => setFromBlock(e.target.value)}
+        />
+        <TextField
+          label="To block"
+          type="number"
+          value={toBlock}
+          onChange={(e) => setToBlock(e.target.value)}
+        />
+      </Stack>
+      <FormControlLabel
+        label="Include signed messages"
+        control={
+          <Checkbox
+            checked={includeSignatures}
+            onChange={(e) => setIncludeSignatures(e.target.checked)}
+          />
+        }
+      />
+      <Button
+        variant="outlined"
+        disabled={!chainId || !address}
+        onClick={exportHistory}
+      >
+        Export history
+      </Button>
+      {result && (
+        <Alert severity={result.ok ? "success" : "error"}>
+          {result.message}
+        </Alert>
+      )}
+    </Stack>
+  );
+}
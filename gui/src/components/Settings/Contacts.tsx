@@ -0,0 +1,177 @@
+import { zodResolver } from "@hookform/resolvers/zod";
+import { ExpandMore } from "@mui/icons-material";
+import {
+  Accordion,
+  AccordionDetails,
+  AccordionSummary,
+  Button,
+  Chip,
+  Stack,
+  TextField,
+  Typography,
+} from "@mui/material";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useEffect, useState } from "react";
+import { FieldValues, useForm } from "react-hook-form";
+import truncateEthAddress from "truncate-eth-address";
+
+import { useInvoke } from "../../hooks";
+import { Contact, contactSchema } from "../../types";
+
+type NewChild = { new?: boolean };
+
+const emptyContact: Contact = { name: "", address: "" };
+
+export function SettingsContacts() {
+  const { data: contacts, mutate } = useInvoke<Contact[]>("contacts_get_all");
+  const [newContact, setNewContact] = useState<Contact>();
+
+  // deployments are added from the backend as forge scripts broadcast
+  useEffect(() => {
+    const unlisten = listen("contacts-updated", () => mutate());
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [mutate]);
+
+  if (!contacts) return null;
+
+  const all: (Contact & NewChild)[] = newContact
+    ? [...contacts, { ...newContact, new: true }]
+    : contacts;
+
+  const save = async (contact: Contact & NewChild, params: FieldValues) => {
+    const updated = {
+      ...params,
+      chainId: params.chainId ? Number(params.chainId) : null,
+    };
+    if (contact.new) {
+      await invoke("contacts_add", { contact: updated });
+      setNewContact(undefined);
+    } else {
+      await invoke("contacts_update", {
+        address: contact.address,
+        chainId: contact.chainId,
+        contact: updated,
+      });
+    }
+    mutate();
+  };
+
+  const remove = async (contact: Contact & NewChild) => {
+    if (contact.new) {
+      setNewContact(undefined);
+    } else {
+      await invoke("contacts_remove", {
+        address: contact.address,
+        chainId: contact.chainId,
+      });
+      mutate();
+    }
+  };
+
+  return (
+    <Stack spacing={2} alignItems="flex-start">
+      <Stack alignSelf="stretch">
+        {all.map((contact) => (
+          <Accordion
+            key={`${contact.address}-${contact.chainId}`}
+            defaultExpanded={contact.new}
+          >
+            <AccordionSummary expandIcon={<ExpandMore />}>
+              <Stack alignItems="center" direction="row" spacing={1}>
+                <Typography>
+                  {contact.new ? "New contact" : contact.name}
+                </Typography>
+                {!contact.new && (
+                  <Typography variant="body2">
+                    {truncateEthAddress(contact.address)}
+                  </Typography>
+                )}
+                {contact.chainId && (
+                  <Chip size="small" label={`chain ${contact.chainId}`} />
+                )}
+                {contact.deployment && <Chip size="small" label="deployment" />}
+              </Stack>
+            </AccordionSummary>
+            <AccordionDetails>
+              <ContactForm
+                contact={contact}
+                onSubmit={(params) => save(contact, params)}
+                onRemove={() => remove(contact)}
+              />
+            </AccordionDetails>
+          </Accordion>
+        ))}
+      </Stack>
+      <Button
+        variant="contained"
+        disabled={!!newContact}
+        onClick={() => setNewContact(emptyContact)}
+      >
+        Add contact
+      </Button>
+    </Stack>
+  );
+}
+
+interface ContactFormProps {
+  contact: Contact;
+  onSubmit: (params: FieldValues) => void;
+  onRemove: () => void;
+}
+
+function ContactForm({ contact, onSubmit, onRemove }: ContactFormProps) {
+  const {
+    handleSubmit,
+    register,
+    formState: { isValid, errors },
+  } = useForm({
+    mode: "onChange",
+    resolver: zodResolver(contactSchema),
+    defaultValues: contact,
+  });
+
+  return (
+    <form onSubmit={handleSubmit(onSubmit)}>
+      <Stack spacing={2}>
+        <TextField
+          label="Name"
+          error={!!errors.name}
+          helperText={errors.name?.message?.toString()}
+          {...register("name")}
+        />
+        <TextField
+          label="Address"
+          error={!!errors.address}
+          helperText={errors.address?.message?.toString()}
+          {...register("address")}
+        />
+        <TextField
+          label="Chain ID"
+          error={!!errors.chainId}
+          helperText={
+            errors.chainId?.message?.toString() || "Leave empty for all chains"
+          }
+          {...register("chainId")}
+        />
+        <TextField
+          label="Notes"
+          multiline
+          error={!!errors.notes}
+          helperText={errors.notes?.message?.toString()}
+          {...register("notes")}
+        />
+        <Stack direction="row" spacing={2}>
+          <Button variant="contained" type="submit" disabled={!isValid}>
+            Save
+          </Button>
+          <Button color="warning" onClick={onRemove}>
+            Remove
+          </Button>
+        </Stack>
+      </Stack>
+    </form>
+  );
+}
@@ -0,0 +1,46 @@
+import { Alert, Button, Stack, Typography } from "@mui/material";
+import { invoke } from "@tauri-apps/api/tauri";
+
+import { useInvoke } from "../../hooks";
+import { CopyToClipboard } from "../";
+
+// The token the browser extension is paired with. Connections without it are
+// refused, so that other local processes can't pose as the extension
+export function SettingsExtension() {
+  const { data: token, mutate } = useInvoke<string | null>(
+    "ws_get_connection_token"
+  );
+
+  const regenerate = async () => {
+    await invoke("ws_regenerate_connection_token");
+    mutate();
+  };
+
+  return (
+    <Stack spacing={2} alignItems="flex-start">
+      <Typography>
+        Paste this token into the extension&apos;s options to pair it with the
+        app.
+      </Typography>
+      {token ? (
+        <CopyToClipboard label={token}>
+          <Typography sx={{ fontFamily: "monospace", wordBreak: "break-all" }}>
+            {token}
+          </Typography>
+        </CopyToClipboard>
+      ) : (
+        <Alert severity="error">
+          No connection token could be loaded, so the extension can&apos;t
+          connect
+        </Alert>
+      )}
+      <Button variant="contained" color="warning" onClick={regenerate}>
+        Regenerate
+      </Button>
+      <Typography variant="body2" color="text.secondary">
+        Regenerating disconnects every dapp, until the extension is paired
+        again with the new token
+      </Typography>
+    </Stack>
+  );
+}
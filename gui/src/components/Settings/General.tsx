@@ -16,11 +16,14 @@ import { invoke } from "@tauri-apps/api/tauri";
 import { useCallback, useEffect } from "react";
 import { Controller, FieldValues, useForm } from "react-hook-form";
 
-import { useInvoke } from "../../hooks";
+import { useInvoke, useRefreshSettings } from "../../hooks";
+import { useNetworks } from "../../store";
 import { GeneralSettings, generalSettingsSchema } from "../../types";
 
 export function SettingsGeneral() {
   const { data: general, mutate } = useInvoke<GeneralSettings>("settings_get");
+  const networks = useNetworks((s) => s.networks);
+  useRefreshSettings(mutate);
 
   const {
     handleSubmit,
@@ -119,6 +122,39 @@ export function SettingsGeneral() {
           error={!!errors.etherscanApiKey}
           helperText={errors.etherscanApiKey?.message?.toString()}
         />
+        <TextField
+          label="WalletConnect Project ID"
+          {...register("walletconnectProjectId")}
+          fullWidth
+          error={!!errors.walletconnectProjectId}
+          helperText={errors.walletconnectProjectId?.message?.toString()}
+        />
+        <FormControl>
+          <InputLabel id="priceSource">Price source</InputLabel>
+          <Controller
+            name="priceSource"
+            defaultValue={general.priceSource}
+            control={control}
+            render={({ field }) => (
+              <Select
+                aria-labelledby="priceSource"
+                size="small"
+                label="Price source"
+                sx={{ minWidth: 120 }}
+                {...field}
+              >
+                <MenuItem value={"coingecko"}>CoinGecko</MenuItem>
+                <MenuItem value={"chainlink"}>Chainlink</MenuItem>
+              </Select>
+            )}
+          />
+        </FormControl>
+        <TextField
+          label="Fiat currency"
+          {...register("fiatCurrency")}
+          error={!!errors.fiatCurrency}
+          helperText={errors.fiatCurrency?.message?.toString()}
+        />
         <FormControl error={!!errors.hideEmptyTokens}>
           <FormGroup>
             <FormControlLabel
@@ -139,6 +175,104 @@ export function SettingsGeneral() {
             />
           </FormGroup>
         </FormControl>
+        <FormControl error={!!errors.stateDiffPreview}>
+          <FormGroup>
+            <FormControlLabel
+              label="Preview state changes of transactions under review"
+              control={
+                <Controller
+                  name="stateDiffPreview"
+                  control={control}
+                  render={({ field }) => (
+                    <Checkbox
+                      {...field}
+                      checked={field.value}
+                      onChange={(e) => field.onChange(e.target.checked)}
+                    />
+                  )}
+                />
+              }
+            />
+          </FormGroup>
+        </FormControl>
+        <FormControl>
+          <InputLabel id="defaultNetwork">Default network</InputLabel>
+          <Controller
+            name="defaultNetwork"
+            defaultValue={general.defaultNetwork ?? ""}
+            control={control}
+            render={({ field }) => (
+              <Select
+                aria-labelledby="defaultNetwork"
+                size="small"
+                label="Default network"
+                sx={{ minWidth: 120 }}
+                {...field}
+                value={field.value ?? ""}
+                onChange={(e) => field.onChange(e.target.value || null)}
+              >
+                <MenuItem value={""}>Last used</MenuItem>
+                {networks.map(({ name }) => (
+                  <MenuItem key={name} value={name}>
+                    {name}
+                  </MenuItem>
+                ))}
+              </Select>
+            )}
+          />
+        </FormControl>
+        <FormControl>
+          <InputLabel id="gasPreset">Gas preset</InputLabel>
+          <Controller
+            name="gasPreset"
+            defaultValue={general.gasPreset}
+            control={control}
+            render={({ field }) => (
+              <Select
+                aria-labelledby="gasPreset"
+                size="small"
+                label="Gas preset"
+                sx={{ minWidth: 120 }}
+                {...field}
+              >
+                <MenuItem value={"slow"}>Slow</MenuItem>
+                <MenuItem value={"normal"}>Normal</MenuItem>
+                <MenuItem value={"fast"}>Fast</MenuItem>
+              </Select>
+            )}
+          />
+        </FormControl>
+        <TextField
+          label="Auto-lock after (minutes)"
+          {...register("autoLockMinutes")}
+          error={!!errors.autoLockMinutes}
+          helperText={
+            errors.autoLockMinutes?.message?.toString() ||
+            "Leave empty to never lock"
+          }
+        />
+        {(
+          [
+            ["blocklistUrls", "Blocklist URLs, one per line"],
+            ["customBlocklist", "Blocked domains and addresses, one per line"],
+          ] as const
+        ).map(([name, label]) => (
+          <Controller
+            key={name}
+            name={name}
+            control={control}
+            render={({ field }) => (
+              <TextField
+                label={label}
+                multiline
+                fullWidth
+                spellCheck="false"
+                value={(field.value || []).join("\n")}
+                onChange={(e) => field.onChange(e.target.value.split("\n"))}
+              />
+            )}
+          />
+        ))}
         <Button
           variant="contained"
           type="submit"
@@ -5,6 +5,8 @@ import {
   AccordionDetails,
   AccordionSummary,
   Button,
+  Checkbox,
+  FormControlLabel,
   Stack,
   TextField,
 } from "@mui/material";
@@ -14,6 +16,7 @@ import { useFieldArray, useForm } from "react-hook-form";
 import { ConfirmationDialog } from "../";
 import { useNetworks } from "../../store";
 import { Network, networkSchema } from "../../types";
+import { AddChain } from "./AddChain";
 
 type NewChild = { new?: boolean };
 
@@ -27,6 +30,16 @@ const emptyNetwork: Network & NewChild = {
   chain_id: undefined!,
   // eslint-disable-next-line @typescript-eslint/no-non-null-assertion
   decimals: undefined!,
+  fallback_rpc_urls: [],
+  supports_eip1559: true,
+  explorer_api_url: "",
+  explorer_api_key: "",
+  bundler_url: "",
+  private_rpc_url: "",
+  private_by_default: false,
+  rpc_timeout_secs: 30,
+  rpc_retries: 2,
+  rpc_backoff_ms: 500,
   new: true,
 };
 
@@ -69,122 +82,214 @@ export function SettingsNetwork() {
   if (!networks) return <>Loading</>;
 
   return (
-    <form onSubmit={handleSubmit(onSubmit)}>
-      {fields.map((field, index) => {
-        //        const item = networks[index];
-        const err = (errors.networks && errors.networks[index]) || {};
-        return (
-          <Accordion key={field.id} defaultExpanded={field.new}>
-            <AccordionSummary expandIcon={<ExpandMore />}>
-              {field.name} - {field.chain_id}
-            </AccordionSummary>
-            <AccordionDetails>
-              <Stack spacing={2} alignItems="flex-start">
-                <Stack spacing={2} direction="row">
+    <>
+      <AddChain />
+      <form onSubmit={handleSubmit(onSubmit)}>
+        {fields.map((field, index) => {
+          //        const item = networks[index];
+          const err = (errors.networks && errors.networks[index]) || {};
+          return (
+            <Accordion key={field.id} defaultExpanded={field.new}>
+              <AccordionSummary expandIcon={<ExpandMore />}>
+                {field.name} - {field.chain_id}
+              </AccordionSummary>
+              <AccordionDetails>
+                <Stack spacing={2} alignItems="flex-start">
+                  <Stack spacing={2} direction="row">
+                    <TextField
+                      label="Name"
+                      error={!!err.name}
+                      helperText={err.name?.message?.toString()}
+                      {...register(`networks.${index}.name`)}
+                    />
+                    <TextField
+                      label="Chain Id"
+                      error={!!err.chain_id}
+                      helperText={err.chain_id?.message?.toString()}
+                      {...register(`networks.${index}.chain_id`, {
+                        valueAsNumber: true,
+                      })}
+                    />
+                  </Stack>
                   <TextField
-                    label="Name"
-                    error={!!err.name}
-                    helperText={err.name?.message?.toString()}
-                    {...register(`networks.${index}.name`)}
+                    label="HTTP RPC"
+                    {...register(`networks.${index}.http_url`)}
+                    fullWidth
+                    error={!!err.http_url}
+                    helperText={err.http_url?.message?.toString()}
                   />
                   <TextField
-                    label="Chain Id"
-                    error={!!err.chain_id}
-                    helperText={err.chain_id?.message?.toString()}
-                    {...register(`networks.${index}.chain_id`, {
-                      valueAsNumber: true,
-                    })}
+                    label="WebSockets URL"
+                    {...register(`networks.${index}.ws_url`)}
+                    fullWidth
+                    error={!!err.ws_url}
+                    helperText={err.ws_url?.message?.toString()}
                   />
-                </Stack>
-                <TextField
-                  label="HTTP RPC"
-                  {...register(`networks.${index}.http_url`)}
-                  fullWidth
-                  error={!!err.http_url}
-                  helperText={err.http_url?.message?.toString()}
-                />
-                <TextField
-                  label="WebSockets URL"
-                  {...register(`networks.${index}.ws_url`)}
-                  fullWidth
-                  error={!!err.ws_url}
-                  helperText={err.ws_url?.message?.toString()}
-                />
-                <TextField
-                  label="Explorer URL"
-                  {...register(`networks.${index}.explorer_url`)}
-                  fullWidth
-                  error={!!err.explorer_url}
-                  helperText={err.explorer_url?.message?.toString()}
-                />
-                <Stack spacing={2} direction="row">
                   <TextField
-                    label="Currency"
-                    {...register(`networks.${index}.currency`)}
-                    error={!!err.currency}
-                    helperText={err.currency?.message?.toString()}
+                    label="Explorer URL"
+                    {...register(`networks.${index}.explorer_url`)}
+                    fullWidth
+                    error={!!err.explorer_url}
+                    helperText={err.explorer_url?.message?.toString()}
+                  />
+                  <Stack spacing={2} direction="row">
+                    <TextField
+                      label="Explorer API URL"
+                      {...register(`networks.${index}.explorer_api_url`)}
+                      fullWidth
+                      error={!!err.explorer_api_url}
+                      helperText={
+                        err.explorer_api_url?.message?.toString() ||
+                        "Etherscan-compatible. Known chains default to Etherscan"
+                      }
+                    />
+                    <TextField
+                      label="Explorer API key"
+                      {...register(`networks.${index}.explorer_api_key`)}
+                      fullWidth
+                      error={!!err.explorer_api_key}
+                      helperText={err.explorer_api_key?.message?.toString()}
+                    />
+                  </Stack>
+                  <TextField
+                    label="ERC-4337 bundler URL"
+                    {...register(`networks.${index}.bundler_url`)}
+                    fullWidth
+                    error={!!err.bundler_url}
+                    helperText={
+                      err.bundler_url?.message?.toString() ||
+                      "Enables smart accounts on this network"
+                    }
                   />
                   <TextField
-                    label="Decimals"
-                    {...register(`networks.${index}.decimals`, {
-                      valueAsNumber: true,
-                    })}
-                    error={!!err.decimals}
-                    helperText={err.decimals?.message?.toString()}
+                    label="Private relay URL"
+                    {...register(`networks.${index}.private_rpc_url`)}
+                    fullWidth
+                    error={!!err.private_rpc_url}
+                    helperText={
+                      err.private_rpc_url?.message?.toString() ||
+                      "Such as Flashbots Protect, to keep transactions out of the public mempool"
+                    }
                   />
+                  <Stack spacing={2} direction="row">
+                    <TextField
+                      label="Currency"
+                      {...register(`networks.${index}.currency`)}
+                      error={!!err.currency}
+                      helperText={err.currency?.message?.toString()}
+                    />
+                    <TextField
+                      label="Decimals"
+                      {...register(`networks.${index}.decimals`, {
+                        valueAsNumber: true,
+                      })}
+                      error={!!err.decimals}
+                      helperText={err.decimals?.message?.toString()}
+                    />
+                  </Stack>
+                  <Stack spacing={2} direction="row">
+                    <TextField
+                      label="RPC timeout (s)"
+                      {...register(`networks.${index}.rpc_timeout_secs`, {
+                        valueAsNumber: true,
+                      })}
+                      error={!!err.rpc_timeout_secs}
+                      helperText={err.rpc_timeout_secs?.message?.toString()}
+                    />
+                    <TextField
+                      label="Retries"
+                      {...register(`networks.${index}.rpc_retries`, {
+                        valueAsNumber: true,
+                      })}
+                      error={!!err.rpc_retries}
+                      helperText={
+                        err.rpc_retries?.message?.toString() ||
+                        "Once every RPC URL failed"
+                      }
+                    />
+                    <TextField
+                      label="Backoff (ms)"
+                      {...register(`networks.${index}.rpc_backoff_ms`, {
+                        valueAsNumber: true,
+                      })}
+                      error={!!err.rpc_backoff_ms}
+                      helperText={
+                        err.rpc_backoff_ms?.message?.toString() ||
+                        "Doubles with each retry"
+                      }
+                    />
+                  </Stack>
+                  <FormControlLabel
+                    label="EIP-1559 fees"
+                    control={
+                      <Checkbox
+                        defaultChecked={field.supports_eip1559}
+                        {...register(`networks.${index}.supports_eip1559`)}
+                      />
+                    }
+                  />
+                  <FormControlLabel
+                    label="Send privately by default"
+                    control={
+                      <Checkbox
+                        defaultChecked={field.private_by_default}
+                        {...register(`networks.${index}.private_by_default`)}
+                      />
+                    }
+                  />
+                  <Button
+                    color="warning"
+                    size="small"
+                    onClick={() => remove(index)}
+                  >
+                    Remove
+                  </Button>
                 </Stack>
-                <Button
-                  color="warning"
-                  size="small"
-                  onClick={() => remove(index)}
-                >
-                  Remove
-                </Button>
-              </Stack>
-            </AccordionDetails>
-          </Accordion>
-        );
-      })}
-      <Stack spacing={2} direction="row" sx={{ mt: 4, mb: 2 }}>
-        <Button
-          color="primary"
-          variant="contained"
-          type="submit"
-          disabled={!isDirtyAlt || !isValid}
-        >
-          Save
-        </Button>
-        <Button
-          variant="outlined"
-          color="info"
-          size="medium"
-          onClick={() => append(emptyNetwork)}
-        >
-          Add network
-        </Button>
-      </Stack>
-      <ConfirmationDialog
-        content={
-          <>
-            You are about to reset the networks to their default configuration.
-            This action will replace your existing networks.
-          </>
-        }
-        title="Reset Networks"
-        confirmationLabel="Reset Networks"
-        onConfirm={resetNetworks}
-      >
-        {({ onOpen }) => (
+              </AccordionDetails>
+            </Accordion>
+          );
+        })}
+        <Stack spacing={2} direction="row" sx={{ mt: 4, mb: 2 }}>
+          <Button
+            color="primary"
+            variant="contained"
+            type="submit"
+            disabled={!isDirtyAlt || !isValid}
+          >
+            Save
+          </Button>
           <Button
             variant="outlined"
-            color="warning"
+            color="info"
             size="medium"
-            onClick={() => onOpen()}
+            onClick={() => append(emptyNetwork)}
           >
-            Reset Networks
+            Add network
           </Button>
-        )}
-      </ConfirmationDialog>
-    </form>
+        </Stack>
+        <ConfirmationDialog
+          content={
+            <>
+              You are about to reset the networks to their default
+              configuration. This action will replace your existing networks.
+            </>
+          }
+          title="Reset Networks"
+          confirmationLabel="Reset Networks"
+          onConfirm={resetNetworks}
+        >
+          {({ onOpen }) => (
+            <Button
+              variant="outlined"
+              color="warning"
+              size="medium"
+              onClick={() => onOpen()}
+            >
+              Reset Networks
+            </Button>
+          )}
+        </ConfirmationDialog>
+      </form>
+    </>
   );
 }
@@ -9,6 +9,10 @@ import {
 import { find } from "lodash-es";
 import { useState } from "react";
 
+import { SettingsAnvil } from "./Anvil";
+import { SettingsBackup } from "./Backup";
+import { SettingsContacts } from "./Contacts";
+import { SettingsExtension } from "./Extension";
 import { SettingsGeneral } from "./General";
 import { SettingsNetwork } from "./Network";
 import { SettingsWallets } from "./Wallets";
@@ -17,6 +21,10 @@ const TABS = [
   { name: "General", component: SettingsGeneral },
   { name: "Wallets", component: SettingsWallets },
   { name: "Network", component: SettingsNetwork },
+  { name: "Contacts", component: SettingsContacts },
+  { name: "Extension", component: SettingsExtension },
+  { name: "Backup", component: SettingsBackup },
+  { name: "Anvil", component: SettingsAnvil },
 ];
 
 const WIDTH = 140;
@@ -0,0 +1,138 @@
+import {
+  Button,
+  ButtonGroup,
+  Stack,
+  Table,
+  TableBody,
+  TableCell,
+  TableContainer,
+  TableRow,
+  TextField,
+} from "@mui/material";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useState } from "react";
+import truncateEthAddress from "truncate-eth-address";
+import { formatUnits } from "viem";
+
+import { useProvider } from "../../../hooks";
+import { DerivedAccount, derivationPathSchema } from "../../../types";
+
+const presets = [
+  { label: "MetaMask", path: "m/44'/60'/0'/0" },
+  { label: "Ledger Live", path: "m/44'/60'/x'/0/0" },
+  { label: "Ledger Legacy", path: "m/44'/60'/0'" },
+];
+
+const previewCount = 10;
+
+interface DerivationProps {
+  name: string;
+  derivationPath: string;
+}
+
+// Previews the accounts another derivation path gives, with their balances,
+// before switching the wallet to one of them
+export function Derivation({ name, derivationPath }: DerivationProps) {
+  const provider = useProvider();
+  const decimals = provider?.chain?.nativeCurrency.decimals || 18;
+  const symbol = provider?.chain?.nativeCurrency.symbol || "ETH";
+
+  const [path, setPath] = useState(derivationPath);
+  const [accounts, setAccounts] = useState<DerivedAccount[]>([]);
+  const [selected, setSelected] = useState<number | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const valid = derivationPathSchema.safeParse(path).success;
+
+  const preview = (path: string) => {
+    setPath(path);
+    setSelected(null);
+    setError(null);
+    invoke<DerivedAccount[]>("wallets_preview_derivation", {
+      name,
+      derivationPath: path,
+      count: previewCount,
+    })
+      .then(setAccounts)
+      .catch((e) => {
+        setAccounts([]);
+        setError(String(e));
+      });
+  };
+
+  const apply = () => {
+    if (selected === null) return;
+    invoke("wallets_set_derivation", {
+      name,
+      derivationPath: path,
+      idx: selected,
+    }).catch((e) => setError(String(e)));
+  };
+
+  return (
+    <Stack spacing={2} alignSelf="stretch">
+      <ButtonGroup size="small">
+        {presets.map((preset) => (
+          <Button
+            key={preset.label}
+            variant={path === preset.path ? "contained" : "outlined"}
+            onClick={() => preview(preset.path)}
+          >
+            {preset.label}
+          </Button>
+        ))}
+      </ButtonGroup>
+      <Stack direction="row" spacing={2} alignItems="center">
+        <TextField
+          size="small"
+          label="Preview derivation path"
+          spellCheck="false"
+          value={path}
+          error={!valid || !!error}
+          helperText={error || (!valid && "invalid path format")}
+          onChange={(e) => setPath(e.target.value)}
+        />
+        <Button
+          variant="outlined"
+          disabled={!valid}
+          onClick={() => preview(path)}
+        >
+          Preview
+        </Button>
+      </Stack>
+      {accounts.length > 0 && (
+        <>
+          <TableContainer>
+            <Table size="small">
+              <TableBody>
+                {accounts.map(({ path, address, balance }, idx) => (
+                  <TableRow
+                    hover
+                    selected={selected === idx}
+                    sx={{ cursor: "pointer" }}
+                    onClick={() => setSelected(idx)}
+                    key={path}
+                  >
+                    <TableCell>{path}</TableCell>
+                    <TableCell>{truncateEthAddress(address)}</TableCell>
+                    <TableCell align="right">
+                      {balance && formatUnits(BigInt(balance), decimals)}{" "}
+                      {balance && symbol}
+                    </TableCell>
+                  </TableRow>
+                ))}
+              </TableBody>
+            </Table>
+          </TableContainer>
+          <Button
+            variant="contained"
+            disabled={selected === null}
+            onClick={apply}
+          >
+            Use this account
+          </Button>
+        </>
+      )}
+    </Stack>
+  );
+}
@@ -21,6 +21,8 @@ import { formatUnits } from "viem";
 import { z } from "zod";
 
 import { useProvider } from "../../../hooks";
+import { Derivation } from "./Derivation";
+import { Keychain } from "./Keychain";
 import {
   Address,
   Wallet,
@@ -44,6 +46,9 @@ type FormType = z.infer<typeof createSchema> | z.infer<typeof updateSchema>;
 
 const steps = ["Import", "Secure", "Review"];
 
+const derivationPathHint =
+  "MetaMask: m/44'/60'/0'/0 · Ledger Live: m/44'/60'/x'/0/0 · Ledger Legacy: m/44'/60'/0'";
+
 interface Props {
   type: "create" | "update";
   wallet: Wallet & { type: "HDWallet" };
@@ -292,7 +297,9 @@ function ReviewStep({ mnemonic, onSubmit, onCancel }: ReviewStepProps) {
     invoke<[string, Address][]>("wallets_get_mnemonic_addresses", {
       mnemonic,
       derivationPath,
-    }).then(setAddresses);
+    })
+      .then(setAddresses)
+      .catch(() => setAddresses([]));
   }, [mnemonic, derivationPath]);
 
   return (
@@ -305,7 +312,9 @@ function ReviewStep({ mnemonic, onSubmit, onCancel }: ReviewStepProps) {
       <TextField
         label="Derivation Path"
         error={!!errors.derivationPath}
-        helperText={errors.derivationPath?.message?.toString()}
+        helperText={
+          errors.derivationPath?.message?.toString() || derivationPathHint
+        }
         {...register("derivationPath")}
       />
       {isValid && (
@@ -426,6 +435,8 @@ function HDWalletUpdateForm({
         helperText={errors.count?.message?.toString() || ""}
         {...register("count", { valueAsNumber: true })}
       />
+      <Derivation name={wallet.name} derivationPath={wallet.derivationPath} />
+      <Keychain name={wallet.name} enabled={!!wallet.keychain} />
       <Stack direction="row" spacing={2}>
         <Button
           color="primary"
@@ -45,6 +45,13 @@ export function JsonKeystore({
         fullWidth
         {...register("file")}
       />
+      <TextField
+        label="Passphrase"
+        type="password"
+        helperText="Checked on import. Only needed if the file doesn't list its address"
+        fullWidth
+        {...register("password")}
+      />
       <Stack direction="row" spacing={2}>
         <Button
           color="primary"
@@ -0,0 +1,45 @@
+import { Button, Stack, TextField } from "@mui/material";
+import { invoke } from "@tauri-apps/api/tauri";
+import { useState } from "react";
+
+interface KeychainProps {
+  name: string;
+  // plaintext wallets have no password, and are encrypted when moved to the keychain
+  plaintext?: boolean;
+  enabled: boolean;
+}
+
+export function Keychain({ name, plaintext, enabled }: KeychainProps) {
+  const [password, setPassword] = useState("");
+
+  const toggle = () => {
+    invoke("wallets_set_keychain", {
+      name,
+      enabled: !enabled,
+      password: plaintext ? null : password,
+    }).then(() => setPassword(""));
+  };
+
+  if (plaintext) {
+    return (
+      <Button variant="outlined" onClick={toggle}>
+        Encrypt and keep in OS keychain
+      </Button>
+    );
+  }
+
+  return (
+    <Stack direction="row" spacing={2} alignItems="center">
+      <TextField
+        type="password"
+        size="small"
+        label={enabled ? "New password" : "Password"}
+        value={password}
+        onChange={(e) => setPassword(e.target.value)}
+      />
+      <Button variant="outlined" disabled={!password} onClick={toggle}>
+        {enabled ? "Remove from OS keychain" : "Keep in OS keychain"}
+      </Button>
+    </Stack>
+  );
+}
@@ -0,0 +1,72 @@
+import { zodResolver } from "@hookform/resolvers/zod";
+import { Button, Stack, TextField } from "@mui/material";
+import { useForm } from "react-hook-form";
+
+import { Wallet, walletSchema } from "../../../types";
+import { Derivation } from "./Derivation";
+
+interface Props {
+  wallet: Wallet & { type: "ledger" };
+  onSubmit: (data: Wallet & { type: "ledger" }) => void;
+  onRemove: () => void;
+}
+
+export function LedgerForm({ wallet, onSubmit, onRemove }: Props) {
+  const {
+    register,
+    handleSubmit,
+    formState: { isValid, isDirty, errors },
+  } = useForm({
+    mode: "onBlur",
+    resolver: zodResolver(walletSchema),
+    defaultValues: wallet,
+  });
+
+  return (
+    <Stack
+      spacing={2}
+      alignItems="flex-start"
+      component="form"
+      onSubmit={handleSubmit(onSubmit)}
+    >
+      <input type="hidden" {...register("type")} />
+      <TextField
+        label="Name"
+        error={!!errors.name}
+        helperText={errors.name?.message?.toString()}
+        {...register("name")}
+      />
+      <TextField
+        label="Derivation Path"
+        spellCheck="false"
+        error={!!errors.derivationPath}
+        helperText={
+          errors.derivationPath?.message?.toString() ||
+          "Ledger Live: m/44'/60'/x'/0/0 · Ledger Legacy: m/44'/60'/0'"
+        }
+        {...register("derivationPath")}
+      />
+      <TextField
+        label="Address count"
+        spellCheck="false"
+        error={!!errors.count}
+        helperText={errors.count?.message?.toString() || ""}
+        {...register("count", { valueAsNumber: true })}
+      />
+      <Derivation name={wallet.name} derivationPath={wallet.derivationPath} />
+      <Stack direction="row" spacing={2}>
+        <Button
+          color="primary"
+          variant="contained"
+          type="submit"
+          disabled={!isDirty || !isValid}
+        >
+          Save
+        </Button>
+        <Button color="warning" variant="contained" onClick={onRemove}>
+          Remove
+        </Button>
+      </Stack>
+    </Stack>
+  );
+}
@@ -12,6 +12,8 @@ import {
 import { Controller, useForm } from "react-hook-form";
 
 import { Wallet, walletSchema } from "../../../types";
+import { Derivation } from "./Derivation";
+import { Keychain } from "./Keychain";
 
 export interface Props {
   wallet: Wallet & { type: "plaintext" };
@@ -95,6 +97,10 @@ export function Plaintext({ wallet, onSubmit, onRemove }: Props) {
         helperText={errors.count?.message?.toString() || ""}
         {...register("count", { valueAsNumber: true })}
       />
+      <Derivation name={wallet.name} derivationPath={wallet.derivationPath} />
+      {!wallet.dev && (
+        <Keychain name={wallet.name} plaintext enabled={false} />
+      )}
       <Stack direction="row" spacing={2}>
         <Button
           color="primary"
@@ -0,0 +1,91 @@
+import { zodResolver } from "@hookform/resolvers/zod";
+import {
+  Button,
+  Checkbox,
+  FormControlLabel,
+  Stack,
+  TextField,
+} from "@mui/material";
+import { Controller, useForm } from "react-hook-form";
+
+import { Wallet, walletSchema } from "../../../types";
+
+interface Props {
+  wallet: Wallet & { type: "privateKey" };
+  onSubmit: (data: Wallet & { type: "privateKey" }) => void;
+  onRemove: () => void;
+}
+
+export function PrivateKeyForm({ wallet, onSubmit, onRemove }: Props) {
+  const {
+    register,
+    handleSubmit,
+    control,
+    formState: { isValid, isDirty, errors },
+  } = useForm({
+    mode: "onBlur",
+    resolver: zodResolver(walletSchema),
+    defaultValues: wallet,
+  });
+
+  return (
+    <Stack
+      spacing={2}
+      alignItems="flex-start"
+      component="form"
+      onSubmit={handleSubmit(onSubmit)}
+    >
+      <input type="hidden" {...register("type")} />
+      <TextField
+        label="Name"
+        error={!!errors.name}
+        helperText={errors.name?.message?.toString()}
+        {...register("name")}
+      />
+      <FormControlLabel
+        label="Dev account"
+        control={
+          <Controller
+            name="dev"
+            control={control}
+            render={({ field }) => (
+              <Checkbox
+                {...field}
+                checked={field.value}
+                onChange={(e) => field.onChange(e.target.checked)}
+              />
+            )}
+          />
+        }
+      />
+      <TextField
+        label="Private key"
+        type="password"
+        error={!!errors.privateKey}
+        helperText={errors.privateKey?.message?.toString() || ""}
+        fullWidth
+        {...register("privateKey")}
+      />
+      <TextField
+        label="Password"
+        type="password"
+        helperText="Encrypts the key at rest. Only dev accounts may go without"
+        fullWidth
+        {...register("password")}
+      />
+      <Stack direction="row" spacing={2}>
+        <Button
+          color="primary"
+          variant="contained"
+          type="submit"
+          disabled={!isDirty || !isValid}
+        >
+          Save
+        </Button>
+        <Button color="warning" variant="contained" onClick={onRemove}>
+          Remove
+        </Button>
+      </Stack>
+    </Stack>
+  );
+}
@@ -0,0 +1,114 @@
+import { zodResolver } from "@hookform/resolvers/zod";
+import { Button, Stack, TextField } from "@mui/material";
+import { useFieldArray, useForm } from "react-hook-form";
+import { z } from "zod";
+
+import { Wallet, addressSchema } from "../../../types";
+
+// same object-array workaround as the impersonator form
+const schema = z.object({
+  type: z.literal("watchOnly"),
+  name: z.string().min(1),
+  addresses: z
+    .array(
+      z.object({
+        address: addressSchema,
+      })
+    )
+    .min(1),
+  current: z.number().optional(),
+});
+
+type Schema = z.infer<typeof schema>;
+
+interface Props {
+  wallet: Wallet & { type: "watchOnly" };
+  onSubmit: (data: Wallet & { type: "watchOnly" }) => void;
+  onRemove: () => void;
+}
+
+export function WatchOnlyForm({ wallet, onSubmit, onRemove }: Props) {
+  const formWallet = {
+    ...wallet,
+    addresses: wallet.addresses.map((address) => ({ address })),
+  };
+
+  const {
+    register,
+    handleSubmit,
+    control,
+    formState: { isValid, isDirty, errors },
+  } = useForm({
+    mode: "onBlur",
+    resolver: zodResolver(schema),
+    defaultValues: formWallet,
+  });
+
+  const prepareAndSubmit = (data: Schema) => {
+    onSubmit({
+      ...data,
+      addresses: data.addresses.map(({ address }) => address),
+    });
+  };
+
+  const {
+    fields: addressFields,
+    append,
+    remove,
+  } = useFieldArray({
+    control,
+    name: "addresses",
+  });
+
+  return (
+    <Stack
+      spacing={2}
+      alignItems="flex-start"
+      component="form"
+      onSubmit={handleSubmit(prepareAndSubmit)}
+    >
+      <input type="hidden" {...register("type")} />
+      <TextField
+        label="Name"
+        error={!!errors.name}
+        helperText={errors.name?.message?.toString()}
+        {...register("name")}
+      />
+      {addressFields.map((field, i) => (
+        <Stack alignSelf="stretch" key={field.id} direction="row" spacing={2}>
+          <TextField
+            label="Address"
+            fullWidth
+            error={!!errors.addresses && !!errors.addresses[i]}
+            helperText={
+              errors.addresses && errors.addresses[i]?.address?.message
+            }
+            {...register(`addresses.${i}.address`)}
+          />
+          <Button
+            onClick={() => remove(i)}
+            disabled={addressFields.length === 1}
+          >
+            Remove
+          </Button>
+        </Stack>
+      ))}
+      <Button color="secondary" onClick={() => append({ address: "" })}>
+        Add
+      </Button>
+      <Stack direction="row" spacing={2}>
+        <Button
+          color="primary"
+          variant="contained"
+          type="submit"
+          disabled={!isDirty || !isValid}
+        >
+          Save
+        </Button>
+        <Button color="warning" variant="contained" onClick={onRemove}>
+          Remove
+        </Button>
+      </Stack>
+    </Stack>
+  );
+}
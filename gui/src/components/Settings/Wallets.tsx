@@ -19,7 +19,10 @@ import { Wallet, walletTypes } from "../../types";
 import { HDWalletForm } from "./Wallet/HDWallet";
 import { ImpersonatorForm } from "./Wallet/Impersonator";
 import { JsonKeystore } from "./Wallet/JsonKeystore";
+import { LedgerForm } from "./Wallet/Ledger";
 import { Plaintext } from "./Wallet/Plaintext";
+import { PrivateKeyForm } from "./Wallet/PrivateKey";
+import { WatchOnlyForm } from "./Wallet/WatchOnly";
 
 type NewChild = { new?: boolean };
 
@@ -96,6 +99,15 @@ export function SettingsWallets() {
                 {wallet.type === "impersonator" && (
                   <ImpersonatorForm wallet={wallet} {...props} />
                 )}
+                {wallet.type === "privateKey" && (
+                  <PrivateKeyForm wallet={wallet} {...props} />
+                )}
+                {wallet.type === "watchOnly" && (
+                  <WatchOnlyForm wallet={wallet} {...props} />
+                )}
+                {wallet.type === "ledger" && (
+                  <LedgerForm wallet={wallet} {...props} />
+                )}
               </AccordionDetails>
             </Accordion>
           );
@@ -193,4 +205,24 @@ const emptyWallets: Record<Wallet["type"], Wallet & NewChild> = {
     addresses: [""],
     new: true,
   },
+  privateKey: {
+    type: "privateKey",
+    name: "",
+    privateKey: "",
+    dev: false,
+    new: true,
+  },
+  watchOnly: {
+    type: "watchOnly",
+    name: "",
+    addresses: [""],
+    new: true,
+  },
+  ledger: {
+    type: "ledger",
+    name: "",
+    derivationPath: "m/44'/60'/x'/0/0",
+    count: 5,
+    new: true,
+  },
 };
@@ -1,6 +1,7 @@
 import CallToActionIcon from "@mui/icons-material/CallToAction";
 import OnlinePredictionSharpIcon from "@mui/icons-material/OnlinePredictionSharp";
 import ReceiptIcon from "@mui/icons-material/Receipt";
+import RemoveModeratorIcon from "@mui/icons-material/RemoveModerator";
 import RequestQuoteSharpIcon from "@mui/icons-material/RequestQuoteSharp";
 import { Box, Button, Drawer, IconButton, Stack, Toolbar } from "@mui/material";
 import { blue } from "@mui/material/colors";
@@ -13,7 +14,9 @@ import { useKeyPress, useMenuAction, useOS } from "../hooks";
 import { useTheme } from "../store";
 import {
   Account,
+  Approvals,
   Contracts,
+  NetworkHealthIndicator,
   Peers,
   QuickAddressSelect,
   QuickNetworkSelect,
@@ -37,6 +40,12 @@ export const TABS = [
     component: Txs,
     icon: ReceiptIcon,
   },
+  {
+    path: "approvals",
+    name: "Approvals",
+    component: Approvals,
+    icon: RemoveModeratorIcon,
+  },
   {
     path: "contracts",
     name: "Contracts",
@@ -178,6 +187,7 @@ export function Sidebar() {
             <QuickWalletSelect />
             <QuickAddressSelect />
             <QuickNetworkSelect />
+            <NetworkHealthIndicator />
           </Stack>
           <Stack
             p={3}
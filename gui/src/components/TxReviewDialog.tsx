@@ -1,46 +1,346 @@
-import { Button, Stack, Typography } from "@mui/material";
-import { formatEther } from "viem";
+import { ExpandMore } from "@mui/icons-material";
+import {
+  Accordion,
+  AccordionDetails,
+  AccordionSummary,
+  Button,
+  FormControlLabel,
+  Stack,
+  Switch,
+  ToggleButton,
+  ToggleButtonGroup,
+  Typography,
+} from "@mui/material";
+import { useState } from "react";
+import { formatUnits } from "viem";
 
-import { useDialog } from "../hooks";
+import { useDialog, useInvoke } from "../hooks";
+import { useBalances } from "../store";
+import { GeneralSettings } from "../types";
+import { formatFiat } from "../utils";
 import { AddressView, ContextMenu } from "./";
 
+export type TxWarning =
+  | { type: "unlimitedApproval"; token?: string; spender: string }
+  | { type: "phishingOrigin"; origin: string }
+  | { type: "maliciousAddress"; address: string }
+  | { type: "unverifiedContract"; address: string };
+
+export interface DecodedCall {
+  contract?: string | null;
+  signature: string;
+  args: { name: string; value: string }[];
+}
+
+export interface L1Fee {
+  rollup: "opStack" | "arbitrum";
+  fee: string;
+  includedInGas: boolean;
+}
+
+export interface Simulation {
+  gasUsed?: string | null;
+  l1Fee?: L1Fee | null;
+  balanceChanges: { token?: string | null; delta: string }[];
+  transfers?: { token: string; from: string; to: string; amount: string }[];
+}
+
+interface Change {
+  before: string;
+  after: string;
+}
+
+export interface AccountDiff {
+  address: string;
+  balance?: Change | null;
+  nonce?: Change | null;
+  codeChanged: boolean;
+  storage: ({ slot: string } & Change)[];
+}
+
+export type Fees =
+  | { type: "eip1559"; maxFeePerGas: string; maxPriorityFeePerGas: string }
+  | { type: "legacy"; gasPrice: string };
+
+type Speed = "slow" | "normal" | "fast";
+
 export interface TxRequest {
   data: string;
   from: string;
   to: string;
+  toName?: string | null;
   value: string;
+  warnings?: TxWarning[];
+  revert?: string;
+  nativeCurrency?: { symbol: string; decimals: number };
+  decoded?: DecodedCall | null;
+  simulation?: Simulation;
+  // only computed when enabled in settings, and null if the node can't trace
+  stateDiff?: AccountDiff[] | null;
+  feeSuggestions?: Record<Speed, Fees> | null;
+  replaces?: string | null;
+  userOperation?: object;
+  privateRelay?: boolean;
+  private?: boolean;
+  // signed and handed back, to be broadcast elsewhere
+  signOnly?: boolean;
 }
 
 export function TxReviewDialog({ id }: { id: number }) {
   const { data, accept, reject } = useDialog<TxRequest>(id);
+  // left unset unless the user picks one, so that fees set by the dapp are kept
+  const [speed, setSpeed] = useState<Speed>();
+  // likewise left unset, so that the network's default applies
+  const [sendPrivately, setSendPrivately] = useState<boolean>();
+  const prices = useBalances((s) => s.prices);
+  // the speed fees were filled in at, unless the dapp set its own
+  const { data: settings } = useInvoke<GeneralSettings>("settings_get");
 
   if (!data) return null;
 
-  const { from, to, value: valueStr, data: calldata } = data;
+  const {
+    from,
+    to,
+    toName,
+    value: valueStr,
+    data: calldata,
+    warnings = [],
+    revert,
+    decoded,
+    simulation,
+    stateDiff,
+    feeSuggestions,
+    replaces,
+    userOperation,
+    privateRelay,
+    signOnly,
+    nativeCurrency = { symbol: "ETH", decimals: 18 },
+  } = data;
   const value = BigInt(valueStr || 0);
+  const isPrivate = sendPrivately ?? !!data.private;
+  const l1Fee = simulation?.l1Fee;
+
+  const submit = () => {
+    const fees = speed && feeSuggestions?.[speed];
+    const reviewed = { ...data, private: isPrivate };
+    accept(fees ? { ...reviewed, ...fees } : reviewed);
+  };
 
   return (
     <Stack direction="column" spacing={2} sx={{ p: 2 }}>
       <Typography variant="h6" component="h1">
-        Transaction review
+        {replaces
+          ? "Replace pending transaction"
+          : signOnly
+          ? "Sign transaction"
+          : "Transaction review"}
       </Typography>
+      {signOnly && (
+        <Typography variant="body2">
+          Only signed, not broadcast. Whoever holds the signed transaction can
+          broadcast it at any time
+        </Typography>
+      )}
+      {replaces && (
+        <Typography variant="body2">Replaces {replaces}</Typography>
+      )}
+      {userOperation && (
+        <Typography variant="body2">
+          Sent from your smart account, through the network&apos;s bundler
+        </Typography>
+      )}
       <Stack direction="row" justifyContent="space-between">
         <Stack direction="row" alignItems="center" spacing={1}>
           <AddressView address={from} /> <span>→</span>{" "}
           <AddressView address={to} />
+          {toName && <Typography variant="body2">({toName})</Typography>}
         </Stack>
-        <ContextMenu>{formatEther(BigInt(value))} Ξ</ContextMenu>
+        <ContextMenu>
+          {formatUnits(value, nativeCurrency.decimals)} {nativeCurrency.symbol}
+          {prices?.native &&
+            value > 0n &&
+            ` (${formatFiat(
+              Number(formatUnits(value, nativeCurrency.decimals)) *
+                prices.native.price,
+              prices.currency
+            )})`}
+        </ContextMenu>
       </Stack>
-      <Typography>data: {calldata}</Typography>
+      {decoded ? (
+        <Stack>
+          <Typography>
+            {decoded.contract && `${decoded.contract}.`}
+            {decoded.signature}
+          </Typography>
+          {decoded.args.map(({ name, value }, i) => (
+            <Typography key={i} variant="body2">
+              {name || `#${i}`}: {value}
+            </Typography>
+          ))}
+        </Stack>
+      ) : (
+        <Typography>data: {calldata}</Typography>
+      )}
+      {warnings.map((warning, i) => (
+        <Stack key={i} direction="row" alignItems="center" spacing={1}>
+          <TxWarningView warning={warning} />
+        </Stack>
+      ))}
+      {revert && (
+        <Typography color="error">
+          This transaction will revert: {revert}
+        </Typography>
+      )}
+      {simulation?.gasUsed && (
+        <Typography variant="body2">
+          Expected gas: {BigInt(simulation.gasUsed).toString()}
+        </Typography>
+      )}
+      {l1Fee && (
+        <Typography variant="body2">
+          L1 data fee:{" "}
+          {formatUnits(BigInt(l1Fee.fee), nativeCurrency.decimals)}{" "}
+          {nativeCurrency.symbol}
+          {l1Fee.includedInGas ? " (included in gas)" : " (on top of gas)"}
+        </Typography>
+      )}
+      {simulation?.balanceChanges.map(({ token, delta }, i) => (
+        <Stack key={i} direction="row" alignItems="center" spacing={1}>
+          <Typography
+            variant="body2"
+            color={delta.startsWith("-") ? "error" : "success.main"}
+          >
+            {token
+              ? delta
+              : `${formatUnits(BigInt(delta), nativeCurrency.decimals)} ${
+                  nativeCurrency.symbol
+                }`}
+          </Typography>
+          {token && <AddressView address={token} />}
+        </Stack>
+      ))}
+      {stateDiff && (
+        <Accordion disableGutters>
+          <AccordionSummary expandIcon={<ExpandMore />}>
+            <Typography variant="body2">
+              State changes ({stateDiff.length} accounts)
+            </Typography>
+          </AccordionSummary>
+          <AccordionDetails>
+            <Stack spacing={1}>
+              {stateDiff.map((diff) => (
+                <AccountDiffView key={diff.address} diff={diff} />
+              ))}
+            </Stack>
+          </AccordionDetails>
+        </Accordion>
+      )}
+
+      {/* replacements are priced above the original, which suggestions may not be */}
+      {feeSuggestions && !replaces && !userOperation && (
+        <ToggleButtonGroup
+          size="small"
+          exclusive
+          value={speed ?? settings?.gasPreset ?? "normal"}
+          onChange={(_e, value) => value && setSpeed(value)}
+        >
+          {(["slow", "normal", "fast"] as Speed[]).map((s) => (
+            <ToggleButton key={s} value={s}>
+              {s} ({formatGwei(feeSuggestions[s])} gwei)
+            </ToggleButton>
+          ))}
+        </ToggleButtonGroup>
+      )}
+
+      {privateRelay && !userOperation && (
+        <FormControlLabel
+          control={
+            <Switch
+              checked={isPrivate}
+              onChange={(e) => setSendPrivately(e.target.checked)}
+            />
+          }
+          label="Send privately, keeping it out of the public mempool"
+        />
+      )}
 
       <Stack direction="row" justifyContent="center" spacing={2}>
         <Button variant="contained" color="error" onClick={() => reject()}>
           Cancel
         </Button>
-        <Button variant="contained" type="submit" onClick={() => accept(data)}>
-          Submit
+        <Button variant="contained" type="submit" onClick={submit}>
+          {signOnly ? "Sign" : "Submit"}
         </Button>
       </Stack>
     </Stack>
   );
 }
+
+function AccountDiffView({ diff }: { diff: AccountDiff }) {
+  const { address, balance, nonce, codeChanged, storage } = diff;
+  const change = ({ before, after }: Change) =>
+    `${BigInt(before).toString()} → ${BigInt(after).toString()}`;
+
+  return (
+    <Stack>
+      <AddressView address={address} />
+      {balance && (
+        <Typography variant="caption">balance: {change(balance)}</Typography>
+      )}
+      {nonce && (
+        <Typography variant="caption">nonce: {change(nonce)}</Typography>
+      )}
+      {codeChanged && (
+        <Typography variant="caption">code deployed</Typography>
+      )}
+      {storage.map(({ slot, before, after }) => (
+        <Typography
+          key={slot}
+          variant="caption"
+          sx={{ fontFamily: "monospace", wordBreak: "break-all" }}
+        >
+          {slot}: {before} → {after}
+        </Typography>
+      ))}
+    </Stack>
+  );
+}
+
+function formatGwei(fees: Fees) {
+  const wei = fees.type === "eip1559" ? fees.maxFeePerGas : fees.gasPrice;
+  return Number(formatUnits(BigInt(wei), 9)).toFixed(2);
+}
+
+function TxWarningView({ warning }: { warning: TxWarning }) {
+  switch (warning.type) {
+    case "unlimitedApproval":
+      return (
+        <>
+          <Typography color="error">Unlimited token approval to</Typography>
+          <AddressView address={warning.spender} />
+        </>
+      );
+    case "phishingOrigin":
+      return (
+        <Typography color="error">
+          {warning.origin} is a known phishing site
+        </Typography>
+      );
+    case "maliciousAddress":
+      return (
+        <>
+          <Typography color="error">Known malicious address</Typography>
+          <AddressView address={warning.address} />
+        </>
+      );
+    case "unverifiedContract":
+      return (
+        <>
+          <Typography color="warning.main">
+            First interaction with unverified contract
+          </Typography>
+          <AddressView address={warning.address} />
+        </>
+      );
+  }
+}
@@ -13,7 +13,7 @@ import { invoke } from "@tauri-apps/api/tauri";
 import "core-js/features/array/at";
 import { createElement, useCallback, useEffect, useState } from "react";
 import InfiniteScroll from "react-infinite-scroller";
-import { formatEther } from "viem";
+import { formatUnits } from "viem";
 
 import { useRefreshTransactions } from "../hooks";
 import { useNetworks, useWallets } from "../store";
@@ -91,6 +91,7 @@ interface ReceiptProps {
 }
 
 function Receipt({ account, tx }: ReceiptProps) {
+  const network = useNetworks((s) => s.current);
   const value = BigInt(tx.value);
 
   return (
@@ -116,7 +117,8 @@ function Receipt({ account, tx }: ReceiptProps) {
       <Box>
         {value > 0n && (
           <ContextMenu copy={value.toString()}>
-            {formatEther(value)} Ξ
+            {formatUnits(value, network?.decimals ?? 18)}{" "}
+            {network?.currency ?? "ETH"}
           </ContextMenu>
         )}
       </Box>
@@ -0,0 +1,34 @@
+import { Button, Stack, Typography } from "@mui/material";
+
+import { useDialog } from "../hooks";
+
+interface Proposal {
+  peer: { name: string; url: string; description: string; icons: string[] };
+  chains: number[];
+}
+
+export function WalletConnectProposalDialog({ id }: { id: number }) {
+  const { data, accept, reject } = useDialog<Proposal>(id);
+
+  if (!data) return null;
+
+  return (
+    <Stack direction="column" spacing={2} sx={{ p: 2 }}>
+      <Typography variant="h6" component="h1">
+        {data.peer.name} wants to connect
+      </Typography>
+
+      <Typography sx={{ wordBreak: "break-all" }}>{data.peer.url}</Typography>
+      <Typography>Chains: {data.chains.join(", ")}</Typography>
+
+      <Stack direction="row" justifyContent="center" spacing={2}>
+        <Button variant="contained" color="error" onClick={() => reject()}>
+          Reject
+        </Button>
+        <Button variant="contained" type="submit" onClick={() => accept(data)}>
+          Connect
+        </Button>
+      </Stack>
+    </Stack>
+  );
+}
@@ -0,0 +1,41 @@
+import { Button, Stack, Typography } from "@mui/material";
+
+import { useDialog } from "../hooks";
+
+// metadata is read from the contract itself, not taken from the dapp
+interface Request {
+  address: string;
+  chainName: string;
+  name: string;
+  symbol: string;
+  decimals: number;
+}
+
+export function WatchAssetDialog({ id }: { id: number }) {
+  const { data, accept, reject } = useDialog<Request>(id);
+
+  if (!data) return null;
+
+  return (
+    <Stack direction="column" spacing={2} sx={{ p: 2 }}>
+      <Typography variant="h6" component="h1">
+        Watch {data.symbol} on {data.chainName}
+      </Typography>
+
+      <Typography>Name: {data.name}</Typography>
+      <Typography sx={{ wordBreak: "break-all" }}>
+        Contract: {data.address}
+      </Typography>
+      <Typography>Decimals: {data.decimals}</Typography>
+
+      <Stack direction="row" justifyContent="center" spacing={2}>
+        <Button variant="contained" color="error" onClick={() => reject()}>
+          Reject
+        </Button>
+        <Button variant="contained" type="submit" onClick={() => accept(data)}>
+          Watch
+        </Button>
+      </Stack>
+    </Stack>
+  );
+}
@@ -1,17 +1,21 @@
 export { ABIForm } from "./ABIForm";
 export { AddressView } from "./AddressView";
 export { Account } from "./Account";
+export { Approvals } from "./Approvals";
 export { BalancesList } from "./BalancesList";
 export { CommandBar } from "./CommandBar";
 export { ConfirmationDialog } from "./ConfirmationDialog";
 export { ContextMenu } from "./ContextMenu";
 export { Contracts } from "./Contracts";
 export { CopyToClipboard } from "./CopyToClipboard";
+export { ErrorNotice } from "./ErrorNotice";
 export { HomePage } from "./HomePage";
 export { IconCrypto } from "./IconCrypto";
+export { LockScreen, useReportActivity } from "./LockScreen";
 export { Modal } from "./Modal";
 export { Navbar } from "./Navbar";
 export { NestedRoutes } from "./NestedRoutes";
+export { NetworkHealthIndicator } from "./NetworkHealthIndicator";
 export { NewVersionNotice } from "./NewVersionNotice";
 export { Panel } from "./Panel";
 export { Peers } from "./Peers";
@@ -25,4 +29,7 @@ export { Txs } from "./Txs";
 export { WagmiWrapper } from "./WagmiWrapper";
 export { WalletUnlockDialog } from "./WalletUnlockDialog";
 export { MsgSignDialog } from "./MsgSignDialog";
+export { ChainChangeDialog } from "./ChainChangeDialog";
+export { WatchAssetDialog } from "./WatchAssetDialog";
+export { WalletConnectProposalDialog } from "./WalletConnectProposalDialog";
 export { DevBuildNotice } from "./DevBuildNotice";
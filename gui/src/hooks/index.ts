@@ -4,6 +4,7 @@ export { useMenuGoAction as useMenuAction } from "./useMenuAction";
 export { useProvider } from "./useProvider";
 export { useRefreshPeers } from "./useRefreshPeers";
 export { useRefreshContracts } from "./useRefreshContracts";
+export { useRefreshSettings } from "./useRefreshSettings";
 export { useRefreshTransactions } from "./useRefreshTransactions";
 export { useDialog } from "./useDialog";
 export { useKeyPress } from "./useKeyPress";
@@ -0,0 +1,14 @@
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+
+export function useRefreshSettings(callback: () => unknown) {
+  useEffect(() => {
+    const unlisten = listen("settings-updated", () => {
+      callback();
+    });
+
+    return () => {
+      unlisten.then((cb) => cb());
+    };
+  }, [callback]);
+}
@@ -3,13 +3,14 @@ import { invoke } from "@tauri-apps/api/tauri";
 import { StateCreator, create } from "zustand";
 import { subscribeWithSelector } from "zustand/middleware";
 
-import { Address, TokenBalance } from "../types";
+import { Address, Prices, TokenBalance } from "../types";
 import { useNetworks } from "./networks";
 import { useWallets } from "./wallets";
 
 interface State {
   nativeBalance?: bigint;
   erc20Balances: TokenBalance[];
+  prices?: Prices;
 
   address?: Address;
   chainId?: number;
@@ -17,6 +18,7 @@ interface State {
 
 interface Setters {
   reload: () => Promise<void>;
+  reloadPrices: () => Promise<void>;
 
   setAddress: (address?: Address) => void;
   setChainId: (chainId?: number) => void;
@@ -45,6 +47,19 @@ const store: StateCreator<Store> = (set, get) => ({
       nativeBalance: BigInt(native),
       erc20Balances,
     });
+    await get().reloadPrices();
+  },
+
+  async reloadPrices() {
+    const { chainId } = get();
+    if (!chainId) return;
+
+    try {
+      set({ prices: await invoke<Prices>("prices_get", { chainId }) });
+    } catch {
+      // prices are optional, balances are still shown without them
+      set({ prices: undefined });
+    }
   },
 
   setAddress(address) {
@@ -64,6 +79,10 @@ listen("balances-updated", async () => {
   await useBalances.getState().reload();
 });
 
+listen("prices-updated", async () => {
+  await useBalances.getState().reloadPrices();
+});
+
 (async () => {
   await useBalances.getState().reload();
 
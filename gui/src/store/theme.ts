@@ -1,5 +1,6 @@
 import { PaletteMode, Theme, ThemeOptions, createTheme } from "@mui/material";
 import { grey, lightBlue } from "@mui/material/colors";
+import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/tauri";
 import { Action } from "kbar";
 import { StateCreator, create } from "zustand";
@@ -56,6 +57,10 @@ const store: StateCreator<Store> = (set, get) => ({
 
 export const useTheme = create<Store>()(store);
 
+listen("settings-updated", async () => {
+  await useTheme.getState().reload();
+});
+
 (async () => {
   await useTheme.getState().reload();
 })();
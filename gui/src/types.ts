@@ -6,7 +6,19 @@ export const generalSettingsSchema = z.object({
   abiWatchPath: z.string().optional().nullable(),
   alchemyApiKey: z.string().optional().nullable(),
   etherscanApiKey: z.string().optional().nullable(),
+  walletconnectProjectId: z.string().optional().nullable(),
+  priceSource: z.enum(["coingecko", "chainlink"]),
+  fiatCurrency: z.string().min(1),
   hideEmptyTokens: z.boolean(),
+  defaultNetwork: z.string().optional().nullable(),
+  autoLockMinutes: z.preprocess(
+    (v) => (v === "" || v === null ? undefined : v),
+    z.coerce.number().int().positive().optional()
+  ),
+  gasPreset: z.enum(["slow", "normal", "fast"]),
+  blocklistUrls: z.array(z.string()),
+  customBlocklist: z.array(z.string()),
+  stateDiffPreview: z.boolean(),
   onboarded: z.boolean(),
 });
 
@@ -22,6 +34,16 @@ export const networkSchema = z.object({
         currency: z.string().min(1),
         chain_id: z.number(),
         decimals: z.number(),
+        fallback_rpc_urls: z.array(z.string().min(1)).default([]),
+        supports_eip1559: z.boolean().default(true),
+        explorer_api_url: z.string().optional().nullable(),
+        explorer_api_key: z.string().optional().nullable(),
+        bundler_url: z.string().optional().nullable(),
+        private_rpc_url: z.string().optional().nullable(),
+        private_by_default: z.boolean().default(false),
+        rpc_timeout_secs: z.number().int().min(1).default(30),
+        rpc_retries: z.number().int().min(0).max(10).default(2),
+        rpc_backoff_ms: z.number().int().min(0).default(500),
       })
       .refine(
         (data) =>
@@ -70,7 +92,8 @@ export const mnemonicSchema = z
 
 export const derivationPathSchema = z
   .string()
-  .regex(/^m\/(\d+'?\/)+\d+$/, {
+  // `x` marks where the account index goes, for paths like Ledger Live's
+  .regex(/^m\/((\d+|x)'?\/)+\d+$/, {
     message: "invalid path format",
   })
   .default("m/44'/60'/0'/0");
@@ -97,6 +120,7 @@ export const hdWalletSchema = z.object({
   mnemonic: mnemonicSchema,
   derivationPath: derivationPathSchema,
   password: passwordSchema,
+  keychain: z.string().optional(),
 });
 
 export const hdWalletUpdateSchema = hdWalletSchema.pick({
@@ -119,6 +143,9 @@ export const walletSchema = z.discriminatedUnion("type", [
     type: z.literal("jsonKeystore"),
     name: z.string().min(1),
     file: z.string().min(1),
+    address: z.string().optional(),
+    // only used on import, to check the passphrase and learn the address
+    password: z.string().optional(),
     currentPath: z.string().optional(),
   }),
   z.object({
@@ -131,6 +158,29 @@ export const walletSchema = z.discriminatedUnion("type", [
     currentPath: z.string().optional(),
   }),
   impersonatorSchema,
+  z.object({
+    type: z.literal("privateKey"),
+    name: z.string().min(1),
+    // encrypted keys aren't sent back, and are only replaced if a new one is given
+    privateKey: z
+      .string()
+      .regex(/^((0x)?[a-fA-F0-9]{64})?$/, "not a valid private key")
+      .optional(),
+    password: z.string().optional(),
+    dev: z.boolean().default(false),
+  }),
+  z.object({
+    type: z.literal("watchOnly"),
+    name: z.string().min(1),
+    addresses: z.array(addressSchema).min(1),
+    current: z.number().optional(),
+  }),
+  z.object({
+    type: z.literal("ledger"),
+    name: z.string().min(1),
+    derivationPath: derivationPathSchema,
+    count: z.number().int().min(1).max(100),
+  }),
 ]);
 
 export const walletTypes: Wallet["type"][] = Array.from(
@@ -143,11 +193,70 @@ export const walletsSchema = z.object({
   wallets: z.array(walletSchema),
 });
 
+export const contactSchema = z.object({
+  name: z.string().trim().min(1),
+  address: addressSchema,
+  chainId: z.preprocess(
+    (v) => (v === "" || v === null ? undefined : v),
+    z.coerce.number().int().positive().optional()
+  ),
+  notes: z.string().optional().nullable(),
+  deployment: z.boolean().optional(),
+});
+
 export type Address = `0x${string}`;
 export type Wallet = z.infer<typeof walletSchema>;
 export type Wallets = z.infer<typeof walletsSchema>;
 export type Network = z.infer<typeof networkSchema.shape.networks>[number];
 export type GeneralSettings = z.infer<typeof generalSettingsSchema>;
+export type Contact = z.infer<typeof contactSchema>;
+
+export type HealthStatus = "healthy" | "degraded" | "down" | "unknown";
+
+export interface EndpointHealth {
+  url: string;
+  status: HealthStatus;
+  latencyMs?: number;
+  blockNumber?: number;
+  lag?: number;
+  history: {
+    timestamp: number;
+    latencyMs?: number;
+    blockNumber?: number;
+  }[];
+}
+
+export interface NetworkHealth {
+  chainId: number;
+  status: HealthStatus;
+  activeUrl: string;
+  endpoints: EndpointHealth[];
+  suggestedUrl?: string;
+}
+
+// Fees paid in a block, as sampled by the gas tracker, in wei
+export interface GasSample {
+  blockNumber: number;
+  timestamp: number;
+  baseFee: string;
+  priorityFeeLow: string;
+  priorityFeeMedium: string;
+  priorityFeeHigh: string;
+}
+
+export interface GasHistory {
+  chainId: number;
+  samples: GasSample[];
+  // the UTC hour of the day base fees were lowest at, once a few were sampled
+  cheapestHour?: { hour: number; averageBaseFee: string };
+}
+
+// An account another derivation path would give, previewed before switching
+export interface DerivedAccount {
+  path: string;
+  address: Address;
+  balance?: string;
+}
 
 export interface TokenBalance {
   contract: Address;
@@ -161,6 +270,18 @@ export interface TokenMetadata {
   decimals: number;
 }
 
+export interface TokenPrice {
+  contract?: Address;
+  price: number;
+  updatedAt: number;
+}
+
+export interface Prices {
+  currency: string;
+  native?: TokenPrice;
+  tokens: TokenPrice[];
+}
+
 export interface ABIFunctionInput {
   name: string;
   type: string;
@@ -179,6 +300,13 @@ export interface ABIMatch {
   abi: ABIItem[];
 }
 
+export interface Deployment {
+  chainId: number;
+  address: Address;
+  project: string;
+  name: string;
+}
+
 export interface Tx {
   hash: `0x${string}`;
   from: Address;
@@ -209,3 +337,52 @@ export interface IContract {
   abi: ABIItem[];
   name: string;
 }
+
+export interface SafeInfo {
+  address: Address;
+  version: string;
+  owners: Address[];
+  threshold: string;
+  nonce: string;
+  ownOwners: Address[];
+  canPropose: boolean;
+}
+
+export interface SmartAccount {
+  address: Address;
+  owner: Address;
+  deployed: boolean;
+  entryPoint: Address;
+}
+
+export type Grant =
+  | { kind: "erc20"; amount: string }
+  | { kind: "erc721"; tokenId: string }
+  | { kind: "operator" };
+
+export type Allowance = Grant & {
+  token: Address;
+  spender: Address;
+  symbol: string | null;
+  decimals: number | null;
+};
+
+export interface ContractFunction {
+  name: string;
+  signature: string;
+  inputs: ABIFunctionInput[];
+  outputs: ABIFunctionInput[];
+  readOnly: boolean;
+  payable: boolean;
+}
+
+export interface DecodedArg {
+  name: string;
+  value: string;
+}
+
+// Returned by `rpc_call`, with outputs decoded when the signature has them
+export interface CallOutput {
+  data: `0x${string}`;
+  decoded: DecodedArg[];
+}
@@ -0,0 +1,12 @@
+// Formats a fiat amount in the given ISO 4217 currency, e.g. `$1,234.56`
+export function formatFiat(amount: number, currency: string) {
+  try {
+    return new Intl.NumberFormat(undefined, {
+      style: "currency",
+      currency: currency.toUpperCase(),
+    }).format(amount);
+  } catch {
+    // not an ISO code, such as `btc`
+    return `${amount.toFixed(2)} ${currency.toUpperCase()}`;
+  }
+}
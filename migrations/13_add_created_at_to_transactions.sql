@@ -0,0 +1,4 @@
+ALTER TABLE transactions
+ADD created_at INTEGER NOT NULL DEFAULT 0;
+
+UPDATE transactions SET created_at = CAST(strftime('%s', 'now') AS INTEGER);
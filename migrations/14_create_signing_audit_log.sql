@@ -0,0 +1,9 @@
+CREATE TABLE signing_audit_log (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  created_at INTEGER NOT NULL,
+  address VARCHAR NOT NULL COLLATE NOCASE,
+  chain_id INTEGER NOT NULL,
+  kind VARCHAR NOT NULL,
+  payload_hash VARCHAR NOT NULL,
+  outcome VARCHAR NOT NULL
+);
@@ -0,0 +1,11 @@
+CREATE TABLE tx_history (
+  hash VARCHAR PRIMARY KEY COLLATE NOCASE,
+  chain_id INTEGER NOT NULL,
+  from_address VARCHAR NOT NULL COLLATE NOCASE,
+  to_address VARCHAR COLLATE NOCASE,
+  value VARCHAR NOT NULL DEFAULT "0",
+  status VARCHAR NOT NULL,
+  created_at INTEGER NOT NULL
+);
+
+CREATE INDEX tx_history_chain_id_created_at ON tx_history (chain_id, created_at);
@@ -0,0 +1,5 @@
+CREATE TABLE watched_tokens (
+  chain_id INTEGER NOT NULL,
+  contract VARCHAR NOT NULL COLLATE NOCASE,
+  PRIMARY KEY (chain_id, contract)
+);
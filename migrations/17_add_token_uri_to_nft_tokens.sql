@@ -0,0 +1 @@
+ALTER TABLE nft_tokens ADD COLUMN token_uri VARCHAR;
@@ -0,0 +1,9 @@
+CREATE TABLE prices (
+  chain_id INTEGER NOT NULL,
+  -- a contract address, or `native` for the network's own currency
+  asset VARCHAR NOT NULL COLLATE NOCASE,
+  currency VARCHAR NOT NULL,
+  price REAL NOT NULL,
+  updated_at INTEGER NOT NULL,
+  PRIMARY KEY (chain_id, asset, currency)
+);
@@ -0,0 +1,12 @@
+CREATE TABLE signed_messages (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  created_at INTEGER NOT NULL,
+  address VARCHAR NOT NULL COLLATE NOCASE,
+  chain_id INTEGER NOT NULL,
+  origin VARCHAR,
+  method VARCHAR NOT NULL,
+  payload_hash VARCHAR NOT NULL COLLATE NOCASE,
+  signature VARCHAR NOT NULL COLLATE NOCASE
+);
+
+CREATE INDEX signed_messages_created_at ON signed_messages (created_at);
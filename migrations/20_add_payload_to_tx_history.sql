@@ -0,0 +1,3 @@
+-- what it takes to resume tracking, rebroadcast or replace a pending transaction after a restart
+ALTER TABLE tx_history ADD COLUMN nonce VARCHAR;
+ALTER TABLE tx_history ADD COLUMN raw VARCHAR;
@@ -0,0 +1,2 @@
+-- transactions sent through a private relay, which aren't in the public mempool
+ALTER TABLE tx_history ADD COLUMN private BOOLEAN NOT NULL DEFAULT FALSE;
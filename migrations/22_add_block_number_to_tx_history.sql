@@ -0,0 +1,3 @@
+-- the block a transaction was mined in, so that it can be put back to pending if that block is
+-- reorganized away
+ALTER TABLE tx_history ADD COLUMN block_number INTEGER;
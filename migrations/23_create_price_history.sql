@@ -0,0 +1,11 @@
+-- hourly fiat prices, so that past transactions can be valued at the time they happened
+CREATE TABLE price_history (
+  chain_id INTEGER NOT NULL,
+  -- a contract address, or `native` for the network's own currency
+  asset VARCHAR NOT NULL COLLATE NOCASE,
+  currency VARCHAR NOT NULL,
+  -- unix timestamp divided by 3600
+  hour INTEGER NOT NULL,
+  price REAL NOT NULL,
+  PRIMARY KEY (chain_id, asset, currency, hour)
+);
@@ -0,0 +1,13 @@
+-- fees paid in recent blocks, for charting them and suggesting cheaper times to send
+CREATE TABLE gas_history (
+  chain_id INTEGER NOT NULL,
+  block_number INTEGER NOT NULL,
+  base_fee VARCHAR NOT NULL,
+  priority_fee_low VARCHAR NOT NULL,
+  priority_fee_medium VARCHAR NOT NULL,
+  priority_fee_high VARCHAR NOT NULL,
+  created_at INTEGER NOT NULL,
+  PRIMARY KEY (chain_id, block_number)
+);
+
+CREATE INDEX gas_history_chain_id_created_at ON gas_history (chain_id, created_at);
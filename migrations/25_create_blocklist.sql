@@ -0,0 +1,6 @@
+-- phishing domains and malicious addresses from the configured lists, as last fetched, so that
+-- they're still checked against while offline
+CREATE TABLE blocklist (
+  entry VARCHAR PRIMARY KEY COLLATE NOCASE,
+  updated_at INTEGER NOT NULL
+);